@@ -0,0 +1,189 @@
+use std::marker::PhantomData;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::responses::{
+    CreateResponse, FunctionToolCall, InputItem, InputParam, OutputItem, OutputMessageContent,
+    Reasoning, ReasoningEffort, RefusalContent, ResponseFormatJsonSchema, ResponseTextParam,
+    TextResponseFormatConfiguration, ToolChoiceOptions, ToolChoiceParam, Truncation,
+};
+use tracing::{debug, warn};
+
+use super::query::{Parsed, Query};
+use super::retry::{REFUSAL_RETRIES, check_tool_rounds, create_response, softened_retry_message};
+use super::token_budget::TokenBudget;
+use super::tools::ToolRegistry;
+use crate::AppResult;
+use crate::error::AppError;
+
+/// How the final answer of [`run_agent`] is asked for and read.
+pub trait FromAnswer {
+    type Answer;
+
+    /// The schema the model is asked to answer with.
+    fn response_format(&self) -> ResponseFormatJsonSchema;
+
+    /// Read an answer, asking again when it does not match the schema. See
+    /// [`Query::from_answer`].
+    fn from_answer(&self, s: &str, retries: &mut u32) -> AppResult<Parsed<Self::Answer>>;
+}
+
+impl<Q: Query> FromAnswer for PhantomData<Q> {
+    type Answer = Q;
+
+    fn response_format(&self) -> ResponseFormatJsonSchema {
+        Q::response_format()
+    }
+
+    fn from_answer(&self, s: &str, retries: &mut u32) -> AppResult<Parsed<Q>> {
+        Q::from_answer(s, retries)
+    }
+}
+
+/// How each request of [`run_agent`] is sampled.
+#[derive(Debug, Clone)]
+pub struct AgentSettings<'a> {
+    pub instructions: &'a str,
+    pub effort: ReasoningEffort,
+    pub temperature: f32,
+    pub top_p: f32,
+    /// Response the first request continues from, for a follow-up question.
+    pub previous_response_id: Option<String>,
+}
+
+/// The final answer of [`run_agent`].
+#[derive(Debug)]
+pub struct AgentAnswer<T> {
+    pub answer: T,
+    /// The answer as the model wrote it, for the response cache.
+    pub text: String,
+    /// Id of the response that answered, to continue the conversation from.
+    pub response_id: String,
+}
+
+/// Ask `model` until it answers, running the tools of `registry` it calls along the way.
+///
+/// Tool output is cut to fit `budget`. An answer that does not match the schema is asked for
+/// again, and a refusal or empty answer once more with a softened message; an answer that still
+/// cannot be used fails with [`AppError::NoAnswer`].
+pub async fn run_agent<C: Config, R: ToolRegistry, A: FromAnswer>(
+    client: &Client<C>,
+    model: &str,
+    settings: AgentSettings<'_>,
+    mut input_items: Vec<InputItem>,
+    registry: &R,
+    answer: &A,
+    budget: TokenBudget,
+) -> AppResult<AgentAnswer<A::Answer>> {
+    let format = answer.response_format();
+    let base = CreateResponse {
+        model: Some(model.to_string()),
+        background: Some(false),
+        instructions: Some(settings.instructions.to_string()),
+        parallel_tool_calls: Some(false),
+        reasoning: Some(Reasoning {
+            effort: Some(settings.effort),
+            summary: None,
+        }),
+        store: Some(true),
+        stream: Some(false),
+        temperature: Some(settings.temperature),
+        text: Some(ResponseTextParam {
+            format: TextResponseFormatConfiguration::JsonSchema(format.clone()),
+            verbosity: None,
+        }),
+        tool_choice: Some(ToolChoiceParam::Mode(ToolChoiceOptions::Auto)),
+        tools: Some(registry.definitions()),
+        top_logprobs: Some(0),
+        top_p: Some(settings.top_p),
+        truncation: Some(Truncation::Disabled),
+        ..Default::default()
+    };
+    let mut previous_response_id = settings.previous_response_id;
+    let mut tool_rounds = 0;
+    let mut schema_retries = 0;
+    let mut refusal_retries = 0;
+
+    loop {
+        let request = CreateResponse {
+            input: InputParam::Items(input_items.clone()),
+            previous_response_id: previous_response_id.clone(),
+            ..base.clone()
+        };
+
+        let response = create_response(client, request).await?;
+        debug!("AI Response: {:?}", response);
+        previous_response_id = Some(response.id.clone());
+
+        let function_calls: Vec<FunctionToolCall> = response
+            .output
+            .iter()
+            .filter_map(|item| {
+                if let OutputItem::FunctionCall(fc) = item {
+                    Some(fc.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if function_calls.is_empty() {
+            let mut response_content = String::new();
+            let mut refusal = None;
+            for out in &response.output {
+                if let OutputItem::Message(msg) = out {
+                    for content in &msg.content {
+                        match content {
+                            OutputMessageContent::OutputText(text) => {
+                                response_content.push_str(&text.text)
+                            }
+                            OutputMessageContent::Refusal(RefusalContent { refusal: reason }) => {
+                                warn!("The model refused the {} answer: {reason}", format.name);
+                                refusal = Some(reason.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            if response_content.trim().is_empty() {
+                if refusal_retries < REFUSAL_RETRIES {
+                    refusal_retries += 1;
+                    warn!("Asking again for the {} answer", format.name);
+                    input_items.push(softened_retry_message());
+                    continue;
+                }
+                return Err(AppError::NoAnswer(match refusal {
+                    Some(reason) => format!("the model refused to write it: {reason}"),
+                    None => "the model answered with nothing".to_string(),
+                }));
+            }
+            match answer.from_answer(&response_content, &mut schema_retries) {
+                Ok(Parsed::Answer(answer)) => {
+                    return Ok(AgentAnswer {
+                        answer,
+                        text: response_content,
+                        response_id: response.id,
+                    });
+                }
+                Ok(Parsed::Retry(message)) => {
+                    input_items.push(message);
+                    continue;
+                }
+                Err(e) => {
+                    return Err(AppError::NoAnswer(format!(
+                        "its answer could not be read: {e}"
+                    )));
+                }
+            }
+        }
+
+        tool_rounds += 1;
+        check_tool_rounds(tool_rounds)?;
+
+        // Handle each tool call in order and feed results back into the conversation.
+        for call in function_calls {
+            let output = registry.execute(call).await;
+            input_items.extend(budget.fit_tool_output(output));
+        }
+    }
+}
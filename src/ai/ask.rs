@@ -0,0 +1,201 @@
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, ReasoningEffort,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::agent::{AgentSettings, run_agent};
+use super::glossary::with_glossary;
+use super::persona::with_about_me;
+use super::prompt::{Prompt, vars};
+use super::query::Query;
+use super::summary::{MinifiedContext, WorkSummary};
+use super::token_budget::TokenBudget;
+use super::tools::fetch::VisitedSites;
+use super::tools::summary::SummaryTools;
+use crate::context::Context;
+use crate::redact::Redactor;
+use crate::{AppResult, impl_query};
+
+static ASK_PROMPT: Prompt =
+    Prompt::new("ask_prompt.md", std::include_str!("prompts/ask_prompt.md"));
+
+/// # ask
+/// An answer to a question about the collected work.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AskQuery {
+    /// The answer, in Markdown
+    pub answer: String,
+    /// Commits, commands, and pages the answer rests on
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(AskQuery, ASK_PROMPT);
+
+impl AskQuery {
+    /// The answer as Markdown, followed by its sources and the model's caveats.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("{}\n", self.answer.trim());
+        for (heading, items) in [("Sources", &self.sources), ("Notes", &self.notes)] {
+            if items.is_empty() {
+                continue;
+            }
+            let _ = write!(out, "\n**{heading}**\n\n");
+            for item in items {
+                let _ = writeln!(out, "- {}", item.trim());
+            }
+        }
+        out
+    }
+}
+
+/// Input handed to the model: the question, the day's summary, and the collected work.
+#[derive(Serialize)]
+struct AskInput<'a> {
+    question: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a WorkSummary>,
+    #[serde(flatten)]
+    context: MinifiedContext,
+}
+
+/// A later question in a conversation. The model already has the collected work from the first.
+#[derive(Serialize)]
+struct FollowUp<'a> {
+    question: &'a str,
+}
+
+/// How the model may look things up while answering questions about a run.
+#[derive(Debug, Clone, Copy)]
+pub struct AskOptions<'a> {
+    /// Leave out the tools that reach the internet.
+    pub offline: bool,
+    pub budget: TokenBudget,
+    /// Redacts the files the model reads, or `None` with `--no-redact`.
+    pub redactor: Option<&'a Redactor>,
+}
+
+/// A conversation about one run. The first question goes to the model with the collected work;
+/// later ones only carry the question and continue from the model's last answer.
+pub struct Conversation<'a> {
+    context: &'a Context,
+    summary: Option<&'a WorkSummary>,
+    options: AskOptions<'a>,
+    previous_response_id: Option<String>,
+}
+
+impl<'a> Conversation<'a> {
+    pub fn new(
+        context: &'a Context,
+        summary: Option<&'a WorkSummary>,
+        options: AskOptions<'a>,
+    ) -> Self {
+        Conversation {
+            context,
+            summary,
+            options,
+            previous_response_id: None,
+        }
+    }
+
+    /// The first message of the next question: the question with the collected work, or on its
+    /// own once the model has seen that.
+    fn question_input(&self, question: &str, prompt: &str) -> AppResult<Vec<InputItem>> {
+        let message = |text: String, role: InputRole| {
+            InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+                content: vec![InputContent::InputText(InputTextContent { text })],
+                role,
+                status: None,
+            })))
+        };
+        if self.previous_response_id.is_some() {
+            return Ok(vec![message(
+                serde_json::to_string_pretty(&FollowUp { question })?,
+                InputRole::User,
+            )]);
+        }
+        let mut input = AskInput {
+            question,
+            summary: self.summary,
+            context: MinifiedContext::from(self.context),
+        };
+        self.options.budget.fit_context(&mut input.context);
+        Ok(vec![
+            message(serde_json::to_string_pretty(&input)?, InputRole::User),
+            message(prompt.to_string(), InputRole::System),
+        ])
+    }
+
+    /// Answer `question`, letting the model look things up through the summary tools.
+    #[tracing::instrument(name = "Answering a question", level = "debug", skip(self, client))]
+    pub async fn ask<C: Config>(
+        &mut self,
+        client: &Client<C>,
+        model: &str,
+        question: &str,
+    ) -> AppResult<AskQuery> {
+        let context = self.context;
+        let AskOptions {
+            offline,
+            budget,
+            redactor,
+        } = self.options;
+        let prompt = with_glossary(&with_about_me(&AskQuery::prompt(vars())));
+        let input_items = self.question_input(question, &prompt)?;
+        let registry = SummaryTools {
+            context,
+            offline,
+            sites: VisitedSites::from_context(context),
+            redactor,
+        };
+        let settings = AgentSettings {
+            instructions: &prompt,
+            effort: ReasoningEffort::High,
+            temperature: 0.1,
+            top_p: 0.1,
+            previous_response_id: self.previous_response_id.clone(),
+        };
+        let agent = run_agent(
+            client,
+            model,
+            settings,
+            input_items,
+            &registry,
+            &PhantomData::<AskQuery>,
+            budget,
+        )
+        .await?;
+        // Only kept once the answer arrives, so a question that fails part way does not leave
+        // the conversation waiting on tool output.
+        self.previous_response_id = Some(agent.response_id);
+        Ok(agent.answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_sources_and_notes_after_the_answer() {
+        let answer = AskQuery {
+            answer: "You fixed the parser.\n".into(),
+            sources: vec!["commit 0123456 in daily-ai".into()],
+            notes: Vec::new(),
+        };
+        assert_eq!(
+            answer.to_markdown(),
+            "You fixed the parser.\n\n**Sources**\n\n- commit 0123456 in daily-ai\n"
+        );
+    }
+}
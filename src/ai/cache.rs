@@ -0,0 +1,242 @@
+use std::io::{Cursor, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use murmur3::murmur3_x64_128;
+use time::{Date, OffsetDateTime};
+use tokio::fs;
+use tracing::{debug, warn};
+
+use crate::dirs::DirType;
+use crate::io_utils::shred_file;
+use crate::{AppResult, time_utils};
+
+/// Directory under the cache directory holding cached responses.
+const CACHE_DIR: &str = "responses";
+
+/// Cached responses older than this are removed when the cache is opened.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Model answers stored under the cache directory, keyed by a hash of the model, the prompt, and
+/// the input, so re-running a summary for the same data skips the requests whose input has not
+/// changed.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    /// `None` when caching is turned off with `--no-cache`.
+    dir: Option<PathBuf>,
+}
+
+impl ResponseCache {
+    /// A cache in `dir`, or one that never stores anything.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        ResponseCache { dir }
+    }
+
+    /// Open the response cache in the cache directory, dropping expired entries, or a disabled
+    /// cache with `no_cache`. A cache directory that cannot be created disables caching.
+    pub async fn open(no_cache: bool) -> Self {
+        if no_cache {
+            return ResponseCache::new(None);
+        }
+        let dir = match DirType::Cache.ensure_dir_async().await {
+            Ok(dir) => dir.join(CACHE_DIR),
+            Err(e) => {
+                warn!("Not caching model responses: {e}");
+                return ResponseCache::new(None);
+            }
+        };
+        if let Err(e) = fs::create_dir_all(&dir).await {
+            warn!("Not caching model responses: {e}");
+            return ResponseCache::new(None);
+        }
+        if let Err(e) = prune(&dir).await {
+            debug!("Unable to remove expired cached responses: {e}");
+        }
+        ResponseCache::new(Some(dir))
+    }
+
+    /// Key for the answer to `prompt` given `input` from `model`.
+    pub fn key(model: &str, prompt: &str, input: &str) -> AppResult<String> {
+        let text = [model, prompt, input].join("\0");
+        Ok(format!(
+            "{:032x}",
+            murmur3_x64_128(&mut Cursor::new(text), 0)?
+        ))
+    }
+
+    /// The cached answer for `key`, if there is one.
+    pub async fn get(&self, key: &str) -> Option<String> {
+        let path = self.dir.as_ref()?.join(format!("{key}.json"));
+        match fs::read_to_string(&path).await {
+            Ok(response) => {
+                debug!("Using the cached response {key}");
+                Some(response)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => {
+                debug!(
+                    "Ignoring unreadable cached response {}: {e}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Store `response` as the answer for `key`. Failures only cost a later cache miss.
+    pub async fn put(&self, key: &str, response: &str) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let path = dir.join(format!("{key}.json"));
+        if let Err(e) = fs::write(&path, response).await {
+            debug!("Unable to cache response in {}: {e}", path.display());
+        }
+    }
+}
+
+/// How many responses are cached, and how many of them have expired and will be removed the
+/// next time the cache is opened.
+pub async fn count() -> AppResult<(usize, usize)> {
+    let dir = DirType::Cache.get_dir()?.join(CACHE_DIR);
+    if !fs::try_exists(&dir).await? {
+        return Ok((0, 0));
+    }
+    let mut entries = fs::read_dir(dir).await?;
+    let now = SystemTime::now();
+    let (mut cached, mut expired) = (0, 0);
+    while let Some(entry) = entries.next_entry().await? {
+        cached += 1;
+        let modified = entry.metadata().await?.modified()?;
+        if now.duration_since(modified).unwrap_or_default() > MAX_AGE {
+            expired += 1;
+        }
+    }
+    Ok((cached, expired))
+}
+
+/// Remove the cached responses written before `before`, shredding them first with `paranoid`,
+/// and return how many were removed.
+///
+/// Entries are keyed by a hash of their input, so the day an answer was cached stands in for the
+/// day of the data it was given. Entries are removed whichever sources are purged, since one
+/// answer can draw on every source.
+pub async fn purge(before: Date, paranoid: bool) -> AppResult<usize> {
+    purge_in(&DirType::Cache.get_dir()?.join(CACHE_DIR), before, paranoid).await
+}
+
+async fn purge_in(dir: &Path, before: Date, paranoid: bool) -> AppResult<usize> {
+    if !fs::try_exists(dir).await? {
+        return Ok(0);
+    }
+    let cutoff = before.midnight().assume_offset(time_utils::local_offset());
+    let mut entries = fs::read_dir(dir).await?;
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let modified = OffsetDateTime::from(entry.metadata().await?.modified()?);
+        if modified >= cutoff {
+            continue;
+        }
+        if paranoid {
+            shred_file(entry.path()).await?;
+        } else {
+            fs::remove_file(entry.path()).await?;
+        }
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Remove cached responses last written more than [`MAX_AGE`] ago.
+async fn prune(dir: &Path) -> AppResult<()> {
+    let mut entries = fs::read_dir(dir).await?;
+    let now = SystemTime::now();
+    while let Some(entry) = entries.next_entry().await? {
+        let modified = entry.metadata().await?.modified()?;
+        if now.duration_since(modified).unwrap_or_default() > MAX_AGE {
+            fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_responses_by_model_prompt_and_input() {
+        let dir =
+            std::env::temp_dir().join(format!("daily-ai-response-cache-{}", ulid::Ulid::new()));
+        fs::create_dir_all(&dir).await.unwrap();
+        let cache = ResponseCache::new(Some(dir.clone()));
+        let key = ResponseCache::key("qwen3", "Summarize", "{}").unwrap();
+
+        assert_eq!(cache.get(&key).await, None);
+        cache.put(&key, "{\"summary\": \"done\"}").await;
+        assert_eq!(
+            cache.get(&key).await.as_deref(),
+            Some("{\"summary\": \"done\"}")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("qwen3", "Summarize", "{\"a\": 1}").unwrap()
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("gpt-oss", "Summarize", "{}").unwrap()
+        );
+
+        let disabled = ResponseCache::new(None);
+        disabled.put(&key, "ignored").await;
+        assert_eq!(disabled.get(&key).await, None);
+
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
+    #[tokio::test]
+    async fn purges_responses_cached_before_a_day() {
+        let dir =
+            std::env::temp_dir().join(format!("daily-ai-response-purge-{}", ulid::Ulid::new()));
+        fs::create_dir_all(&dir).await.unwrap();
+        let old = dir.join("old.json");
+        let older = dir.join("older.json");
+        let new = dir.join("new.json");
+        for path in [&old, &older, &new] {
+            fs::write(path, "{}").await.unwrap();
+        }
+        for (path, date) in [
+            (&old, date!(2024 - 03 - 01)),
+            (&older, date!(2023 - 12 - 31)),
+        ] {
+            let modified = date.midnight().assume_utc() + time::Duration::hours(12);
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified.into())
+                .unwrap();
+        }
+
+        assert_eq!(
+            purge_in(&dir, date!(2024 - 01 - 01), false).await.unwrap(),
+            1
+        );
+        assert!(!older.exists());
+        assert!(old.exists());
+        assert_eq!(
+            purge_in(&dir, date!(2024 - 06 - 01), true).await.unwrap(),
+            1
+        );
+        assert!(!old.exists());
+        assert!(new.exists());
+
+        let _ = fs::remove_dir_all(&dir).await;
+        assert_eq!(
+            purge_in(&dir, date!(2024 - 06 - 01), true).await.unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::error::OpenAIError;
+use async_openai::types::chat::CreateChatCompletionRequest;
+use async_openai::types::responses::{CreateResponse, Response};
+use serde_json::{Map, Value, json};
+use tracing::debug;
+
+use super::provider;
+
+/// Output tokens asked for when the request does not say; Chat Completions providers such as
+/// Anthropic need a limit.
+const DEFAULT_MAX_TOKENS: u64 = 16_384;
+
+/// Conversations sent through Chat Completions so far, by the ID of their last answer, kept for
+/// as long as the process runs. Chat Completions keeps no state on the server, so this stands in
+/// for `previous_response_id`.
+static TRANSCRIPTS: LazyLock<Mutex<HashMap<String, Transcript>>> = LazyLock::new(Default::default);
+
+/// The messages of a conversation up to one answer.
+#[derive(Debug, Clone, Default)]
+struct Transcript {
+    /// Every message sent and answered, without the instructions.
+    messages: Vec<Value>,
+    /// The input of the last request, as messages. Requests continuing a conversation send their
+    /// earlier input again, which the Responses API tolerates but Chat Completions does not.
+    input: Vec<Value>,
+}
+
+/// Send `request` through the Responses API, or through Chat Completions when the configured
+/// provider only serves those, translating the request and the answer.
+pub async fn create<C: Config>(
+    client: &Client<C>,
+    request: CreateResponse,
+) -> Result<Response, OpenAIError> {
+    if provider::configured().chat_completions_only() {
+        create_through_chat(client, request).await
+    } else {
+        client.responses().create(request).await
+    }
+}
+
+/// Send `request` as a Chat Completions request and answer it as the Responses API would.
+async fn create_through_chat<C: Config>(
+    client: &Client<C>,
+    request: CreateResponse,
+) -> Result<Response, OpenAIError> {
+    let (chat_request, mut transcript) = to_chat(&request)?;
+    let chat_request: CreateChatCompletionRequest =
+        serde_json::from_value(chat_request).map_err(invalid)?;
+    let answer = client.chat().create(chat_request).await?;
+    let answer = serde_json::to_value(answer).map_err(invalid)?;
+    let (response, message) = from_chat(&answer)?;
+    transcript.messages.push(message);
+    TRANSCRIPTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(response.id.clone(), transcript);
+    Ok(response)
+}
+
+fn invalid(error: impl std::fmt::Display) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!("Unable to go through Chat Completions: {error}"))
+}
+
+/// The Chat Completions request for `request`, and the conversation it continues with its input
+/// added.
+fn to_chat(request: &CreateResponse) -> Result<(Value, Transcript), OpenAIError> {
+    let request = serde_json::to_value(request).map_err(invalid)?;
+    let mut transcript = match request["previous_response_id"].as_str() {
+        Some(id) => TRANSCRIPTS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()
+            .ok_or_else(|| invalid(format!("no earlier answer with the ID {id}")))?,
+        None => Transcript::default(),
+    };
+    let input = match &request["input"] {
+        Value::String(text) => vec![json!({"role": "user", "content": text})],
+        Value::Array(items) => items.iter().filter_map(to_message).collect(),
+        _ => Vec::new(),
+    };
+    let new_input = if input.starts_with(&transcript.input) {
+        &input[transcript.input.len()..]
+    } else {
+        &input[..]
+    };
+    transcript.messages.extend_from_slice(new_input);
+    transcript.input = input;
+
+    let mut instructions = request["instructions"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let format = &request["text"]["format"];
+    if format["type"] == "json_schema" {
+        // Not every provider honours `response_format`, so the schema is spelled out as well.
+        let schema = serde_json::to_string_pretty(&format["schema"]).map_err(invalid)?;
+        instructions.push_str(&format!(
+            "\n\nAnswer with only JSON matching this schema, with no other text before or after \
+             it:\n\n{schema}"
+        ));
+    }
+    let mut messages = Vec::new();
+    if !instructions.trim().is_empty() {
+        messages.push(json!({"role": "system", "content": instructions.trim()}));
+    }
+    messages.extend(transcript.messages.iter().cloned());
+
+    let tools: Vec<Value> = request["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|tool| tool["type"] == "function")
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool["name"],
+                    "description": tool["description"],
+                    "parameters": tool["parameters"],
+                },
+            })
+        })
+        .collect();
+
+    let mut chat = Map::new();
+    chat.insert("model".into(), request["model"].clone());
+    chat.insert("messages".into(), messages.into());
+    chat.insert(
+        "max_completion_tokens".into(),
+        request["max_output_tokens"]
+            .as_u64()
+            .unwrap_or(DEFAULT_MAX_TOKENS)
+            .into(),
+    );
+    // Some models refuse `temperature` and `top_p` together, so only the first is passed on.
+    if !request["temperature"].is_null() {
+        chat.insert("temperature".into(), request["temperature"].clone());
+    }
+    if !tools.is_empty() {
+        chat.insert("tools".into(), tools.into());
+        chat.insert("tool_choice".into(), "auto".into());
+        if !request["parallel_tool_calls"].is_null() {
+            chat.insert(
+                "parallel_tool_calls".into(),
+                request["parallel_tool_calls"].clone(),
+            );
+        }
+    }
+    Ok((Value::Object(chat), transcript))
+}
+
+/// The text of a Responses API content list, or of plain string content.
+fn text_of(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// One Responses API input item as a Chat Completions message, or `None` for items that have no
+/// counterpart there.
+fn to_message(item: &Value) -> Option<Value> {
+    match item["type"].as_str() {
+        Some("function_call") => Some(json!({
+            "role": "assistant",
+            "tool_calls": [{
+                "id": item["call_id"],
+                "type": "function",
+                "function": {"name": item["name"], "arguments": item["arguments"]},
+            }],
+        })),
+        Some("function_call_output") => Some(json!({
+            "role": "tool",
+            "tool_call_id": item["call_id"],
+            "content": text_of(&item["output"]),
+        })),
+        Some("message") | None if item["role"].is_string() => {
+            let role = match item["role"].as_str() {
+                Some("developer") => "system",
+                Some(role) => role,
+                None => "user",
+            };
+            Some(json!({"role": role, "content": text_of(&item["content"])}))
+        }
+        other => {
+            debug!("Leaving a {other:?} item out of a Chat Completions request");
+            None
+        }
+    }
+}
+
+/// The Responses API answer for a Chat Completions `answer`, and the assistant message to keep
+/// in the conversation.
+fn from_chat(answer: &Value) -> Result<(Response, Value), OpenAIError> {
+    let choice = &answer["choices"][0];
+    let message = &choice["message"];
+    let mut output = Vec::new();
+    let mut content = Vec::new();
+    if let Some(text) = message["content"].as_str().filter(|text| !text.is_empty()) {
+        content.push(json!({"type": "output_text", "text": text, "annotations": []}));
+    }
+    if let Some(refusal) = message["refusal"].as_str() {
+        content.push(json!({"type": "refusal", "refusal": refusal}));
+    }
+    if !content.is_empty() {
+        output.push(json!({
+            "type": "message",
+            "id": format!("{}_message", answer["id"].as_str().unwrap_or_default()),
+            "role": "assistant",
+            "status": "completed",
+            "content": content,
+        }));
+    }
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        output.push(json!({
+            "type": "function_call",
+            "id": call["id"],
+            "call_id": call["id"],
+            "name": call["function"]["name"],
+            "arguments": call["function"]["arguments"],
+            "status": "completed",
+        }));
+    }
+    let incomplete = choice["finish_reason"] == "length";
+    let usage = &answer["usage"];
+    let input_tokens = usage["prompt_tokens"].as_u64().unwrap_or_default();
+    let output_tokens = usage["completion_tokens"].as_u64().unwrap_or_default();
+    let response = json!({
+        "id": answer["id"],
+        "object": "response",
+        "created_at": answer["created"],
+        "model": answer["model"],
+        "status": if incomplete { "incomplete" } else { "completed" },
+        "incomplete_details": incomplete.then(|| json!({"reason": "max_output_tokens"})),
+        "output": output,
+        "usage": {
+            "input_tokens": input_tokens,
+            "input_tokens_details": {"cached_tokens": 0},
+            "output_tokens": output_tokens,
+            "output_tokens_details": {"reasoning_tokens": 0},
+            "total_tokens": input_tokens + output_tokens,
+        },
+    });
+    let mut kept = json!({"role": "assistant", "content": message["content"]});
+    if message["tool_calls"].is_array() {
+        kept["tool_calls"] = message["tool_calls"].clone();
+    }
+    Ok((serde_json::from_value(response).map_err(invalid)?, kept))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_openai::types::responses::{OutputItem, OutputMessageContent};
+
+    use super::*;
+    use crate::ai::stub_server::StubServer;
+
+    /// A Chat Completions answer with `message` as the model's message.
+    fn chat_answer(message: Value) -> Value {
+        json!({
+            "id": format!("chatcmpl_{}", ulid::Ulid::new()),
+            "object": "chat.completion",
+            "created": 1_738_368_000,
+            "model": "stub",
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "finish_reason": "stop",
+                "logprobs": null
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        })
+    }
+
+    #[tokio::test]
+    async fn continues_conversations_through_chat_completions() {
+        let server = StubServer::start(|request| {
+            if request.body["messages"].as_array().unwrap().len() < 3 {
+                chat_answer(json!({
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "list_commits", "arguments": "{}"}
+                    }]
+                }))
+            } else {
+                chat_answer(json!({"role": "assistant", "content": "{\"answer\": \"done\"}"}))
+            }
+        })
+        .await;
+        let client = server.client();
+        let question = json!({
+            "type": "message",
+            "role": "user",
+            "content": [{"type": "input_text", "text": "What did I do?"}]
+        });
+        let request = |input: Vec<Value>, previous: Option<&str>| -> CreateResponse {
+            serde_json::from_value(json!({
+                "model": "claude",
+                "input": input,
+                "instructions": "Answer the question.",
+                "previous_response_id": previous,
+                "temperature": 0.1,
+                "top_p": 0.1,
+                "text": {"format": {
+                    "type": "json_schema",
+                    "name": "ask",
+                    "schema": {"type": "object", "properties": {"answer": {"type": "string"}}}
+                }},
+                "tools": [{
+                    "type": "function",
+                    "name": "list_commits",
+                    "description": "List the commits",
+                    "parameters": {"type": "object", "properties": {}}
+                }]
+            }))
+            .unwrap()
+        };
+
+        let first = create_through_chat(&client, request(vec![question.clone()], None))
+            .await
+            .unwrap();
+        let [OutputItem::FunctionCall(call)] = first.output.as_slice() else {
+            panic!("expected one tool call, got {:?}", first.output);
+        };
+        assert_eq!(call.call_id, "call_1");
+
+        // As the commands do, the second request repeats the question before the tool output.
+        let output =
+            json!({"type": "function_call_output", "call_id": "call_1", "output": "3 commits"});
+        let second = create_through_chat(&client, request(vec![question, output], Some(&first.id)))
+            .await
+            .unwrap();
+        let [OutputItem::Message(message)] = second.output.as_slice() else {
+            panic!("expected one message, got {:?}", second.output);
+        };
+        let [OutputMessageContent::OutputText(text)] = message.content.as_slice() else {
+            panic!("expected text, got {:?}", message.content);
+        };
+        assert_eq!(text.text, "{\"answer\": \"done\"}");
+
+        let requests = server.requests();
+        assert!(requests.iter().all(|r| r.path == "/v1/chat/completions"));
+        let body = &requests[1].body;
+        let roles: Vec<_> = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool"]);
+        assert!(
+            body["messages"][0]["content"]
+                .as_str()
+                .unwrap()
+                .contains("\"answer\"")
+        );
+        assert_eq!(body["messages"][2]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(body["messages"][3]["content"], "3 commits");
+        assert_eq!(body["tools"][0]["function"]["name"], "list_commits");
+        assert!(body["top_p"].is_null());
+    }
+}
@@ -1,26 +1,33 @@
 use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
 
 use async_openai::Client;
 use async_openai::config::Config;
 use async_openai::types::evals::InputTextContent;
 use async_openai::types::responses::{
-    CreateResponse, FunctionToolCall, InputContent, InputItem, InputMessage, InputParam, InputRole,
-    Item, MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort,
-    RefusalContent, ResponseTextParam, TextResponseFormatConfiguration, Tool, ToolChoiceOptions,
-    ToolChoiceParam, Truncation,
+    InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, ReasoningEffort,
 };
 use git2::{Diff, Repository};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error};
 
+use super::agent::{AgentSettings, run_agent};
+use super::glossary::with_glossary;
+use super::prompt::{Prompt, vars};
 use super::query::Query;
-use super::tools::commit::{CommitMessageToolContext, GetFile, GetPatch};
-use super::tools::{CustomTool, unknown_tool};
+use super::token_budget::TokenBudget;
+use super::tools::commit::CommitMessageToolContext;
 use crate::git::diff::get_diff_summary;
+use crate::redact::Redactor;
 use crate::{AppResult, impl_query};
 
-static COMMIT_MESSAGE_PROMPT: &str = std::include_str!("prompts/commit_message_prompt.md");
+static COMMIT_MESSAGE_PROMPT: Prompt = Prompt::new(
+    "commit_message_prompt.md",
+    std::include_str!("prompts/commit_message_prompt.md"),
+);
+
+/// Per-repository git config key naming the language commit messages are written in.
+pub const COMMIT_LANGUAGE_CONFIG_KEY: &str = "dailyai.commitLanguage";
 
 /// Commit message output from the model: summary plus optional body.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -43,25 +50,83 @@ impl Display for CommitMessage {
 
 impl_query!(CommitMessage, COMMIT_MESSAGE_PROMPT);
 
+/// How commit messages are written, and what is scrubbed from the changes the model reads.
+#[derive(Debug, Clone, Copy)]
+pub struct CommitMessageOptions<'a> {
+    /// `--commit-language`, which wins over the repository's `dailyai.commitLanguage`.
+    pub language: Option<&'a str>,
+    /// Redacts the diff summary, patches, and files the model reads, or `None` with
+    /// `--no-redact`.
+    pub redactor: Option<&'a Redactor>,
+    /// Limits the patches in the first message and the result of each tool call.
+    pub budget: TokenBudget,
+}
+
+/// Resolve the language commit messages should be written in.
+///
+/// An explicit `--commit-language` wins over the repository's `dailyai.commitLanguage` config.
+/// English (the prompt's default) and blank values resolve to `None`.
+pub fn resolve_commit_language(flag: Option<&str>, repo: &Repository) -> Option<String> {
+    let language = match flag {
+        Some(language) => language.to_string(),
+        None => repo
+            .config()
+            .and_then(|config| config.get_string(COMMIT_LANGUAGE_CONFIG_KEY))
+            .ok()?,
+    };
+    let language = language.trim();
+    if language.is_empty() || language.eq_ignore_ascii_case("english") || language == "en" {
+        return None;
+    }
+    Some(language.to_string())
+}
+
+/// Commit message prompt, extended with language instructions when a language is set.
+fn commit_message_prompt(language: Option<&str>) -> String {
+    match language {
+        None => CommitMessage::prompt(vars()),
+        Some(language) => format!(
+            "{}\n\n# LANGUAGE\n\n\
+            - Write the summary and body in {language}.\n\
+            - Keep conventional-commit type and scope tokens (e.g. `feat`, `fix(parser):`) in \
+            English, along with code identifiers, file paths, and command names.\n\
+            - The JSON keys stay in English.\n",
+            CommitMessage::prompt(vars())
+        ),
+    }
+}
+
 /// Generate a commit message using the model, optionally calling back into file/patch tools.
 #[tracing::instrument(
     name = "Generating a commit message with LLM",
     level = "debug",
-    skip(client, diff, repo)
+    skip(client, diff, repo, options)
 )]
 pub async fn generate_commit_message<'c, 'd, C: Config>(
     client: &'c Client<C>,
+    model: &str,
     diff: &Diff<'d>,
     repo: &Repository,
+    options: CommitMessageOptions<'_>,
 ) -> AppResult<CommitMessage> {
+    let CommitMessageOptions {
+        language,
+        redactor,
+        budget,
+    } = options;
+    let prompt = with_glossary(&commit_message_prompt(
+        resolve_commit_language(language, repo).as_deref(),
+    ));
+    let mut summary = get_diff_summary(repo.path().parent().unwrap(), diff)?;
+    if let Some(redactor) = redactor {
+        redactor.redact_diff(&mut summary);
+    }
+    budget.fit_diff(&mut summary);
     // Kick off first turn with diff summary and commit prompt.
     let mut input_items: Vec<InputItem> = vec![InputItem::Item(Item::Message(MessageItem::Input(
         InputMessage {
             content: vec![InputContent::InputText(InputTextContent {
-                text: serde_json::to_string_pretty(&get_diff_summary(
-                    repo.path().parent().unwrap(),
-                    diff,
-                )?)?,
+                text: serde_json::to_string_pretty(&summary)?,
             })],
             role: InputRole::User,
             status: None,
@@ -70,97 +135,122 @@ pub async fn generate_commit_message<'c, 'd, C: Config>(
     input_items.push(InputItem::Item(Item::Message(MessageItem::Input(
         InputMessage {
             content: vec![InputContent::InputText(InputTextContent {
-                text: COMMIT_MESSAGE_PROMPT.to_string(),
+                text: prompt.clone(),
             })],
             role: InputRole::System,
             status: None,
         },
     ))));
-    let mut previous_response_id: Option<String> = None;
-    let tools = vec![
-        Tool::Function(GetPatch::definition()),
-        Tool::Function(GetFile::definition()),
-    ];
-
-    loop {
-        let request = CreateResponse {
-            model: Some("openai/gpt-oss-20b".to_string()),
-            input: InputParam::Items(input_items.clone()),
-            background: Some(false),
-            instructions: Some(COMMIT_MESSAGE_PROMPT.to_string()),
-            parallel_tool_calls: Some(false),
-            reasoning: Some(Reasoning {
-                effort: Some(ReasoningEffort::Medium),
-                summary: None,
-            }),
-            store: Some(true),
-            stream: Some(false),
-            temperature: Some(0.05),
-            text: Some(ResponseTextParam {
-                format: TextResponseFormatConfiguration::JsonSchema(
-                    CommitMessage::response_format(),
-                ),
-                verbosity: None,
-            }),
-            tool_choice: Some(ToolChoiceParam::Mode(ToolChoiceOptions::Auto)),
-            tools: Some(tools.clone()),
-            top_logprobs: Some(0),
-            top_p: Some(0.1),
-            truncation: Some(Truncation::Disabled),
-            previous_response_id: previous_response_id.clone(),
-            ..Default::default()
-        };
-
-        let response = client.responses().create(request).await?;
-        debug!("AI Response: {:?}", response);
-        previous_response_id = Some(response.id.clone());
-
-        let function_calls: Vec<FunctionToolCall> = response
-            .output
-            .iter()
-            .filter_map(|item| {
-                if let OutputItem::FunctionCall(fc) = item {
-                    Some(fc.clone())
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        if function_calls.is_empty() {
-            let mut response_content = String::new();
-            for out in &response.output {
-                if let OutputItem::Message(msg) = out {
-                    for content in &msg.content {
-                        match content {
-                            OutputMessageContent::OutputText(text) => {
-                                response_content.push_str(&text.text)
-                            }
-                            OutputMessageContent::Refusal(RefusalContent { refusal }) => {
-                                error!("AI refused prompt: {}", refusal);
-                            }
-                        }
-                    }
-                }
+    let registry = CommitMessageToolContext {
+        repo,
+        diff,
+        redactor,
+    };
+    let settings = AgentSettings {
+        instructions: &prompt,
+        effort: ReasoningEffort::Medium,
+        temperature: 0.05,
+        top_p: 0.1,
+        previous_response_id: None,
+    };
+    let agent = run_agent(
+        client,
+        model,
+        settings,
+        input_items,
+        &registry,
+        &PhantomData::<CommitMessage>,
+        budget,
+    )
+    .await?;
+    Ok(agent.answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::ai::stub_server::{StubServer, text_response, tool_call_response};
+
+    #[test]
+    fn flag_overrides_repo_commit_language() {
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let dir = std::env::temp_dir().join(format!("commit_language_{nonce}"));
+        let repo = Repository::init(&dir).unwrap();
+
+        assert_eq!(resolve_commit_language(None, &repo), None);
+
+        repo.config()
+            .unwrap()
+            .set_str(COMMIT_LANGUAGE_CONFIG_KEY, "German")
+            .unwrap();
+        assert_eq!(
+            resolve_commit_language(None, &repo).as_deref(),
+            Some("German")
+        );
+        assert_eq!(
+            resolve_commit_language(Some("Japanese"), &repo).as_deref(),
+            Some("Japanese")
+        );
+        assert_eq!(resolve_commit_language(Some("English"), &repo), None);
+
+        let prompt = commit_message_prompt(Some("German"));
+        assert!(prompt.starts_with(&CommitMessage::prompt(vars())));
+        assert!(prompt.contains("in German"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn redacts_staged_secrets_before_the_model_reads_them() {
+        let secret = "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY";
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let dir = std::env::temp_dir().join(format!("commit_redaction_{nonce}"));
+        let repo = Repository::init(&dir).unwrap();
+        std::fs::write(
+            dir.join("deploy.env"),
+            format!("AWS_SECRET_ACCESS_KEY={secret}\n"),
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("deploy.env")).unwrap();
+        let diff = repo.diff_tree_to_index(None, Some(&index), None).unwrap();
+        // The model reads the patch through its tool before answering.
+        let server = StubServer::start(|request| {
+            if request.body.to_string().contains("function_call_output") {
+                text_response(r#"{"summary": "Add deploy settings", "body": null}"#)
+            } else {
+                tool_call_response("get_patch", json!({"path": "deploy.env"}))
             }
-            return CommitMessage::from_str(&response_content);
-        }
+        })
+        .await;
+        let redactor = Redactor::new(&[]);
+
+        let message = generate_commit_message(
+            &server.client(),
+            "stub",
+            &diff,
+            &repo,
+            CommitMessageOptions {
+                language: None,
+                redactor: Some(&redactor),
+                budget: TokenBudget::new(crate::ai::token_budget::DEFAULT_CONTEXT_WINDOW),
+            },
+        )
+        .await
+        .unwrap();
 
-        // Handle each tool call in order and feed results back into the conversation.
-        for call in function_calls {
-            match call.name.as_str() {
-                name if name == GetFile::NAME => {
-                    input_items.extend(
-                        GetFile::process(call, &CommitMessageToolContext { repo, diff }).await,
-                    );
-                }
-                name if name == GetPatch::NAME => {
-                    input_items.extend(
-                        GetPatch::process(call, &CommitMessageToolContext { repo, diff }).await,
-                    );
-                }
-                _ => input_items.extend(unknown_tool(call)),
-            };
+        assert_eq!(message.summary, "Add deploy settings");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            let body = request.body.to_string();
+            assert!(!body.contains(secret), "{body}");
+            assert!(body.contains("AWS_SECRET_ACCESS_KEY=[REDACTED]"), "{body}");
         }
+        let _ = std::fs::remove_dir_all(dir);
     }
 }
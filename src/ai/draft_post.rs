@@ -0,0 +1,125 @@
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, ReasoningEffort,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::agent::{AgentSettings, run_agent};
+use super::glossary::with_glossary;
+use super::persona::with_about_me;
+use super::prompt::{Prompt, vars};
+use super::query::Query;
+use super::summary::MinifiedContext;
+use super::token_budget::TokenBudget;
+use super::tools::fetch::VisitedSites;
+use super::tools::summary::DraftTools;
+use crate::context::Context;
+use crate::{AppResult, impl_query};
+
+static DRAFT_POST_PROMPT: Prompt = Prompt::new(
+    "draft_post_prompt.md",
+    std::include_str!("prompts/draft_post_prompt.md"),
+);
+
+/// # draft_post
+/// A Markdown blog-post draft about one topic from the collected work.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DraftPostQuery {
+    /// Title of the post
+    pub title: String,
+    /// Body of the post in Markdown, without the title
+    pub markdown: String,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(DraftPostQuery, DRAFT_POST_PROMPT);
+
+impl DraftPostQuery {
+    /// The draft as one Markdown document, with the model's notes as a closing comment.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n{}\n", self.title.trim(), self.markdown.trim());
+        if !self.notes.is_empty() {
+            out.push_str("\n<!--\nDrafting notes:\n");
+            for note in &self.notes {
+                let _ = writeln!(out, "- {}", note.trim());
+            }
+            out.push_str("-->\n");
+        }
+        out
+    }
+}
+
+/// Input handed to the model: the topic hint and the work selected for it.
+#[derive(Serialize)]
+struct DraftInput<'a> {
+    topic: &'a str,
+    #[serde(flatten)]
+    context: MinifiedContext,
+}
+
+/// Draft a blog post about `topic` from `context`, letting the model pull diffs, commit
+/// messages, and pages through the summary tools.
+#[tracing::instrument(name = "Drafting a blog post", level = "debug", skip(client, context))]
+pub async fn generate_draft_post<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    context: &Context,
+    topic: &str,
+    offline: bool,
+    budget: TokenBudget,
+) -> AppResult<DraftPostQuery> {
+    let mut input = DraftInput {
+        topic,
+        context: MinifiedContext::from(context),
+    };
+    budget.fit_context(&mut input.context);
+    let prompt = with_glossary(&with_about_me(&DraftPostQuery::prompt(vars())));
+    let input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: serde_json::to_string_pretty(&input)?,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let registry = DraftTools {
+        context,
+        offline,
+        sites: VisitedSites::from_context(context),
+    };
+    let settings = AgentSettings {
+        instructions: &prompt,
+        effort: ReasoningEffort::High,
+        // Prose reads better with a little more room than the structured summary fields.
+        temperature: 0.4,
+        top_p: 0.9,
+        previous_response_id: None,
+    };
+    let agent = run_agent(
+        client,
+        model,
+        settings,
+        input_items,
+        &registry,
+        &PhantomData::<DraftPostQuery>,
+        budget,
+    )
+    .await?;
+    Ok(agent.answer)
+}
@@ -0,0 +1,272 @@
+use std::sync::OnceLock;
+
+use serde_json::Value;
+use tracing::debug;
+
+/// Names the user wants spelled their way, from the `glossary` key of the configuration file.
+static GLOSSARY: OnceLock<Glossary> = OnceLock::new();
+
+/// A name from the glossary, split into the words it is matched by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    /// The name as it should be written, e.g. `Kestrel` or `Platform Infra`.
+    canonical: String,
+    /// Its words in lower case, or nothing when it has punctuation and is only given to the model.
+    words: Vec<String>,
+}
+
+/// Product code names, team names, and other terms the model should spell and capitalize as
+/// given. They are listed in every prompt, and answers are corrected afterwards.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Glossary {
+    terms: Vec<Term>,
+}
+
+impl Glossary {
+    pub fn new(terms: Vec<String>) -> Self {
+        let mut terms: Vec<Term> = terms
+            .into_iter()
+            .filter(|term| !term.trim().is_empty())
+            .map(|canonical| {
+                let words = if canonical
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c.is_whitespace())
+                {
+                    canonical
+                        .split_whitespace()
+                        .map(str::to_lowercase)
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                Term { canonical, words }
+            })
+            .collect();
+        // Longer names first, so `Kestrel Ingest` wins over `Kestrel`.
+        terms.sort_by_key(|term| std::cmp::Reverse(term.words.len()));
+        Glossary { terms }
+    }
+
+    /// The prompt section listing the terms, or `None` without any.
+    fn prompt(&self) -> Option<String> {
+        if self.terms.is_empty() {
+            return None;
+        }
+        let mut terms: Vec<&str> = self.terms.iter().map(|t| t.canonical.as_str()).collect();
+        terms.sort_unstable();
+        Some(format!(
+            "# GLOSSARY\n\nThese are names of products, projects, and teams. Always spell and \
+             capitalize them exactly as written here:\n\n{}",
+            terms
+                .iter()
+                .map(|term| format!("- {term}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+    }
+
+    /// `text` with every glossary term spelled and capitalized as in the glossary.
+    ///
+    /// Terms match regardless of case. A single-word term of five or more letters also matches a
+    /// word of the same length with one letter wrong or two letters swapped, so `kestral` becomes
+    /// `Kestrel`. Words that are part of a URL, path, host name, or identifier are left alone.
+    pub fn correct(&self, text: &str) -> String {
+        let words = words(text);
+        let mut corrected = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut i = 0;
+        while i < words.len() {
+            let matched = self.terms.iter().find(|term| {
+                let n = term.words.len();
+                n > 0
+                    && i + n <= words.len()
+                    && words[i..i + n].iter().all(|word| word.plain)
+                    // Words of a longer name are separated only by whitespace.
+                    && words[i..i + n].windows(2).all(|pair| {
+                        text[pair[0].end..pair[1].start]
+                            .chars()
+                            .all(char::is_whitespace)
+                    })
+                    && words[i..i + n]
+                        .iter()
+                        .zip(&term.words)
+                        .all(|(word, term_word)| {
+                            word_matches(&text[word.start..word.end], term_word, n == 1)
+                        })
+            });
+            match matched {
+                Some(term) => {
+                    let n = term.words.len();
+                    let (start, end) = (words[i].start, words[i + n - 1].end);
+                    if text[start..end] != term.canonical {
+                        debug!("Correcting {} to {}", &text[start..end], term.canonical);
+                    }
+                    corrected.push_str(&text[copied..start]);
+                    corrected.push_str(&term.canonical);
+                    copied = end;
+                    i += n;
+                }
+                None => i += 1,
+            }
+        }
+        corrected.push_str(&text[copied..]);
+        corrected
+    }
+
+    /// Correct every string in `json`, leaving keys and anything that does not parse as is.
+    fn correct_json(&self, json: &str) -> String {
+        if self.terms.is_empty() {
+            return json.to_string();
+        }
+        let Ok(mut value) = serde_json::from_str::<Value>(json) else {
+            return json.to_string();
+        };
+        self.correct_value(&mut value);
+        serde_json::to_string(&value).unwrap_or_else(|_| json.to_string())
+    }
+
+    fn correct_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.correct(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.correct_value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.correct_value(item)),
+            _ => {}
+        }
+    }
+}
+
+/// A run of letters and digits in the text being corrected.
+#[derive(Debug, Clone, Copy)]
+struct Word {
+    start: usize,
+    end: usize,
+    /// Whether the word stands on its own rather than inside a URL, path, or identifier.
+    plain: bool,
+}
+
+/// The words of `text`, with byte offsets.
+fn words(text: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if !c.is_alphanumeric() {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some(&(i, next)) = chars.peek()
+            && next.is_alphanumeric()
+        {
+            end = i + next.len_utf8();
+            chars.next();
+        }
+        let before = text[..start].chars().next_back();
+        let mut after = text[end..].chars();
+        let (next, then) = (after.next(), after.next());
+        // `kestrel.io` and `kestrel://` are names, while `kestrel.` ends a sentence.
+        let joined = matches!(next, Some('.' | ':'))
+            && then.is_some_and(|c| c.is_alphanumeric() || c == '/');
+        let plain = !joined
+            && !before.is_some_and(|c| "./\\_-@#:`~".contains(c))
+            && !next.is_some_and(|c| "/\\_-@`".contains(c));
+        words.push(Word { start, end, plain });
+    }
+    words
+}
+
+/// Whether `word` is `term` (in lower case), ignoring case, or with `fuzzy` a likely misspelling
+/// of it.
+fn word_matches(word: &str, term: &str, fuzzy: bool) -> bool {
+    let word = word.to_lowercase();
+    if word == term {
+        return true;
+    }
+    let (word, term): (Vec<char>, Vec<char>) = (word.chars().collect(), term.chars().collect());
+    if !fuzzy || term.len() < 5 || word.len() != term.len() || word[0] != term[0] {
+        return false;
+    }
+    let differ: Vec<usize> = (0..term.len()).filter(|&i| word[i] != term[i]).collect();
+    match differ[..] {
+        [_] => true,
+        [a, b] => b == a + 1 && word[a] == term[b] && word[b] == term[a],
+        _ => false,
+    }
+}
+
+/// Use `terms` for every prompt and answer from now on. Only the first call has an effect.
+pub fn configure(terms: Vec<String>) {
+    let _ = GLOSSARY.set(Glossary::new(terms));
+}
+
+/// `prompt` followed by the glossary, when there is one.
+pub fn with_glossary(prompt: &str) -> String {
+    match GLOSSARY.get().and_then(Glossary::prompt) {
+        Some(glossary) => format!("{prompt}\n\n{glossary}"),
+        None => prompt.to_string(),
+    }
+}
+
+/// A model's JSON answer with the glossary terms in its strings corrected.
+pub fn correct_json(json: &str) -> String {
+    match GLOSSARY.get() {
+        Some(glossary) => glossary.correct_json(json),
+        None => json.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glossary() -> Glossary {
+        Glossary::new(vec![
+            "Kestrel".into(),
+            "Kestrel Ingest".into(),
+            "GraphQL".into(),
+            "SRE".into(),
+        ])
+    }
+
+    #[test]
+    fn corrects_case_and_near_misses() {
+        let glossary = glossary();
+        assert_eq!(
+            glossary.correct("Shipped kestral retries and a graphql schema for the sre team."),
+            "Shipped Kestrel retries and a GraphQL schema for the SRE team."
+        );
+        assert_eq!(
+            glossary.correct("Moved KESTREL  ingest to the new queue; kestrels nest."),
+            "Moved Kestrel Ingest to the new queue; kestrels nest."
+        );
+        // Too short to guess at, a letter missing, and two letters swapped.
+        assert_eq!(glossary.correct("Ask the SER team"), "Ask the SER team");
+        assert_eq!(glossary.correct("kestrl"), "kestrl");
+        assert_eq!(glossary.correct("kesrtel"), "Kestrel");
+    }
+
+    #[test]
+    fn leaves_urls_paths_and_identifiers_alone() {
+        let glossary = glossary();
+        let text = "See https://kestrel.example.com/kestral, ~/src/kestrel, kestrel_ingest, \
+                    and kestrel-cli.";
+        assert_eq!(glossary.correct(text), text);
+        assert_eq!(glossary.correct("It was kestrel."), "It was Kestrel.");
+    }
+
+    #[test]
+    fn corrects_strings_but_not_keys() {
+        let glossary = Glossary::new(vec!["Summary".into()]);
+        let json = glossary.correct_json(r#"{"summary": ["the summary"], "count": 2}"#);
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"summary": ["the Summary"], "count": 2})
+        );
+        assert!(
+            glossary
+                .prompt()
+                .unwrap()
+                .ends_with("exactly as written here:\n\n- Summary")
+        );
+        assert_eq!(Glossary::default().prompt(), None);
+    }
+}
@@ -0,0 +1,61 @@
+use std::future::Future;
+use std::time::Duration;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::responses::{CreateResponse, InputParam};
+use tracing::{debug, info};
+
+use super::chat;
+
+/// Tokens asked for in a ping; the smallest number every provider accepts.
+const PING_TOKENS: u32 = 16;
+
+/// Ask `model` for a few tokens, only so the server loads it or keeps it loaded. Failures are
+/// logged and otherwise ignored: the run's own requests report them properly.
+async fn ping<C: Config>(client: &Client<C>, model: &str) {
+    let request = CreateResponse {
+        model: Some(model.to_string()),
+        input: InputParam::Text("ping".to_string()),
+        max_output_tokens: Some(PING_TOKENS),
+        store: Some(false),
+        stream: Some(false),
+        ..Default::default()
+    };
+    match chat::create(client, request).await {
+        Ok(_) => debug!("Pinged {model}"),
+        Err(e) => debug!("Unable to ping {model}: {e}"),
+    }
+}
+
+/// Ping each of `models` now, to load them, and then every `every`. Never returns.
+async fn ping_every<C: Config>(client: &Client<C>, models: &[&str], every: Duration) {
+    info!("Loading {} on the model server", models.join(" and "));
+    loop {
+        for model in models {
+            ping(client, model).await;
+        }
+        tokio::time::sleep(every).await;
+    }
+}
+
+/// Run `work` while keeping `models` loaded on the server.
+///
+/// Local servers unload a model after a few idle minutes, so the first question after a long
+/// collection would otherwise wait for it to load again. With `every` set, each model is asked
+/// for a few tokens as `work` starts, which loads it while the collectors run, and again every
+/// `every` until `work` finishes.
+pub async fn keep_warm<C: Config, T>(
+    client: &Client<C>,
+    models: &[&str],
+    every: Option<Duration>,
+    work: impl Future<Output = T>,
+) -> T {
+    let Some(every) = every.filter(|_| !models.is_empty()) else {
+        return work.await;
+    };
+    tokio::select! {
+        output = work => output,
+        () = ping_every(client, models, every) => unreachable!("the pings never stop"),
+    }
+}
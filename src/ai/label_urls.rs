@@ -6,20 +6,26 @@ use async_openai::types::evals::InputTextContent;
 use async_openai::types::responses::{
     CreateResponse, FunctionToolCall, InputContent, InputItem, InputMessage, InputParam, InputRole,
     Item, MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort,
-    RefusalContent, ResponseTextParam, TextResponseFormatConfiguration, Tool, ToolChoiceOptions,
+    RefusalContent, ResponseTextParam, TextResponseFormatConfiguration, ToolChoiceOptions,
     ToolChoiceParam, Truncation,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
-use super::query::Query;
-use super::tools::fetch::FetchUrl;
-use super::tools::{CustomTool, unknown_tool};
+use super::glossary::with_glossary;
+use super::prompt::{Prompt, vars};
+use super::query::{Parsed, Query};
+use super::retry::{check_tool_rounds, create_response};
+use super::tools::ToolRegistry;
+use super::tools::fetch::{FetchTools, VisitedSites};
 use crate::safari::SafariHistoryItem;
 use crate::{AppResult, impl_query};
 
-static LABEL_URLS_PROMPT: &str = std::include_str!("prompts/label_urls_prompt.md");
+static LABEL_URLS_PROMPT: Prompt = Prompt::new(
+    "label_urls_prompt.md",
+    std::include_str!("prompts/label_urls_prompt.md"),
+);
 
 /// Label returned by the model for a cluster of URLs.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -44,8 +50,11 @@ impl_query!(UrlLabel, LABEL_URLS_PROMPT);
 )]
 pub async fn label_url_cluster<C: Config>(
     client: &Client<C>,
+    model: &str,
     urls: &[SafariHistoryItem],
+    offline: bool,
 ) -> AppResult<UrlLabel> {
+    let prompt = with_glossary(&UrlLabel::prompt(vars()));
     // Kick off first turn with the URL list and system prompt.
     let mut input_items: Vec<InputItem> = vec![InputItem::Item(Item::Message(MessageItem::Input(
         InputMessage {
@@ -59,21 +68,28 @@ pub async fn label_url_cluster<C: Config>(
     input_items.push(InputItem::Item(Item::Message(MessageItem::Input(
         InputMessage {
             content: vec![InputContent::InputText(InputTextContent {
-                text: LABEL_URLS_PROMPT.to_string(),
+                text: prompt.clone(),
             })],
             role: InputRole::System,
             status: None,
         },
     ))));
-    let tools = vec![Tool::Function(FetchUrl::definition())];
+    // Offline runs label from titles and URLs alone.
+    let registry = FetchTools {
+        offline,
+        sites: VisitedSites::new(urls),
+    };
+    let tools = registry.definitions();
     let mut previous_response_id: Option<String> = None;
+    let mut tool_rounds = 0;
+    let mut schema_retries = 0;
 
     loop {
         let request = CreateResponse {
-            model: Some("openai/gpt-oss-20b".to_string()),
+            model: Some(model.to_string()),
             input: InputParam::Items(input_items.clone()),
             background: Some(false),
-            instructions: Some(LABEL_URLS_PROMPT.to_string()),
+            instructions: Some(prompt.clone()),
             parallel_tool_calls: Some(false),
             reasoning: Some(Reasoning {
                 effort: Some(ReasoningEffort::Medium),
@@ -86,8 +102,9 @@ pub async fn label_url_cluster<C: Config>(
                 format: TextResponseFormatConfiguration::JsonSchema(UrlLabel::response_format()),
                 verbosity: None,
             }),
-            tool_choice: Some(ToolChoiceParam::Mode(ToolChoiceOptions::Auto)),
-            tools: Some(tools.clone()),
+            tool_choice: (!tools.is_empty())
+                .then_some(ToolChoiceParam::Mode(ToolChoiceOptions::Auto)),
+            tools: (!tools.is_empty()).then(|| tools.clone()),
             top_logprobs: Some(0),
             top_p: Some(0.1),
             truncation: Some(Truncation::Disabled),
@@ -95,7 +112,7 @@ pub async fn label_url_cluster<C: Config>(
             ..Default::default()
         };
 
-        let response = client.responses().create(request).await?;
+        let response = create_response(client, request).await?;
         debug!("AI Response: {:?}", response);
         previous_response_id = Some(response.id.clone());
 
@@ -127,17 +144,21 @@ pub async fn label_url_cluster<C: Config>(
                     }
                 }
             }
-            return UrlLabel::from_str(&response_content);
+            match UrlLabel::from_answer(&response_content, &mut schema_retries)? {
+                Parsed::Answer(answer) => return Ok(answer),
+                Parsed::Retry(message) => {
+                    input_items.push(message);
+                    continue;
+                }
+            }
         }
 
+        tool_rounds += 1;
+        check_tool_rounds(tool_rounds)?;
+
         // Handle each tool call in sequence and feed results back to the model.
         for call in function_calls {
-            match call.name.as_str() {
-                name if name == FetchUrl::NAME => {
-                    input_items.extend(FetchUrl::process(call, &()).await);
-                }
-                _ => input_items.extend(unknown_tool(call)),
-            };
+            input_items.extend(registry.execute(call).await);
         }
     }
 }
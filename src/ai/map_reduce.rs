@@ -0,0 +1,470 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    CreateResponse, InputContent, InputItem, InputMessage, InputParam, InputRole, Item,
+    MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort, RefusalContent,
+    ResponseTextParam, TextResponseFormatConfiguration, Truncation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+use super::cache::ResponseCache;
+use super::glossary::with_glossary;
+use super::prompt::{Prompt, vars};
+use super::query::{Parsed, Query};
+use super::retry::create_response;
+use super::summary::MinifiedGitRepoHistory;
+use super::token_budget::{TokenBudget, estimate_json_tokens, estimate_tokens, truncate_text};
+use crate::git::GitRepoHistory;
+use crate::git::diff::DiffSummary;
+use crate::{AppResult, impl_query};
+
+static DIFF_CHUNK_PROMPT: Prompt = Prompt::new(
+    "diff_chunk_prompt.md",
+    std::include_str!("prompts/full_summary/diff_chunk_prompt.md"),
+);
+static DIFF_DIGEST_PROMPT: Prompt = Prompt::new(
+    "diff_digest_prompt.md",
+    std::include_str!("prompts/full_summary/diff_digest_prompt.md"),
+);
+static REPO_DIGEST_PROMPT: Prompt = Prompt::new(
+    "repo_digest_prompt.md",
+    std::include_str!("prompts/full_summary/repo_digest_prompt.md"),
+);
+
+/// Repositories the summary queries see in full. With more, each is summarized on its own first.
+pub const MAX_DETAILED_REPOS: usize = 10;
+
+/// # diff_chunk
+/// A summary of one part of a large diff.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DiffChunkQuery {
+    /// What changed in this part
+    pub summary: String,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(DiffChunkQuery, DIFF_CHUNK_PROMPT);
+
+/// # diff_digest
+/// A summary of a repository's changes, combined from the summaries of its parts.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DiffDigestQuery {
+    /// What changed in the repository
+    pub summary: String,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(DiffDigestQuery, DIFF_DIGEST_PROMPT);
+
+/// # repo_digest
+/// A short summary of the work in one repository.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RepoDigestQuery {
+    /// The work done in the repository
+    pub summary: String,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(RepoDigestQuery, REPO_DIGEST_PROMPT);
+
+/// A changed file in a chunk of a diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ChunkFile {
+    path: PathBuf,
+    /// `added`, `modified`, or `untracked`.
+    status: &'static str,
+    patch: String,
+}
+
+/// Neighbouring directories of a diff, small enough to summarize in one request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+struct DiffChunk {
+    directories: Vec<PathBuf>,
+    files: Vec<ChunkFile>,
+}
+
+/// The directory `path` is in, or `.` for the top of the repository.
+fn directory(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Split the patches of `diff` into chunks of at most about `max_tokens` each.
+///
+/// Files are grouped by directory, and directories are packed into chunks in path order, so a
+/// chunk holds related code. A directory too large for one chunk is split between files, and a
+/// single patch too large for one is cut.
+fn chunk_diff(diff: &DiffSummary, max_tokens: usize) -> Vec<DiffChunk> {
+    let mut directories: BTreeMap<PathBuf, Vec<ChunkFile>> = BTreeMap::new();
+    let changed = [
+        ("added", &diff.added),
+        ("modified", &diff.modified),
+        ("untracked", &diff.untracked),
+    ];
+    for (status, files) in changed {
+        for file in files {
+            directories
+                .entry(directory(&file.path))
+                .or_default()
+                .push(ChunkFile {
+                    path: file.path.clone(),
+                    status,
+                    patch: truncate_text(&file.patch, max_tokens),
+                });
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut chunk = DiffChunk::default();
+    let mut tokens = 0;
+    for (dir, mut files) in directories {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        // Start the directory in a new chunk rather than split it, when it fits in one.
+        if tokens + estimate_json_tokens(&files) > max_tokens && !chunk.files.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            tokens = 0;
+        }
+        for file in files {
+            let cost = estimate_json_tokens(&file);
+            if tokens + cost > max_tokens && !chunk.files.is_empty() {
+                chunks.push(std::mem::take(&mut chunk));
+                tokens = 0;
+            }
+            if chunk.directories.last() != Some(&dir) {
+                chunk.directories.push(dir.clone());
+            }
+            chunk.files.push(file);
+            tokens += cost;
+        }
+    }
+    if !chunk.files.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Ask `model` the query `Q` about `input`, without tools, reusing a cached answer when there
+/// is one.
+async fn ask_once<C: Config, Q: Query>(
+    client: &Client<C>,
+    model: &str,
+    input: &impl Serialize,
+    cache: &ResponseCache,
+) -> AppResult<Q> {
+    let prompt = with_glossary(&Q::prompt(vars()));
+    let input_text = serde_json::to_string_pretty(input)?;
+    let cache_key = ResponseCache::key(model, &prompt, &input_text)?;
+    if let Some(cached) = cache.get(&cache_key).await
+        && let Ok(answer) = Q::from_str(&cached)
+    {
+        return Ok(answer);
+    }
+
+    let mut input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: input_text,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let mut schema_retries = 0;
+    loop {
+        let request = CreateResponse {
+            model: Some(model.to_string()),
+            input: InputParam::Items(input_items.clone()),
+            background: Some(false),
+            instructions: Some(prompt.clone()),
+            reasoning: Some(Reasoning {
+                effort: Some(ReasoningEffort::Medium),
+                summary: None,
+            }),
+            store: Some(false),
+            stream: Some(false),
+            temperature: Some(0.05),
+            text: Some(ResponseTextParam {
+                format: TextResponseFormatConfiguration::JsonSchema(Q::response_format()),
+                verbosity: None,
+            }),
+            top_logprobs: Some(0),
+            top_p: Some(0.1),
+            truncation: Some(Truncation::Disabled),
+            ..Default::default()
+        };
+
+        let response = create_response(client, request).await?;
+        debug!("AI Response: {:?}", response);
+
+        let mut response_content = String::new();
+        for out in &response.output {
+            if let OutputItem::Message(msg) = out {
+                for content in &msg.content {
+                    match content {
+                        OutputMessageContent::OutputText(text) => {
+                            response_content.push_str(&text.text)
+                        }
+                        OutputMessageContent::Refusal(RefusalContent { refusal }) => {
+                            error!("AI refused prompt: {}", refusal);
+                        }
+                    }
+                }
+            }
+        }
+        match Q::from_answer(&response_content, &mut schema_retries)? {
+            Parsed::Answer(answer) => {
+                cache.put(&cache_key, &response_content).await;
+                return Ok(answer);
+            }
+            Parsed::Retry(message) => input_items.push(message),
+        }
+    }
+}
+
+/// What the model is told about one part of a diff.
+#[derive(Serialize)]
+struct ChunkInput<'a> {
+    project: &'a str,
+    part: String,
+    directories: &'a [PathBuf],
+    files: &'a [ChunkFile],
+}
+
+/// The summary of one part of a diff, as passed on to the digest.
+#[derive(Serialize)]
+struct ChunkSummary<'a> {
+    directories: &'a [PathBuf],
+    summary: String,
+}
+
+/// What the model is told when combining the parts of a diff.
+#[derive(Serialize)]
+struct DigestInput<'a> {
+    project: &'a str,
+    commits: Vec<&'a str>,
+    parts: Vec<ChunkSummary<'a>>,
+    deleted: Vec<&'a Path>,
+    renamed: Vec<String>,
+}
+
+/// Summarize the diff of `repo` part by part, then combine the parts into one summary.
+async fn digest_diff<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    repo: &GitRepoHistory,
+    max_tokens: usize,
+    cache: &ResponseCache,
+) -> AppResult<String> {
+    let project = repo.project_name();
+    let chunks = chunk_diff(&repo.diff, max_tokens);
+    info!(
+        "The diff of {project} is too large to read at once; summarizing it in {} parts",
+        chunks.len()
+    );
+    let mut parts = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let input = ChunkInput {
+            project: &project,
+            part: format!("{} of {}", i + 1, chunks.len()),
+            directories: &chunk.directories,
+            files: &chunk.files,
+        };
+        let answer: DiffChunkQuery = ask_once(client, model, &input, cache).await?;
+        parts.push(ChunkSummary {
+            directories: &chunk.directories,
+            summary: answer.summary,
+        });
+    }
+
+    let mut deleted: Vec<&Path> = repo.diff.deleted.iter().map(PathBuf::as_path).collect();
+    deleted.sort();
+    let mut renamed: Vec<String> = repo
+        .diff
+        .renamed
+        .iter()
+        .map(|r| format!("{} -> {}", r.from.display(), r.to.display()))
+        .collect();
+    renamed.sort();
+    let input = DigestInput {
+        project: &project,
+        commits: repo.commits.iter().map(|c| c.summary.as_str()).collect(),
+        parts,
+        deleted,
+        renamed,
+    };
+    let answer: DiffDigestQuery = ask_once(client, model, &input, cache).await?;
+    Ok(answer.summary)
+}
+
+/// Digests of the repositories in `repos` whose diff is too large for one `get_diff` result,
+/// by repository path.
+///
+/// Such a diff would reach the model cut short, so it is summarized in chunks of neighbouring
+/// directories first, and the chunk summaries are combined into one summary of the repository.
+/// A repository whose digest fails is left out, and the model falls back to the cut diff.
+pub async fn digest_large_diffs<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    repos: &[GitRepoHistory],
+    budget: TokenBudget,
+    cache: &ResponseCache,
+) -> BTreeMap<PathBuf, String> {
+    let mut digests = BTreeMap::new();
+    for repo in repos {
+        if estimate_json_tokens(&repo.diff) <= budget.tool_output_tokens() {
+            continue;
+        }
+        match digest_diff(client, model, repo, budget.context_tokens(), cache).await {
+            Ok(digest) => {
+                debug!(
+                    "Digest of {}: {} tokens",
+                    repo.diff.repo_path.display(),
+                    estimate_tokens(&digest)
+                );
+                digests.insert(repo.diff.repo_path.clone(), digest);
+            }
+            Err(e) => error!(
+                "Unable to summarize the diff of {} in parts: {e}",
+                repo.diff.repo_path.display()
+            ),
+        }
+    }
+    digests
+}
+
+/// What the model is told about one repository on a busy day.
+#[derive(Serialize)]
+struct RepoInput<'a> {
+    #[serde(flatten)]
+    repo: &'a MinifiedGitRepoHistory,
+    /// The diff as JSON, cut to fit, unless `repo` carries a digest of it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+}
+
+/// Summarize each of `repos` on its own, when there are more than [`MAX_DETAILED_REPOS`].
+///
+/// The summary queries then see each repository's summary in place of its commits and diff
+/// digest, rather than losing repositories when the context is trimmed to fit. `full` holds the
+/// collected repositories, for their diffs. A repository whose summary fails is kept as it was.
+pub async fn digest_repos<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    repos: &mut [MinifiedGitRepoHistory],
+    full: &[GitRepoHistory],
+    budget: TokenBudget,
+    cache: &ResponseCache,
+) {
+    if repos.len() <= MAX_DETAILED_REPOS {
+        return;
+    }
+    info!(
+        "Summarizing each of the {} repositories worked in before the summary",
+        repos.len()
+    );
+    for repo in repos.iter_mut() {
+        let diff = match &repo.diff_digest {
+            Some(_) => None,
+            None => full
+                .iter()
+                .find(|r| r.diff.repo_path == repo.repo)
+                .and_then(|r| serde_json::to_string_pretty(&r.diff).ok())
+                .map(|diff| truncate_text(&diff, budget.context_tokens())),
+        };
+        let input = RepoInput { repo: &*repo, diff };
+        match ask_once::<_, RepoDigestQuery>(client, model, &input, cache).await {
+            Ok(answer) => {
+                repo.summary = Some(answer.summary);
+                repo.commits.clear();
+                repo.diff_digest = None;
+            }
+            Err(e) => error!("Unable to summarize {}: {e}", repo.repo.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::git::diff::DiffWithPatch;
+
+    fn patch(path: &str, lines: usize) -> DiffWithPatch {
+        DiffWithPatch {
+            path: PathBuf::from(path),
+            patch: (0..lines).map(|i| format!("+line {i}\n")).collect(),
+        }
+    }
+
+    #[test]
+    fn packs_neighbouring_directories_into_chunks() {
+        let diff = DiffSummary {
+            repo_path: PathBuf::from("/repo"),
+            unmodified: HashSet::new(),
+            added: vec![patch("src/parser/recover.rs", 40), patch("README.md", 5)],
+            deleted: HashSet::new(),
+            modified: vec![
+                patch("src/parser/mod.rs", 40),
+                patch("src/lexer/mod.rs", 40),
+                patch("tests/big.rs", 2000),
+            ],
+            renamed: HashSet::new(),
+            copied: HashSet::new(),
+            untracked: vec![],
+            typechange: HashSet::new(),
+            unreadable: HashSet::new(),
+            conflicted: HashSet::new(),
+        };
+        let chunks = chunk_diff(&diff, 500);
+
+        let layout: Vec<Vec<&str>> = chunks
+            .iter()
+            .map(|chunk| {
+                chunk
+                    .files
+                    .iter()
+                    .map(|f| f.path.to_str().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                vec!["README.md", "src/lexer/mod.rs"],
+                vec!["src/parser/mod.rs", "src/parser/recover.rs"],
+                vec!["tests/big.rs"],
+            ]
+        );
+        assert_eq!(
+            chunks[0].directories,
+            [PathBuf::from("."), PathBuf::from("src/lexer")]
+        );
+        assert!(
+            chunks[2].files[0]
+                .patch
+                .ends_with("cut to fit the context window]")
+        );
+    }
+}
@@ -1,11 +1,49 @@
+pub mod agent;
+pub mod ask;
+pub mod cache;
+pub mod chat;
 pub mod commit_message;
+pub mod draft_post;
+pub mod glossary;
+pub mod keep_alive;
 pub mod label_urls;
+pub mod map_reduce;
+pub mod persona;
+pub mod prompt;
+pub mod proofread;
+pub mod provider;
 pub mod query;
+pub mod retry;
+pub mod rollup;
+pub mod standup;
+#[cfg(test)]
+pub mod stub_server;
 pub mod summary;
+pub mod tagging;
+pub mod timesheet;
+pub mod token_budget;
 pub mod tools;
 
 use tracing::info;
 
+/// Model asked on the language model server unless `--model` says otherwise.
+pub const DEFAULT_MODEL: &str = "openai/gpt-oss-20b";
+
+/// Kinds of requests that can go to a different model than `--model`, e.g. a small, fast model
+/// for labelling URL clusters and a larger one for the summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelTask {
+    /// Naming clusters of browsing history.
+    LabelUrls,
+    /// Writing commit messages.
+    CommitMessage,
+    /// The summary queries, the stand-up update, and the drafts, answers, rollups, and
+    /// timesheets written from collected work.
+    Summary,
+    /// Summaries of large diffs and of each repository on busy days, read by the summary.
+    Digest,
+}
+
 pub trait SchemaInfo: Sized {
     fn schema_value() -> serde_json::Value;
     fn title() -> String;
@@ -0,0 +1,101 @@
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+/// Who the reports are written for, from the `[about_me]` table of the configuration file.
+static ABOUT_ME: OnceLock<AboutMe> = OnceLock::new();
+
+/// Stable facts about the user, added to every summary prompt so the model uses the right
+/// terminology and emphasis.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AboutMe {
+    /// What to call the user.
+    pub name: Option<String>,
+    /// Job title or role, e.g. `Staff engineer, platform`.
+    pub role: Option<String>,
+    /// Company or organization the user works for.
+    pub employer: Option<String>,
+    /// Team and what it owns.
+    pub team: Option<String>,
+    /// Projects in flight, so their names are recognized in commits and URLs.
+    pub projects: Vec<String>,
+    /// How reports should read, e.g. `Plain and terse; no marketing language`.
+    pub style: Option<String>,
+    /// Time zone the user works in, e.g. `Europe/Berlin`, when the local one is not it.
+    pub timezone: Option<String>,
+    /// Language to write reports in, e.g. `German`.
+    pub language: Option<String>,
+}
+
+impl AboutMe {
+    /// The prompt section describing the user, or `None` when nothing is set.
+    fn prompt(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        if let Some(name) = &self.name {
+            lines.push(format!("- Name: {name}"));
+        }
+        if let Some(role) = &self.role {
+            lines.push(format!("- Role: {role}"));
+        }
+        if let Some(employer) = &self.employer {
+            lines.push(format!("- Employer: {employer}"));
+        }
+        if let Some(team) = &self.team {
+            lines.push(format!("- Team: {team}"));
+        }
+        if !self.projects.is_empty() {
+            lines.push(format!("- Current projects: {}", self.projects.join(", ")));
+        }
+        if let Some(style) = &self.style {
+            lines.push(format!("- Writing style: {style}"));
+        }
+        if let Some(timezone) = &self.timezone {
+            lines.push(format!("- Time zone: {timezone}"));
+        }
+        if let Some(language) = &self.language {
+            lines.push(format!("- Write the report in {language}"));
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "# ABOUT THE USER\n\nThe user has described themselves as follows. Use it to choose \
+             terminology, emphasis, and tone, and to recognize project names; do not repeat it \
+             back or treat it as activity.\n\n{}",
+            lines.join("\n")
+        ))
+    }
+}
+
+/// Use `about_me` for every prompt from now on. Only the first call has an effect.
+pub fn configure(about_me: AboutMe) {
+    let _ = ABOUT_ME.set(about_me);
+}
+
+/// `prompt` followed by what the user has said about themselves, when anything.
+pub fn with_about_me(prompt: &str) -> String {
+    match ABOUT_ME.get().and_then(AboutMe::prompt) {
+        Some(about_me) => format!("{prompt}\n\n{about_me}"),
+        None => prompt.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_only_the_fields_that_are_set() {
+        assert_eq!(AboutMe::default().prompt(), None);
+
+        let about_me = AboutMe {
+            role: Some("SRE".into()),
+            projects: vec!["ingest-v2".into(), "pager rotation".into()],
+            ..Default::default()
+        };
+        let prompt = about_me.prompt().unwrap();
+        assert!(prompt.starts_with("# ABOUT THE USER"));
+        assert!(prompt.ends_with("- Role: SRE\n- Current projects: ingest-v2, pager rotation"));
+    }
+}
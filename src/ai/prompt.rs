@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use minijinja::Environment;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::persona::AboutMe;
+use crate::dirs::DirType;
+use crate::time_utils;
+
+/// Directory under the configuration directory with prompts that replace the built-in ones.
+const PROMPTS_DIR: &str = "prompts";
+
+/// Prompts read from the prompts directory at startup, by file name.
+static OVERRIDES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Values for the prompts, from the `[about_me]` table of the configuration file.
+static VARS: OnceLock<PromptVars> = OnceLock::new();
+
+/// Used until [`configure`] is called.
+static NO_VARS: PromptVars = PromptVars {
+    name: None,
+    employer: None,
+    timezone: None,
+    tone: None,
+    length: None,
+    language: None,
+};
+
+/// Voice of the summary, as given to `--tone`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    /// Complete sentences without slang, as in an update to a manager
+    Formal,
+    /// Relaxed and conversational, as in a note to a teammate
+    Casual,
+    /// Terse bullet points instead of paragraphs
+    BulletPoints,
+}
+
+impl Tone {
+    /// How the prompts describe the tone.
+    fn describe(self) -> &'static str {
+        match self {
+            Tone::Formal => {
+                "formal and precise, as in an update to a manager: complete sentences, no slang, \
+                 contractions, or jokes."
+            }
+            Tone::Casual => {
+                "casual and conversational, as in a note to a teammate: plain words and \
+                 contractions are fine, but keep the technical details."
+            }
+            Tone::BulletPoints => {
+                "terse bullet points instead of paragraphs: one line starting with \"- \" per \
+                 piece of work, without connecting prose."
+            }
+        }
+    }
+}
+
+/// How long the summary is, as given to `--length`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {
+    /// A few sentences
+    Short,
+    /// A few paragraphs, as without --length
+    Medium,
+    /// Every thread of work in depth
+    Detailed,
+}
+
+impl Length {
+    /// How the prompts ask for the length.
+    fn describe(self) -> &'static str {
+        match self {
+            Length::Short => {
+                "Keep it short: two to four sentences, or at most five bullet points, covering \
+                 only the main threads of work."
+            }
+            Length::Medium => "Aim for two to four paragraphs, or at most twelve bullet points.",
+            Length::Detailed => {
+                "Go into detail: cover every thread of work in its own paragraph or group of \
+                 bullet points, naming the files, commands, errors, and reasons behind it."
+            }
+        }
+    }
+}
+
+/// Values a prompt can use as minijinja variables, e.g. `{{ name }}` or
+/// `{% if language %}Write in {{ language }}.{% endif %}`. Unset values are empty.
+///
+/// The summary prompts use `tone` and `length` to follow `--tone` and `--length`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PromptVars {
+    pub name: Option<String>,
+    pub employer: Option<String>,
+    /// The configured time zone, or the local UTC offset, e.g. `UTC+02:00`.
+    pub timezone: Option<String>,
+    /// The `--tone` preset, or else the configured writing style.
+    pub tone: Option<String>,
+    /// The `--length` preset.
+    pub length: Option<String>,
+    pub language: Option<String>,
+}
+
+impl PromptVars {
+    pub fn new(about_me: &AboutMe) -> Self {
+        let local_offset = || {
+            let (hours, minutes, _) = time_utils::known_local_offset()?.as_hms();
+            Some(format!("UTC{hours:+03}:{:02}", minutes.abs()))
+        };
+        PromptVars {
+            name: about_me.name.clone(),
+            employer: about_me.employer.clone(),
+            timezone: about_me.timezone.clone().or_else(local_offset),
+            tone: about_me.style.clone(),
+            length: None,
+            language: about_me.language.clone(),
+        }
+    }
+
+    /// These values with the `--tone` and `--length` presets, when given. A tone replaces the
+    /// configured writing style.
+    pub fn with_presets(mut self, tone: Option<Tone>, length: Option<Length>) -> Self {
+        if let Some(tone) = tone {
+            self.tone = Some(tone.describe().to_string());
+        }
+        self.length = length.map(|length| length.describe().to_string());
+        self
+    }
+}
+
+/// The values for the prompts.
+pub fn vars() -> &'static PromptVars {
+    VARS.get().unwrap_or(&NO_VARS)
+}
+
+/// A prompt compiled into the binary. A file of the same name in `~/.config/dailyai/prompts/`,
+/// e.g. `summary_prompt.md`, is used in its place, so the wording can be tuned without a rebuild.
+#[derive(Debug)]
+pub struct Prompt {
+    /// File name of the built-in prompt, without the directory it is in.
+    name: &'static str,
+    builtin: &'static str,
+}
+
+impl Prompt {
+    pub const fn new(name: &'static str, builtin: &'static str) -> Self {
+        Prompt { name, builtin }
+    }
+
+    /// The user's version of the prompt when there is one, the built-in prompt otherwise.
+    fn text(&self) -> &'static str {
+        OVERRIDES
+            .get()
+            .and_then(|overrides| overrides.get(self.name))
+            .map_or(self.builtin, String::as_str)
+    }
+
+    /// The prompt with `vars` filled in. A prompt that cannot be filled in is used as written.
+    pub fn render(&self, vars: &PromptVars) -> String {
+        let text = self.text();
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        env.render_str(text, vars).unwrap_or_else(|e| {
+            warn!("Unable to fill in {}, using it as written: {e}", self.name);
+            text.to_string()
+        })
+    }
+}
+
+/// Read the prompt files in `dir`, by file name. Empty and unreadable files are skipped.
+fn read_overrides(dir: &Path) -> HashMap<String, String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    let mut overrides = HashMap::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || name.starts_with('.') {
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(text) if text.trim().is_empty() => {
+                warn!("Ignoring {}: the prompt is empty", path.display());
+            }
+            Ok(text) => match Environment::new().template_from_str(&text) {
+                Ok(_) => {
+                    info!("Using {} in place of the built-in prompt", path.display());
+                    overrides.insert(name.to_string(), text);
+                }
+                Err(e) => warn!("Ignoring {}: {e}", path.display()),
+            },
+            Err(e) => warn!("Ignoring {}: {e}", path.display()),
+        }
+    }
+    overrides
+}
+
+/// Read the user's prompts from the configuration directory, and fill `vars` in to every prompt
+/// from now on. Only the first call has an effect.
+pub fn configure(vars: PromptVars) {
+    let _ = VARS.set(vars);
+    let overrides = match DirType::Config.get_dir() {
+        Ok(dir) => read_overrides(&dir.join(PROMPTS_DIR)),
+        Err(e) => {
+            warn!("Using the built-in prompts: {e}");
+            HashMap::new()
+        }
+    };
+    let _ = OVERRIDES.set(overrides);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_non_empty_prompt_files_by_name() {
+        let dir = std::env::temp_dir().join(format!("prompts_{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(dir.join("full_summary")).unwrap();
+        std::fs::write(dir.join("summary_prompt.md"), "Be brief.").unwrap();
+        std::fs::write(dir.join("ask_prompt.md"), "  \n").unwrap();
+        std::fs::write(dir.join(".summary_prompt.md.swp"), "x").unwrap();
+
+        let overrides = read_overrides(&dir);
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides["summary_prompt.md"], "Be brief.");
+        assert!(read_overrides(&dir.join("missing")).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn fills_in_the_values_that_are_set() {
+        let prompt = Prompt::new(
+            "test_prompt.md",
+            "Hi {{ name }}.{% if language %} Write in {{ language }}.{% endif %}\n",
+        );
+        let vars = PromptVars {
+            name: Some("Sam".into()),
+            ..Default::default()
+        };
+        assert_eq!(prompt.render(&vars), "Hi Sam.\n");
+        let vars = PromptVars {
+            language: Some("German".into()),
+            ..vars
+        };
+        assert_eq!(prompt.render(&vars), "Hi Sam. Write in German.\n");
+        let broken = Prompt::new("broken_prompt.md", "Hi {{ name");
+        assert_eq!(broken.render(&vars), "Hi {{ name");
+    }
+
+    #[test]
+    fn asks_for_the_tone_and_length_presets() {
+        let summary = Prompt::new(
+            "summary_prompt.md",
+            include_str!("prompts/full_summary/summary_prompt.md"),
+        );
+        assert!(
+            !summary
+                .render(&PromptVars::default())
+                .contains("# STYLE AND LENGTH")
+        );
+
+        let style = PromptVars {
+            tone: Some("Plain and terse".into()),
+            ..Default::default()
+        };
+        assert_eq!(style.clone().with_presets(None, None), style);
+        let prompt =
+            summary.render(&style.with_presets(Some(Tone::BulletPoints), Some(Length::Short)));
+        assert!(prompt.contains("- Write in this style: terse bullet points"));
+        assert!(prompt.contains("- Keep it short: two to four sentences"));
+    }
+}
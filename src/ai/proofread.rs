@@ -0,0 +1,119 @@
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    CreateResponse, InputContent, InputItem, InputMessage, InputParam, InputRole, Item,
+    MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort, RefusalContent,
+    ResponseTextParam, TextResponseFormatConfiguration, Truncation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use super::prompt::{Prompt, vars};
+use super::query::{Parsed, Query};
+use super::retry::create_response;
+use crate::{AppResult, impl_query};
+
+static PROOFREAD_PROMPT: Prompt = Prompt::new(
+    "proofread_prompt.md",
+    std::include_str!("prompts/proofread_prompt.md"),
+);
+
+/// # proofread_passages
+/// The passages of a daily log with their typos and broken sentences fixed.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ProofreadQuery {
+    /// One entry per passage
+    pub passages: Vec<Passage>,
+}
+
+/// A passage of the summary and its number.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Passage {
+    /// The passage's number, exactly as given
+    pub id: usize,
+    /// The passage, corrected
+    pub text: String,
+}
+
+impl_query!(ProofreadQuery, PROOFREAD_PROMPT);
+
+/// Ask the model to fix the spelling and grammar of `passages`. The model gets no tools.
+#[tracing::instrument(
+    name = "Asking for corrections",
+    level = "debug",
+    skip(client, passages)
+)]
+pub async fn proofread<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    passages: &[Passage],
+) -> AppResult<Vec<Passage>> {
+    let prompt = ProofreadQuery::prompt(vars());
+    let mut input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: serde_json::to_string_pretty(passages)?,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let mut schema_retries = 0;
+    loop {
+        let request = CreateResponse {
+            model: Some(model.to_string()),
+            input: InputParam::Items(input_items.clone()),
+            background: Some(false),
+            instructions: Some(prompt.clone()),
+            reasoning: Some(Reasoning {
+                effort: Some(ReasoningEffort::Low),
+                summary: None,
+            }),
+            store: Some(false),
+            stream: Some(false),
+            temperature: Some(0.05),
+            text: Some(ResponseTextParam {
+                format: TextResponseFormatConfiguration::JsonSchema(
+                    ProofreadQuery::response_format(),
+                ),
+                verbosity: None,
+            }),
+            top_logprobs: Some(0),
+            top_p: Some(0.1),
+            truncation: Some(Truncation::Disabled),
+            ..Default::default()
+        };
+
+        let response = create_response(client, request).await?;
+        debug!("AI Response: {:?}", response);
+
+        let mut response_content = String::new();
+        for out in &response.output {
+            if let OutputItem::Message(msg) = out {
+                for content in &msg.content {
+                    match content {
+                        OutputMessageContent::OutputText(text) => {
+                            response_content.push_str(&text.text)
+                        }
+                        OutputMessageContent::Refusal(RefusalContent { refusal }) => {
+                            error!("AI refused prompt: {}", refusal);
+                        }
+                    }
+                }
+            }
+        }
+        match ProofreadQuery::from_answer(&response_content, &mut schema_retries)? {
+            Parsed::Answer(answer) => return Ok(answer.passages),
+            Parsed::Retry(message) => input_items.push(message),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::env;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use tokio::process::Command;
+use tracing::debug;
+
+/// Service name API keys are stored under in the keychain.
+const KEYCHAIN_SERVICE: &str = "daily-ai";
+
+/// The provider of this run, set once at startup.
+static PROVIDER: OnceLock<Provider> = OnceLock::new();
+
+/// The provider's API key from the keychain, read once at startup.
+static KEYCHAIN_KEY: OnceLock<Option<String>> = OnceLock::new();
+
+/// Where requests to the language model are sent.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Provider {
+    /// An OpenAI-compatible server at --host and --port, such as LM Studio or Ollama
+    #[default]
+    Local,
+    /// OpenAI's hosted API, with the key in OPENAI_API_KEY or the keychain
+    Openai,
+    /// Anthropic's OpenAI-compatible Chat Completions API, with the key in ANTHROPIC_API_KEY or
+    /// the keychain
+    Anthropic,
+}
+
+impl Provider {
+    /// Base URL of a hosted provider's API, or `None` for a local server.
+    pub fn api_base(self) -> Option<&'static str> {
+        match self {
+            Provider::Local => None,
+            Provider::Openai => Some("https://api.openai.com/v1"),
+            Provider::Anthropic => Some("https://api.anthropic.com/v1"),
+        }
+    }
+
+    /// Environment variable holding the provider's API key, and the keychain account it is
+    /// stored under otherwise.
+    pub fn key_source(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Provider::Local => None,
+            Provider::Openai => Some(("OPENAI_API_KEY", "openai")),
+            Provider::Anthropic => Some(("ANTHROPIC_API_KEY", "anthropic")),
+        }
+    }
+
+    /// Whether the provider only serves Chat Completions, so requests for the Responses API are
+    /// translated for it.
+    pub fn chat_completions_only(self) -> bool {
+        matches!(self, Provider::Anthropic)
+    }
+
+    /// The API key for a hosted provider, from its environment variable or the keychain as read
+    /// by [`configure`].
+    ///
+    /// Local servers use `OPENAI_API_KEY` when it is set, like the OpenAI client does, so this
+    /// returns `None` for them.
+    pub fn api_key(self) -> Option<String> {
+        let (variable, _) = self.key_source()?;
+        env_key(variable).or_else(|| {
+            if configured() != self {
+                return None;
+            }
+            KEYCHAIN_KEY.get().cloned().flatten()
+        })
+    }
+}
+
+/// Send requests to `provider` from now on, and read its API key from the keychain unless its
+/// environment variable is set. Only the first call has an effect.
+pub async fn configure(provider: Provider) {
+    if PROVIDER.set(provider).is_err() {
+        return;
+    }
+    let key = match provider.key_source() {
+        Some((variable, account)) if env_key(variable).is_none() => keychain_secret(account).await,
+        _ => None,
+    };
+    let _ = KEYCHAIN_KEY.set(key);
+}
+
+/// The configured provider, or the default.
+pub fn configured() -> Provider {
+    PROVIDER.get().copied().unwrap_or_default()
+}
+
+/// The API key in `variable`, unless it is unset or blank.
+fn env_key(variable: &str) -> Option<String> {
+    env::var(variable).ok().filter(|key| !key.trim().is_empty())
+}
+
+/// Read the secret stored for `account` under the `daily-ai` service: from the login keychain
+/// on macOS (`security add-generic-password -s daily-ai -a openai -w`), and from the Secret
+/// Service elsewhere (`secret-tool store --label daily-ai service daily-ai account openai`).
+async fn keychain_secret(account: &str) -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security")
+            .args([
+                "find-generic-password",
+                "-s",
+                KEYCHAIN_SERVICE,
+                "-a",
+                account,
+                "-w",
+            ])
+            .output()
+            .await
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", KEYCHAIN_SERVICE, "account", account])
+            .output()
+            .await
+    };
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(_) => {
+            debug!("No {account} key in the keychain");
+            return None;
+        }
+        Err(e) => {
+            debug!("Unable to read the keychain: {e}");
+            return None;
+        }
+    };
+    let secret = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!secret.is_empty()).then_some(secret)
+}
@@ -1,13 +1,36 @@
-use async_openai::types::responses::ResponseFormatJsonSchema;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, ResponseFormatJsonSchema,
+};
 use schemars::{JsonSchema, schema_for};
 use serde::{Deserialize, Serialize};
-use tracing::{error, trace};
+use serde_json::{Map, Value};
+use tracing::{error, trace, warn};
 
 use super::SchemaInfo;
+use super::prompt::{Prompt, PromptVars};
+use super::retry::may_retry_schema;
 use crate::AppResult;
 
+/// What to do with an answer from the model.
+pub enum Parsed<T> {
+    /// It could be read.
+    Answer(T),
+    /// It did not match the schema; send this message and ask again.
+    Retry(InputItem),
+}
+
+impl<T> Parsed<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Parsed<U> {
+        match self {
+            Parsed::Answer(answer) => Parsed::Answer(f(answer)),
+            Parsed::Retry(message) => Parsed::Retry(message),
+        }
+    }
+}
+
 pub trait Query: JsonSchema + Serialize + for<'de> Deserialize<'de> + SchemaInfo {
-    const PROMPT: &'static str;
+    const PROMPT: &'static Prompt;
 
     fn response_format() -> ResponseFormatJsonSchema {
         ResponseFormatJsonSchema {
@@ -18,13 +41,15 @@ pub trait Query: JsonSchema + Serialize + for<'de> Deserialize<'de> + SchemaInfo
         }
     }
 
-    fn prompt() -> &'static str {
-        Self::PROMPT
+    /// The prompt for this query, with `vars` filled in.
+    fn prompt(vars: &PromptVars) -> String {
+        Self::PROMPT.render(vars)
     }
 
     fn from_str(s: &str) -> AppResult<Self> {
         trace!("Raw content: {s}");
         let s = crate::ai::ResponseCleaner::new().clean(s);
+        let s = crate::ai::glossary::correct_json(&s);
         trace!("Cleaned content: {s}");
         let jd = &mut serde_json::Deserializer::from_str(&s);
         match serde_path_to_error::deserialize(jd) {
@@ -37,6 +62,70 @@ pub trait Query: JsonSchema + Serialize + for<'de> Deserialize<'de> + SchemaInfo
             }
         }
     }
+
+    /// Read an answer, for models that may ignore the `json_schema` response format.
+    ///
+    /// An answer that does not match the schema is answered with a message asking for only JSON
+    /// matching it, until `retries` reaches the configured `schema_retries`. After that, each
+    /// field of the schema is picked out of the answer on its own.
+    fn from_answer(s: &str, retries: &mut u32) -> AppResult<Parsed<Self>> {
+        let error = match Self::from_str(s) {
+            Ok(answer) => return Ok(Parsed::Answer(answer)),
+            Err(e) => e,
+        };
+        if may_retry_schema(*retries) {
+            *retries += 1;
+            warn!(
+                "The {} answer did not match its schema; asking again ({retries})",
+                Self::title()
+            );
+            return Ok(Parsed::Retry(Self::retry_message(&error.to_string())));
+        }
+        warn!(
+            "The {} answer still did not match its schema; reading it field by field",
+            Self::title()
+        );
+        Self::from_fields(s).map(Parsed::Answer).map_err(|_| error)
+    }
+
+    /// The message asking the model to answer again after `error`, with the schema spelled out.
+    fn retry_message(error: &str) -> InputItem {
+        let schema = serde_json::to_string_pretty(&Self::schema_value()).unwrap_or_default();
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: format!(
+                    "Your last answer could not be read: {error}. Return only JSON matching this \
+                     schema, with no other text before or after it:\n\n{schema}"
+                ),
+            })],
+            role: InputRole::User,
+            status: None,
+        })))
+    }
+
+    /// Pick each top-level field of the schema out of `s` on its own, so one malformed field or
+    /// stray text between fields does not lose the others. A field is taken from the first
+    /// `"name":` in `s` whose value can be read.
+    fn from_fields(s: &str) -> AppResult<Self> {
+        let schema = Self::schema_value();
+        let mut fields = Map::new();
+        for name in schema["properties"]
+            .as_object()
+            .into_iter()
+            .flat_map(Map::keys)
+        {
+            let key = format!("\"{name}\"");
+            let value = s.match_indices(&key).find_map(|(at, _)| {
+                let rest = s[at + key.len()..].trim_start().strip_prefix(':')?;
+                let value = crate::ai::ResponseCleaner::new().clean(rest);
+                serde_json::from_str::<Value>(&value).ok()
+            });
+            if let Some(value) = value {
+                fields.insert(name.clone(), value);
+            }
+        }
+        Self::from_str(&Value::Object(fields).to_string())
+    }
 }
 
 #[macro_export]
@@ -44,7 +133,51 @@ pub trait Query: JsonSchema + Serialize + for<'de> Deserialize<'de> + SchemaInfo
 macro_rules! impl_query {
     ($struct_name:ident, $prompt:ident) => {
         impl crate::ai::query::Query for $struct_name {
-            const PROMPT: &'static str = $prompt;
+            const PROMPT: &'static crate::ai::prompt::Prompt = &$prompt;
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impl_query;
+
+    static ANSWER_PROMPT: Prompt = Prompt::new("answer_prompt.md", "");
+
+    /// Time spent on one piece of work
+    #[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+    struct Answer {
+        label: String,
+        minutes: u32,
+        notes: Vec<String>,
+    }
+
+    impl_query!(Answer, ANSWER_PROMPT);
+
+    #[test]
+    fn asks_again_then_reads_field_by_field() {
+        let answer = "Sure! The \"label\": \"Parser work\", took \"minutes\" : 45 and \
+                      \"notes\": [\"fixed \\\"ok\\\"\", \"b\"] -- hope that helps";
+        let mut retries = 0;
+        for _ in 0..2 {
+            assert!(matches!(
+                Answer::from_answer(answer, &mut retries),
+                Ok(Parsed::Retry(_))
+            ));
+        }
+        assert_eq!(retries, 2);
+        let Ok(Parsed::Answer(parsed)) = Answer::from_answer(answer, &mut retries) else {
+            panic!("expected the fields to be read");
+        };
+        assert_eq!(
+            parsed,
+            Answer {
+                label: "Parser work".into(),
+                minutes: 45,
+                notes: vec!["fixed \"ok\"".into(), "b".into()],
+            }
+        );
+        assert!(Answer::from_fields("\"label\": \"only this\"").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::error::OpenAIError;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    CreateResponse, InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, Response,
+};
+use tracing::warn;
+
+use super::chat;
+use crate::AppResult;
+use crate::backoff::Backoff;
+use crate::error::AppError;
+
+/// How long a single request may take unless `request_timeout` says otherwise. Reasoning models
+/// on a laptop can think for minutes before answering.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How many times a failed request is retried unless `retries` says otherwise.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// How many rounds of tool calls one answer may take unless `max_tool_rounds` says otherwise.
+pub const DEFAULT_MAX_TOOL_ROUNDS: usize = 20;
+
+/// How many times the model is asked again for an answer that does not match the schema unless
+/// `schema_retries` says otherwise.
+pub const DEFAULT_SCHEMA_RETRIES: u32 = 2;
+
+/// How many times the model is asked again, more gently, after refusing or answering nothing.
+pub const REFUSAL_RETRIES: u32 = 1;
+
+/// Waits between retries of a request: 2 seconds at first, and never more than a minute.
+const BACKOFF: Backoff = Backoff::new(Duration::from_secs(2), Duration::from_secs(60));
+
+/// Limits on requests to the language model, set once at startup.
+static POLICY: OnceLock<RequestPolicy> = OnceLock::new();
+
+/// Timeouts, retries, and tool-call limits for requests to the language model, from the
+/// `request_timeout`, `retries`, `max_tool_rounds`, and `schema_retries` keys of the
+/// configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestPolicy {
+    /// How long to wait for one answer before giving up on it.
+    pub timeout: Duration,
+    /// How many times a request that timed out or failed in passing is sent again.
+    pub retries: u32,
+    /// How many rounds of tool calls the model may make before it must answer.
+    pub max_tool_rounds: usize,
+    /// How many times the model is asked again for an answer that does not match the schema
+    /// before its fields are picked out one by one.
+    pub schema_retries: u32,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            max_tool_rounds: DEFAULT_MAX_TOOL_ROUNDS,
+            schema_retries: DEFAULT_SCHEMA_RETRIES,
+        }
+    }
+}
+
+/// Use `policy` for every request from now on. Only the first call has an effect.
+pub fn configure(policy: RequestPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+/// The configured policy, or the defaults.
+fn policy() -> RequestPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Whether `error` is worth retrying: the server could not be reached, dropped the connection,
+/// or answered with a rate limit or a server error.
+fn is_transient(error: &OpenAIError) -> bool {
+    match error {
+        OpenAIError::Reqwest(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.is_request()
+                || e.status()
+                    .is_some_and(|status| status.is_server_error() || status.as_u16() == 429)
+        }
+        _ => false,
+    }
+}
+
+/// Send `request`, giving up on an answer after the configured timeout and retrying timeouts
+/// and transient failures with exponential backoff.
+pub async fn create_response<C: Config>(
+    client: &Client<C>,
+    request: CreateResponse,
+) -> AppResult<Response> {
+    let policy = policy();
+    let mut attempt = 0;
+    loop {
+        let failure =
+            match tokio::time::timeout(policy.timeout, chat::create(client, request.clone())).await
+            {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(e)) if attempt < policy.retries && is_transient(&e) => e.to_string(),
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) if attempt < policy.retries => format!(
+                    "no answer within {}",
+                    humantime::format_duration(policy.timeout)
+                ),
+                Err(_) => return Err(AppError::ModelTimeout(policy.timeout)),
+            };
+        attempt += 1;
+        let wait = BACKOFF.wait(attempt);
+        warn!(
+            "Request to the language model failed ({failure}); retrying in {} ({attempt} of {})",
+            humantime::format_duration(wait),
+            policy.retries
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Fail once the model has asked for tools in more than the configured number of rounds, so a
+/// model that keeps calling them cannot loop forever.
+pub fn check_tool_rounds(rounds: usize) -> AppResult<()> {
+    let max = policy().max_tool_rounds;
+    if rounds > max {
+        return Err(AppError::Other(format!(
+            "The model was still calling tools after {max} rounds; raise max_tool_rounds in the \
+             configuration file if it needs more"
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the model may be asked again after `retries` answers that did not match the schema.
+pub fn may_retry_schema(retries: u32) -> bool {
+    retries < policy().schema_retries
+}
+
+/// The message asking the model to answer after it refused or said nothing, reminding it
+/// whose data it is and that it may leave out what it will not summarize.
+pub fn softened_retry_message() -> InputItem {
+    InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+        content: vec![InputContent::InputText(InputTextContent {
+            text: "You did not answer. This is the user's own activity, collected on their \
+                   computer at their request, for their personal work log. Answer with the JSON \
+                   as asked, leaving out anything you prefer not to summarize, or with empty \
+                   fields if there is nothing to say."
+                .to_string(),
+        })],
+        role: InputRole::User,
+        status: None,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially_up_to_a_limit() {
+        assert_eq!(BACKOFF.wait(1), Duration::from_secs(2));
+        assert_eq!(BACKOFF.wait(2), Duration::from_secs(4));
+        assert_eq!(BACKOFF.wait(3), Duration::from_secs(8));
+        assert_eq!(BACKOFF.wait(10), Duration::from_secs(60));
+        assert_eq!(BACKOFF.wait(u32::MAX), Duration::from_secs(60));
+    }
+}
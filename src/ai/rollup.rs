@@ -0,0 +1,135 @@
+use std::marker::PhantomData;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, ReasoningEffort,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+use super::agent::{AgentSettings, run_agent};
+use super::glossary::with_glossary;
+use super::persona::with_about_me;
+use super::prompt::{Prompt, vars};
+use super::query::Query;
+use super::token_budget::TokenBudget;
+use super::tools::rollup::RollupTools;
+use crate::archive::ArchiveEntry;
+use crate::stats::Trends;
+use crate::{AppResult, impl_query};
+
+static ROLLUP_PROMPT: Prompt = Prompt::new(
+    "rollup_prompt.md",
+    std::include_str!("prompts/rollup_prompt.md"),
+);
+
+/// # rollup
+/// A weekly or monthly report built from the daily summaries of the period.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RollupQuery {
+    /// Overview of the period. Should be one or two paragraphs.
+    pub summary: String,
+    /// The main things that got done
+    pub accomplishments: Vec<String>,
+    /// Projects and kinds of work that ran through the period
+    #[serde(default)]
+    pub themes: Vec<String>,
+    /// How the work changed over the period
+    #[serde(default)]
+    pub trends: Vec<String>,
+    /// Work left open at the end of the period
+    #[serde(default)]
+    pub carry_over: Vec<String>,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(RollupQuery, ROLLUP_PROMPT);
+
+/// One day as first shown to the model; the rest is behind `get_daily_summary`.
+#[derive(Serialize)]
+struct RollupDay<'a> {
+    date: Date,
+    summary: &'a str,
+    highlights: &'a [String],
+}
+
+#[derive(Serialize)]
+struct RollupInput<'a> {
+    period: &'a str,
+    stats: &'a Trends,
+    days: Vec<RollupDay<'a>>,
+}
+
+/// Write a `period` report ("week" or "month") from the summaries of `entries`, with the
+/// period's `trends` as numbers to ground the model's claims. Each day the model looks up is cut
+/// to fit `budget`.
+#[tracing::instrument(
+    name = "Generating a rollup report",
+    level = "debug",
+    skip(client, entries, trends)
+)]
+pub async fn generate_rollup<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    period: &str,
+    entries: &[ArchiveEntry],
+    trends: &Trends,
+    budget: TokenBudget,
+) -> AppResult<RollupQuery> {
+    let input = RollupInput {
+        period,
+        stats: trends,
+        days: entries
+            .iter()
+            .filter_map(|entry| {
+                let summary = entry.context.summary.as_ref()?;
+                Some(RollupDay {
+                    date: entry.date,
+                    summary: &summary.summary,
+                    highlights: &summary.highlights,
+                })
+            })
+            .collect(),
+    };
+    let prompt = with_glossary(&with_about_me(&RollupQuery::prompt(vars())));
+    let input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: serde_json::to_string_pretty(&input)?,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let registry = RollupTools { entries };
+    let settings = AgentSettings {
+        instructions: &prompt,
+        effort: ReasoningEffort::High,
+        temperature: 0.05,
+        top_p: 0.1,
+        previous_response_id: None,
+    };
+    let agent = run_agent(
+        client,
+        model,
+        settings,
+        input_items,
+        &registry,
+        &PhantomData::<RollupQuery>,
+        budget,
+    )
+    .await?;
+    Ok(agent.answer)
+}
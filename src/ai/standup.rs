@@ -0,0 +1,144 @@
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    CreateResponse, InputContent, InputItem, InputMessage, InputParam, InputRole, Item,
+    MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort, RefusalContent,
+    ResponseTextParam, TextResponseFormatConfiguration, Truncation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use super::glossary::with_glossary;
+use super::persona::with_about_me;
+use super::prompt::{Prompt, vars};
+use super::query::{Parsed, Query};
+use super::retry::create_response;
+use super::summary::WorkSummary;
+use crate::{AppResult, impl_query};
+
+static STANDUP_PROMPT: Prompt = Prompt::new(
+    "standup_prompt.md",
+    std::include_str!("prompts/standup_prompt.md"),
+);
+
+/// # standup
+/// A stand-up update derived from the day's work summary.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StandupQuery {
+    /// What got done
+    pub yesterday: Vec<String>,
+    /// What comes next
+    pub today: Vec<String>,
+    /// What is stuck or waiting on someone else
+    #[serde(default)]
+    pub blockers: Vec<String>,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(StandupQuery, STANDUP_PROMPT);
+
+/// A "Yesterday / Today / Blockers" update, rendered by `--format standup`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Standup {
+    /// What got done
+    #[serde(default)]
+    pub yesterday: Vec<String>,
+    /// What comes next
+    #[serde(default)]
+    pub today: Vec<String>,
+    /// What is stuck or waiting on someone else
+    #[serde(default)]
+    pub blockers: Vec<String>,
+}
+
+impl From<StandupQuery> for Standup {
+    fn from(query: StandupQuery) -> Self {
+        Standup {
+            yesterday: query.yesterday,
+            today: query.today,
+            blockers: query.blockers,
+        }
+    }
+}
+
+/// Condense a finished work summary into a stand-up update. The model gets no tools: the
+/// summary already holds everything the update may mention.
+#[tracing::instrument(
+    name = "Generating a stand-up update",
+    level = "debug",
+    skip(client, summary)
+)]
+pub async fn generate_standup<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    summary: &WorkSummary,
+) -> AppResult<Standup> {
+    let prompt = with_glossary(&with_about_me(&StandupQuery::prompt(vars())));
+    let mut input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: serde_json::to_string_pretty(summary)?,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let mut schema_retries = 0;
+    loop {
+        let request = CreateResponse {
+            model: Some(model.to_string()),
+            input: InputParam::Items(input_items.clone()),
+            background: Some(false),
+            instructions: Some(prompt.clone()),
+            reasoning: Some(Reasoning {
+                effort: Some(ReasoningEffort::Medium),
+                summary: None,
+            }),
+            store: Some(false),
+            stream: Some(false),
+            temperature: Some(0.05),
+            text: Some(ResponseTextParam {
+                format: TextResponseFormatConfiguration::JsonSchema(StandupQuery::response_format()),
+                verbosity: None,
+            }),
+            top_logprobs: Some(0),
+            top_p: Some(0.1),
+            truncation: Some(Truncation::Disabled),
+            ..Default::default()
+        };
+
+        let response = create_response(client, request).await?;
+        debug!("AI Response: {:?}", response);
+
+        let mut response_content = String::new();
+        for out in &response.output {
+            if let OutputItem::Message(msg) = out {
+                for content in &msg.content {
+                    match content {
+                        OutputMessageContent::OutputText(text) => {
+                            response_content.push_str(&text.text)
+                        }
+                        OutputMessageContent::Refusal(RefusalContent { refusal }) => {
+                            error!("AI refused prompt: {}", refusal);
+                        }
+                    }
+                }
+            }
+        }
+        match StandupQuery::from_answer(&response_content, &mut schema_retries)? {
+            Parsed::Answer(answer) => return Ok(answer.into()),
+            Parsed::Retry(message) => input_items.push(message),
+        }
+    }
+}
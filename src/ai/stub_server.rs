@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use crate::http;
+
+/// A request the stub server received.
+#[derive(Debug, Clone)]
+pub struct StubRequest {
+    /// Path of the request, e.g. `/v1/responses`.
+    pub path: String,
+    pub body: Value,
+}
+
+/// A language model server for tests, answering each request on `127.0.0.1` with the JSON that
+/// `answer` returns for it.
+pub struct StubServer {
+    /// API base to give the client, ending in `/v1`.
+    pub api_base: String,
+    requests: Arc<Mutex<Vec<StubRequest>>>,
+}
+
+impl StubServer {
+    pub async fn start(answer: impl Fn(&StubRequest) -> Value + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}/v1", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let answer = Arc::new(answer);
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let answer = answer.clone();
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    let path = line.split(' ').nth(1).unwrap_or_default().to_string();
+                    let mut length = 0;
+                    loop {
+                        line.clear();
+                        stream.read_line(&mut line).await.unwrap();
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':')
+                            && name.eq_ignore_ascii_case("content-length")
+                        {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    stream.read_exact(&mut body).await.unwrap();
+                    let request = StubRequest {
+                        path,
+                        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+                    };
+                    let reply = answer(&request).to_string();
+                    received.lock().unwrap().push(request);
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                        reply.len()
+                    );
+                    let _ = stream.get_mut().write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        StubServer { api_base, requests }
+    }
+
+    /// A client for the server, built like the one the commands use.
+    pub fn client(&self) -> Client<OpenAIConfig> {
+        Client::with_config(OpenAIConfig::default().with_api_base(&self.api_base))
+            .with_http_client(http::model_server_client(&self.api_base).unwrap())
+    }
+
+    /// Every request received so far, oldest first.
+    pub fn requests(&self) -> Vec<StubRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// A Responses API answer with `content` as the one message.
+pub fn response(content: Value) -> Value {
+    json!({
+        "id": format!("resp_{}", ulid::Ulid::new()),
+        "object": "response",
+        "created_at": 1_738_368_000,
+        "status": "completed",
+        "error": null,
+        "incomplete_details": null,
+        "instructions": null,
+        "max_output_tokens": null,
+        "model": "stub",
+        "output": [{
+            "type": "message",
+            "id": format!("msg_{}", ulid::Ulid::new()),
+            "status": "completed",
+            "role": "assistant",
+            "content": [content]
+        }],
+        "parallel_tool_calls": false,
+        "previous_response_id": null,
+        "reasoning": {"effort": null, "summary": null},
+        "store": true,
+        "temperature": 1.0,
+        "text": {"format": {"type": "text"}},
+        "tool_choice": "auto",
+        "tools": [],
+        "top_p": 1.0,
+        "truncation": "disabled",
+        "usage": {
+            "input_tokens": 1,
+            "input_tokens_details": {"cached_tokens": 0},
+            "output_tokens": 1,
+            "output_tokens_details": {"reasoning_tokens": 0},
+            "total_tokens": 2
+        },
+        "user": null,
+        "metadata": {}
+    })
+}
+
+/// A Responses API answer with `text` as the model's output.
+pub fn text_response(text: &str) -> Value {
+    response(json!({"type": "output_text", "text": text, "annotations": []}))
+}
+
+/// A Responses API answer calling the tool `name` with `arguments`.
+pub fn tool_call_response(name: &str, arguments: Value) -> Value {
+    let mut answer = response(Value::Null);
+    answer["output"] = json!([{
+        "type": "function_call",
+        "id": format!("fc_{}", ulid::Ulid::new()),
+        "call_id": format!("call_{}", ulid::Ulid::new()),
+        "name": name,
+        "arguments": arguments.to_string(),
+        "status": "completed"
+    }]);
+    answer
+}
+
+/// A Responses API answer refusing with `reason`.
+pub fn refusal_response(reason: &str) -> Value {
+    response(json!({"type": "refusal", "refusal": reason}))
+}
@@ -1,41 +1,86 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use async_openai::Client;
 use async_openai::config::Config;
 use async_openai::types::evals::InputTextContent;
 use async_openai::types::responses::{
-    CreateResponse, FunctionToolCall, InputContent, InputItem, InputMessage, InputParam, InputRole,
-    Item, MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort,
-    RefusalContent, ResponseFormatJsonSchema, ResponseTextParam, TextResponseFormatConfiguration,
-    Tool, ToolChoiceOptions, ToolChoiceParam, Truncation,
+    InputContent, InputItem, InputMessage, InputRole, Item, MessageItem, ReasoningEffort,
+    ResponseFormatJsonSchema,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error};
-
-use super::query::Query;
-use super::tools::fetch::FetchUrl;
-use super::tools::summary::{
-    GetBrowserHistory, GetCommitMessages, GetDiff, GetRepo, GetShellHistory,
-};
-use super::tools::{CustomTool, unknown_tool};
+use tracing::warn;
+
+use super::agent::{AgentSettings, FromAnswer, run_agent};
+use super::cache::ResponseCache;
+use super::glossary::with_glossary;
+use super::map_reduce::{digest_large_diffs, digest_repos};
+use super::persona::with_about_me;
+use super::prompt::{Prompt, PromptVars, vars};
+use super::query::{Parsed, Query};
+use super::standup::Standup;
+use super::token_budget::TokenBudget;
+use super::tools::fetch::VisitedSites;
+use super::tools::summary::SummaryTools;
 use crate::AppResult;
+use crate::apple_notes::AppleNote;
+use crate::ci::{CiRun, CiStatus};
 use crate::classify::UrlCluster;
+use crate::clipboard::ClipboardActivity;
+use crate::containers::ContainerActivity;
 use crate::context::Context;
-use crate::git::CommitMeta;
+use crate::email::EmailThread;
+use crate::error::AppError;
+use crate::external::ExternalActivity;
+use crate::git::deps::DependencyChange;
+use crate::git::{CommitMeta, ParkedWork, UnpushedBranch};
+use crate::hosts::HostActivity;
+use crate::ide_builds::IdeBuild;
 use crate::impl_query;
+use crate::k8s::K8sActivity;
+use crate::learning::LearningLeads;
+use crate::messages::MessageConversation;
+use crate::notes::NoteActivity;
+use crate::recordings::Recording;
+use crate::redact::Redactor;
+use crate::releases::Release;
+use crate::review::{self, Decision};
 use crate::shell::ShellHistoryEntry;
-
-static SUMMARY_PROMPT: &str = std::include_str!("prompts/full_summary/summary_prompt.md");
-static HIGHLIGHTS_PROMPT: &str = std::include_str!("prompts/full_summary/highlights_prompt.md");
-static TIME_BREAKDOWN_PROMPT: &str =
-    std::include_str!("prompts/full_summary/time_breakdown_prompt.md");
-static COMMON_GROUPS_PROMPT: &str =
-    std::include_str!("prompts/full_summary/common_groups_prompt.md");
-static REPO_SUMMARIES_PROMPT: &str =
-    std::include_str!("prompts/full_summary/repo_summaries_prompt.md");
-static SHELL_OVERVIEW_PROMPT: &str =
-    std::include_str!("prompts/full_summary/shell_overview_prompt.md");
+use crate::slack::SlackChannelActivity;
+use crate::tags::Tagging;
+use crate::tasks::CompletedTask;
+use crate::tickets::TicketActivity;
+use crate::tmux::{self, ShellSessionHint, TmuxSession};
+
+static SUMMARY_PROMPT: Prompt = Prompt::new(
+    "summary_prompt.md",
+    std::include_str!("prompts/full_summary/summary_prompt.md"),
+);
+static HIGHLIGHTS_PROMPT: Prompt = Prompt::new(
+    "highlights_prompt.md",
+    std::include_str!("prompts/full_summary/highlights_prompt.md"),
+);
+static TIME_BREAKDOWN_PROMPT: Prompt = Prompt::new(
+    "time_breakdown_prompt.md",
+    std::include_str!("prompts/full_summary/time_breakdown_prompt.md"),
+);
+static COMMON_GROUPS_PROMPT: Prompt = Prompt::new(
+    "common_groups_prompt.md",
+    std::include_str!("prompts/full_summary/common_groups_prompt.md"),
+);
+static REPO_SUMMARIES_PROMPT: Prompt = Prompt::new(
+    "repo_summaries_prompt.md",
+    std::include_str!("prompts/full_summary/repo_summaries_prompt.md"),
+);
+static SHELL_OVERVIEW_PROMPT: Prompt = Prompt::new(
+    "shell_overview_prompt.md",
+    std::include_str!("prompts/full_summary/shell_overview_prompt.md"),
+);
+static LEARNING_PROMPT: Prompt = Prompt::new(
+    "learning_prompt.md",
+    std::include_str!("prompts/full_summary/learning_prompt.md"),
+);
 
 /// # common_groups
 /// Identify common projects or categories of work the changes belong to.
@@ -118,12 +163,45 @@ pub struct ShellOverviewQuery {
 
 impl_query!(ShellOverviewQuery, SHELL_OVERVIEW_PROMPT);
 
+/// # learned
+/// Things learned or looked up during the day.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LearningQuery {
+    /// Technologies, libraries, and concepts that were new or researched
+    pub learned: Vec<String>,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(LearningQuery, LEARNING_PROMPT);
+
+/// A block of related work inferred from the timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct TimeBlock {
+    /// Estimated duration, e.g. `1h 20m`
+    pub duration: String,
+    /// What the session was about
+    pub description: String,
+    /// When the block started, as an RFC 3339 timestamp from the input data, if known
+    pub start: Option<String>,
+    /// When the block ended, as an RFC 3339 timestamp from the input data, if known
+    pub end: Option<String>,
+}
+
+impl TimeBlock {
+    /// The block as a `time_breakdown` line, e.g. `1h 20m: Debugging the parser.`
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.duration, self.description)
+    }
+}
+
 /// # time_breakdown
 /// Breakdown of time spent on different tasks.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TimeBreakdownQuery {
     /// The time breakdown
-    pub time_breakdown: Vec<String>,
+    pub time_breakdown: Vec<TimeBlock>,
     /// Any specific notes
     #[serde(default)]
     pub notes: Vec<String>,
@@ -143,6 +221,9 @@ pub struct WorkSummary {
     /// Breakdown of time spent on different tasks.
     #[serde(default)]
     pub time_breakdown: Vec<String>,
+    /// The time breakdown's blocks with their start and end times, for `--format ics`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_blocks: Vec<TimeBlock>,
     /// Common projects or categories of work the changes belong to.
     #[serde(default)]
     pub common_groups: Vec<String>,
@@ -152,57 +233,159 @@ pub struct WorkSummary {
     /// Overview of shell operations performed. Should be a concise paragraph or two.
     #[serde(default)]
     pub shell_overview: String,
+    /// Tags, published packages, and GitHub releases made during the day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub releases: Vec<String>,
+    /// Tickets referenced by the day's commits and branches, with any status changes.
+    #[serde(default)]
+    pub tickets_touched: Vec<String>,
+    /// Work that is committed locally but not pushed yet, and CI runs that failed or are still
+    /// running.
+    #[serde(default)]
+    pub pending_review: Vec<String>,
+    /// Email threads with sent and received counts.
+    #[serde(default)]
+    pub communication: Vec<String>,
     /// Any notes, observations, recommendations, warnings, or cautions about the work done.
     #[serde(default)]
     pub notes: Vec<String>,
+    /// Things learned or looked up during the day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub learned: Vec<String>,
+    /// Gentle notes about an unsustainable pace, from the local archive rather than the model.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pace: Vec<String>,
+    /// Stand-up update, generated only for `--format standup`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub standup: Option<Standup>,
+    /// Sections the model refused or failed to write; the rest of the summary is still used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unavailable: Vec<String>,
+    /// Emoji and tags for the browsing groups, repositories, and highlights, by label, name, and
+    /// text, with `--tags`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, Tagging>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MinifiedContext {
     pub shell_history: Vec<ShellHistoryEntry>,
+    /// tmux and zellij session and window names for shell sessions, as project hints.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shell_session_hints: Vec<ShellSessionHint>,
     pub safari_history: Vec<UrlCluster>,
     pub commit_history: Vec<MinifiedGitRepoHistory>,
+    pub tickets: Vec<TicketActivity>,
+    /// Tags, published packages, and GitHub releases made in the window.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub releases: Vec<Release>,
+    pub messages: Vec<MessageConversation>,
+    pub tmux: Vec<TmuxSession>,
+    pub containers: Vec<ContainerActivity>,
+    pub ide_builds: Vec<IdeBuild>,
+    pub k8s: Vec<K8sActivity>,
+    /// Hosts reached over ssh, scp, rsync, sftp, or mosh.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<HostActivity>,
+    pub clipboard: Vec<ClipboardActivity>,
+    pub vault_notes: Vec<NoteActivity>,
+    pub apple_notes: Vec<AppleNote>,
+    /// asciinema recordings made in the window, to be linked as demos.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recordings: Vec<Recording>,
+    pub completed_tasks: Vec<CompletedTask>,
+    pub slack: Vec<SlackChannelActivity>,
+    pub email: Vec<EmailThread>,
+    pub external: Vec<ExternalActivity>,
+    pub learning_leads: LearningLeads,
     pub notes: Vec<String>,
+    /// Lists cut short to fit the context window; the tools return them in full.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MinifiedGitRepoHistory {
     pub repo: PathBuf,
+    /// `owner/repo` from the remote URL, or the directory name for local-only repositories.
+    pub project: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
     pub commits: Vec<CommitMeta>,
+    pub unpushed: Vec<UnpushedBranch>,
+    pub parked: Vec<ParkedWork>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependency_changes: Vec<DependencyChange>,
+    /// Latest CI run per workflow and branch for the window's commits.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ci: Vec<CiRun>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub limitations: Vec<String>,
+    /// Summary of a diff too large for `get_diff` to return whole, read in chunks beforehand.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_digest: Option<String>,
+    /// Summary of the repository's work, written beforehand when many repositories were worked
+    /// in. `commits` is then left empty; `get_commit_messages` still returns them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
 }
 
 impl From<&Context> for MinifiedContext {
     fn from(ctx: &Context) -> Self {
         let commit_history = ctx
-            .commit_history
+            .commit_history()
             .iter()
-            .take(10.min(ctx.commit_history.len()))
             .map(|repo_hist| MinifiedGitRepoHistory {
                 repo: repo_hist.diff.repo_path.clone(),
+                project: repo_hist.project_name(),
+                remote_url: repo_hist.web_url(),
+                default_branch: repo_hist.default_branch.clone(),
                 commits: repo_hist.commits.clone(),
+                unpushed: repo_hist.unpushed.clone(),
+                parked: repo_hist.parked.clone(),
+                dependency_changes: repo_hist.dependency_changes.clone(),
+                ci: ctx
+                    .ci_runs()
+                    .iter()
+                    .filter(|run| run.repo == repo_hist.diff.repo_path)
+                    .cloned()
+                    .collect(),
+                limitations: repo_hist.limitations.clone(),
+                diff_digest: None,
+                summary: None,
             })
             .collect();
-        let safari_history = ctx
-            .safari_history
-            .iter()
-            .map(|cluster| {
-                let max_urls = 10.min(cluster.urls.len());
-                UrlCluster {
-                    label: cluster.label.clone(),
-                    urls: cluster.urls[..max_urls].to_vec(),
-                }
-            })
-            .collect();
-        let shell_hist_len = 10.min(ctx.shell_history.len());
         MinifiedContext {
-            shell_history: ctx.shell_history[..shell_hist_len].to_vec(),
-            safari_history,
+            shell_history: ctx.shell_history().to_vec(),
+            shell_session_hints: tmux::shell_session_hints(ctx.tmux(), ctx.shell_history()),
+            safari_history: ctx.safari_history().to_vec(),
             commit_history,
+            tickets: ctx.tickets().to_vec(),
+            releases: ctx.releases().to_vec(),
+            messages: ctx.messages().to_vec(),
+            tmux: ctx.tmux().to_vec(),
+            containers: ctx.containers().to_vec(),
+            ide_builds: ctx.ide_builds().to_vec(),
+            k8s: ctx.k8s().to_vec(),
+            hosts: ctx.hosts().to_vec(),
+            clipboard: ctx.clipboard().to_vec(),
+            vault_notes: ctx.vault_notes().to_vec(),
+            apple_notes: ctx.apple_notes().to_vec(),
+            recordings: ctx.recordings().to_vec(),
+            completed_tasks: ctx.completed_tasks().to_vec(),
+            slack: ctx.slack().to_vec(),
+            email: ctx.email().to_vec(),
+            external: ctx.external().to_vec(),
+            learning_leads: LearningLeads::default(),
             notes: vec![],
+            truncated: vec![],
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QueryType {
     Summary,
     Highlights,
@@ -210,6 +393,7 @@ pub enum QueryType {
     ShellOverview,
     TimeBreakdown,
     CommonGroups,
+    Learning,
 }
 
 pub enum QueryResponse {
@@ -219,28 +403,33 @@ pub enum QueryResponse {
     ShellOverview(ShellOverviewQuery),
     TimeBreakdown(TimeBreakdownQuery),
     CommonGroups(CommonGroupsQuery),
+    Learning(LearningQuery),
 }
 
-impl QueryType {
-    pub fn response_format(&self) -> ResponseFormatJsonSchema {
-        match self {
-            QueryType::Summary => SummaryQuery::response_format(),
-            QueryType::Highlights => HighlightsQuery::response_format(),
-            QueryType::RepoSummary => RepoSummaryQuery::response_format(),
-            QueryType::ShellOverview => ShellOverviewQuery::response_format(),
-            QueryType::TimeBreakdown => TimeBreakdownQuery::response_format(),
-            QueryType::CommonGroups => CommonGroupsQuery::response_format(),
-        }
+impl std::fmt::Display for QueryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            QueryType::Summary => "Summary",
+            QueryType::Highlights => "Highlights",
+            QueryType::RepoSummary => "Repository summaries",
+            QueryType::ShellOverview => "Shell overview",
+            QueryType::TimeBreakdown => "Time breakdown",
+            QueryType::CommonGroups => "Common groups",
+            QueryType::Learning => "Learning",
+        })
     }
+}
 
-    pub fn prompt(&self) -> &'static str {
+impl QueryType {
+    pub fn prompt(&self, vars: &PromptVars) -> String {
         match self {
-            QueryType::Summary => SummaryQuery::prompt(),
-            QueryType::Highlights => HighlightsQuery::prompt(),
-            QueryType::RepoSummary => RepoSummaryQuery::prompt(),
-            QueryType::ShellOverview => ShellOverviewQuery::prompt(),
-            QueryType::TimeBreakdown => TimeBreakdownQuery::prompt(),
-            QueryType::CommonGroups => CommonGroupsQuery::prompt(),
+            QueryType::Summary => SummaryQuery::prompt(vars),
+            QueryType::Highlights => HighlightsQuery::prompt(vars),
+            QueryType::RepoSummary => RepoSummaryQuery::prompt(vars),
+            QueryType::ShellOverview => ShellOverviewQuery::prompt(vars),
+            QueryType::TimeBreakdown => TimeBreakdownQuery::prompt(vars),
+            QueryType::CommonGroups => CommonGroupsQuery::prompt(vars),
+            QueryType::Learning => LearningQuery::prompt(vars),
         }
     }
 
@@ -260,11 +449,67 @@ impl QueryType {
             QueryType::CommonGroups => {
                 Ok(QueryResponse::CommonGroups(CommonGroupsQuery::from_str(s)?))
             }
+            QueryType::Learning => Ok(QueryResponse::Learning(LearningQuery::from_str(s)?)),
+        }
+    }
+}
+
+impl FromAnswer for QueryType {
+    type Answer = QueryResponse;
+
+    fn response_format(&self) -> ResponseFormatJsonSchema {
+        match self {
+            QueryType::Summary => SummaryQuery::response_format(),
+            QueryType::Highlights => HighlightsQuery::response_format(),
+            QueryType::RepoSummary => RepoSummaryQuery::response_format(),
+            QueryType::ShellOverview => ShellOverviewQuery::response_format(),
+            QueryType::TimeBreakdown => TimeBreakdownQuery::response_format(),
+            QueryType::CommonGroups => CommonGroupsQuery::response_format(),
+            QueryType::Learning => LearningQuery::response_format(),
         }
     }
+
+    fn from_answer(&self, s: &str, retries: &mut u32) -> AppResult<Parsed<QueryResponse>> {
+        Ok(match self {
+            QueryType::Summary => {
+                SummaryQuery::from_answer(s, retries)?.map(QueryResponse::Summary)
+            }
+            QueryType::Highlights => {
+                HighlightsQuery::from_answer(s, retries)?.map(QueryResponse::Highlights)
+            }
+            QueryType::RepoSummary => {
+                RepoSummaryQuery::from_answer(s, retries)?.map(QueryResponse::RepoSummary)
+            }
+            QueryType::ShellOverview => {
+                ShellOverviewQuery::from_answer(s, retries)?.map(QueryResponse::ShellOverview)
+            }
+            QueryType::TimeBreakdown => {
+                TimeBreakdownQuery::from_answer(s, retries)?.map(QueryResponse::TimeBreakdown)
+            }
+            QueryType::CommonGroups => {
+                CommonGroupsQuery::from_answer(s, retries)?.map(QueryResponse::CommonGroups)
+            }
+            QueryType::Learning => {
+                LearningQuery::from_answer(s, retries)?.map(QueryResponse::Learning)
+            }
+        })
+    }
 }
 
 impl QueryResponse {
+    /// The answer as pretty-printed JSON, as shown for review and edited.
+    pub fn to_json(&self) -> AppResult<String> {
+        Ok(match self {
+            QueryResponse::Summary(q) => serde_json::to_string_pretty(q)?,
+            QueryResponse::Highlights(q) => serde_json::to_string_pretty(q)?,
+            QueryResponse::RepoSummary(q) => serde_json::to_string_pretty(q)?,
+            QueryResponse::ShellOverview(q) => serde_json::to_string_pretty(q)?,
+            QueryResponse::TimeBreakdown(q) => serde_json::to_string_pretty(q)?,
+            QueryResponse::CommonGroups(q) => serde_json::to_string_pretty(q)?,
+            QueryResponse::Learning(q) => serde_json::to_string_pretty(q)?,
+        })
+    }
+
     pub fn extract_notes(&self) -> Vec<String> {
         match self {
             QueryResponse::Summary(q) => q.notes.clone(),
@@ -273,6 +518,7 @@ impl QueryResponse {
             QueryResponse::ShellOverview(q) => q.notes.clone(),
             QueryResponse::TimeBreakdown(q) => q.notes.clone(),
             QueryResponse::CommonGroups(q) => q.notes.clone(),
+            QueryResponse::Learning(q) => q.notes.clone(),
         }
     }
 
@@ -312,15 +558,33 @@ impl QueryResponse {
                 ws.shell_overview = q.shell_overview.clone();
             }
             QueryResponse::TimeBreakdown(q) => {
-                ws.time_breakdown = q.time_breakdown.clone();
+                ws.time_breakdown = q.time_breakdown.iter().map(TimeBlock::describe).collect();
+                ws.time_blocks = q.time_breakdown.clone();
             }
             QueryResponse::CommonGroups(q) => {
                 ws.common_groups = q.common_groups.clone();
             }
+            QueryResponse::Learning(q) => {
+                ws.learned = q.learned.clone();
+            }
         }
     }
 }
 
+/// Options of [`generate_summary`].
+#[derive(Debug, Clone, Copy)]
+pub struct SummaryOptions<'a> {
+    /// Model for the summaries of large diffs and, on busy days, of each repository.
+    pub digest_model: &'a str,
+    /// Leave out the tools that reach the internet.
+    pub offline: bool,
+    pub budget: TokenBudget,
+    /// Show each section for review before it is used.
+    pub interactive: bool,
+    /// Redacts the files the model reads, or `None` with `--no-redact`.
+    pub redactor: Option<&'a Redactor>,
+}
+
 /// Generate a commit message using the model, optionally calling back into file/patch tools.
 #[tracing::instrument(
     name = "Generating the full summary of work done",
@@ -329,148 +593,235 @@ impl QueryResponse {
 )]
 pub async fn generate_summary<C: Config>(
     client: &Client<C>,
+    model: &str,
     context: &Context,
+    learning_leads: LearningLeads,
+    options: SummaryOptions<'_>,
+    cache: &ResponseCache,
 ) -> AppResult<WorkSummary> {
+    let SummaryOptions {
+        digest_model,
+        offline,
+        budget,
+        interactive,
+        redactor,
+    } = options;
     // Kick off first turn with diff summary and commit prompt.
     let mut input_context = MinifiedContext::from(context);
-    let queries: Vec<QueryType> = vec![
+    let mut queries: Vec<QueryType> = vec![
         QueryType::CommonGroups,
         QueryType::Highlights,
         QueryType::TimeBreakdown,
         QueryType::RepoSummary,
         QueryType::ShellOverview,
-        QueryType::Summary,
     ];
+    // Without browsing or leads there is nothing to learn from, so skip the call.
+    if !learning_leads.is_empty() || !context.safari_history().is_empty() {
+        queries.push(QueryType::Learning);
+    }
+    queries.push(QueryType::Summary);
+    input_context.learning_leads = learning_leads;
+    let mut digests = digest_large_diffs(
+        client,
+        digest_model,
+        context.commit_history(),
+        budget,
+        cache,
+    )
+    .await;
+    for repo in &mut input_context.commit_history {
+        repo.diff_digest = digests.remove(&repo.repo);
+    }
+    digest_repos(
+        client,
+        digest_model,
+        &mut input_context.commit_history,
+        context.commit_history(),
+        budget,
+        cache,
+    )
+    .await;
+    budget.fit_context(&mut input_context);
 
     let mut work_summary = WorkSummary::default();
     let mut notes: Vec<String> = vec![];
-    let tools = vec![
-        Tool::Function(FetchUrl::definition()),
-        Tool::Function(GetDiff::definition()),
-        Tool::Function(GetRepo::definition()),
-        Tool::Function(GetCommitMessages::definition()),
-        Tool::Function(GetBrowserHistory::definition()),
-        Tool::Function(GetShellHistory::definition()),
-    ];
+    let registry = SummaryTools {
+        context,
+        offline,
+        sites: VisitedSites::from_context(context),
+        redactor,
+    };
 
     for query in queries {
-        let mut previous_response_id: Option<String> = None;
-        input_context.notes = notes.clone();
-
-        let mut input_items: Vec<InputItem> = vec![
-            InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
-                content: vec![InputContent::InputText(InputTextContent {
-                    text: serde_json::to_string_pretty(&input_context)?,
-                })],
-                role: InputRole::User,
-                status: None,
-            }))),
-            InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
-                content: vec![InputContent::InputText(InputTextContent {
-                    text: query.prompt().to_string(),
-                })],
-                role: InputRole::System,
-                status: None,
-            }))),
-        ];
-
-        loop {
-            let request = CreateResponse {
-                model: Some("openai/gpt-oss-20b".to_string()),
-                input: InputParam::Items(input_items.clone()),
-                background: Some(false),
-                instructions: Some(query.prompt().to_string()),
-                parallel_tool_calls: Some(false),
-                reasoning: Some(Reasoning {
-                    effort: Some(ReasoningEffort::High),
-                    summary: None,
-                }),
-                store: Some(true),
-                stream: Some(false),
-                temperature: Some(0.05),
-                text: Some(ResponseTextParam {
-                    format: TextResponseFormatConfiguration::JsonSchema(query.response_format()),
-                    verbosity: None,
-                }),
-                tool_choice: Some(ToolChoiceParam::Mode(ToolChoiceOptions::Auto)),
-                tools: Some(tools.clone()),
-                top_logprobs: Some(0),
-                top_p: Some(0.1),
-                truncation: Some(Truncation::Disabled),
-                previous_response_id: previous_response_id.clone(),
-                ..Default::default()
-            };
-
-            let response = client.responses().create(request).await?;
-            debug!("AI Response: {:?}", response);
-            previous_response_id = Some(response.id.clone());
-
-            let function_calls: Vec<FunctionToolCall> = response
-                .output
-                .iter()
-                .filter_map(|item| {
-                    if let OutputItem::FunctionCall(fc) = item {
-                        Some(fc.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            if function_calls.is_empty() {
-                let mut response_content = String::new();
-                for out in &response.output {
-                    if let OutputItem::Message(msg) = out {
-                        for content in &msg.content {
-                            match content {
-                                OutputMessageContent::OutputText(text) => {
-                                    response_content.push_str(&text.text)
-                                }
-                                OutputMessageContent::Refusal(RefusalContent { refusal }) => {
-                                    error!("AI refused prompt: {}", refusal);
-                                }
-                            }
-                        }
+        // Reviewer feedback for regenerating this query, with `--interactive`.
+        let mut feedback: Vec<String> = Vec::new();
+        let query_response = loop {
+            input_context.notes = notes.iter().chain(&feedback).cloned().collect();
+            let input_text = serde_json::to_string_pretty(&input_context)?;
+
+            // Earlier answers feed `notes`, so a query is only reused when everything before it
+            // was.
+            let prompt = with_glossary(&with_about_me(&query.prompt(vars())));
+            let cache_key = ResponseCache::key(model, &prompt, &input_text)?;
+            let query_response = if let Some(cached) = cache.get(&cache_key).await
+                && let Ok(query_response) = query.get_response(&cached)
+            {
+                Ok(query_response)
+            } else {
+                let input_items: Vec<InputItem> = vec![
+                    InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+                        content: vec![InputContent::InputText(InputTextContent {
+                            text: input_text,
+                        })],
+                        role: InputRole::User,
+                        status: None,
+                    }))),
+                    InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+                        content: vec![InputContent::InputText(InputTextContent {
+                            text: prompt.clone(),
+                        })],
+                        role: InputRole::System,
+                        status: None,
+                    }))),
+                ];
+                let settings = AgentSettings {
+                    instructions: &prompt,
+                    effort: ReasoningEffort::High,
+                    temperature: 0.05,
+                    top_p: 0.1,
+                    previous_response_id: None,
+                };
+                match run_agent(
+                    client,
+                    model,
+                    settings,
+                    input_items,
+                    &registry,
+                    &query,
+                    budget,
+                )
+                .await
+                {
+                    Ok(agent) => {
+                        cache.put(&cache_key, &agent.text).await;
+                        Ok(agent.answer)
                     }
+                    // A section the model will not write is left out; the rest is still used.
+                    Err(AppError::NoAnswer(reason)) => Err(reason),
+                    Err(e) => return Err(e),
                 }
-                let query_response = query.get_response(&response_content)?;
+            };
+
+            let query_response = match query_response {
+                Ok(query_response) => query_response,
+                Err(reason) => break Err(reason),
+            };
+            if !interactive {
+                break Ok(query_response);
+            }
+            let section = query_response.to_json()?;
+            let parse = move |text: &str| query.get_response(text);
+            match review::review(&query.to_string(), &section, parse).await? {
+                Decision::Accept => break Ok(query_response),
+                Decision::Edited(edited) => break Ok(edited),
+                Decision::Regenerate(text) => feedback.push(format!(
+                    "Feedback on the previous draft of this section, to follow this time: {text}"
+                )),
+            }
+        };
+        match query_response {
+            Ok(query_response) => {
                 query_response.update_work_summary(&mut work_summary);
                 notes.extend(query_response.extract_notes());
-                break;
             }
-
-            // Handle each tool call in order and feed results back into the conversation.
-            for call in function_calls {
-                match call.name.as_str() {
-                    name if name == FetchUrl::NAME => {
-                        input_items.extend(FetchUrl::process(call, &()).await);
-                    }
-                    name if name == GetDiff::NAME => {
-                        input_items.extend(GetDiff::process(call, &context.commit_history).await);
-                    }
-                    name if name == GetRepo::NAME => {
-                        input_items.extend(GetRepo::process(call, &context.commit_history).await);
-                    }
-                    name if name == GetCommitMessages::NAME => {
-                        input_items.extend(
-                            GetCommitMessages::process(call, &context.commit_history).await,
-                        );
-                    }
-                    name if name == GetBrowserHistory::NAME => {
-                        input_items.extend(
-                            GetBrowserHistory::process(call, &context.safari_history).await,
-                        );
-                    }
-                    name if name == GetShellHistory::NAME => {
-                        input_items
-                            .extend(GetShellHistory::process(call, &context.shell_history).await);
-                    }
-                    _ => input_items.extend(unknown_tool(call)),
-                };
+            Err(reason) => {
+                let note = format!("The {query} section is unavailable: {reason}");
+                warn!("{note}");
+                work_summary.unavailable.push(query.to_string());
+                notes.push(note);
             }
         }
     }
 
+    work_summary.releases = context.releases().iter().map(Release::describe).collect();
+    work_summary.tickets_touched = context
+        .tickets()
+        .iter()
+        .map(TicketActivity::describe)
+        .collect();
+    work_summary.pending_review = context
+        .commit_history()
+        .iter()
+        .flat_map(|repo| {
+            repo.unpushed
+                .iter()
+                .map(|branch| branch.describe(&repo.diff.repo_path))
+        })
+        // Red or unfinished builds still need attention before the work is done.
+        .chain(
+            context
+                .ci_runs()
+                .iter()
+                .filter(|run| matches!(run.status, CiStatus::Failed | CiStatus::InProgress))
+                .map(CiRun::describe),
+        )
+        .collect();
+    work_summary.communication = context
+        .email()
+        .iter()
+        .map(EmailThread::describe)
+        .chain(context.slack().iter().map(SlackChannelActivity::describe))
+        .collect();
     work_summary.notes = notes;
     Ok(work_summary)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::stub_server::{StubServer, refusal_response};
+
+    #[tokio::test]
+    async fn refused_sections_are_asked_again_then_marked_unavailable() {
+        let server = StubServer::start(|_| refusal_response("I can't help with that.")).await;
+        let options = SummaryOptions {
+            digest_model: "stub",
+            offline: true,
+            budget: TokenBudget::new(128_000),
+            interactive: false,
+            redactor: None,
+        };
+        let summary = generate_summary(
+            &server.client(),
+            "stub",
+            &Context::default(),
+            LearningLeads::default(),
+            options,
+            &ResponseCache::new(None),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            summary.unavailable,
+            [
+                "Common groups",
+                "Highlights",
+                "Time breakdown",
+                "Repository summaries",
+                "Shell overview",
+                "Summary",
+            ]
+        );
+        assert!(summary.notes.iter().any(|note| note
+            == "The Summary section is unavailable: the model refused to write it: I can't help \
+                with that."));
+        // Each section is asked once, then once more with the softened message.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2 * summary.unavailable.len());
+        for pair in requests.chunks(2) {
+            assert!(!pair[0].body.to_string().contains("You did not answer"));
+            assert!(pair[1].body.to_string().contains("You did not answer"));
+        }
+    }
+}
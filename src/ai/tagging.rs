@@ -0,0 +1,118 @@
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    CreateResponse, InputContent, InputItem, InputMessage, InputParam, InputRole, Item,
+    MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort, RefusalContent,
+    ResponseTextParam, TextResponseFormatConfiguration, Truncation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use super::prompt::{Prompt, vars};
+use super::query::{Parsed, Query};
+use super::retry::create_response;
+use crate::{AppResult, impl_query};
+
+static TAGS_PROMPT: Prompt = Prompt::new(
+    "tags_prompt.md",
+    std::include_str!("prompts/tags_prompt.md"),
+);
+
+/// # item_tags
+/// An emoji and tags for each item of a daily log.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TagsQuery {
+    /// One entry per item
+    pub items: Vec<ItemTags>,
+}
+
+/// The emoji and tags the model picked for one item.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ItemTags {
+    /// The item, exactly as given
+    pub item: String,
+    /// One emoji fitting the first tag
+    #[serde(default)]
+    pub emoji: String,
+    /// One to three tags from the list, most fitting first
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl_query!(TagsQuery, TAGS_PROMPT);
+
+/// Ask the model for an emoji and tags for each of `items`, for those the tagging rules could
+/// not place. The model gets no tools.
+#[tracing::instrument(name = "Asking for tags", level = "debug", skip(client, items))]
+pub async fn suggest_tags<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    items: &[String],
+) -> AppResult<Vec<ItemTags>> {
+    let prompt = TagsQuery::prompt(vars());
+    let mut input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: serde_json::to_string_pretty(items)?,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let mut schema_retries = 0;
+    loop {
+        let request = CreateResponse {
+            model: Some(model.to_string()),
+            input: InputParam::Items(input_items.clone()),
+            background: Some(false),
+            instructions: Some(prompt.clone()),
+            reasoning: Some(Reasoning {
+                effort: Some(ReasoningEffort::Low),
+                summary: None,
+            }),
+            store: Some(false),
+            stream: Some(false),
+            temperature: Some(0.05),
+            text: Some(ResponseTextParam {
+                format: TextResponseFormatConfiguration::JsonSchema(TagsQuery::response_format()),
+                verbosity: None,
+            }),
+            top_logprobs: Some(0),
+            top_p: Some(0.1),
+            truncation: Some(Truncation::Disabled),
+            ..Default::default()
+        };
+
+        let response = create_response(client, request).await?;
+        debug!("AI Response: {:?}", response);
+
+        let mut response_content = String::new();
+        for out in &response.output {
+            if let OutputItem::Message(msg) = out {
+                for content in &msg.content {
+                    match content {
+                        OutputMessageContent::OutputText(text) => {
+                            response_content.push_str(&text.text)
+                        }
+                        OutputMessageContent::Refusal(RefusalContent { refusal }) => {
+                            error!("AI refused prompt: {}", refusal);
+                        }
+                    }
+                }
+            }
+        }
+        match TagsQuery::from_answer(&response_content, &mut schema_retries)? {
+            Parsed::Answer(answer) => return Ok(answer.items),
+            Parsed::Retry(message) => input_items.push(message),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use async_openai::Client;
+use async_openai::config::Config;
+use async_openai::types::evals::InputTextContent;
+use async_openai::types::responses::{
+    CreateResponse, InputContent, InputItem, InputMessage, InputParam, InputRole, Item,
+    MessageItem, OutputItem, OutputMessageContent, Reasoning, ReasoningEffort, RefusalContent,
+    ResponseTextParam, TextResponseFormatConfiguration, Truncation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, warn};
+
+use super::glossary::with_glossary;
+use super::prompt::{Prompt, vars};
+use super::query::{Parsed, Query};
+use super::retry::create_response;
+use crate::timesheet::LineItem;
+use crate::{AppResult, impl_query};
+
+static TIMESHEET_PROMPT: Prompt = Prompt::new(
+    "timesheet_prompt.md",
+    std::include_str!("prompts/timesheet_prompt.md"),
+);
+
+/// A work description for one line of a timesheet.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LineDescription {
+    /// Project name, exactly as given
+    pub project: String,
+    /// One short paragraph on the work done
+    pub description: String,
+}
+
+/// # timesheet
+/// Client-facing descriptions of the work billed on each line of a timesheet.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TimesheetQuery {
+    /// One description per line item
+    pub descriptions: Vec<LineDescription>,
+    /// Any specific notes
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl_query!(TimesheetQuery, TIMESHEET_PROMPT);
+
+/// What the model sees of one line item.
+#[derive(Debug, Serialize)]
+pub struct LineWork<'a> {
+    project: &'a str,
+    hours: f64,
+    commits: &'a [String],
+}
+
+impl<'a> From<&'a LineItem> for LineWork<'a> {
+    fn from(line: &'a LineItem) -> Self {
+        LineWork {
+            project: &line.project,
+            hours: line.hours,
+            commits: &line.commits,
+        }
+    }
+}
+
+/// Write a work description for each line of a timesheet. The model gets no tools: the commit
+/// summaries are all a client-facing paragraph should draw on.
+#[tracing::instrument(
+    name = "Describing timesheet lines",
+    level = "debug",
+    skip(client, lines)
+)]
+pub async fn generate_descriptions<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    lines: &[LineWork<'_>],
+) -> AppResult<Vec<LineDescription>> {
+    let prompt = with_glossary(&TimesheetQuery::prompt(vars()));
+    let mut input_items: Vec<InputItem> = vec![
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: serde_json::to_string_pretty(lines)?,
+            })],
+            role: InputRole::User,
+            status: None,
+        }))),
+        InputItem::Item(Item::Message(MessageItem::Input(InputMessage {
+            content: vec![InputContent::InputText(InputTextContent {
+                text: prompt.clone(),
+            })],
+            role: InputRole::System,
+            status: None,
+        }))),
+    ];
+    let mut schema_retries = 0;
+    loop {
+        let request = CreateResponse {
+            model: Some(model.to_string()),
+            input: InputParam::Items(input_items.clone()),
+            background: Some(false),
+            instructions: Some(prompt.clone()),
+            reasoning: Some(Reasoning {
+                effort: Some(ReasoningEffort::Medium),
+                summary: None,
+            }),
+            store: Some(false),
+            stream: Some(false),
+            temperature: Some(0.1),
+            text: Some(ResponseTextParam {
+                format: TextResponseFormatConfiguration::JsonSchema(
+                    TimesheetQuery::response_format(),
+                ),
+                verbosity: None,
+            }),
+            top_logprobs: Some(0),
+            top_p: Some(0.1),
+            truncation: Some(Truncation::Disabled),
+            ..Default::default()
+        };
+
+        let response = create_response(client, request).await?;
+        debug!("AI Response: {:?}", response);
+
+        let mut response_content = String::new();
+        for out in &response.output {
+            if let OutputItem::Message(msg) = out {
+                for content in &msg.content {
+                    match content {
+                        OutputMessageContent::OutputText(text) => {
+                            response_content.push_str(&text.text)
+                        }
+                        OutputMessageContent::Refusal(RefusalContent { refusal }) => {
+                            error!("AI refused prompt: {}", refusal);
+                        }
+                    }
+                }
+            }
+        }
+        let query = match TimesheetQuery::from_answer(&response_content, &mut schema_retries)? {
+            Parsed::Answer(answer) => answer,
+            Parsed::Retry(message) => {
+                input_items.push(message);
+                continue;
+            }
+        };
+        for note in &query.notes {
+            warn!("Timesheet: {note}");
+        }
+        return Ok(query.descriptions);
+    }
+}
@@ -0,0 +1,406 @@
+use async_openai::types::responses::{FunctionCallOutput, InputItem, Item};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use super::summary::MinifiedContext;
+use crate::git::diff::DiffSummary;
+
+/// Context window assumed for the model unless `--context-window` says otherwise.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 32_768;
+
+/// How a run of characters is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Letter,
+    Digit,
+    Whitespace,
+    Punctuation,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_alphabetic() {
+            CharClass::Letter
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else if c.is_whitespace() {
+            CharClass::Whitespace
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
+/// Estimate how many tokens `text` takes, without the model's tokenizer.
+///
+/// Text is split the way BPE tokenizers pre-split it, into runs of letters, digits, whitespace,
+/// and punctuation, and each run is costed from what those tokenizers usually merge: about four
+/// ASCII letters or three digits per token, one token per non-ASCII letter, a line break with its
+/// indentation as one token, and a space before a word for free. Estimates lean high, so trimming
+/// to them leaves a little room to spare.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let class = CharClass::of(c);
+        let mut len = 1;
+        let mut ascii = c.is_ascii();
+        let mut newline = c == '\n';
+        while let Some(&next) = chars.peek()
+            && CharClass::of(next) == class
+        {
+            len += 1;
+            ascii &= next.is_ascii();
+            newline |= next == '\n';
+            chars.next();
+        }
+        tokens += match class {
+            CharClass::Letter if ascii => len.div_ceil(4),
+            CharClass::Letter => len,
+            CharClass::Digit => len.div_ceil(3),
+            CharClass::Whitespace if newline => 1,
+            CharClass::Whitespace => (len - 1).div_ceil(4),
+            CharClass::Punctuation => len.div_ceil(2),
+        };
+    }
+    tokens
+}
+
+/// Estimate the tokens `value` takes once serialized as the pretty JSON the model is sent.
+pub fn estimate_json_tokens<T: Serialize>(value: &T) -> usize {
+    serde_json::to_string_pretty(value)
+        .map(|json| estimate_tokens(&json))
+        .unwrap_or_default()
+}
+
+/// Keep the leading lines of `text` that fit in `max_tokens`, noting how many were cut.
+pub fn truncate_text(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let mut kept = String::new();
+    let mut used = 0;
+    let mut kept_lines = 0;
+    for line in &lines {
+        let cost = estimate_tokens(line) + 1;
+        if used + cost > max_tokens {
+            break;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+        used += cost;
+        kept_lines += 1;
+    }
+    if kept_lines == 0 {
+        // A single line too long to fit, such as minified HTML: keep its start.
+        kept = lines
+            .first()
+            .map(|line| line.chars().take(max_tokens * 4).collect())
+            .unwrap_or_default();
+        kept.push('\n');
+        kept_lines = 1;
+    }
+    kept.push_str(&format!(
+        "[{} more lines were cut to fit the context window]",
+        lines.len() - kept_lines
+    ));
+    kept
+}
+
+/// How much of the model's context window the collected data and tool results may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBudget {
+    context_window: usize,
+}
+
+impl TokenBudget {
+    pub fn new(context_window: usize) -> Self {
+        TokenBudget { context_window }
+    }
+
+    /// Tokens for the collected context. The other half is left for the prompt, tool calls and
+    /// their results, reasoning, and the answer.
+    pub fn context_tokens(self) -> usize {
+        self.context_window / 2
+    }
+
+    /// Tokens for the result of one tool call, so a large diff or page cannot crowd out the rest
+    /// of the conversation.
+    pub fn tool_output_tokens(self) -> usize {
+        self.context_window / 8
+    }
+
+    /// Cut the text results in `items`, as returned by [`CustomTool::process`], to fit
+    /// [`TokenBudget::tool_output_tokens`].
+    ///
+    /// [`CustomTool::process`]: super::tools::CustomTool::process
+    pub fn fit_tool_output(self, mut items: Vec<InputItem>) -> Vec<InputItem> {
+        let max_tokens = self.tool_output_tokens();
+        for item in &mut items {
+            if let InputItem::Item(Item::FunctionCallOutput(call)) = item
+                && let FunctionCallOutput::Text(text) = &mut call.output
+                && estimate_tokens(text) > max_tokens
+            {
+                debug!("Cutting the result of tool call {} to fit", call.call_id);
+                *text = truncate_text(text, max_tokens);
+            }
+        }
+        items
+    }
+
+    /// Cut the patches in `diff` to equal shares of [`TokenBudget::context_tokens`], for the
+    /// first message of a commit message. The model can ask for a whole patch with `get_patch`.
+    pub fn fit_diff(self, diff: &mut DiffSummary) {
+        if estimate_json_tokens(diff) <= self.context_tokens() {
+            return;
+        }
+        let patches: Vec<&mut String> = diff
+            .added
+            .iter_mut()
+            .chain(diff.modified.iter_mut())
+            .chain(diff.untracked.iter_mut())
+            .map(|entry| &mut entry.patch)
+            .collect();
+        let share = self.context_tokens() / patches.len().max(1);
+        debug!(
+            "Cutting each of {} patches to {share} tokens",
+            patches.len()
+        );
+        for patch in patches {
+            if estimate_tokens(patch) > share {
+                *patch = truncate_text(patch, share);
+            }
+        }
+    }
+
+    /// Trim the longest lists in `context` until it fits [`TokenBudget::context_tokens`],
+    /// recording what was cut in `context.truncated` so the model knows to ask the tools for more.
+    pub fn fit_context(self, context: &mut MinifiedContext) {
+        let max_tokens = self.context_tokens();
+        let original: Vec<(Trimmable, usize)> = Trimmable::ALL
+            .iter()
+            .map(|list| (*list, list.len(context)))
+            .collect();
+        let mut tokens = estimate_json_tokens(context);
+        while tokens > max_tokens {
+            let Some(list) = Trimmable::ALL
+                .iter()
+                .filter(|list| list.len(context) > 1)
+                .max_by_key(|list| list.tokens(context))
+            else {
+                warn!(
+                    "The collected data takes about {tokens} tokens even after trimming, more \
+                     than the {max_tokens} set aside for it; raise --context-window if the model \
+                     allows"
+                );
+                break;
+            };
+            list.halve(context);
+            tokens = estimate_json_tokens(context);
+        }
+        context.truncated = original
+            .into_iter()
+            .filter_map(|(list, len)| {
+                let kept = list.len(context);
+                (kept < len).then(|| format!("{}: kept {kept} of {len}", list.describe()))
+            })
+            .collect();
+        if !context.truncated.is_empty() {
+            debug!("Trimmed the context to fit: {:?}", context.truncated);
+        }
+    }
+}
+
+/// Lists in [`MinifiedContext`] that can be shortened, since the summary tools can fetch them in
+/// full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trimmable {
+    ShellHistory,
+    Repositories,
+    Commits,
+    Urls,
+    Containers,
+}
+
+impl Trimmable {
+    const ALL: [Trimmable; 5] = [
+        Trimmable::ShellHistory,
+        Trimmable::Repositories,
+        Trimmable::Commits,
+        Trimmable::Urls,
+        Trimmable::Containers,
+    ];
+
+    fn describe(self) -> &'static str {
+        match self {
+            Trimmable::ShellHistory => "shell_history entries",
+            Trimmable::Repositories => "commit_history repositories",
+            Trimmable::Commits => "commits across repositories",
+            Trimmable::Urls => "safari_history URLs across clusters",
+            Trimmable::Containers => "containers",
+        }
+    }
+
+    fn len(self, context: &MinifiedContext) -> usize {
+        match self {
+            Trimmable::ShellHistory => context.shell_history.len(),
+            Trimmable::Repositories => context.commit_history.len(),
+            Trimmable::Commits => context
+                .commit_history
+                .iter()
+                .map(|repo| repo.commits.len())
+                .sum(),
+            Trimmable::Urls => context
+                .safari_history
+                .iter()
+                .map(|cluster| cluster.urls.len())
+                .sum(),
+            Trimmable::Containers => context.containers.len(),
+        }
+    }
+
+    fn tokens(self, context: &MinifiedContext) -> usize {
+        match self {
+            Trimmable::ShellHistory => estimate_json_tokens(&context.shell_history),
+            Trimmable::Repositories => estimate_json_tokens(&context.commit_history),
+            Trimmable::Commits => context
+                .commit_history
+                .iter()
+                .map(|repo| estimate_json_tokens(&repo.commits))
+                .sum(),
+            Trimmable::Urls => context
+                .safari_history
+                .iter()
+                .map(|cluster| estimate_json_tokens(&cluster.urls))
+                .sum(),
+            Trimmable::Containers => estimate_json_tokens(&context.containers),
+        }
+    }
+
+    /// Keep the first half of the list, or of each repository's commits or cluster's URLs.
+    fn halve(self, context: &mut MinifiedContext) {
+        fn keep_half<T>(list: &mut Vec<T>) {
+            list.truncate(list.len().div_ceil(2));
+        }
+        match self {
+            Trimmable::ShellHistory => keep_half(&mut context.shell_history),
+            Trimmable::Repositories => keep_half(&mut context.commit_history),
+            Trimmable::Commits => context
+                .commit_history
+                .iter_mut()
+                .for_each(|repo| keep_half(&mut repo.commits)),
+            Trimmable::Urls => context
+                .safari_history
+                .iter_mut()
+                .for_each(|cluster| keep_half(&mut cluster.urls)),
+            Trimmable::Containers => keep_half(&mut context.containers),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+
+    #[test]
+    fn estimates_words_numbers_and_json() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world"), 4);
+        assert_eq!(estimate_tokens("2025"), 2);
+        assert_eq!(estimate_tokens("{\n  \"a\": 1\n}"), 8);
+        // Roughly four characters per token for ordinary prose.
+        let prose =
+            "The parser now reports the line and column of every error it finds. ".repeat(20);
+        let tokens = estimate_tokens(&prose);
+        assert!((prose.len() / 6..=prose.len() / 3).contains(&tokens));
+    }
+
+    #[test]
+    fn truncates_whole_lines_and_says_so() {
+        let text = (0..100)
+            .map(|i| format!("line number {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let truncated = truncate_text(&text, 30);
+        assert!(truncated.starts_with("line number 0\nline number 1\n"));
+        assert!(truncated.ends_with("more lines were cut to fit the context window]"));
+        assert!(estimate_tokens(&truncated) < 50);
+        assert_eq!(truncate_text("short", 30), "short");
+    }
+
+    #[test]
+    fn trims_the_largest_lists_until_the_context_fits() {
+        let mut context = MinifiedContext::from(&Context::default());
+        context.shell_history = (0..400)
+            .map(|i| crate::shell::ShellHistoryEntry {
+                date_time: time::macros::datetime!(2025-01-01 09:00 UTC),
+                duration: time::Duration::ZERO,
+                host: "laptop".into(),
+                directory: "/src/parser".into(),
+                command: format!("cargo test --package parser -- case_{i}"),
+                exit_code: 0,
+                session_id: "s".into(),
+            })
+            .collect();
+        let budget = TokenBudget::new(8_192);
+        assert!(estimate_json_tokens(&context) > budget.context_tokens());
+
+        budget.fit_context(&mut context);
+
+        assert!(estimate_json_tokens(&context) <= budget.context_tokens());
+        assert!(!context.shell_history.is_empty());
+        assert_eq!(
+            context.truncated,
+            vec![format!(
+                "shell_history entries: kept {} of 400",
+                context.shell_history.len()
+            )]
+        );
+    }
+
+    #[test]
+    fn cuts_patches_to_fit_the_context() {
+        let patch = |i: usize| crate::git::diff::DiffWithPatch {
+            path: format!("src/file_{i}.rs").into(),
+            patch: (0..400)
+                .map(|line| format!("+    let value_{line} = compute({line});"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        let mut diff = DiffSummary {
+            repo_path: "/src/parser".into(),
+            unmodified: Default::default(),
+            added: vec![patch(0)],
+            deleted: Default::default(),
+            modified: vec![patch(1), patch(2)],
+            renamed: Default::default(),
+            copied: Default::default(),
+            untracked: Vec::new(),
+            typechange: Default::default(),
+            unreadable: Default::default(),
+            conflicted: Default::default(),
+        };
+        let budget = TokenBudget::new(8_192);
+        assert!(estimate_json_tokens(&diff) > budget.context_tokens());
+
+        budget.fit_diff(&mut diff);
+
+        let share = budget.context_tokens() / 3;
+        for entry in diff.added.iter().chain(&diff.modified) {
+            // The note about the cut lines comes on top of the share.
+            assert!(estimate_tokens(&entry.patch) <= share + 20);
+        }
+        assert!(
+            diff.modified[1]
+                .patch
+                .starts_with("+    let value_0 = compute(0);")
+        );
+        assert!(
+            diff.modified[1]
+                .patch
+                .ends_with("more lines were cut to fit the context window]")
+        );
+    }
+}
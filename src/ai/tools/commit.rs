@@ -8,10 +8,24 @@ use tracing::error;
 
 use super::CustomTool;
 use crate::git::diff::{get_file, get_patch};
+use crate::redact::Redactor;
+use crate::tool_registry;
 
 pub struct CommitMessageToolContext<'a> {
     pub repo: &'a Repository,
     pub diff: &'a Diff<'a>,
+    /// Redacts the files and patches the model reads, or `None` with `--no-redact`.
+    pub redactor: Option<&'a Redactor>,
+}
+
+impl CommitMessageToolContext<'_> {
+    /// `content` as the model may see it.
+    fn redact(&self, content: String) -> String {
+        match self.redactor {
+            Some(redactor) => redactor.redact(&content).into_owned(),
+            None => content,
+        }
+    }
 }
 
 /// # get_file
@@ -51,7 +65,7 @@ impl CustomTool for GetFile {
             self.start_line,
             self.end_line,
         ) {
-            Ok(content) => (OutputStatus::Completed, content),
+            Ok(content) => (OutputStatus::Completed, context.redact(content)),
             Err(e) => {
                 let error_msg = format!("Error retrieving file {:?}: {}", self.path, e);
                 error!("{}", error_msg);
@@ -73,7 +87,7 @@ impl CustomTool for GetPatch {
             self.start_line.map(|n| n as u32),
             self.end_line.map(|n| n as u32),
         ) {
-            Ok(content) => (OutputStatus::Completed, content),
+            Ok(content) => (OutputStatus::Completed, context.redact(content)),
             Err(e) => {
                 let error_msg = format!("Error retrieving patch for {:?}: {}", self.path, e);
                 error!("{}", error_msg);
@@ -82,3 +96,8 @@ impl CustomTool for GetPatch {
         }
     }
 }
+
+tool_registry!(CommitMessageToolContext<'_>, |context| {
+    GetPatch => context,
+    GetFile => context,
+});
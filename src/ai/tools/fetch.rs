@@ -1,9 +1,174 @@
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+
 use async_openai::types::responses::OutputStatus;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Url};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use tracing::{debug, error};
+
+use super::{CustomTool, readability, robots};
+use crate::context::Context;
+use crate::safari::SafariHistoryItem;
+use crate::{allowlist, http, tool_registry};
+
+/// How long one fetch may take unless `fetch_timeout` says otherwise.
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How much of a page is read unless `fetch_max_bytes` says otherwise.
+pub const DEFAULT_FETCH_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// The name `robots.txt` files can address `fetch_url` by.
+const ROBOTS_AGENT: &str = "daily-ai";
+
+/// Sent with every fetch, so site owners can tell what is reading their pages.
+const USER_AGENT: &str = concat!(
+    "daily-ai/",
+    env!("CARGO_PKG_VERSION"),
+    " (fetch_url; reads pages from the user's browser history)"
+);
+
+/// Limits on `fetch_url`, set once at startup.
+static POLICY: OnceLock<FetchPolicy> = OnceLock::new();
+
+/// Which URLs `fetch_url` may fetch and how much of them, from `--allow-any-url` and the
+/// `fetch_timeout` and `fetch_max_bytes` keys of the configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchPolicy {
+    /// Fetch any URL the model asks for, not only those on visited sites.
+    pub allow_any_url: bool,
+    /// How long to wait for a page, including its `robots.txt`.
+    pub timeout: Duration,
+    /// How much of a page to read; the rest is cut off.
+    pub max_bytes: usize,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        FetchPolicy {
+            allow_any_url: false,
+            timeout: DEFAULT_FETCH_TIMEOUT,
+            max_bytes: DEFAULT_FETCH_MAX_BYTES,
+        }
+    }
+}
+
+/// Use `policy` for every fetch from now on. Only the first call has an effect.
+pub fn configure(policy: FetchPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+/// The configured policy, or the defaults.
+fn policy() -> FetchPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Whether a response of type `content_type` can be read as text. A missing type is read, and
+/// the body is checked for binary data instead.
+fn is_text(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    mime.is_empty()
+        || mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-javascript"
+                | "application/x-yaml"
+                | "application/yaml"
+                | "application/toml"
+        )
+}
 
-use super::CustomTool;
+/// Whether the site's `robots.txt` lets `fetch_url` read `url`. A site without one, or whose
+/// `robots.txt` cannot be read, allows everything.
+async fn robots_allow(client: &Client, url: &Url) -> bool {
+    let Ok(robots_url) = url.join("/robots.txt") else {
+        return true;
+    };
+    let robots = match client.get(robots_url).send().await {
+        Ok(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+        Ok(resp) => {
+            debug!("No robots.txt for {url}: {}", resp.status());
+            return true;
+        }
+        Err(e) => {
+            debug!("Unable to read robots.txt for {url}: {e}");
+            return true;
+        }
+    };
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    robots::allows(&robots, ROBOTS_AGENT, &path)
+}
+
+/// `host` in lower case, without a trailing dot or a leading `www.`.
+fn site_of(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    match host.strip_prefix("www.") {
+        Some(site) => site.to_string(),
+        None => host,
+    }
+}
+
+/// The sites in the user's browser history, which are the only ones `fetch_url` fetches from,
+/// so the model cannot send what it has read to a host of its choosing.
+#[derive(Debug, Default)]
+pub struct VisitedSites {
+    hosts: BTreeSet<String>,
+}
+
+impl VisitedSites {
+    pub fn new<'a>(pages: impl IntoIterator<Item = &'a SafariHistoryItem>) -> Self {
+        let hosts = pages
+            .into_iter()
+            .filter_map(|page| Url::parse(&page.url).ok()?.host_str().map(site_of))
+            .collect();
+        VisitedSites { hosts }
+    }
+
+    /// The sites of the pages collected for `context`.
+    pub fn from_context(context: &Context) -> Self {
+        Self::new(
+            context
+                .safari_history()
+                .iter()
+                .flat_map(|cluster| &cluster.urls),
+        )
+    }
+
+    /// Check that `url` is on a visited host, unless `any` is set, and return its host.
+    ///
+    /// Only a leading `www.` may differ: other subdomains are refused, since a visit to a shared
+    /// host such as `github.io` would otherwise let any of its tenants receive data.
+    fn check(&self, url: &str, any: bool) -> Result<String, String> {
+        let url = Url::parse(url).map_err(|e| format!("it is not a valid URL: {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("{} URLs are not fetched", url.scheme()));
+        }
+        let host = url.host_str().unwrap_or_default().to_string();
+        let site = site_of(&host);
+        if any || self.hosts.contains(&site) {
+            Ok(host)
+        } else {
+            Err(format!(
+                "{host} is not in the browser history; pass --allow-any-url to fetch from any site"
+            ))
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FetchUrl {
@@ -17,35 +182,80 @@ pub struct FetchUrl {
     pub max_lines: Option<usize>,
 }
 
+impl FetchUrl {
+    /// Read the page as text, checking the URL against `sites` and the site's `robots.txt`, and
+    /// reading at most the configured number of bytes. HTML is cut down to its main content and
+    /// converted to Markdown.
+    async fn fetch(&self, sites: &VisitedSites) -> Result<String, String> {
+        let policy = policy();
+        let host = sites.check(&self.url, policy.allow_any_url)?;
+        let url = Url::parse(&self.url).map_err(|e| e.to_string())?;
+        allowlist::allow(&host);
+        let client = http::builder(&[])
+            .and_then(|builder| {
+                Ok(builder
+                    .timeout(policy.timeout)
+                    .user_agent(USER_AGENT)
+                    .build()?)
+            })
+            .map_err(|e| format!("unable to set up an HTTP client: {e}"))?;
+        if !robots_allow(&client, &url).await {
+            return Err(format!("the robots.txt of {host} does not allow it"));
+        }
+        let mut resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("the server answered {}", resp.status()));
+        }
+        let content_type = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !is_text(&content_type) {
+            return Err(format!("{content_type} is not text"));
+        }
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+            let room = policy.max_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        // Text has no NUL bytes; images and archives served with the wrong type usually do.
+        if body.iter().take(8192).any(|byte| *byte == 0) {
+            return Err("the response is binary, not text".to_string());
+        }
+        let text = String::from_utf8_lossy(&body);
+        let mut text = if content_type.to_lowercase().contains("html") {
+            html2md::parse_html(&readability::main_content(&text))
+        } else {
+            text.into_owned()
+        };
+        if truncated {
+            text.push_str(&format!(
+                "\n\n[Cut off after the first {} bytes]",
+                policy.max_bytes
+            ));
+        }
+        Ok(text)
+    }
+}
+
 impl CustomTool for FetchUrl {
-    type Context<'a> = ();
+    type Context<'a> = VisitedSites;
     const NAME: &'static str = "fetch_url";
     const DESCRIPTION: &'static str = "Fetches the content of a URL.";
 
-    async fn call(&self, _context: &Self::Context<'_>) -> (OutputStatus, String) {
-        let resp = match reqwest::get(&self.url).await {
-            Ok(r) => r,
+    async fn call(&self, sites: &Self::Context<'_>) -> (OutputStatus, String) {
+        let resp_text = match self.fetch(sites).await {
+            Ok(text) => text,
             Err(e) => {
-                let error_msg = format!("Failed to fetch URL {}: {e}", self.url);
-                error!(error_msg);
-                return (OutputStatus::Incomplete, error_msg);
-            }
-        };
-        let ct = if let Some(content) = resp.headers().get("content-type") {
-            content.to_str().unwrap_or_default().to_string()
-        } else {
-            "text/plain".to_string()
-        };
-        let resp_text = match resp.text().await {
-            Ok(t) => {
-                if ct.to_lowercase().contains("text/html") {
-                    html2md::parse_html(&t)
-                } else {
-                    t
-                }
-            }
-            Err(e) => {
-                let error_msg = format!("Failed to read response text from URL {}: {e}", self.url);
+                let error_msg = format!("Unable to fetch URL {}: {e}", self.url);
                 error!(error_msg);
                 return (OutputStatus::Incomplete, error_msg);
             }
@@ -78,3 +288,65 @@ impl CustomTool for FetchUrl {
         )
     }
 }
+
+/// Just [`FetchUrl`], offered unless the run is offline.
+pub struct FetchTools {
+    pub offline: bool,
+    pub sites: VisitedSites,
+}
+
+tool_registry!(FetchTools, |tools| {
+    FetchUrl if !tools.offline => &tools.sites,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetches_only_from_visited_sites() {
+        let page = |url: &str| SafariHistoryItem {
+            url: url.to_string(),
+            title: None,
+            visit_count: 1,
+            last_visited: time::OffsetDateTime::UNIX_EPOCH,
+        };
+        let sites = VisitedSites::new(&[
+            page("https://www.docs.rs/tokio/latest/tokio/"),
+            page("https://GitHub.com/me/app/pull/7"),
+            page("https://github.io/"),
+            page("not a url"),
+        ]);
+
+        assert_eq!(
+            sites.check("https://docs.rs/reqwest", false),
+            Ok("docs.rs".to_string())
+        );
+        assert!(sites.check("https://www.github.com/me/app", false).is_ok());
+        assert!(sites.check("https://gist.github.com/me/1", false).is_err());
+        assert!(
+            sites
+                .check("https://attacker.github.io/?q=secret", false)
+                .is_err()
+        );
+        assert!(
+            sites
+                .check("https://evilgithub.com/?q=secret", false)
+                .is_err()
+        );
+        assert!(sites.check("https://example.com/?q=secret", false).is_err());
+        assert!(sites.check("file:///etc/passwd", true).is_err());
+        assert!(sites.check("https://example.com/", true).is_ok());
+    }
+
+    #[test]
+    fn reads_only_text_content_types() {
+        assert!(is_text("text/html; charset=utf-8"));
+        assert!(is_text("application/ld+json"));
+        assert!(is_text("Application/JSON"));
+        assert!(is_text(""));
+        assert!(!is_text("image/png"));
+        assert!(!is_text("application/pdf"));
+        assert!(!is_text("application/octet-stream"));
+    }
+}
@@ -1,10 +1,14 @@
 pub mod commit;
 pub mod fetch;
+pub mod readability;
+pub mod robots;
+pub mod rollup;
 pub mod summary;
+pub mod time_spans;
 
 use async_openai::types::responses::{
     FunctionCallOutput, FunctionCallOutputItemParam, FunctionTool, FunctionToolCall, InputItem,
-    Item, OutputStatus,
+    Item, OutputStatus, Tool,
 };
 use schemars::{JsonSchema, schema_for};
 use serde::Serialize;
@@ -86,6 +90,107 @@ pub trait CustomTool:
     }
 }
 
+/// The tools offered to the model in one conversation, and the data they read. Implemented with
+/// [`tool_registry!`](crate::tool_registry).
+pub trait ToolRegistry {
+    /// Definitions of the tools to send with each request.
+    fn definitions(&self) -> Vec<Tool>;
+
+    /// Run the tool `call` names with its data, or tell the model there is no such tool.
+    async fn execute(&self, call: FunctionToolCall) -> Vec<InputItem>;
+}
+
+/// Implement [`ToolRegistry`] for a type holding the data the tools read.
+///
+/// Each entry names a [`CustomTool`], an optional condition under which it is offered, and the
+/// context it is called with, with the registry bound to the name given between the bars:
+///
+/// ```ignore
+/// tool_registry!(SummaryTools<'_>, |tools| {
+///     FetchUrl if !tools.offline => &tools.sites,
+///     GetDiff => tools.context.commit_history(),
+/// });
+/// ```
+///
+/// With `as Name` after the registry, it also defines the enum `Name` of all its tools, whose
+/// schemas `daily-ai show` prints, so the two list the same tools.
+#[macro_export]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! tool_registry {
+    (@enabled) => {
+        true
+    };
+    (@enabled $enabled:expr) => {
+        $enabled
+    };
+    (
+        $registry:ident $(<$lt:lifetime>)? as $show:ident,
+        |$this:ident| { $($tool:ident $(if $enabled:expr)? => $context:expr),* $(,)? }
+    ) => {
+        #[doc = concat!("The tools of [`", stringify!($registry), "`], for `daily-ai show`.")]
+        #[derive(clap::ValueEnum, Debug, Clone)]
+        pub enum $show {
+            $($tool),*
+        }
+
+        impl crate::cli::PrintSchema for $show {
+            fn print_schema(&self) -> String {
+                let val = match self {
+                    $(Self::$tool => <$tool as crate::ai::SchemaInfo>::schema_value()),*
+                };
+                match serde_json::to_string_pretty(&val) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::error!("Failed to serialize schema for {:?}: {}", self, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+
+        crate::tool_registry!($registry $(<$lt>)?, |$this| {
+            $($tool $(if $enabled)? => $context),*
+        });
+    };
+    (
+        $registry:ident $(<$lt:lifetime>)?,
+        |$this:ident| { $($tool:ident $(if $enabled:expr)? => $context:expr),* $(,)? }
+    ) => {
+        impl crate::ai::tools::ToolRegistry for $registry $(<$lt>)? {
+            fn definitions(&self) -> Vec<async_openai::types::responses::Tool> {
+                #[allow(unused_variables)]
+                let $this = self;
+                let mut tools = Vec::new();
+                $(
+                    if crate::tool_registry!(@enabled $($enabled)?) {
+                        tools.push(async_openai::types::responses::Tool::Function(
+                            <$tool as crate::ai::tools::CustomTool>::definition(),
+                        ));
+                    }
+                )*
+                tools
+            }
+
+            async fn execute(
+                &self,
+                call: async_openai::types::responses::FunctionToolCall,
+            ) -> Vec<async_openai::types::responses::InputItem> {
+                let $this = self;
+                match call.name.as_str() {
+                    $(
+                        name if name == <$tool as crate::ai::tools::CustomTool>::NAME
+                            $(&& $enabled)? =>
+                        {
+                            <$tool as crate::ai::tools::CustomTool>::process(call, $context).await
+                        }
+                    )*
+                    _ => crate::ai::tools::unknown_tool(call),
+                }
+            }
+        }
+    };
+}
+
 pub fn arbitrary_tool_error(call: FunctionToolCall, msg: &str) -> Vec<InputItem> {
     warn!(msg);
     let mut items = vec![InputItem::Item(Item::FunctionCall(call.clone()))];
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+/// Elements that hold a page's main content, in the order they are looked for.
+const CONTENT: &[&str] = &["main", "article", "body"];
+
+/// Elements with nothing worth reading: scripts, styles, and the site's navigation and forms.
+const BOILERPLATE: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "aside", "footer", "form",
+];
+
+/// Whether an element starts at `at` in `lower`: `<tag` followed by the end of the tag name.
+fn opens(lower: &str, at: usize, tag: &str) -> bool {
+    lower[at..].starts_with('<')
+        && lower[at + 1..].starts_with(tag)
+        && lower[at + 1 + tag.len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_whitespace() || c == '>' || c == '/')
+}
+
+/// The byte ranges of the outermost `tag` elements in `lower`, the lower-cased page, with their
+/// start and end tags. An element that is never closed runs to the end of the page.
+fn elements(lower: &str, tag: &str) -> Vec<Range<usize>> {
+    let close = format!("</{tag}");
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = lower[from..]
+        .match_indices('<')
+        .map(|(at, _)| from + at)
+        .find(|&at| opens(lower, at, tag))
+    {
+        let open_end = lower[start..]
+            .find('>')
+            .map_or(lower.len(), |at| start + at + 1);
+        if lower[..open_end].ends_with("/>") {
+            found.push(start..open_end);
+            from = open_end;
+            continue;
+        }
+        let mut depth = 1;
+        let mut at = open_end;
+        let end = loop {
+            let Some(next) = lower[at..].find('<').map(|next| at + next) else {
+                break lower.len();
+            };
+            if lower[next..].starts_with(&close) {
+                depth -= 1;
+                at = lower[next..]
+                    .find('>')
+                    .map_or(lower.len(), |end| next + end + 1);
+                if depth == 0 {
+                    break at;
+                }
+            } else {
+                if opens(lower, next, tag) {
+                    depth += 1;
+                }
+                at = next + 1;
+            }
+        };
+        found.push(start..end);
+        from = end;
+    }
+    found
+}
+
+/// The main content of the HTML page `html`, the way reader modes pick it: the `<main>`
+/// element, else the longest `<article>`, else the `<body>`, without scripts, styles,
+/// navigation, sidebars, footers, or forms.
+pub fn main_content(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let range = CONTENT
+        .iter()
+        .find_map(|tag| elements(&lower, tag).into_iter().max_by_key(Range::len))
+        .unwrap_or(0..html.len());
+    let mut content = html[range].to_string();
+    for tag in BOILERPLATE {
+        let lower = content.to_ascii_lowercase();
+        for range in elements(&lower, tag).into_iter().rev() {
+            content.replace_range(range, "");
+        }
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_main_content_without_boilerplate() {
+        let page = "<html><head><STYLE>p {}</STYLE></head><body><nav><a href=/>Home</a></nav>\
+             <article><p>Short</p></article>\
+             <article class=post><h1>Title</h1><script>track()</script><svg/>\
+             <p>Body <mark>text</mark></p><aside><aside>Ad</aside> more ad</aside></article>\
+             <footer>(c)</footer></body></html>";
+
+        assert_eq!(
+            main_content(page),
+            "<article class=post><h1>Title</h1><p>Body <mark>text</mark></p></article>"
+        );
+        assert_eq!(main_content("<p>No body"), "<p>No body");
+        assert_eq!(
+            main_content("<body><main>Main</main><nav>Menu"),
+            "<main>Main</main>"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+/// A rule of a `robots.txt` group: a path pattern and whether it allows or disallows.
+#[derive(Debug)]
+struct Rule<'a> {
+    allow: bool,
+    pattern: &'a str,
+}
+
+/// Whether `path` matches `pattern`, where `*` matches any run of characters and a trailing `$`
+/// anchors the pattern to the end of the path.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return true;
+    };
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part of an anchored pattern has to end the path.
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Whether `robots`, the text of a site's `robots.txt`, lets `agent` fetch `path`, as RFC 9309
+/// reads it.
+///
+/// The groups naming `agent` apply, or the `*` groups when none does. The longest matching rule
+/// wins, and `Allow` wins a tie. A path no rule matches is allowed.
+pub fn allows(robots: &str, agent: &str, path: &str) -> bool {
+    let agent = agent.to_lowercase();
+    let mut named: Vec<Rule> = Vec::new();
+    let mut any: Vec<Rule> = Vec::new();
+    // The agents of the group being read, and whether its rules have started.
+    let (mut for_agent, mut for_any, mut in_rules) = (false, false, false);
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "user-agent" => {
+                if in_rules {
+                    (for_agent, for_any, in_rules) = (false, false, false);
+                }
+                let name = value.to_lowercase();
+                for_agent |= name == agent;
+                for_any |= name == "*";
+            }
+            key @ ("allow" | "disallow") => {
+                in_rules = true;
+                // An empty `Disallow` allows everything, so it adds no rule.
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = || Rule {
+                    allow: key == "allow",
+                    pattern: value,
+                };
+                if for_agent {
+                    named.push(rule());
+                }
+                if for_any {
+                    any.push(rule());
+                }
+            }
+            _ => {}
+        }
+    }
+    let rules = if named.is_empty() { any } else { named };
+    rules
+        .iter()
+        .filter(|rule| matches(rule.pattern, path))
+        .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+        .is_none_or(|rule| rule.allow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_group_for_the_agent_and_the_longest_rule() {
+        let robots = "User-agent: *\nDisallow: /private/\nAllow: /private/press$\n\n\
+             User-agent: Daily-AI\nUser-agent: other\nDisallow: /*.pdf$\nDisallow: /drafts\n\
+             # Allow: /drafts\n\nUser-agent: nobody\nDisallow: /\n";
+
+        assert!(allows(robots, "someone", "/docs/intro"));
+        assert!(!allows(robots, "someone", "/private/notes"));
+        assert!(allows(robots, "someone", "/private/press"));
+        assert!(!allows(robots, "someone", "/private/press/2024"));
+        assert!(allows(robots, "daily-ai", "/private/notes"));
+        assert!(!allows(robots, "daily-ai", "/drafts/today"));
+        assert!(!allows(robots, "daily-ai", "/papers/rfc.pdf"));
+        assert!(allows(robots, "daily-ai", "/papers/rfc.pdf.html"));
+        assert!(allows("", "daily-ai", "/"));
+        assert!(allows("User-agent: *\nDisallow:\n", "daily-ai", "/"));
+    }
+}
@@ -0,0 +1,53 @@
+use async_openai::types::responses::OutputStatus;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use super::CustomTool;
+use crate::archive::ArchiveEntry;
+use crate::tool_registry;
+
+/// # get_daily_summary
+/// Get the full summary of one day in the period: highlights, time breakdown, repository
+/// summaries, tickets, notes, and everything else the daily log recorded.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GetDailySummary {
+    /// The day, as listed in `days` (YYYY-MM-DD)
+    pub date: String,
+}
+
+impl CustomTool for GetDailySummary {
+    type Context<'a> = [ArchiveEntry];
+    const NAME: &'static str = "get_daily_summary";
+    const DESCRIPTION: &'static str = "Get the full summary of one day in the period.";
+
+    async fn call(&self, context: &Self::Context<'_>) -> (OutputStatus, String) {
+        let Some(summary) = context
+            .iter()
+            .rev()
+            .find(|entry| entry.date.to_string() == self.date.trim())
+            .and_then(|entry| entry.context.summary.as_ref())
+        else {
+            let error_msg = format!("No summary stored for {}", self.date);
+            error!(error_msg);
+            return (OutputStatus::Incomplete, error_msg);
+        };
+        match serde_json::to_string_pretty(summary) {
+            Ok(json) => (OutputStatus::Completed, json),
+            Err(e) => {
+                let error_msg = format!("Failed to serialize the summary of {}: {e}", self.date);
+                error!(error_msg);
+                (OutputStatus::Incomplete, error_msg)
+            }
+        }
+    }
+}
+
+/// The tools for writing a rollup from the archived days of its period.
+pub struct RollupTools<'a> {
+    pub entries: &'a [ArchiveEntry],
+}
+
+tool_registry!(RollupTools<'_>, |tools| {
+    GetDailySummary => tools.entries,
+});
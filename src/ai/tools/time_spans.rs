@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+
+use async_openai::types::responses::OutputStatus;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tracing::error;
+
+use super::CustomTool;
+use crate::context::Context;
+
+/// Longest pause that still counts as one block unless the model asks for another.
+const DEFAULT_MAX_GAP_MINUTES: u32 = 20;
+
+/// Directories, repositories, and topics listed per block, most active first.
+const TOP_LABELS: usize = 3;
+
+/// # compute_time_spans
+/// Compute the contiguous blocks of activity in the shell history, browser history, and commits,
+/// with their exact start, end, and duration. Use these numbers for the time breakdown instead
+/// of estimating durations.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ComputeTimeSpans {
+    /// Longest pause, in minutes, that still counts as the same block (default 20)
+    #[serde(default)]
+    pub max_gap_minutes: Option<u32>,
+    /// Sources to include: `shell`, `browser`, and `git` (default all of them)
+    #[serde(default)]
+    pub sources: Option<Vec<String>>,
+    /// Optional RFC 3339 timestamp; activity before it is left out
+    #[serde(default)]
+    pub start_time: Option<String>,
+    /// Optional RFC 3339 timestamp; activity after it is left out
+    #[serde(default)]
+    pub end_time: Option<String>,
+}
+
+/// Where an activity came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Shell,
+    Browser,
+    Git,
+}
+
+/// One shell command, page visit, or commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Activity {
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    source: Source,
+    /// The directory, browsing topic, or repository it belongs to.
+    label: String,
+}
+
+/// A contiguous block of activity, as returned to the model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct TimeSpan {
+    start: String,
+    end: String,
+    /// E.g. `1h 20m`.
+    duration: String,
+    minutes: i64,
+    shell_commands: usize,
+    page_visits: usize,
+    commits: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    directories: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    topics: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    repos: Vec<String>,
+}
+
+/// The blocks of a day and the time they add up to.
+#[derive(Debug, Serialize)]
+struct TimeSpans {
+    spans: Vec<TimeSpan>,
+    total: String,
+    total_minutes: i64,
+}
+
+/// `minutes` as `2h`, `45m`, or `1h 20m`.
+fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+/// The most frequent `labels` of `source`, most frequent first.
+fn top_labels(activities: &[Activity], source: Source) -> Vec<String> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for activity in activities.iter().filter(|a| a.source == source) {
+        *counts.entry(&activity.label).or_default() += 1;
+    }
+    let mut labels: Vec<(&str, usize)> = counts.into_iter().collect();
+    labels.sort_by(|a, b| b.1.cmp(&a.1));
+    labels
+        .into_iter()
+        .take(TOP_LABELS)
+        .map(|(label, _)| label.to_string())
+        .collect()
+}
+
+fn to_span(activities: &[Activity]) -> Option<TimeSpan> {
+    let start = activities.first()?.start;
+    let end = activities.iter().map(|a| a.end).max()?;
+    let minutes = (end - start).whole_minutes();
+    let count = |source| activities.iter().filter(|a| a.source == source).count();
+    Some(TimeSpan {
+        start: start.format(&Rfc3339).ok()?,
+        end: end.format(&Rfc3339).ok()?,
+        duration: format_minutes(minutes),
+        minutes,
+        shell_commands: count(Source::Shell),
+        page_visits: count(Source::Browser),
+        commits: count(Source::Git),
+        directories: top_labels(activities, Source::Shell),
+        topics: top_labels(activities, Source::Browser),
+        repos: top_labels(activities, Source::Git),
+    })
+}
+
+/// Group `activities` into blocks, starting a new one after a pause longer than `max_gap`.
+fn spans(mut activities: Vec<Activity>, max_gap: Duration) -> Vec<TimeSpan> {
+    activities.sort_by_key(|a| a.start);
+    let mut spans = Vec::new();
+    let mut block: Vec<Activity> = Vec::new();
+    let mut block_end = OffsetDateTime::UNIX_EPOCH;
+    for activity in activities {
+        if !block.is_empty() && activity.start - block_end > max_gap {
+            spans.extend(to_span(&block));
+            block.clear();
+        }
+        block_end = if block.is_empty() {
+            activity.end
+        } else {
+            block_end.max(activity.end)
+        };
+        block.push(activity);
+    }
+    spans.extend(to_span(&block));
+    spans
+}
+
+/// The shell commands, page visits, and commits of `context` from `sources`.
+fn activities(context: &Context, sources: &[Source]) -> Vec<Activity> {
+    let mut activities = Vec::new();
+    if sources.contains(&Source::Shell) {
+        activities.extend(context.shell_history().iter().map(|entry| Activity {
+            start: entry.date_time,
+            end: entry.date_time + entry.duration.max(Duration::ZERO),
+            source: Source::Shell,
+            label: entry.directory.display().to_string(),
+        }));
+    }
+    if sources.contains(&Source::Browser) {
+        for cluster in context.safari_history() {
+            activities.extend(cluster.urls.iter().map(|page| Activity {
+                start: page.last_visited,
+                end: page.last_visited,
+                source: Source::Browser,
+                label: cluster.label.clone(),
+            }));
+        }
+    }
+    if sources.contains(&Source::Git) {
+        for repo in context.commit_history() {
+            let project = repo.project_name();
+            activities.extend(repo.commits.iter().map(|commit| Activity {
+                start: commit.timestamp,
+                end: commit.timestamp,
+                source: Source::Git,
+                label: project.clone(),
+            }));
+        }
+    }
+    activities
+}
+
+impl ComputeTimeSpans {
+    fn sources(&self) -> Result<Vec<Source>, String> {
+        let Some(names) = &self.sources else {
+            return Ok(vec![Source::Shell, Source::Browser, Source::Git]);
+        };
+        names
+            .iter()
+            .map(|name| match name.trim().to_lowercase().as_str() {
+                "shell" => Ok(Source::Shell),
+                "browser" => Ok(Source::Browser),
+                "git" => Ok(Source::Git),
+                other => Err(format!(
+                    "Unknown source {other:?}; use shell, browser, or git"
+                )),
+            })
+            .collect()
+    }
+
+    fn bound(time: Option<&str>) -> Result<Option<OffsetDateTime>, String> {
+        time.map(|time| {
+            OffsetDateTime::parse(time, &Rfc3339)
+                .map_err(|e| format!("Invalid timestamp {time:?}: {e}"))
+        })
+        .transpose()
+    }
+
+    fn compute(&self, context: &Context) -> Result<TimeSpans, String> {
+        let start = Self::bound(self.start_time.as_deref())?;
+        let end = Self::bound(self.end_time.as_deref())?;
+        let mut activities = activities(context, &self.sources()?);
+        activities
+            .retain(|a| start.is_none_or(|s| a.start >= s) && end.is_none_or(|e| a.start <= e));
+        let max_gap = self.max_gap_minutes.unwrap_or(DEFAULT_MAX_GAP_MINUTES);
+        let spans = spans(activities, Duration::minutes(max_gap.into()));
+        let total_minutes = spans.iter().map(|span| span.minutes).sum();
+        Ok(TimeSpans {
+            spans,
+            total: format_minutes(total_minutes),
+            total_minutes,
+        })
+    }
+}
+
+impl CustomTool for ComputeTimeSpans {
+    type Context<'a> = Context;
+    const NAME: &'static str = "compute_time_spans";
+    const DESCRIPTION: &'static str =
+        "Compute the contiguous blocks of activity of the day with their exact durations.";
+
+    async fn call(&self, context: &Self::Context<'_>) -> (OutputStatus, String) {
+        let json = self
+            .compute(context)
+            .and_then(|spans| serde_json::to_string_pretty(&spans).map_err(|e| e.to_string()));
+        match json {
+            Ok(json) => (OutputStatus::Completed, json),
+            Err(e) => {
+                let error_msg = format!("Failed to compute time spans: {e}");
+                error!(error_msg);
+                (OutputStatus::Incomplete, error_msg)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn activity(start: OffsetDateTime, minutes: i64, source: Source, label: &str) -> Activity {
+        Activity {
+            start,
+            end: start + Duration::minutes(minutes),
+            source,
+            label: label.to_string(),
+        }
+    }
+
+    #[test]
+    fn splits_activity_at_long_pauses() {
+        let activities = vec![
+            activity(
+                datetime!(2025-01-10 09:00 UTC),
+                0,
+                Source::Shell,
+                "~/parser",
+            ),
+            activity(
+                datetime!(2025-01-10 09:10 UTC),
+                30,
+                Source::Shell,
+                "~/parser",
+            ),
+            activity(datetime!(2025-01-10 09:55 UTC), 0, Source::Git, "me/parser"),
+            activity(
+                datetime!(2025-01-10 09:20 UTC),
+                0,
+                Source::Browser,
+                "nom docs",
+            ),
+            activity(datetime!(2025-01-10 13:00 UTC), 0, Source::Browser, "Email"),
+        ];
+        let spans = spans(activities, Duration::minutes(20));
+
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].start, "2025-01-10T09:00:00Z");
+        assert_eq!(spans[0].end, "2025-01-10T09:55:00Z");
+        assert_eq!(spans[0].duration, "55m");
+        assert_eq!(
+            (
+                spans[0].shell_commands,
+                spans[0].page_visits,
+                spans[0].commits
+            ),
+            (2, 1, 1)
+        );
+        assert_eq!(spans[0].directories, ["~/parser"]);
+        assert_eq!(spans[1].minutes, 0);
+        assert_eq!(format_minutes(80), "1h 20m");
+        assert_eq!(format_minutes(120), "2h");
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{LazyLock, RwLock};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Proxy, Url};
+use tokio::net::TcpStream;
+use tracing::{debug, warn};
+
+use crate::AppResult;
+use crate::error::AppError;
+
+/// How many redirects a request follows before giving up, as reqwest does by default.
+const MAX_REDIRECTS: usize = 10;
+
+/// Host of the proxy that requests to hosts off the allowlist are sent to. The `.invalid` domain
+/// is reserved and never on the allowlist, so [`Resolver`] refuses it.
+const REFUSED_PROXY_HOST: &str = "host-not-allowed.invalid";
+
+/// Every host daily-ai may talk to, registered by the code that talks to it.
+static ALLOWLIST: LazyLock<RwLock<Allowlist>> = LazyLock::new(Default::default);
+
+/// Hosts allowed to receive requests. Allowing a host name allows its subdomains too, so allowing
+/// `hf.co` covers the CDN that `huggingface.co` redirects downloads to. IP addresses only match
+/// themselves.
+#[derive(Debug, Default)]
+struct Allowlist {
+    hosts: BTreeSet<String>,
+}
+
+impl Allowlist {
+    fn insert(&mut self, host: &str) -> bool {
+        let host = normalize(host);
+        !host.is_empty() && self.hosts.insert(host)
+    }
+
+    fn allows(&self, host: &str) -> bool {
+        let host = normalize(host);
+        self.hosts.iter().any(|allowed| {
+            host == *allowed
+                || (host.parse::<IpAddr>().is_err()
+                    && host
+                        .strip_suffix(allowed.as_str())
+                        .is_some_and(|sub| sub.ends_with('.')))
+        })
+    }
+}
+
+/// `host` in lower case, without IPv6 brackets or a trailing dot.
+fn normalize(host: &str) -> String {
+    host.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_lowercase()
+}
+
+/// The host of `target`, which is either a URL or a bare host name.
+fn host_of(target: &str) -> Option<String> {
+    if target.contains("://") {
+        Url::parse(target).ok()?.host_str().map(str::to_string)
+    } else {
+        Some(target.to_string())
+    }
+}
+
+/// Allow requests to the host of `target`, a URL or a bare host name, and its subdomains.
+///
+/// Call this where an integration decides which server it talks to: the model server, Hugging
+/// Face, the ticket trackers, and so on. Nothing else can be reached.
+pub fn allow(target: &str) {
+    let Some(host) = host_of(target) else {
+        warn!("Unable to tell the host of {target}; it stays off the network allowlist");
+        return;
+    };
+    let mut allowlist = ALLOWLIST.write().unwrap_or_else(|e| e.into_inner());
+    if allowlist.insert(&host) {
+        debug!("Added {host} to the network allowlist");
+    }
+}
+
+/// Whether requests to `host` are allowed.
+pub fn is_allowed(host: &str) -> bool {
+    ALLOWLIST
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .allows(host)
+}
+
+fn rejected(host: &str) -> AppError {
+    AppError::HostNotAllowed(host.to_string())
+}
+
+/// Open a TCP connection to `host`, which must be on the allowlist.
+#[allow(clippy::disallowed_methods)]
+pub async fn connect(host: &str, port: u16) -> AppResult<TcpStream> {
+    if !is_allowed(host) {
+        return Err(rejected(host));
+    }
+    Ok(TcpStream::connect((host, port)).await?)
+}
+
+/// Resolves only host names on the allowlist, so an HTTP client built with it cannot reach any
+/// other host, including through redirects.
+#[derive(Debug, Clone, Copy)]
+pub struct Resolver;
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            if host == REFUSED_PROXY_HOST {
+                return Err(
+                    "the request's host is not on the network allowlist, so nothing was \
+                            sent to it"
+                        .into(),
+                );
+            }
+            if !is_allowed(&host) {
+                return Err(rejected(&host).to_string().into());
+            }
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Checks the host of every request before a connection is opened, which the [`Resolver`]
+/// cannot do for URLs with an IP address or for requests sent through a proxy. Requests to
+/// hosts off the allowlist are sent to a proxy that the resolver refuses, so they fail before
+/// anything leaves the machine; other requests are left to the proxies added after this one.
+pub fn guard() -> Proxy {
+    Proxy::custom(|url| {
+        let host = url.host_str().unwrap_or_default();
+        if is_allowed(host) {
+            return None;
+        }
+        debug!("Refused a request to {host}, which is not on the network allowlist");
+        Some(format!("http://{REFUSED_PROXY_HOST}"))
+    })
+}
+
+/// Follows redirects only to hosts on the allowlist, which covers redirects to IP addresses
+/// that are never resolved.
+pub fn redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        let host = attempt.url().host_str().unwrap_or_default().to_string();
+        if !is_allowed(&host) {
+            attempt.error(rejected(&host).to_string())
+        } else if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error(format!("stopped after {MAX_REDIRECTS} redirects"))
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_registered_hosts_and_their_subdomains_only() {
+        let mut allowlist = Allowlist::default();
+        for target in [
+            "https://api.linear.app/graphql",
+            "hf.co",
+            "127.0.0.1",
+            "[::1]",
+            "2.3.4",
+        ] {
+            allowlist.insert(&host_of(target).unwrap());
+        }
+
+        assert!(allowlist.allows("api.linear.app"));
+        assert!(allowlist.allows("cas-bridge.xethub.hf.co."));
+        assert!(allowlist.allows("CDN-LFS.HF.CO"));
+        assert!(allowlist.allows("127.0.0.1"));
+        assert!(allowlist.allows("[::1]"));
+        assert!(!allowlist.allows("linear.app"));
+        assert!(!allowlist.allows("evilhf.co"));
+        assert!(!allowlist.allows("telemetry.example.com"));
+        assert!(!allowlist.allows("1.2.3.4"));
+        assert!(!allowlist.allows(""));
+    }
+}
@@ -0,0 +1,299 @@
+use std::env;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::error::AppError;
+use crate::time_utils::{macos_past_ts, macos_to_datetime};
+
+/// Bodies longer than this many characters are cut, so one long note cannot crowd out the rest
+/// of the context.
+const MAX_BODY_CHARS: usize = 4000;
+
+/// Notes created or modified after `?` (macOS seconds), with their folder. `{created}` is
+/// replaced by the creation date columns the store has, which changed names across macOS
+/// releases, and `{data}` by the body column, or `NULL` when bodies are not read.
+const NOTES_QUERY: &str = "
+SELECT
+    n.ZTITLE1 AS title,
+    f.ZTITLE2 AS folder,
+    {created} AS created,
+    n.ZMODIFICATIONDATE1 AS modified,
+    COALESCE(n.ZISPASSWORDPROTECTED, 0) AS locked,
+    {data} AS data
+FROM ZICCLOUDSYNCINGOBJECT n
+LEFT JOIN ZICCLOUDSYNCINGOBJECT f ON f.Z_PK = n.ZFOLDER
+LEFT JOIN ZICNOTEDATA d ON d.Z_PK = n.ZNOTEDATA
+WHERE n.ZTITLE1 IS NOT NULL
+    AND COALESCE(n.ZMARKEDFORDELETION, 0) = 0
+    AND n.ZMODIFICATIONDATE1 > ?
+ORDER BY n.ZMODIFICATIONDATE1 ASC";
+
+/// Raw row returned by `NOTES_QUERY`.
+#[derive(Debug, FromQueryResult)]
+struct NoteRow {
+    title: String,
+    folder: Option<String>,
+    created: Option<f64>,
+    modified: f64,
+    locked: bool,
+    data: Option<Vec<u8>>,
+}
+
+/// An Apple Notes note created or modified during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AppleNote {
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub folder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(with = "crate::serde_helpers::option_offset_datetime")]
+    pub created: Option<OffsetDateTime>,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub modified: OffsetDateTime,
+    /// Whether the note was created during the window (rather than only edited).
+    pub new: bool,
+    /// Plain text of the note, only read with `--apple-notes-body` and never for locked notes.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub body: Option<String>,
+}
+
+/// Read a protobuf varint from the front of `buf`, advancing it.
+fn varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The first length-delimited field `number` of the protobuf message `buf`.
+fn field(mut buf: &[u8], number: u64) -> Option<&[u8]> {
+    while !buf.is_empty() {
+        let key = varint(&mut buf)?;
+        let skip = match key & 7 {
+            0 => {
+                varint(&mut buf)?;
+                0
+            }
+            1 => 8,
+            2 => {
+                let len = usize::try_from(varint(&mut buf)?).ok()?;
+                if key >> 3 == number {
+                    return buf.get(..len);
+                }
+                len
+            }
+            5 => 4,
+            _ => return None,
+        };
+        buf = buf.get(skip..)?;
+    }
+    None
+}
+
+/// Plain text of a note from its gzipped `ZICNOTEDATA.ZDATA` blob.
+///
+/// The blob is a protobuf whose text sits at document (field 2) → note (field 3) → text
+/// (field 2). Formatting runs and attachments are ignored.
+fn note_text(data: &[u8]) -> Option<String> {
+    let mut raw = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut raw).ok()?;
+    let text = field(field(field(&raw, 2)?, 3)?, 2)?;
+    let text = String::from_utf8_lossy(text)
+        // Attachments are stored as U+FFFC placeholders.
+        .replace('\u{fffc}', "")
+        .trim()
+        .to_string();
+    Some(match text.char_indices().nth(MAX_BODY_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    })
+}
+
+impl NoteRow {
+    fn into_note(self, since: f64, with_body: bool) -> AppleNote {
+        let body = match (&self.data, with_body && !self.locked) {
+            (Some(data), true) => note_text(data),
+            _ => None,
+        };
+        AppleNote {
+            new: self.created.is_some_and(|created| created > since),
+            title: self.title.trim().to_string(),
+            folder: self.folder,
+            created: self.created.map(macos_to_datetime),
+            modified: macos_to_datetime(self.modified),
+            body,
+        }
+    }
+}
+
+/// Resolve the Apple Notes `NoteStore.sqlite` path, honouring `APPLE_NOTES_DB_PATH`.
+#[tracing::instrument(name = "Searching for the Apple Notes database file", level = "info")]
+fn get_notes_db_path() -> AppResult<PathBuf> {
+    if let Ok(path) = env::var("APPLE_NOTES_DB_PATH") {
+        return Ok(PathBuf::from(path));
+    }
+    env::home_dir()
+        .or_else(|| env::var("HOME").ok().map(PathBuf::from))
+        .map(|home| home.join("Library/Group Containers/group.com.apple.notes/NoteStore.sqlite"))
+        .ok_or_else(|| AppError::DirNotFound("~/Library/Group Containers".into()))
+}
+
+/// Open the Apple Notes database read-only.
+#[tracing::instrument(name = "Connecting to the Apple Notes database", level = "info")]
+async fn connect_to_db<P: AsRef<Path> + std::fmt::Debug>(
+    db_path: P,
+) -> AppResult<DatabaseConnection> {
+    let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=ro", db_path.as_ref().display()));
+    opt.sqlx_logging(false);
+    trace!("Connecting to Apple Notes database");
+    Ok(Database::connect(opt).await?)
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ColumnRow {
+    name: String,
+}
+
+/// Columns of `table`, to pick between the names different macOS releases use.
+async fn columns(db: &DatabaseConnection, table: &str) -> AppResult<Vec<String>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "SELECT name FROM pragma_table_info(?)",
+        [table.into()],
+    );
+    Ok(ColumnRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| row.name)
+        .collect())
+}
+
+/// Fetch the notes created or modified during the window, with their titles and folders, and
+/// their bodies when `with_body` is set.
+///
+/// Reading `NoteStore.sqlite` requires Full Disk Access for the terminal running daily-ai.
+#[tracing::instrument(name = "Fetching Apple Notes activity", level = "info")]
+pub async fn get_apple_notes_activity(
+    duration: &Duration,
+    with_body: bool,
+) -> AppResult<Vec<AppleNote>> {
+    let db_path = get_notes_db_path()?;
+    let db = connect_to_db(&db_path).await?;
+    let notes = fetch_notes(&db, macos_past_ts(duration), with_body).await?;
+    debug!("Fetched {} notes", notes.len());
+    Ok(notes)
+}
+
+/// Run `NOTES_QUERY` for notes modified after `since` (macOS seconds).
+async fn fetch_notes(
+    db: &DatabaseConnection,
+    since: f64,
+    with_body: bool,
+) -> AppResult<Vec<AppleNote>> {
+    let note_columns = columns(db, "ZICCLOUDSYNCINGOBJECT").await?;
+    let created: Vec<String> = ["ZCREATIONDATE3", "ZCREATIONDATE1", "ZCREATIONDATE"]
+        .into_iter()
+        .filter(|column| note_columns.iter().any(|c| c == column))
+        .map(|column| format!("n.{column}"))
+        .collect();
+    let created = match created.len() {
+        0 => "NULL".to_string(),
+        1 => created[0].clone(),
+        _ => format!("COALESCE({})", created.join(", ")),
+    };
+    let data = if with_body { "d.ZDATA" } else { "NULL" };
+    let sql = NOTES_QUERY
+        .replace("{created}", &created)
+        .replace("{data}", data);
+    let stmt = Statement::from_sql_and_values(DbBackend::Sqlite, &sql, [since.into()]);
+    Ok(NoteRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| row.into_note(since, with_body))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use sea_orm::{ConnectionTrait, Value};
+
+    use super::*;
+
+    /// A length-delimited protobuf field.
+    fn message(number: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![(number << 3) | 2, body.len() as u8];
+        out.extend_from_slice(body);
+        out
+    }
+
+    #[tokio::test]
+    async fn reads_recent_notes_with_folders_and_bodies() {
+        let text = message(
+            2,
+            "Design: retry budget\u{fffc}\nCap retries at 3.".as_bytes(),
+        );
+        // A varint field before the text, as real notes have.
+        let note = [vec![0x08, 0x96, 0x01], text].concat();
+        let proto = message(2, &message(3, &note));
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&proto).unwrap();
+        let blob = gz.finish().unwrap();
+
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            "CREATE TABLE ZICCLOUDSYNCINGOBJECT (Z_PK INTEGER PRIMARY KEY, ZTITLE1 TEXT, ZTITLE2 TEXT, ZFOLDER INTEGER, ZCREATIONDATE3 REAL, ZMODIFICATIONDATE1 REAL, ZMARKEDFORDELETION INTEGER, ZISPASSWORDPROTECTED INTEGER, ZNOTEDATA INTEGER)",
+            "CREATE TABLE ZICNOTEDATA (Z_PK INTEGER PRIMARY KEY, ZDATA BLOB)",
+            "INSERT INTO ZICCLOUDSYNCINGOBJECT VALUES
+                (1, NULL, 'Work', NULL, NULL, NULL, 0, 0, NULL),
+                (2, 'Design: retry budget', NULL, 1, 700000100, 700000200, 0, 0, 1),
+                (3, 'Old note', NULL, 1, 500000000, 700000300, 0, 1, NULL),
+                (4, 'Stale', NULL, 1, 500000000, 500000000, 0, 0, NULL),
+                (5, 'Deleted', NULL, 1, 700000000, 700000400, 1, 0, NULL)",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO ZICNOTEDATA VALUES (1, ?)",
+            [Value::from(blob)],
+        ))
+        .await
+        .unwrap();
+
+        let notes = fetch_notes(&db, 600_000_000.0, true).await.unwrap();
+
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].title, "Design: retry budget");
+        assert_eq!(notes[0].folder.as_deref(), Some("Work"));
+        assert!(notes[0].new);
+        assert_eq!(
+            notes[0].body.as_deref(),
+            Some("Design: retry budget\nCap retries at 3.")
+        );
+        assert!(!notes[1].new);
+        assert_eq!(notes[1].body, None);
+        let without_body = fetch_notes(&db, 600_000_000.0, false).await.unwrap();
+        assert_eq!(without_body[0].body, None);
+    }
+}
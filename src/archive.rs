@@ -0,0 +1,378 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use time::Date;
+use time::macros::format_description;
+use tokio::fs;
+use tracing::{debug, info, warn};
+
+use crate::AppResult;
+use crate::context::{FullContext, SourceKind};
+use crate::dirs::DirType;
+use crate::io_utils::shred_file;
+use crate::time_utils;
+
+/// File stem format for archived days, e.g. `2025-01-31`.
+const ARCHIVE_DATE: &[time::format_description::BorrowedFormatItem] =
+    format_description!("[year]-[month]-[day]");
+
+/// One archived summarize run, keyed by the local date it was produced on.
+#[derive(Debug)]
+pub struct ArchiveEntry {
+    pub date: Date,
+    pub context: FullContext,
+}
+
+/// Raw data sources that can be purged from archived runs.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveSource {
+    /// Shell history from atuin
+    Shell,
+    /// Safari, Edge, and Chrome browsing history and URL clusters
+    Safari,
+    /// Git commits, diffs, the tickets found in them, their CI runs, and releases
+    Git,
+    /// Messages.app conversation metadata
+    Messages,
+    /// tmux sessions and window titles
+    Tmux,
+    /// Docker and Podman container activity
+    Containers,
+    /// kubectl/helm activity and the workloads it touched
+    K8s,
+    /// Hosts reached over ssh, scp, rsync, sftp, or mosh
+    Hosts,
+    /// Clipboard manager copy counts
+    Clipboard,
+    /// Notes created or edited in a Markdown vault
+    Notes,
+    /// Apple Notes titles, folders, and any collected bodies
+    AppleNotes,
+    /// Xcode, Gradle, and IntelliJ build results and project names
+    IdeBuilds,
+    /// asciinema recording titles, paths, and lengths
+    Recordings,
+    /// Titles and projects of completed tasks
+    Tasks,
+    /// Slack sent-message counts and channel names
+    Slack,
+    /// Email thread counts and subject lines
+    Email,
+    /// Activities reported by external collectors
+    External,
+}
+
+impl ArchiveSource {
+    /// Context sections holding this source's raw data.
+    fn kinds(self) -> &'static [SourceKind] {
+        match self {
+            ArchiveSource::Shell => &[SourceKind::ShellHistory],
+            ArchiveSource::Safari => &[SourceKind::SafariHistory],
+            ArchiveSource::Git => &[
+                SourceKind::CommitHistory,
+                SourceKind::Tickets,
+                SourceKind::CiRuns,
+                SourceKind::Releases,
+            ],
+            ArchiveSource::Messages => &[SourceKind::Messages],
+            ArchiveSource::Tmux => &[SourceKind::Tmux],
+            ArchiveSource::Containers => &[SourceKind::Containers],
+            ArchiveSource::K8s => &[SourceKind::K8s],
+            ArchiveSource::Hosts => &[SourceKind::Hosts],
+            ArchiveSource::Clipboard => &[SourceKind::Clipboard],
+            ArchiveSource::Notes => &[SourceKind::VaultNotes],
+            ArchiveSource::AppleNotes => &[SourceKind::AppleNotes],
+            ArchiveSource::IdeBuilds => &[SourceKind::IdeBuilds],
+            ArchiveSource::Recordings => &[SourceKind::Recordings],
+            ArchiveSource::Tasks => &[SourceKind::CompletedTasks],
+            ArchiveSource::Slack => &[SourceKind::Slack],
+            ArchiveSource::Email => &[SourceKind::Email],
+            ArchiveSource::External => &[SourceKind::External],
+        }
+    }
+
+    /// Drop this source's raw data from `context`. Returns whether anything was removed.
+    fn clear(self, context: &mut FullContext) -> bool {
+        let mut removed = false;
+        for kind in self.kinds() {
+            removed |= context.context.remove(*kind).is_some();
+        }
+        removed
+    }
+}
+
+/// What to remove from archived runs older than a cutoff date.
+#[derive(Debug, Clone)]
+pub struct PurgeOptions {
+    /// Runs dated strictly before this day are purged.
+    pub before: Date,
+    /// Sources to remove. Empty means every source.
+    pub sources: Vec<ArchiveSource>,
+    /// Also remove the generated summaries, deleting whole runs when every source is purged.
+    pub drop_summaries: bool,
+    /// Overwrite removed data on disk before deleting it.
+    pub paranoid: bool,
+}
+
+impl PurgeOptions {
+    /// Sources to remove, with every source when none were chosen.
+    fn sources(&self) -> Vec<ArchiveSource> {
+        if self.sources.is_empty() {
+            ArchiveSource::value_variants().to_vec()
+        } else {
+            self.sources.clone()
+        }
+    }
+
+    /// Whether whole runs are deleted rather than scrubbed.
+    pub fn removes_runs(&self) -> bool {
+        let sources = self.sources();
+        self.drop_summaries
+            && ArchiveSource::value_variants()
+                .iter()
+                .all(|source| sources.contains(source))
+    }
+
+    /// Remove the chosen sources, and summary if asked, from `context`. Returns whether anything
+    /// was removed.
+    pub fn scrub(&self, context: &mut FullContext) -> bool {
+        let mut changed = false;
+        for source in self.sources() {
+            changed |= source.clear(context);
+        }
+        if self.drop_summaries {
+            changed |= context.summary.take().is_some();
+        }
+        changed
+    }
+}
+
+/// Counts reported after a purge.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    /// Runs whose raw data was removed but which were kept on disk.
+    pub scrubbed: usize,
+    /// Runs deleted entirely.
+    pub removed: usize,
+}
+
+/// Directory holding one JSON file per archived day.
+pub fn archive_dir() -> AppResult<PathBuf> {
+    Ok(DirType::Data.get_dir()?.join("archive"))
+}
+
+/// Path of the archive file for `date` inside `dir`.
+fn entry_path(dir: &Path, date: Date) -> AppResult<PathBuf> {
+    let stem = date.format(ARCHIVE_DATE)?;
+    Ok(dir.join(format!("{stem}.json")))
+}
+
+/// Save a run under today's local date, replacing any earlier run from the same day.
+#[tracing::instrument(name = "Archiving run", level = "info", skip(context))]
+pub async fn save(context: &FullContext) -> AppResult<PathBuf> {
+    let today = time_utils::now_local().date();
+    save_in(&archive_dir()?, today, context).await
+}
+
+/// Save a run for `date` into `dir`.
+pub async fn save_in(dir: &Path, date: Date, context: &FullContext) -> AppResult<PathBuf> {
+    fs::create_dir_all(dir).await?;
+    let path = entry_path(dir, date)?;
+    fs::write(&path, serde_json::to_string_pretty(context)?).await?;
+    info!("Archived run to {}", path.display());
+    Ok(path)
+}
+
+/// Load every archived day, oldest first.
+#[tracing::instrument(name = "Loading archive", level = "info")]
+pub async fn load_all() -> AppResult<Vec<ArchiveEntry>> {
+    load_all_in(&archive_dir()?).await
+}
+
+/// Load every archived day in `dir`, oldest first. Unreadable files are skipped with a warning.
+pub async fn load_all_in(dir: &Path) -> AppResult<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    if !fs::try_exists(dir).await? {
+        debug!("No archive at {}", dir.display());
+        return Ok(entries);
+    }
+    let mut read_dir = fs::read_dir(dir).await?;
+    while let Some(file) = read_dir.next_entry().await? {
+        let path = file.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(date) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| Date::parse(stem, ARCHIVE_DATE).ok())
+        else {
+            debug!("Skipping non-archive file {}", path.display());
+            continue;
+        };
+        let data = fs::read_to_string(&path).await?;
+        match FullContext::from_json(&data) {
+            Ok(context) => entries.push(ArchiveEntry { date, context }),
+            Err(e) => warn!("Skipping unreadable archive file {}: {}", path.display(), e),
+        }
+    }
+    entries.sort_by_key(|entry| entry.date);
+    Ok(entries)
+}
+
+/// Purge raw data older than `options.before` from the archive.
+#[tracing::instrument(name = "Purging archive", level = "info")]
+pub async fn purge(options: &PurgeOptions) -> AppResult<PurgeReport> {
+    purge_in(&archive_dir()?, options).await
+}
+
+/// Purge raw data older than `options.before` from the archive in `dir`.
+pub async fn purge_in(dir: &Path, options: &PurgeOptions) -> AppResult<PurgeReport> {
+    let mut report = PurgeReport::default();
+    for mut entry in load_all_in(dir)
+        .await?
+        .into_iter()
+        .filter(|entry| entry.date < options.before)
+    {
+        let path = entry_path(dir, entry.date)?;
+        if options.removes_runs() {
+            remove(&path, options.paranoid).await?;
+            report.removed += 1;
+            continue;
+        }
+
+        if options.scrub(&mut entry.context) {
+            // Shredding first keeps the old contents from surviving the rewrite in place.
+            if options.paranoid {
+                shred_file(&path).await?;
+            }
+            fs::write(&path, serde_json::to_string_pretty(&entry.context)?).await?;
+            report.scrubbed += 1;
+        }
+    }
+    info!(
+        "Purged {} archived run(s) and removed {} more",
+        report.scrubbed, report.removed
+    );
+    Ok(report)
+}
+
+/// Delete an archive file, shredding it first in paranoid mode.
+async fn remove(path: &Path, paranoid: bool) -> AppResult<()> {
+    if paranoid {
+        shred_file(path).await
+    } else {
+        Ok(fs::remove_file(path).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+    use time::macros::date;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        std::env::temp_dir().join(format!("{name}_{nonce}"))
+    }
+
+    #[tokio::test]
+    async fn round_trips_entries_in_date_order() {
+        let dir = temp_dir("archive_round_trip");
+        save_in(&dir, date!(2025 - 02 - 01), &FullContext::default())
+            .await
+            .unwrap();
+        save_in(&dir, date!(2025 - 01 - 31), &FullContext::default())
+            .await
+            .unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").await.unwrap();
+
+        let entries = load_all_in(&dir).await.unwrap();
+
+        let dates: Vec<Date> = entries.iter().map(|e| e.date).collect();
+        assert_eq!(dates, vec![date!(2025 - 01 - 31), date!(2025 - 02 - 01)]);
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
+    /// A run in the version 1 layout, which loading migrates.
+    fn sample_run() -> FullContext {
+        FullContext::from_json(
+            &serde_json::json!({
+            "shell_history": [],
+            "safari_history": [{"label": "Docs", "urls": []}],
+            "commit_history": [],
+            "summary": {"summary": "Wrote docs."}
+            })
+            .to_string(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn purge_keeps_summaries_and_newer_runs() {
+        let dir = temp_dir("archive_purge");
+        save_in(&dir, date!(2025 - 01 - 01), &sample_run())
+            .await
+            .unwrap();
+        save_in(&dir, date!(2025 - 02 - 01), &sample_run())
+            .await
+            .unwrap();
+
+        let report = purge_in(
+            &dir,
+            &PurgeOptions {
+                before: date!(2025 - 02 - 01),
+                sources: vec![ArchiveSource::Safari],
+                drop_summaries: false,
+                paranoid: true,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            report,
+            PurgeReport {
+                scrubbed: 1,
+                removed: 0
+            }
+        );
+        let entries = load_all_in(&dir).await.unwrap();
+        assert!(entries[0].context.context.safari_history().is_empty());
+        assert!(entries[0].context.summary.is_some());
+        assert_eq!(entries[1].context.context.safari_history().len(), 1);
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
+    #[tokio::test]
+    async fn purge_everything_removes_runs() {
+        let dir = temp_dir("archive_purge_all");
+        save_in(&dir, date!(2025 - 01 - 01), &sample_run())
+            .await
+            .unwrap();
+
+        let report = purge_in(
+            &dir,
+            &PurgeOptions {
+                before: date!(2025 - 01 - 02),
+                sources: vec![],
+                drop_summaries: true,
+                paranoid: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.removed, 1);
+        assert!(load_all_in(&dir).await.unwrap().is_empty());
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
+    #[tokio::test]
+    async fn missing_archive_is_empty() {
+        let entries = load_all_in(&temp_dir("archive_missing")).await.unwrap();
+        assert!(entries.is_empty());
+    }
+}
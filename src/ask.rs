@@ -0,0 +1,233 @@
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use time::macros::format_description;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::{error, info};
+
+use crate::ai::ask::{AskOptions, AskQuery, Conversation};
+use crate::context::{Context, FullContext};
+use crate::history;
+use crate::render::terminal;
+use crate::{AppResult, io_utils, time_utils};
+
+const CHAT_HELP: &str = "Ask anything about the run, or use one of these commands:
+
+- `/repos`: list the repositories worked in
+- `/urls`: list the pages visited
+- `/export [FILE]`: save the conversation as Markdown
+- `/help`: show this list
+- `/quit`: leave the chat
+";
+
+/// The stored run `run`, a run id or a date, or today's latest run.
+async fn load_run(run: Option<&str>) -> AppResult<(String, FullContext)> {
+    let today = time_utils::now_local()
+        .date()
+        .format(format_description!("[year]-[month]-[day]"))?;
+    let run = run.unwrap_or(&today).to_string();
+    let stored = history::load(&run).await?;
+    Ok((run, stored))
+}
+
+/// Answer `question` from the stored run `run`, a run id or a date, or today's latest run, and
+/// write the answer to `output` or show it.
+#[tracing::instrument(name = "Answering a question", level = "info", skip(client))]
+pub async fn ask<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    question: &str,
+    run: Option<&str>,
+    options: AskOptions<'_>,
+    output: Option<&Path>,
+) -> AppResult<()> {
+    let (run, stored) = load_run(run).await?;
+    info!("Answering from the run of {run}");
+    let answer = Conversation::new(&stored.context, stored.summary.as_ref(), options)
+        .ask(client, model, question)
+        .await?;
+    io_utils::output_markdown(output, &answer.to_markdown()).await
+}
+
+/// A line typed into the chat.
+#[derive(Debug, PartialEq, Eq)]
+enum ChatLine<'a> {
+    Question(&'a str),
+    Repos,
+    Urls,
+    Export(Option<&'a str>),
+    Help,
+    Quit,
+    Unknown(&'a str),
+}
+
+/// What `line` asks for, or `None` when it is blank.
+fn parse_line(line: &str) -> Option<ChatLine<'_>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let Some(command) = line.strip_prefix('/') else {
+        return Some(ChatLine::Question(line));
+    };
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, Some(argument.trim()).filter(|a| !a.is_empty())),
+        None => (command, None),
+    };
+    Some(match name {
+        "repos" => ChatLine::Repos,
+        "urls" => ChatLine::Urls,
+        "export" => ChatLine::Export(argument),
+        "help" | "?" => ChatLine::Help,
+        "quit" | "exit" | "q" => ChatLine::Quit,
+        _ => ChatLine::Unknown(name),
+    })
+}
+
+/// The repositories of `context`, with their commit counts, as a Markdown list.
+fn list_repos(context: &Context) -> String {
+    let mut out = String::new();
+    for repo in context.commit_history() {
+        let _ = writeln!(
+            out,
+            "- {} ({} commits)",
+            repo.project_name(),
+            repo.commits.len()
+        );
+    }
+    if out.is_empty() {
+        out.push_str("No repositories were collected in this run.\n");
+    }
+    out
+}
+
+/// The pages of `context`, grouped as when summarizing, as a Markdown list.
+fn list_urls(context: &Context) -> String {
+    let mut out = String::new();
+    for cluster in context.safari_history() {
+        let _ = writeln!(out, "- {}", cluster.label);
+        for page in &cluster.urls {
+            match &page.title {
+                Some(title) => {
+                    let _ = writeln!(out, "  - {title}: {}", page.url);
+                }
+                None => {
+                    let _ = writeln!(out, "  - {}", page.url);
+                }
+            }
+        }
+    }
+    if out.is_empty() {
+        out.push_str("No browser history was collected in this run.\n");
+    }
+    out
+}
+
+/// The conversation so far as a Markdown document.
+fn transcript(run: &str, exchanges: &[(String, AskQuery)]) -> String {
+    let mut out = format!("# Questions about {run}\n");
+    for (question, answer) in exchanges {
+        let _ = write!(out, "\n## {question}\n\n{}", answer.to_markdown());
+    }
+    out
+}
+
+/// Show `markdown` in the chat: rendered on a terminal, plain otherwise.
+fn reply(markdown: &str) -> AppResult<()> {
+    if std::io::stdout().is_terminal() {
+        terminal::print(terminal::to_terminal(markdown))
+    } else {
+        terminal::print(format!("{markdown}\n"))
+    }
+}
+
+/// Write the chat prompt. It goes to stderr, so a transcript piped from stdout holds only the
+/// answers.
+fn prompt() -> AppResult<()> {
+    let mut stderr = std::io::stderr().lock();
+    stderr.write_all(b"> ")?;
+    stderr.flush()?;
+    Ok(())
+}
+
+/// Talk with the model about the stored run `run`, a run id or a date, or today's latest run.
+///
+/// Each question continues the conversation, so follow-ups can refer to earlier answers. Lines
+/// starting with `/` are commands, listed by `/help`.
+#[tracing::instrument(name = "Chatting about a run", level = "info", skip(client))]
+pub async fn chat<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    run: Option<&str>,
+    options: AskOptions<'_>,
+) -> AppResult<()> {
+    let (run, stored) = load_run(run).await?;
+    info!("Chatting about the run of {run}; /help lists the commands");
+    let mut conversation = Conversation::new(&stored.context, stored.summary.as_ref(), options);
+    let mut exchanges: Vec<(String, AskQuery)> = Vec::new();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        prompt()?;
+        let Some(line) = lines.next_line().await? else {
+            return Ok(());
+        };
+        match parse_line(&line) {
+            None => {}
+            Some(ChatLine::Question(question)) => {
+                match conversation.ask(client, model, question).await {
+                    Ok(answer) => {
+                        reply(&answer.to_markdown())?;
+                        exchanges.push((question.to_string(), answer));
+                    }
+                    // The conversation goes on; the question can be asked again.
+                    Err(e) => error!("{e}"),
+                }
+            }
+            Some(ChatLine::Repos) => reply(&list_repos(&stored.context))?,
+            Some(ChatLine::Urls) => reply(&list_urls(&stored.context))?,
+            Some(ChatLine::Export(path)) => {
+                let path = path.map_or_else(|| format!("chat-{run}.md"), str::to_string);
+                let transcript = transcript(&run, &exchanges);
+                if let Err(e) = io_utils::output_file(
+                    Path::new(&path),
+                    &io_utils::OutputVars::now(),
+                    transcript,
+                )
+                .await
+                {
+                    error!("{e}");
+                }
+            }
+            Some(ChatLine::Help) => reply(CHAT_HELP)?,
+            Some(ChatLine::Quit) => return Ok(()),
+            Some(ChatLine::Unknown(name)) => {
+                reply(&format!("Unknown command /{name}.\n\n{CHAT_HELP}"))?
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_questions_and_slash_commands() {
+        assert_eq!(parse_line("  \n"), None);
+        assert_eq!(
+            parse_line("what broke in CI?"),
+            Some(ChatLine::Question("what broke in CI?"))
+        );
+        assert_eq!(parse_line("/repos"), Some(ChatLine::Repos));
+        assert_eq!(parse_line("/export"), Some(ChatLine::Export(None)));
+        assert_eq!(
+            parse_line("/export  notes/today.md "),
+            Some(ChatLine::Export(Some("notes/today.md")))
+        );
+        assert_eq!(parse_line("/exit"), Some(ChatLine::Quit));
+        assert_eq!(parse_line("/commits"), Some(ChatLine::Unknown("commits")));
+    }
+}
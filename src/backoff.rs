@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+/// Waits between retries of something that failed in passing: a request to the language model,
+/// a webhook delivery, or a read of a locked database.
+///
+/// Each retry waits twice as long as the one before, so a server or database that is busy gets
+/// longer to recover the longer it stays busy, without the first retry waiting long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    /// Wait `initial` before the first retry, and never longer than `max`.
+    pub const fn new(initial: Duration, max: Duration) -> Self {
+        Backoff { initial, max }
+    }
+
+    /// How long to wait before retry number `attempt`, counting from 1.
+    pub fn wait(self, attempt: u32) -> Duration {
+        self.initial
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_up_to_the_cap() {
+        let backoff = Backoff::new(Duration::from_millis(250), Duration::from_secs(1));
+        assert_eq!(backoff.wait(0), Duration::from_millis(250));
+        assert_eq!(backoff.wait(1), Duration::from_millis(250));
+        assert_eq!(backoff.wait(2), Duration::from_millis(500));
+        assert_eq!(backoff.wait(3), Duration::from_secs(1));
+        assert_eq!(backoff.wait(4), Duration::from_secs(1));
+        assert_eq!(backoff.wait(u32::MAX), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,207 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+use time::Duration;
+use tokio::fs;
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::dirs::DirType;
+use crate::io_utils::shred_file;
+use crate::safari::{SafariHistoryItem, is_sign_in_url};
+use crate::time_utils::{webkit_past_ts, webkit_to_datetime};
+
+/// Pages visited in the window, newest first. Chromium stores `visit_time` in microseconds since
+/// 1601-01-01 UTC.
+const HISTORY_QUERY: &str = "
+SELECT
+    u.url AS url,
+    u.title AS title,
+    u.visit_count AS visit_count,
+    MAX(v.visit_time) AS last_visit
+FROM visits v
+JOIN urls u ON u.id = v.url
+WHERE v.visit_time > ?
+GROUP BY u.id
+ORDER BY last_visit DESC";
+
+/// Raw row returned by `HISTORY_QUERY`.
+#[derive(Debug, FromQueryResult)]
+struct HistoryRow {
+    url: String,
+    title: Option<String>,
+    visit_count: i64,
+    last_visit: i64,
+}
+
+impl From<HistoryRow> for SafariHistoryItem {
+    fn from(row: HistoryRow) -> Self {
+        SafariHistoryItem {
+            url: row.url,
+            title: row.title.filter(|title| !title.is_empty()),
+            visit_count: row.visit_count,
+            last_visited: webkit_to_datetime(row.last_visit),
+        }
+    }
+}
+
+/// A Chromium-based browser whose history database is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChromiumBrowser {
+    Edge,
+    Chrome,
+}
+
+impl ChromiumBrowser {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChromiumBrowser::Edge => "Edge",
+            ChromiumBrowser::Chrome => "Chrome",
+        }
+    }
+
+    /// Environment variable pointing at the `History` database, for other profiles or builds.
+    fn path_variable(self) -> &'static str {
+        match self {
+            ChromiumBrowser::Edge => "EDGE_HISTORY_DB_PATH",
+            ChromiumBrowser::Chrome => "CHROME_HISTORY_DB_PATH",
+        }
+    }
+
+    /// The browser's user data directory: under `%LOCALAPPDATA%` on Windows, `Application Support`
+    /// on macOS, and the XDG config directory elsewhere.
+    fn user_data_dir(self) -> Option<PathBuf> {
+        if cfg!(windows) {
+            let local = PathBuf::from(env::var_os("LOCALAPPDATA")?);
+            return Some(match self {
+                ChromiumBrowser::Edge => local.join("Microsoft").join("Edge").join("User Data"),
+                ChromiumBrowser::Chrome => local.join("Google").join("Chrome").join("User Data"),
+            });
+        }
+        let home = env::home_dir()?;
+        if cfg!(target_os = "macos") {
+            let support = home.join("Library/Application Support");
+            return Some(match self {
+                ChromiumBrowser::Edge => support.join("Microsoft Edge"),
+                ChromiumBrowser::Chrome => support.join("Google/Chrome"),
+            });
+        }
+        let config = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+        Some(match self {
+            ChromiumBrowser::Edge => config.join("microsoft-edge"),
+            ChromiumBrowser::Chrome => config.join("google-chrome"),
+        })
+    }
+
+    /// The default profile's `History` database, or `None` when the browser is not installed.
+    #[tracing::instrument(name = "Searching for a browser history database", level = "info")]
+    fn history_db_path(self) -> Option<PathBuf> {
+        let path = match env::var_os(self.path_variable()) {
+            Some(path) => PathBuf::from(path),
+            None => self.user_data_dir()?.join("Default").join("History"),
+        };
+        path.is_file().then_some(path)
+    }
+}
+
+/// Open a copy of a browser history database read-only.
+#[tracing::instrument(name = "Connecting to a browser history database", level = "info")]
+async fn connect_to_db(db_path: &Path) -> AppResult<DatabaseConnection> {
+    let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=ro", db_path.display()));
+    opt.sqlx_logging(false);
+    trace!("Connecting to browser history database");
+    Ok(Database::connect(opt).await?)
+}
+
+/// Run `HISTORY_QUERY` for visits newer than `since` (Chromium microseconds).
+async fn fetch_history(db: &DatabaseConnection, since: i64) -> AppResult<Vec<SafariHistoryItem>> {
+    let stmt = Statement::from_sql_and_values(DbBackend::Sqlite, HISTORY_QUERY, [since.into()]);
+    Ok(HistoryRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(SafariHistoryItem::from)
+        .filter(|item| !is_sign_in_url(&item.url))
+        .collect())
+}
+
+/// Fetch the pages visited in `browser` during the window, or nothing when it is not installed.
+///
+/// The browser keeps its database locked while it runs (and on Windows, exclusively), so a copy
+/// in the cache directory is read and shredded afterwards.
+#[tracing::instrument(name = "Fetching browser history", level = "info")]
+pub async fn get_chromium_history(
+    browser: ChromiumBrowser,
+    duration: &Duration,
+) -> AppResult<Vec<SafariHistoryItem>> {
+    let Some(db_path) = browser.history_db_path() else {
+        debug!("No {} history database found", browser.name());
+        return Ok(Vec::new());
+    };
+    let copy = DirType::Cache
+        .ensure_dir_async()
+        .await?
+        .join(format!("{}-history.sqlite", browser.name().to_lowercase()));
+    fs::copy(&db_path, &copy).await?;
+    let history = match connect_to_db(&copy).await {
+        Ok(db) => {
+            let history = fetch_history(&db, webkit_past_ts(duration)).await;
+            let _ = db.close().await;
+            history
+        }
+        Err(e) => Err(e),
+    };
+    shred_file(&copy).await?;
+    let history = history?;
+    debug!("Fetched {} {} history items", history.len(), browser.name());
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::time_utils::datetime_to_webkit_time;
+
+    #[tokio::test]
+    async fn reads_recent_visits_and_skips_sign_in_pages() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let at = |dt| datetime_to_webkit_time(&dt);
+        for sql in [
+            "CREATE TABLE urls (id INTEGER PRIMARY KEY, url TEXT, title TEXT, visit_count INTEGER)"
+                .to_string(),
+            "CREATE TABLE visits (id INTEGER PRIMARY KEY, url INTEGER, visit_time INTEGER)"
+                .to_string(),
+            "INSERT INTO urls VALUES
+                (1, 'https://docs.rs/sea-orm', 'sea_orm - Rust', 3),
+                (2, 'https://login.example.com/sso', 'Sign in', 1),
+                (3, 'https://old.example.com/', 'Old', 1)"
+                .to_string(),
+            format!(
+                "INSERT INTO visits VALUES (1, 1, {}), (2, 1, {}), (3, 2, {}), (4, 3, {})",
+                at(datetime!(2025-01-01 09:00 UTC)),
+                at(datetime!(2025-01-01 10:30 UTC)),
+                at(datetime!(2025-01-01 09:05 UTC)),
+                at(datetime!(2024-12-01 09:00 UTC)),
+            ),
+        ] {
+            db.execute_unprepared(&sql).await.unwrap();
+        }
+
+        let history = fetch_history(&db, at(datetime!(2025-01-01 00:00 UTC)))
+            .await
+            .unwrap();
+
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].url, "https://docs.rs/sea-orm");
+        assert_eq!(history[0].visit_count, 3);
+        assert_eq!(history[0].last_visited, datetime!(2025-01-01 10:30 UTC));
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::error::AppError;
+use crate::git::GitRepoHistory;
+use crate::git::remote;
+use crate::http;
+use crate::time_utils::past_ts;
+use crate::warnings::{self, WarningKind};
+
+/// Runs per page of the workflow runs API; GitHub's maximum.
+const PAGE_SIZE: &str = "100";
+
+/// Pages read at most per repository.
+const MAX_PAGES: u64 = 5;
+
+/// Outcome of a CI workflow run.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CiStatus {
+    Passed,
+    Failed,
+    /// Queued, waiting, or running.
+    InProgress,
+    Cancelled,
+    Skipped,
+}
+
+impl CiStatus {
+    /// Map a GitHub Actions run's `status` and `conclusion`.
+    fn from_github(status: &str, conclusion: Option<&str>) -> Self {
+        if status != "completed" {
+            return CiStatus::InProgress;
+        }
+        match conclusion.unwrap_or_default() {
+            "success" | "neutral" => CiStatus::Passed,
+            "cancelled" | "stale" => CiStatus::Cancelled,
+            "skipped" => CiStatus::Skipped,
+            _ => CiStatus::Failed,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            CiStatus::Passed => "passed",
+            CiStatus::Failed => "failed",
+            CiStatus::InProgress => "is still running",
+            CiStatus::Cancelled => "was cancelled",
+            CiStatus::Skipped => "was skipped",
+        }
+    }
+}
+
+/// The latest run of one CI workflow on one branch for commits made during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CiRun {
+    pub repo: PathBuf,
+    /// `owner/repo` on GitHub.
+    pub project: String,
+    pub workflow: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub branch: Option<String>,
+    /// Abbreviated id of the commit the run tested.
+    pub commit: String,
+    pub status: CiStatus,
+    pub url: String,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub updated: OffsetDateTime,
+}
+
+impl CiRun {
+    /// One-line description, e.g. `annie444/daily-ai: CI on main (1a2b3c4) failed`.
+    pub fn describe(&self) -> String {
+        let branch = self
+            .branch
+            .as_deref()
+            .map(|branch| format!(" on {branch}"))
+            .unwrap_or_default();
+        format!(
+            "{}: {}{branch} ({}) {}",
+            self.project,
+            self.workflow,
+            self.commit,
+            self.status.describe()
+        )
+    }
+}
+
+/// GitHub API root for repositories hosted on `host`: github.com, or the GitHub Enterprise
+/// Server named by `GH_HOST`.
+fn api_root(host: &str) -> Option<String> {
+    if host == "github.com" {
+        return Some("https://api.github.com".into());
+    }
+    env::var("GH_HOST")
+        .ok()
+        .filter(|gh_host| gh_host == host)
+        .map(|gh_host| format!("https://{gh_host}/api/v3"))
+}
+
+/// Latest run per workflow and branch among `runs` that tested one of `commits`, oldest first.
+fn latest_runs(
+    runs: &[Value],
+    commits: &HashSet<&str>,
+    repo: &GitRepoHistory,
+    project: &str,
+) -> Vec<CiRun> {
+    let mut latest: BTreeMap<(String, Option<String>), CiRun> = BTreeMap::new();
+    for run in runs {
+        let Some(sha) = run["head_sha"].as_str().filter(|sha| commits.contains(sha)) else {
+            continue;
+        };
+        let Some(updated) = run["updated_at"]
+            .as_str()
+            .and_then(|time| OffsetDateTime::parse(time, &Rfc3339).ok())
+        else {
+            continue;
+        };
+        let workflow = run["name"].as_str().unwrap_or("workflow").to_string();
+        let branch = run["head_branch"].as_str().map(str::to_string);
+        let ci_run = CiRun {
+            repo: repo.diff.repo_path.clone(),
+            project: project.to_string(),
+            workflow: workflow.clone(),
+            branch: branch.clone(),
+            commit: sha.chars().take(7).collect(),
+            status: CiStatus::from_github(
+                run["status"].as_str().unwrap_or_default(),
+                run["conclusion"].as_str(),
+            ),
+            url: run["html_url"].as_str().unwrap_or_default().to_string(),
+            updated,
+        };
+        match latest.get(&(workflow.clone(), branch.clone())) {
+            Some(newer) if newer.updated >= updated => {}
+            _ => {
+                latest.insert((workflow, branch), ci_run);
+            }
+        }
+    }
+    let mut runs: Vec<CiRun> = latest.into_values().collect();
+    runs.sort_by_key(|run| run.updated);
+    runs
+}
+
+/// GitHub Actions runs created in `project` since `since`.
+async fn fetch_runs(
+    client: &reqwest::Client,
+    api_root: &str,
+    project: &str,
+    token: Option<&str>,
+    since: OffsetDateTime,
+) -> AppResult<Vec<Value>> {
+    let url = format!("{api_root}/repos/{project}/actions/runs");
+    let created = format!(">={}", since.format(&Rfc3339)?);
+    let mut runs = Vec::new();
+    for page in 1..=MAX_PAGES {
+        let mut request = client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .query(&[
+                ("created", created.as_str()),
+                ("per_page", PAGE_SIZE),
+                ("page", &page.to_string()),
+            ]);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            return Err(AppError::Other(format!(
+                "GitHub returned {} for the workflow runs of {project}",
+                resp.status()
+            )));
+        }
+        let mut data: Value = serde_json::from_str(&resp.text().await?)?;
+        let total = data["total_count"].as_u64().unwrap_or_default();
+        if let Value::Array(found) = data["workflow_runs"].take() {
+            trace!("{project}: {} workflow runs on page {page}", found.len());
+            runs.extend(found);
+        }
+        if runs.len() as u64 >= total {
+            break;
+        }
+    }
+    Ok(runs)
+}
+
+/// Look up the GitHub Actions runs for the commits made during the window.
+///
+/// Only repositories hosted on GitHub are checked. A token from `GITHUB_TOKEN` or `GH_TOKEN` is
+/// needed for private repositories and raises the rate limit; public repositories are read
+/// without one. Commits that were never pushed have no runs and are left out.
+#[tracing::instrument(name = "Collecting CI status", level = "info", skip(commit_history))]
+pub async fn get_ci_runs(
+    commit_history: &[GitRepoHistory],
+    duration: &Duration,
+) -> AppResult<Vec<CiRun>> {
+    let token = ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|token| !token.is_empty()));
+    let since = past_ts(duration);
+    let gh_host = env::var("GH_HOST").ok();
+    let hosts: Vec<&str> = ["api.github.com"]
+        .into_iter()
+        .chain(gh_host.as_deref())
+        .collect();
+    let client = http::client(&hosts)?;
+    let mut ci_runs = Vec::new();
+    for repo in commit_history
+        .iter()
+        .filter(|repo| !repo.commits.is_empty())
+    {
+        let Some(web_url) = repo.web_url() else {
+            continue;
+        };
+        let host = web_url
+            .trim_start_matches("https://")
+            .split('/')
+            .next()
+            .unwrap_or_default();
+        let (Some(api_root), Some(project)) = (api_root(host), remote::project_name(&web_url))
+        else {
+            debug!("{web_url} is not hosted on GitHub; skipping CI status");
+            continue;
+        };
+        let commits: HashSet<&str> = repo.commits.iter().map(|c| c.id.as_str()).collect();
+        match fetch_runs(&client, &api_root, project, token.as_deref(), since).await {
+            Ok(runs) => {
+                let found = latest_runs(&runs, &commits, repo, project);
+                debug!(
+                    "{project}: {} workflows ran on the window's commits",
+                    found.len()
+                );
+                ci_runs.extend(found);
+            }
+            Err(e) => warnings::record(
+                WarningKind::Skipped,
+                "ci",
+                format!("Unable to read the CI status of {project}: {e}"),
+            ),
+        }
+    }
+    Ok(ci_runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::git::diff::DiffSummary;
+
+    #[test]
+    fn keeps_the_latest_run_per_workflow_and_branch() {
+        let repo = GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from("/src/daily-ai"),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: Vec::new(),
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits: Vec::new(),
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        };
+        let run = |sha: &str, status: &str, conclusion: Option<&str>, updated: &str| {
+            json!({
+                "name": "CI",
+                "head_branch": "main",
+                "head_sha": sha,
+                "status": status,
+                "conclusion": conclusion,
+                "html_url": format!("https://github.com/annie444/daily-ai/actions/runs/{updated}"),
+                "updated_at": updated,
+            })
+        };
+        let runs = [
+            run(
+                "aaaaaaa111",
+                "completed",
+                Some("success"),
+                "2025-01-31T10:00:00Z",
+            ),
+            run(
+                "bbbbbbb222",
+                "completed",
+                Some("failure"),
+                "2025-01-31T16:00:00Z",
+            ),
+            run("ccccccc333", "in_progress", None, "2025-01-31T17:00:00Z"),
+            json!({
+                "name": "Release", "head_branch": "v1.2.0", "head_sha": "aaaaaaa111",
+                "status": "completed", "conclusion": "skipped",
+                "html_url": "", "updated_at": "2025-01-31T10:05:00Z"
+            }),
+        ];
+        let commits = HashSet::from(["aaaaaaa111", "bbbbbbb222"]);
+
+        let latest = latest_runs(&runs, &commits, &repo, "annie444/daily-ai");
+
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].status, CiStatus::Skipped);
+        assert_eq!(latest[1].status, CiStatus::Failed);
+        assert_eq!(
+            latest[1].describe(),
+            "annie444/daily-ai: CI on main (bbbbbbb) failed"
+        );
+    }
+}
@@ -13,10 +13,65 @@ use tracing::{debug, info_span, warn};
 use tracing_indicatif::span_ext::IndicatifSpanExt;
 use tracing_indicatif::style::ProgressStyle;
 
+use super::embed::Embedder;
 use crate::AppResult;
 use crate::dirs::DirType;
 use crate::error::AppError;
+use crate::http;
+use crate::io_utils::shred_file;
 use crate::safari::SafariHistoryItem;
+use crate::warnings::{self, WarningKind};
+
+/// Shred every cached URL embedding. Downloaded model files are left alone.
+///
+/// Cache entries are keyed by a hash of the URL text, so they cannot be matched to a date range;
+/// the cache is rebuilt on the next run.
+#[tracing::instrument(name = "Shredding cached embeddings", level = "info")]
+pub async fn shred_embedding_cache() -> AppResult<usize> {
+    let cache_dir = DirType::Cache.get_dir()?;
+    if !tokio::fs::try_exists(&cache_dir).await? {
+        return Ok(0);
+    }
+    let mut shredded = 0;
+    let mut entries = tokio::fs::read_dir(&cache_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_file() && path.extension().is_some_and(|ext| ext == "bin") {
+            shred_file(&path).await?;
+            shredded += 1;
+        }
+    }
+    debug!("Shredded {shredded} cached embeddings");
+    Ok(shredded)
+}
+
+/// Files downloaded for each Hugging Face model.
+const MODEL_FILES: [&str; 3] = ["config.json", "model.safetensors", "tokenizer.json"];
+
+/// Where a Hugging Face model is cached under `cache_dir`.
+fn model_dir(cache_dir: &Path, model_name: &str) -> PathBuf {
+    cache_dir
+        .join("huggingface")
+        .join("transformers")
+        .join(model_name.replace('/', "_"))
+}
+
+/// The cached directory for `model_name`, or an error naming the first file that has not been
+/// downloaded yet.
+pub fn cached_model_dir(model_name: &str) -> AppResult<PathBuf> {
+    let model_dir = model_dir(&DirType::Cache.get_dir()?, model_name);
+    for file in MODEL_FILES {
+        let path = model_dir.join(file);
+        if !path.is_file() {
+            return Err(AppError::Config(format!(
+                "--offline needs the {model_name} embedding model, but {} is missing; run once \
+                 without --offline to download it",
+                path.display()
+            )));
+        }
+    }
+    Ok(model_dir)
+}
 
 /// Wrapper around a BERT encoder for URL/title embeddings.
 #[derive(Clone)]
@@ -25,6 +80,8 @@ pub struct BertEmbedder {
     model: Arc<BertModel>,
     tokenizer: Arc<Tokenizer>,
     cache_dir: PathBuf,
+    /// Texts run through the model in one forward pass.
+    batch_size: usize,
 }
 
 impl BertEmbedder {
@@ -43,17 +100,24 @@ impl BertEmbedder {
         }
     }
 
+    /// Load a model from the Hugging Face cache, downloading any missing files first.
+    ///
+    /// With `offline`, nothing is downloaded and a missing file is an error. Texts are embedded
+    /// `batch_size` at a time.
     #[tracing::instrument(name = "Downloading embedding model from Hugging Face", level = "info")]
     pub async fn new_from_pretrained<S: AsRef<str> + std::fmt::Debug>(
         model_name: S,
+        offline: bool,
+        batch_size: usize,
     ) -> AppResult<Self> {
-        let hf_cache_dir = DirType::Cache
-            .ensure_dir_async()
-            .await?
-            .join("huggingface")
-            .join("transformers");
+        if offline {
+            return Self::new_from_dir(cached_model_dir(model_name.as_ref())?, batch_size);
+        }
 
-        let model_dir = hf_cache_dir.join(model_name.as_ref().replace('/', "_"));
+        let model_dir = model_dir(
+            &DirType::Cache.ensure_dir_async().await?,
+            model_name.as_ref(),
+        );
 
         if !model_dir.exists() {
             tokio::fs::create_dir_all(&model_dir).await?;
@@ -65,18 +129,16 @@ impl BertEmbedder {
         );
 
         // Minimal fetcher for the few files we need; retries and progress for better UX.
-        let client = reqwest::ClientBuilder::new()
-            .user_agent(format!("daily-ai/{}", env!("CARGO_PKG_VERSION")))
-            .redirect(reqwest::redirect::Policy::limited(10))
+        // Downloads are redirected to the CDN under hf.co.
+        let client = http::builder(&["huggingface.co", "hf.co"])?
             .referer(true)
             .retry(
                 reqwest::retry::for_host("huggingface.co")
                     .max_retries_per_request(3)
                     .max_extra_load(5.0),
             )
-            .build()
-            .unwrap();
-        for file in ["config.json", "model.safetensors", "tokenizer.json"] {
+            .build()?;
+        for file in MODEL_FILES {
             let file_path = model_dir.join(file);
             if !file_path.exists() {
                 // Stream download into cache file.
@@ -133,7 +195,7 @@ impl BertEmbedder {
             }
         }
 
-        Self::new_from_dir(model_dir)
+        Self::new_from_dir(model_dir, batch_size)
     }
 
     /// Load BERT from local files / HF cache.
@@ -147,7 +209,7 @@ impl BertEmbedder {
         level = "info",
         skip(model_dir)
     )]
-    pub fn new_from_dir<P: AsRef<Path>>(model_dir: P) -> AppResult<Self> {
+    pub fn new_from_dir<P: AsRef<Path>>(model_dir: P, batch_size: usize) -> AppResult<Self> {
         let cache_dir = DirType::Cache.ensure_dir()?;
         let model_dir = model_dir.as_ref();
 
@@ -184,40 +246,86 @@ impl BertEmbedder {
             model: Arc::new(model),
             tokenizer: Arc::new(tokenizer),
             cache_dir,
+            batch_size: batch_size.max(1),
         })
     }
 
-    /// Synchronous embedding of one text. You will call this from `spawn_blocking`.
-    fn embed_text_blocking(&self, text: &str) -> AppResult<Vec<f32>> {
-        let text = text.trim();
+    /// Where the embedding of `text` is cached.
+    fn cache_path(&self, text: &str) -> AppResult<PathBuf> {
         let hash_result = murmur3_x86_128(&mut Cursor::new(text), 0)?;
-        let cache_path = self.cache_dir.join(format!("{hash_result}.bin"));
-        if cache_path.exists() {
-            // Load cached embedding.
-            let f = std::fs::File::open(&cache_path)?;
-            let reader = std::io::BufReader::new(f);
-            let vec: Vec<f32> = bincode::decode_from_reader(reader, bincode::config::standard())
-                .map_err(|e| {
-                    AppError::Other(format!("failed to deserialize cached embedding: {e}"))
-                })?;
-            return Ok(vec);
-        }
-
-        // 1) Tokenize
-        let encoding = self.tokenizer.encode(text, true)?;
+        Ok(self.cache_dir.join(format!("{hash_result}.bin")))
+    }
 
-        let ids = encoding.get_ids();
-        let type_ids = encoding.get_type_ids();
-        let attn_mask = encoding.get_attention_mask();
+    /// The cached embedding at `cache_path`, or `None` when there is none. An unreadable cache
+    /// entry is counted in `corrupt`.
+    fn read_cached(cache_path: &Path, corrupt: &mut usize) -> AppResult<Option<Vec<f32>>> {
+        if !cache_path.exists() {
+            return Ok(None);
+        }
+        let f = std::fs::File::open(cache_path)?;
+        let reader = std::io::BufReader::new(f);
+        match bincode::decode_from_reader(reader, bincode::config::standard()) {
+            Ok(vec) => Ok(Some(vec)),
+            Err(e) => {
+                debug!(
+                    "Recomputing corrupt cached embedding {}: {e}",
+                    cache_path.display()
+                );
+                *corrupt += 1;
+                Ok(None)
+            }
+        }
+    }
 
-        let seq_len = ids.len();
-        let batch_size = 1usize;
+    /// Run `texts` through the model in one forward pass and mean-pool each over its tokens.
+    ///
+    /// Shorter texts are padded to the longest, and the attention mask keeps the padding out of
+    /// both the attention and the mean, so each embedding matches a pass of its text alone.
+    fn forward_batch(&self, texts: &[&str]) -> AppResult<Vec<Vec<f32>>> {
+        // 1) Tokenize, padding every text to the longest.
+        let encodings = texts
+            .iter()
+            .map(|text| self.tokenizer.encode(*text, true))
+            .collect::<Result<Vec<_>, _>>()?;
+        let batch_size = encodings.len();
+        let seq_len = encodings
+            .iter()
+            .map(|encoding| encoding.get_ids().len())
+            .max()
+            .unwrap_or(0);
+        let pad_id = self.tokenizer.token_to_id("[PAD]").unwrap_or(0);
+        let mut ids = Vec::with_capacity(batch_size * seq_len);
+        let mut type_ids = Vec::with_capacity(batch_size * seq_len);
+        let mut attn_mask = Vec::with_capacity(batch_size * seq_len);
+        for encoding in &encodings {
+            let padding = seq_len - encoding.get_ids().len();
+            ids.extend(
+                encoding
+                    .get_ids()
+                    .iter()
+                    .copied()
+                    .chain(std::iter::repeat_n(pad_id, padding)),
+            );
+            type_ids.extend(
+                encoding
+                    .get_type_ids()
+                    .iter()
+                    .copied()
+                    .chain(std::iter::repeat_n(0, padding)),
+            );
+            attn_mask.extend(
+                encoding
+                    .get_attention_mask()
+                    .iter()
+                    .copied()
+                    .chain(std::iter::repeat_n(0, padding)),
+            );
+        }
 
         // 2) Build tensors on our device
-        let input_ids = Tensor::new(ids, &self.device)?.reshape((batch_size, seq_len))?;
-        let token_type_ids = Tensor::new(type_ids, &self.device)?.reshape((batch_size, seq_len))?;
-        let attention_mask =
-            Tensor::new(attn_mask, &self.device)?.reshape((batch_size, seq_len))?;
+        let input_ids = Tensor::from_vec(ids, (batch_size, seq_len), &self.device)?;
+        let token_type_ids = Tensor::from_vec(type_ids, (batch_size, seq_len), &self.device)?;
+        let attention_mask = Tensor::from_vec(attn_mask, (batch_size, seq_len), &self.device)?;
 
         // 3) Forward pass.
         // NOTE: BERT forward signature is:
@@ -227,28 +335,56 @@ impl BertEmbedder {
             .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
 
         // outputs shape: [batch, seq_len, hidden_dim]
-        // We’ll average over seq_len to get a single embedding.
-        let hidden_dim = outputs.dim(2)?;
-        let seq_len = outputs.dim(1)?;
-
-        // Mean pool over the second dimension (seq_len).
-        let sum = outputs.sum(1)?;
-        let mean = (sum / (seq_len as f64))?;
-
-        // mean shape: [batch, hidden_dim] → [hidden_dim]
-        let embedding = mean.squeeze(0)?.to_vec1::<f32>()?;
-        debug_assert_eq!(embedding.len(), hidden_dim);
-
-        // Cache the embedding.
-        let mut f = std::fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&cache_path)?;
-        bincode::encode_into_std_write(&embedding, &mut f, bincode::config::standard())
-            .map_err(|e| AppError::Other(format!("failed to serialize cached embedding: {e}")))?;
-
-        Ok(embedding)
+        // Average over the real tokens of each text to get a single embedding.
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let sum = outputs.broadcast_mul(&mask)?.sum(1)?;
+        let counts = mask.sum(1)?;
+        let mean = sum.broadcast_div(&counts)?;
+
+        // mean shape: [batch, hidden_dim]
+        Ok(mean.to_vec2::<f32>()?)
+    }
+
+    /// Synchronous embedding of `texts`. You will call this from `spawn_blocking`.
+    ///
+    /// Cached embeddings are reused, and the rest are run through the model `batch_size` at a
+    /// time. An unreadable cache entry is recomputed and counted in `corrupt`.
+    fn embed_texts_blocking(
+        &self,
+        texts: &[String],
+        corrupt: &mut usize,
+    ) -> AppResult<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut missing = Vec::new();
+        for (i, text) in texts.iter().enumerate() {
+            let text = text.trim();
+            let cache_path = self.cache_path(text)?;
+            let cached = Self::read_cached(&cache_path, corrupt)?;
+            if cached.is_none() {
+                missing.push((i, text, cache_path));
+            }
+            embeddings.push(cached.unwrap_or_default());
+        }
+
+        for batch in missing.chunks(self.batch_size) {
+            let batch_texts: Vec<&str> = batch.iter().map(|(_, text, _)| *text).collect();
+            let computed = self.forward_batch(&batch_texts)?;
+            for ((i, _, cache_path), embedding) in batch.iter().zip(computed) {
+                // Cache the embedding.
+                let mut f = std::fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(cache_path)?;
+                bincode::encode_into_std_write(&embedding, &mut f, bincode::config::standard())
+                    .map_err(|e| {
+                        AppError::Other(format!("failed to serialize cached embedding: {e}"))
+                    })?;
+                embeddings[*i] = embedding;
+            }
+        }
+
+        Ok(embeddings)
     }
 
     /// Asynchronously embed many texts. Runs in a blocking worker so Candle stays off Tokio.
@@ -275,8 +411,9 @@ impl BertEmbedder {
             .collect();
         let items = history.to_vec();
 
-        let embeddings = tokio::task::spawn_blocking(move || {
+        let (embeddings, corrupt) = tokio::task::spawn_blocking(move || {
             let mut embeddings = Vec::new();
+            let mut corrupt = 0;
             let header_span = info_span!("Running embeddings for URLs");
             header_span.pb_set_message("Embedding...");
             header_span.pb_set_finish_message("Embedding complete");
@@ -288,16 +425,158 @@ impl BertEmbedder {
             );
             let header_span_enter = header_span.enter();
 
-            for (i, t) in texts.iter().enumerate() {
-                let emb = this.embed_text_blocking(t)?;
-                embeddings.push((items[i].clone(), emb));
-                header_span.pb_inc(1);
+            let batches = items
+                .chunks(this.batch_size)
+                .zip(texts.chunks(this.batch_size));
+            for (batch, batch_texts) in batches {
+                let computed = this.embed_texts_blocking(batch_texts, &mut corrupt)?;
+                embeddings.extend(batch.iter().cloned().zip(computed));
+                header_span.pb_inc(batch_texts.len() as u64);
             }
             std::mem::drop(header_span_enter);
             std::mem::drop(header_span);
-            Result::<_, AppError>::Ok(embeddings)
+            Result::<_, AppError>::Ok((embeddings, corrupt))
+        })
+        .await??;
+        if corrupt > 0 {
+            warnings::record(
+                WarningKind::CacheCorrupt,
+                "safari",
+                format!("Recomputed {corrupt} corrupt cached embedding(s)"),
+            );
+        }
+        Ok(embeddings)
+    }
+}
+
+impl Embedder for BertEmbedder {
+    /// Asynchronously embed short passages such as summary highlights, in a blocking worker.
+    async fn embed_texts(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        let this = self.clone();
+        let texts: Vec<String> = texts.iter().map(|text| format!("query: {text}")).collect();
+        let (embeddings, corrupt) = tokio::task::spawn_blocking(move || {
+            let mut corrupt = 0;
+            let embeddings = this.embed_texts_blocking(&texts, &mut corrupt)?;
+            Result::<_, AppError>::Ok((embeddings, corrupt))
         })
         .await??;
+        if corrupt > 0 {
+            warnings::record(
+                WarningKind::CacheCorrupt,
+                "highlights",
+                format!("Recomputed {corrupt} corrupt cached embedding(s)"),
+            );
+        }
         Ok(embeddings)
     }
+
+    async fn embed_history(
+        &self,
+        history: &[SafariHistoryItem],
+    ) -> AppResult<Vec<(SafariHistoryItem, Vec<f32>)>> {
+        self.embed_batch(history).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use candle_nn::VarMap;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+    use super::*;
+
+    /// A two-layer BERT over a few words, with the random weights in `varmap`, caching under a
+    /// fresh directory.
+    fn tiny_embedder(varmap: &VarMap, batch_size: usize) -> BertEmbedder {
+        let words = [
+            "[PAD]", "[UNK]", "rust", "release", "notes", "for", "the", "parser", "tests", "pass",
+        ];
+        let vocab: HashMap<String, u32> = words
+            .iter()
+            .enumerate()
+            .map(|(id, word)| (word.to_string(), id as u32))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab.into_iter().collect())
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Some(Whitespace {}));
+
+        let config: BertConfig = serde_json::from_value(serde_json::json!({
+            "vocab_size": words.len(),
+            "hidden_size": 16,
+            "num_hidden_layers": 2,
+            "num_attention_heads": 2,
+            "intermediate_size": 32,
+            "hidden_act": "gelu",
+            "hidden_dropout_prob": 0.0,
+            "max_position_embeddings": 32,
+            "type_vocab_size": 2,
+            "initializer_range": 0.02,
+            "layer_norm_eps": 1e-12,
+            "pad_token_id": 0,
+            "position_embedding_type": "absolute",
+            "use_cache": false,
+            "classifier_dropout": null,
+            "model_type": null
+        }))
+        .unwrap();
+        let vb = VarBuilder::from_varmap(varmap, DType::F32, &Device::Cpu);
+        let cache_dir = std::env::temp_dir().join(format!("bert_batch_{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        BertEmbedder {
+            device: Device::Cpu,
+            model: Arc::new(BertModel::load(vb, &config).unwrap()),
+            tokenizer: Arc::new(tokenizer),
+            cache_dir,
+            batch_size,
+        }
+    }
+
+    fn assert_close(left: &[Vec<f32>], right: &[Vec<f32>]) {
+        assert_eq!(left.len(), right.len());
+        for (a, b) in left.iter().zip(right) {
+            assert_eq!(a.len(), b.len());
+            for (x, y) in a.iter().zip(b) {
+                assert!((x - y).abs() < 1e-4, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn batching_pads_without_changing_embeddings() {
+        let varmap = VarMap::new();
+        let one = tiny_embedder(&varmap, 1);
+        let texts = [
+            "rust",
+            "release notes for the parser",
+            "the parser tests pass",
+            "notes",
+        ];
+
+        let alone: Vec<Vec<f32>> = texts
+            .iter()
+            .map(|text| one.forward_batch(&[text]).unwrap().remove(0))
+            .collect();
+        assert_close(&one.forward_batch(&texts).unwrap(), &alone);
+
+        // The same weights with one batch for every text.
+        let many = tiny_embedder(&varmap, texts.len());
+        let texts: Vec<String> = texts.iter().map(|text| text.to_string()).collect();
+        let (mut corrupt_one, mut corrupt_many) = (0, 0);
+        assert_close(
+            &one.embed_texts_blocking(&texts, &mut corrupt_one).unwrap(),
+            &many
+                .embed_texts_blocking(&texts, &mut corrupt_many)
+                .unwrap(),
+        );
+        assert_eq!((corrupt_one, corrupt_many), (0, 0));
+        let _ = std::fs::remove_dir_all(&one.cache_dir);
+        let _ = std::fs::remove_dir_all(&many.cache_dir);
+    }
 }
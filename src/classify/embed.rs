@@ -0,0 +1,289 @@
+use clap::ValueEnum;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, warn};
+
+use super::bert::BertEmbedder;
+use crate::AppResult;
+use crate::error::AppError;
+use crate::http;
+use crate::safari::SafariHistoryItem;
+
+/// Ollama server asked for embeddings unless `--ollama-url` says otherwise.
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Most texts sent in one request to an OpenAI-compatible `/embeddings` endpoint.
+const BATCH_SIZE: usize = 100;
+
+/// Where text is embedded, as given to `--embedder`.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmbedderKind {
+    /// The Hugging Face model from --embedding-model, run on this machine
+    #[default]
+    Local,
+    /// The OpenAI-compatible `/embeddings` endpoint of the language model server
+    Openai,
+    /// The `/api/embeddings` endpoint of the Ollama server at --ollama-url
+    Ollama,
+}
+
+impl EmbedderKind {
+    /// Model a remote embedder is asked for unless `--remote-embedding-model` says otherwise.
+    fn default_remote_model(self) -> &'static str {
+        match self {
+            EmbedderKind::Ollama => "nomic-embed-text",
+            EmbedderKind::Local | EmbedderKind::Openai => "text-embedding-3-small",
+        }
+    }
+}
+
+/// Which embedder a run uses and how to reach it, from the common options.
+#[derive(Debug, Clone)]
+pub struct EmbedderSettings {
+    pub kind: EmbedderKind,
+    /// Hugging Face model run by the local embedder.
+    pub local_model: String,
+    /// Texts the local embedder runs through the model at once.
+    pub batch_size: usize,
+    /// Model asked for by the remote embedders, or `None` for their default.
+    pub remote_model: Option<String>,
+    /// Whether the local model must already be downloaded, for `--offline` runs.
+    pub offline: bool,
+    /// API base of the language model server, for the OpenAI-compatible embedder.
+    pub api_base: String,
+    pub api_key: Option<String>,
+    pub ollama_url: String,
+}
+
+/// Turns text into vectors whose cosine similarity says how alike the texts are.
+pub trait Embedder {
+    /// Embed short passages such as summary highlights, one vector per text.
+    async fn embed_texts(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>>;
+
+    /// Embed browsing history from each page's title and URL.
+    async fn embed_history(
+        &self,
+        history: &[SafariHistoryItem],
+    ) -> AppResult<Vec<(SafariHistoryItem, Vec<f32>)>> {
+        let texts: Vec<String> = history
+            .iter()
+            .map(|item| format!("{} {}", item.title.as_deref().unwrap_or_default(), item.url))
+            .collect();
+        let embeddings = self.embed_texts(&texts).await?;
+        Ok(history.iter().cloned().zip(embeddings).collect())
+    }
+}
+
+/// The HTTP API a remote embedder speaks.
+#[derive(Debug, Clone, Copy)]
+enum Api {
+    /// `POST /embeddings` with every text in `input`.
+    OpenAi,
+    /// `POST /api/embeddings` with one text in `prompt`.
+    Ollama,
+}
+
+/// Ollama's answer to `/api/embeddings`.
+#[derive(Deserialize)]
+struct OllamaEmbedding {
+    embedding: Vec<f32>,
+}
+
+/// An OpenAI-compatible answer to `/embeddings`.
+#[derive(Deserialize)]
+struct OpenAiEmbeddings {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl OpenAiEmbeddings {
+    /// The vectors in the order of the `count` texts sent, which servers need not keep.
+    fn into_vectors(mut self, count: usize) -> AppResult<Vec<Vec<f32>>> {
+        self.data.sort_by_key(|item| item.index);
+        let in_order = self
+            .data
+            .iter()
+            .enumerate()
+            .all(|(i, item)| item.index == i);
+        if self.data.len() != count || !in_order {
+            return Err(AppError::Other(format!(
+                "The embeddings endpoint returned {} vectors for {count} texts",
+                self.data.len()
+            )));
+        }
+        Ok(self.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+/// Embeds text on a server: the language model server or Ollama.
+#[derive(Debug, Clone)]
+pub struct RemoteEmbedder {
+    api: Api,
+    client: Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl RemoteEmbedder {
+    /// The embedder `kind` names, or the language model server's for [`EmbedderKind::Local`].
+    fn new(settings: &EmbedderSettings, kind: EmbedderKind) -> AppResult<Self> {
+        let model = settings
+            .remote_model
+            .clone()
+            .unwrap_or_else(|| kind.default_remote_model().to_string());
+        match kind {
+            EmbedderKind::Ollama => Ok(RemoteEmbedder {
+                api: Api::Ollama,
+                client: http::client(&[&settings.ollama_url])?,
+                url: format!(
+                    "{}/api/embeddings",
+                    settings.ollama_url.trim_end_matches('/')
+                ),
+                model,
+                api_key: None,
+            }),
+            EmbedderKind::Local | EmbedderKind::Openai => Ok(RemoteEmbedder {
+                api: Api::OpenAi,
+                client: http::model_server_client(&settings.api_base)?,
+                url: format!("{}/embeddings", settings.api_base.trim_end_matches('/')),
+                model,
+                api_key: settings.api_key.clone(),
+            }),
+        }
+    }
+
+    /// Send `body` to the embedder and return its answer.
+    async fn post(&self, body: serde_json::Value) -> AppResult<String> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&body)?);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            return Err(AppError::Other(format!(
+                "{} returned {} for the {} embedding model",
+                self.url,
+                resp.status(),
+                self.model
+            )));
+        }
+        Ok(resp.text().await?)
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    async fn embed_texts(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        debug!(
+            "Embedding {} texts with {} at {}",
+            texts.len(),
+            self.model,
+            self.url
+        );
+        let mut embeddings = Vec::with_capacity(texts.len());
+        match self.api {
+            Api::OpenAi => {
+                for batch in texts.chunks(BATCH_SIZE) {
+                    let body = json!({ "model": self.model, "input": batch });
+                    let answer: OpenAiEmbeddings = serde_json::from_str(&self.post(body).await?)?;
+                    embeddings.extend(answer.into_vectors(batch.len())?);
+                }
+            }
+            Api::Ollama => {
+                for text in texts {
+                    let body = json!({ "model": self.model, "prompt": text });
+                    let answer: OllamaEmbedding = serde_json::from_str(&self.post(body).await?)?;
+                    embeddings.push(answer.embedding);
+                }
+            }
+        }
+        Ok(embeddings)
+    }
+}
+
+/// The embedder picked with `--embedder`.
+pub enum AnyEmbedder {
+    Local(BertEmbedder),
+    Remote(RemoteEmbedder),
+}
+
+impl Embedder for AnyEmbedder {
+    async fn embed_texts(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        match self {
+            AnyEmbedder::Local(embedder) => embedder.embed_texts(texts).await,
+            AnyEmbedder::Remote(embedder) => embedder.embed_texts(texts).await,
+        }
+    }
+
+    async fn embed_history(
+        &self,
+        history: &[SafariHistoryItem],
+    ) -> AppResult<Vec<(SafariHistoryItem, Vec<f32>)>> {
+        match self {
+            AnyEmbedder::Local(embedder) => embedder.embed_history(history).await,
+            AnyEmbedder::Remote(embedder) => embedder.embed_history(history).await,
+        }
+    }
+}
+
+/// Load the embedder `settings` pick, for embedding `source`'s data.
+///
+/// When the local model cannot be loaded, the language model server's `/embeddings` endpoint
+/// is used instead.
+pub async fn load(settings: &EmbedderSettings, source: &str) -> AppResult<AnyEmbedder> {
+    if settings.kind != EmbedderKind::Local {
+        return Ok(AnyEmbedder::Remote(RemoteEmbedder::new(
+            settings,
+            settings.kind,
+        )?));
+    }
+    match BertEmbedder::new_from_pretrained(
+        &settings.local_model,
+        settings.offline,
+        settings.batch_size,
+    )
+    .await
+    {
+        Ok(embedder) => Ok(AnyEmbedder::Local(embedder)),
+        Err(e) => {
+            let remote = RemoteEmbedder::new(settings, EmbedderKind::Openai)?;
+            warn!(
+                "Unable to load the {} embedding model, so {} at {} embeds the {source} data \
+                 instead: {e}",
+                settings.local_model, remote.model, remote.url
+            );
+            Ok(AnyEmbedder::Remote(remote))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_openai_embeddings_by_index() {
+        let answer = r#"{"object":"list","data":[
+            {"object":"embedding","index":1,"embedding":[0.5,0.25]},
+            {"object":"embedding","index":0,"embedding":[1.0,0.0]}
+        ],"model":"text-embedding-3-small"}"#;
+        let parsed: OpenAiEmbeddings = serde_json::from_str(answer).unwrap();
+        assert_eq!(
+            parsed.into_vectors(2).unwrap(),
+            vec![vec![1.0, 0.0], vec![0.5, 0.25]]
+        );
+
+        let parsed: OpenAiEmbeddings = serde_json::from_str(answer).unwrap();
+        assert!(parsed.into_vectors(3).is_err());
+    }
+}
@@ -1,9 +1,12 @@
 pub(super) mod bert;
 pub(super) mod convert;
+pub(super) mod embed;
 pub(super) mod knn;
 pub(super) mod linalg;
 pub(super) mod pca;
 
+pub(crate) use bert::shred_embedding_cache;
+
 use std::collections::HashMap;
 
 use async_openai::{Client, config::Config};
@@ -16,6 +19,22 @@ use tracing_indicatif::style::ProgressStyle;
 use crate::AppResult;
 use crate::ai::label_urls::label_url_cluster;
 use crate::safari::SafariHistoryItem;
+use embed::{Embedder, EmbedderSettings};
+
+/// Hugging Face model used to embed URLs and titles unless `--embedding-model` says otherwise.
+pub const DEFAULT_EMBEDDING_MODEL: &str = "intfloat/e5-small-v2";
+
+/// Texts embedded in one forward pass unless `--embedding-batch-size` says otherwise.
+pub const DEFAULT_EMBEDDING_BATCH_SIZE: usize = 32;
+
+/// Neighbours whose distance picks the DBSCAN radius. Histories with no more pages than this
+/// are labeled as one group instead of clustered.
+const NEIGHBOURS: usize = 25;
+
+/// Check that the embedding model has already been downloaded, for `--offline` runs.
+pub fn ensure_embedding_model_cached(embedding_model: &str) -> AppResult<()> {
+    bert::cached_model_dir(embedding_model).map(|_| ())
+}
 
 /// Cluster of Safari URLs with a human-friendly label.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -31,7 +50,9 @@ pub struct UrlCluster {
 )]
 async fn build_cluster_output<C: Config>(
     client: &Client<C>,
+    model: &str,
     grouped: HashMap<usize, Vec<SafariHistoryItem>>,
+    offline: bool,
 ) -> AppResult<Vec<UrlCluster>> {
     let mut clusters = Vec::new();
     let mut misc = Vec::new();
@@ -54,7 +75,7 @@ async fn build_cluster_output<C: Config>(
             misc.extend(urls);
             continue;
         }
-        let label = label_url_cluster(client, &urls).await?;
+        let label = label_url_cluster(client, model, &urls, offline).await?;
         clusters.push(UrlCluster {
             label: label.label,
             urls,
@@ -64,7 +85,7 @@ async fn build_cluster_output<C: Config>(
 
     if !misc.is_empty() {
         info!("Labeling miscellaneous URLs...");
-        let label = label_url_cluster(client, &misc).await?;
+        let label = label_url_cluster(client, model, &misc, offline).await?;
         clusters.push(UrlCluster {
             label: label.label,
             urls: misc,
@@ -78,15 +99,28 @@ async fn build_cluster_output<C: Config>(
 }
 
 /// Entry point: embed Safari URLs, cluster them, and produce labeled clusters via the model.
-#[tracing::instrument(name = "Grouping browser history", level = "info", skip(client, urls))]
+#[tracing::instrument(
+    name = "Grouping browser history",
+    level = "info",
+    skip(client, embedder, urls)
+)]
 pub async fn embed_urls<C: Config>(
     client: &Client<C>,
+    model: &str,
+    embedder: &EmbedderSettings,
     urls: Vec<SafariHistoryItem>,
+    offline: bool,
 ) -> AppResult<Vec<UrlCluster>> {
     let starting_count = urls.len();
+    if starting_count <= NEIGHBOURS {
+        debug!("Only {starting_count} pages visited; labeling them as one group");
+        return build_cluster_output(client, model, HashMap::from([(0, urls)]), offline).await;
+    }
 
-    let embedder = bert::BertEmbedder::new_from_pretrained("intfloat/e5-small-v2").await?;
-    let embeddings = embedder.embed_batch(&urls).await?;
+    let embeddings = embed::load(embedder, "safari")
+        .await?
+        .embed_history(&urls)
+        .await?;
 
     // Normalize
     let embs_only: Vec<Vec<f32>> = embeddings
@@ -130,7 +164,7 @@ pub async fn embed_urls<C: Config>(
 
     // compute k‐distance
     let mut knn = knn::Knn::default();
-    knn.set_k(25).fit(&reduced)?;
+    knn.set_k(NEIGHBOURS).fit(&reduced)?;
     debug!("Computed k‐distance graph for k={}", knn.k);
     let kdists = knn.distances(&reduced)?;
     let dist_cols = kdists.ncols();
@@ -172,7 +206,7 @@ pub async fn embed_urls<C: Config>(
         clustered.len()
     );
 
-    let ret = build_cluster_output(client, clustered).await?;
+    let ret = build_cluster_output(client, model, clustered, offline).await?;
 
     Ok(ret)
 }
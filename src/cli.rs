@@ -1,20 +1,63 @@
 use std::fmt::Display;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
 
 use async_openai::Client;
 use async_openai::config::{Config, OpenAIConfig};
+use clap::builder::PossibleValuesParser;
 use clap::builder::styling::{AnsiColor, Color, Style, Styles};
-use clap::{ArgAction, Args, ColorChoice, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use clap::{
+    ArgAction, ArgMatches, Args, ColorChoice, Command, CommandFactory, FromArgMatches, Parser,
+    Subcommand, ValueEnum,
+};
 use clap_complete::aot::{Generator, Shell, generate};
 use clap_complete_nushell::Nushell;
 use clap_verbosity_flag::{InfoLevel, Verbosity};
-use time::Duration;
-use tracing::{error, info};
-
-use crate::ai::SchemaInfo;
+use time::macros::format_description;
+use time::{Date, Duration};
+use tracing::{error, info, warn};
+use ulid::Ulid;
+
+use crate::ai::ask::AskOptions;
+use crate::ai::cache::ResponseCache;
+use crate::ai::commit_message::CommitMessageOptions;
+use crate::ai::keep_alive;
+use crate::ai::prompt::{Length, Tone};
+use crate::ai::provider::Provider;
+use crate::ai::summary::SummaryOptions;
+use crate::ai::token_budget::TokenBudget;
+use crate::ai::tools::summary::SummaryToolSchema;
+use crate::ai::{ModelTask, SchemaInfo};
+use crate::archive::{ArchiveSource, PurgeOptions};
+use crate::classify::embed::{self, EmbedderKind, EmbedderSettings};
+use crate::collector::builtin::{
+    AppleNotesCollector, ChromeCollector, CiCollector, ClipboardCollector, ContainersCollector,
+    EdgeCollector, EmailCollector, ExternalCollector, GitCollector, HostsCollector,
+    IdeBuildsCollector, K8sCollector, MessagesCollector, NotesCollector, RecordingsCollector,
+    ReleasesCollector, SafariCollector, ShellCollector, SlackCollector, TasksCollector,
+    TmuxCollector,
+};
+use crate::collector::{Collector, CollectorSettings, CollectorSpec, run_collectors};
 use crate::context::{Context, FullContext};
-use crate::{AppResult, ai, classify, git, safari, shell};
+use crate::email::smtp::{EmailExporter, SmtpConfig, parse_mailbox};
+use crate::error::AppError;
+use crate::external::ExternalCommand;
+use crate::git::RepoFilter;
+use crate::highlights::RepeatedHighlights;
+use crate::http;
+use crate::redact::{self, Redactor};
+use crate::rollup::RollupPeriod;
+use crate::shell::ShellBackend;
+use crate::stats::StatsFormat;
+use crate::timesheet::{ProjectMapping, ProjectRate, TimesheetOptions};
+use crate::verify::ClaimCheck;
+use crate::webhook::WebhookExporter;
+use crate::{
+    AppResult, ai, archive, ask, classify, compare, config, dashboard, draft, feed, git,
+    highlights, history, io_utils, learning, pace, proofread, render, rollup, run_status, stats,
+    status, tags, timesheet, verify, warnings,
+};
 
 const STYLES: Styles = Styles::styled()
     .header(Style::new().bold())
@@ -39,11 +82,22 @@ const STYLES: Styles = Styles::styled()
 const LONG_ABOUT: &str = "Daily AI - Summarize your daily activities using AI
 
 This tool collects:
-- your shell history (with \x1b]8;;https://atuin.sh\x1b\\\x1b[4;36matuin\x1b[24;39m\x1b]8;;\x1b\\)
+- your shell history (with \x1b]8;;https://atuin.sh\x1b\\\x1b[4;36matuin\x1b[24;39m\x1b]8;;\x1b\\, or from zsh, bash, and fish history files)
 - Safari browsing history (from the sqlite database)
 - Git commit history (from your local git repositories, based on your shell history)
+- Ticket activity (Jira/Linear tickets referenced by your commits and branches)
+
+Then, it sends this data to a language model server (like \x1b]8;;https://lmstudio.ai/\x1b\\\x1b[4;36mLM Studio\x1b[24;39m\x1b]8;;\x1b\\), or to OpenAI or Anthropic with --provider, to generate a summary.
 
-Then, it sends this data to a language model server (like \x1b]8;;https://lmstudio.ai/\x1b\\\x1b[4;36mLM Studio\x1b[24;39m\x1b]8;;\x1b\\) to generate a summary.";
+Defaults for most options can be set in ~/.config/dailyai/config.toml (see `daily-ai config init`).
+
+Exit status:
+  0  success
+  1  any other failure
+  2  partial: output was written, but some collectors failed or summary sections are missing
+  3  nothing was collected (only with --fail-on-empty)
+  4  invalid arguments or configuration
+  5  the language model server could not be reached";
 
 /// Daily AI - Summarize your daily activities using AI.
 #[derive(Parser, Debug, Clone)]
@@ -53,13 +107,20 @@ pub struct Cli {
     #[arg(long, default_value_t = ColorChoice::Auto)]
     pub color: ColorChoice,
 
+    /// Use the defaults of this profile from the configuration file
+    ///
+    /// A profile is a `[profiles.NAME]` table in config.toml. Its settings replace the
+    /// top-level ones, and options given on the command line still win
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
     /// Subcommand to run
     #[command(subcommand)]
     pub cmd: Cmd,
 }
 
 /// Output format for the collected history.
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Debug, PartialEq, Eq)]
 pub enum OutputFormat {
     /// Output a JSON file containing all collected changes
     ///
@@ -69,6 +130,51 @@ pub enum OutputFormat {
     /// browsing history) and patch files for each git repository
     ///
     Dir,
+
+    /// Output the summary and commit list as a Markdown document, with commits linked to their
+    /// GitHub/GitLab/Bitbucket pages when the repository has a remote
+    ///
+    Markdown,
+
+    /// Output the summary and commit list as a PDF document, laid out like the Markdown output.
+    /// Characters outside Windows-1252, such as CJK text or emoji, are shown as `?`
+    ///
+    Pdf,
+
+    /// Output the summary and commit list as an Org-mode document
+    ///
+    Org,
+
+    /// Output a short "Yesterday / Today / Blockers" stand-up update as plain text
+    ///
+    Standup,
+
+    /// Output the summary and commit list as plain text: no colours or tables, headings
+    /// underlined, and lines wrapped at `--text-width`, for screen readers and for piping into
+    /// tools such as `mail` or `wall`
+    ///
+    Text,
+
+    /// Output JSON Lines: one object per collected item and summary section, tagged with a
+    /// `type` field for filtering with jq or shipping to a log pipeline
+    ///
+    Jsonl,
+
+    /// Append the run's context and summary to a SQLite database, one row per run id
+    ///
+    Sqlite,
+
+    /// Merge the summary into today's daily note (`YYYY-MM-DD.md`) of the Obsidian vault given
+    /// by `--output`, under a `## Daily AI` heading. The rest of the note is left untouched, and
+    /// the daily notes folder configured in the vault is honoured
+    ///
+    Obsidian,
+
+    /// Output the time breakdown as an iCalendar (`.ics`) file with one event per block of work,
+    /// to view the reconstructed day in a calendar app. Blocks the model could not place in time
+    /// are left out
+    ///
+    Ics,
 }
 
 /// Top-level commands supported by the CLI.
@@ -78,9 +184,76 @@ pub enum Cmd {
     /// This is the default command
     Summarize {
         #[command(flatten)]
-        shell: ShellCollectArgs,
+        sources: SourceArgs,
         #[command(flatten)]
         default: DefaultArgs,
+        /// Commit pending changes in visited repositories (with AI-written messages) before
+        /// collecting git history
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        auto_commit: bool,
+        #[command(flatten)]
+        language: CommitLanguageArgs,
+        /// Do not save this run to the local archive or the history database
+        #[arg(long = "no-archive", default_value_t = true, action = ArgAction::SetFalse)]
+        archive: bool,
+        /// Email the summary to this address once it is written. May be repeated
+        ///
+        /// The summary is sent as Markdown with an HTML alternative, through the server in the
+        /// `[smtp]` table of the configuration file. `SMTP_HOST`, `SMTP_PORT` (587 for
+        /// STARTTLS, or 465), `SMTP_USER`, and `SMTP_FROM` override it, and the password is read
+        /// from `SMTP_PASSWORD`. Retrying a run with `--run-id` sends the corrected summary as a
+        /// reply to the first email
+        #[arg(
+            long = "email-to",
+            value_name = "ADDRESS",
+            value_parser = parse_mailbox,
+            conflicts_with = "offline"
+        )]
+        email_to: Vec<String>,
+        /// POST the summary as JSON to this URL once it is written. May be repeated
+        ///
+        /// Failed deliveries are retried with backoff. When `DAILY_AI_WEBHOOK_SECRET` is set, the
+        /// body is signed with it using HMAC-SHA256, in the `X-Daily-AI-Signature-256` header.
+        /// Retrying a run with `--run-id` sends a `summary.updated` event
+        #[arg(long, value_name = "URL", conflicts_with = "offline")]
+        webhook: Vec<String>,
+        /// What to do with highlights that say the same as one from the last few archived days
+        ///
+        /// Highlights are compared by embedding similarity, so rewordings of the same news count
+        /// as repeats
+        #[arg(long, value_enum, default_value_t = RepeatedHighlights::Mark)]
+        repeated_highlights: RepeatedHighlights,
+        /// Days of archived highlights to compare with, or 0 to skip the comparison
+        #[arg(long, value_name = "DAYS", default_value_t = highlights::DEFAULT_LOOKBACK_DAYS)]
+        highlight_lookback: u32,
+        /// Review each section of the summary as it is written: accept it, have it regenerated
+        /// with your feedback, or edit it in `$EDITOR`
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        interactive: bool,
+        /// Voice of the summary, in place of the `style` from `[about_me]`
+        #[arg(long, value_enum)]
+        tone: Option<Tone>,
+        /// Length of the summary
+        ///
+        /// Defaults to a few paragraphs, like `medium`
+        #[arg(long, value_enum)]
+        length: Option<Length>,
+        /// Check the repositories, tickets, URLs, counts, and names in backticks that the summary
+        /// mentions against the collected data, and flag or remove those it does not contain
+        #[arg(long, value_enum, default_value_t = ClaimCheck::Off)]
+        verify_claims: ClaimCheck,
+        /// Have the model fix typos and broken sentences in the summary before it is written
+        ///
+        /// Asks --label-model, so a small model is enough. Corrections that change a number,
+        /// URL, or name in backticks are not used
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        proofread: bool,
+        /// Give the browsing groups, repositories, and highlights an emoji and tags such as
+        /// `#bugfix`, `#research`, and `#ops`, so a long report can be scanned
+        ///
+        /// Most are tagged from the words in them; the model is asked only about the rest
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        tags: bool,
         #[command(flatten)]
         verbosity: Verbosity<InfoLevel>,
     },
@@ -96,6 +269,80 @@ pub enum Cmd {
         cmd: CollectCmd,
     },
 
+    /// Commit the staged changes in the current repository with a generated message
+    Commit {
+        /// Amend HEAD instead, regenerating the message for HEAD plus the staged changes.
+        /// Refused when HEAD has already been pushed
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        amend: bool,
+        #[command(flatten)]
+        language: CommitLanguageArgs,
+
+        #[command(flatten)]
+        default: DefaultArgs,
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Compare two stored runs: new and dropped repositories and topics, and how the time
+    /// spent changed
+    ///
+    /// Each run is given by run id, or by date for that day's latest run, as listed by
+    /// `daily-ai history list`.
+    Diff {
+        /// The earlier run id or date (YYYY-MM-DD)
+        first: String,
+
+        /// The later run id or date (YYYY-MM-DD)
+        second: String,
+
+        /// How to print the comparison; rendered Markdown on a terminal and JSON otherwise by
+        /// default. `dir`, `standup`, `sqlite`, `obsidian`, and `ics` only apply to single runs
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// File to write the comparison to; the extension picks the format unless `--format`
+        /// is given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Draft a blog or "today I learned" post about one topic from archived runs
+    ///
+    /// Picks the commits, diffs, and browsing that mention the topic from the archive, then has
+    /// the model write a Markdown skeleton with code snippets pulled from the diffs. Written to
+    /// `--output`, or shown like a summary
+    DraftPost {
+        /// What the post is about, e.g. "switching the parser to winnow"
+        #[arg(short, long)]
+        topic: String,
+
+        /// Only use the run archived on this day (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date, conflicts_with = "period")]
+        date: Option<Date>,
+
+        /// How far back to look, e.g. `1d` or `2weeks`
+        #[arg(short, long, default_value = "7d", value_parser = parse_period)]
+        period: Duration,
+
+        #[command(flatten)]
+        default: DefaultArgs,
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Manage the configuration file, `config.toml` in the config directory
+    ///
+    /// It sets defaults for the language model server, model, duration, output format, and
+    /// collectors. Options given on the command line override it.
+    Config {
+        #[command(subcommand)]
+        cmd: ConfigCmd,
+    },
+
     /// Generate shell completion for a given shell
     Completion {
         /// Output file to write the completion script to
@@ -114,6 +361,294 @@ pub enum Cmd {
         #[command(subcommand)]
         query: Queries,
     },
+
+    /// Delete archived raw data older than a given date
+    ///
+    /// Summaries are kept unless `--drop-summaries` is given, so the dashboard keeps working
+    /// after the underlying history is gone. Model answers cached before the date are deleted
+    /// too, whichever sources are purged.
+    Purge {
+        /// Purge runs dated before this day (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        before: Date,
+
+        /// Only purge these sources (repeatable). Purges every source when omitted
+        #[arg(long = "source", value_enum)]
+        sources: Vec<ArchiveSource>,
+
+        /// Also delete the generated summaries
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        drop_summaries: bool,
+
+        /// Overwrite purged data and cached answers before deleting them, and shred every cached
+        /// URL embedding
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        paranoid: bool,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Render the archive of past summaries as a static HTML site
+    ///
+    /// The site has an index by month, a page per day, charts of commits, URLs, and active
+    /// time, a heatmap of active hours by weekday, and a prebuilt search index.
+    Dashboard {
+        /// Directory to write the site to
+        #[arg(short, long, default_value = "site")]
+        output: PathBuf,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Write the last archived summaries as an Atom feed, for reading in a feed reader
+    ///
+    /// Each day is an entry titled with its date and first highlight, with the summary as HTML.
+    Feed {
+        /// File to write the feed to
+        #[arg(short, long, default_value = "feed.xml")]
+        output: PathBuf,
+
+        /// How many of the latest summarized days to include
+        #[arg(short = 'n', long, default_value_t = 30)]
+        count: usize,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Browse and search the summaries stored in the history database
+    History {
+        #[command(subcommand)]
+        cmd: HistoryCmd,
+    },
+
+    /// Answer a question about what you did from a stored run
+    ///
+    /// The model gets the run's summary and collected data, and the same tools as when
+    /// summarizing to look up diffs, commands, and pages. Written to `--output`, or shown like a
+    /// summary
+    Ask {
+        /// The question, e.g. "what did I change in the parser?"
+        question: String,
+
+        /// The run id or date (YYYY-MM-DD) to ask about, as listed by `daily-ai history list`;
+        /// today's latest run by default
+        #[arg(long, value_name = "RUN")]
+        run: Option<String>,
+
+        #[command(flatten)]
+        default: DefaultArgs,
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Talk with the model about a stored run
+    ///
+    /// Like `ask`, but follow-up questions continue the conversation. Lines starting with `/`
+    /// are commands: `/repos` and `/urls` list what was collected, `/export [FILE]` saves the
+    /// conversation as Markdown, and `/quit` leaves
+    Chat {
+        /// The run id or date (YYYY-MM-DD) to talk about, as listed by `daily-ai history list`;
+        /// today's latest run by default
+        #[arg(long, value_name = "RUN")]
+        run: Option<String>,
+
+        #[command(flatten)]
+        default: DefaultArgs,
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Write a weekly or monthly report from the archived daily summaries
+    ///
+    /// The model groups the period's work by project and outcome. The report adds the
+    /// period's numbers from `daily-ai stats` and any pace notes. Written to `--output`, or
+    /// shown like a summary
+    Rollup {
+        /// How much time the report covers
+        #[arg(short, long, value_enum, default_value_t)]
+        period: RollupPeriod,
+
+        #[command(flatten)]
+        default: DefaultArgs,
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Build a timesheet of measured hours per project from the archive
+    ///
+    /// Time is counted in 15-minute slots with a commit or a shell command inside a
+    /// repository, billed at the given hourly rates. Written to `--output` (CSV when it ends
+    /// in `.csv`), or shown like a summary
+    Timesheet {
+        /// How far back to look, e.g. `2weeks` or `1month`
+        #[arg(short, long, default_value = "30d", value_parser = parse_period)]
+        period: Duration,
+
+        /// Bill matching repositories as one project, e.g. `Acme=acme-api,acme-web`
+        /// (repeatable). A repository matches when its name or path contains a pattern;
+        /// unmatched repositories are billed under their own name
+        #[arg(long = "project", value_name = "NAME=PATTERN[,PATTERN]")]
+        projects: Vec<ProjectMapping>,
+
+        /// Hourly rate of a project, e.g. `Acme=95` (repeatable)
+        #[arg(long = "rate", value_name = "NAME=AMOUNT")]
+        rates: Vec<ProjectRate>,
+
+        /// Hourly rate of projects without a `--rate`
+        #[arg(long, value_name = "AMOUNT")]
+        default_rate: Option<f64>,
+
+        /// Currency shown next to rates and amounts, e.g. `EUR`
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// Have the model write a work description for each project
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        describe: bool,
+
+        /// Print CSV instead of Markdown
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        csv: bool,
+
+        #[command(flatten)]
+        default: DefaultArgs,
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Show trends across the archive of past runs
+    ///
+    /// Commits per day, deep-work hours, recurring browsing categories, the most-touched
+    /// repositories, and the hosts connected to, computed locally without the language model.
+    Stats {
+        /// How far back to look, e.g. `7d` or `12weeks`
+        #[arg(short, long, default_value = "30d", value_parser = parse_period)]
+        period: Duration,
+
+        /// How to print the trends
+        #[arg(short, long, value_enum, default_value_t)]
+        format: StatsFormat,
+
+        /// Show when you are active instead: a grid of active time by weekday and hour
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        heatmap: bool,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Show the last run, the next scheduled one, and what is kept on disk
+    ///
+    /// Reads the run status file, the crontab and launch agents that run daily-ai, the archive,
+    /// and the cache. A running summary updates the status as it goes, so menubar and tray apps
+    /// can poll `daily-ai status --json` to show its progress
+    Status {
+        /// Print a JSON object instead of plain text
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        json: bool,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+}
+
+/// Subcommands of `daily-ai config`.
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigCmd {
+    /// Write a configuration file listing every setting at its default
+    Init {
+        /// Replace an existing configuration file
+        #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+        force: bool,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+}
+
+impl ConfigCmd {
+    /// Run the chosen config subcommand.
+    pub async fn run(&self) -> AppResult<()> {
+        match self {
+            ConfigCmd::Init { force, .. } => {
+                let path = config::init(*force).await?;
+                info!("Wrote the configuration file to {}", path.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl GetVerbosity for ConfigCmd {
+    fn get_verbosity(&self) -> &Verbosity<InfoLevel> {
+        match self {
+            ConfigCmd::Init { verbosity, .. } => verbosity,
+        }
+    }
+}
+
+/// Subcommands of `daily-ai history`.
+#[derive(Subcommand, Debug, Clone)]
+pub enum HistoryCmd {
+    /// List the most recent runs with the first sentence of their summary
+    List {
+        /// How many runs to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: u64,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Show a stored run, given its run id or a date (YYYY-MM-DD) for that day's latest run
+    Show {
+        /// Run id or date
+        run: String,
+
+        /// How to print the run; rendered Markdown on a terminal and JSON otherwise by default
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+
+    /// Search stored summaries, notes, and every other summary entry for some text
+    Search {
+        /// Text to look for, ignoring case
+        text: String,
+
+        /// How many runs to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: u64,
+
+        #[command(flatten)]
+        verbosity: Verbosity<InfoLevel>,
+    },
+}
+
+impl HistoryCmd {
+    /// Run the chosen history subcommand.
+    pub async fn run(&self) -> AppResult<()> {
+        match self {
+            HistoryCmd::List { limit, .. } => history::print_list(*limit).await,
+            HistoryCmd::Show { run, format, .. } => history::show(run, format.as_ref()).await,
+            HistoryCmd::Search { text, limit, .. } => history::print_search(text, *limit).await,
+        }
+    }
+}
+
+impl GetVerbosity for HistoryCmd {
+    fn get_verbosity(&self) -> &Verbosity<InfoLevel> {
+        match self {
+            HistoryCmd::List { verbosity, .. }
+            | HistoryCmd::Show { verbosity, .. }
+            | HistoryCmd::Search { verbosity, .. } => verbosity,
+        }
+    }
 }
 
 /// Supported completion targets for shell auto-completion.
@@ -166,67 +701,117 @@ impl Generator for &CompletionShell {
     }
 }
 
-static SHELL_CMD_ABOUT: &str = "Collect shell history from atuin
-Requires atuin to be installed and configured
-See \x1b]8;;https://atuin.sh\x1b\\\x1b[4;36matuin.sh\x1b[24;39m\x1b]8;;\x1b\\ for more information";
-
-static SAFARI_CMD_ABOUT: &str = "Collect Safari browsing history
-Only works on macOS
-Requires access to the Safari history database
-See \x1b]8;;https://developer.apple.com/documentation/safariservices/safari_history\x1b\\\x1b[4;36mApple's developer documentation\x1b[24;39m\x1b]8;;\x1b\\ for more information
-Note: This command is a no-op on non-macOS systems";
+/// Collectors available to `summarize` and `collect`, in the order they run.
+///
+/// Collectors must come after the collectors they require. Each entry gets a `collect <name>`
+/// subcommand and can be toggled with `--enable`/`--disable`.
+static REGISTRY: &[CollectorSpec] = &[
+    CollectorSpec::of::<ShellCollector>(),
+    CollectorSpec::of::<SafariCollector>(),
+    CollectorSpec::of::<EdgeCollector>(),
+    CollectorSpec::of::<ChromeCollector>(),
+    CollectorSpec::of::<GitCollector>(),
+    CollectorSpec::of::<CiCollector>(),
+    CollectorSpec::of::<ReleasesCollector>(),
+    CollectorSpec::of::<MessagesCollector>(),
+    CollectorSpec::of::<TmuxCollector>(),
+    CollectorSpec::of::<ContainersCollector>(),
+    CollectorSpec::of::<IdeBuildsCollector>(),
+    CollectorSpec::of::<K8sCollector>(),
+    CollectorSpec::of::<HostsCollector>(),
+    CollectorSpec::of::<ClipboardCollector>(),
+    CollectorSpec::of::<NotesCollector>(),
+    CollectorSpec::of::<AppleNotesCollector>(),
+    CollectorSpec::of::<RecordingsCollector>(),
+    CollectorSpec::of::<TasksCollector>(),
+    CollectorSpec::of::<SlackCollector>(),
+    CollectorSpec::of::<EmailCollector>(),
+    CollectorSpec::of::<ExternalCollector>(),
+];
+
+/// Collectors that read browser history, chosen by a profile's `browsers` list.
+pub static BROWSER_COLLECTORS: &[&str] = &[
+    SafariCollector::NAME,
+    EdgeCollector::NAME,
+    ChromeCollector::NAME,
+];
+
+static ALL_CMD_ABOUT: &str = "Collect all enabled data sources
+Opt-in sources (messages, clipboard, apple-notes, tasks, slack, email) only run with --enable or their --with-* flag
+Use --disable to skip a source";
 
 /// Subcommands for collecting data without summarizing
 ///
 /// This is useful for debugging or if you want to inspect the collected data
-/// Each subcommand corresponds to a specific data source
+/// Each subcommand corresponds to a collector in the registry, plus `all`
 ///
 /// See the documentation for each subcommand for more information
-/// Subcommands for collecting data without summarizing.
-#[derive(Subcommand, Debug, Clone)]
-pub enum CollectCmd {
-    #[command(about = "Collect shell history from atuin", long_about = SHELL_CMD_ABOUT)]
-    Shell {
-        #[command(flatten)]
-        shell: ShellCollectArgs,
-        #[command(flatten)]
-        default: DefaultArgs,
-        #[command(flatten)]
-        verbosity: Verbosity<InfoLevel>,
-    },
+#[derive(Debug, Clone)]
+pub struct CollectCmd {
+    /// Name of the collector to run, or `all`
+    pub source: String,
+    pub args: CollectArgs,
+}
 
-    #[command(about = "Collect Safari browsing history", long_about = SAFARI_CMD_ABOUT)]
-    Safari {
-        #[command(flatten)]
-        default: DefaultArgs,
-        #[command(flatten)]
-        verbosity: Verbosity<InfoLevel>,
-    },
+impl FromArgMatches for CollectCmd {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let Some((source, matches)) = matches.subcommand() else {
+            return Err(clap::Error::raw(
+                ErrorKind::MissingSubcommand,
+                "a data source to collect is required",
+            ));
+        };
+        Ok(CollectCmd {
+            source: source.to_string(),
+            args: CollectArgs::from_arg_matches(matches)?,
+        })
+    }
 
-    /// Collect git commit history from local repositories
-    /// Based on the shell history collected from atuin
-    /// Requires git to be installed and accessible in your PATH
-    Git {
-        #[command(flatten)]
-        git: GitCollectArgs,
-        #[command(flatten)]
-        shell: ShellCollectArgs,
-        #[command(flatten)]
-        default: DefaultArgs,
-        #[command(flatten)]
-        verbosity: Verbosity<InfoLevel>,
-    },
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
 
-    /// Collect all data sources (shell history, Safari history, git history)
-    /// This is the default command
-    All {
-        #[command(flatten)]
-        shell: ShellCollectArgs,
-        #[command(flatten)]
-        default: DefaultArgs,
-        #[command(flatten)]
-        verbosity: Verbosity<InfoLevel>,
-    },
+impl Subcommand for CollectCmd {
+    fn augment_subcommands(cmd: Command) -> Command {
+        REGISTRY
+            .iter()
+            .fold(cmd, |cmd, spec| {
+                cmd.subcommand(
+                    CollectArgs::augment_args(Command::new(spec.name))
+                        .about(spec.about)
+                        .long_about(spec.long_about),
+                )
+            })
+            .subcommand(
+                CollectArgs::augment_args(Command::new("all"))
+                    .about("Collect all enabled data sources")
+                    .long_about(ALL_CMD_ABOUT),
+            )
+            .subcommand_required(true)
+    }
+
+    fn augment_subcommands_for_update(cmd: Command) -> Command {
+        Self::augment_subcommands(cmd)
+    }
+
+    fn has_subcommand(name: &str) -> bool {
+        name == "all" || REGISTRY.iter().any(|spec| spec.name == name)
+    }
+}
+
+/// Options for a `collect` subcommand.
+#[derive(Args, Debug, Clone)]
+pub struct CollectArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+    #[command(flatten)]
+    pub git: GitCollectArgs,
+    #[command(flatten)]
+    pub default: DefaultArgs,
+    #[command(flatten)]
+    pub verbosity: Verbosity<InfoLevel>,
 }
 
 static SHOW_CMD_ABOUT: &str = "Show the AI queries that are available.
@@ -243,7 +828,7 @@ pub enum Queries {
     LabelUrls(QueryArgs<LabelUrlsTools, LabelUrlsResponses>),
 
     /// Schemas for daily summary generation
-    Summary(QueryArgs<SummaryTools, SummaryResponses>),
+    Summary(QueryArgs<SummaryToolSchema, SummaryResponses>),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -407,36 +992,6 @@ impl PrintSchema for LabelUrlsResponses {
     }
 }
 
-#[derive(ValueEnum, Debug, Clone)]
-pub enum SummaryTools {
-    FetchUrl,
-    GetDiff,
-    GetRepo,
-    GetCommitMessages,
-    GetBrowserHistory,
-    GetShellHistory,
-}
-
-impl PrintSchema for SummaryTools {
-    fn print_schema(&self) -> String {
-        let val = match self {
-            Self::FetchUrl => ai::tools::fetch::FetchUrl::schema_value(),
-            Self::GetDiff => ai::tools::summary::GetDiff::schema_value(),
-            Self::GetRepo => ai::tools::summary::GetRepo::schema_value(),
-            Self::GetCommitMessages => ai::tools::summary::GetCommitMessages::schema_value(),
-            Self::GetBrowserHistory => ai::tools::summary::GetBrowserHistory::schema_value(),
-            Self::GetShellHistory => ai::tools::summary::GetShellHistory::schema_value(),
-        };
-        match serde_json::to_string_pretty(&val) {
-            Ok(s) => s,
-            Err(e) => {
-                error!("Failed to serialize schema for {:?}: {}", self, e);
-                std::process::exit(1);
-            }
-        }
-    }
-}
-
 #[derive(ValueEnum, Debug, Clone)]
 pub enum SummaryResponses {
     FullSummary,
@@ -446,6 +1001,7 @@ pub enum SummaryResponses {
     ShellOverview,
     TimeBreakdown,
     CommonGroups,
+    Learning,
 }
 
 impl PrintSchema for SummaryResponses {
@@ -458,6 +1014,7 @@ impl PrintSchema for SummaryResponses {
             Self::ShellOverview => ai::summary::ShellOverviewQuery::schema_value(),
             Self::TimeBreakdown => ai::summary::TimeBreakdownQuery::schema_value(),
             Self::CommonGroups => ai::summary::CommonGroupsQuery::schema_value(),
+            Self::Learning => ai::summary::LearningQuery::schema_value(),
         };
         match serde_json::to_string_pretty(&val) {
             Ok(s) => s,
@@ -469,15 +1026,252 @@ impl PrintSchema for SummaryResponses {
     }
 }
 
-/// Options controlling shell history collection.
+/// Options choosing which collectors run and how they are configured.
 #[derive(Args, Debug, Clone)]
-pub struct ShellCollectArgs {
-    /// Disable syncing atuin history before collecting
-    #[arg(long = "no-sync", default_value_t = true, action = ArgAction::SetFalse)]
-    pub sync: bool,
+pub struct SourceArgs {
+    /// Also run these collectors (repeatable, or comma-separated), including opt-in ones
+    #[arg(long, value_delimiter = ',', value_parser = collector_names())]
+    pub enable: Vec<String>,
+    /// Skip these collectors (repeatable, or comma-separated)
+    #[arg(long, value_delimiter = ',', value_parser = collector_names())]
+    pub disable: Vec<String>,
+    #[command(flatten)]
+    pub repos: RepoFilterArgs,
+    #[command(flatten)]
+    pub shell: ShellCollectArgs,
+    #[command(flatten)]
+    pub safari: SafariCollectArgs,
+    #[command(flatten)]
+    pub messages: MessagesCollectArgs,
+    #[command(flatten)]
+    pub k8s: K8sCollectArgs,
+    #[command(flatten)]
+    pub clipboard: ClipboardCollectArgs,
+    #[command(flatten)]
+    pub notes: NotesCollectArgs,
+    #[command(flatten)]
+    pub apple_notes: AppleNotesCollectArgs,
+    #[command(flatten)]
+    pub recordings: RecordingsCollectArgs,
+    #[command(flatten)]
+    pub tasks: TasksCollectArgs,
+    #[command(flatten)]
+    pub slack: SlackCollectArgs,
+    #[command(flatten)]
+    pub email: EmailCollectArgs,
+    #[command(flatten)]
+    pub external: ExternalCollectArgs,
 }
 
-/// Options controlling git history collection.
+impl SourceArgs {
+    /// Names of the collectors to run for `summarize` and `collect all`, in registry order.
+    fn selected(&self) -> Vec<&'static str> {
+        let opted_in = [
+            (MessagesCollector::NAME, self.messages.with_messages),
+            (ClipboardCollector::NAME, self.clipboard.with_clipboard),
+            (
+                AppleNotesCollector::NAME,
+                self.apple_notes.with_apple_notes || self.apple_notes.apple_notes_body,
+            ),
+            (TasksCollector::NAME, self.tasks.with_tasks),
+            (SlackCollector::NAME, self.slack.with_slack),
+            (EmailCollector::NAME, self.email.with_email),
+        ];
+        REGISTRY
+            .iter()
+            .filter(|spec| {
+                let enabled = spec.default_enabled
+                    || self.enable.iter().any(|name| name == spec.name)
+                    || opted_in.contains(&(spec.name, true));
+                enabled && !self.disable.iter().any(|name| name == spec.name)
+            })
+            .map(|spec| spec.name)
+            .collect()
+    }
+
+    /// Collector settings for a run that does not commit pending changes.
+    fn settings<'a>(&'a self, default: &'a DefaultArgs) -> CollectorSettings<'a> {
+        CollectorSettings {
+            default,
+            repos: RepoFilter::new(&self.repos.repos, &self.repos.exclude_repos),
+            shell_backend: self.shell.shell_backend,
+            sync: self.shell.sync,
+            auto_commit: false,
+            commit_language: None,
+            query_cluster: self.k8s.with_k8s,
+            safari_history_db: self.safari.safari_history_db.as_deref(),
+            notes_vault: self.notes.notes_vault.as_deref(),
+            recordings_dir: self.recordings.recordings_dir.as_deref(),
+            apple_notes_body: self.apple_notes.apple_notes_body,
+            external: &self.external.external_collectors,
+        }
+    }
+}
+
+/// Value parser accepting the names of registered collectors.
+fn collector_names() -> PossibleValuesParser {
+    PossibleValuesParser::new(REGISTRY.iter().map(|spec| spec.name))
+}
+
+/// Options choosing which git repositories are reported.
+#[derive(Args, Debug, Clone)]
+pub struct RepoFilterArgs {
+    /// Only report repositories whose name or path contains this text, ignoring case
+    /// (repeatable)
+    #[arg(long = "repo", value_name = "PATTERN")]
+    pub repos: Vec<String>,
+    /// Leave out repositories whose name or path contains this text, ignoring case (repeatable)
+    #[arg(long = "exclude-repo", value_name = "PATTERN")]
+    pub exclude_repos: Vec<String>,
+}
+
+/// Options controlling shell history collection.
+#[derive(Args, Debug, Clone)]
+pub struct ShellCollectArgs {
+    /// Where to read shell history from
+    ///
+    /// `auto` uses atuin when it is set up, otherwise every zsh, bash, and fish history file
+    /// found. History files have no working directories or exit codes, so directories are
+    /// estimated from `cd` commands
+    #[arg(long, value_enum, default_value_t = ShellBackend::Auto)]
+    pub shell_backend: ShellBackend,
+    /// Sync atuin history with its server before collecting (ignored with `--offline`)
+    ///
+    /// atuin syncs through its own HTTP client, which the network allowlist cannot check, so
+    /// this is off unless asked for
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub sync: bool,
+}
+
+/// Options controlling Safari collection.
+#[derive(Args, Debug, Clone)]
+pub struct SafariCollectArgs {
+    /// Safari `History.db` to read, e.g. a copy taken from another Mac
+    ///
+    /// Defaults to History.db in the current directory, the SAFARI_HISTORY_DB_PATH environment
+    /// variable, or ~/Library/Safari/History.db. A database given here is read on any platform
+    #[arg(long, value_name = "PATH")]
+    pub safari_history_db: Option<PathBuf>,
+}
+
+/// Options controlling Messages collection.
+#[derive(Args, Debug, Clone)]
+pub struct MessagesCollectArgs {
+    /// Include Messages.app conversation counts and time ranges (never message bodies)
+    ///
+    /// Requires Full Disk Access to read ~/Library/Messages/chat.db
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_messages: bool,
+}
+
+/// Options controlling clipboard manager collection.
+#[derive(Args, Debug, Clone)]
+pub struct ClipboardCollectArgs {
+    /// Include per-app copy counts from Maccy or Paste (never clipboard contents)
+    ///
+    /// Set MACCY_DB_PATH or PASTE_DB_PATH if the store is not in its default location
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_clipboard: bool,
+}
+
+/// Options controlling Apple Notes collection.
+#[derive(Args, Debug, Clone)]
+pub struct AppleNotesCollectArgs {
+    /// Include the titles and folders of Apple Notes created or edited in the window
+    ///
+    /// Requires Full Disk Access to read NoteStore.sqlite. Set APPLE_NOTES_DB_PATH if the store
+    /// is not in its default location
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_apple_notes: bool,
+    /// Also include the text of each note, never of locked ones (implies --with-apple-notes)
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub apple_notes_body: bool,
+}
+
+/// Options controlling completed-task collection.
+#[derive(Args, Debug, Clone)]
+pub struct TasksCollectArgs {
+    /// Include tasks completed in Things 3, OmniFocus, and Apple Reminders
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_tasks: bool,
+}
+
+/// Options controlling Slack collection.
+#[derive(Args, Debug, Clone)]
+pub struct SlackCollectArgs {
+    /// Include per-channel counts of the Slack messages you sent, never their text
+    ///
+    /// Requires a Slack user token with the search:read scope in SLACK_USER_TOKEN
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_slack: bool,
+}
+
+/// Options controlling email collection.
+#[derive(Args, Debug, Clone)]
+pub struct EmailCollectArgs {
+    /// Include per-thread email counts, sender addresses, and subject lines (never bodies)
+    ///
+    /// Reads Mail.app's Envelope Index (requires Full Disk Access), or an IMAP server when
+    /// IMAP_HOST, IMAP_USER, and IMAP_PASSWORD are set
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_email: bool,
+}
+
+/// Options configuring external collectors.
+#[derive(Args, Debug, Clone)]
+pub struct ExternalCollectArgs {
+    /// Run an external collector, given as NAME=COMMAND (repeatable)
+    ///
+    /// The command runs with `sh -c` and must print `{"activities": [...]}` as JSON on stdout.
+    /// See `daily-ai collect external --help` for the format
+    #[arg(long = "external-collector", value_name = "NAME=COMMAND", value_parser = parse_external_command)]
+    pub external_collectors: Vec<ExternalCommand>,
+}
+
+/// Options controlling notes vault collection.
+#[derive(Args, Debug, Clone)]
+pub struct NotesCollectArgs {
+    /// Markdown notes vault (e.g. an Obsidian vault) to scan for notes written in the window
+    ///
+    /// Defaults to the NOTES_VAULT environment variable; notes are skipped when neither is set
+    #[arg(long)]
+    pub notes_vault: Option<PathBuf>,
+}
+
+/// Options controlling asciinema recording collection.
+#[derive(Args, Debug, Clone)]
+pub struct RecordingsCollectArgs {
+    /// Directory holding asciinema `.cast` recordings to link from the journal
+    ///
+    /// Defaults to the ASCIINEMA_RECORDINGS_DIR environment variable; recordings are skipped
+    /// when neither is set
+    #[arg(long, value_name = "DIR")]
+    pub recordings_dir: Option<PathBuf>,
+}
+
+/// Options controlling Kubernetes collection.
+#[derive(Args, Debug, Clone)]
+pub struct K8sCollectArgs {
+    /// Query the current cluster (read-only `kubectl get`) for the context, namespaces, and
+    /// workloads touched by kubectl/helm commands in shell history
+    ///
+    /// Without this, or with `--offline`, Kubernetes activity is taken from shell history alone
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub with_k8s: bool,
+}
+
+/// Options controlling the language of generated commit messages.
+#[derive(Args, Debug, Clone)]
+pub struct CommitLanguageArgs {
+    /// Write commit summaries and bodies in this language (e.g. "German", "ja")
+    ///
+    /// Conventional-commit type tokens stay in English. Defaults to the repository's
+    /// `dailyai.commitLanguage` git config, then English
+    #[arg(long)]
+    pub commit_language: Option<String>,
+}
+
+/// Options controlling git history collection.
 #[derive(Args, Debug, Clone)]
 pub struct GitCollectArgs {
     /// Include shell history in output when collecting git commits
@@ -488,6 +1282,26 @@ pub struct GitCollectArgs {
 /// Common options shared across commands.
 #[derive(Args, Debug, Clone)]
 pub struct DefaultArgs {
+    /// Where to send requests to the language model
+    ///
+    /// `openai` and `anthropic` use the hosted APIs instead of --host and --port, with the key
+    /// in OPENAI_API_KEY or ANTHROPIC_API_KEY, or stored in the keychain under the `daily-ai`
+    /// service with the provider name as the account. Pick one of the provider's models with
+    /// --model. Anthropic only serves Chat Completions, so requests to it are translated from
+    /// the Responses API the other providers use
+    #[arg(long, value_enum, default_value_t)]
+    pub provider: Provider,
+
+    /// OpenAI organization to bill requests to. Defaults to the OPENAI_ORG_ID environment
+    /// variable
+    #[arg(long, value_name = "ID")]
+    pub openai_org: Option<String>,
+
+    /// OpenAI project to bill requests to. Defaults to the OPENAI_PROJECT_ID environment
+    /// variable
+    #[arg(long, value_name = "ID")]
+    pub openai_project: Option<String>,
+
     /// Whether to use secure connection (HTTPS) to the language model server
     /// Defaults to false for local servers (i.e. `localhost` and private subnets)
     /// Defaults to true for public IP addresses and hostnames
@@ -509,6 +1323,73 @@ pub struct DefaultArgs {
     #[arg(long, default_value = "v1")]
     pub api_version: String,
 
+    /// Model to ask on the language model server
+    #[arg(long, default_value = ai::DEFAULT_MODEL)]
+    pub model: String,
+
+    /// Model used to label clusters of browsing history, instead of --model
+    ///
+    /// Labelling is a short, simple request, so a small model is usually enough
+    #[arg(long, value_name = "MODEL")]
+    pub label_model: Option<String>,
+
+    /// Model used to write commit messages, instead of --model
+    #[arg(long, value_name = "MODEL")]
+    pub commit_model: Option<String>,
+
+    /// Model used for the summary, stand-up update, and the reports and answers written from
+    /// collected work, instead of --model
+    #[arg(long, value_name = "MODEL")]
+    pub summary_model: Option<String>,
+
+    /// Model used to summarize large diffs and, when more than ten repositories were worked in,
+    /// each repository before the summary, instead of --model
+    #[arg(long, value_name = "MODEL")]
+    pub digest_model: Option<String>,
+
+    /// Context window of the model, in tokens
+    ///
+    /// Half is set aside for the collected data and an eighth for each tool result; the longest
+    /// lists (shell history, repositories, commits, URLs) are trimmed to fit
+    #[arg(long, value_name = "TOKENS", default_value_t = ai::token_budget::DEFAULT_CONTEXT_WINDOW)]
+    pub context_window: usize,
+
+    /// Hugging Face model used to embed browsing history before grouping it
+    ///
+    /// Downloaded to the cache directory on first use
+    #[arg(long, default_value = classify::DEFAULT_EMBEDDING_MODEL)]
+    pub embedding_model: String,
+
+    /// How many texts the local embedding model embeds in one pass
+    ///
+    /// Larger batches are faster on a GPU but use more memory
+    #[arg(
+        long,
+        value_name = "TEXTS",
+        default_value_t = classify::DEFAULT_EMBEDDING_BATCH_SIZE,
+        value_parser = parse_batch_size
+    )]
+    pub embedding_batch_size: usize,
+
+    /// Where browsing history and highlights are embedded
+    ///
+    /// `local` runs --embedding-model on this machine, and uses `openai` instead when the model
+    /// cannot be loaded. `openai` sends the text to the OpenAI-compatible `/embeddings` endpoint
+    /// of the language model server (--provider, or --host and --port), and `ollama` to the
+    /// `/api/embeddings` endpoint of the Ollama server at --ollama-url
+    #[arg(long, value_enum, default_value_t)]
+    pub embedder: EmbedderKind,
+
+    /// Model asked for embeddings by the `openai` and `ollama` embedders
+    ///
+    /// Defaults to text-embedding-3-small for `openai` and nomic-embed-text for `ollama`
+    #[arg(long, value_name = "MODEL")]
+    pub remote_embedding_model: Option<String>,
+
+    /// URL of the Ollama server used by `--embedder ollama`
+    #[arg(long, value_name = "URL", default_value = embed::DEFAULT_OLLAMA_URL)]
+    pub ollama_url: String,
+
     /// Duration (since now) of history to summarize
     ///
     /// Some valid suffixes are:
@@ -523,17 +1404,193 @@ pub struct DefaultArgs {
     pub duration: Option<String>,
 
     /// Output format for the summary
-    #[arg(short, long, value_enum, default_value_t = OutputFormat::Json)]
-    pub format: OutputFormat,
+    ///
+    /// Defaults to the format implied by the `--output` extension (`.json`, `.jsonl`, `.md`,
+    /// `.txt`, `.pdf`, `.org`, `.db`/`.sqlite`, or a trailing `/` for a directory), then JSON
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Column to wrap `--format text` at
+    #[arg(long, value_name = "COLUMNS", default_value_t = render::text::DEFAULT_WIDTH)]
+    pub text_width: usize,
 
     /// Output file to write the summary to
     /// If not provided, prints to stdout
+    ///
+    /// May contain `{{date}}`, `{{time}}`, `{{year}}`, `{{month}}`, `{{day}}`, `{{week}}`,
+    /// `{{weekday}}`, and `{{profile}}`, e.g. `~/journal/{{date}}-summary.md`
     #[arg(short, long)]
     pub output: Option<PathBuf>,
+
+    /// Render the output with a minijinja (Jinja2-style) template instead of a built-in format
+    ///
+    /// The template receives `summary` (the AI summary, when there is one), `sources` (each
+    /// collected source's items by name, e.g. `sources.commit_history`), `data_quality`,
+    /// `failed_collectors`, `run_id`, `markdown` (the Markdown output), `date`, and `time`.
+    /// Values are HTML-escaped when the template's name ends in `.html`
+    #[arg(long, value_name = "PATH", conflicts_with = "format")]
+    pub template: Option<PathBuf>,
+
+    /// Run without network access other than the language model server
+    ///
+    /// Skips the atuin sync, the CI status lookup, Jira and Linear tickets, Slack, IMAP, and
+    /// the cluster queries of `--with-k8s`, never downloads the embedding model, does not give
+    /// the model the `fetch_url` tool, and cannot be combined with `--email-to`. Fails before
+    /// collecting if the embedding model has not been downloaded yet. `--embedder ollama` still
+    /// reaches the Ollama server at `--ollama-url`, which may be another host
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub offline: bool,
+
+    /// Let the model's `fetch_url` tool fetch any URL, not only pages on sites in the collected
+    /// browser history
+    ///
+    /// By default the tool refuses other sites, so the model cannot send what it has read to a
+    /// host of its choosing
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue, conflicts_with = "offline")]
+    pub allow_any_url: bool,
+
+    /// Ask the model again instead of reusing its cached answers
+    ///
+    /// Answers are cached for 30 days under the cache directory, keyed by the model, the prompt,
+    /// and the data sent, so re-running a summary for the same day only asks about what changed
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub no_cache: bool,
+
+    /// Load the model as the run starts, and ping it this often until the first question, e.g.
+    /// `4m`
+    ///
+    /// Local servers unload a model after a few idle minutes, so without this the first question
+    /// after a long collection waits for the model to load again. Pick an interval shorter than
+    /// the server's idle timeout
+    #[arg(long, value_name = "INTERVAL", value_parser = humantime::parse_duration)]
+    pub keep_alive: Option<std::time::Duration>,
+
+    /// Exit with status 3 instead of 0 when no collector finds anything
+    ///
+    /// Lets automation tell a quiet day apart from a broken collection, which exits with
+    /// status 2
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub fail_on_empty: bool,
+
+    /// Reuse the ID of an earlier run instead of assigning a new one
+    ///
+    /// Pass the `run_id` of a failed or interrupted run when retrying it, so that anything it
+    /// already exported is updated in place rather than posted twice
+    #[arg(long, value_name = "ULID")]
+    pub run_id: Option<Ulid>,
+
+    /// Send and save collected data without redacting secrets
+    ///
+    /// By default AWS keys, API tokens, passwords, private keys, credentials in URLs, email
+    /// addresses, and IP addresses are replaced with `[REDACTED]` in shell commands, patches,
+    /// and URLs before they reach the language model or the output
+    #[arg(long, default_value_t = false, action = ArgAction::SetTrue)]
+    pub no_redact: bool,
+
+    /// Also redact text matching this regular expression. May be given more than once
+    #[arg(long = "redact", value_name = "REGEX", value_parser = redact::parse_rule, conflicts_with = "no_redact")]
+    pub redact_rules: Vec<String>,
 }
 
 impl DefaultArgs {
+    /// The model to ask for `task`: its override when one was given, otherwise `--model`.
+    pub fn model_for(&self, task: ModelTask) -> &str {
+        let model = match task {
+            ModelTask::LabelUrls => &self.label_model,
+            ModelTask::CommitMessage => &self.commit_model,
+            ModelTask::Summary => &self.summary_model,
+            ModelTask::Digest => &self.digest_model,
+        };
+        model.as_deref().unwrap_or(&self.model)
+    }
+
+    /// How much of `--context-window` the collected data and tool results may use.
+    pub fn token_budget(&self) -> TokenBudget {
+        TokenBudget::new(self.context_window)
+    }
+
+    /// The format the run is written or printed in: `--format`, or what `--output` implies.
+    pub fn output_format(&self) -> AppResult<Option<OutputFormat>> {
+        match &self.output {
+            Some(output) => io_utils::resolve_output_format(output, self.format.as_ref()).map(Some),
+            None => Ok(self.format.clone()),
+        }
+    }
+
+    /// The `--run-id` to reuse, or a fresh one.
+    pub fn run_id(&self) -> Ulid {
+        // Not `unwrap_or_default`: the default ULID is the nil ID, not a new one.
+        match self.run_id {
+            Some(run_id) => run_id,
+            None => Ulid::new(),
+        }
+    }
+
+    /// The redaction pass for collected data, or `None` with `--no-redact`.
+    pub fn redactor(&self) -> Option<Redactor> {
+        (!self.no_redact).then(|| Redactor::new(&self.redact_rules))
+    }
+
     pub fn get_client(&self) -> Client<Box<dyn Config>> {
+        let api_base = self.api_base();
+        let mut config = OpenAIConfig::default().with_api_base(&api_base);
+        if let Some(api_key) = self.provider.api_key() {
+            config = config.with_api_key(api_key);
+        } else if let Some((variable, account)) = self.provider.key_source() {
+            warn!(
+                "No API key found: set {variable} or store one in the keychain under the daily-ai service and the {account} account"
+            );
+        }
+        let org = self
+            .openai_org
+            .clone()
+            .or_else(|| std::env::var("OPENAI_ORG_ID").ok());
+        if let Some(org) = org {
+            config = config.with_org_id(org);
+        }
+        let project = self
+            .openai_project
+            .clone()
+            .or_else(|| std::env::var("OPENAI_PROJECT_ID").ok());
+        if let Some(project) = project {
+            config = config.with_project_id(project);
+        }
+
+        let client = Client::with_config(Box::new(config) as Box<dyn Config>);
+        match http::model_server_client(&api_base) {
+            Ok(http_client) => client.with_http_client(http_client),
+            Err(e) => {
+                warn!("Unable to apply the proxy settings to the model server client: {e}");
+                client
+            }
+        }
+    }
+
+    /// Which embedder to use, and how to reach it, for `--embedder`.
+    pub fn embedder(&self) -> EmbedderSettings {
+        EmbedderSettings {
+            kind: self.embedder,
+            local_model: self.embedding_model.clone(),
+            batch_size: self.embedding_batch_size,
+            remote_model: self.remote_embedding_model.clone(),
+            offline: self.offline,
+            api_base: self.api_base(),
+            api_key: self.provider.api_key(),
+            ollama_url: self.ollama_url.clone(),
+        }
+    }
+
+    /// API base of the language model server: the hosted provider's, or the server at `--host`
+    /// and `--port`.
+    fn api_base(&self) -> String {
+        match self.provider.api_base() {
+            Some(api_base) => api_base.to_string(),
+            None => self.local_api_base(),
+        }
+    }
+
+    /// API base of the server at `--host` and `--port`, over HTTPS unless the host looks local.
+    fn local_api_base(&self) -> String {
         let schema = if let Some(secure) = self.secure {
             if secure { "https" } else { "http" }
         } else if self.host == "localhost"
@@ -556,12 +1613,10 @@ impl DefaultArgs {
         } else {
             "https"
         };
-        let config = Box::new(OpenAIConfig::default().with_api_base(format!(
+        format!(
             "{schema}://{}:{}/{}",
             self.host, self.port, self.api_version
-        ))) as Box<dyn Config>;
-
-        Client::with_config(config)
+        )
     }
 }
 
@@ -583,24 +1638,49 @@ impl GetDefaultArgs for Cmd {
         match self {
             Cmd::Summarize { default, .. } => default,
             Cmd::Collect { cmd } => cmd.get_default_args(),
+            Cmd::Commit { default, .. } => default,
+            Cmd::DraftPost { default, .. } => default,
+            Cmd::Ask { default, .. } => default,
+            Cmd::Chat { default, .. } => default,
+            Cmd::Rollup { default, .. } => default,
+            Cmd::Timesheet { default, .. } => default,
             Cmd::Show { .. } => {
                 panic!("Show command does not have default args")
             }
+            Cmd::Config { .. } => {
+                panic!("Config command does not have default args")
+            }
             Cmd::Completion { .. } => {
                 panic!("Completion command does not have default args")
             }
+            Cmd::Purge { .. } => {
+                panic!("Purge command does not have default args")
+            }
+            Cmd::Dashboard { .. } => {
+                panic!("Dashboard command does not have default args")
+            }
+            Cmd::Feed { .. } => {
+                panic!("Feed command does not have default args")
+            }
+            Cmd::Diff { .. } => {
+                panic!("Diff command does not have default args")
+            }
+            Cmd::History { .. } => {
+                panic!("History command does not have default args")
+            }
+            Cmd::Stats { .. } => {
+                panic!("Stats command does not have default args")
+            }
+            Cmd::Status { .. } => {
+                panic!("Status command does not have default args")
+            }
         }
     }
 }
 
 impl GetDefaultArgs for CollectCmd {
     fn get_default_args(&self) -> &DefaultArgs {
-        match self {
-            CollectCmd::Shell { default, .. } => default,
-            CollectCmd::Safari { default, .. } => default,
-            CollectCmd::Git { default, .. } => default,
-            CollectCmd::All { default, .. } => default,
-        }
+        &self.args.default
     }
 }
 
@@ -609,20 +1689,29 @@ impl GetVerbosity for Cmd {
         match self {
             Cmd::Summarize { verbosity, .. } => verbosity,
             Cmd::Collect { cmd } => cmd.get_verbosity(),
+            Cmd::Commit { verbosity, .. } => verbosity,
+            Cmd::DraftPost { verbosity, .. } => verbosity,
+            Cmd::Ask { verbosity, .. } => verbosity,
+            Cmd::Chat { verbosity, .. } => verbosity,
+            Cmd::Rollup { verbosity, .. } => verbosity,
+            Cmd::Timesheet { verbosity, .. } => verbosity,
+            Cmd::Config { cmd } => cmd.get_verbosity(),
             Cmd::Completion { verbosity, .. } => verbosity,
             Cmd::Show { query } => query.get_verbosity(),
+            Cmd::Purge { verbosity, .. } => verbosity,
+            Cmd::Dashboard { verbosity, .. } => verbosity,
+            Cmd::Feed { verbosity, .. } => verbosity,
+            Cmd::Diff { verbosity, .. } => verbosity,
+            Cmd::History { cmd } => cmd.get_verbosity(),
+            Cmd::Stats { verbosity, .. } => verbosity,
+            Cmd::Status { verbosity, .. } => verbosity,
         }
     }
 }
 
 impl GetVerbosity for CollectCmd {
     fn get_verbosity(&self) -> &Verbosity<InfoLevel> {
-        match self {
-            CollectCmd::Shell { verbosity, .. } => verbosity,
-            CollectCmd::Safari { verbosity, .. } => verbosity,
-            CollectCmd::Git { verbosity, .. } => verbosity,
-            CollectCmd::All { verbosity, .. } => verbosity,
-        }
+        &self.args.verbosity
     }
 }
 
@@ -636,6 +1725,54 @@ impl GetVerbosity for Queries {
     }
 }
 
+/// Parse a `NAME=COMMAND` external collector argument.
+fn parse_external_command(value: &str) -> Result<ExternalCommand, String> {
+    match value.split_once('=') {
+        Some((name, command)) if !name.trim().is_empty() && !command.trim().is_empty() => {
+            Ok(ExternalCommand {
+                name: name.trim().to_string(),
+                command: command.to_string(),
+            })
+        }
+        _ => Err("expected NAME=COMMAND, e.g. toggl=~/bin/toggl-today".into()),
+    }
+}
+
+/// With `--offline`, fail before collecting anything if a selected collector needs a file that
+/// would otherwise be downloaded.
+fn check_offline_assets(selected: &[&str], settings: &CollectorSettings<'_>) -> AppResult<()> {
+    if settings.default.offline
+        && settings.default.embedder == EmbedderKind::Local
+        && selected
+            .iter()
+            .any(|name| BROWSER_COLLECTORS.contains(name))
+    {
+        classify::ensure_embedding_model_cached(&settings.default.embedding_model)?;
+    }
+    Ok(())
+}
+
+/// Parse a `YYYY-MM-DD` date argument.
+fn parse_date(value: &str) -> Result<Date, String> {
+    Date::parse(value, format_description!("[year]-[month]-[day]"))
+        .map_err(|e| format!("expected a date like 2025-01-31: {e}"))
+}
+
+/// Parse a batch size argument, which must be at least 1.
+fn parse_batch_size(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a humantime period argument such as `30d`.
+fn parse_period(value: &str) -> Result<Duration, String> {
+    let period = humantime::parse_duration(value).map_err(|e| e.to_string())?;
+    Duration::try_from(period).map_err(|e| e.to_string())
+}
+
 fn get_duration(duration_str: &Option<String>) -> Duration {
     duration_str
         .as_ref()
@@ -650,20 +1787,192 @@ fn get_duration(duration_str: &Option<String>) -> Duration {
 }
 
 impl Cmd {
+    /// Common options of the commands whose result is written to `--output`.
+    pub fn output_args(&self) -> Option<&DefaultArgs> {
+        match self {
+            Cmd::Summarize { default, .. } => Some(default),
+            Cmd::Collect { cmd } => Some(cmd.get_default_args()),
+            _ => None,
+        }
+    }
+
+    /// The `--tone` and `--length` presets for the summary prompts.
+    pub fn summary_presets(&self) -> (Option<Tone>, Option<Length>) {
+        match self {
+            Cmd::Summarize { tone, length, .. } => (*tone, *length),
+            _ => (None, None),
+        }
+    }
+
+    /// Whether `--allow-any-url` was given.
+    pub fn allow_any_url(&self) -> bool {
+        match self {
+            Cmd::Summarize { default, .. }
+            | Cmd::Commit { default, .. }
+            | Cmd::DraftPost { default, .. }
+            | Cmd::Ask { default, .. }
+            | Cmd::Chat { default, .. }
+            | Cmd::Rollup { default, .. }
+            | Cmd::Timesheet { default, .. } => default.allow_any_url,
+            Cmd::Collect { cmd } => cmd.get_default_args().allow_any_url,
+            _ => false,
+        }
+    }
+
+    /// The `--provider` to send requests to the language model to.
+    pub fn provider(&self) -> Provider {
+        match self {
+            Cmd::Summarize { default, .. }
+            | Cmd::Commit { default, .. }
+            | Cmd::DraftPost { default, .. }
+            | Cmd::Ask { default, .. }
+            | Cmd::Chat { default, .. }
+            | Cmd::Rollup { default, .. }
+            | Cmd::Timesheet { default, .. } => default.provider,
+            Cmd::Collect { cmd } => cmd.get_default_args().provider,
+            _ => Provider::default(),
+        }
+    }
+
+    /// Where to email the run, from `--email-to` and the SMTP settings.
+    pub fn email_exporter(&self) -> AppResult<Option<EmailExporter>> {
+        let Cmd::Summarize { email_to, .. } = self else {
+            return Ok(None);
+        };
+        if email_to.is_empty() {
+            return Ok(None);
+        }
+        let config = SmtpConfig::load().ok_or_else(|| {
+            AppError::Config(
+                "--email-to needs an SMTP server; set host in the [smtp] table of the \
+                 configuration file, or SMTP_HOST"
+                    .into(),
+            )
+        })?;
+        Ok(Some(EmailExporter::new(config, email_to.clone())))
+    }
+
+    /// Where to POST the run, from `--webhook`.
+    pub fn webhook_exporters(&self) -> AppResult<Vec<WebhookExporter>> {
+        let Cmd::Summarize { webhook, .. } = self else {
+            return Ok(Vec::new());
+        };
+        webhook
+            .iter()
+            .map(|url| WebhookExporter::new(url.clone()))
+            .collect()
+    }
+
     /// Execute the chosen top-level command.
+    ///
+    /// Returns the context to write out, or `None` when the command has no output.
     #[tracing::instrument(name = "Running command", level = "info", skip(self))]
-    pub async fn run(&self) -> AppResult<FullContext> {
+    pub async fn run(&self) -> AppResult<Option<FullContext>> {
         match self {
             Cmd::Summarize {
-                shell: ShellCollectArgs { sync },
-                default: DefaultArgs { duration, .. },
+                sources,
+                default,
+                auto_commit,
+                language: CommitLanguageArgs { commit_language },
+                archive,
+                ..
+            } => {
+                let client = self.get_client();
+                let settings = CollectorSettings {
+                    auto_commit: *auto_commit,
+                    commit_language: commit_language.as_deref(),
+                    ..sources.settings(default)
+                };
+                let run_id = default.run_id();
+                let (context, data_quality) = warnings::scope(run_status::scope(
+                    run_id.to_string(),
+                    self.run_summarize(
+                        &client,
+                        &sources.selected(),
+                        &settings,
+                        get_duration(&default.duration),
+                    ),
+                ))
+                .await;
+                let context = FullContext {
+                    run_id: Some(run_id),
+                    data_quality,
+                    ..context?
+                };
+                if *archive {
+                    archive::save(&context).await?;
+                    history::save(&context).await?;
+                }
+                Ok(Some(context))
+            }
+            Cmd::Collect { cmd } => {
+                let run_id = cmd.get_default_args().run_id();
+                let (context, data_quality) =
+                    warnings::scope(run_status::scope(run_id.to_string(), cmd.run())).await;
+                Ok(Some(FullContext {
+                    run_id: Some(run_id),
+                    data_quality,
+                    ..context?.into()
+                }))
+            }
+            Cmd::Commit {
+                amend,
+                language: CommitLanguageArgs { commit_language },
+                default,
                 ..
             } => {
                 let client = self.get_client();
-                self.run_summarize(&client, *sync, get_duration(duration))
-                    .await
+                let repo = git2::Repository::open_from_env()?;
+                let redactor = default.redactor();
+                git::commit::commit_staged(
+                    &client,
+                    default.model_for(ModelTask::CommitMessage),
+                    &repo,
+                    *amend,
+                    CommitMessageOptions {
+                        language: commit_language.as_deref(),
+                        redactor: redactor.as_ref(),
+                        budget: default.token_budget(),
+                    },
+                )
+                .await?;
+                Ok(None)
+            }
+            Cmd::Diff {
+                first,
+                second,
+                format,
+                output,
+                ..
+            } => {
+                compare::compare(first, second, format.as_ref(), output.as_deref()).await?;
+                Ok(None)
+            }
+            Cmd::DraftPost {
+                topic,
+                date,
+                period,
+                default,
+                ..
+            } => {
+                let client = self.get_client();
+                draft::draft_post(
+                    &client,
+                    default.model_for(ModelTask::Summary),
+                    topic,
+                    *date,
+                    *period,
+                    default.offline,
+                    default.token_budget(),
+                    default.output.as_deref(),
+                )
+                .await?;
+                Ok(None)
+            }
+            Cmd::Config { cmd } => {
+                cmd.run().await?;
+                Ok(None)
             }
-            Cmd::Collect { cmd } => Ok(cmd.run().await?.into()),
             Cmd::Completion { shell, output, .. } => {
                 let mut cmd = Cli::command();
                 if let Some(output_path) = output {
@@ -683,11 +1992,156 @@ impl Cmd {
                     // Fallback: print completion script to stdout.
                     generate(shell, &mut cmd, "daily-ai", &mut std::io::stdout());
                 }
-                std::process::exit(0);
+                Ok(None)
             }
             Cmd::Show { query } => {
                 query.run();
-                std::process::exit(0);
+                Ok(None)
+            }
+            Cmd::Purge {
+                before,
+                sources,
+                drop_summaries,
+                paranoid,
+                ..
+            } => {
+                let options = PurgeOptions {
+                    before: *before,
+                    sources: sources.clone(),
+                    drop_summaries: *drop_summaries,
+                    paranoid: *paranoid,
+                };
+                let report = archive::purge(&options).await?;
+                info!(
+                    "Scrubbed {} archived run(s) and removed {}",
+                    report.scrubbed, report.removed
+                );
+                let report = history::purge(&options).await?;
+                info!(
+                    "Scrubbed {} run(s) in the history database and removed {}",
+                    report.scrubbed, report.removed
+                );
+                let removed = ai::cache::purge(*before, *paranoid).await?;
+                info!("Removed {removed} cached model response(s)");
+                if *paranoid {
+                    let shredded = classify::shred_embedding_cache().await?;
+                    info!("Shredded {} cached embedding(s)", shredded);
+                }
+                Ok(None)
+            }
+            Cmd::Feed { output, count, .. } => {
+                feed::generate_feed(output, *count).await?;
+                Ok(None)
+            }
+            Cmd::Dashboard { output, .. } => {
+                dashboard::generate_dashboard(output).await?;
+                Ok(None)
+            }
+            Cmd::History { cmd } => {
+                cmd.run().await?;
+                Ok(None)
+            }
+            Cmd::Ask {
+                question,
+                run,
+                default,
+                ..
+            } => {
+                let client = self.get_client();
+                let redactor = default.redactor();
+                ask::ask(
+                    &client,
+                    default.model_for(ModelTask::Summary),
+                    question,
+                    run.as_deref(),
+                    AskOptions {
+                        offline: default.offline,
+                        budget: default.token_budget(),
+                        redactor: redactor.as_ref(),
+                    },
+                    default.output.as_deref(),
+                )
+                .await?;
+                Ok(None)
+            }
+            Cmd::Chat { run, default, .. } => {
+                if !std::io::stdin().is_terminal() {
+                    return Err(AppError::Other(
+                        "chat needs a terminal; use `daily-ai ask` for a single question"
+                            .to_string(),
+                    ));
+                }
+                let client = self.get_client();
+                let redactor = default.redactor();
+                ask::chat(
+                    &client,
+                    default.model_for(ModelTask::Summary),
+                    run.as_deref(),
+                    AskOptions {
+                        offline: default.offline,
+                        budget: default.token_budget(),
+                        redactor: redactor.as_ref(),
+                    },
+                )
+                .await?;
+                Ok(None)
+            }
+            Cmd::Rollup {
+                period, default, ..
+            } => {
+                let client = self.get_client();
+                rollup::rollup(
+                    &client,
+                    default.model_for(ModelTask::Summary),
+                    *period,
+                    default.output.as_deref(),
+                    default.token_budget(),
+                )
+                .await?;
+                Ok(None)
+            }
+            Cmd::Timesheet {
+                period,
+                projects,
+                rates,
+                default_rate,
+                currency,
+                describe,
+                csv,
+                default,
+                ..
+            } => {
+                let client = self.get_client();
+                let options = TimesheetOptions {
+                    period: *period,
+                    projects,
+                    rates,
+                    default_rate: *default_rate,
+                    currency: currency.clone(),
+                    describe: *describe,
+                    csv: *csv,
+                };
+                timesheet::timesheet(
+                    &client,
+                    default.model_for(ModelTask::Summary),
+                    options,
+                    default.output.as_deref(),
+                )
+                .await?;
+                Ok(None)
+            }
+            Cmd::Stats {
+                period,
+                format,
+                heatmap,
+                ..
+            } => {
+                stats::show(*period, *format, *heatmap).await?;
+                Ok(None)
+            }
+            Cmd::Status { json, .. } => {
+                status::show(*json).await?;
+                Ok(None)
             }
         }
     }
@@ -695,112 +2149,156 @@ impl Cmd {
     #[tracing::instrument(
         name = "Collecting and summarizing history",
         level = "info",
-        skip(self, client)
+        skip(self, client, settings)
     )]
     async fn run_summarize<C: Config>(
         &self,
         client: &Client<C>,
-        sync: bool,
+        selected: &[&str],
+        settings: &CollectorSettings<'_>,
         duration: Duration,
     ) -> AppResult<FullContext> {
-        // Collect shell, Safari, and git history, then return the aggregated context.
-        let shell_history = shell::get_history(sync, &duration).await?;
-
-        let safari_history =
-            classify::embed_urls(client, safari::get_safari_history(&duration).await?).await?;
-
-        let commit_history = git::get_git_history(client, &shell_history, &duration).await?;
-
-        let ctx = Context {
-            shell_history,
-            safari_history,
-            commit_history,
+        check_offline_assets(selected, settings)?;
+        let mut models = vec![settings.default.model_for(ModelTask::Summary)];
+        if selected
+            .iter()
+            .any(|name| BROWSER_COLLECTORS.contains(name))
+        {
+            models.push(settings.default.model_for(ModelTask::LabelUrls));
+        }
+        models.dedup();
+        let ctx = keep_alive::keep_warm(
+            client,
+            &models,
+            settings.default.keep_alive,
+            run_collectors(REGISTRY, selected, settings, duration),
+        )
+        .await?;
+
+        let interactive = matches!(
+            self,
+            Cmd::Summarize {
+                interactive: true,
+                ..
+            }
+        );
+        if interactive && !std::io::stdin().is_terminal() {
+            return Err(AppError::Other(
+                "--interactive needs a terminal to review the summary in".to_string(),
+            ));
+        }
+        let leads = learning::find_for_run(&ctx).await;
+        let model = settings.default.model_for(ModelTask::Summary);
+        let redactor = settings.default.redactor();
+        run_status::start("summary").await;
+        let summary = ai::summary::generate_summary(
+            client,
+            model,
+            &ctx,
+            leads,
+            SummaryOptions {
+                digest_model: settings.default.model_for(ModelTask::Digest),
+                offline: settings.default.offline,
+                budget: settings.default.token_budget(),
+                interactive,
+                redactor: redactor.as_ref(),
+            },
+            &ResponseCache::open(settings.default.no_cache).await,
+        )
+        .await;
+        let mut summary = match summary {
+            Ok(summary) => summary,
+            Err(e) => {
+                run_status::fail("summary", &e).await;
+                return Err(e);
+            }
         };
+        run_status::finish("summary", Some(summary.highlights.len())).await;
+        if let Cmd::Summarize {
+            repeated_highlights,
+            highlight_lookback,
+            ..
+        } = self
+        {
+            run_status::start("highlights").await;
+            highlights::dedupe_run(
+                &mut summary,
+                &settings.default.embedder(),
+                *highlight_lookback,
+                *repeated_highlights,
+            )
+            .await;
+            run_status::finish("highlights", Some(summary.highlights.len())).await;
+        }
+        if let Cmd::Summarize {
+            proofread: true, ..
+        } = self
+        {
+            run_status::start("proofread").await;
+            let model = settings.default.model_for(ModelTask::LabelUrls);
+            proofread::proofread_run(client, model, &mut summary).await;
+            run_status::finish("proofread", None).await;
+        }
+        if let Cmd::Summarize { verify_claims, .. } = self {
+            verify::check_summary(&mut summary, &ctx, *verify_claims)?;
+        }
+        if let Cmd::Summarize { tags: true, .. } = self {
+            run_status::start("tags").await;
+            let model = settings.default.model_for(ModelTask::LabelUrls);
+            tags::tag_run(client, model, &ctx, &mut summary).await;
+            run_status::finish("tags", Some(summary.tags.len())).await;
+        }
+        if settings.default.output_format()? == Some(OutputFormat::Standup) {
+            run_status::start("standup").await;
+            summary.standup = Some(ai::standup::generate_standup(client, model, &summary).await?);
+            run_status::finish("standup", None).await;
+        }
 
-        let summary = ai::summary::generate_summary(client, &ctx).await?;
-
-        Ok(FullContext::from((ctx, summary)))
+        let mut context = FullContext::from((ctx, summary));
+        run_status::start("pace").await;
+        let pace = pace::assess_run(&context).await;
+        run_status::finish("pace", Some(pace.len())).await;
+        if let Some(summary) = &mut context.summary {
+            summary.pace = pace.iter().map(ToString::to_string).collect();
+        }
+        Ok(context)
     }
 }
 
 impl CollectCmd {
+    /// Names of the collectors this subcommand runs, in registry order.
+    fn selected(&self) -> Vec<&'static str> {
+        if self.source == "all" {
+            return self.args.sources.selected();
+        }
+        REGISTRY
+            .iter()
+            .map(|spec| spec.name)
+            .filter(|name| {
+                *name == self.source
+                    || (self.source == GitCollector::NAME
+                        && self.args.git.with_shell_history
+                        && *name == ShellCollector::NAME)
+            })
+            .collect()
+    }
+
     /// Execute the specific collect subcommand without summarization.
     #[tracing::instrument(name = "Collecting history", level = "info", skip(self))]
     pub async fn run(&self) -> AppResult<Context> {
-        match self {
-            CollectCmd::Shell {
-                shell: ShellCollectArgs { sync },
-                default: DefaultArgs { duration, .. },
-                ..
-            } => {
-                let duration = get_duration(duration);
-                let shell_history = shell::get_history(*sync, &duration).await?;
-                Ok(Context {
-                    shell_history,
-                    safari_history: vec![],
-                    commit_history: vec![],
-                })
-            }
-            CollectCmd::Safari {
-                default: DefaultArgs { duration, .. },
-                ..
-            } => {
-                let client = self.get_client();
-                let duration = get_duration(duration);
-                let safari_history =
-                    classify::embed_urls(&client, safari::get_safari_history(&duration).await?)
-                        .await?;
-                Ok(Context {
-                    shell_history: vec![],
-                    safari_history,
-                    commit_history: vec![],
-                })
-            }
-            CollectCmd::Git {
-                shell: ShellCollectArgs { sync },
-                git: GitCollectArgs { with_shell_history },
-                default: DefaultArgs { duration, .. },
-                ..
-            } => {
-                let client = self.get_client();
-                let duration = get_duration(duration);
-                let shell_history = shell::get_history(*sync, &duration).await?;
-                let commit_history =
-                    git::get_git_history(&client, &shell_history, &duration).await?;
-                let shell_history = if *with_shell_history {
-                    shell_history
-                } else {
-                    vec![]
-                };
-                Ok(Context {
-                    shell_history,
-                    safari_history: vec![],
-                    commit_history,
-                })
-            }
-            CollectCmd::All {
-                shell: ShellCollectArgs { sync },
-                default: DefaultArgs { duration, .. },
-                ..
-            } => {
-                let client = self.get_client();
-                let duration = get_duration(duration);
-                let shell_history = shell::get_history(*sync, &duration).await?;
-
-                let safari_history =
-                    classify::embed_urls(&client, safari::get_safari_history(&duration).await?)
-                        .await?;
-
-                let commit_history =
-                    git::get_git_history(&client, &shell_history, &duration).await?;
-
-                Ok(Context {
-                    shell_history,
-                    safari_history,
-                    commit_history,
-                })
-            }
-        }
+        let CollectArgs {
+            sources, default, ..
+        } = &self.args;
+        let selected = self.selected();
+        let settings = sources.settings(default);
+        check_offline_assets(&selected, &settings)?;
+        run_collectors(
+            REGISTRY,
+            &selected,
+            &settings,
+            get_duration(&default.duration),
+        )
+        .await
     }
 }
 
@@ -0,0 +1,284 @@
+use std::env;
+use std::path::PathBuf;
+
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, FromQueryResult,
+    Statement,
+};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::time_utils::{macos_past_ts, macos_to_datetime};
+use crate::warnings::{self, WarningKind};
+
+/// Where a clipboard manager keeps its history and which columns hold the source app and date.
+///
+/// Both managers use Core Data stores, so dates are seconds since 2001. Only the app and date
+/// columns are ever selected; clipboard contents are never read.
+struct ClipboardStore {
+    manager: &'static str,
+    /// Environment variable overriding the store path.
+    env: &'static str,
+    /// Store path relative to the home directory.
+    path: &'static str,
+    /// Candidate table/column names, tried in order. Paste does not document its schema, so
+    /// several known layouts are probed.
+    tables: &'static [&'static str],
+    app_columns: &'static [&'static str],
+    date_columns: &'static [&'static str],
+    /// Column counting repeated copies of the same item, if the store keeps one. The counter
+    /// covers the item's whole lifetime, so an item copied before the window too is over-counted.
+    count_column: Option<&'static str>,
+}
+
+const STORES: [ClipboardStore; 2] = [
+    ClipboardStore {
+        manager: "Maccy",
+        env: "MACCY_DB_PATH",
+        path: "Library/Containers/org.p0deje.Maccy/Data/Library/Application Support/Maccy/Storage.sqlite",
+        tables: &["ZHISTORYITEM"],
+        app_columns: &["ZAPPLICATION"],
+        date_columns: &["ZLASTCOPIEDAT"],
+        count_column: Some("ZNUMBEROFCOPIES"),
+    },
+    ClipboardStore {
+        manager: "Paste",
+        env: "PASTE_DB_PATH",
+        path: "Library/Containers/com.wiheads.paste/Data/Library/Application Support/Paste/Paste.sqlite",
+        tables: &["ZSNIPPET", "ZPASTEITEM", "ZITEM"],
+        app_columns: &[
+            "ZAPPLICATIONIDENTIFIER",
+            "ZAPPLICATIONBUNDLEIDENTIFIER",
+            "ZSOURCEAPPLICATION",
+            "ZAPPLICATION",
+        ],
+        date_columns: &["ZDATE", "ZCREATIONDATE", "ZCREATEDAT", "ZTIMESTAMP"],
+        count_column: None,
+    },
+];
+
+/// Copy counts for one source application.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClipboardActivity {
+    /// Clipboard manager the counts were read from.
+    pub manager: String,
+    /// Bundle identifier or name of the app the content was copied from.
+    pub app: String,
+    pub copies: i64,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_copy: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_copy: OffsetDateTime,
+}
+
+/// Raw aggregate row for one app.
+#[derive(Debug, FromQueryResult)]
+struct ClipboardRow {
+    app: String,
+    copies: i64,
+    first_copy: f64,
+    last_copy: f64,
+}
+
+/// Table and columns found in a store.
+#[derive(Debug, PartialEq, Eq)]
+struct ResolvedSchema {
+    table: &'static str,
+    app: &'static str,
+    date: &'static str,
+    count: Option<&'static str>,
+}
+
+/// Column names of `table`, or an empty list when it does not exist.
+async fn table_columns(db: &DatabaseConnection, table: &str) -> AppResult<Vec<String>> {
+    #[derive(FromQueryResult)]
+    struct Column {
+        name: String,
+    }
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "SELECT name FROM pragma_table_info(?)",
+        [table.into()],
+    );
+    Ok(Column::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|c| c.name.to_uppercase())
+        .collect())
+}
+
+/// Find the first candidate table that has an app and a date column.
+async fn resolve_schema(
+    db: &DatabaseConnection,
+    store: &ClipboardStore,
+) -> AppResult<Option<ResolvedSchema>> {
+    for table in store.tables {
+        let columns = table_columns(db, table).await?;
+        let find = |candidates: &[&'static str]| {
+            candidates
+                .iter()
+                .copied()
+                .find(|c| columns.iter().any(|col| col == c))
+        };
+        if let (Some(app), Some(date)) = (find(store.app_columns), find(store.date_columns)) {
+            return Ok(Some(ResolvedSchema {
+                table,
+                app,
+                date,
+                count: store.count_column.and_then(|c| find(&[c])),
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Count copies per source app since `since` (seconds since 2001).
+async fn fetch_activity(
+    db: &DatabaseConnection,
+    store: &ClipboardStore,
+    since: f64,
+) -> AppResult<Vec<ClipboardActivity>> {
+    let Some(schema) = resolve_schema(db, store).await? else {
+        warnings::record(
+            WarningKind::Skipped,
+            "clipboard",
+            format!(
+                "{} store has an unrecognised schema; skipping clipboard history",
+                store.manager
+            ),
+        );
+        return Ok(Vec::new());
+    };
+    trace!("Resolved {} schema: {:?}", store.manager, schema);
+    // Identifiers come from the fixed candidate lists above, never from the store itself.
+    let sql = format!(
+        "SELECT COALESCE(NULLIF({app}, ''), 'unknown') AS app,
+            SUM({count}) AS copies,
+            MIN({date}) * 1.0 AS first_copy,
+            MAX({date}) * 1.0 AS last_copy
+        FROM {table}
+        WHERE {date} > ?
+        GROUP BY 1
+        ORDER BY copies DESC",
+        app = schema.app,
+        count = schema
+            .count
+            .map(|c| format!("MAX(COALESCE({c}, 1), 1)"))
+            .unwrap_or_else(|| "1".into()),
+        date = schema.date,
+        table = schema.table,
+    );
+    let stmt = Statement::from_sql_and_values(db.get_database_backend(), sql, [since.into()]);
+    Ok(ClipboardRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| ClipboardActivity {
+            manager: store.manager.to_string(),
+            app: row.app,
+            copies: row.copies,
+            first_copy: macos_to_datetime(row.first_copy),
+            last_copy: macos_to_datetime(row.last_copy),
+        })
+        .collect())
+}
+
+/// Resolve a store's path, honouring its environment override.
+fn store_path(store: &ClipboardStore) -> Option<PathBuf> {
+    if let Ok(path) = env::var(store.env) {
+        return Some(PathBuf::from(path));
+    }
+    env::home_dir()
+        .or_else(|| env::var("HOME").ok().map(PathBuf::from))
+        .map(|home| home.join(store.path))
+}
+
+/// Collect per-app copy counts from installed clipboard managers (Maccy and Paste).
+///
+/// Stores are opened read-only and only source apps and timestamps are queried; clipboard
+/// contents are never read. Managers that are not installed are skipped.
+#[tracing::instrument(name = "Collecting clipboard activity", level = "info")]
+pub async fn get_clipboard_activity(duration: &Duration) -> AppResult<Vec<ClipboardActivity>> {
+    let since = macos_past_ts(duration);
+    let mut activity = Vec::new();
+    for store in &STORES {
+        let Some(path) = store_path(store).filter(|p| p.exists()) else {
+            debug!("{} is not installed", store.manager);
+            continue;
+        };
+        let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=ro", path.display()));
+        opt.sqlx_logging(false);
+        let db = Database::connect(opt).await?;
+        activity.extend(fetch_activity(&db, store, since).await?);
+    }
+    debug!("Found clipboard activity for {} apps", activity.len());
+    Ok(activity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn counts_copies_per_app_without_reading_contents() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            "CREATE TABLE ZHISTORYITEM (Z_PK INTEGER PRIMARY KEY, ZAPPLICATION TEXT, ZLASTCOPIEDAT TIMESTAMP, ZNUMBEROFCOPIES INTEGER, ZTITLE TEXT)",
+            "INSERT INTO ZHISTORYITEM VALUES
+                (1, 'com.apple.Terminal', 700000000, 3, 'secret'),
+                (2, 'com.apple.Terminal', 700000600, 1, 'token'),
+                (3, 'com.apple.Safari', 700000300, NULL, 'url'),
+                (4, NULL, 700000900, 1, 'x'),
+                (5, 'com.apple.Safari', 500000000, 1, 'old')",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+
+        let activity = fetch_activity(&db, &STORES[0], 600_000_000.0)
+            .await
+            .unwrap();
+
+        assert_eq!(activity.len(), 3);
+        assert_eq!(activity[0].app, "com.apple.Terminal");
+        assert_eq!(activity[0].copies, 4);
+        assert_eq!(
+            activity[0].last_copy - activity[0].first_copy,
+            Duration::minutes(10)
+        );
+        assert!(activity.iter().any(|a| a.app == "unknown"));
+        assert!(
+            activity
+                .iter()
+                .all(|a| !serde_json::to_string(a).unwrap().contains("secret"))
+        );
+    }
+
+    #[tokio::test]
+    async fn probes_alternative_layouts_and_skips_unknown_ones() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        db.execute_unprepared(
+            "CREATE TABLE ZITEM (Z_PK INTEGER PRIMARY KEY, ZSOURCEAPPLICATION TEXT, ZDATE TIMESTAMP)",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            resolve_schema(&db, &STORES[1]).await.unwrap(),
+            Some(ResolvedSchema {
+                table: "ZITEM",
+                app: "ZSOURCEAPPLICATION",
+                date: "ZDATE",
+                count: None,
+            })
+        );
+        assert!(
+            fetch_activity(&db, &STORES[0], 0.0)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
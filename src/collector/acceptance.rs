@@ -0,0 +1,305 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use atuin_client::database::Sqlite;
+use clap::Parser;
+use git2::{Repository, Signature};
+use reqwest::Url;
+use sea_orm::{ConnectionTrait, Database};
+use time::{Duration, OffsetDateTime};
+use tokio::process::Command;
+
+use crate::ai::stub_server::{StubServer, text_response};
+use crate::cli::Cli;
+use crate::time_utils::datetime_to_macos_time;
+
+/// Session every fixture command ran in, also exported as `ATUIN_SESSION`.
+const SESSION: &str = "0190f5c8a6b47c3e9d2f1a0b3c4d5e6f";
+
+/// Holds the fixture directory in the environment of the test process that runs the collectors.
+const FIXTURES_VAR: &str = "DAILY_AI_ACCEPTANCE_FIXTURES";
+
+/// Full name of the test below, to run it on its own in that process.
+const TEST_NAME: &str = "collector::acceptance::collects_every_source_end_to_end";
+
+/// Label the stub model server gives every group of pages.
+const LABEL: &str = "git2 documentation";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+    let dir = std::env::temp_dir().join(format!("{name}_{nonce}"));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A repository in `dir` with two commits made just now.
+fn fixture_repo(dir: &Path) {
+    let repo = Repository::init(dir).unwrap();
+    let sig = Signature::now("Test", "test@example.com").unwrap();
+    let mut parent = None;
+    for (file, content, message) in [
+        ("parser.rs", "fn parse() {}\n", "Add parser"),
+        (
+            "parser.rs",
+            "fn parse() -> bool { true }\n",
+            "Fix parser bug",
+        ),
+    ] {
+        fs::write(dir.join(file), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents: Vec<_> = parent.iter().collect();
+        let oid = repo
+            .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+        parent = Some(repo.find_commit(oid).unwrap());
+    }
+}
+
+/// An atuin history database at `db_path` with commands run in `cwd`: three recent ones, one
+/// deleted, and one from two days ago.
+async fn fixture_atuin(db_path: &Path, cwd: &Path) {
+    // Creating the database through atuin runs its migrations, so the schema is the real one.
+    Sqlite::new(db_path, 5.0).await.unwrap();
+    let db = Database::connect(format!("sqlite://{}", db_path.display()))
+        .await
+        .unwrap();
+    let now = OffsetDateTime::now_utc();
+    let ago = |minutes: i64| (now - Duration::minutes(minutes)).unix_timestamp_nanos();
+    for (id, timestamp, command, deleted_at) in [
+        ("a1", ago(90), "cargo new parser", None),
+        ("a2", ago(60), "cargo test", None),
+        ("a3", ago(30), "git commit -m 'Fix parser bug'", None),
+        ("a4", ago(20), "echo deleted", Some(ago(10))),
+        ("a5", ago(2 * 24 * 60), "echo too old", None),
+    ] {
+        db.execute_unprepared(&format!(
+            "INSERT INTO history
+                (id, timestamp, duration, exit, command, cwd, session, hostname, deleted_at)
+             VALUES ('{id}', {timestamp}, 1000000, 0, '{}', '{}', '{SESSION}', 'laptop:test', {})",
+            command.replace('\'', "''"),
+            cwd.display(),
+            deleted_at.map_or("NULL".to_string(), |at| at.to_string()),
+        ))
+        .await
+        .unwrap();
+    }
+    db.close().await.unwrap();
+}
+
+/// Point atuin at `db_path` through a settings file in `config_dir`.
+fn atuin_settings(config_dir: &Path, db_path: &Path) {
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "db_path = \"{}\"\nrecord_store_path = \"{}\"\nkey_path = \"{}\"\n",
+            db_path.display(),
+            config_dir.join("records.db").display(),
+            config_dir.join("key").display(),
+        ),
+    )
+    .unwrap();
+}
+
+/// A Safari `History.db` at `db_path` with a recent page, a sign-in page, and an old page.
+async fn fixture_safari(db_path: &Path) {
+    let db = Database::connect(format!("sqlite://{}?mode=rwc", db_path.display()))
+        .await
+        .unwrap();
+    let now = OffsetDateTime::now_utc();
+    let at = |minutes: i64| datetime_to_macos_time(&(now - Duration::minutes(minutes)));
+    for sql in [
+        "CREATE TABLE history_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL UNIQUE,
+            domain_expansion TEXT NULL,
+            visit_count INTEGER NOT NULL,
+            daily_visit_counts BLOB NOT NULL,
+            weekly_visit_counts BLOB NULL,
+            autocomplete_triggers BLOB NULL,
+            should_recompute_derived_visit_counts INTEGER NOT NULL,
+            visit_count_score INTEGER NOT NULL,
+            status_code INTEGER NOT NULL DEFAULT 0
+        )"
+        .to_string(),
+        "CREATE TABLE history_visits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            history_item INTEGER NOT NULL REFERENCES history_items(id) ON DELETE CASCADE,
+            visit_time REAL NOT NULL,
+            title TEXT NULL,
+            load_successful BOOLEAN NOT NULL DEFAULT 1,
+            http_non_get BOOLEAN NOT NULL DEFAULT 0,
+            synthesized BOOLEAN NOT NULL DEFAULT 0,
+            redirect_source INTEGER NULL UNIQUE REFERENCES history_visits(id) ON DELETE CASCADE,
+            redirect_destination INTEGER NULL UNIQUE REFERENCES history_visits(id) ON DELETE CASCADE,
+            origin INTEGER NOT NULL DEFAULT 0,
+            generation INTEGER NOT NULL DEFAULT 0,
+            attributes INTEGER NOT NULL DEFAULT 0,
+            score INTEGER NOT NULL DEFAULT 0
+        )"
+        .to_string(),
+        "INSERT INTO history_items
+            (id, url, visit_count, daily_visit_counts, should_recompute_derived_visit_counts,
+             visit_count_score)
+         VALUES
+            (1, 'https://docs.rs/git2', 3, x'', 0, 0),
+            (2, 'https://login.example.com/sso', 1, x'', 0, 0),
+            (3, 'https://old.example.com/', 1, x'', 0, 0)"
+            .to_string(),
+        format!(
+            "INSERT INTO history_visits (history_item, visit_time, title) VALUES
+                (1, {}, 'git2 - Rust'), (2, {}, 'Sign in'), (3, {}, 'Old')",
+            at(45),
+            at(40),
+            at(3 * 24 * 60),
+        ),
+    ] {
+        db.execute_unprepared(&sql).await.unwrap();
+    }
+    db.close().await.unwrap();
+}
+
+/// Runs `collect all` over the fixtures, with a stub server in place of the language model, so
+/// schema changes and regressions in the collectors show up here.
+///
+/// atuin only finds its settings and shell session through environment variables, which cannot
+/// be changed while other tests run. The collectors run in a second test process instead,
+/// started with those variables and a home directory of its own.
+#[tokio::test]
+async fn collects_every_source_end_to_end() {
+    match std::env::var_os(FIXTURES_VAR) {
+        Some(dir) => collect_all(Path::new(&dir)).await,
+        None => collect_all_in_own_process().await,
+    }
+}
+
+/// Build the fixtures and run this test again in a process that collects from them.
+async fn collect_all_in_own_process() {
+    let dir = temp_dir("acceptance");
+    let repo_dir = dir.join("parser");
+    fs::create_dir_all(&repo_dir).unwrap();
+    fixture_repo(&repo_dir);
+    let atuin_db = dir.join("atuin.db");
+    fixture_atuin(&atuin_db, &repo_dir).await;
+    atuin_settings(&dir, &atuin_db);
+    fixture_safari(&dir.join("History.db")).await;
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([TEST_NAME, "--exact", "--nocapture"])
+        .env_clear()
+        .envs(std::env::var_os("PATH").map(|path| ("PATH", path)))
+        .env("HOME", &dir)
+        .env("ATUIN_CONFIG_DIR", &dir)
+        .env("ATUIN_SESSION", SESSION)
+        .env(FIXTURES_VAR, &dir)
+        .output()
+        .await
+        .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success() && stdout.contains("1 passed"),
+        "{stdout}{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Run `collect all` over the fixtures in `dir` and check the context it returns.
+async fn collect_all(dir: &Path) {
+    let server = StubServer::start(|_| text_response(&format!(r#"{{"label": "{LABEL}"}}"#))).await;
+    let port = Url::parse(&server.api_base).unwrap().port().unwrap();
+    let repo_dir = dir.join("parser");
+    let safari_db = dir.join("History.db");
+    let cli = Cli::try_parse_from([
+        "daily-ai",
+        "collect",
+        "all",
+        "--host",
+        "127.0.0.1",
+        "--port",
+        &port.to_string(),
+        "--no-cache",
+        "--shell-backend",
+        "atuin",
+        "--safari-history-db",
+        safari_db.to_str().unwrap(),
+        // These read the machine rather than files in the fixture directory.
+        "--disable",
+        "edge,chrome,tmux,containers,ide-builds",
+    ])
+    .unwrap();
+
+    let context = cli.cmd.run().await.unwrap().unwrap().context;
+
+    assert!(
+        context.failed_collectors.is_empty(),
+        "{:?}",
+        context.failed_collectors
+    );
+    let mut commands: Vec<&str> = context
+        .shell_history()
+        .iter()
+        .map(|entry| entry.command.as_str())
+        .collect();
+    commands.sort_unstable();
+    assert_eq!(
+        commands,
+        [
+            "cargo new parser",
+            "cargo test",
+            "git commit -m 'Fix parser bug'"
+        ]
+    );
+    assert!(
+        context
+            .shell_history()
+            .iter()
+            .all(|entry| entry.directory == repo_dir && entry.session_id == SESSION)
+    );
+
+    let [repo] = &context.commit_history()[..] else {
+        panic!(
+            "expected one repository, got {:?}",
+            context.commit_history()
+        );
+    };
+    let mut summaries: Vec<&str> = repo
+        .commits
+        .iter()
+        .map(|commit| commit.summary.as_str())
+        .collect();
+    summaries.sort_unstable();
+    assert_eq!(summaries, ["Add parser", "Fix parser bug"]);
+    assert!(
+        repo.diff
+            .added
+            .iter()
+            .chain(&repo.diff.modified)
+            .any(|file| file.path == Path::new("parser.rs"))
+    );
+    assert!(context.tickets().is_empty());
+
+    let [group] = context.safari_history() else {
+        panic!("expected one group, got {:?}", context.safari_history());
+    };
+    assert_eq!(group.label, LABEL);
+    let [page] = &group.urls[..] else {
+        panic!("expected one page, got {:?}", group.urls);
+    };
+    assert_eq!(page.url, "https://docs.rs/git2");
+    assert_eq!(page.title.as_deref(), Some("git2 - Rust"));
+    assert_eq!(page.visit_count, 3);
+
+    // Only the page worth a label reached the model.
+    let requests = server.requests();
+    let [request] = &requests[..] else {
+        panic!("expected one request, got {requests:?}");
+    };
+    assert_eq!(request.path, "/v1/responses");
+    let body = request.body.to_string();
+    assert!(body.contains("https://docs.rs/git2"));
+    assert!(!body.contains("login.example.com") && !body.contains("old.example.com"));
+}
@@ -0,0 +1,649 @@
+use std::path::PathBuf;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use time::Duration;
+
+use super::{Collector, CollectorOutput, CollectorSettings};
+use crate::ai::ModelTask;
+use crate::ai::commit_message::CommitMessageOptions;
+use crate::ai::token_budget::TokenBudget;
+use crate::chromium::ChromiumBrowser;
+use crate::classify::embed::EmbedderSettings;
+use crate::context::Context;
+use crate::external::ExternalCommand;
+use crate::git::RepoFilter;
+use crate::redact::Redactor;
+use crate::safari::SafariHistoryItem;
+use crate::shell::ShellBackend;
+use crate::warnings::{self, WarningKind};
+use crate::{
+    AppResult, apple_notes, chromium, ci, classify, clipboard, containers, email, external, git,
+    hosts, ide_builds, k8s, messages, notes, recordings, releases, safari, shell, slack, tasks,
+    tickets, tmux,
+};
+
+/// Shell history from atuin or shell history files.
+pub struct ShellCollector {
+    backend: ShellBackend,
+    sync: bool,
+}
+
+impl Collector for ShellCollector {
+    const NAME: &'static str = "shell";
+    const ABOUT: &'static str = "Collect shell history from atuin or shell history files";
+    const LONG_ABOUT: &'static str = "Collect shell history from atuin or shell history files
+Uses atuin when it is installed and configured, otherwise ~/.zsh_history, ~/.bash_history, and
+fish_history (pick one with --shell-backend)
+See \x1b]8;;https://atuin.sh\x1b\\\x1b[4;36matuin.sh\x1b[24;39m\x1b]8;;\x1b\\ for more information";
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        ShellCollector {
+            backend: settings.shell_backend,
+            sync: settings.sync && !settings.default.offline,
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default()
+            .with_shell_history(shell::get_history(self.backend, self.sync, &window).await?))
+    }
+}
+
+/// Embeds browsing history and groups it with the language model, for the browser collectors.
+struct BrowserGrouping {
+    client: Client<Box<dyn Config>>,
+    model: String,
+    embedder: EmbedderSettings,
+    offline: bool,
+    /// Applied before the history is grouped, since grouping sends page titles to the model.
+    redactor: Option<Redactor>,
+}
+
+impl BrowserGrouping {
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        BrowserGrouping {
+            client: settings.default.get_client(),
+            model: settings.default.model_for(ModelTask::LabelUrls).to_string(),
+            embedder: settings.default.embedder(),
+            offline: settings.default.offline,
+            redactor: settings.default.redactor(),
+        }
+    }
+
+    async fn group(&self, mut history: Vec<SafariHistoryItem>) -> AppResult<CollectorOutput> {
+        if history.is_empty() {
+            return Ok(Context::default());
+        }
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_urls(&mut history);
+        }
+        let history = classify::embed_urls(
+            &self.client,
+            &self.model,
+            &self.embedder,
+            history,
+            self.offline,
+        )
+        .await?;
+        Ok(Context::default().with_safari_history(history))
+    }
+}
+
+/// Safari browsing history, embedded and clustered by the language model.
+pub struct SafariCollector {
+    grouping: BrowserGrouping,
+    history_db: Option<PathBuf>,
+}
+
+impl Collector for SafariCollector {
+    const NAME: &'static str = "safari";
+    const ABOUT: &'static str = "Collect Safari browsing history";
+    const LONG_ABOUT: &'static str = "Collect Safari browsing history
+Only works on macOS
+Requires access to the Safari history database
+See \x1b]8;;https://developer.apple.com/documentation/safariservices/safari_history\x1b\\\x1b[4;36mApple's developer documentation\x1b[24;39m\x1b]8;;\x1b\\ for more information
+Note: This command is a no-op on non-macOS systems unless --safari-history-db is given";
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        SafariCollector {
+            grouping: BrowserGrouping::new(settings),
+            history_db: settings.safari_history_db.map(PathBuf::from),
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        if !cfg!(target_os = "macos") && self.history_db.is_none() {
+            return Ok(Context::default());
+        }
+        let history = safari::get_safari_history(self.history_db.as_deref(), &window).await?;
+        self.grouping.group(history).await
+    }
+}
+
+/// Microsoft Edge browsing history, embedded and clustered by the language model.
+pub struct EdgeCollector {
+    grouping: BrowserGrouping,
+}
+
+impl Collector for EdgeCollector {
+    const NAME: &'static str = "edge";
+    const ABOUT: &'static str = "Collect Microsoft Edge browsing history";
+    const LONG_ABOUT: &'static str = "Collect Microsoft Edge browsing history
+Reads the default profile under %LOCALAPPDATA% on Windows, Application Support on macOS, and
+~/.config elsewhere; set EDGE_HISTORY_DB_PATH to read another profile's History file
+Runs by default on Windows; elsewhere enable it with --enable edge or the browsers setting";
+    const DEFAULT_ENABLED: bool = cfg!(windows);
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        EdgeCollector {
+            grouping: BrowserGrouping::new(settings),
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        let history = chromium::get_chromium_history(ChromiumBrowser::Edge, &window).await?;
+        self.grouping.group(history).await
+    }
+}
+
+/// Google Chrome browsing history, embedded and clustered by the language model.
+pub struct ChromeCollector {
+    grouping: BrowserGrouping,
+}
+
+impl Collector for ChromeCollector {
+    const NAME: &'static str = "chrome";
+    const ABOUT: &'static str = "Collect Google Chrome browsing history";
+    const LONG_ABOUT: &'static str = "Collect Google Chrome browsing history
+Reads the default profile under %LOCALAPPDATA% on Windows, Application Support on macOS, and
+~/.config elsewhere; set CHROME_HISTORY_DB_PATH to read another profile's History file
+Runs by default on Windows; elsewhere enable it with --enable chrome or the browsers setting";
+    const DEFAULT_ENABLED: bool = cfg!(windows);
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        ChromeCollector {
+            grouping: BrowserGrouping::new(settings),
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        let history = chromium::get_chromium_history(ChromiumBrowser::Chrome, &window).await?;
+        self.grouping.group(history).await
+    }
+}
+
+/// Git commits in repositories visited in the shell history, and the tickets they reference.
+pub struct GitCollector {
+    /// Set when pending changes should be committed before reading history.
+    client: Option<Client<Box<dyn Config>>>,
+    model: String,
+    commit_language: Option<String>,
+    /// Applied to the changes the model reads while writing commit messages.
+    redactor: Option<Redactor>,
+    budget: TokenBudget,
+    repos: RepoFilter,
+    offline: bool,
+}
+
+impl Collector for GitCollector {
+    const NAME: &'static str = "git";
+    const ABOUT: &'static str = "Collect git commit history from local repositories";
+    const LONG_ABOUT: &'static str = "Collect git commit history from local repositories
+Based on the shell history collected from atuin
+Requires git to be installed and accessible in your PATH
+Jira and Linear tickets named in commits are skipped with --offline";
+    const REQUIRES: &'static [&'static str] = &[ShellCollector::NAME];
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        GitCollector {
+            client: settings.auto_commit.then(|| settings.default.get_client()),
+            model: settings
+                .default
+                .model_for(ModelTask::CommitMessage)
+                .to_string(),
+            commit_language: settings.commit_language.map(str::to_string),
+            redactor: settings.default.redactor(),
+            budget: settings.default.token_budget(),
+            repos: settings.repos.clone(),
+            offline: settings.default.offline,
+        }
+    }
+
+    async fn collect(&self, window: Duration, collected: &Context) -> AppResult<CollectorOutput> {
+        if let Some(client) = &self.client {
+            git::commit::commit_pending_changes(
+                client,
+                &self.model,
+                collected.shell_history(),
+                CommitMessageOptions {
+                    language: self.commit_language.as_deref(),
+                    redactor: self.redactor.as_ref(),
+                    budget: self.budget,
+                },
+            )
+            .await?;
+        }
+        let mut commit_history = git::get_git_history(collected.shell_history(), &window).await?;
+        commit_history.retain(|repo| self.repos.allows(repo));
+        let tickets = if self.offline {
+            Vec::new()
+        } else {
+            tickets::get_ticket_activity(&commit_history, &window).await?
+        };
+        Ok(Context::default()
+            .with_commit_history(commit_history)
+            .with_tickets(tickets))
+    }
+}
+
+/// GitHub Actions status of the commits found by the git collector.
+pub struct CiCollector {
+    offline: bool,
+}
+
+impl Collector for CiCollector {
+    const NAME: &'static str = "ci";
+    const ABOUT: &'static str = "Collect the GitHub Actions status of the day's commits";
+    const LONG_ABOUT: &'static str = "Collect the GitHub Actions status of the day's commits
+Reports the latest run of each workflow per branch for commits made during the window
+Set GITHUB_TOKEN or GH_TOKEN to read private repositories; GitHub Enterprise Server is checked
+when GH_HOST names its host
+Skipped with --offline";
+    const REQUIRES: &'static [&'static str] = &[GitCollector::NAME];
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        CiCollector {
+            offline: settings.default.offline,
+        }
+    }
+
+    async fn collect(&self, window: Duration, collected: &Context) -> AppResult<CollectorOutput> {
+        if self.offline {
+            return Ok(Context::default());
+        }
+        Ok(Context::default()
+            .with_ci_runs(ci::get_ci_runs(collected.commit_history(), &window).await?))
+    }
+}
+
+/// Tags, published packages, and GitHub releases.
+pub struct ReleasesCollector;
+
+impl Collector for ReleasesCollector {
+    const NAME: &'static str = "releases";
+    const ABOUT: &'static str = "Collect releases: new git tags and package publishes";
+    const LONG_ABOUT: &'static str = "Collect releases: new git tags and package publishes
+Reads tags created in the repositories found by the git collector, and cargo publish, npm
+publish, and gh release create runs from shell history";
+    const REQUIRES: &'static [&'static str] = &[ShellCollector::NAME, GitCollector::NAME];
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        ReleasesCollector
+    }
+
+    async fn collect(&self, window: Duration, collected: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_releases(
+            releases::get_releases(
+                collected.shell_history(),
+                collected.commit_history(),
+                &window,
+            )
+            .await?,
+        ))
+    }
+}
+
+/// Messages.app conversation counts.
+pub struct MessagesCollector;
+
+impl Collector for MessagesCollector {
+    const NAME: &'static str = "messages";
+    const ABOUT: &'static str =
+        "Collect Messages.app conversation metadata (counts and time ranges, no message bodies)";
+    const LONG_ABOUT: &'static str =
+        "Collect Messages.app conversation metadata (counts and time ranges, no message bodies)
+Only works on macOS and requires Full Disk Access";
+    const DEFAULT_ENABLED: bool = false;
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        MessagesCollector
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_messages(messages::get_messages_activity(&window).await?))
+    }
+}
+
+/// tmux and zellij sessions and windows.
+pub struct TmuxCollector;
+
+impl Collector for TmuxCollector {
+    const NAME: &'static str = "tmux";
+    const ABOUT: &'static str =
+        "Collect tmux and zellij sessions, window titles, and attached durations";
+    const LONG_ABOUT: &'static str =
+        "Collect tmux and zellij sessions, window titles, and attached durations
+Reads the running tmux server, or the latest tmux-resurrect save when no server is running, and
+the session layouts zellij caches
+Session and window names are used as project hints for the shell sessions run inside them";
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        TmuxCollector
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_tmux(tmux::get_tmux_sessions(&window).await?))
+    }
+}
+
+/// Docker and Podman container activity.
+pub struct ContainersCollector;
+
+impl Collector for ContainersCollector {
+    const NAME: &'static str = "containers";
+    const ABOUT: &'static str = "Collect Docker and Podman container runs, builds, and pulls";
+    const LONG_ABOUT: &'static str = "Collect Docker and Podman container runs, builds, and pulls
+Requires docker or podman to be installed and its daemon or socket to be reachable";
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        ContainersCollector
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_containers(containers::get_container_activity(&window).await?))
+    }
+}
+
+/// Builds and test runs from Xcode, Gradle, and IntelliJ logs.
+pub struct IdeBuildsCollector;
+
+impl Collector for IdeBuildsCollector {
+    const NAME: &'static str = "ide-builds";
+    const ABOUT: &'static str =
+        "Collect builds and test runs from Xcode, Gradle, and IntelliJ logs";
+    const LONG_ABOUT: &'static str =
+        "Collect builds and test runs from Xcode, Gradle, and IntelliJ logs
+Reads Xcode's DerivedData log manifests, Gradle daemon logs, and JetBrains IDE logs
+Set XCODE_DERIVED_DATA or GRADLE_USER_HOME to read them from another location";
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        IdeBuildsCollector
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_ide_builds(ide_builds::get_ide_builds(&window).await?))
+    }
+}
+
+/// kubectl/helm activity from the shell history.
+pub struct K8sCollector {
+    query_cluster: bool,
+    /// `--with-k8s` was given with `--offline`, so the cluster is left alone.
+    skip_query: bool,
+}
+
+impl Collector for K8sCollector {
+    const NAME: &'static str = "k8s";
+    const ABOUT: &'static str =
+        "Collect kubectl/helm activity from shell history, grouped by context and namespace";
+    const LONG_ABOUT: &'static str =
+        "Collect kubectl/helm activity from shell history, grouped by context and namespace
+Optionally queries the cluster for the workloads touched, except with --offline";
+    const REQUIRES: &'static [&'static str] = &[ShellCollector::NAME];
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        K8sCollector {
+            query_cluster: settings.query_cluster && !settings.default.offline,
+            skip_query: settings.query_cluster && settings.default.offline,
+        }
+    }
+
+    async fn collect(&self, _: Duration, collected: &Context) -> AppResult<CollectorOutput> {
+        if self.skip_query {
+            warnings::record(
+                WarningKind::Skipped,
+                Self::NAME,
+                "The cluster is not queried with --offline",
+            );
+        }
+        Ok(Context::default()
+            .with_k8s(k8s::get_k8s_activity(collected.shell_history(), self.query_cluster).await?))
+    }
+}
+
+/// ssh, scp, rsync, sftp, and mosh connections from the shell history.
+pub struct HostsCollector;
+
+impl Collector for HostsCollector {
+    const NAME: &'static str = "hosts";
+    const ABOUT: &'static str = "Collect the hosts reached with ssh, scp, rsync, sftp, and mosh";
+    const LONG_ABOUT: &'static str =
+        "Collect the hosts reached with ssh, scp, rsync, sftp, and mosh
+Read from shell history, with per-host connection counts by kind (interactive sessions, remote
+commands, uploads, and downloads)";
+    const REQUIRES: &'static [&'static str] = &[ShellCollector::NAME];
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        HostsCollector
+    }
+
+    async fn collect(&self, _: Duration, collected: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_hosts(hosts::find_host_activity(collected.shell_history())))
+    }
+}
+
+/// Per-app copy counts from clipboard managers.
+pub struct ClipboardCollector;
+
+impl Collector for ClipboardCollector {
+    const NAME: &'static str = "clipboard";
+    const ABOUT: &'static str =
+        "Collect per-app copy counts from clipboard managers (Maccy, Paste), never contents";
+    const LONG_ABOUT: &'static str =
+        "Collect per-app copy counts from clipboard managers (Maccy, Paste), never contents
+Only works on macOS with one of the supported clipboard managers installed";
+    const DEFAULT_ENABLED: bool = false;
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        ClipboardCollector
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_clipboard(clipboard::get_clipboard_activity(&window).await?))
+    }
+}
+
+/// Markdown notes written in a notes vault.
+pub struct NotesCollector {
+    vault: Option<PathBuf>,
+}
+
+impl Collector for NotesCollector {
+    const NAME: &'static str = "notes";
+    const ABOUT: &'static str =
+        "Collect Markdown notes created or edited in a notes vault (e.g. Obsidian)";
+    const LONG_ABOUT: &'static str =
+        "Collect Markdown notes created or edited in a notes vault (e.g. Obsidian)
+Uses file modification times and front-matter dates";
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        NotesCollector {
+            vault: settings.notes_vault.map(PathBuf::from),
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default()
+            .with_vault_notes(notes::get_notes_activity(self.vault.as_deref(), &window).await?))
+    }
+}
+
+/// asciinema recordings made during the window.
+pub struct RecordingsCollector {
+    dir: Option<PathBuf>,
+}
+
+impl Collector for RecordingsCollector {
+    const NAME: &'static str = "asciinema";
+    const ABOUT: &'static str = "Collect asciinema terminal recordings made in the window";
+    const LONG_ABOUT: &'static str = "Collect asciinema terminal recordings made in the window
+Reads the title, length, and command of each .cast file in the recordings directory so the
+journal can link to demos recorded that day";
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        RecordingsCollector {
+            dir: settings.recordings_dir.map(PathBuf::from),
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default()
+            .with_recordings(recordings::get_recordings(self.dir.as_deref(), &window).await?))
+    }
+}
+
+/// Notes created or edited in Apple Notes.
+pub struct AppleNotesCollector {
+    with_body: bool,
+}
+
+impl Collector for AppleNotesCollector {
+    const NAME: &'static str = "apple-notes";
+    const ABOUT: &'static str =
+        "Collect Apple Notes created or edited (titles and folders, bodies only when asked)";
+    const LONG_ABOUT: &'static str =
+        "Collect Apple Notes created or edited (titles and folders, bodies only when asked)
+Only works on macOS and requires Full Disk Access
+Pass --apple-notes-body to include the text of each note";
+    const DEFAULT_ENABLED: bool = false;
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        AppleNotesCollector {
+            with_body: settings.apple_notes_body,
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_apple_notes(
+            apple_notes::get_apple_notes_activity(&window, self.with_body).await?,
+        ))
+    }
+}
+
+/// Tasks completed in Things 3, OmniFocus, and Apple Reminders.
+pub struct TasksCollector;
+
+impl Collector for TasksCollector {
+    const NAME: &'static str = "tasks";
+    const ABOUT: &'static str =
+        "Collect tasks completed in Things 3, OmniFocus, and Apple Reminders";
+    const LONG_ABOUT: &'static str =
+        "Collect tasks completed in Things 3, OmniFocus, and Apple Reminders
+Only works on macOS and reading Reminders requires Full Disk Access
+Apps that are not installed are skipped; set THINGS_DB_PATH, OMNIFOCUS_DB_PATH, or
+REMINDERS_DB_PATH to read a database from another location";
+    const DEFAULT_ENABLED: bool = false;
+
+    fn new(_: &CollectorSettings<'_>) -> Self {
+        TasksCollector
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default().with_completed_tasks(tasks::get_completed_tasks(&window).await?))
+    }
+}
+
+/// Slack messages sent, counted per channel.
+pub struct SlackCollector {
+    offline: bool,
+}
+
+impl Collector for SlackCollector {
+    const NAME: &'static str = "slack";
+    const ABOUT: &'static str =
+        "Collect how many Slack messages you sent per channel (no message text)";
+    const LONG_ABOUT: &'static str =
+        "Collect how many Slack messages you sent per channel (no message text)
+Requires a Slack user token with the search:read scope in SLACK_USER_TOKEN
+Direct and group messages are counted without naming who they were with
+Skipped with --offline";
+    const DEFAULT_ENABLED: bool = false;
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        SlackCollector {
+            offline: settings.default.offline,
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        if self.offline {
+            warnings::record(
+                WarningKind::Skipped,
+                Self::NAME,
+                "Slack is not searched with --offline",
+            );
+            return Ok(Context::default());
+        }
+        Ok(Context::default().with_slack(slack::get_slack_activity(&window).await?))
+    }
+}
+
+/// Per-thread email counts from Mail.app or IMAP.
+pub struct EmailCollector {
+    offline: bool,
+}
+
+impl Collector for EmailCollector {
+    const NAME: &'static str = "email";
+    const ABOUT: &'static str =
+        "Collect per-thread email counts and subject lines from Mail.app or IMAP (never bodies)";
+    const LONG_ABOUT: &'static str =
+        "Collect per-thread email counts and subject lines from Mail.app or IMAP (never bodies)
+Reads IMAP when IMAP_HOST is set, otherwise Mail.app (requires Full Disk Access)
+IMAP is skipped with --offline";
+    const DEFAULT_ENABLED: bool = false;
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        EmailCollector {
+            offline: settings.default.offline,
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        if self.offline && email::imap::ImapConfig::from_env().is_some() {
+            warnings::record(
+                WarningKind::Skipped,
+                Self::NAME,
+                "The IMAP server is not read with --offline",
+            );
+            return Ok(Context::default());
+        }
+        Ok(Context::default().with_email(email::get_email_activity(&window).await?))
+    }
+}
+
+/// User-configured commands that print activities as JSON.
+pub struct ExternalCollector {
+    commands: Vec<ExternalCommand>,
+}
+
+impl Collector for ExternalCollector {
+    const NAME: &'static str = "external";
+    const ABOUT: &'static str = "Run the commands given with --external-collector";
+    const LONG_ABOUT: &'static str = "Run the commands given with --external-collector
+Each command runs with `sh -c` and gets the window as DAILY_AI_SINCE and DAILY_AI_UNTIL (RFC 3339)
+and DAILY_AI_WINDOW_SECONDS. It must print {\"activities\": [...]} on stdout, where each activity
+has a `title` and optionally `start`, `end` (RFC 3339), `details`, and `tags`";
+
+    fn new(settings: &CollectorSettings<'_>) -> Self {
+        ExternalCollector {
+            commands: settings.external.to_vec(),
+        }
+    }
+
+    async fn collect(&self, window: Duration, _: &Context) -> AppResult<CollectorOutput> {
+        Ok(Context::default()
+            .with_external(external::get_external_activity(&self.commands, &window).await?))
+    }
+}
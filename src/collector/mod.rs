@@ -0,0 +1,366 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use futures::future::LocalBoxFuture;
+use time::Duration;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::cli::DefaultArgs;
+use crate::context::{Context, Section};
+use crate::error::AppError;
+use crate::external::ExternalCommand;
+use crate::git::RepoFilter;
+use crate::run_status;
+use crate::shell::ShellBackend;
+use crate::warnings::{self, WarningKind};
+
+/// Adapters exposing the built-in data sources as collectors.
+pub(crate) mod builtin;
+
+/// End-to-end runs of the built-in collectors against fixture databases and repositories.
+#[cfg(test)]
+mod acceptance;
+
+/// Data produced by one collector: a [`Context`] with only that collector's fields filled in.
+pub type CollectorOutput = Context;
+
+/// Settings shared by every collector in a run, built from the command line.
+pub struct CollectorSettings<'a> {
+    /// Language model server connection, for collectors that call the model.
+    pub default: &'a DefaultArgs,
+    /// Repositories the git collector reports on.
+    pub repos: RepoFilter,
+    /// Where shell history is read from.
+    pub shell_backend: ShellBackend,
+    /// Sync atuin history before reading it.
+    pub sync: bool,
+    /// Commit pending changes in visited repositories before reading git history.
+    pub auto_commit: bool,
+    pub commit_language: Option<&'a str>,
+    /// Query the current Kubernetes cluster for the workloads touched.
+    pub query_cluster: bool,
+    /// Safari `History.db` to read instead of the one found in the usual places.
+    pub safari_history_db: Option<&'a Path>,
+    pub notes_vault: Option<&'a Path>,
+    /// Directory of asciinema `.cast` files.
+    pub recordings_dir: Option<&'a Path>,
+    /// Read the bodies of Apple Notes, not just titles and folders.
+    pub apple_notes_body: bool,
+    /// Commands run by the external collector.
+    pub external: &'a [ExternalCommand],
+}
+
+/// A data source that contributes to the [`Context`] for a run.
+///
+/// Implementations are registered in the collector registry in `cli.rs`, which generates a
+/// `collect <NAME>` subcommand for each one and decides which collectors run for `summarize`
+/// and `collect all`.
+pub trait Collector: Sized {
+    /// Name of the `collect` subcommand and of the `--enable`/`--disable` value.
+    const NAME: &'static str;
+    /// One-line help for the `collect` subcommand.
+    const ABOUT: &'static str;
+    /// Long help for the `collect` subcommand.
+    const LONG_ABOUT: &'static str;
+    /// Collectors whose output this one reads from the `collected` context. They always run
+    /// first, but their output is only kept when they were selected too.
+    const REQUIRES: &'static [&'static str] = &[];
+    /// Whether the collector runs for `summarize` and `collect all` without `--enable`.
+    const DEFAULT_ENABLED: bool = true;
+
+    /// Build the collector from the settings for this run.
+    fn new(settings: &CollectorSettings<'_>) -> Self;
+
+    /// Collect data for the last `window`. `collected` holds the output of the collectors that
+    /// ran before this one, including everything in [`Collector::REQUIRES`].
+    async fn collect(&self, window: Duration, collected: &Context) -> AppResult<CollectorOutput>;
+}
+
+/// Type-erased entry point for running a collector.
+type RunFn = for<'a> fn(
+    &'a CollectorSettings<'a>,
+    Duration,
+    &'a Context,
+) -> LocalBoxFuture<'a, AppResult<CollectorOutput>>;
+
+/// Registry entry describing a collector.
+pub struct CollectorSpec {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub long_about: &'static str,
+    pub requires: &'static [&'static str],
+    pub default_enabled: bool,
+    run: RunFn,
+}
+
+impl CollectorSpec {
+    /// Registry entry for collector `C`.
+    pub const fn of<C: Collector + 'static>() -> Self {
+        CollectorSpec {
+            name: C::NAME,
+            about: C::ABOUT,
+            long_about: C::LONG_ABOUT,
+            requires: C::REQUIRES,
+            default_enabled: C::DEFAULT_ENABLED,
+            run: run_collector::<C>,
+        }
+    }
+}
+
+fn run_collector<'a, C: Collector + 'static>(
+    settings: &'a CollectorSettings<'a>,
+    window: Duration,
+    collected: &'a Context,
+) -> LocalBoxFuture<'a, AppResult<CollectorOutput>> {
+    Box::pin(async move { C::new(settings).collect(window, collected).await })
+}
+
+/// Names of `selected` plus everything they require, transitively.
+fn with_requirements<'a>(registry: &'a [CollectorSpec], selected: &[&'a str]) -> HashSet<&'a str> {
+    let mut needed: HashSet<&str> = selected.iter().copied().collect();
+    let mut pending: Vec<&str> = selected.to_vec();
+    while let Some(name) = pending.pop() {
+        let Some(spec) = registry.iter().find(|spec| spec.name == name) else {
+            continue;
+        };
+        for requirement in spec.requires {
+            if needed.insert(requirement) {
+                pending.push(requirement);
+            }
+        }
+    }
+    needed
+}
+
+/// Run the `selected` collectors, plus the collectors they require, in registry order.
+///
+/// Only the output of selected collectors ends up in the returned context. Registry order must
+/// list requirements before the collectors that need them. Unless `--no-redact` is given, each
+/// collector's output is redacted before later collectors, the model, or the output see it.
+///
+/// A collector that fails is logged and listed in [`Context::failed_collectors`] so the others
+/// still run; the run only fails when every selected collector does. With `--fail-on-empty`,
+/// collecting nothing at all is an error.
+#[tracing::instrument(name = "Running collectors", level = "info", skip(registry, settings))]
+pub async fn run_collectors(
+    registry: &[CollectorSpec],
+    selected: &[&str],
+    settings: &CollectorSettings<'_>,
+    window: Duration,
+) -> AppResult<Context> {
+    let needed = with_requirements(registry, selected);
+    let mut collected = Context::default();
+    let mut output = Context::default();
+    let mut last_error = None;
+    let redactor = settings.default.redactor();
+    for spec in registry.iter().filter(|spec| needed.contains(spec.name)) {
+        debug!("Running the {} collector", spec.name);
+        run_status::start(spec.name).await;
+        let mut result = match (spec.run)(settings, window, &collected).await {
+            Ok(result) => result,
+            Err(e) => {
+                run_status::fail(spec.name, &e).await;
+                warnings::record(
+                    WarningKind::for_error(&e),
+                    spec.name,
+                    format!("The {} collector failed: {e}", spec.name),
+                );
+                output.failed_collectors.push(spec.name.to_string());
+                last_error = Some(e);
+                continue;
+            }
+        };
+        let count = result.sections().iter().map(Section::len).sum();
+        run_status::finish(spec.name, Some(count)).await;
+        if let Some(redactor) = &redactor {
+            redactor.redact_context(&mut result);
+        }
+        if selected.contains(&spec.name) {
+            output.merge(result.clone());
+        }
+        collected.merge(result);
+    }
+
+    let all_failed = !selected.is_empty()
+        && selected
+            .iter()
+            .all(|name| output.failed_collectors.iter().any(|failed| failed == name));
+    if let Some(e) = last_error.filter(|_| all_failed) {
+        return Err(e);
+    }
+    if settings.default.fail_on_empty && output.is_empty() && output.failed_collectors.is_empty() {
+        return Err(AppError::NothingCollected(format!(
+            "Checked {} over the last {}.",
+            selected.join(", "),
+            humantime::format_duration(window.unsigned_abs())
+        )));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::tmux::TmuxSession;
+
+    fn session(name: String) -> TmuxSession {
+        TmuxSession {
+            multiplexer: Default::default(),
+            name,
+            created: OffsetDateTime::UNIX_EPOCH,
+            last_activity: OffsetDateTime::UNIX_EPOCH,
+            attached: false,
+            attached_for: Duration::ZERO,
+            windows: Vec::new(),
+        }
+    }
+
+    /// Reports how many tmux sessions were collected before it ran.
+    struct Counter;
+
+    impl Collector for Counter {
+        const NAME: &'static str = "counter";
+        const ABOUT: &'static str = "";
+        const LONG_ABOUT: &'static str = "";
+        const REQUIRES: &'static [&'static str] = &["seed"];
+
+        fn new(_: &CollectorSettings<'_>) -> Self {
+            Counter
+        }
+
+        async fn collect(&self, _: Duration, collected: &Context) -> AppResult<CollectorOutput> {
+            Ok(Context::default()
+                .with_tmux(vec![session(format!("after {}", collected.tmux().len()))]))
+        }
+    }
+
+    struct Seed;
+
+    impl Collector for Seed {
+        const NAME: &'static str = "seed";
+        const ABOUT: &'static str = "";
+        const LONG_ABOUT: &'static str = "";
+
+        fn new(_: &CollectorSettings<'_>) -> Self {
+            Seed
+        }
+
+        async fn collect(&self, _: Duration, _: &Context) -> AppResult<CollectorOutput> {
+            Ok(Context::default().with_tmux(vec![session("seed".into())]))
+        }
+    }
+
+    /// Always fails.
+    struct Broken;
+
+    impl Collector for Broken {
+        const NAME: &'static str = "broken";
+        const ABOUT: &'static str = "";
+        const LONG_ABOUT: &'static str = "";
+
+        fn new(_: &CollectorSettings<'_>) -> Self {
+            Broken
+        }
+
+        async fn collect(&self, _: Duration, _: &Context) -> AppResult<CollectorOutput> {
+            Err(AppError::Other("unreadable".into()))
+        }
+    }
+
+    fn default_args(fail_on_empty: bool) -> DefaultArgs {
+        DefaultArgs {
+            provider: Default::default(),
+            openai_org: None,
+            openai_project: None,
+            secure: None,
+            host: "localhost".into(),
+            port: 1234,
+            api_version: "v1".into(),
+            model: crate::ai::DEFAULT_MODEL.into(),
+            label_model: None,
+            commit_model: None,
+            summary_model: None,
+            digest_model: None,
+            context_window: crate::ai::token_budget::DEFAULT_CONTEXT_WINDOW,
+            embedding_model: crate::classify::DEFAULT_EMBEDDING_MODEL.into(),
+            embedding_batch_size: crate::classify::DEFAULT_EMBEDDING_BATCH_SIZE,
+            embedder: Default::default(),
+            remote_embedding_model: None,
+            ollama_url: crate::classify::embed::DEFAULT_OLLAMA_URL.into(),
+            duration: None,
+            format: None,
+            text_width: crate::render::text::DEFAULT_WIDTH,
+            output: None,
+            template: None,
+            offline: false,
+            allow_any_url: false,
+            no_cache: false,
+            keep_alive: None,
+            fail_on_empty,
+            run_id: None,
+            no_redact: false,
+            redact_rules: Vec::new(),
+        }
+    }
+
+    fn settings(default: &DefaultArgs) -> CollectorSettings<'_> {
+        CollectorSettings {
+            default,
+            repos: RepoFilter::default(),
+            shell_backend: ShellBackend::Auto,
+            sync: false,
+            auto_commit: false,
+            commit_language: None,
+            query_cluster: false,
+            safari_history_db: None,
+            notes_vault: None,
+            recordings_dir: None,
+            apple_notes_body: false,
+            external: &[],
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_requirements_first_but_only_keeps_selected_output() {
+        let registry = [CollectorSpec::of::<Seed>(), CollectorSpec::of::<Counter>()];
+        let default = default_args(false);
+
+        let context = run_collectors(
+            &registry,
+            &["counter"],
+            &settings(&default),
+            Duration::days(1),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(context.tmux().len(), 1);
+        assert_eq!(context.tmux()[0].name, "after 1");
+    }
+
+    #[tokio::test]
+    async fn records_failed_collectors_and_fails_only_when_all_do() {
+        let registry = [CollectorSpec::of::<Seed>(), CollectorSpec::of::<Broken>()];
+        let default = default_args(true);
+        let settings = settings(&default);
+
+        let context = run_collectors(&registry, &["seed", "broken"], &settings, Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(context.tmux().len(), 1);
+        assert_eq!(context.failed_collectors, ["broken"]);
+
+        let error = run_collectors(&registry, &["broken"], &settings, Duration::days(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AppError::Other(_)));
+
+        let error = run_collectors(&[], &[], &settings, Duration::days(1))
+            .await
+            .unwrap_err();
+        assert_eq!(error.exit_status(), crate::error::ExitStatus::Empty);
+    }
+}
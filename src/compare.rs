@@ -0,0 +1,475 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::json;
+use ulid::Ulid;
+
+use crate::cli::OutputFormat;
+use crate::context::FullContext;
+use crate::dashboard::DayStats;
+use crate::error::AppError;
+use crate::render::{pdf, terminal, text};
+use crate::{AppResult, history, io_utils};
+
+/// Activity counters of one run.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub commits: usize,
+    pub urls: usize,
+    pub shell_commands: usize,
+    /// Estimated active time, in minutes.
+    pub active_minutes: i64,
+    /// Active time spent in unbroken stretches of at least an hour, in minutes.
+    pub deep_work_minutes: i64,
+}
+
+/// One side of a comparison.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Side {
+    /// The run id or date the run was asked for by.
+    pub key: String,
+    pub run_id: Option<Ulid>,
+    pub totals: Totals,
+    /// The summary's time breakdown, if the run has a summary.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub time_breakdown: Vec<String>,
+}
+
+impl Side {
+    fn new(key: &str, context: &FullContext) -> Self {
+        let stats = DayStats::from_context(context);
+        Side {
+            key: key.to_string(),
+            run_id: context.run_id,
+            totals: Totals {
+                commits: stats.commits,
+                urls: stats.urls,
+                shell_commands: context.context.shell_history().len(),
+                active_minutes: stats.active_minutes,
+                deep_work_minutes: stats.deep_work_minutes,
+            },
+            time_breakdown: context
+                .summary
+                .as_ref()
+                .map(|summary| summary.time_breakdown.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// How much of something, e.g. commits to a repository, each run had.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Count {
+    pub name: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// What changed from the `first` run to the `second`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DayComparison {
+    pub first: Side,
+    pub second: Side,
+    /// Repositories with commits only in the second run, by project name.
+    pub new_repos: Vec<Count>,
+    /// Repositories with commits only in the first run.
+    pub dropped_repos: Vec<Count>,
+    /// Repositories with commits in both runs.
+    pub continued_repos: Vec<Count>,
+    /// Browsing clusters only in the second run, with their number of pages.
+    pub new_topics: Vec<Count>,
+    /// Browsing clusters only in the first run.
+    pub dropped_topics: Vec<Count>,
+    /// Browsing clusters in both runs.
+    pub continued_topics: Vec<Count>,
+}
+
+/// Split the counts of two runs into new, dropped, and continued entries.
+fn split(
+    before: BTreeMap<String, usize>,
+    mut after: BTreeMap<String, usize>,
+) -> (Vec<Count>, Vec<Count>, Vec<Count>) {
+    let mut dropped = Vec::new();
+    let mut continued = Vec::new();
+    for (name, before) in before {
+        match after.remove(&name) {
+            Some(after) => continued.push(Count {
+                name,
+                before,
+                after,
+            }),
+            None => dropped.push(Count {
+                name,
+                before,
+                after: 0,
+            }),
+        }
+    }
+    let new = after
+        .into_iter()
+        .map(|(name, after)| Count {
+            name,
+            before: 0,
+            after,
+        })
+        .collect();
+    (new, dropped, continued)
+}
+
+fn commits_per_repo(context: &FullContext) -> BTreeMap<String, usize> {
+    let mut repos = BTreeMap::new();
+    for repo in context.context.commit_history() {
+        if !repo.commits.is_empty() {
+            *repos.entry(repo.project_name()).or_default() += repo.commits.len();
+        }
+    }
+    repos
+}
+
+fn pages_per_topic(context: &FullContext) -> BTreeMap<String, usize> {
+    let mut topics = BTreeMap::new();
+    for cluster in context.context.safari_history() {
+        *topics.entry(cluster.label.clone()).or_default() += cluster.urls.len();
+    }
+    topics
+}
+
+fn hours(minutes: i64) -> String {
+    format!("{:.1} h", minutes as f64 / 60.0)
+}
+
+fn signed(change: i64) -> String {
+    if change > 0 {
+        format!("+{change}")
+    } else {
+        change.to_string()
+    }
+}
+
+impl DayComparison {
+    /// Compare the run asked for by `first_key` with the one asked for by `second_key`.
+    pub fn new(
+        first_key: &str,
+        first: &FullContext,
+        second_key: &str,
+        second: &FullContext,
+    ) -> Self {
+        let (new_repos, dropped_repos, continued_repos) =
+            split(commits_per_repo(first), commits_per_repo(second));
+        let (new_topics, dropped_topics, continued_topics) =
+            split(pages_per_topic(first), pages_per_topic(second));
+        DayComparison {
+            first: Side::new(first_key, first),
+            second: Side::new(second_key, second),
+            new_repos,
+            dropped_repos,
+            continued_repos,
+            new_topics,
+            dropped_topics,
+            continued_topics,
+        }
+    }
+
+    /// Render as an outline with `mark` heading markers: `#` for Markdown, `*` for Org.
+    fn outline(&self, mark: &str) -> String {
+        let heading = |level: usize, title: &str| format!("{} {title}\n", mark.repeat(level));
+        let (first, second) = (&self.first, &self.second);
+        let mut out = heading(1, &format!("{} compared with {}", second.key, first.key));
+        out.push('\n');
+
+        out.push_str(&heading(2, "Time"));
+        out.push('\n');
+        let (before, after) = (first.totals, second.totals);
+        for (label, before, after) in [
+            ("Active time", before.active_minutes, after.active_minutes),
+            (
+                "Deep work",
+                before.deep_work_minutes,
+                after.deep_work_minutes,
+            ),
+        ] {
+            let _ = writeln!(
+                out,
+                "- {label}: {} to {} ({} min)",
+                hours(before),
+                hours(after),
+                signed(after - before)
+            );
+        }
+        for (label, before, after) in [
+            ("Commits", before.commits, after.commits),
+            ("Pages visited", before.urls, after.urls),
+            (
+                "Shell commands",
+                before.shell_commands,
+                after.shell_commands,
+            ),
+        ] {
+            let _ = writeln!(
+                out,
+                "- {label}: {before} to {after} ({})",
+                signed(after as i64 - before as i64)
+            );
+        }
+        out.push('\n');
+
+        let sections: [(&str, &[Count], &str); 6] = [
+            ("New repositories", &self.new_repos, "commits"),
+            ("Dropped repositories", &self.dropped_repos, "commits"),
+            ("Continued repositories", &self.continued_repos, "commits"),
+            ("New topics", &self.new_topics, "pages"),
+            ("Dropped topics", &self.dropped_topics, "pages"),
+            ("Continued topics", &self.continued_topics, "pages"),
+        ];
+        for (title, counts, unit) in sections {
+            if counts.is_empty() {
+                continue;
+            }
+            out.push_str(&heading(2, title));
+            out.push('\n');
+            for count in counts {
+                let _ = match (count.before, count.after) {
+                    (0, after) => writeln!(out, "- {}: {after} {unit}", count.name),
+                    (before, 0) => writeln!(out, "- {}: {before} {unit}", count.name),
+                    (before, after) => writeln!(
+                        out,
+                        "- {}: {before} to {after} {unit} ({})",
+                        count.name,
+                        signed(after as i64 - before as i64)
+                    ),
+                };
+            }
+            out.push('\n');
+        }
+
+        if !first.time_breakdown.is_empty() || !second.time_breakdown.is_empty() {
+            out.push_str(&heading(2, "Time breakdown"));
+            out.push('\n');
+            for side in [first, second] {
+                out.push_str(&heading(3, &side.key));
+                out.push('\n');
+                for item in &side.time_breakdown {
+                    let _ = writeln!(out, "- {}", item.trim());
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        self.outline("#")
+    }
+
+    pub fn to_org(&self) -> String {
+        self.outline("*")
+    }
+
+    /// Render as JSON Lines: one `run` line per side, then one line per repository and topic
+    /// tagged with whether it is new, dropped, or continued.
+    pub fn to_jsonl(&self) -> AppResult<String> {
+        let mut out = String::new();
+        for side in [&self.first, &self.second] {
+            let mut line = serde_json::to_value(side)?;
+            line["type"] = "run".into();
+            let _ = writeln!(out, "{line}");
+        }
+        for (kind, change, counts) in [
+            ("repository", "new", &self.new_repos),
+            ("repository", "dropped", &self.dropped_repos),
+            ("repository", "continued", &self.continued_repos),
+            ("topic", "new", &self.new_topics),
+            ("topic", "dropped", &self.dropped_topics),
+            ("topic", "continued", &self.continued_topics),
+        ] {
+            for count in counts {
+                let line = json!({
+                    "type": kind,
+                    "change": change,
+                    "name": count.name,
+                    "before": count.before,
+                    "after": count.after,
+                });
+                let _ = writeln!(out, "{line}");
+            }
+        }
+        Ok(out)
+    }
+
+    /// Render in `format`. Formats that only make sense for a single run are refused.
+    pub fn render(&self, format: &OutputFormat) -> AppResult<Vec<u8>> {
+        Ok(match format {
+            OutputFormat::Json => serde_json::to_string_pretty(self)?.into_bytes(),
+            OutputFormat::Markdown => self.to_markdown().into_bytes(),
+            OutputFormat::Text => text::render_markdown(&self.to_markdown()).into_bytes(),
+            OutputFormat::Org => self.to_org().into_bytes(),
+            OutputFormat::Jsonl => self.to_jsonl()?.into_bytes(),
+            OutputFormat::Pdf => pdf::render_markdown(&self.to_markdown())?,
+            OutputFormat::Dir
+            | OutputFormat::Standup
+            | OutputFormat::Sqlite
+            | OutputFormat::Obsidian
+            | OutputFormat::Ics => {
+                return Err(AppError::Config(format!(
+                    "--format {} does not apply to a comparison; use json, jsonl, markdown, text, org, or pdf",
+                    io_utils::format_name(format)
+                )));
+            }
+        })
+    }
+}
+
+/// Compare two stored runs, each given by run id or date, and write the comparison to `output`
+/// or show it: rendered Markdown on a terminal and JSON otherwise, unless `format` says.
+#[tracing::instrument(name = "Comparing stored runs", level = "info")]
+pub async fn compare(
+    first: &str,
+    second: &str,
+    format: Option<&OutputFormat>,
+    output: Option<&Path>,
+) -> AppResult<()> {
+    let comparison = DayComparison::new(
+        first,
+        &history::load(first).await?,
+        second,
+        &history::load(second).await?,
+    );
+    let tty = std::io::stdout().is_terminal();
+    let format = match (output, format) {
+        (Some(output), format) => io_utils::resolve_output_format(output, format)?,
+        (None, Some(format)) => format.clone(),
+        (None, None) if tty => OutputFormat::Markdown,
+        (None, None) => OutputFormat::Json,
+    };
+    if let Some(output) = output {
+        return io_utils::output_file(
+            output,
+            &io_utils::OutputVars::now(),
+            comparison.render(&format)?,
+        )
+        .await;
+    }
+    match format {
+        OutputFormat::Pdf if tty => Err(AppError::Config(
+            "--format pdf needs --output or a pipe, e.g. `--format pdf > diff.pdf`".into(),
+        )),
+        OutputFormat::Markdown if tty => {
+            terminal::page(&terminal::to_terminal(&comparison.to_markdown())).await
+        }
+        _ if tty => terminal::page(&String::from_utf8_lossy(&comparison.render(&format)?)).await,
+        _ => terminal::print(comparison.render(&format)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::classify::UrlCluster;
+    use crate::context::Context;
+    use crate::git::diff::DiffSummary;
+    use crate::git::{CommitMeta, GitRepoHistory};
+
+    fn repo(path: &str, commits: usize) -> GitRepoHistory {
+        GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from(path),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: Vec::new(),
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits: (0..commits)
+                .map(|i| CommitMeta {
+                    id: format!("{path}{i}"),
+                    summary: "Work".into(),
+                    body: None,
+                    timestamp: datetime!(2025-01-01 09:00 UTC),
+                    branches: Vec::new(),
+                })
+                .collect(),
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        }
+    }
+
+    fn run(repos: Vec<GitRepoHistory>, labels: &[&str]) -> FullContext {
+        FullContext::from(
+            Context::default()
+                .with_commit_history(repos)
+                .with_safari_history(
+                    labels
+                        .iter()
+                        .map(|label| UrlCluster {
+                            label: label.to_string(),
+                            urls: Vec::new(),
+                        })
+                        .collect(),
+                ),
+        )
+    }
+
+    #[test]
+    fn compares_repos_topics_and_totals() {
+        let first = run(
+            vec![repo("/src/app", 2), repo("/src/old", 1)],
+            &["Rust docs", "News"],
+        );
+        let second = run(
+            vec![
+                repo("/src/app", 5),
+                repo("/src/new", 3),
+                repo("/src/idle", 0),
+            ],
+            &["Rust docs", "Kubernetes"],
+        );
+
+        let comparison = DayComparison::new("2025-01-30", &first, "2025-01-31", &second);
+
+        let names = |counts: &[Count]| counts.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&comparison.new_repos), ["new"]);
+        assert_eq!(names(&comparison.dropped_repos), ["old"]);
+        assert_eq!(
+            comparison.continued_repos,
+            [Count {
+                name: "app".into(),
+                before: 2,
+                after: 5
+            }]
+        );
+        assert_eq!(names(&comparison.new_topics), ["Kubernetes"]);
+        assert_eq!(names(&comparison.dropped_topics), ["News"]);
+        assert_eq!(comparison.first.totals.commits, 3);
+        assert_eq!(comparison.second.totals.commits, 8);
+
+        let markdown = comparison.to_markdown();
+        assert!(markdown.starts_with("# 2025-01-31 compared with 2025-01-30\n"));
+        assert!(markdown.contains("- Commits: 3 to 8 (+5)\n"));
+        assert!(markdown.contains("## New repositories\n\n- new: 3 commits\n"));
+        assert!(markdown.contains("- app: 2 to 5 commits (+3)\n"));
+        assert!(
+            comparison
+                .to_org()
+                .contains("* Dropped topics\n\n- News: 0 pages\n")
+        );
+        assert_eq!(comparison.to_jsonl().unwrap().lines().count(), 2 + 3 + 3);
+        assert!(comparison.render(&OutputFormat::Standup).is_err());
+    }
+}
@@ -0,0 +1,792 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use clap::{Command, ValueEnum};
+use serde::Deserialize;
+use tokio::fs;
+
+use crate::AppResult;
+use crate::ai::persona::AboutMe;
+use crate::ai::provider::Provider;
+use crate::ai::retry::RequestPolicy;
+use crate::ai::tools::fetch::FetchPolicy;
+use crate::classify::embed::EmbedderKind;
+use crate::cli::{BROWSER_COLLECTORS, OutputFormat};
+use crate::dirs::DirType;
+use crate::email::smtp::SmtpSettings;
+use crate::error::AppError;
+use crate::http::ProxySettings;
+use crate::{ai, classify, render};
+
+/// Name of the configuration file in the config directory.
+const CONFIG_FILE: &str = "config.toml";
+
+/// Defaults for command-line options, read from `config.toml` in the config directory.
+///
+/// Every key is optional, and options given on the command line win over the file. Named
+/// profiles in `[profiles.NAME]` tables take the same keys and replace the top-level values
+/// when chosen with `--profile`.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// Where requests to the language model go, as given to `--provider`.
+    pub provider: Option<String>,
+    /// OpenAI organization to bill requests to.
+    pub openai_org: Option<String>,
+    /// OpenAI project to bill requests to.
+    pub openai_project: Option<String>,
+    /// Proxy for every HTTP request. `HTTPS_PROXY` and friends are ignored.
+    pub proxy: Option<String>,
+    /// Hosts reached without the proxy, in `NO_PROXY` syntax.
+    pub no_proxy: Option<String>,
+    /// Whether to use HTTPS to reach the language model server.
+    pub secure: Option<bool>,
+    /// Host for the language model server.
+    pub host: Option<String>,
+    /// Port for the language model server.
+    pub port: Option<u16>,
+    /// OpenAI API version of the language model server.
+    pub api_version: Option<String>,
+    /// Model asked on the language model server.
+    pub model: Option<String>,
+    /// Model used to label browsing history clusters, instead of `model`.
+    pub label_model: Option<String>,
+    /// Model used to write commit messages, instead of `model`.
+    pub commit_model: Option<String>,
+    /// Model used for the summary, stand-up update, and the reports and answers written from
+    /// collected work, instead of `model`.
+    pub summary_model: Option<String>,
+    /// Model used for the summaries of large diffs and busy days' repositories, instead of
+    /// `model`.
+    pub digest_model: Option<String>,
+    /// Context window of the model, in tokens.
+    pub context_window: Option<usize>,
+    /// How long to wait for one answer from the model, e.g. `10m`.
+    pub request_timeout: Option<String>,
+    /// How many times a timed-out or failed request to the model is sent again.
+    pub retries: Option<u32>,
+    /// How many rounds of tool calls the model may make for one answer.
+    pub max_tool_rounds: Option<usize>,
+    /// How many times the model is asked again for an answer that does not match the schema.
+    pub schema_retries: Option<u32>,
+    /// How long the model's `fetch_url` tool waits for a page, e.g. `30s`.
+    pub fetch_timeout: Option<String>,
+    /// How many bytes of a page the model's `fetch_url` tool reads.
+    pub fetch_max_bytes: Option<usize>,
+    /// How often to ping the model while collecting, so it is loaded when the questions start.
+    pub keep_alive: Option<String>,
+    /// Hugging Face model used to embed browsing history.
+    pub embedding_model: Option<String>,
+    /// Texts the local embedding model embeds in one pass.
+    pub embedding_batch_size: Option<usize>,
+    /// Where text is embedded, as given to `--embedder`.
+    pub embedder: Option<String>,
+    /// Model asked for embeddings by the `openai` and `ollama` embedders.
+    pub remote_embedding_model: Option<String>,
+    /// URL of the Ollama server for `--embedder ollama`.
+    pub ollama_url: Option<String>,
+    /// History to summarize, e.g. `1d`.
+    pub duration: Option<String>,
+    /// Output format, as given to `--format`.
+    pub format: Option<String>,
+    /// Column plain-text output is wrapped at, as given to `--text-width`.
+    pub text_width: Option<usize>,
+    /// Where `summarize` and `collect` write their output, as given to `--output`.
+    pub output: Option<PathBuf>,
+    /// Collectors to run besides the default ones.
+    pub enable: Option<Vec<String>>,
+    /// Collectors to skip.
+    pub disable: Option<Vec<String>>,
+    /// Only report repositories matching one of these patterns.
+    pub repos: Option<Vec<String>>,
+    /// Leave out repositories matching any of these patterns.
+    pub exclude_repos: Option<Vec<String>>,
+    /// Browsers whose history is collected; the others are skipped.
+    pub browsers: Option<Vec<String>>,
+    /// Names to spell and capitalize as given in everything the model writes.
+    pub glossary: Option<Vec<String>>,
+    /// Who the reports are for, added to every summary prompt.
+    pub about_me: Option<AboutMe>,
+    /// The server `--email-to` sends through.
+    pub smtp: Option<SmtpSettings>,
+    /// Named sets of settings chosen with `--profile`.
+    pub profiles: BTreeMap<String, FileConfig>,
+}
+
+impl FileConfig {
+    /// Where the configuration file lives.
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(DirType::Config.get_dir()?.join(CONFIG_FILE))
+    }
+
+    /// Load the configuration file, or nothing when it does not exist.
+    pub async fn load() -> AppResult<Self> {
+        Self::load_from(&Self::path()?).await
+    }
+
+    /// Load the configuration at `path`, or nothing when it does not exist.
+    pub async fn load_from(path: &Path) -> AppResult<Self> {
+        let text = match fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(FileConfig::default()),
+            Err(e) => return Err(e.into()),
+        };
+        Self::parse(&text).map_err(|e| AppError::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// Parse and check a configuration file, so a typo fails here rather than as a flag.
+    fn parse(text: &str) -> Result<Self, String> {
+        let config: FileConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+        config.check()?;
+        for (name, profile) in &config.profiles {
+            if !profile.profiles.is_empty() {
+                return Err(format!("profile {name}: profiles cannot be nested"));
+            }
+            profile
+                .check()
+                .map_err(|e| format!("profile {name}: {e}"))?;
+        }
+        Ok(config)
+    }
+
+    /// Check the values clap would otherwise only reject once they are used.
+    fn check(&self) -> Result<(), String> {
+        if let Some(provider) = &self.provider {
+            Provider::from_str(provider, true)?;
+        }
+        self.proxy_settings().check()?;
+        if self.embedding_batch_size == Some(0) {
+            return Err("embedding_batch_size: must be at least 1".to_string());
+        }
+        if let Some(embedder) = &self.embedder {
+            EmbedderKind::from_str(embedder, true)?;
+        }
+        if let Some(format) = &self.format {
+            OutputFormat::from_str(format, true)?;
+        }
+        if let Some(duration) = &self.duration {
+            humantime::parse_duration(duration).map_err(|e| format!("duration: {e}"))?;
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            humantime::parse_duration(keep_alive).map_err(|e| format!("keep_alive: {e}"))?;
+        }
+        self.request_policy()?;
+        self.fetch_policy()?;
+        for browser in self.browsers.iter().flatten() {
+            if !BROWSER_COLLECTORS.contains(&browser.as_str()) {
+                return Err(format!(
+                    "unknown browser {browser}; expected one of {}",
+                    BROWSER_COLLECTORS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The settings of profile `name` over the top-level ones, or the top-level settings alone
+    /// when no profile is chosen.
+    pub fn with_profile(mut self, name: Option<&str>) -> AppResult<Self> {
+        let profiles = std::mem::take(&mut self.profiles);
+        let Some(name) = name else {
+            return Ok(self);
+        };
+        let Some(profile) = profiles.get(name) else {
+            let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            return Err(AppError::Config(format!(
+                "There is no profile named {name} in the configuration file. Known profiles: {}",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )));
+        };
+        let profile = profile.clone();
+        Ok(FileConfig {
+            provider: profile.provider.or(self.provider),
+            openai_org: profile.openai_org.or(self.openai_org),
+            openai_project: profile.openai_project.or(self.openai_project),
+            proxy: profile.proxy.or(self.proxy),
+            no_proxy: profile.no_proxy.or(self.no_proxy),
+            secure: profile.secure.or(self.secure),
+            host: profile.host.or(self.host),
+            port: profile.port.or(self.port),
+            api_version: profile.api_version.or(self.api_version),
+            model: profile.model.or(self.model),
+            label_model: profile.label_model.or(self.label_model),
+            commit_model: profile.commit_model.or(self.commit_model),
+            summary_model: profile.summary_model.or(self.summary_model),
+            digest_model: profile.digest_model.or(self.digest_model),
+            context_window: profile.context_window.or(self.context_window),
+            request_timeout: profile.request_timeout.or(self.request_timeout),
+            retries: profile.retries.or(self.retries),
+            max_tool_rounds: profile.max_tool_rounds.or(self.max_tool_rounds),
+            schema_retries: profile.schema_retries.or(self.schema_retries),
+            fetch_timeout: profile.fetch_timeout.or(self.fetch_timeout),
+            fetch_max_bytes: profile.fetch_max_bytes.or(self.fetch_max_bytes),
+            keep_alive: profile.keep_alive.or(self.keep_alive),
+            embedding_model: profile.embedding_model.or(self.embedding_model),
+            embedding_batch_size: profile.embedding_batch_size.or(self.embedding_batch_size),
+            embedder: profile.embedder.or(self.embedder),
+            remote_embedding_model: profile
+                .remote_embedding_model
+                .or(self.remote_embedding_model),
+            ollama_url: profile.ollama_url.or(self.ollama_url),
+            duration: profile.duration.or(self.duration),
+            format: profile.format.or(self.format),
+            text_width: profile.text_width.or(self.text_width),
+            output: profile.output.or(self.output),
+            enable: profile.enable.or(self.enable),
+            disable: profile.disable.or(self.disable),
+            repos: profile.repos.or(self.repos),
+            exclude_repos: profile.exclude_repos.or(self.exclude_repos),
+            browsers: profile.browsers.or(self.browsers),
+            glossary: profile.glossary.or(self.glossary),
+            about_me: profile.about_me.or(self.about_me),
+            smtp: profile.smtp.or(self.smtp),
+            profiles: BTreeMap::new(),
+        })
+    }
+
+    /// Proxy settings for the HTTP clients. These are not options, so they are applied directly
+    /// rather than through [`FileConfig::defaults`].
+    pub fn proxy_settings(&self) -> ProxySettings {
+        ProxySettings {
+            url: self.proxy.clone(),
+            no_proxy: self.no_proxy.clone(),
+        }
+    }
+
+    /// Timeouts, retries, and tool-call limits for requests to the model. Like the proxy, these
+    /// are applied directly rather than through [`FileConfig::defaults`].
+    pub fn request_policy(&self) -> Result<RequestPolicy, String> {
+        let mut policy = RequestPolicy::default();
+        if let Some(timeout) = &self.request_timeout {
+            policy.timeout =
+                humantime::parse_duration(timeout).map_err(|e| format!("request_timeout: {e}"))?;
+            if policy.timeout.is_zero() {
+                return Err("request_timeout: must be longer than zero".to_string());
+            }
+        }
+        if let Some(retries) = self.retries {
+            policy.retries = retries;
+        }
+        if let Some(max_tool_rounds) = self.max_tool_rounds {
+            if max_tool_rounds == 0 {
+                return Err("max_tool_rounds: must be at least 1".to_string());
+            }
+            policy.max_tool_rounds = max_tool_rounds;
+        }
+        if let Some(schema_retries) = self.schema_retries {
+            policy.schema_retries = schema_retries;
+        }
+        Ok(policy)
+    }
+
+    /// Limits on the pages the model fetches. Like the request policy, these are applied
+    /// directly; `--allow-any-url` is set from the command line.
+    pub fn fetch_policy(&self) -> Result<FetchPolicy, String> {
+        let mut policy = FetchPolicy::default();
+        if let Some(timeout) = &self.fetch_timeout {
+            policy.timeout =
+                humantime::parse_duration(timeout).map_err(|e| format!("fetch_timeout: {e}"))?;
+            if policy.timeout.is_zero() {
+                return Err("fetch_timeout: must be longer than zero".to_string());
+            }
+        }
+        if let Some(max_bytes) = self.fetch_max_bytes {
+            if max_bytes == 0 {
+                return Err("fetch_max_bytes: must be at least 1".to_string());
+            }
+            policy.max_bytes = max_bytes;
+        }
+        Ok(policy)
+    }
+
+    /// Collectors to enable and disable, with the browsers list folded in.
+    fn collectors(&self) -> (Vec<String>, Vec<String>) {
+        let mut enable = self.enable.clone().unwrap_or_default();
+        let mut disable = self.disable.clone().unwrap_or_default();
+        if let Some(browsers) = &self.browsers {
+            for browser in BROWSER_COLLECTORS {
+                if browsers.iter().any(|name| name == browser) {
+                    enable.push(browser.to_string());
+                } else {
+                    disable.push(browser.to_string());
+                }
+            }
+        }
+        (enable, disable)
+    }
+
+    /// Argument ids, the argument marking the commands they apply to, and the default values
+    /// the file gives them.
+    ///
+    /// Language model and collection settings apply wherever the common options (marked by
+    /// `host`) are; the output location only to `summarize` and `collect` (marked by `enable`),
+    /// since other commands write reports of their own.
+    fn defaults(&self) -> Vec<(&'static str, &'static str, Vec<String>)> {
+        let (enable, disable) = self.collectors();
+        let one = |value: &Option<String>| value.iter().cloned().collect::<Vec<_>>();
+        vec![
+            ("provider", "host", one(&self.provider)),
+            ("openai_org", "host", one(&self.openai_org)),
+            ("openai_project", "host", one(&self.openai_project)),
+            (
+                "secure",
+                "host",
+                self.secure.iter().map(bool::to_string).collect(),
+            ),
+            ("host", "host", one(&self.host)),
+            (
+                "port",
+                "host",
+                self.port.iter().map(u16::to_string).collect(),
+            ),
+            ("api_version", "host", one(&self.api_version)),
+            ("model", "host", one(&self.model)),
+            ("label_model", "host", one(&self.label_model)),
+            ("commit_model", "host", one(&self.commit_model)),
+            ("summary_model", "host", one(&self.summary_model)),
+            ("digest_model", "host", one(&self.digest_model)),
+            (
+                "context_window",
+                "host",
+                self.context_window.iter().map(usize::to_string).collect(),
+            ),
+            ("keep_alive", "host", one(&self.keep_alive)),
+            ("embedding_model", "host", one(&self.embedding_model)),
+            (
+                "embedding_batch_size",
+                "host",
+                self.embedding_batch_size
+                    .iter()
+                    .map(usize::to_string)
+                    .collect(),
+            ),
+            ("embedder", "host", one(&self.embedder)),
+            (
+                "remote_embedding_model",
+                "host",
+                one(&self.remote_embedding_model),
+            ),
+            ("ollama_url", "host", one(&self.ollama_url)),
+            ("duration", "host", one(&self.duration)),
+            ("format", "enable", one(&self.format)),
+            (
+                "text_width",
+                "enable",
+                self.text_width.iter().map(usize::to_string).collect(),
+            ),
+            (
+                "output",
+                "enable",
+                self.output
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+            ),
+            ("enable", "enable", enable),
+            ("disable", "enable", disable),
+            ("repos", "enable", self.repos.clone().unwrap_or_default()),
+            (
+                "exclude_repos",
+                "enable",
+                self.exclude_repos.clone().unwrap_or_default(),
+            ),
+        ]
+    }
+
+    /// Make the file's values the defaults of `cmd` and all of its subcommands, so that
+    /// options given on the command line still override them.
+    pub fn apply(&self, cmd: Command) -> Command {
+        apply_defaults(cmd, &self.defaults())
+    }
+}
+
+fn apply_defaults(
+    mut cmd: Command,
+    defaults: &[(&'static str, &'static str, Vec<String>)],
+) -> Command {
+    for (id, marker, values) in defaults {
+        let has = |id: &str| cmd.get_arguments().any(|arg| arg.get_id() == id);
+        if values.is_empty() || !has(id) || !has(marker) {
+            continue;
+        }
+        cmd = cmd.mut_arg(*id, |arg| arg.default_values(values.clone()));
+    }
+    let subcommands: Vec<String> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in subcommands {
+        cmd = cmd.mut_subcommand(name, |sub| apply_defaults(sub, defaults));
+    }
+    cmd
+}
+
+/// The `--profile` given in `args`, found before parsing so that the profile's values can
+/// become defaults.
+pub fn profile_arg<I: IntoIterator<Item = OsString>>(args: I) -> Option<String> {
+    let mut args = args
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned());
+    let mut profile = None;
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == "--profile" {
+            profile = args.next();
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_string());
+        }
+    }
+    profile
+}
+
+/// A configuration file listing every setting, commented out at its built-in default.
+fn scaffold() -> String {
+    format!(
+        "# daily-ai configuration
+#
+# Each setting is the default for the command-line option of the same name; options given on
+# the command line win. Uncomment a line to change it.
+
+# Language model server: local (any OpenAI-compatible server at host and port), openai, or
+# anthropic. API keys are read from OPENAI_API_KEY or ANTHROPIC_API_KEY, or the keychain, never
+# from this file
+# provider = \"local\"
+# openai_org = \"org-...\"
+# openai_project = \"proj_...\"
+
+# Proxy for model servers, downloads, and APIs (HTTPS_PROXY and friends are ignored), and the
+# hosts reached directly, instead of NO_PROXY
+# proxy = \"http://proxy.example.com:3128\"
+# no_proxy = \"localhost,.internal\"
+# secure = false
+# host = \"localhost\"
+# port = 1234
+# api_version = \"v1\"
+# model = \"{model}\"
+
+# Models for particular requests, instead of `model`: labelling browsing history clusters,
+# writing commit messages, the summary, stand-up update, reports, and answers, and summarizing
+# large diffs and busy days' repositories before the summary
+# label_model = \"qwen3-4b\"
+# commit_model = \"qwen3-4b\"
+# summary_model = \"openai/gpt-oss-120b\"
+# digest_model = \"qwen3-4b\"
+
+# Context window of the model in tokens; collected data is trimmed to fit
+# context_window = {context_window}
+
+# How long to wait for each answer from the model, how many times to retry one that times out
+# or fails in passing (waiting longer each time), and how many rounds of tool calls the model
+# may make before it must answer
+# request_timeout = \"{request_timeout}\"
+# retries = {retries}
+# max_tool_rounds = {max_tool_rounds}
+
+# How many times to ask again when an answer does not match the expected JSON, for local models
+# that ignore the schema; after that its fields are picked out of the answer one by one
+# schema_retries = {schema_retries}
+
+# How long the model's fetch_url tool waits for a page, and how many bytes of it are read; the
+# rest is cut off. Pages are only fetched from sites in the browser history, and only when the
+# site's robots.txt allows it
+# fetch_timeout = \"{fetch_timeout}\"
+# fetch_max_bytes = {fetch_max_bytes}
+
+# Load the model as a run starts and ping it this often while collecting, for local servers
+# that unload idle models; off unless set
+# keep_alive = \"4m\"
+
+# Hugging Face model used to embed browsing history before grouping it
+# embedding_model = \"{embedding_model}\"
+
+# How many texts that model embeds in one pass; larger batches are faster on a GPU but use more
+# memory
+# embedding_batch_size = {embedding_batch_size}
+
+# Where browsing history and highlights are embedded: local (the model above, or the language
+# model server's /embeddings endpoint when it cannot be loaded), openai (that endpoint), or
+# ollama (the server at ollama_url); the remote embedders ask for remote_embedding_model
+# embedder = \"local\"
+# remote_embedding_model = \"nomic-embed-text\"
+# ollama_url = \"{ollama_url}\"
+
+# How much history to summarize, e.g. \"1d\" or \"12h\"
+# duration = \"1d\"
+
+# Output format: json, dir, markdown, text, pdf, org, standup, jsonl, sqlite, obsidian, or ics
+# format = \"markdown\"
+
+# Column the text format is wrapped at
+# text_width = {text_width}
+
+# Where `summarize` and `collect` write their output; may contain {{{{date}}}} and {{{{profile}}}}
+# output = \"~/journal/{{{{date}}}}-{{{{profile}}}}.md\"
+
+# Collectors to run besides the default ones, and collectors to skip
+# enable = [\"messages\", \"tasks\"]
+# disable = [\"slack\"]
+
+# Only report repositories whose name or path contains one of these, and never these
+# repos = [\"acme\"]
+# exclude_repos = [\"dotfiles\"]
+
+# Browsers whose history is collected (safari, edge, or chrome); the others are skipped
+# browsers = [\"safari\"]
+
+# Product, project, and team names to spell and capitalize as written here. They are listed in
+# every prompt, and case slips and near misses like \"kestral\" are corrected in the answers
+# glossary = [\"Kestrel\", \"Platform Infra\", \"GraphQL\"]
+#
+# To reword a prompt, put a file with its name, e.g. summary_prompt.md, in the prompts directory
+# next to this file; it replaces the built-in prompt. Prompts can use the name, employer,
+# timezone, tone (the style, or --tone), and language from [about_me], and the --length preset
+# as length, e.g. {{{{ name }}}}
+
+# Who the reports are for, so summaries use your terminology and recognize your projects
+# [about_me]
+# name = \"Sam\"
+# role = \"Staff engineer\"
+# employer = \"Example Corp\"
+# team = \"Platform: build, CI, and deploy tooling\"
+# projects = [\"ingest-v2\", \"pager rotation\"]
+# style = \"Plain and terse; no marketing language\"
+# timezone = \"Europe/Berlin\"
+# language = \"English\"
+
+# Server that --email-to sends through. SMTP_HOST, SMTP_PORT, SMTP_USER, and SMTP_FROM override
+# these, and the password is only read from SMTP_PASSWORD
+# [smtp]
+# host = \"smtp.example.com\"
+# port = 587
+# user = \"sam@example.com\"
+# from = \"Sam <sam@example.com>\"
+
+# Profiles take the same settings and replace the ones above with `--profile NAME`
+# [profiles.work]
+# host = \"llm.internal.example.com\"
+# repos = [\"acme\"]
+# output = \"~/work-journal/{{{{date}}}}.md\"
+#
+# [profiles.personal]
+# exclude_repos = [\"acme\"]
+# browsers = []
+",
+        model = ai::DEFAULT_MODEL,
+        embedding_model = classify::DEFAULT_EMBEDDING_MODEL,
+        embedding_batch_size = classify::DEFAULT_EMBEDDING_BATCH_SIZE,
+        ollama_url = classify::embed::DEFAULT_OLLAMA_URL,
+        context_window = ai::token_budget::DEFAULT_CONTEXT_WINDOW,
+        text_width = render::text::DEFAULT_WIDTH,
+        request_timeout = humantime::format_duration(ai::retry::DEFAULT_REQUEST_TIMEOUT),
+        retries = ai::retry::DEFAULT_RETRIES,
+        max_tool_rounds = ai::retry::DEFAULT_MAX_TOOL_ROUNDS,
+        schema_retries = ai::retry::DEFAULT_SCHEMA_RETRIES,
+        fetch_timeout = humantime::format_duration(ai::tools::fetch::DEFAULT_FETCH_TIMEOUT),
+        fetch_max_bytes = ai::tools::fetch::DEFAULT_FETCH_MAX_BYTES,
+    )
+}
+
+/// Write a commented configuration file to the config directory, returning its path.
+///
+/// An existing file is only replaced when `force` is set.
+pub async fn init(force: bool) -> AppResult<PathBuf> {
+    let path = FileConfig::path()?;
+    if !force && fs::try_exists(&path).await? {
+        return Err(AppError::Config(format!(
+            "{} already exists; pass --force to replace it",
+            path.display()
+        )));
+    }
+    DirType::Config.ensure_dir_async().await?;
+    fs::write(&path, scaffold()).await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+    use crate::ai::ModelTask;
+    use crate::cli::{Cli, Cmd};
+
+    fn parse(config: &FileConfig, args: &[&str]) -> Cli {
+        let matches = config
+            .apply(Cli::command())
+            .try_get_matches_from(args)
+            .unwrap();
+        Cli::from_arg_matches(&matches).unwrap()
+    }
+
+    #[test]
+    fn file_values_are_defaults_that_flags_override() {
+        let config = FileConfig::parse(
+            "host = \"gpu-box.lan\"\nport = 8080\nmodel = \"qwen3\"\nenable = [\"tasks\"]\n",
+        )
+        .unwrap();
+
+        let Cmd::Summarize {
+            sources, default, ..
+        } = parse(&config, &["daily-ai", "summarize"]).cmd
+        else {
+            panic!("expected summarize");
+        };
+        assert_eq!(default.host, "gpu-box.lan");
+        assert_eq!(default.port, 8080);
+        assert_eq!(default.model, "qwen3");
+        assert_eq!(default.api_version, "v1");
+        assert_eq!(sources.enable, ["tasks"]);
+
+        let Cmd::Summarize { default, .. } = parse(
+            &config,
+            &[
+                "daily-ai",
+                "summarize",
+                "--host",
+                "localhost",
+                "--port",
+                "1234",
+            ],
+        )
+        .cmd
+        else {
+            panic!("expected summarize");
+        };
+        assert_eq!(default.host, "localhost");
+        assert_eq!(default.port, 1234);
+        assert_eq!(default.model, "qwen3");
+    }
+
+    #[test]
+    fn task_models_fall_back_to_the_main_model() {
+        let config = FileConfig::parse("model = \"qwen3\"\nlabel_model = \"qwen3-4b\"\n").unwrap();
+
+        let Cmd::Summarize { default, .. } = parse(
+            &config,
+            &["daily-ai", "summarize", "--summary-model", "gpt-oss-120b"],
+        )
+        .cmd
+        else {
+            panic!("expected summarize");
+        };
+        assert_eq!(default.model_for(ModelTask::LabelUrls), "qwen3-4b");
+        assert_eq!(default.model_for(ModelTask::CommitMessage), "qwen3");
+        assert_eq!(default.model_for(ModelTask::Summary), "gpt-oss-120b");
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        assert!(FileConfig::parse("hostname = \"x\"").is_err());
+        assert!(FileConfig::parse("format = \"docx\"").is_err());
+        assert!(FileConfig::parse("provider = \"gemini\"").is_err());
+        assert!(FileConfig::parse("duration = \"soon\"").is_err());
+        assert!(FileConfig::parse("proxy = \"not a url\"").is_err());
+        assert!(FileConfig::parse("request_timeout = \"soon\"").is_err());
+        assert!(FileConfig::parse("keep_alive = \"soon\"").is_err());
+        assert!(FileConfig::parse("max_tool_rounds = 0").is_err());
+        assert!(FileConfig::parse("fetch_max_bytes = 0").is_err());
+        assert!(FileConfig::parse("[about_me]\nnickname = \"x\"").is_err());
+        assert!(FileConfig::parse("[smtp]\npassword = \"x\"").is_err());
+        assert_eq!(FileConfig::parse("").unwrap(), FileConfig::default());
+    }
+
+    #[test]
+    fn scaffold_parses_to_the_built_in_defaults() {
+        assert_eq!(
+            FileConfig::parse(&scaffold()).unwrap(),
+            FileConfig::default()
+        );
+    }
+
+    #[test]
+    fn profiles_replace_top_level_settings() {
+        let config = FileConfig::parse(
+            r#"
+host = "localhost"
+model = "qwen3"
+format = "markdown"
+
+[profiles.work]
+host = "llm.corp"
+repos = ["acme"]
+output = "~/work/{{date}}.md"
+
+[profiles.personal]
+browsers = []
+"#,
+        )
+        .unwrap();
+
+        let work = config.clone().with_profile(Some("work")).unwrap();
+        assert_eq!(work.host.as_deref(), Some("llm.corp"));
+        assert_eq!(work.model.as_deref(), Some("qwen3"));
+        let Cmd::Summarize {
+            sources, default, ..
+        } = parse(&work, &["daily-ai", "summarize"]).cmd
+        else {
+            panic!("expected summarize");
+        };
+        assert_eq!(default.host, "llm.corp");
+        assert_eq!(sources.repos.repos, ["acme"]);
+        assert_eq!(
+            default.output.as_deref(),
+            Some(Path::new("~/work/{{date}}.md"))
+        );
+
+        let personal = config.clone().with_profile(Some("personal")).unwrap();
+        let Cmd::Summarize { sources, .. } = parse(&personal, &["daily-ai", "summarize"]).cmd
+        else {
+            panic!("expected summarize");
+        };
+        assert_eq!(sources.disable, ["safari", "edge", "chrome"]);
+
+        let about_me = FileConfig::parse("[about_me]\nrole = \"SRE\"\nprojects = [\"ingest-v2\"]")
+            .unwrap()
+            .about_me
+            .unwrap();
+        assert_eq!(about_me.role.as_deref(), Some("SRE"));
+        assert_eq!(about_me.projects, ["ingest-v2"]);
+
+        assert!(config.with_profile(Some("play")).is_err());
+        assert!(FileConfig::parse("[profiles.a.profiles.b]").is_err());
+        assert!(FileConfig::parse("browsers = [\"lynx\"]").is_err());
+    }
+
+    #[test]
+    fn output_settings_leave_other_commands_alone() {
+        let config = FileConfig::parse("format = \"markdown\"\noutput = \"out.md\"").unwrap();
+
+        let Cmd::Stats { format, .. } = parse(&config, &["daily-ai", "stats"]).cmd else {
+            panic!("expected stats");
+        };
+        assert_eq!(format, crate::stats::StatsFormat::default());
+        let Cmd::Dashboard { output, .. } = parse(&config, &["daily-ai", "dashboard"]).cmd else {
+            panic!("expected dashboard");
+        };
+        assert_eq!(output, Path::new("site"));
+    }
+
+    #[test]
+    fn finds_the_profile_before_parsing() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            profile_arg(args(&["daily-ai", "summarize", "--profile", "work"])).as_deref(),
+            Some("work")
+        );
+        assert_eq!(
+            profile_arg(args(&["daily-ai", "--profile=home", "stats"])).as_deref(),
+            Some("home")
+        );
+        assert_eq!(
+            profile_arg(args(&["daily-ai", "summarize", "--", "--profile", "x"])),
+            None
+        );
+    }
+}
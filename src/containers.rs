@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::time_utils::past_ts;
+
+/// Event types worth reporting. Network and volume events are mostly side effects of these.
+const EVENT_TYPES: [&str; 2] = ["container", "image"];
+
+/// Container engine the events were read from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerEngine {
+    Docker,
+    Podman,
+}
+
+impl ContainerEngine {
+    fn binary(self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker",
+            ContainerEngine::Podman => "podman",
+        }
+    }
+}
+
+/// Everything that happened to one container or image during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerActivity {
+    pub engine: ContainerEngine,
+    /// Event type, `container` or `image`.
+    pub kind: String,
+    /// Container name, or image reference for image events.
+    pub name: String,
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Number of times each action (`start`, `die`, `build`, `pull`, ...) was seen.
+    pub actions: BTreeMap<String, usize>,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_seen: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_seen: OffsetDateTime,
+}
+
+/// A single event, normalised across the Docker and Podman JSON formats.
+#[derive(Debug, PartialEq, Eq)]
+struct ContainerEvent {
+    kind: String,
+    action: String,
+    name: String,
+    image: Option<String>,
+    time: OffsetDateTime,
+}
+
+fn str_field<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| value.pointer(key).and_then(Value::as_str))
+        .filter(|s| !s.is_empty())
+}
+
+/// Parse one line of `docker events --format '{{json .}}'` or `podman events --format json`.
+///
+/// Docker nests names under `Actor.Attributes` and reports `time`/`timeNano` as unix
+/// timestamps. Podman uses flat `Name`/`Image`/`Status` fields and an RFC 3339 `Time` (older
+/// releases) or a unix `time` (newer ones).
+fn parse_event(line: &str) -> Option<ContainerEvent> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let kind = str_field(&value, &["/Type", "/type"])?.to_lowercase();
+    let action = str_field(&value, &["/Action", "/Status", "/status"])?
+        // Docker reports exec events as e.g. `exec_start: sh -c ...`.
+        .split(':')
+        .next()?
+        .trim()
+        .to_string();
+    let image =
+        str_field(&value, &["/Actor/Attributes/image", "/Image", "/from"]).map(str::to_string);
+    let name = str_field(&value, &["/Actor/Attributes/name", "/Name"])
+        .map(str::to_string)
+        .or_else(|| image.clone())
+        .or_else(|| str_field(&value, &["/Actor/ID", "/ID", "/id"]).map(str::to_string))?;
+    let time = if let Some(nanos) = value.get("timeNano").and_then(Value::as_i64) {
+        OffsetDateTime::from_unix_timestamp_nanos(nanos.into()).ok()?
+    } else if let Some(secs) = ["time", "Time"]
+        .iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_i64))
+    {
+        OffsetDateTime::from_unix_timestamp(secs).ok()?
+    } else {
+        OffsetDateTime::parse(str_field(&value, &["/Time"])?, &Rfc3339).ok()?
+    };
+    Some(ContainerEvent {
+        kind,
+        action,
+        name,
+        image,
+        time,
+    })
+}
+
+/// Group events by kind and name, counting actions and tracking when each was first and last seen.
+fn aggregate(engine: ContainerEngine, events: Vec<ContainerEvent>) -> Vec<ContainerActivity> {
+    let mut activity: BTreeMap<(String, String), ContainerActivity> = BTreeMap::new();
+    for event in events {
+        let entry = activity
+            .entry((event.kind.clone(), event.name.clone()))
+            .or_insert_with(|| ContainerActivity {
+                engine,
+                kind: event.kind,
+                name: event.name,
+                image: None,
+                actions: BTreeMap::new(),
+                first_seen: event.time,
+                last_seen: event.time,
+            });
+        *entry.actions.entry(event.action).or_default() += 1;
+        entry.first_seen = entry.first_seen.min(event.time);
+        entry.last_seen = entry.last_seen.max(event.time);
+        if event.image.is_some() {
+            entry.image = event.image;
+        }
+    }
+    let mut activity: Vec<ContainerActivity> = activity.into_values().collect();
+    activity.sort_by_key(|a| a.first_seen);
+    activity
+}
+
+/// Read past events from one engine. Returns `None` when it is not installed or the daemon is
+/// not reachable.
+async fn read_events(
+    engine: ContainerEngine,
+    since: OffsetDateTime,
+    until: OffsetDateTime,
+) -> AppResult<Option<Vec<ContainerEvent>>> {
+    let mut cmd = Command::new(engine.binary());
+    cmd.arg("events")
+        .arg("--since")
+        .arg(since.unix_timestamp().to_string())
+        .arg("--until")
+        .arg(until.unix_timestamp().to_string());
+    for kind in EVENT_TYPES {
+        cmd.arg("--filter").arg(format!("type={kind}"));
+    }
+    match engine {
+        ContainerEngine::Docker => cmd.args(["--format", "{{json .}}"]),
+        ContainerEngine::Podman => cmd.args(["--format", "json", "--stream=false"]),
+    };
+    let output = match cmd.output().await {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("{} is not installed", engine.binary());
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !output.status.success() {
+        debug!(
+            "{} events exited with {}: {}",
+            engine.binary(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_event)
+            .collect(),
+    ))
+}
+
+/// Collect container and image activity (runs, builds, pulls) from Docker and Podman.
+///
+/// Both engines are queried, since it is common to have both installed. Returns an empty list
+/// when neither is installed or running.
+#[tracing::instrument(name = "Collecting container activity", level = "info")]
+pub async fn get_container_activity(duration: &Duration) -> AppResult<Vec<ContainerActivity>> {
+    let since = past_ts(duration);
+    let until = OffsetDateTime::now_utc();
+    let mut activity = Vec::new();
+    for engine in [ContainerEngine::Docker, ContainerEngine::Podman] {
+        if let Some(events) = read_events(engine, since, until).await? {
+            activity.extend(aggregate(engine, events));
+        }
+    }
+    debug!(
+        "Found activity for {} containers and images",
+        activity.len()
+    );
+    Ok(activity)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn parses_docker_and_podman_events() {
+        let docker = parse_event(
+            r#"{"status":"start","id":"abc","from":"postgres:16","Type":"container","Action":"start","Actor":{"ID":"abc","Attributes":{"image":"postgres:16","name":"db"}},"time":1735722000,"timeNano":1735722000000000000}"#,
+        )
+        .unwrap();
+        assert_eq!(docker.kind, "container");
+        assert_eq!(docker.action, "start");
+        assert_eq!(docker.name, "db");
+        assert_eq!(docker.time, datetime!(2025-01-01 09:00 UTC));
+
+        let podman = parse_event(
+            r#"{"ID":"def","Image":"localhost/app:dev","Name":"localhost/app:dev","Status":"build","Time":"2025-01-01T10:00:00Z","Type":"image"}"#,
+        )
+        .unwrap();
+        assert_eq!(podman.kind, "image");
+        assert_eq!(podman.action, "build");
+        assert_eq!(podman.image.as_deref(), Some("localhost/app:dev"));
+
+        let exec = parse_event(
+            r#"{"Type":"container","Action":"exec_start: sh -c ls","Actor":{"ID":"abc","Attributes":{"name":"db"}},"time":1735722000}"#,
+        )
+        .unwrap();
+        assert_eq!(exec.action, "exec_start");
+        assert!(parse_event("not json").is_none());
+    }
+
+    #[test]
+    fn groups_events_by_container() {
+        let event = |action: &str, name: &str, hour: u8| ContainerEvent {
+            kind: "container".into(),
+            action: action.into(),
+            name: name.into(),
+            image: Some("postgres:16".into()),
+            time: datetime!(2025-01-01 00:00 UTC).replace_hour(hour).unwrap(),
+        };
+        let activity = aggregate(
+            ContainerEngine::Docker,
+            vec![
+                event("start", "db", 9),
+                event("die", "db", 10),
+                event("start", "db", 11),
+                event("start", "cache", 8),
+            ],
+        );
+
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].name, "cache");
+        let db = &activity[1];
+        assert_eq!(db.actions["start"], 2);
+        assert_eq!(db.actions["die"], 1);
+        assert_eq!(db.last_seen - db.first_seen, Duration::hours(2));
+    }
+}
@@ -1,33 +1,298 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use ulid::Ulid;
 
 use crate::ai::summary::WorkSummary;
+use crate::apple_notes::AppleNote;
+use crate::ci::CiRun;
 use crate::classify::UrlCluster;
+use crate::clipboard::ClipboardActivity;
+use crate::containers::ContainerActivity;
+use crate::email::EmailThread;
+use crate::external::ExternalActivity;
 use crate::git::hist::GitRepoHistory;
+use crate::hosts::HostActivity;
+use crate::ide_builds::IdeBuild;
+use crate::k8s::K8sActivity;
+use crate::messages::MessageConversation;
+use crate::notes::NoteActivity;
+use crate::recordings::Recording;
+use crate::releases::Release;
 use crate::shell::ShellHistoryEntry;
+use crate::slack::SlackChannelActivity;
+use crate::tasks::CompletedTask;
+use crate::tickets::TicketActivity;
+use crate::tmux::TmuxSession;
+use crate::warnings::DataWarning;
+
+/// Layout version of serialized contexts.
+///
+/// Version 1 had no `version` field and stored each source as a top-level array;
+/// [`FullContext::from_json`] migrates it. Bump this, and add a migration, whenever the layout
+/// changes in a way older readers cannot follow.
+pub const CONTEXT_VERSION: u32 = 2;
+
+/// Declare the per-source sections of a [`Context`].
+///
+/// Each entry generates a [`Section`] and [`SourceKind`] variant, a `Context` accessor returning
+/// the source's items, and a `with_*` builder. The accessor name doubles as the section's tag
+/// and its version 1 field name.
+macro_rules! sections {
+    ($($(#[$doc:meta])* $variant:ident($item:ty) => $getter:ident, $builder:ident;)*) => {
+        /// Data collected from one source, tagged with the source's name.
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        #[serde(tag = "source", content = "data", rename_all = "snake_case")]
+        pub enum Section {
+            $($(#[$doc])* $variant(Vec<$item>),)*
+        }
+
+        /// Identifies the source of a [`Section`].
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum SourceKind {
+            $($(#[$doc])* $variant,)*
+        }
+
+        impl SourceKind {
+            /// Every source, in the order sections are stored.
+            pub const ALL: &[SourceKind] = &[$(SourceKind::$variant,)*];
+
+            /// Section tag, also used for file names in `--format dir` output.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(SourceKind::$variant => stringify!($getter),)*
+                }
+            }
+        }
+
+        impl Section {
+            pub fn kind(&self) -> SourceKind {
+                match self {
+                    $(Section::$variant(_) => SourceKind::$variant,)*
+                }
+            }
+
+            pub fn len(&self) -> usize {
+                match self {
+                    $(Section::$variant(items) => items.len(),)*
+                }
+            }
+
+            /// The section's items as a JSON array.
+            pub fn items_json(&self) -> serde_json::Result<Value> {
+                match self {
+                    $(Section::$variant(items) => serde_json::to_value(items),)*
+                }
+            }
+
+            /// Append `other`'s items when it holds the same source, otherwise hand it back.
+            fn extend(&mut self, other: Section) -> Option<Section> {
+                match (self, other) {
+                    $((Section::$variant(items), Section::$variant(more)) => {
+                        items.extend(more);
+                        None
+                    })*
+                    (_, other) => Some(other),
+                }
+            }
+        }
+
+        impl Context {
+            $(
+                pub fn $getter(&self) -> &[$item] {
+                    match self.section(SourceKind::$variant) {
+                        Some(Section::$variant(items)) => items,
+                        _ => &[],
+                    }
+                }
+
+                pub fn $builder(mut self, items: Vec<$item>) -> Self {
+                    self.insert(Section::$variant(items));
+                    self
+                }
+            )*
+        }
+    };
+}
+
+sections! {
+    /// Shell commands from atuin or shell history files
+    ShellHistory(ShellHistoryEntry) => shell_history, with_shell_history;
+    /// Labelled clusters of Safari history
+    SafariHistory(UrlCluster) => safari_history, with_safari_history;
+    /// Commits and diffs per repository
+    CommitHistory(GitRepoHistory) => commit_history, with_commit_history;
+    /// Builds and test runs started from IDEs
+    IdeBuilds(IdeBuild) => ide_builds, with_ide_builds;
+    /// Tickets referenced by commits and branches
+    Tickets(TicketActivity) => tickets, with_tickets;
+    /// CI workflow runs for the window's commits
+    CiRuns(CiRun) => ci_runs, with_ci_runs;
+    /// Tags, published packages, and GitHub releases
+    Releases(Release) => releases, with_releases;
+    /// Messages.app conversation metadata
+    Messages(MessageConversation) => messages, with_messages;
+    /// tmux sessions and windows
+    Tmux(TmuxSession) => tmux, with_tmux;
+    /// Docker and Podman container activity
+    Containers(ContainerActivity) => containers, with_containers;
+    /// kubectl/helm activity
+    K8s(K8sActivity) => k8s, with_k8s;
+    /// Hosts reached with ssh, scp, rsync, sftp, or mosh
+    Hosts(HostActivity) => hosts, with_hosts;
+    /// Clipboard manager copy counts
+    Clipboard(ClipboardActivity) => clipboard, with_clipboard;
+    /// Notes touched in a Markdown vault
+    VaultNotes(NoteActivity) => vault_notes, with_vault_notes;
+    /// Apple Notes created or edited
+    AppleNotes(AppleNote) => apple_notes, with_apple_notes;
+    /// asciinema terminal recordings
+    Recordings(Recording) => recordings, with_recordings;
+    /// Tasks completed in Things, OmniFocus, or Reminders
+    CompletedTasks(CompletedTask) => completed_tasks, with_completed_tasks;
+    /// Slack messages sent, counted per channel
+    Slack(SlackChannelActivity) => slack, with_slack;
+    /// Email thread metadata
+    Email(EmailThread) => email, with_email;
+    /// Activities reported by external collectors
+    External(ExternalActivity) => external, with_external;
+}
+
+impl Section {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 /// Aggregate of all histories collected by the tool for a run.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Sources are stored as tagged [`Section`]s, at most one per [`SourceKind`] and never empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Context {
-    pub shell_history: Vec<ShellHistoryEntry>,
-    pub safari_history: Vec<UrlCluster>,
-    pub commit_history: Vec<GitRepoHistory>,
+    pub version: u32,
+    #[serde(default)]
+    sections: Vec<Section>,
+    /// Collectors that failed during the run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_collectors: Vec<String>,
 }
 
-/// Aggregate of all histories collected by the tool for a run.
-#[derive(Debug, Serialize, Deserialize)]
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            version: CONTEXT_VERSION,
+            sections: Vec::new(),
+            failed_collectors: Vec::new(),
+        }
+    }
+}
+
+impl Context {
+    /// The collected sections, in [`SourceKind`] order.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The collected sections, to edit their items in place. Sections must not be emptied.
+    pub fn sections_mut(&mut self) -> &mut [Section] {
+        &mut self.sections
+    }
+
+    pub fn section(&self, kind: SourceKind) -> Option<&Section> {
+        self.sections.iter().find(|section| section.kind() == kind)
+    }
+
+    /// Add `section`, replacing the existing section for its source. Empty sections are dropped.
+    pub fn insert(&mut self, section: Section) {
+        self.remove(section.kind());
+        if section.is_empty() {
+            return;
+        }
+        let at = self
+            .sections
+            .partition_point(|existing| existing.kind() < section.kind());
+        self.sections.insert(at, section);
+    }
+
+    /// Remove and return the section for `kind`.
+    pub fn remove(&mut self, kind: SourceKind) -> Option<Section> {
+        let at = self
+            .sections
+            .iter()
+            .position(|section| section.kind() == kind)?;
+        Some(self.sections.remove(at))
+    }
+
+    /// Whether no collector found anything.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Append everything collected in `other`.
+    pub fn merge(&mut self, other: Context) {
+        for section in other.sections {
+            let kind = section.kind();
+            let leftover = match self.sections.iter_mut().find(|s| s.kind() == kind) {
+                Some(existing) => existing.extend(section),
+                None => Some(section),
+            };
+            if let Some(section) = leftover {
+                self.insert(section);
+            }
+        }
+        self.failed_collectors.extend(other.failed_collectors);
+    }
+}
+
+/// A run's collected context together with its summary and data quality report.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct FullContext {
-    pub shell_history: Vec<ShellHistoryEntry>,
-    pub safari_history: Vec<UrlCluster>,
-    pub commit_history: Vec<GitRepoHistory>,
+    /// Identifies the run in outputs, archives, and exports. Missing from older archives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<Ulid>,
+    #[serde(flatten)]
+    pub context: Context,
+    /// Gaps in the collected data, rendered as the "Data quality" section.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_quality: Vec<DataWarning>,
     pub summary: Option<WorkSummary>,
 }
 
+impl FullContext {
+    /// Parse a serialized run, migrating older layouts to [`CONTEXT_VERSION`].
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let mut value: Value = serde_json::from_str(json)?;
+        match &mut value {
+            Value::Object(fields) if !fields.contains_key("version") => migrate_v1(fields),
+            _ => {}
+        }
+        serde_json::from_value(value)
+    }
+}
+
+/// Move version 1's top-level source arrays into tagged sections.
+fn migrate_v1(fields: &mut Map<String, Value>) {
+    let mut sections = Vec::new();
+    for kind in SourceKind::ALL {
+        match fields.remove(kind.name()) {
+            Some(Value::Array(items)) if !items.is_empty() => {
+                let mut section = Map::new();
+                section.insert("source".into(), kind.name().into());
+                section.insert("data".into(), Value::Array(items));
+                sections.push(Value::Object(section));
+            }
+            _ => {}
+        }
+    }
+    fields.insert("version".into(), CONTEXT_VERSION.into());
+    fields.insert("sections".into(), Value::Array(sections));
+}
+
 impl From<(Context, WorkSummary)> for FullContext {
     fn from((context, summary): (Context, WorkSummary)) -> Self {
         FullContext {
-            shell_history: context.shell_history,
-            safari_history: context.safari_history,
-            commit_history: context.commit_history,
+            run_id: None,
+            context,
+            data_quality: Vec::new(),
             summary: Some(summary),
         }
     }
@@ -36,10 +301,76 @@ impl From<(Context, WorkSummary)> for FullContext {
 impl From<Context> for FullContext {
     fn from(context: Context) -> Self {
         FullContext {
-            shell_history: context.shell_history,
-            safari_history: context.safari_history,
-            commit_history: context.commit_history,
+            run_id: None,
+            context,
+            data_quality: Vec::new(),
             summary: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn session(name: &str) -> TmuxSession {
+        TmuxSession {
+            multiplexer: Default::default(),
+            name: name.into(),
+            created: OffsetDateTime::UNIX_EPOCH,
+            last_activity: OffsetDateTime::UNIX_EPOCH,
+            attached: false,
+            attached_for: time::Duration::ZERO,
+            windows: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn merges_sections_by_source() {
+        let mut context = Context::default().with_tmux(vec![session("a")]);
+        context.merge(
+            Context::default()
+                .with_tmux(vec![session("b")])
+                .with_shell_history(Vec::new()),
+        );
+
+        assert_eq!(context.tmux().len(), 2);
+        assert_eq!(context.sections().len(), 1);
+        assert!(context.shell_history().is_empty());
+        assert!(context.remove(SourceKind::Tmux).is_some());
+        assert!(context.is_empty());
+    }
+
+    #[test]
+    fn migrates_version_1_archives() {
+        let v1 = r#"{
+            "shell_history": [],
+            "safari_history": [],
+            "commit_history": [],
+            "tmux": [{
+                "name": "work",
+                "created": "2025-01-01T09:00:00Z",
+                "last_activity": "2025-01-01T10:00:00Z",
+                "attached": true,
+                "attached_for": "1h",
+                "windows": []
+            }],
+            "summary": null
+        }"#;
+
+        let full = FullContext::from_json(v1).unwrap();
+
+        assert_eq!(full.context.version, CONTEXT_VERSION);
+        assert_eq!(full.context.tmux()[0].name, "work");
+        assert_eq!(full.context.sections().len(), 1);
+
+        let round_trip = serde_json::to_string(&full).unwrap();
+        assert!(round_trip.contains(r#""sections":[{"source":"tmux","data":["#));
+        assert_eq!(
+            FullContext::from_json(&round_trip).unwrap().context.tmux()[0].name,
+            "work"
+        );
+    }
+}
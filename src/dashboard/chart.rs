@@ -0,0 +1,58 @@
+use std::fmt::Write;
+
+use super::html::escape;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 160.0;
+const PADDING: f64 = 24.0;
+
+/// Render a labelled bar chart as inline SVG. Each bar carries a `<title>` tooltip.
+pub fn bar_chart(title: &str, unit: &str, points: &[(String, f64)]) -> String {
+    let mut svg = String::new();
+    let max = points.iter().map(|(_, v)| *v).fold(0.0, f64::max).max(1.0);
+    let slot = (WIDTH - 2.0 * PADDING) / points.len().max(1) as f64;
+    let bar_width = (slot * 0.8).max(1.0);
+    let plot_height = HEIGHT - 2.0 * PADDING;
+
+    let _ = write!(
+        svg,
+        r#"<figure class="chart"><figcaption>{}</figcaption><svg viewBox="0 0 {WIDTH} {HEIGHT}" role="img" aria-label="{}">"#,
+        escape(title),
+        escape(title)
+    );
+    let _ = write!(
+        svg,
+        r#"<line class="axis" x1="{PADDING}" y1="{y}" x2="{x2}" y2="{y}"/>"#,
+        y = HEIGHT - PADDING,
+        x2 = WIDTH - PADDING
+    );
+    for (i, (label, value)) in points.iter().enumerate() {
+        let height = value / max * plot_height;
+        let x = PADDING + i as f64 * slot + (slot - bar_width) / 2.0;
+        let y = HEIGHT - PADDING - height;
+        let _ = write!(
+            svg,
+            r#"<rect class="bar" x="{x:.1}" y="{y:.1}" width="{bar_width:.1}" height="{height:.1}"><title>{}: {value} {}</title></rect>"#,
+            escape(label),
+            escape(unit)
+        );
+    }
+    let _ = write!(
+        svg,
+        r#"<text class="max" x="{PADDING}" y="{y}">{max} {}</text>"#,
+        escape(unit),
+        y = PADDING - 8.0
+    );
+    if let (Some((first, _)), Some((last, _))) = (points.first(), points.last()) {
+        let _ = write!(
+            svg,
+            r#"<text class="label" x="{PADDING}" y="{y}">{}</text><text class="label end" x="{x}" y="{y}">{}</text>"#,
+            escape(first),
+            escape(last),
+            y = HEIGHT - 6.0,
+            x = WIDTH - PADDING
+        );
+    }
+    svg.push_str("</svg></figure>");
+    svg
+}
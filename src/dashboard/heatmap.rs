@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use time::{OffsetDateTime, UtcOffset};
+
+use super::{ACTIVE_SLOT_SECS, activity_timestamps};
+use crate::context::FullContext;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Terminal cells by intensity, two columns wide so the grid looks roughly square.
+const TERMINAL_LEVELS: [&str; 5] = ["··", "░░", "▒▒", "▓▓", "██"];
+
+const CELL: f64 = 20.0;
+const GAP: f64 = 3.0;
+const LABEL_WIDTH: f64 = 34.0;
+const HEADER_HEIGHT: f64 = 16.0;
+
+/// Active minutes per weekday (Monday first) and hour of the day, in local time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heatmap {
+    minutes: [[i64; 24]; 7],
+}
+
+impl Heatmap {
+    /// Count the distinct active 15-minute slots of every run, placed in `offset`'s local time.
+    pub fn from_contexts<'a>(
+        contexts: impl IntoIterator<Item = &'a FullContext>,
+        offset: UtcOffset,
+    ) -> Self {
+        let slots: HashSet<i64> = contexts
+            .into_iter()
+            .flat_map(activity_timestamps)
+            .map(|ts| ts.unix_timestamp().div_euclid(ACTIVE_SLOT_SECS))
+            .collect();
+        let mut heatmap = Heatmap::default();
+        for slot in slots {
+            let Ok(start) = OffsetDateTime::from_unix_timestamp(slot * ACTIVE_SLOT_SECS) else {
+                continue;
+            };
+            let local = start.to_offset(offset);
+            let weekday = local.weekday().number_days_from_monday() as usize;
+            heatmap.minutes[weekday][local.hour() as usize] += ACTIVE_SLOT_SECS / 60;
+        }
+        heatmap
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.minutes.iter().flatten().all(|&minutes| minutes == 0)
+    }
+
+    /// Intensity from 0 (no activity) to 4 (the busiest hour), relative to the busiest hour.
+    fn level(&self, minutes: i64) -> usize {
+        let max = self.minutes.iter().flatten().copied().max().unwrap_or(0);
+        if minutes <= 0 || max <= 0 {
+            return 0;
+        }
+        ((minutes * 4 + max - 1) / max).clamp(1, 4) as usize
+    }
+
+    /// Render the grid as Unicode blocks, one row per weekday and one column per hour.
+    pub fn to_terminal(&self) -> String {
+        let mut out = String::from("    ");
+        for hour in (0..24).step_by(3) {
+            let _ = write!(out, "{hour:02}    ");
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        for (weekday, hours) in WEEKDAYS.iter().zip(&self.minutes) {
+            let _ = write!(out, "{weekday} ");
+            for &minutes in hours {
+                out.push_str(TERMINAL_LEVELS[self.level(minutes)]);
+            }
+            out.push('\n');
+        }
+        let _ = writeln!(out, "    less {} more", TERMINAL_LEVELS.concat());
+        out
+    }
+
+    /// Render the grid as inline SVG. Each cell carries a `<title>` tooltip.
+    pub fn to_svg(&self) -> String {
+        let width = LABEL_WIDTH + 24.0 * (CELL + GAP);
+        let height = HEADER_HEIGHT + 7.0 * (CELL + GAP);
+        let mut svg = format!(
+            r#"<figure class="chart heatmap"><figcaption>Active time by hour</figcaption><svg viewBox="0 0 {width} {height}" role="img" aria-label="Active time by hour">"#
+        );
+        for hour in (0..24).step_by(3) {
+            let _ = write!(
+                svg,
+                r#"<text class="label" x="{x:.1}" y="{y}">{hour:02}</text>"#,
+                x = LABEL_WIDTH + hour as f64 * (CELL + GAP),
+                y = HEADER_HEIGHT - 4.0
+            );
+        }
+        for (row, (weekday, hours)) in WEEKDAYS.iter().zip(&self.minutes).enumerate() {
+            let y = HEADER_HEIGHT + row as f64 * (CELL + GAP);
+            let _ = write!(
+                svg,
+                r#"<text class="label" x="0" y="{y:.1}">{weekday}</text>"#,
+                y = y + CELL * 0.7
+            );
+            for (hour, &minutes) in hours.iter().enumerate() {
+                let _ = write!(
+                    svg,
+                    r#"<rect class="cell level-{level}" x="{x:.1}" y="{y:.1}" width="{CELL}" height="{CELL}" rx="3"><title>{weekday} {hour:02}:00: {minutes} active minutes</title></rect>"#,
+                    level = self.level(minutes),
+                    x = LABEL_WIDTH + hour as f64 * (CELL + GAP),
+                );
+            }
+        }
+        svg.push_str("</svg></figure>");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::Duration;
+    use time::macros::{datetime, offset};
+
+    use super::*;
+    use crate::context::Context;
+    use crate::shell::ShellHistoryEntry;
+
+    fn run_at(times: &[OffsetDateTime]) -> FullContext {
+        FullContext::from(
+            Context::default().with_shell_history(
+                times
+                    .iter()
+                    .map(|&date_time| ShellHistoryEntry {
+                        date_time,
+                        duration: Duration::ZERO,
+                        host: "localhost".into(),
+                        directory: PathBuf::from("/tmp"),
+                        command: "make".into(),
+                        exit_code: 0,
+                        session_id: "abc".into(),
+                    })
+                    .collect(),
+            ),
+        )
+    }
+
+    #[test]
+    fn buckets_active_slots_by_local_weekday_and_hour() {
+        // 2025-01-06 is a Monday.
+        let runs = [
+            run_at(&[
+                datetime!(2025-01-06 08:00 UTC),
+                datetime!(2025-01-06 08:05 UTC),
+                datetime!(2025-01-06 08:20 UTC),
+                datetime!(2025-01-06 08:35 UTC),
+                datetime!(2025-01-06 08:50 UTC),
+            ]),
+            run_at(&[datetime!(2025-01-07 23:30 UTC)]),
+        ];
+
+        let heatmap = Heatmap::from_contexts(&runs, offset!(+1));
+
+        assert_eq!(heatmap.minutes[0][9], 60);
+        assert_eq!(heatmap.minutes[2][0], 15);
+        assert_eq!(heatmap.minutes.iter().flatten().sum::<i64>(), 75);
+        assert_eq!(
+            (heatmap.level(60), heatmap.level(15), heatmap.level(0)),
+            (4, 1, 0)
+        );
+
+        let terminal = heatmap.to_terminal();
+        let monday = terminal.lines().nth(1).unwrap();
+        assert_eq!(monday.chars().count(), 4 + 48);
+        assert!(monday.starts_with("Mon ··················██"));
+        assert!(
+            heatmap
+                .to_svg()
+                .contains("<title>Mon 09:00: 60 active minutes</title>")
+        );
+        assert!(Heatmap::default().is_empty());
+    }
+}
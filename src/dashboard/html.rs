@@ -0,0 +1,74 @@
+/// Escape text for inclusion in HTML element content or quoted attributes.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Wrap page content in the shared layout. `root` is the relative path back to the site root.
+pub fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} · daily-ai</title>
+<link rel="stylesheet" href="{root}style.css">
+</head>
+<body>
+<header><a href="{root}index.html">daily-ai</a> <a href="{root}search.html">search</a></header>
+<main>
+<h1>{title}</h1>
+{body}
+</main>
+</body>
+</html>
+"#,
+        title = escape(title),
+    )
+}
+
+/// Render a `<ul>` of already-escaped items, or nothing when empty.
+pub fn list<I: IntoIterator<Item = String>>(items: I) -> String {
+    let items: Vec<String> = items
+        .into_iter()
+        .map(|item| format!("<li>{item}</li>"))
+        .collect();
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("<ul>{}</ul>", items.concat())
+    }
+}
+
+/// Render a titled section, or nothing when the content is empty.
+pub fn section(title: &str, content: &str) -> String {
+    if content.is_empty() {
+        String::new()
+    } else {
+        format!("<section><h2>{}</h2>{content}</section>\n", escape(title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup_characters() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
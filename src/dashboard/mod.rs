@@ -0,0 +1,510 @@
+/// Inline SVG charts.
+pub(crate) mod chart;
+
+/// Hour-by-weekday activity heatmap, for the terminal and the dashboard.
+pub(crate) mod heatmap;
+
+/// HTML escaping and shared page layout.
+pub(crate) mod html;
+
+/// Prebuilt lunr search index.
+pub(crate) mod search;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, UtcOffset};
+use tokio::fs;
+use tracing::{info, warn};
+
+use self::chart::bar_chart;
+use self::heatmap::Heatmap;
+use self::html::{escape, list, page, section};
+use self::search::{SearchDoc, build_lunr_index};
+use crate::AppResult;
+use crate::archive::{self, ArchiveEntry};
+use crate::context::FullContext;
+use crate::time_utils;
+
+/// Width of the activity slots used to estimate active time.
+pub(crate) const ACTIVE_SLOT_SECS: i64 = 15 * 60;
+
+/// Consecutive active slots that count as a deep-work block (one hour).
+const DEEP_WORK_SLOTS: usize = 4;
+
+static STYLE_CSS: &str = std::include_str!("style.css");
+static SEARCH_JS: &str = std::include_str!("search.js");
+
+/// When anything happened in a run: shell commands, commits, and page visits.
+pub fn activity_timestamps(context: &FullContext) -> impl Iterator<Item = OffsetDateTime> + '_ {
+    context
+        .context
+        .shell_history()
+        .iter()
+        .map(|entry| entry.date_time)
+        .chain(
+            context
+                .context
+                .commit_history()
+                .iter()
+                .flat_map(|repo| repo.commits.iter().map(|commit| commit.timestamp)),
+        )
+        .chain(
+            context
+                .context
+                .safari_history()
+                .iter()
+                .flat_map(|cluster| cluster.urls.iter().map(|url| url.last_visited)),
+        )
+}
+
+/// Per-day counters plotted on the dashboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayStats {
+    pub commits: usize,
+    pub urls: usize,
+    /// Estimated active time: the number of 15-minute slots with any shell, git, or browser
+    /// activity, in minutes.
+    pub active_minutes: i64,
+    /// Active time spent in unbroken stretches of at least an hour, in minutes.
+    pub deep_work_minutes: i64,
+}
+
+impl DayStats {
+    /// Compute the counters for one archived run.
+    pub fn from_context(context: &FullContext) -> Self {
+        let commits = context
+            .context
+            .commit_history()
+            .iter()
+            .map(|repo| repo.commits.len())
+            .sum();
+        let urls = context
+            .context
+            .safari_history()
+            .iter()
+            .map(|cluster| cluster.urls.len())
+            .sum();
+        let slots: BTreeSet<i64> = activity_timestamps(context)
+            .map(|ts| ts.unix_timestamp().div_euclid(ACTIVE_SLOT_SECS))
+            .collect();
+        let mut deep_work_slots = 0;
+        let mut run = 0;
+        let mut previous = None;
+        for &slot in &slots {
+            run = if previous == Some(slot - 1) {
+                run + 1
+            } else {
+                1
+            };
+            previous = Some(slot);
+            // Count the whole block once it is long enough, then each slot that extends it.
+            if run == DEEP_WORK_SLOTS {
+                deep_work_slots += DEEP_WORK_SLOTS;
+            } else if run > DEEP_WORK_SLOTS {
+                deep_work_slots += 1;
+            }
+        }
+        Self {
+            commits,
+            urls,
+            active_minutes: slots.len() as i64 * ACTIVE_SLOT_SECS / 60,
+            deep_work_minutes: deep_work_slots as i64 * ACTIVE_SLOT_SECS / 60,
+        }
+    }
+}
+
+/// `YYYY-MM` key used for month directories.
+fn month_key(date: Date) -> String {
+    format!("{:04}-{:02}", date.year(), u8::from(date.month()))
+}
+
+/// `YYYY-MM-DD` label used for day pages.
+fn day_key(date: Date) -> AppResult<String> {
+    Ok(date.format(format_description!("[year]-[month]-[day]"))?)
+}
+
+/// Relative URL of a day page from the site root.
+fn day_href(date: Date) -> AppResult<String> {
+    Ok(format!("{}/{}.html", month_key(date), day_key(date)?))
+}
+
+/// First sentence (or line) of the summary, for listings.
+fn excerpt(context: &FullContext) -> String {
+    let summary = context
+        .summary
+        .as_ref()
+        .map(|s| s.summary.as_str())
+        .unwrap_or_default();
+    let first = summary.split_inclusive(". ").next().unwrap_or_default();
+    first.lines().next().unwrap_or_default().trim().to_string()
+}
+
+/// Commits, URLs, and active-hours charts for a run of days, and when in the week the activity
+/// happened.
+fn charts(days: &[(&ArchiveEntry, DayStats)], offset: UtcOffset) -> AppResult<String> {
+    let mut commits = Vec::new();
+    let mut urls = Vec::new();
+    let mut hours = Vec::new();
+    for (entry, stats) in days {
+        let label = day_key(entry.date)?;
+        commits.push((label.clone(), stats.commits as f64));
+        urls.push((label.clone(), stats.urls as f64));
+        hours.push((
+            label,
+            (stats.active_minutes as f64 / 60.0 * 10.0).round() / 10.0,
+        ));
+    }
+    let heatmap = Heatmap::from_contexts(days.iter().map(|(entry, _)| &entry.context), offset);
+    Ok(format!(
+        r#"<div class="charts">{}{}{}{}</div>"#,
+        bar_chart("Commits", "commits", &commits),
+        bar_chart("URLs visited", "urls", &urls),
+        bar_chart("Active time", "hours", &hours),
+        if heatmap.is_empty() {
+            String::new()
+        } else {
+            heatmap.to_svg()
+        },
+    ))
+}
+
+/// Render the page for a single archived day.
+fn day_page(entry: &ArchiveEntry, stats: DayStats) -> AppResult<String> {
+    let context = &entry.context;
+    let mut body = format!(
+        r#"<p class="stats">{} commits · {} URLs · {:.1} active hours · <a href="index.html">{}</a></p>"#,
+        stats.commits,
+        stats.urls,
+        stats.active_minutes as f64 / 60.0,
+        month_key(entry.date)
+    );
+
+    if let Some(summary) = &context.summary {
+        body.push_str(&section(
+            "Summary",
+            &if summary.summary.is_empty() {
+                String::new()
+            } else {
+                format!("<p>{}</p>", escape(&summary.summary))
+            },
+        ));
+        for (title, items) in [
+            ("Highlights", &summary.highlights),
+            ("Releases", &summary.releases),
+            ("Time breakdown", &summary.time_breakdown),
+            ("Tickets touched", &summary.tickets_touched),
+            ("Pending review", &summary.pending_review),
+            ("Communication", &summary.communication),
+            ("Repositories", &summary.repo_summaries),
+            ("Common themes", &summary.common_groups),
+            ("Learned today", &summary.learned),
+        ] {
+            body.push_str(&section(title, &list(items.iter().map(|i| escape(i)))));
+        }
+        if !summary.shell_overview.is_empty() {
+            body.push_str(&section(
+                "Shell",
+                &format!("<p>{}</p>", escape(&summary.shell_overview)),
+            ));
+        }
+    }
+
+    let commits = list(context.context.commit_history().iter().map(|repo| {
+        format!(
+            "{} <code>{}</code>{}",
+            escape(&repo.project_name()),
+            escape(&repo.diff.repo_path.display().to_string()),
+            list(
+                repo.commits
+                    .iter()
+                    .map(|commit| match repo.commit_url(commit) {
+                        Some(url) => format!(
+                            r#"<a href="{}" rel="noreferrer">{}</a>"#,
+                            escape(&url),
+                            escape(&commit.summary)
+                        ),
+                        None => escape(&commit.summary),
+                    })
+                    .collect::<Vec<String>>()
+            )
+        )
+    }));
+    body.push_str(&section("Commits", &commits));
+
+    let browsing = list(context.context.safari_history().iter().map(|cluster| {
+        format!(
+            "{}{}",
+            escape(&cluster.label),
+            list(
+                cluster
+                    .urls
+                    .iter()
+                    .map(|url| {
+                        format!(
+                            r#"<a href="{}" rel="noreferrer">{}</a>"#,
+                            escape(&url.url),
+                            escape(url.title.as_deref().unwrap_or(&url.url))
+                        )
+                    })
+                    .collect::<Vec<String>>()
+            )
+        )
+    }));
+    body.push_str(&section("Browsing", &browsing));
+
+    Ok(page(&day_key(entry.date)?, "../", &body))
+}
+
+/// Render a month page: charts for the month plus one line per day.
+fn month_page(
+    month: &str,
+    days: &[(&ArchiveEntry, DayStats)],
+    offset: UtcOffset,
+) -> AppResult<String> {
+    let mut items = Vec::new();
+    for (entry, _) in days.iter().rev() {
+        let day = day_key(entry.date)?;
+        items.push(format!(
+            r#"<a href="{day}.html">{day}</a> {}"#,
+            escape(&excerpt(&entry.context))
+        ));
+    }
+    let body = format!("{}{}", charts(days, offset)?, section("Days", &list(items)));
+    Ok(page(month, "../", &body))
+}
+
+/// Render the site index: charts across every archived day plus the list of months.
+fn index_page(
+    months: &BTreeMap<String, Vec<(&ArchiveEntry, DayStats)>>,
+    offset: UtcOffset,
+) -> AppResult<String> {
+    let all_days: Vec<(&ArchiveEntry, DayStats)> = months.values().flatten().copied().collect();
+    let items = months.iter().rev().map(|(month, days)| {
+        format!(
+            r#"<a href="{month}/index.html">{month}</a> ({} day{})"#,
+            days.len(),
+            if days.len() == 1 { "" } else { "s" }
+        )
+    });
+    let body = format!(
+        "{}{}",
+        charts(&all_days, offset)?,
+        section("Months", &list(items))
+    );
+    Ok(page("Daily summaries", "", &body))
+}
+
+/// Render the search page and the script that searches the prebuilt index. Nothing is loaded
+/// from other sites, so the dashboard works offline.
+fn search_page() -> String {
+    let body = format!(
+        r#"<input id="query" type="search" placeholder="Search summaries, commits, and URLs" autofocus>
+<ol id="results"></ol>
+<script src="search-index.js"></script>
+<script>{SEARCH_JS}</script>"#
+    );
+    page("Search", "", &body)
+}
+
+/// Searchable text for one archived day.
+fn search_doc(entry: &ArchiveEntry) -> AppResult<SearchDoc> {
+    let context = &entry.context;
+    let mut body: Vec<&str> = Vec::new();
+    if let Some(summary) = &context.summary {
+        body.push(&summary.summary);
+        body.push(&summary.shell_overview);
+        for items in [
+            &summary.highlights,
+            &summary.releases,
+            &summary.time_breakdown,
+            &summary.tickets_touched,
+            &summary.pending_review,
+            &summary.communication,
+            &summary.repo_summaries,
+            &summary.common_groups,
+        ] {
+            body.extend(items.iter().map(String::as_str));
+        }
+    }
+    for repo in context.context.commit_history() {
+        body.extend(repo.commits.iter().map(|commit| commit.summary.as_str()));
+    }
+    for cluster in context.context.safari_history() {
+        body.push(&cluster.label);
+        body.extend(cluster.urls.iter().filter_map(|url| url.title.as_deref()));
+    }
+    Ok(SearchDoc {
+        reference: day_href(entry.date)?,
+        title: day_key(entry.date)?,
+        body: body.join("\n"),
+    })
+}
+
+/// `search-index.js`: the serialized lunr index plus titles and excerpts for result rendering.
+fn search_index_js(entries: &[ArchiveEntry]) -> AppResult<String> {
+    let docs = entries
+        .iter()
+        .map(search_doc)
+        .collect::<AppResult<Vec<_>>>()?;
+    let index = build_lunr_index(&docs);
+    let store: serde_json::Map<String, serde_json::Value> = docs
+        .iter()
+        .zip(entries)
+        .map(|(doc, entry)| {
+            (
+                doc.reference.clone(),
+                serde_json::json!({ "title": doc.title, "excerpt": excerpt(&entry.context) }),
+            )
+        })
+        .collect();
+    Ok(format!(
+        "window.DAILY_AI_SEARCH = {{\"index\": {}, \"docs\": {}}};\n",
+        serde_json::to_string(&index)?,
+        serde_json::to_string(&store)?
+    ))
+}
+
+/// Render `entries` into a static site rooted at `output`.
+#[tracing::instrument(name = "Rendering dashboard", level = "info", skip(entries))]
+pub async fn build_site(entries: &[ArchiveEntry], output: &Path) -> AppResult<()> {
+    fs::create_dir_all(output).await?;
+    let offset = time_utils::local_offset();
+
+    let mut months: BTreeMap<String, Vec<(&ArchiveEntry, DayStats)>> = BTreeMap::new();
+    for entry in entries {
+        months
+            .entry(month_key(entry.date))
+            .or_default()
+            .push((entry, DayStats::from_context(&entry.context)));
+    }
+
+    for (month, days) in &months {
+        let month_dir = output.join(month);
+        fs::create_dir_all(&month_dir).await?;
+        for (entry, stats) in days {
+            fs::write(output.join(day_href(entry.date)?), day_page(entry, *stats)?).await?;
+        }
+        fs::write(
+            month_dir.join("index.html"),
+            month_page(month, days, offset)?,
+        )
+        .await?;
+    }
+
+    fs::write(output.join("index.html"), index_page(&months, offset)?).await?;
+    fs::write(output.join("search.html"), search_page()).await?;
+    fs::write(output.join("search-index.js"), search_index_js(entries)?).await?;
+    fs::write(output.join("style.css"), STYLE_CSS).await?;
+    Ok(())
+}
+
+/// Load the archive and render it as a static site at `output`.
+#[tracing::instrument(name = "Generating dashboard", level = "info")]
+pub async fn generate_dashboard(output: &Path) -> AppResult<()> {
+    let entries = archive::load_all().await?;
+    if entries.is_empty() {
+        warn!("The archive is empty. Run `daily-ai summarize` to start filling it.");
+    }
+    build_site(&entries, output).await?;
+    info!(
+        "Wrote a dashboard covering {} day(s) to {}",
+        entries.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::macros::{date, datetime};
+    use time::{Duration, OffsetDateTime};
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+    use crate::context::Context;
+    use crate::shell::ShellHistoryEntry;
+
+    fn shell_entry(date_time: OffsetDateTime) -> ShellHistoryEntry {
+        ShellHistoryEntry {
+            date_time,
+            duration: Duration::seconds(1),
+            host: "localhost".into(),
+            directory: PathBuf::from("/tmp"),
+            command: "cargo test".into(),
+            exit_code: 0,
+            session_id: "abc".into(),
+        }
+    }
+
+    #[test]
+    fn active_minutes_count_distinct_slots() {
+        let context = FullContext::from(Context::default().with_shell_history(vec![
+            shell_entry(datetime!(2025-01-01 09:00 UTC)),
+            shell_entry(datetime!(2025-01-01 09:10 UTC)),
+            shell_entry(datetime!(2025-01-01 11:00 UTC)),
+        ]));
+
+        let stats = DayStats::from_context(&context);
+
+        assert_eq!(stats.active_minutes, 30);
+        assert_eq!(stats.deep_work_minutes, 0);
+        assert_eq!(stats.commits, 0);
+    }
+
+    #[test]
+    fn deep_work_counts_hour_long_stretches() {
+        let context = FullContext::from(
+            Context::default().with_shell_history(
+                [
+                    datetime!(2025-01-01 09:00 UTC),
+                    datetime!(2025-01-01 09:20 UTC),
+                    datetime!(2025-01-01 09:35 UTC),
+                    datetime!(2025-01-01 09:50 UTC),
+                    datetime!(2025-01-01 10:05 UTC),
+                    datetime!(2025-01-01 13:00 UTC),
+                    datetime!(2025-01-01 13:15 UTC),
+                ]
+                .map(shell_entry)
+                .to_vec(),
+            ),
+        );
+
+        let stats = DayStats::from_context(&context);
+
+        assert_eq!(stats.active_minutes, 105);
+        assert_eq!(stats.deep_work_minutes, 75);
+    }
+
+    #[tokio::test]
+    async fn builds_month_day_and_search_pages() {
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let output = std::env::temp_dir().join(format!("dashboard_{nonce}"));
+        let entries = vec![ArchiveEntry {
+            date: date!(2025 - 01 - 31),
+            context: FullContext {
+                summary: Some(WorkSummary {
+                    summary: "Shipped the <parser>. Then reviewed PRs.".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        }];
+
+        build_site(&entries, &output).await.unwrap();
+
+        let day = fs::read_to_string(output.join("2025-01/2025-01-31.html"))
+            .await
+            .unwrap();
+        assert!(day.contains("Shipped the &lt;parser&gt;."));
+        assert!(output.join("2025-01/index.html").exists());
+        let index = fs::read_to_string(output.join("search-index.js"))
+            .await
+            .unwrap();
+        assert!(index.contains("\"shipped\""));
+        let _ = fs::remove_dir_all(output).await;
+    }
+}
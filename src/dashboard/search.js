@@ -0,0 +1,65 @@
+(function () {
+  var data = window.DAILY_AI_SEARCH;
+  var index = data.index;
+  var input = document.getElementById("query");
+  var results = document.getElementById("results");
+
+  // The index's terms in order, and each page's BM25 score per term number, summed over fields.
+  var terms = index.invertedIndex.map(function (entry) { return entry[0]; });
+  var scores = {};
+  index.fieldVectors.forEach(function (vector) {
+    var ref = vector[0].slice(vector[0].indexOf("/") + 1);
+    var doc = scores[ref] || (scores[ref] = {});
+    for (var i = 0; i < vector[1].length; i += 2) {
+      doc[vector[1][i]] = (doc[vector[1][i]] || 0) + vector[1][i + 1];
+    }
+  });
+
+  // Split a query the way the index was built, leaving out lunr's query syntax.
+  function tokenize(query) {
+    return query
+      .toLowerCase()
+      .split(/[\s\-]+/)
+      .map(function (t) { return t.replace(/[:^~+*]/g, "").replace(/^[^\p{L}\p{N}_]+|[^\p{L}\p{N}_]+$/gu, ""); })
+      .filter(Boolean);
+  }
+
+  // Pages matching any word of the query, by prefix, best first.
+  function search(query) {
+    var matched = [];
+    tokenize(query).forEach(function (word) {
+      terms.forEach(function (term, i) {
+        if (term.lastIndexOf(word, 0) === 0) {
+          matched.push(i);
+        }
+      });
+    });
+    var hits = [];
+    Object.keys(scores).forEach(function (ref) {
+      var score = 0;
+      matched.forEach(function (i) { score += scores[ref][i] || 0; });
+      if (score > 0) {
+        hits.push({ ref: ref, score: score });
+      }
+    });
+    return hits.sort(function (a, b) { return b.score - a.score; });
+  }
+
+  input.addEventListener("input", function () {
+    results.innerHTML = "";
+    var query = input.value.trim();
+    if (!query) {
+      return;
+    }
+    search(query).forEach(function (hit) {
+      var doc = data.docs[hit.ref];
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = hit.ref;
+      link.textContent = doc.title;
+      item.appendChild(link);
+      item.appendChild(document.createTextNode(" " + doc.excerpt));
+      results.appendChild(item);
+    });
+  });
+})();
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{Map, Value, json};
+
+/// Lunr version whose serialized index format we emit.
+pub const LUNR_VERSION: &str = "2.3.9";
+
+/// BM25 term-frequency saturation, matching lunr's default.
+const K1: f64 = 1.2;
+/// BM25 field-length normalisation, matching lunr's default.
+const B: f64 = 0.75;
+
+/// Common English words left out of the index (a subset of lunr's stop-word list).
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "i",
+    "if", "in", "into", "is", "it", "its", "of", "on", "or", "so", "that", "the", "their", "then",
+    "there", "these", "this", "to", "was", "were", "will", "with",
+];
+
+/// A document to index: a page reference plus searchable fields.
+#[derive(Debug, Clone)]
+pub struct SearchDoc {
+    /// Reference returned by lunr on a hit; we use the page's relative URL.
+    pub reference: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// Split text into lunr-compatible terms: lowercase, split on whitespace and dashes, trim
+/// non-word characters from both ends, and drop stop words.
+///
+/// The index is emitted without lunr's stemmer in the search pipeline, so queries are matched
+/// against exactly these terms.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .map(|token| token.trim_matches(|c: char| !(c.is_alphanumeric() || c == '_')))
+        .filter(|token| !token.is_empty() && !STOP_WORDS.contains(token))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Build a serialized lunr index (the JSON accepted by `lunr.Index.load`) over `docs`. The
+/// dashboard's `search.js` scores queries against it directly, without loading lunr.
+///
+/// Scoring mirrors `lunr.Builder`: BM25 per field, rounded to three decimals.
+pub fn build_lunr_index(docs: &[SearchDoc]) -> Value {
+    const FIELDS: [&str; 2] = ["title", "body"];
+
+    // Term frequencies per (field, doc) and field lengths.
+    let mut field_terms: Vec<(&str, &str, HashMap<String, usize>, usize)> = Vec::new();
+    for doc in docs {
+        for (field, text) in FIELDS.iter().zip([&doc.title, &doc.body]) {
+            let terms = tokenize(text);
+            let mut freqs: HashMap<String, usize> = HashMap::new();
+            for term in &terms {
+                *freqs.entry(term.clone()).or_default() += 1;
+            }
+            field_terms.push((field, &doc.reference, freqs, terms.len()));
+        }
+    }
+
+    // Postings: term -> field -> refs, ordered by term as lunr requires when loading.
+    let mut postings: BTreeMap<String, BTreeMap<&str, Vec<&str>>> = BTreeMap::new();
+    for (field, reference, freqs, _) in &field_terms {
+        for term in freqs.keys() {
+            postings
+                .entry(term.clone())
+                .or_default()
+                .entry(field)
+                .or_default()
+                .push(reference);
+        }
+    }
+    let term_index: HashMap<&str, usize> = postings
+        .keys()
+        .enumerate()
+        .map(|(i, term)| (term.as_str(), i))
+        .collect();
+
+    let doc_count = docs.len().max(1) as f64;
+    let idf: HashMap<&str, f64> = postings
+        .iter()
+        .map(|(term, fields)| {
+            let with_term = fields.values().map(Vec::len).sum::<usize>() as f64;
+            let x = (doc_count - with_term + 0.5) / (with_term + 0.5);
+            (term.as_str(), (1.0 + x.abs()).ln())
+        })
+        .collect();
+    let average_length: HashMap<&str, f64> = FIELDS
+        .iter()
+        .map(|field| {
+            let total: usize = field_terms
+                .iter()
+                .filter(|(f, ..)| f == field)
+                .map(|(.., len)| len)
+                .sum();
+            (*field, (total as f64 / doc_count).max(1.0))
+        })
+        .collect();
+
+    let field_vectors: Vec<Value> = field_terms
+        .iter()
+        .map(|(field, reference, freqs, len)| {
+            let mut elements: Vec<(usize, f64)> = freqs
+                .iter()
+                .map(|(term, tf)| {
+                    let tf = *tf as f64;
+                    let norm = 1.0 - B + B * (*len as f64 / average_length[field]);
+                    let score = idf[term.as_str()] * ((K1 + 1.0) * tf) / (K1 * norm + tf);
+                    (term_index[term.as_str()], (score * 1000.0).round() / 1000.0)
+                })
+                .collect();
+            elements.sort_by_key(|(index, _)| *index);
+            let flat: Vec<Value> = elements
+                .into_iter()
+                .flat_map(|(index, score)| [json!(index), json!(score)])
+                .collect();
+            json!([format!("{field}/{reference}"), flat])
+        })
+        .collect();
+
+    let inverted_index: Vec<Value> = postings
+        .iter()
+        .map(|(term, fields)| {
+            let mut posting = Map::new();
+            posting.insert("_index".into(), json!(term_index[term.as_str()]));
+            for field in FIELDS {
+                let refs: Map<String, Value> = fields
+                    .get(field)
+                    .into_iter()
+                    .flatten()
+                    .map(|reference| (reference.to_string(), json!({})))
+                    .collect();
+                posting.insert(field.into(), Value::Object(refs));
+            }
+            json!([term, posting])
+        })
+        .collect();
+
+    json!({
+        "version": LUNR_VERSION,
+        "fields": FIELDS,
+        "fieldVectors": field_vectors,
+        "invertedIndex": inverted_index,
+        "pipeline": [],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_like_lunr_without_stemming() {
+        assert_eq!(
+            tokenize("Fixed the CLI-parser (again), see ENG-42."),
+            vec!["fixed", "cli", "parser", "again", "see", "eng", "42"]
+        );
+    }
+
+    #[test]
+    fn builds_sorted_inverted_index_with_field_vectors() {
+        let docs = vec![
+            SearchDoc {
+                reference: "2025-01/2025-01-01.html".into(),
+                title: "Parser work".into(),
+                body: "parser parser tests".into(),
+            },
+            SearchDoc {
+                reference: "2025-01/2025-01-02.html".into(),
+                title: "Release".into(),
+                body: "tests".into(),
+            },
+        ];
+
+        let index = build_lunr_index(&docs);
+
+        let terms: Vec<&str> = index["invertedIndex"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry[0].as_str().unwrap())
+            .collect();
+        assert_eq!(terms, vec!["parser", "release", "tests", "work"]);
+        let tests_posting = &index["invertedIndex"][2][1];
+        assert_eq!(tests_posting["_index"], 2);
+        assert_eq!(tests_posting["body"].as_object().unwrap().len(), 2);
+        assert_eq!(index["fieldVectors"].as_array().unwrap().len(), 4);
+        assert_eq!(index["fieldVectors"][1][0], "body/2025-01/2025-01-01.html");
+    }
+}
@@ -13,15 +13,21 @@ pub enum DirType {
     Data,
     Config,
     Cache,
+    /// Files describing the state of a run, such as its progress, for other programs to read.
+    State,
 }
 
 impl Display for DirType {
     /// Pretty-print the default directory path hint for this dir type.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if cfg!(windows) {
+            return write!(f, "%{}%\\{}", self.windows_key(), APP_NAME);
+        }
         match self {
             DirType::Data => write!(f, "~/.local/share/")?,
             DirType::Config => write!(f, "~/.config/")?,
             DirType::Cache => write!(f, "~/.cache/")?,
+            DirType::State => write!(f, "~/.local/state/")?,
         };
         write!(f, "{}", APP_NAME)
     }
@@ -34,6 +40,16 @@ impl DirType {
             DirType::Data => "XDG_DATA_HOME",
             DirType::Config => "XDG_CONFIG_HOME",
             DirType::Cache => "XDG_CACHE_HOME",
+            DirType::State => "XDG_STATE_HOME",
+        }
+    }
+
+    /// Windows known-folder environment variable for this directory type: the roaming
+    /// `%APPDATA%` for data and configuration, and `%LOCALAPPDATA%` for caches and run state.
+    fn windows_key(&self) -> &'static str {
+        match self {
+            DirType::Data | DirType::Config => "APPDATA",
+            DirType::Cache | DirType::State => "LOCALAPPDATA",
         }
     }
 
@@ -43,13 +59,19 @@ impl DirType {
             DirType::Data => ".local/share",
             DirType::Config => ".config",
             DirType::Cache => ".cache",
+            DirType::State => ".local/state",
         }
     }
 
-    /// Resolve the directory path from XDG or fallback environment hints.
+    /// Resolve the directory path from XDG, the Windows known folders, or fallback environment
+    /// hints.
     pub fn get_dir(&self) -> AppResult<PathBuf> {
         if let Some(dir) = env::var_os(self.xdg_key()) {
             Ok(PathBuf::from(dir).join(APP_NAME))
+        } else if cfg!(windows)
+            && let Some(dir) = env::var_os(self.windows_key())
+        {
+            Ok(PathBuf::from(dir).join(APP_NAME))
         } else if let Some(home_dir) = env::home_dir() {
             Ok(home_dir.join(self.rel_path()).join(APP_NAME))
         } else if let Ok(home) = env::var("HOME") {
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use time::{Date, Duration};
+use tracing::info;
+
+use crate::ai::draft_post::generate_draft_post;
+use crate::ai::token_budget::TokenBudget;
+use crate::archive::{self, ArchiveEntry};
+use crate::classify::UrlCluster;
+use crate::context::Context;
+use crate::error::AppError;
+use crate::git::GitRepoHistory;
+use crate::shell::ShellHistoryEntry;
+use crate::{AppResult, io_utils, time_utils};
+
+/// Words too common to say anything about a topic.
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "how", "what", "why", "from", "into", "about", "using", "use",
+    "via", "vs",
+];
+
+/// Lowercase words of `topic` worth matching on.
+fn keywords(topic: &str) -> Vec<String> {
+    topic
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() >= 2 && !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+fn mentions(text: &str, keywords: &[String]) -> bool {
+    let text = text.to_lowercase();
+    keywords
+        .iter()
+        .any(|keyword| text.contains(keyword.as_str()))
+}
+
+/// Add `repo` to `repos`, merging commits and diffs into an earlier day's copy of the same
+/// repository so the diff tools see one entry per path.
+fn merge_repo(repos: &mut Vec<GitRepoHistory>, repo: GitRepoHistory) {
+    let Some(existing) = repos
+        .iter_mut()
+        .find(|existing| existing.diff.repo_path == repo.diff.repo_path)
+    else {
+        repos.push(repo);
+        return;
+    };
+    let known: HashSet<String> = existing.commits.iter().map(|c| c.id.clone()).collect();
+    existing.commits.extend(
+        repo.commits
+            .into_iter()
+            .filter(|commit| commit.id.is_empty() || !known.contains(&commit.id)),
+    );
+    // Later days win for files changed on several days.
+    for (files, newer) in [
+        (&mut existing.diff.added, repo.diff.added),
+        (&mut existing.diff.modified, repo.diff.modified),
+    ] {
+        for file in newer {
+            files.retain(|old| old.path != file.path);
+            files.push(file);
+        }
+    }
+    existing.unpushed = repo.unpushed;
+}
+
+/// Select the commits, diffs, browsing, and shell commands of `entries` that mention `topic`.
+///
+/// A repository whose name matches keeps all its commits; otherwise only matching commits are
+/// kept, along with the repository's diffs for the model to pull snippets from. A browsing
+/// cluster whose label matches is kept whole.
+pub fn select(entries: &[ArchiveEntry], topic: &str) -> Context {
+    let keywords = keywords(topic);
+    let mut repos: Vec<GitRepoHistory> = Vec::new();
+    let mut clusters: Vec<UrlCluster> = Vec::new();
+    let mut shell: Vec<ShellHistoryEntry> = Vec::new();
+    for entry in entries {
+        let context = &entry.context.context;
+        for repo in context.commit_history() {
+            let mut repo = repo.clone();
+            let named = mentions(&repo.project_name(), &keywords)
+                || mentions(&repo.diff.repo_path.to_string_lossy(), &keywords);
+            if !named {
+                repo.commits.retain(|commit| {
+                    mentions(&commit.summary, &keywords)
+                        || commit
+                            .body
+                            .as_deref()
+                            .is_some_and(|body| mentions(body, &keywords))
+                });
+            }
+            if named || !repo.commits.is_empty() {
+                merge_repo(&mut repos, repo);
+            }
+        }
+        for cluster in context.safari_history() {
+            let urls = if mentions(&cluster.label, &keywords) {
+                cluster.urls.clone()
+            } else {
+                cluster
+                    .urls
+                    .iter()
+                    .filter(|item| {
+                        mentions(&item.url, &keywords)
+                            || item
+                                .title
+                                .as_deref()
+                                .is_some_and(|title| mentions(title, &keywords))
+                    })
+                    .cloned()
+                    .collect()
+            };
+            if urls.is_empty() {
+                continue;
+            }
+            match clusters.iter_mut().find(|c| c.label == cluster.label) {
+                Some(existing) => existing.urls.extend(urls),
+                None => clusters.push(UrlCluster {
+                    label: cluster.label.clone(),
+                    urls,
+                }),
+            }
+        }
+        shell.extend(
+            context
+                .shell_history()
+                .iter()
+                .filter(|entry| mentions(&entry.command, &keywords))
+                .cloned(),
+        );
+    }
+    Context::default()
+        .with_commit_history(repos)
+        .with_safari_history(clusters)
+        .with_shell_history(shell)
+}
+
+/// Draft a blog post about `topic` from the archived runs of `date`, or of the last `period`,
+/// and write it to `output` or show it.
+#[tracing::instrument(name = "Drafting a post", level = "info", skip(client))]
+pub async fn draft_post<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    topic: &str,
+    date: Option<Date>,
+    period: Duration,
+    offline: bool,
+    budget: TokenBudget,
+    output: Option<&Path>,
+) -> AppResult<()> {
+    let today = time_utils::now_local().date();
+    let since = today.saturating_sub(period);
+    let entries: Vec<ArchiveEntry> = archive::load_all()
+        .await?
+        .into_iter()
+        .filter(|entry| match date {
+            Some(date) => entry.date == date,
+            None => entry.date > since,
+        })
+        .collect();
+    let context = select(&entries, topic);
+    if context.is_empty() {
+        return Err(AppError::Config(format!(
+            "None of the {} archived run(s) in the period mention \"{topic}\"; try another \
+             --topic or a longer --period",
+            entries.len()
+        )));
+    }
+    info!(
+        "Drafting from {} repositories and {} browsing clusters",
+        context.commit_history().len(),
+        context.safari_history().len()
+    );
+    let draft = generate_draft_post(client, model, &context, topic, offline, budget).await?;
+    io_utils::output_markdown(output, &draft.to_markdown()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::context::FullContext;
+    use crate::git::CommitMeta;
+    use crate::git::diff::DiffSummary;
+    use crate::safari::SafariHistoryItem;
+
+    fn repo(path: &str, summaries: &[&str]) -> GitRepoHistory {
+        GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from(path),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: Vec::new(),
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits: summaries
+                .iter()
+                .map(|summary| CommitMeta {
+                    id: summary.to_string(),
+                    summary: summary.to_string(),
+                    body: None,
+                    timestamp: datetime!(2025-01-01 09:00 UTC),
+                    branches: Vec::new(),
+                })
+                .collect(),
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        }
+    }
+
+    fn day(date: Date, repos: Vec<GitRepoHistory>, clusters: Vec<UrlCluster>) -> ArchiveEntry {
+        ArchiveEntry {
+            date,
+            context: FullContext::from(
+                Context::default()
+                    .with_commit_history(repos)
+                    .with_safari_history(clusters),
+            ),
+        }
+    }
+
+    #[test]
+    fn selects_matching_work_across_days() {
+        let page = |url: &str, title: &str| SafariHistoryItem {
+            url: url.into(),
+            title: Some(title.into()),
+            visit_count: 1,
+            last_visited: datetime!(2025-01-01 09:00 UTC),
+        };
+        let entries = [
+            day(
+                date!(2025 - 01 - 30),
+                vec![repo(
+                    "/src/app",
+                    &["Switch parser to winnow", "Bump CI image"],
+                )],
+                vec![UrlCluster {
+                    label: "Rust docs".into(),
+                    urls: vec![
+                        page("https://docs.rs/winnow", "winnow - Rust"),
+                        page("https://docs.rs/serde", "serde - Rust"),
+                    ],
+                }],
+            ),
+            day(
+                date!(2025 - 01 - 31),
+                vec![
+                    repo("/src/app", &["Winnow: cut_err on commit", "Bump CI image"]),
+                    repo("/src/winnow-playground", &["Initial commit"]),
+                ],
+                Vec::new(),
+            ),
+        ];
+
+        let context = select(&entries, "Parsing with Winnow");
+
+        let repos = context.commit_history();
+        assert_eq!(repos.len(), 2);
+        let summaries: Vec<&str> = repos[0]
+            .commits
+            .iter()
+            .map(|c| c.summary.as_str())
+            .collect();
+        assert_eq!(
+            summaries,
+            ["Switch parser to winnow", "Winnow: cut_err on commit"]
+        );
+        assert_eq!(repos[1].commits.len(), 1);
+        assert_eq!(context.safari_history()[0].urls.len(), 1);
+        assert!(select(&entries, "kubernetes").is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+use std::env;
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use time::OffsetDateTime;
+use time::macros::format_description;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto};
+use tracing::{debug, trace};
+
+use super::EmailEnvelope;
+use crate::error::AppError;
+use crate::warnings::{self, WarningKind};
+use crate::{AppResult, allowlist};
+
+/// Headers requested for each message. `BODY.PEEK` leaves the `\Seen` flag untouched.
+const FETCH_ITEMS: &str = "(INTERNALDATE BODY.PEEK[HEADER.FIELDS (FROM SUBJECT)])";
+
+/// Connection settings, read from `IMAP_HOST`, `IMAP_PORT`, `IMAP_USER`, `IMAP_PASSWORD`, and
+/// `IMAP_SENT_MAILBOX`.
+#[derive(Debug, Clone)]
+pub struct ImapConfig {
+    host: String,
+    port: u16,
+    user: String,
+    password: String,
+    sent_mailbox: String,
+}
+
+impl ImapConfig {
+    /// Read the configuration from the environment. Returns `None` unless `IMAP_HOST` is set.
+    pub fn from_env() -> Option<Self> {
+        let host = env::var("IMAP_HOST").ok().filter(|h| !h.is_empty())?;
+        Some(ImapConfig {
+            host,
+            port: env::var("IMAP_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(993),
+            user: env::var("IMAP_USER").unwrap_or_default(),
+            password: env::var("IMAP_PASSWORD").unwrap_or_default(),
+            sent_mailbox: env::var("IMAP_SENT_MAILBOX").unwrap_or_else(|_| "Sent".into()),
+        })
+    }
+}
+
+/// One untagged `FETCH` response: the response text and the header literal it carried.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct FetchResponse {
+    text: String,
+    headers: Vec<u8>,
+}
+
+/// A minimal IMAP4rev1 session over implicit TLS. Only the commands needed to read headers
+/// from mailboxes opened with `EXAMINE` (read-only) are implemented.
+struct ImapSession {
+    stream: BufReader<TlsStream<TcpStream>>,
+    next_tag: u32,
+}
+
+impl ImapSession {
+    async fn connect(config: &ImapConfig) -> AppResult<Self> {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls = ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| AppError::Imap(e.to_string()))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let server_name = ServerName::try_from(config.host.clone())
+            .map_err(|e| AppError::Imap(format!("invalid host {}: {e}", config.host)))?;
+        allowlist::allow(&config.host);
+        let tcp = allowlist::connect(&config.host, config.port).await?;
+        let stream = TlsConnector::from(Arc::new(tls))
+            .connect(server_name, tcp)
+            .await?;
+        let mut session = ImapSession {
+            stream: BufReader::new(stream),
+            next_tag: 1,
+        };
+        let greeting = session.read_line().await?;
+        if !greeting.starts_with("* OK") {
+            return Err(AppError::Imap(format!("unexpected greeting: {greeting}")));
+        }
+        Ok(session)
+    }
+
+    async fn read_line(&mut self) -> AppResult<String> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(AppError::Imap("server closed the connection".into()));
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Send a command and collect its untagged responses, including any literals they carry.
+    async fn command(&mut self, command: &str) -> AppResult<Vec<FetchResponse>> {
+        let tag = format!("A{}", self.next_tag);
+        self.next_tag += 1;
+        self.stream
+            .get_mut()
+            .write_all(format!("{tag} {command}\r\n").as_bytes())
+            .await?;
+        self.stream.get_mut().flush().await?;
+
+        let mut responses: Vec<FetchResponse> = Vec::new();
+        loop {
+            let line = self.read_line().await?;
+            if let Some(status) = line.strip_prefix(&format!("{tag} ")) {
+                if status.starts_with("OK") {
+                    return Ok(responses);
+                }
+                return Err(AppError::Imap(status.to_string()));
+            }
+            if line.starts_with("* ") {
+                responses.push(FetchResponse::default());
+            }
+            let Some(response) = responses.last_mut() else {
+                continue;
+            };
+            response.text.push_str(&line);
+            if let Some(size) = literal_size(&line) {
+                let mut literal = vec![0; size];
+                self.stream.read_exact(&mut literal).await?;
+                response.headers.extend(literal);
+            }
+        }
+    }
+
+    async fn login(&mut self, config: &ImapConfig) -> AppResult<()> {
+        self.command(&format!(
+            "LOGIN {} {}",
+            quote(&config.user),
+            quote(&config.password)
+        ))
+        .await
+        .map(|_| ())
+    }
+
+    /// Read envelopes from `mailbox` for messages since `since`.
+    async fn envelopes(
+        &mut self,
+        mailbox: &str,
+        since: OffsetDateTime,
+        sent: bool,
+    ) -> AppResult<Vec<EmailEnvelope>> {
+        self.command(&format!("EXAMINE {}", quote(mailbox))).await?;
+        let date = since
+            .date()
+            .format(format_description!("[day]-[month repr:short]-[year]"))?;
+        let uids: Vec<String> = self
+            .command(&format!("UID SEARCH SINCE {date}"))
+            .await?
+            .iter()
+            .filter_map(|r| r.text.strip_prefix("* SEARCH"))
+            .flat_map(|ids| ids.split_whitespace().map(str::to_string))
+            .collect();
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        trace!("Fetching {} headers from {mailbox}", uids.len());
+        let responses = self
+            .command(&format!("UID FETCH {} {FETCH_ITEMS}", uids.join(",")))
+            .await?;
+        Ok(responses
+            .iter()
+            .filter_map(|r| parse_envelope(r, sent))
+            // SEARCH SINCE only has day granularity.
+            .filter(|e| e.date > since)
+            .collect())
+    }
+}
+
+/// Quote a string for use as an IMAP astring.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Size of the literal announced at the end of a response line (`... {123}`).
+fn literal_size(line: &str) -> Option<usize> {
+    line.strip_suffix('}')?.rsplit_once('{')?.1.parse().ok()
+}
+
+/// Decode RFC 2047 encoded words (`=?UTF-8?B?...?=` and `=?UTF-8?Q?...?=`) in a header value.
+/// Words in other charsets are decoded as UTF-8 on a best-effort basis.
+fn decode_header(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let word = &rest[start + 2..];
+        let decoded = word.split_once('?').and_then(|(_, word)| {
+            let (encoding, word) = word.split_once('?')?;
+            let (text, tail) = word.split_once("?=")?;
+            let bytes = match encoding.to_ascii_uppercase().as_str() {
+                "B" => STANDARD.decode(text).ok()?,
+                "Q" => decode_q(text),
+                _ => return None,
+            };
+            Some((String::from_utf8_lossy(&bytes).into_owned(), tail))
+        });
+        match decoded {
+            Some((text, tail)) => {
+                let before = &rest[..start];
+                // Whitespace between adjacent encoded words is not displayed.
+                if out.is_empty() || !before.trim().is_empty() {
+                    out.push_str(before);
+                }
+                out.push_str(&text);
+                rest = tail;
+            }
+            None => {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decode the "Q" encoding: `_` is a space and `=XX` a hex byte.
+fn decode_q(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => out.push(b' '),
+            b'=' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'='),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Extract the address from a `From:` value such as `Alice <alice@example.com>`.
+fn sender_address(from: &str) -> String {
+    match from.rsplit_once('<') {
+        Some((_, address)) => address.trim_end_matches('>').trim().to_string(),
+        None => from.trim().to_string(),
+    }
+}
+
+/// Build an envelope from a `FETCH` response carrying `INTERNALDATE` and header fields.
+fn parse_envelope(response: &FetchResponse, sent: bool) -> Option<EmailEnvelope> {
+    let date = response
+        .text
+        .split_once("INTERNALDATE \"")?
+        .1
+        .split_once('"')?
+        .0;
+    let date = OffsetDateTime::parse(
+        date.trim_start(),
+        format_description!(
+            "[day padding:none]-[month repr:short]-[year] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+        ),
+    )
+    .ok()?;
+
+    // Unfold continuation lines before splitting into fields.
+    let headers = String::from_utf8_lossy(&response.headers)
+        .replace("\r\n ", " ")
+        .replace("\r\n\t", " ");
+    let field = |name: &str| {
+        headers.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name)
+                .then(|| decode_header(value.trim()))
+        })
+    };
+    Some(EmailEnvelope {
+        subject: field("Subject").unwrap_or_default(),
+        sender: field("From")
+            .map(|from| sender_address(&from))
+            .unwrap_or_else(|| "unknown".into()),
+        sent,
+        date,
+    })
+}
+
+/// Read envelopes from the inbox and the sent mailbox over IMAP.
+///
+/// Mailboxes are opened with `EXAMINE` and headers fetched with `BODY.PEEK`, so nothing is
+/// marked as read. Only the `From` and `Subject` headers are downloaded.
+pub async fn fetch_envelopes(
+    config: &ImapConfig,
+    since: OffsetDateTime,
+) -> AppResult<Vec<EmailEnvelope>> {
+    let mut session = ImapSession::connect(config).await?;
+    session.login(config).await?;
+    let mut envelopes = session.envelopes("INBOX", since, false).await?;
+    match session.envelopes(&config.sent_mailbox, since, true).await {
+        Ok(sent) => envelopes.extend(sent),
+        Err(e) => warnings::record(
+            WarningKind::Skipped,
+            "email",
+            format!(
+                "Unable to read the {} mailbox; set IMAP_SENT_MAILBOX to count sent mail: {e}",
+                config.sent_mailbox
+            ),
+        ),
+    }
+    if let Err(e) = session.command("LOGOUT").await {
+        debug!("IMAP logout failed: {e}");
+    }
+    Ok(envelopes)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn parses_fetch_responses_and_encoded_subjects() {
+        let response = FetchResponse {
+            text: "* 12 FETCH (UID 40 INTERNALDATE \" 2-Jan-2025 09:15:00 +0100\" BODY[HEADER.FIELDS (FROM SUBJECT)] {95})".into(),
+            headers: b"From: =?UTF-8?Q?Zo=C3=AB?= <zoe@example.com>\r\nSubject: Re: =?utf-8?B?RGVwbG95?=\r\n =?utf-8?B?IHBsYW4=?=\r\n\r\n".to_vec(),
+        };
+
+        assert_eq!(literal_size(&response.text), None);
+        assert_eq!(literal_size("* 12 FETCH (BODY[HEADER] {95}"), Some(95));
+        assert_eq!(
+            parse_envelope(&response, false),
+            Some(EmailEnvelope {
+                subject: "Re: Deploy plan".into(),
+                sender: "zoe@example.com".into(),
+                sent: false,
+                date: datetime!(2025-01-02 08:15 UTC),
+            })
+        );
+        assert_eq!(decode_header("=?UTF-8?Q?Zo=C3=AB?= <z@x>"), "Zoë <z@x>");
+        assert_eq!(quote("pa\"ss"), "\"pa\\\"ss\"");
+    }
+}
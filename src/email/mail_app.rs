@@ -0,0 +1,150 @@
+use std::env;
+use std::path::PathBuf;
+
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+use time::OffsetDateTime;
+use tracing::{debug, trace};
+
+use super::EmailEnvelope;
+use crate::AppResult;
+
+/// Subjects, senders, and dates of messages received since `?` (Unix seconds). The mailbox URL
+/// tells sent mail apart from received mail; message bodies live outside this database.
+const ENVELOPES_QUERY: &str = "
+SELECT
+    COALESCE(s.subject, '') AS subject,
+    COALESCE(a.address, 'unknown') AS sender,
+    COALESCE(mb.url LIKE '%Sent%', 0) AS sent,
+    m.date_received AS date
+FROM messages m
+LEFT JOIN subjects s ON s.ROWID = m.subject
+LEFT JOIN addresses a ON a.ROWID = m.sender
+LEFT JOIN mailboxes mb ON mb.ROWID = m.mailbox
+WHERE m.date_received > ? AND COALESCE(m.deleted, 0) = 0
+ORDER BY m.date_received ASC";
+
+/// Raw row returned by `ENVELOPES_QUERY`.
+#[derive(Debug, FromQueryResult)]
+struct EnvelopeRow {
+    subject: String,
+    sender: String,
+    sent: bool,
+    date: i64,
+}
+
+/// Resolve the Envelope Index path, honouring `MAIL_DB_PATH`.
+///
+/// Mail.app keeps its data under a versioned directory (`~/Library/Mail/V10/...`); the newest
+/// version present is used.
+fn envelope_index_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("MAIL_DB_PATH") {
+        return Some(PathBuf::from(path));
+    }
+    let mail_dir = env::home_dir()
+        .or_else(|| env::var("HOME").ok().map(PathBuf::from))?
+        .join("Library/Mail");
+    std::fs::read_dir(mail_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let version = name.to_str()?.strip_prefix('V')?.parse::<u32>().ok()?;
+            Some((version, entry.path()))
+        })
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, dir)| dir.join("MailData/Envelope Index"))
+}
+
+/// Read envelopes for messages since `since` from Mail.app.
+///
+/// Reading the Envelope Index requires Full Disk Access. Returns nothing when Mail.app has not
+/// been set up.
+pub async fn fetch_envelopes(since: OffsetDateTime) -> AppResult<Vec<EmailEnvelope>> {
+    let Some(path) = envelope_index_path().filter(|p| p.exists()) else {
+        debug!("Mail.app Envelope Index not found");
+        return Ok(Vec::new());
+    };
+    let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=ro", path.display()));
+    opt.sqlx_logging(false);
+    trace!("Connecting to Mail.app Envelope Index");
+    let db = Database::connect(opt).await?;
+    query_envelopes(&db, since).await
+}
+
+/// Run `ENVELOPES_QUERY` against an open Envelope Index.
+async fn query_envelopes(
+    db: &DatabaseConnection,
+    since: OffsetDateTime,
+) -> AppResult<Vec<EmailEnvelope>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        ENVELOPES_QUERY,
+        [since.unix_timestamp().into()],
+    );
+    Ok(EnvelopeRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            Some(EmailEnvelope {
+                subject: row.subject,
+                sender: row.sender,
+                sent: row.sent,
+                date: OffsetDateTime::from_unix_timestamp(row.date).ok()?,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_envelopes_and_marks_sent_mail() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            "CREATE TABLE messages (ROWID INTEGER PRIMARY KEY, sender INTEGER, subject INTEGER, date_received INTEGER, mailbox INTEGER, deleted INTEGER)",
+            "CREATE TABLE subjects (ROWID INTEGER PRIMARY KEY, subject TEXT)",
+            "CREATE TABLE addresses (ROWID INTEGER PRIMARY KEY, address TEXT, comment TEXT)",
+            "CREATE TABLE mailboxes (ROWID INTEGER PRIMARY KEY, url TEXT)",
+            "INSERT INTO subjects VALUES (1, 'Release plan'), (2, 'Old news')",
+            "INSERT INTO addresses VALUES (1, 'alice@example.com', 'Alice'), (2, 'me@example.com', 'Me')",
+            "INSERT INTO mailboxes VALUES (1, 'imap://me@example.com/INBOX'), (2, 'imap://me@example.com/Sent%20Messages')",
+            "INSERT INTO messages VALUES
+                (1, 1, 1, 1735722000, 1, 0),
+                (2, 2, 1, 1735725600, 2, 0),
+                (3, 1, 2, 1600000000, 1, 0),
+                (4, 1, 1, 1735729200, 1, 1)",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+
+        let envelopes = query_envelopes(&db, datetime!(2025-01-01 00:00 UTC))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            envelopes,
+            vec![
+                EmailEnvelope {
+                    subject: "Release plan".into(),
+                    sender: "alice@example.com".into(),
+                    sent: false,
+                    date: datetime!(2025-01-01 09:00 UTC),
+                },
+                EmailEnvelope {
+                    subject: "Release plan".into(),
+                    sender: "me@example.com".into(),
+                    sent: true,
+                    date: datetime!(2025-01-01 10:00 UTC),
+                },
+            ]
+        );
+    }
+}
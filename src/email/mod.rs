@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::debug;
+
+use crate::AppResult;
+use crate::time_utils::past_ts;
+
+/// Read-only IMAP access (`EXAMINE` + `UID FETCH` of headers only).
+pub(crate) mod imap;
+
+/// Mail.app's SQLite "Envelope Index".
+pub(crate) mod mail_app;
+
+/// Sending summaries over SMTP, for `--email-to`.
+pub(crate) mod smtp;
+
+/// Reply and forward prefixes stripped when grouping messages into threads.
+const SUBJECT_PREFIXES: [&str; 6] = ["re:", "fwd:", "fw:", "aw:", "wg:", "sv:"];
+
+/// One message's envelope, as read from a mail source. Bodies are never read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EmailEnvelope {
+    pub subject: String,
+    pub sender: String,
+    pub sent: bool,
+    pub date: OffsetDateTime,
+}
+
+/// Message counts for one email thread (messages sharing a normalised subject).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmailThread {
+    /// Where the messages were read from: `Mail.app` or `IMAP`.
+    pub source: String,
+    /// Subject line without `Re:`/`Fwd:` prefixes.
+    pub subject: String,
+    /// Number of received messages per sender address.
+    pub senders: BTreeMap<String, usize>,
+    pub sent: usize,
+    pub received: usize,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_message: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_message: OffsetDateTime,
+}
+
+impl EmailThread {
+    /// One-line description used in the "communication" section of the summary.
+    pub fn describe(&self) -> String {
+        let mut counts = Vec::new();
+        if self.sent > 0 {
+            counts.push(format!("{} sent", self.sent));
+        }
+        if self.received > 0 {
+            counts.push(format!("{} received", self.received));
+        }
+        if self.senders.is_empty() {
+            format!("Email \"{}\": {}", self.subject, counts.join(", "))
+        } else {
+            let senders = self.senders.keys().cloned().collect::<Vec<_>>().join(", ");
+            format!(
+                "Email \"{}\": {} (from {senders})",
+                self.subject,
+                counts.join(", ")
+            )
+        }
+    }
+}
+
+/// Strip reply/forward prefixes and surrounding whitespace from a subject line.
+fn normalize_subject(subject: &str) -> String {
+    let mut subject = subject.trim();
+    loop {
+        let lower = subject.to_lowercase();
+        let Some(prefix) = SUBJECT_PREFIXES.iter().find(|p| lower.starts_with(*p)) else {
+            break;
+        };
+        subject = subject[prefix.len()..].trim_start();
+    }
+    if subject.is_empty() {
+        "(no subject)".into()
+    } else {
+        subject.to_string()
+    }
+}
+
+/// Group envelopes into threads by normalised subject, ordered by last activity.
+fn aggregate(source: &str, envelopes: Vec<EmailEnvelope>) -> Vec<EmailThread> {
+    let mut threads: BTreeMap<String, EmailThread> = BTreeMap::new();
+    for envelope in envelopes {
+        let subject = normalize_subject(&envelope.subject);
+        let thread = threads
+            .entry(subject.to_lowercase())
+            .or_insert_with(|| EmailThread {
+                source: source.to_string(),
+                subject,
+                senders: BTreeMap::new(),
+                sent: 0,
+                received: 0,
+                first_message: envelope.date,
+                last_message: envelope.date,
+            });
+        if envelope.sent {
+            thread.sent += 1;
+        } else {
+            thread.received += 1;
+            *thread
+                .senders
+                .entry(envelope.sender.to_lowercase())
+                .or_default() += 1;
+        }
+        thread.first_message = thread.first_message.min(envelope.date);
+        thread.last_message = thread.last_message.max(envelope.date);
+    }
+    let mut threads: Vec<EmailThread> = threads.into_values().collect();
+    threads.sort_by_key(|t| t.last_message);
+    threads
+}
+
+/// Collect per-thread email counts for the window.
+///
+/// Reads from IMAP when `IMAP_HOST` is set, otherwise from Mail.app's Envelope Index. Only
+/// subjects, sender addresses, and dates are read; message bodies never are.
+#[tracing::instrument(name = "Collecting email activity", level = "info")]
+pub async fn get_email_activity(duration: &Duration) -> AppResult<Vec<EmailThread>> {
+    let since = past_ts(duration);
+    let (source, envelopes) = match imap::ImapConfig::from_env() {
+        Some(config) => ("IMAP", imap::fetch_envelopes(&config, since).await?),
+        None => ("Mail.app", mail_app::fetch_envelopes(since).await?),
+    };
+    let threads = aggregate(source, envelopes);
+    debug!("Found {} active email threads via {source}", threads.len());
+    Ok(threads)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn envelope(subject: &str, sender: &str, sent: bool, date: OffsetDateTime) -> EmailEnvelope {
+        EmailEnvelope {
+            subject: subject.into(),
+            sender: sender.into(),
+            sent,
+            date,
+        }
+    }
+
+    #[test]
+    fn groups_replies_and_forwards_into_one_thread() {
+        assert_eq!(normalize_subject("RE: Fwd:  Release plan"), "Release plan");
+        assert_eq!(normalize_subject("  "), "(no subject)");
+
+        let threads = aggregate(
+            "Mail.app",
+            vec![
+                envelope(
+                    "Release plan",
+                    "Alice@example.com",
+                    false,
+                    datetime!(2025-01-01 09:00 UTC),
+                ),
+                envelope(
+                    "Re: Release plan",
+                    "me@example.com",
+                    true,
+                    datetime!(2025-01-01 10:00 UTC),
+                ),
+                envelope(
+                    "RE: release plan",
+                    "bob@example.com",
+                    false,
+                    datetime!(2025-01-01 11:00 UTC),
+                ),
+                envelope(
+                    "Lunch?",
+                    "carol@example.com",
+                    false,
+                    datetime!(2025-01-01 08:00 UTC),
+                ),
+            ],
+        );
+
+        assert_eq!(threads.len(), 2);
+        let release = &threads[1];
+        assert_eq!(release.subject, "Release plan");
+        assert_eq!((release.sent, release.received), (1, 2));
+        assert_eq!(
+            release.senders.keys().collect::<Vec<_>>(),
+            ["alice@example.com", "bob@example.com"]
+        );
+        assert_eq!(
+            release.last_message - release.first_message,
+            Duration::hours(2)
+        );
+        assert_eq!(
+            release.describe(),
+            "Email \"Release plan\": 1 sent, 2 received (from alice@example.com, bob@example.com)"
+        );
+    }
+}
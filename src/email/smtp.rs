@@ -0,0 +1,500 @@
+use std::env;
+use std::sync::{Arc, OnceLock};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc2822;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto};
+use tracing::{debug, trace};
+use ulid::Ulid;
+
+use crate::context::FullContext;
+use crate::error::AppError;
+use crate::export::Exporter;
+use crate::render::{html, markdown};
+use crate::{AppResult, allowlist, time_utils};
+
+/// Port on which servers expect TLS from the first byte. Any other port starts in plain text
+/// and is upgraded with `STARTTLS`.
+const IMPLICIT_TLS_PORT: u16 = 465;
+
+/// Base64 body lines are wrapped at this many characters (RFC 2045).
+const BASE64_LINE: usize = 76;
+
+/// The `[smtp]` table of the configuration file, set once at startup.
+static SETTINGS: OnceLock<SmtpSettings> = OnceLock::new();
+
+/// The SMTP server from the `[smtp]` table of the configuration file. The password is only read
+/// from `SMTP_PASSWORD`, so it stays out of the file.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpSettings {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub from: Option<String>,
+}
+
+/// Use `settings` for every email from now on. Only the first call has an effect.
+pub fn configure(settings: SmtpSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// Connection settings, from the `[smtp]` table of the configuration file and `SMTP_HOST`,
+/// `SMTP_PORT`, `SMTP_USER`, `SMTP_PASSWORD`, and `SMTP_FROM`.
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    host: String,
+    port: u16,
+    user: String,
+    password: String,
+    from: String,
+}
+
+impl SmtpConfig {
+    /// Read the configuration from the configured [`SmtpSettings`], with the environment
+    /// variables taking precedence. Returns `None` unless a host is set.
+    pub fn load() -> Option<Self> {
+        Self::resolve(SETTINGS.get().cloned().unwrap_or_default(), |name| {
+            env::var(name).ok()
+        })
+    }
+
+    /// `settings` with each value replaced by the variable `var` returns for it, if any.
+    ///
+    /// The port defaults to 587 (`STARTTLS`) and the sender to the user.
+    fn resolve(settings: SmtpSettings, var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        let host = var("SMTP_HOST").or(settings.host)?;
+        let user = var("SMTP_USER").or(settings.user).unwrap_or_default();
+        Some(SmtpConfig {
+            host,
+            port: var("SMTP_PORT")
+                .and_then(|p| p.parse().ok())
+                .or(settings.port)
+                .unwrap_or(587),
+            password: var("SMTP_PASSWORD").unwrap_or_default(),
+            from: var("SMTP_FROM")
+                .or(settings.from)
+                .unwrap_or_else(|| user.clone()),
+            user,
+        })
+    }
+}
+
+/// A minimal ESMTP session: `EHLO`, `AUTH PLAIN`, and one message per `MAIL FROM`.
+struct SmtpSession<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SmtpSession<S> {
+    /// Read one reply, joining the lines of a multiline reply, and return its code and text.
+    async fn reply(&mut self) -> AppResult<(u16, String)> {
+        let mut text = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(AppError::Smtp("server closed the connection".into()));
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse().ok())
+                .ok_or_else(|| AppError::Smtp(format!("malformed reply: {line}")))?;
+            text.push(line.get(4..).unwrap_or_default().to_string());
+            // `250-` continues a multiline reply, `250 ` ends it.
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok((code, text.join("\n")));
+            }
+        }
+    }
+
+    /// Read a reply and fail unless its code is `expected`.
+    async fn expect(&mut self, expected: u16) -> AppResult<String> {
+        let (code, text) = self.reply().await?;
+        if code != expected {
+            return Err(AppError::Smtp(format!("{code} {text}")));
+        }
+        Ok(text)
+    }
+
+    /// Send a command line and read its reply, which must have code `expected`.
+    async fn command(&mut self, command: &str, expected: u16) -> AppResult<String> {
+        trace!(
+            "SMTP > {}",
+            if command.starts_with("AUTH") {
+                "AUTH ..."
+            } else {
+                command
+            }
+        );
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{command}\r\n").as_bytes())
+            .await?;
+        stream.flush().await?;
+        self.expect(expected).await
+    }
+
+    /// Authenticate with `AUTH PLAIN` when a user is configured.
+    async fn login(&mut self, config: &SmtpConfig) -> AppResult<()> {
+        if config.user.is_empty() {
+            return Ok(());
+        }
+        let credentials = STANDARD.encode(format!("\0{}\0{}", config.user, config.password));
+        self.command(&format!("AUTH PLAIN {credentials}"), 235)
+            .await
+            .map(|_| ())
+    }
+
+    /// Send `message` from `from` to every address in `to`.
+    async fn send(&mut self, from: &str, to: &[String], message: &str) -> AppResult<()> {
+        self.command(&format!("MAIL FROM:<{}>", address(from)), 250)
+            .await?;
+        for recipient in to {
+            self.command(&format!("RCPT TO:<{}>", address(recipient)), 250)
+                .await?;
+        }
+        self.command("DATA", 354).await?;
+        let stream = self.stream.get_mut();
+        stream.write_all(dot_stuff(message).as_bytes()).await?;
+        stream.write_all(b".\r\n").await?;
+        stream.flush().await?;
+        self.expect(250).await.map(|_| ())
+    }
+}
+
+/// Extract the address from a mailbox such as `Daily AI <me@example.com>`.
+fn address(mailbox: &str) -> &str {
+    match mailbox.rsplit_once('<') {
+        Some((_, address)) => address.trim_end_matches('>').trim(),
+        None => mailbox.trim(),
+    }
+}
+
+/// Check a recipient given to `--email-to`: `team@example.com` or `Team <team@example.com>`.
+///
+/// Line breaks are rejected, since they would let the value add commands to the SMTP session or
+/// headers to the message.
+pub fn parse_mailbox(value: &str) -> Result<String, String> {
+    if value.chars().any(char::is_control) {
+        return Err("must be a single line".to_string());
+    }
+    let mailbox = value.trim();
+    let address = match mailbox.split_once('<') {
+        Some((name, rest)) if !name.contains('>') => rest
+            .strip_suffix('>')
+            .ok_or_else(|| format!("expected `Name <address>`, got {mailbox}"))?,
+        Some(_) => return Err(format!("expected `Name <address>`, got {mailbox}")),
+        None => mailbox,
+    };
+    let valid = address.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && !local.contains(|c: char| c.is_whitespace() || "<>()[],;:\\\"@".contains(c))
+            && !domain.is_empty()
+            && !domain.starts_with(['.', '-'])
+            && !domain.ends_with(['.', '-'])
+            && domain
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+    });
+    if !valid {
+        return Err(format!("{address} is not an email address"));
+    }
+    Ok(mailbox.to_string())
+}
+
+/// Normalise line endings to CRLF and double leading dots so no line ends `DATA` early.
+fn dot_stuff(message: &str) -> String {
+    let mut out = String::with_capacity(message.len() + 64);
+    for line in message.lines() {
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(line);
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Encode a header value as an RFC 2047 encoded word when it is not plain ASCII.
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// Base64-encode a body part, wrapped to `BASE64_LINE` characters.
+fn base64_body(body: &str) -> String {
+    let encoded = STANDARD.encode(body);
+    encoded
+        .as_bytes()
+        .chunks(BASE64_LINE)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// An outgoing summary email, sent as `multipart/alternative` with the Markdown as the plain
+/// text part and its HTML rendering.
+#[derive(Debug, Clone)]
+struct Email<'a> {
+    from: &'a str,
+    to: &'a [String],
+    subject: &'a str,
+    message_id: &'a str,
+    /// Message this one replaces, threaded with `In-Reply-To` and `References`.
+    replaces: Option<&'a str>,
+    markdown: &'a str,
+    date: OffsetDateTime,
+}
+
+impl Email<'_> {
+    /// The message in RFC 5322 form.
+    fn to_message(&self) -> AppResult<String> {
+        let boundary = format!("daily-ai-{}", Ulid::new());
+        let mut headers = vec![
+            format!("From: {}", self.from),
+            format!("To: {}", self.to.join(", ")),
+            format!("Subject: {}", encode_header(self.subject)),
+            format!("Date: {}", self.date.format(&Rfc2822)?),
+            format!("Message-ID: {}", self.message_id),
+        ];
+        if let Some(replaces) = self.replaces {
+            headers.push(format!("In-Reply-To: {replaces}"));
+            headers.push(format!("References: {replaces}"));
+        }
+        headers.push("MIME-Version: 1.0".into());
+        headers.push(format!(
+            "Content-Type: multipart/alternative; boundary=\"{boundary}\""
+        ));
+        let html = html::render_markdown(self.subject, self.markdown);
+        let part = |content_type: &str, body: &str| {
+            format!(
+                "--{boundary}\r\nContent-Type: {content_type}; charset=utf-8\r\n\
+                 Content-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+                base64_body(body)
+            )
+        };
+        Ok(format!(
+            "{}\r\n\r\n{}{}--{boundary}--\r\n",
+            headers.join("\r\n"),
+            part("text/plain", self.markdown),
+            part("text/html", &html),
+        ))
+    }
+}
+
+/// Open a session with `config.host`, upgrading to TLS with `STARTTLS` unless the port uses
+/// implicit TLS, and authenticate.
+async fn connect(
+    config: &SmtpConfig,
+) -> AppResult<SmtpSession<tokio_rustls::client::TlsStream<TcpStream>>> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let tls = ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| AppError::Smtp(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(config.host.clone())
+        .map_err(|e| AppError::Smtp(format!("invalid host {}: {e}", config.host)))?;
+    allowlist::allow(&config.host);
+    let mut tcp = allowlist::connect(&config.host, config.port).await?;
+    if config.port != IMPLICIT_TLS_PORT {
+        let mut plain = SmtpSession {
+            stream: BufReader::new(tcp),
+        };
+        plain.expect(220).await?;
+        plain.command("EHLO daily-ai", 250).await?;
+        plain.command("STARTTLS", 220).await?;
+        tcp = plain.stream.into_inner();
+    }
+    let stream = TlsConnector::from(Arc::new(tls))
+        .connect(server_name, tcp)
+        .await?;
+    let mut session = SmtpSession {
+        stream: BufReader::new(stream),
+    };
+    if config.port == IMPLICIT_TLS_PORT {
+        session.expect(220).await?;
+    }
+    session.command("EHLO daily-ai", 250).await?;
+    session.login(config).await?;
+    Ok(session)
+}
+
+/// Delivers runs by email to a fixed list of recipients.
+///
+/// A retried run is sent again as a reply to the first message, so mail clients thread the
+/// corrected summary under the original.
+#[derive(Debug, Clone)]
+pub struct EmailExporter {
+    config: SmtpConfig,
+    to: Vec<String>,
+}
+
+impl EmailExporter {
+    pub fn new(config: SmtpConfig, to: Vec<String>) -> Self {
+        EmailExporter { config, to }
+    }
+
+    /// `Message-ID` for a new message, using the sender's domain.
+    fn message_id(&self) -> String {
+        let domain = address(&self.config.from)
+            .rsplit_once('@')
+            .map_or("daily-ai", |(_, domain)| domain);
+        format!("<{}@{domain}>", Ulid::new())
+    }
+
+    async fn deliver(&self, context: &FullContext, replaces: Option<&str>) -> AppResult<String> {
+        let now = time_utils::now_local();
+        let subject = match replaces {
+            Some(_) => format!("Daily summary for {} (updated)", now.date()),
+            None => format!("Daily summary for {}", now.date()),
+        };
+        let message_id = self.message_id();
+        let message = Email {
+            from: &self.config.from,
+            to: &self.to,
+            subject: &subject,
+            message_id: &message_id,
+            replaces,
+            markdown: &markdown::render(context),
+            date: now,
+        }
+        .to_message()?;
+        let mut session = connect(&self.config).await?;
+        session.send(&self.config.from, &self.to, &message).await?;
+        if let Err(e) = session.command("QUIT", 221).await {
+            debug!("SMTP quit failed: {e}");
+        }
+        Ok(message_id)
+    }
+}
+
+impl Exporter for EmailExporter {
+    fn target(&self) -> String {
+        format!("email:{}", self.to.join(","))
+    }
+
+    async fn create(&self, context: &FullContext) -> AppResult<String> {
+        self.deliver(context, None).await
+    }
+
+    async fn update(&self, remote_id: &str, context: &FullContext) -> AppResult<()> {
+        self.deliver(context, Some(remote_id)).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn sends_a_dot_stuffed_multipart_message() {
+        let to = vec!["Team <team@example.com>".to_string()];
+        let message = Email {
+            from: "me@example.com",
+            to: &to,
+            subject: "Daily summary — Friday",
+            message_id: "<1@example.com>",
+            replaces: Some("<0@example.com>"),
+            markdown: "# Daily summary\n\n- Shipped\n",
+            date: datetime!(2025-01-31 17:00 UTC),
+        }
+        .to_message()
+        .unwrap();
+        assert!(message.contains("Subject: =?UTF-8?B?"));
+        assert!(message.contains("In-Reply-To: <0@example.com>\r\n"));
+        assert!(message.contains("Content-Type: text/html; charset=utf-8"));
+
+        let (client, mut server) = tokio::io::duplex(1 << 16);
+        let mut session = SmtpSession {
+            stream: BufReader::new(client),
+        };
+        // Replies are queued up front; the duplex buffer holds them until they are read.
+        server
+            .write_all(b"250 OK\r\n250 OK\r\n354 Go ahead\r\n250-Queued\r\n250 as 42\r\n")
+            .await
+            .unwrap();
+        session
+            .send("Me <me@example.com>", &to, "Hi\n.hidden\n")
+            .await
+            .unwrap();
+        drop(session);
+        let mut transcript = String::new();
+        server.read_to_string(&mut transcript).await.unwrap();
+
+        assert_eq!(
+            transcript,
+            "MAIL FROM:<me@example.com>\r\nRCPT TO:<team@example.com>\r\nDATA\r\n\
+             Hi\r\n..hidden\r\n.\r\n"
+        );
+    }
+
+    #[test]
+    fn checks_recipients() {
+        assert_eq!(
+            parse_mailbox(" team@example.com ").unwrap(),
+            "team@example.com"
+        );
+        assert_eq!(
+            parse_mailbox("Team <team@example.com>").unwrap(),
+            "Team <team@example.com>"
+        );
+        for bad in [
+            "team@example.com\r\nRCPT TO:<all@example.com>",
+            "Team <team@example.com>\nBcc: all@example.com",
+            "team",
+            "@example.com",
+            "team@",
+            "team@@example.com",
+            "team@exam ple.com",
+            "Team <team@example.com",
+            "Team> <team@example.com>",
+            "Team <team@example.com> <x@example.com>",
+        ] {
+            assert!(parse_mailbox(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn environment_overrides_the_configuration_file() {
+        let settings = SmtpSettings {
+            host: Some("smtp.example.com".to_string()),
+            port: Some(465),
+            user: Some("me@example.com".to_string()),
+            from: None,
+        };
+        let config = SmtpConfig::resolve(settings.clone(), |_| None).unwrap();
+        assert_eq!(
+            (config.host.as_str(), config.port, config.from.as_str()),
+            ("smtp.example.com", 465, "me@example.com")
+        );
+
+        let env = |name: &str| match name {
+            "SMTP_HOST" => Some("mail.internal".to_string()),
+            "SMTP_PORT" => Some("587".to_string()),
+            "SMTP_FROM" => Some(String::new()),
+            _ => None,
+        };
+        let config = SmtpConfig::resolve(settings, env).unwrap();
+        assert_eq!(
+            (config.host.as_str(), config.port, config.from.as_str()),
+            ("mail.internal", 587, "me@example.com")
+        );
+        assert!(SmtpConfig::resolve(SmtpSettings::default(), |_| None).is_none());
+    }
+}
@@ -1,5 +1,30 @@
+use std::process::ExitCode;
+
 use thiserror::Error;
 
+/// Exit codes, so automation can tell a quiet day apart from a broken collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Everything ran.
+    Success = 0,
+    /// Any failure without a more specific code.
+    Failure = 1,
+    /// Output was produced, but at least one collector failed or summary section is missing.
+    Partial = 2,
+    /// `--fail-on-empty` was given and nothing was collected.
+    Empty = 3,
+    /// Invalid arguments or configuration.
+    Config = 4,
+    /// The language model server could not be reached or did not answer in time.
+    ServerUnreachable = 5,
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
 /// Unified application error type to simplify bubbling errors through async flows.
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -15,14 +40,29 @@ pub enum AppError {
     SerdeJsonSer(#[from] serde_json::Error),
     #[error("Error communicating with the AI. {0}")]
     AIClient(#[from] async_openai::error::OpenAIError),
+    #[error(
+        "The language model server did not answer within {}.",
+        humantime::format_duration(*.0)
+    )]
+    ModelTimeout(std::time::Duration),
     #[error("Error while writing information to a string. {0}")]
     BufferWrite(#[from] std::fmt::Error),
     #[error("Unable to parse string. {0}")]
     Utf8Parse(#[from] std::str::Utf8Error),
+    #[error(
+        "Safari's history database has a layout daily-ai does not know (schema version {0}; \
+         missing {1}). Please file an issue at https://github.com/annie444/daily-ai/issues with \
+         the schema version and your macOS and Safari versions."
+    )]
+    UnsupportedSafariSchema(i64, String),
     #[error("Error from SQLite driver. {0}")]
     Sqlx(#[from] sea_orm::sqlx::Error),
     #[error("{0}")]
     Other(String),
+    #[error("{0}")]
+    Config(String),
+    #[error("Nothing was collected in the {0}.")]
+    NothingCollected(String),
     #[error("Unable to run local machine learning models. Here's what hugging face said: {0}")]
     Candle(#[from] candle_core::Error),
     #[error("Uh oh! The runtime had a problem. Here's what happened: {0}")]
@@ -39,16 +79,47 @@ pub enum AppError {
         "Something happened while processing shell history from Atuin. Atuin errored with: {0}"
     )]
     AtuinClient(String),
+    #[error("Something went wrong while talking to the IMAP server. Here's what happened: {0}")]
+    Imap(String),
+    #[error("Something went wrong while sending email. The SMTP server said: {0}")]
+    Smtp(String),
     #[error("Something happened while accessing the internet. Here's the error: {0}")]
     MCPClient(#[from] reqwest::Error),
     #[error("Unable to convert the duration string to a number. Got error: {0}")]
     DurationParse(#[from] humantime::DurationError),
+    #[error("Unable to format a date. {0}")]
+    TimeFormat(#[from] time::error::Format),
     #[error("Duration seems too large... The value overflowed with the error: {0}")]
     DurationOverflow(#[from] time::error::ConversionRange),
     #[error("Something happened during linear algebra operations. Here's the error: {0}")]
     Linalg(#[from] ndarray_linalg::error::LinalgError),
     #[error("Something happened while grouping the URLs. This is the error: {0}")]
     Hdbscan(#[from] hdbscan::HdbscanError),
+    #[error("Unable to write the PDF. {0}")]
+    Pdf(#[from] lopdf::Error),
+    #[error("Unable to render the template. {0}")]
+    Template(#[from] minijinja::Error),
+    #[error("The model gave no usable answer: {0}")]
+    NoAnswer(String),
+    #[error("{0} is not on the network allowlist, so nothing was sent to it")]
+    HostNotAllowed(String),
+}
+
+impl AppError {
+    /// Exit code reported when the run fails with this error.
+    pub fn exit_status(&self) -> ExitStatus {
+        match self {
+            AppError::Config(_) => ExitStatus::Config,
+            AppError::NothingCollected(_) => ExitStatus::Empty,
+            AppError::ModelTimeout(_) => ExitStatus::ServerUnreachable,
+            AppError::AIClient(async_openai::error::OpenAIError::Reqwest(e))
+                if e.is_connect() || e.is_timeout() =>
+            {
+                ExitStatus::ServerUnreachable
+            }
+            _ => ExitStatus::Failure,
+        }
+    }
 }
 
 /// Convenience alias for results that bubble `AppError`.
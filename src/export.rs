@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use tokio::fs;
+use tracing::{debug, info};
+use ulid::Ulid;
+
+use crate::AppResult;
+use crate::context::FullContext;
+use crate::dirs::DirType;
+use crate::error::AppError;
+
+/// Somewhere a run's summary is posted, such as a chat channel or a mailbox.
+///
+/// Exporters return an identifier for what they posted so that a retried run (same
+/// `--run-id`) can replace the earlier post rather than adding a second one.
+pub trait Exporter {
+    /// Key for this exporter's entries in the [`ExportLedger`], e.g. `slack:#standup`.
+    fn target(&self) -> String;
+
+    /// Post the run, returning the remote identifier of the new post.
+    async fn create(&self, context: &FullContext) -> AppResult<String>;
+
+    /// Replace the post made earlier for the same run.
+    async fn update(&self, remote_id: &str, context: &FullContext) -> AppResult<()>;
+}
+
+/// How [`export`] delivered a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Created,
+    Updated,
+}
+
+/// Remote identifiers of everything exported, by run and target.
+#[derive(Debug, Default)]
+pub struct ExportLedger {
+    path: PathBuf,
+    runs: BTreeMap<Ulid, BTreeMap<String, String>>,
+}
+
+impl ExportLedger {
+    /// Load the ledger kept in the data directory.
+    pub async fn load() -> AppResult<Self> {
+        Self::load_from(DirType::Data.get_dir()?.join("exports.json")).await
+    }
+
+    /// Load the ledger at `path`, starting empty when it does not exist yet.
+    pub async fn load_from(path: PathBuf) -> AppResult<Self> {
+        let runs = match fs::read_to_string(&path).await {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ExportLedger { path, runs })
+    }
+
+    /// What `run` was posted as on `target`, if it was exported there before.
+    pub fn remote_id(&self, run: Ulid, target: &str) -> Option<&str> {
+        self.runs.get(&run)?.get(target).map(String::as_str)
+    }
+
+    /// Remember that `run` was posted to `target` as `remote_id`, and save the ledger.
+    pub async fn record(&mut self, run: Ulid, target: String, remote_id: String) -> AppResult<()> {
+        self.runs.entry(run).or_default().insert(target, remote_id);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.runs)?).await?;
+        Ok(())
+    }
+}
+
+/// Send a run to `exporter`, updating the earlier post when the run was already exported there.
+#[tracing::instrument(name = "Exporting run", level = "info", skip_all, fields(target = exporter.target()))]
+pub async fn export<E: Exporter>(
+    exporter: &E,
+    ledger: &mut ExportLedger,
+    context: &FullContext,
+) -> AppResult<Delivery> {
+    let Some(run) = context.run_id else {
+        return Err(AppError::Config(
+            "Only runs with a run ID can be exported".into(),
+        ));
+    };
+    let target = exporter.target();
+    if let Some(remote_id) = ledger.remote_id(run, &target) {
+        debug!("Run {run} was already exported to {target} as {remote_id}");
+        exporter.update(remote_id, context).await?;
+        info!("Updated the export of run {run} to {target}");
+        return Ok(Delivery::Updated);
+    }
+    let remote_id = exporter.create(context).await?;
+    ledger.record(run, target.clone(), remote_id).await?;
+    info!("Exported run {run} to {target}");
+    Ok(Delivery::Created)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Records calls instead of posting anywhere.
+    #[derive(Default)]
+    struct Recorder {
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl Exporter for Recorder {
+        fn target(&self) -> String {
+            "test".into()
+        }
+
+        async fn create(&self, _context: &FullContext) -> AppResult<String> {
+            let mut calls = self.calls.borrow_mut();
+            calls.push("create".into());
+            Ok(format!("post-{}", calls.len()))
+        }
+
+        async fn update(&self, remote_id: &str, _context: &FullContext) -> AppResult<()> {
+            self.calls.borrow_mut().push(format!("update {remote_id}"));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn retried_runs_update_their_earlier_export() {
+        let path = std::env::temp_dir().join(format!("daily_ai_exports_{}.json", Ulid::new()));
+        let exporter = Recorder::default();
+        let context = FullContext {
+            run_id: Some(Ulid::new()),
+            ..Default::default()
+        };
+
+        let mut ledger = ExportLedger::load_from(path.clone()).await.unwrap();
+        let first = export(&exporter, &mut ledger, &context).await.unwrap();
+        let mut reloaded = ExportLedger::load_from(path.clone()).await.unwrap();
+        let retry = export(&exporter, &mut reloaded, &context).await.unwrap();
+        let other_run = FullContext {
+            run_id: Some(Ulid::new()),
+            ..Default::default()
+        };
+        export(&exporter, &mut reloaded, &other_run).await.unwrap();
+
+        assert_eq!((first, retry), (Delivery::Created, Delivery::Updated));
+        assert_eq!(
+            *exporter.calls.borrow(),
+            ["create", "update post-1", "create"]
+        );
+        assert!(
+            export(&exporter, &mut reloaded, &FullContext::default())
+                .await
+                .is_err()
+        );
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::ErrorKind;
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::warnings::{self, WarningKind};
+
+/// How long an external collector may run before it is killed.
+const COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A user-configured collector command, given as `--external-collector NAME=COMMAND`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalCommand {
+    pub name: String,
+    /// Shell command line, run with `sh -c`.
+    pub command: String,
+}
+
+/// One activity reported by an external collector.
+///
+/// External collectors print `{"activities": [...]}` on stdout, where each entry has a `title`
+/// and optionally `start`/`end` (RFC 3339), `details`, and `tags`. `source` is filled in from the
+/// collector's name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExternalActivity {
+    #[serde(default)]
+    pub source: String,
+    pub title: String,
+    #[serde(
+        default,
+        with = "crate::serde_helpers::option_offset_datetime",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub start: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        with = "crate::serde_helpers::option_offset_datetime",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// What an external collector prints on stdout.
+#[derive(Debug, Deserialize)]
+struct ExternalOutput {
+    activities: Vec<ExternalActivity>,
+}
+
+/// Parse a collector's stdout, tagging every activity with the collector's name.
+fn parse_output(name: &str, stdout: &str) -> Result<Vec<ExternalActivity>, serde_json::Error> {
+    let output: ExternalOutput = serde_json::from_str(stdout)?;
+    Ok(output
+        .activities
+        .into_iter()
+        .map(|activity| ExternalActivity {
+            source: name.to_string(),
+            ..activity
+        })
+        .collect())
+}
+
+/// Run one external collector. Returns `None` (after logging why) when it fails.
+async fn run_command(
+    command: &ExternalCommand,
+    since: OffsetDateTime,
+    until: OffsetDateTime,
+) -> AppResult<Option<Vec<ExternalActivity>>> {
+    let mut process = Command::new("sh");
+    process
+        .arg("-c")
+        .arg(&command.command)
+        .env("DAILY_AI_SINCE", since.format(&Rfc3339)?)
+        .env("DAILY_AI_UNTIL", until.format(&Rfc3339)?)
+        .env(
+            "DAILY_AI_WINDOW_SECONDS",
+            (until - since).whole_seconds().to_string(),
+        )
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = match tokio::time::timeout(COMMAND_TIMEOUT, process.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) if e.kind() == ErrorKind::NotFound => {
+            warnings::record(
+                WarningKind::CollectorFailed,
+                "external",
+                format!(
+                    "Cannot run external collector {}: sh not found",
+                    command.name
+                ),
+            );
+            return Ok(None);
+        }
+        Ok(Err(e)) => return Err(e.into()),
+        Err(_) => {
+            warnings::record(
+                WarningKind::CollectorFailed,
+                "external",
+                format!(
+                    "External collector {} timed out after {}s",
+                    command.name,
+                    COMMAND_TIMEOUT.as_secs()
+                ),
+            );
+            return Ok(None);
+        }
+    };
+    if !output.status.success() {
+        warnings::record(
+            WarningKind::CollectorFailed,
+            "external",
+            format!(
+                "External collector {} exited with {}: {}",
+                command.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        );
+        return Ok(None);
+    }
+    match parse_output(&command.name, &String::from_utf8_lossy(&output.stdout)) {
+        Ok(activities) => Ok(Some(activities)),
+        Err(e) => {
+            warnings::record(
+                WarningKind::CollectorFailed,
+                "external",
+                format!(
+                    "External collector {} printed invalid output: {}",
+                    command.name, e
+                ),
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Run every external collector for the window and gather what they report.
+///
+/// Each command gets the window as `DAILY_AI_SINCE`/`DAILY_AI_UNTIL` (RFC 3339) and
+/// `DAILY_AI_WINDOW_SECONDS`. A collector that fails, times out, or prints invalid JSON is
+/// skipped with a warning rather than failing the run.
+#[tracing::instrument(name = "Running external collectors", level = "info", skip(commands))]
+pub async fn get_external_activity(
+    commands: &[ExternalCommand],
+    duration: &Duration,
+) -> AppResult<Vec<ExternalActivity>> {
+    let until = OffsetDateTime::now_utc();
+    let since = until.saturating_sub(*duration);
+    let mut activities = Vec::new();
+    for command in commands {
+        if let Some(reported) = run_command(command, since, until).await? {
+            debug!(
+                "External collector {} reported {} activities",
+                command.name,
+                reported.len()
+            );
+            activities.extend(reported);
+        }
+    }
+    Ok(activities)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn parses_activities_and_tags_them_with_the_collector() {
+        let activities = parse_output(
+            "toggl",
+            r#"{"activities": [
+                {"title": "Code review", "start": "2025-01-01T09:00:00Z", "end": "2025-01-01T10:30:00Z", "tags": ["review"]},
+                {"title": "Standup", "source": "ignored", "details": "15 minutes"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            activities,
+            vec![
+                ExternalActivity {
+                    source: "toggl".into(),
+                    title: "Code review".into(),
+                    start: Some(datetime!(2025-01-01 09:00 UTC)),
+                    end: Some(datetime!(2025-01-01 10:30 UTC)),
+                    details: None,
+                    tags: vec!["review".into()],
+                },
+                ExternalActivity {
+                    source: "toggl".into(),
+                    title: "Standup".into(),
+                    start: None,
+                    end: None,
+                    details: Some("15 minutes".into()),
+                    tags: vec![],
+                },
+            ]
+        );
+        assert!(parse_output("toggl", "[]").is_err());
+    }
+
+    #[tokio::test]
+    async fn runs_commands_with_the_window_in_the_environment() {
+        let command = ExternalCommand {
+            name: "echo".into(),
+            command: r#"printf '{"activities": [{"title": "%s"}]}' "$DAILY_AI_WINDOW_SECONDS""#
+                .into(),
+        };
+        let failing = ExternalCommand {
+            name: "broken".into(),
+            command: "exit 3".into(),
+        };
+
+        let activities = get_external_activity(&[failing, command], &Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].source, "echo");
+        assert_eq!(activities[0].title, "3600");
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, Time, UtcOffset};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::AppResult;
+use crate::archive::{self, ArchiveEntry};
+use crate::dashboard::html::escape;
+use crate::render::{html, markdown};
+use crate::time_utils;
+
+/// When a day's entry counts as published: the end of the working day, local time.
+const PUBLISHED_AT: Time = time::macros::time!(18:00);
+
+/// Title of an archived day: its date and first highlight.
+fn entry_title(entry: &ArchiveEntry) -> String {
+    let highlight = entry
+        .context
+        .summary
+        .as_ref()
+        .and_then(|summary| summary.highlights.first());
+    match highlight {
+        Some(highlight) => format!("{}: {highlight}", entry.date),
+        None => entry.date.to_string(),
+    }
+}
+
+/// Render `entries` as an Atom feed, newest first, with times in `offset`.
+fn render(entries: &[&ArchiveEntry], offset: UtcOffset) -> AppResult<String> {
+    let published = |entry: &ArchiveEntry| {
+        entry
+            .date
+            .with_time(PUBLISHED_AT)
+            .assume_offset(offset)
+            .format(&Rfc3339)
+    };
+    let updated = match entries.first() {
+        Some(entry) => published(entry)?,
+        None => OffsetDateTime::now_utc()
+            .to_offset(offset)
+            .format(&Rfc3339)?,
+    };
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    out.push_str("  <title>Daily summaries</title>\n");
+    out.push_str("  <id>urn:daily-ai:feed</id>\n");
+    let _ = writeln!(out, "  <updated>{updated}</updated>");
+    out.push_str("  <author><name>daily-ai</name></author>\n");
+    let _ = writeln!(
+        out,
+        "  <generator version=\"{}\">daily-ai</generator>",
+        env!("CARGO_PKG_VERSION")
+    );
+    for entry in entries {
+        let id = match entry.context.run_id {
+            Some(run_id) => format!("urn:daily-ai:run:{run_id}"),
+            None => format!("urn:daily-ai:day:{}", entry.date),
+        };
+        let content = html::markdown_body(&markdown::render(&entry.context));
+        let _ = write!(
+            out,
+            "  <entry>\n    <title>{}</title>\n    <id>{id}</id>\n    \
+             <updated>{}</updated>\n    <content type=\"html\">{}</content>\n  </entry>\n",
+            escape(&entry_title(entry)),
+            published(entry)?,
+            escape(&content),
+        );
+    }
+    out.push_str("</feed>\n");
+    Ok(out)
+}
+
+/// Write the last `count` archived summaries to `output` as an Atom feed.
+#[tracing::instrument(name = "Generating feed", level = "info")]
+pub async fn generate_feed(output: &Path, count: usize) -> AppResult<()> {
+    let entries = archive::load_all().await?;
+    let summarized: Vec<&ArchiveEntry> = entries
+        .iter()
+        .rev()
+        .filter(|entry| entry.context.summary.is_some())
+        .take(count)
+        .collect();
+    if summarized.is_empty() {
+        warn!("The archive has no summaries. Run `daily-ai summarize` to start filling it.");
+    }
+    let offset = time_utils::local_offset();
+    fs::write(output, render(&summarized, offset)?).await?;
+    info!(
+        "Wrote a feed of {} summaries to {}",
+        summarized.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, offset};
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+    use crate::context::FullContext;
+
+    #[test]
+    fn renders_days_as_atom_entries() {
+        let day = ArchiveEntry {
+            date: date!(2025 - 01 - 31),
+            context: FullContext {
+                summary: Some(WorkSummary {
+                    summary: "Shipped <the> parser.".into(),
+                    highlights: vec!["Parser & lexer merged".into()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+        let feed = render(&[&day], offset!(+1)).unwrap();
+
+        assert!(feed.contains("<updated>2025-01-31T18:00:00+01:00</updated>"));
+        assert!(feed.contains("<title>2025-01-31: Parser &amp; lexer merged</title>"));
+        assert!(feed.contains("<id>urn:daily-ai:day:2025-01-31</id>"));
+        assert!(feed.contains("&lt;p&gt;Shipped &amp;lt;the&amp;gt; parser.&lt;/p&gt;"));
+        assert!(feed.ends_with("</entry>\n</feed>\n"));
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::HashSet;
+
+use async_openai::{Client, config::Config};
+use git2::{BranchType, Commit, Oid, Repository, Status, StatusOptions, Tree};
+use tracing::{debug, error, info, trace};
+
+use super::hist::get_diff_opts;
+use crate::AppResult;
+use crate::ai::commit_message::{CommitMessageOptions, generate_commit_message};
+use crate::error::AppError;
+use crate::shell::ShellHistoryEntry;
+
+/// Status options used to find staged and working-directory changes to commit.
+fn get_status_opts() -> StatusOptions {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .include_ignored(false)
+        .include_unmodified(true)
+        .exclude_submodules(true)
+        .recurse_untracked_dirs(true)
+        .disable_pathspec_match(false)
+        .recurse_ignored_dirs(false)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true)
+        .sort_case_sensitively(false)
+        .sort_case_insensitively(true)
+        .renames_from_rewrites(true)
+        .no_refresh(false)
+        .update_index(true)
+        .include_unreadable(false);
+    opts
+}
+
+/// Get HEAD tree and parents, or an empty tree when HEAD is unborn.
+#[tracing::instrument(name = "Fetching git tree", level = "info", skip(repo))]
+fn head_tree_and_parents<'b, 'a: 'b>(
+    repo: &'a Repository,
+) -> AppResult<(Tree<'b>, Vec<Commit<'b>>)> {
+    if let Ok(head) = repo.head()
+        && let Some(oid) = head.target()
+    {
+        let parent = repo.find_commit(oid)?;
+        let tree = parent.tree()?;
+        return Ok((tree, vec![parent]));
+    }
+    // Unborn HEAD: create an empty tree and no parents.
+    let builder = repo.treebuilder(None)?;
+    let empty_tree_id = builder.write()?;
+    let empty_tree = repo.find_tree(empty_tree_id)?;
+    Ok((empty_tree, Vec::new()))
+}
+
+/// Commit staged and/or working directory changes into the repository so history is current.
+#[tracing::instrument(
+    name = "Checking repo status",
+    level = "info",
+    skip(client, repo, options)
+)]
+async fn check_repo_status<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    repo: &Repository,
+    options: CommitMessageOptions<'_>,
+) -> AppResult<()> {
+    let mut opts = get_status_opts();
+
+    let statuses = repo.statuses(Some(&mut opts))?;
+    let mut staged_changes = false;
+    let mut working_dir_changes = false;
+    for entry in statuses.iter() {
+        let s = entry.status();
+        // look for flags that indicate working‐directory changes (vs just staged)
+        if s.intersects(
+            Status::WT_MODIFIED
+                | Status::WT_DELETED
+                | Status::WT_NEW
+                | Status::WT_TYPECHANGE
+                | Status::WT_RENAMED,
+        ) {
+            // There are working-directory changes
+            trace!("Working directory has changes in: {:?}", entry.path());
+            working_dir_changes = true;
+        }
+        if s.intersects(
+            Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_NEW
+                | Status::INDEX_TYPECHANGE
+                | Status::INDEX_RENAMED,
+        ) {
+            // There are staged changes
+            trace!("Staged changes in: {:?}", entry.path());
+            staged_changes = true;
+        }
+    }
+    if !staged_changes && !working_dir_changes {
+        debug!("No changes to commit.");
+        return Ok(());
+    }
+    if staged_changes {
+        info!(
+            "Committing staged directory changes for {}...",
+            repo.path().display()
+        );
+        let (head_tree, parents) = head_tree_and_parents(repo)?;
+        let mut index = repo.index()?;
+        let diff =
+            repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut get_diff_opts()))?;
+        let tree_id = index.write_tree()?;
+        let tree = repo.find_tree(tree_id)?;
+        let commit_message = generate_commit_message(client, model, &diff, repo, options).await?;
+        let sig = repo.signature()?;
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            commit_message.to_string().as_str(),
+            &tree,
+            &parents.iter().collect::<Vec<&Commit>>(),
+        )?;
+        info!("Staged changes committed.");
+    }
+    if working_dir_changes {
+        info!(
+            "Committing working directory changes for {}...",
+            repo.path().display()
+        );
+        let (head_tree, parents) = head_tree_and_parents(repo)?;
+        let mut index = repo.index()?;
+        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+        index.write()?;
+        let diff =
+            repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut get_diff_opts()))?;
+        let commit_message = generate_commit_message(client, model, &diff, repo, options).await?;
+        let tree_id = index.write_tree()?;
+        let tree = repo.find_tree(tree_id)?;
+        let sig = repo.signature()?;
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            commit_message.to_string().as_str(),
+            &tree,
+            &parents.iter().collect::<Vec<&Commit>>(),
+        )?;
+        info!("Working directory changes committed.");
+    }
+    Ok(())
+}
+
+/// Name of a remote-tracking branch that already contains `oid`, if any.
+///
+/// The upstream of the current branch is checked first, then every other remote branch.
+fn pushed_to(repo: &Repository, oid: Oid) -> AppResult<Option<String>> {
+    let contains = |tip: Oid| tip == oid || repo.graph_descendant_of(tip, oid).unwrap_or(false);
+    let upstream = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string))
+        .and_then(|name| repo.find_branch(&name, BranchType::Local).ok())
+        .and_then(|branch| branch.upstream().ok());
+    let remotes = repo
+        .branches(Some(BranchType::Remote))?
+        .flatten()
+        .map(|(b, _)| b);
+    for branch in upstream.into_iter().chain(remotes) {
+        if let (Some(tip), Ok(Some(name))) = (branch.get().target(), branch.name())
+            && contains(tip)
+        {
+            return Ok(Some(name.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Commit the staged changes with a generated message, or amend HEAD when `amend` is set.
+///
+/// When amending, the message is regenerated from the combined diff of HEAD and the staged
+/// changes against HEAD's parent. Amending is refused once HEAD is on a remote, since rewriting
+/// it would diverge from what others may have pulled.
+#[tracing::instrument(
+    name = "Committing staged changes",
+    level = "info",
+    skip(client, repo, options)
+)]
+pub async fn commit_staged<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    repo: &Repository,
+    amend: bool,
+    options: CommitMessageOptions<'_>,
+) -> AppResult<Oid> {
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    if !amend {
+        let (head_tree, parents) = head_tree_and_parents(repo)?;
+        if head_tree.id() == tree.id() {
+            return Err(AppError::Other("Nothing is staged to commit.".into()));
+        }
+        let diff =
+            repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut get_diff_opts()))?;
+        let commit_message = generate_commit_message(client, model, &diff, repo, options).await?;
+        let sig = repo.signature()?;
+        let oid = repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            commit_message.to_string().as_str(),
+            &tree,
+            &parents.iter().collect::<Vec<&Commit>>(),
+        )?;
+        info!("Committed {}: {}", oid, commit_message.summary);
+        return Ok(oid);
+    }
+
+    let head = repo
+        .head()?
+        .peel_to_commit()
+        .map_err(|_| AppError::Other("There is no commit to amend yet.".into()))?;
+    if let Some(remote) = pushed_to(repo, head.id())? {
+        return Err(AppError::Other(format!(
+            "HEAD ({}) is already on {remote}; refusing to amend a pushed commit.",
+            head.id()
+        )));
+    }
+    let base_tree = match head.parent(0) {
+        Ok(parent) => parent.tree()?,
+        Err(_) => repo.find_tree(repo.treebuilder(None)?.write()?)?,
+    };
+    let diff =
+        repo.diff_tree_to_index(Some(&base_tree), Some(&index), Some(&mut get_diff_opts()))?;
+    let commit_message = generate_commit_message(client, model, &diff, repo, options).await?;
+    let oid = head.amend(
+        Some("HEAD"),
+        None,
+        None,
+        None,
+        Some(commit_message.to_string().as_str()),
+        Some(&tree),
+    )?;
+    info!(
+        "Amended {} into {}: {}",
+        head.id(),
+        oid,
+        commit_message.summary
+    );
+    Ok(oid)
+}
+
+/// Auto-commit pending changes in every repository seen in shell history.
+///
+/// Together with `commit_staged` (the `commit` command), this is the only code path that writes
+/// to repositories. It runs before collection when `summarize --auto-commit` is given, so the
+/// collected history includes the new commits.
+#[tracing::instrument(
+    name = "Auto-committing pending changes",
+    level = "info",
+    skip(client, shell_history, options)
+)]
+pub async fn commit_pending_changes<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    shell_history: &[ShellHistoryEntry],
+    options: CommitMessageOptions<'_>,
+) -> AppResult<()> {
+    let mut visited = HashSet::new();
+    for entry in shell_history {
+        if !visited.insert(entry.directory.clone()) {
+            continue;
+        }
+        if let Ok(repo) = Repository::open(&entry.directory) {
+            match check_repo_status(client, model, &repo, options).await {
+                Ok(_) => debug!("Repository status checked for {:?}", entry.directory),
+                Err(e) => error!(
+                    "Failed to check repository status for {}: {}. Continuing without committing changes.",
+                    entry.directory.display(),
+                    e
+                ),
+            };
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use git2::Signature;
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    #[test]
+    fn detects_commits_already_on_a_remote() {
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let dir = std::env::temp_dir().join(format!("commit_pushed_{nonce}"));
+        let repo = Repository::init(&dir).unwrap();
+        fs::write(dir.join("file.txt"), "one\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let first = repo
+            .commit(Some("HEAD"), &sig, &sig, "first", &tree, &[])
+            .unwrap();
+
+        assert_eq!(pushed_to(&repo, first).unwrap(), None);
+
+        repo.reference("refs/remotes/origin/main", first, true, "test")
+            .unwrap();
+        assert_eq!(
+            pushed_to(&repo, first).unwrap().as_deref(),
+            Some("origin/main")
+        );
+
+        let parent = repo.find_commit(first).unwrap();
+        let second = repo
+            .commit(Some("HEAD"), &sig, &sig, "second", &tree, &[&parent])
+            .unwrap();
+        assert_eq!(pushed_to(&repo, second).unwrap(), None);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use git2::{Delta, Diff, Repository, Tree};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use toml::Value as Toml;
+use tracing::debug;
+
+use super::diff::DiffSummary;
+
+/// Lockfile-only changes listed at most per repository. A fresh `cargo update` or
+/// `npm install` can move hundreds of transitive packages.
+pub const MAX_LOCKFILE_ONLY_CHANGES: usize = 30;
+
+/// Sections of `Cargo.toml` that declare dependencies.
+const CARGO_SECTIONS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Fields of `package.json` that declare dependencies.
+const NPM_SECTIONS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Package ecosystem a dependency belongs to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+}
+
+/// How a dependency changed between the start of the window and HEAD.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyChangeKind {
+    Added,
+    Removed,
+    /// Moved to another version, up or down.
+    Updated,
+}
+
+/// A crate or npm package added, removed, or moved to another version in the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DependencyChange {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub change: DependencyChangeKind,
+    /// Locked versions before the change, or the manifest requirement when no lockfile changed.
+    /// Several versions are comma-separated.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub to: Option<String>,
+    /// Declared in a `Cargo.toml` or `package.json` that changed. `false` for packages that only
+    /// moved in the lockfile, e.g. transitive dependencies or a `cargo update`.
+    pub direct: bool,
+}
+
+impl DependencyChange {
+    /// Classify the versions that disappeared (`removed`) and appeared (`added`) for a package.
+    /// Returns `None` when nothing actually changed.
+    fn new(
+        ecosystem: Ecosystem,
+        name: &str,
+        removed: &BTreeSet<String>,
+        added: &BTreeSet<String>,
+        direct: bool,
+    ) -> Option<Self> {
+        let join = |versions: Vec<&String>| {
+            (!versions.is_empty()).then(|| {
+                versions
+                    .into_iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+        };
+        let from = join(removed.difference(added).collect());
+        let to = join(added.difference(removed).collect());
+        let change = match (&from, &to) {
+            (None, None) => return None,
+            (None, Some(_)) => DependencyChangeKind::Added,
+            (Some(_), None) => DependencyChangeKind::Removed,
+            (Some(_), Some(_)) => DependencyChangeKind::Updated,
+        };
+        Some(DependencyChange {
+            ecosystem,
+            name: name.to_string(),
+            change,
+            from,
+            to,
+            direct,
+        })
+    }
+
+    /// One-line description, e.g. `Updated tokio 1.40.0 → 1.43.0`.
+    pub fn describe(&self) -> String {
+        let from = self.from.as_deref().unwrap_or_default();
+        let to = self.to.as_deref().unwrap_or_default();
+        match self.change {
+            DependencyChangeKind::Added => format!("Added {} {to}", self.name),
+            DependencyChangeKind::Removed => format!("Removed {} {from}", self.name),
+            DependencyChangeKind::Updated => format!("Updated {} {from} → {to}", self.name),
+        }
+    }
+}
+
+/// Dependency files that are parsed instead of diffed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DependencyFile {
+    CargoManifest,
+    CargoLock,
+    NpmManifest,
+    NpmLock,
+}
+
+impl DependencyFile {
+    fn of(path: &Path) -> Option<Self> {
+        match path.file_name()?.to_str()? {
+            "Cargo.toml" => Some(DependencyFile::CargoManifest),
+            "Cargo.lock" => Some(DependencyFile::CargoLock),
+            "package.json" => Some(DependencyFile::NpmManifest),
+            "package-lock.json" | "npm-shrinkwrap.json" => Some(DependencyFile::NpmLock),
+            _ => None,
+        }
+    }
+
+    fn ecosystem(self) -> Ecosystem {
+        match self {
+            DependencyFile::CargoManifest | DependencyFile::CargoLock => Ecosystem::Cargo,
+            DependencyFile::NpmManifest | DependencyFile::NpmLock => Ecosystem::Npm,
+        }
+    }
+
+    fn is_lockfile(self) -> bool {
+        matches!(self, DependencyFile::CargoLock | DependencyFile::NpmLock)
+    }
+
+    /// Package name to the versions (lockfiles) or requirements (manifests) declared in `text`.
+    /// Files that do not parse declare nothing.
+    fn parse(self, text: &str) -> Versions {
+        let mut versions = Versions::new();
+        match self {
+            DependencyFile::CargoManifest => {
+                if let Ok(manifest) = text.parse::<toml::Table>() {
+                    cargo_manifest(&manifest, &mut versions);
+                }
+            }
+            DependencyFile::CargoLock => {
+                if let Ok(lock) = text.parse::<toml::Table>() {
+                    for package in lock
+                        .get("package")
+                        .and_then(Toml::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        if let (Some(name), Some(version)) = (
+                            package.get("name").and_then(Toml::as_str),
+                            package.get("version").and_then(Toml::as_str),
+                        ) {
+                            insert(&mut versions, name, version.to_string());
+                        }
+                    }
+                }
+            }
+            DependencyFile::NpmManifest => {
+                if let Ok(manifest) = serde_json::from_str::<Json>(text) {
+                    for section in NPM_SECTIONS {
+                        for (name, requirement) in
+                            manifest[section].as_object().into_iter().flatten()
+                        {
+                            if let Some(requirement) = requirement.as_str() {
+                                insert(&mut versions, name, requirement.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            DependencyFile::NpmLock => {
+                if let Ok(lock) = serde_json::from_str::<Json>(text) {
+                    npm_lock(&lock, &mut versions);
+                }
+            }
+        }
+        versions
+    }
+}
+
+/// Package name to its versions or requirements. Cargo locks several versions of one crate
+/// side by side.
+type Versions = BTreeMap<String, BTreeSet<String>>;
+
+fn insert(versions: &mut Versions, name: &str, version: String) {
+    versions
+        .entry(name.to_string())
+        .or_default()
+        .insert(version);
+}
+
+/// Requirement of a `Cargo.toml` dependency entry: its version, else where it comes from.
+fn cargo_requirement(spec: &Toml) -> String {
+    let field = |name: &str| spec.get(name).and_then(Toml::as_str);
+    if let Some(version) = spec.as_str().or_else(|| field("version")) {
+        version.to_string()
+    } else if let Some(path) = field("path") {
+        format!("path {path}")
+    } else if let Some(git) = field("git") {
+        match field("rev")
+            .or_else(|| field("tag"))
+            .or_else(|| field("branch"))
+        {
+            Some(reference) => format!("git {git}#{reference}"),
+            None => format!("git {git}"),
+        }
+    } else if spec.get("workspace").and_then(Toml::as_bool) == Some(true) {
+        "workspace".into()
+    } else {
+        "*".into()
+    }
+}
+
+/// Dependencies of a `Cargo.toml`, including `[workspace.dependencies]` and
+/// `[target.'cfg(...)'.dependencies]`.
+fn cargo_manifest(manifest: &toml::Table, versions: &mut Versions) {
+    let mut tables: Vec<&Toml> = CARGO_SECTIONS
+        .iter()
+        .filter_map(|section| manifest.get(*section))
+        .collect();
+    tables.extend(
+        manifest
+            .get("workspace")
+            .and_then(|workspace| workspace.get("dependencies")),
+    );
+    for target in manifest
+        .get("target")
+        .and_then(Toml::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values())
+    {
+        tables.extend(
+            CARGO_SECTIONS
+                .iter()
+                .filter_map(|section| target.get(*section)),
+        );
+    }
+    for table in tables.into_iter().filter_map(Toml::as_table) {
+        for (name, spec) in table {
+            insert(versions, name, cargo_requirement(spec));
+        }
+    }
+}
+
+/// Packages of a `package-lock.json`: the `packages` map of lockfile versions 2 and 3, or the
+/// nested `dependencies` of version 1.
+fn npm_lock(lock: &Json, versions: &mut Versions) {
+    if let Some(packages) = lock["packages"].as_object() {
+        for (path, package) in packages {
+            // `""` is the project itself; workspace members are symlinked with `link: true`.
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            if let Some(version) = package["version"].as_str() {
+                insert(versions, name, version.to_string());
+            }
+        }
+        return;
+    }
+    fn nested(dependencies: &Json, versions: &mut Versions) {
+        for (name, package) in dependencies.as_object().into_iter().flatten() {
+            if let Some(version) = package["version"].as_str() {
+                insert(versions, name, version.to_string());
+            }
+            nested(&package["dependencies"], versions);
+        }
+    }
+    nested(&lock["dependencies"], versions);
+}
+
+/// Contents of the blob at `path` in `tree`, or `None` when it is missing, unfetched, or not
+/// UTF-8.
+fn blob_text(repo: &Repository, tree: &Tree, path: Option<&Path>) -> Option<String> {
+    let entry = tree.get_path(path?).ok()?;
+    let blob = entry.to_object(repo).ok()?.peel_to_blob().ok()?;
+    String::from_utf8(blob.content().to_vec()).ok()
+}
+
+/// Versions that disappeared and appeared per package, keyed by ecosystem and name.
+type Deltas = BTreeMap<(Ecosystem, String), (BTreeSet<String>, BTreeSet<String>)>;
+
+/// Dependency changes between `base` and `head`, read from the `Cargo.toml`, `Cargo.lock`,
+/// `package.json`, and `package-lock.json` files that `diff` touches.
+///
+/// Packages declared in a changed manifest are reported as direct, with their locked versions
+/// when a lockfile changed as well. Packages that only moved in a lockfile follow, capped at
+/// [`MAX_LOCKFILE_ONLY_CHANGES`]; the second value is how many of those were left out.
+pub fn dependency_changes(
+    repo: &Repository,
+    base: &Tree,
+    head: &Tree,
+    diff: &Diff,
+) -> (Vec<DependencyChange>, usize) {
+    let mut manifests = Deltas::new();
+    let mut locks = Deltas::new();
+    for delta in diff.deltas() {
+        if matches!(delta.status(), Delta::Unmodified | Delta::Ignored) {
+            continue;
+        }
+        let old_path = delta.old_file().path();
+        let new_path = delta.new_file().path();
+        let Some(file) = new_path.or(old_path).and_then(DependencyFile::of) else {
+            continue;
+        };
+        let old = file.parse(&blob_text(repo, base, old_path).unwrap_or_default());
+        let new = file.parse(&blob_text(repo, head, new_path).unwrap_or_default());
+        let deltas = if file.is_lockfile() {
+            &mut locks
+        } else {
+            &mut manifests
+        };
+        for name in old.keys().chain(new.keys()) {
+            let before = old.get(name).cloned().unwrap_or_default();
+            let after = new.get(name).cloned().unwrap_or_default();
+            if before != after {
+                let (removed, added) = deltas.entry((file.ecosystem(), name.clone())).or_default();
+                removed.extend(before);
+                added.extend(after);
+            }
+        }
+    }
+
+    let mut changes: Vec<DependencyChange> = manifests
+        .iter()
+        .filter_map(|(key @ (ecosystem, name), requirements)| {
+            let (removed, added) = locks.get(key).unwrap_or(requirements);
+            DependencyChange::new(*ecosystem, name, removed, added, true)
+        })
+        .collect();
+    let lock_only: Vec<DependencyChange> = locks
+        .iter()
+        .filter(|(key, _)| !manifests.contains_key(*key))
+        .filter_map(|((ecosystem, name), (removed, added))| {
+            DependencyChange::new(*ecosystem, name, removed, added, false)
+        })
+        .collect();
+    let omitted = lock_only.len().saturating_sub(MAX_LOCKFILE_ONLY_CHANGES);
+    changes.extend(lock_only.into_iter().take(MAX_LOCKFILE_ONLY_CHANGES));
+    debug!(
+        "Found {} dependency changes ({omitted} lockfile-only changes omitted)",
+        changes.len()
+    );
+    (changes, omitted)
+}
+
+/// Replace lockfile patches with a pointer to the parsed dependency changes, which say the same
+/// thing without thousands of lines of hashes.
+pub fn condense_lockfiles(summary: &mut DiffSummary) {
+    for entry in summary.added.iter_mut().chain(summary.modified.iter_mut()) {
+        if DependencyFile::of(&entry.path).is_some_and(DependencyFile::is_lockfile) {
+            entry.patch = "Lockfile updated; see dependency_changes".into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_direct_and_lockfile_only_changes() {
+        let manifest = |tokio: &str, extra: &str| {
+            format!(
+                "[package]\nname = \"app\"\n\n[dependencies]\ntokio = {{ version = \"{tokio}\", features = [\"full\"] }}\n{extra}\n[target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n"
+            )
+        };
+        let lock = |packages: &[(&str, &str)]| {
+            packages
+                .iter()
+                .map(|(name, version)| {
+                    format!("[[package]]\nname = \"{name}\"\nversion = \"{version}\"\n\n")
+                })
+                .collect::<String>()
+        };
+        let old_manifest = DependencyFile::CargoManifest.parse(&manifest("1.40", "anyhow = \"1\""));
+        let new_manifest = DependencyFile::CargoManifest.parse(&manifest(
+            "1.43",
+            "serde = { workspace = true }\nparser = { path = \"../parser\" }",
+        ));
+        assert_eq!(old_manifest["libc"], BTreeSet::from(["0.2".to_string()]));
+        assert_eq!(
+            new_manifest["serde"],
+            BTreeSet::from(["workspace".to_string()])
+        );
+        assert_eq!(
+            new_manifest["parser"],
+            BTreeSet::from(["path ../parser".to_string()])
+        );
+
+        let old_lock = DependencyFile::CargoLock.parse(&lock(&[
+            ("tokio", "1.40.0"),
+            ("anyhow", "1.0.95"),
+            ("mio", "1.0.2"),
+            ("syn", "1.0.109"),
+            ("syn", "2.0.90"),
+        ]));
+        let new_lock = DependencyFile::CargoLock.parse(&lock(&[
+            ("tokio", "1.43.0"),
+            ("mio", "1.0.3"),
+            ("syn", "2.0.90"),
+        ]));
+        assert_eq!(old_lock["syn"].len(), 2);
+
+        let tokio = DependencyChange::new(
+            Ecosystem::Cargo,
+            "tokio",
+            &old_lock["tokio"],
+            &new_lock["tokio"],
+            true,
+        )
+        .unwrap();
+        assert_eq!(tokio.describe(), "Updated tokio 1.40.0 → 1.43.0");
+        let syn = DependencyChange::new(
+            Ecosystem::Cargo,
+            "syn",
+            &old_lock["syn"],
+            &new_lock["syn"],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            (syn.change, syn.from.as_deref(), syn.to),
+            (DependencyChangeKind::Removed, Some("1.0.109"), None)
+        );
+        assert!(
+            DependencyChange::new(
+                Ecosystem::Cargo,
+                "libc",
+                &old_manifest["libc"],
+                &new_manifest["libc"],
+                true
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn reads_npm_manifests_and_lockfiles() {
+        let manifest = DependencyFile::NpmManifest.parse(
+            r#"{ "name": "web", "dependencies": { "react": "^19.0.0" }, "devDependencies": { "vite": "^6.0.0" } }"#,
+        );
+        assert_eq!(manifest["react"], BTreeSet::from(["^19.0.0".to_string()]));
+        assert!(manifest.contains_key("vite"));
+
+        let v3 = DependencyFile::NpmLock.parse(
+            r#"{ "lockfileVersion": 3, "packages": {
+                "": { "name": "web", "version": "1.0.0" },
+                "node_modules/react": { "version": "19.0.0" },
+                "node_modules/@scope/pkg/node_modules/semver": { "version": "7.6.3" }
+            } }"#,
+        );
+        assert_eq!(v3.keys().collect::<Vec<_>>(), vec!["react", "semver"]);
+        let v1 = DependencyFile::NpmLock.parse(
+            r#"{ "lockfileVersion": 1, "dependencies": {
+                "react": { "version": "18.3.1", "dependencies": { "loose-envify": { "version": "1.4.0" } } }
+            } }"#,
+        );
+        assert_eq!(v1["loose-envify"], BTreeSet::from(["1.4.0".to_string()]));
+    }
+}
@@ -9,6 +9,14 @@ use tracing::error;
 
 use crate::AppResult;
 use crate::error::AppError;
+use crate::warnings::{self, WarningKind};
+
+/// Patches larger than this are replaced with a short description, since they are almost always
+/// generated files (lockfiles, minified bundles, fixtures) that would swamp prompts.
+const MAX_PATCH_BYTES: usize = 100 * 1024;
+
+/// First line of every Git LFS pointer file.
+const LFS_POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
 
 /// Captures the source and destination paths for rename/copy deltas.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
@@ -205,6 +213,105 @@ fn get_filename(delta: &DiffDelta) -> PathBuf {
 
 type PatchCollector = HashMap<PathBuf, (String, Option<(u32, u32, u32, u32)>)>;
 
+/// Render a byte count with binary units, e.g. `1.5 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Old and new object sizes from a patch of a Git LFS pointer file, or `None` if the patch is
+/// not an LFS pointer.
+fn lfs_pointer_sizes(patch: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let mut is_pointer = false;
+    let (mut old, mut new) = (None, None);
+    for line in patch.lines() {
+        let (origin, content) = match line.chars().next() {
+            Some(origin @ ('+' | '-' | ' ')) => (origin, &line[1..]),
+            _ => continue,
+        };
+        if content.trim() == LFS_POINTER_VERSION {
+            is_pointer = true;
+        }
+        if let Some(size) = content
+            .strip_prefix("size ")
+            .and_then(|s| s.trim().parse().ok())
+        {
+            match origin {
+                '-' => old = Some(size),
+                '+' => new = Some(size),
+                _ => (old, new) = (Some(size), Some(size)),
+            }
+        }
+    }
+    is_pointer.then_some((old, new))
+}
+
+/// Replace patches that are useless to read with a one-line description.
+///
+/// Git LFS pointer changes become "LFS object updated (size X → Y)", binary files and patches
+/// over `MAX_PATCH_BYTES` are summarized by size. Returns `None` for ordinary patches.
+pub fn condense_patch(patch: &str) -> Option<String> {
+    if let Some(sizes) = lfs_pointer_sizes(patch) {
+        return Some(match sizes {
+            (Some(old), Some(new)) if old == new => {
+                format!("LFS object updated (size {})", format_size(new))
+            }
+            (Some(old), Some(new)) => format!(
+                "LFS object updated (size {} → {})",
+                format_size(old),
+                format_size(new)
+            ),
+            (None, Some(new)) => format!("LFS object added (size {})", format_size(new)),
+            (Some(old), None) => format!("LFS object removed (size {})", format_size(old)),
+            (None, None) => "LFS object updated".to_string(),
+        });
+    }
+    if patch.lines().any(|line| line.starts_with("Binary files ")) {
+        return Some("Binary file changed".to_string());
+    }
+    if patch.len() > MAX_PATCH_BYTES {
+        let (added, removed) =
+            patch
+                .lines()
+                .fold((0, 0), |(a, r), line| match line.chars().next() {
+                    Some('+') if !line.starts_with("+++") => (a + 1, r),
+                    Some('-') if !line.starts_with("---") => (a, r + 1),
+                    _ => (a, r),
+                });
+        return Some(format!(
+            "Large change omitted (+{added}/-{removed} lines, {} patch)",
+            format_size(patch.len() as u64)
+        ));
+    }
+    None
+}
+
+/// Build a `DiffWithPatch`, condensing LFS pointers, binaries, and oversized patches.
+fn with_condensed_patch(path: PathBuf, patch: String) -> DiffWithPatch {
+    if patch.len() > MAX_PATCH_BYTES {
+        warnings::record(
+            WarningKind::Truncated,
+            "git",
+            format!(
+                "The {} patch for {} was reduced to line counts",
+                format_size(patch.len() as u64),
+                path.display()
+            ),
+        );
+    }
+    let patch = condense_patch(&patch).unwrap_or(patch);
+    DiffWithPatch { path, patch }
+}
+
 /// Generate a `DiffSummary` from a git2 `Diff`, capturing patches and path sets.
 #[tracing::instrument(
     name = "Generating a summary of all changes",
@@ -282,20 +389,75 @@ pub fn get_diff_summary<P: AsRef<Path> + std::fmt::Debug>(
 
     summary.added = added_patches
         .into_iter()
-        .map(|(path, (patch, _))| DiffWithPatch { path, patch })
+        .map(|(path, (patch, _))| with_condensed_patch(path, patch))
         .collect();
     summary.modified = modified_patches
         .into_iter()
-        .map(|(path, (patch, _))| DiffWithPatch { path, patch })
+        .map(|(path, (patch, _))| with_condensed_patch(path, patch))
         .collect();
     summary.untracked = untracked_patches
         .into_iter()
-        .map(|(path, (patch, _))| DiffWithPatch { path, patch })
+        .map(|(path, (patch, _))| with_condensed_patch(path, patch))
         .collect();
 
     Ok(summary)
 }
 
+/// Summarize which paths changed without loading any file contents.
+///
+/// Used when patches cannot be generated, e.g. because a partial clone has not fetched the
+/// blobs. Added, modified, and untracked entries carry empty patches.
+pub fn get_diff_paths<P: AsRef<Path> + std::fmt::Debug>(repo_path: P, diff: &Diff) -> DiffSummary {
+    let mut summary = DiffSummary {
+        repo_path: repo_path.as_ref().to_path_buf(),
+        unmodified: HashSet::new(),
+        added: Vec::new(),
+        deleted: HashSet::new(),
+        modified: Vec::new(),
+        renamed: HashSet::new(),
+        copied: HashSet::new(),
+        untracked: Vec::new(),
+        typechange: HashSet::new(),
+        unreadable: HashSet::new(),
+        conflicted: HashSet::new(),
+    };
+    for delta in diff.deltas() {
+        let path = get_filename(&delta);
+        let without_patch = || DiffWithPatch {
+            path: path.clone(),
+            patch: String::new(),
+        };
+        match delta.status() {
+            Delta::Added => summary.added.push(without_patch()),
+            Delta::Deleted => {
+                summary.deleted.insert(path);
+            }
+            Delta::Modified => summary.modified.push(without_patch()),
+            Delta::Renamed => {
+                summary.renamed.insert(DiffFromTo::from_delta(&delta));
+            }
+            Delta::Copied => {
+                summary.copied.insert(DiffFromTo::from_delta(&delta));
+            }
+            Delta::Untracked => summary.untracked.push(without_patch()),
+            Delta::Typechange => {
+                summary.typechange.insert(path);
+            }
+            Delta::Unreadable => {
+                summary.unreadable.insert(path);
+            }
+            Delta::Conflicted => {
+                summary.conflicted.insert(path);
+            }
+            Delta::Unmodified => {
+                summary.unmodified.insert(path);
+            }
+            Delta::Ignored => {}
+        }
+    }
+    summary
+}
+
 fn line_in_range(
     start: Option<u32>,
     end: Option<u32>,
@@ -398,7 +560,7 @@ pub fn get_patch<P: AsRef<Path> + std::fmt::Debug>(
                     true
                 });
 
-                return Ok(rendered);
+                return Ok(condense_patch(&rendered).unwrap_or(rendered));
             }
             Ok(None) => {
                 // Binary or otherwise unavailable.
@@ -425,3 +587,41 @@ pub fn get_patch<P: AsRef<Path> + std::fmt::Debug>(
         path.as_ref().display()
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_lfs_pointer_changes() {
+        let updated = "diff --git a/model.bin b/model.bin\n@@ -1,3 +1,3 @@\n version https://git-lfs.github.com/spec/v1\n-oid sha256:aaaa\n-size 1048576\n+oid sha256:bbbb\n+size 1572864\n";
+        assert_eq!(
+            condense_patch(updated).as_deref(),
+            Some("LFS object updated (size 1.0 MiB → 1.5 MiB)")
+        );
+
+        let added = "+version https://git-lfs.github.com/spec/v1\n+oid sha256:cccc\n+size 512\n";
+        assert_eq!(
+            condense_patch(added).as_deref(),
+            Some("LFS object added (size 512 B)")
+        );
+    }
+
+    #[test]
+    fn condenses_binary_and_oversized_patches_only() {
+        assert_eq!(
+            condense_patch("diff --git a/x.png b/x.png\nBinary files a/x.png and b/x.png differ\n")
+                .as_deref(),
+            Some("Binary file changed")
+        );
+
+        let lockfile = format!(
+            "+++ b/Cargo.lock\n{}",
+            "+line\n".repeat(MAX_PATCH_BYTES / 4)
+        );
+        let condensed = condense_patch(&lockfile).unwrap();
+        assert!(condensed.starts_with("Large change omitted (+25600/-0 lines"));
+
+        assert_eq!(condense_patch("@@ -1 +1 @@\n-old\n+new\n"), None);
+    }
+}
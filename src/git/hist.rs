@@ -1,39 +1,23 @@
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-use async_openai::{Client, config::Config};
-use git2::{Commit, DiffOptions, Oid, Repository, Revwalk, Status, StatusOptions, Tree};
+use git2::DiffOptions;
 use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
-use tracing::{debug, error, info, trace};
+use tracing::debug;
 
 use crate::AppResult;
-use crate::ai::commit_message::generate_commit_message;
-use crate::git::diff::{DiffSummary, get_diff_summary};
+use crate::git::deps::DependencyChange;
+use crate::git::diff::DiffSummary;
+use crate::git::read_only::ReadOnlyRepoAccess;
 use crate::shell::ShellHistoryEntry;
-use crate::time_utils::{past_ts, timestamp_secs_to_nsecs, unix_time_nsec_to_datetime};
-
-fn get_status_opts() -> StatusOptions {
-    let mut opts = StatusOptions::new();
-    opts.include_untracked(true)
-        .include_ignored(false)
-        .include_unmodified(true)
-        .exclude_submodules(true)
-        .recurse_untracked_dirs(true)
-        .disable_pathspec_match(false)
-        .recurse_ignored_dirs(false)
-        .renames_head_to_index(true)
-        .renames_index_to_workdir(true)
-        .sort_case_sensitively(false)
-        .sort_case_insensitively(true)
-        .renames_from_rewrites(true)
-        .no_refresh(false)
-        .update_index(true)
-        .include_unreadable(false);
-    opts
-}
+use crate::time_utils::past_ts;
+use crate::warnings::{self, WarningKind};
 
 /// Diff options for generating unified patches with metadata for our summaries.
-fn get_diff_opts() -> DiffOptions {
+///
+/// `update_index` stays off so diffing never rewrites the index's stat cache.
+pub(super) fn get_diff_opts() -> DiffOptions {
     let mut opts = DiffOptions::new();
     opts.reverse(false)
         .include_ignored(false)
@@ -48,7 +32,7 @@ fn get_diff_opts() -> DiffOptions {
         .ignore_case(false)
         .skip_binary_check(false)
         .enable_fast_untracked_dirs(false)
-        .update_index(true)
+        .update_index(false)
         .include_unreadable(true)
         .include_unreadable_as_untracked(false)
         .force_text(false)
@@ -66,27 +50,11 @@ fn get_diff_opts() -> DiffOptions {
     opts
 }
 
-/// Get HEAD tree and parents, or an empty tree when HEAD is unborn.
-#[tracing::instrument(name = "Fetching git tree", level = "info", skip(repo))]
-fn head_tree_and_parents<'b, 'a: 'b>(
-    repo: &'a Repository,
-) -> AppResult<(Tree<'b>, Vec<Commit<'b>>)> {
-    if let Ok(head) = repo.head()
-        && let Some(oid) = head.target()
-    {
-        let parent = repo.find_commit(oid)?;
-        let tree = parent.tree()?;
-        return Ok((tree, vec![parent]));
-    }
-    // Unborn HEAD: create an empty tree and no parents.
-    let builder = repo.treebuilder(None)?;
-    let empty_tree_id = builder.write()?;
-    let empty_tree = repo.find_tree(empty_tree_id)?;
-    Ok((empty_tree, Vec::new()))
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitMeta {
+    /// Full commit id.
+    #[serde(default)]
+    pub id: String,
     pub summary: String,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub body: Option<String>,
@@ -95,264 +63,262 @@ pub struct CommitMeta {
     pub branches: Vec<String>,
 }
 
-/// Per-repository history bundle: diff summary plus commit metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GitRepoHistory {
-    pub diff: DiffSummary,
-    pub commits: Vec<CommitMeta>,
+/// A local branch with commits that have not reached its upstream (or any remote) yet.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnpushedBranch {
+    pub name: String,
+    /// Upstream the branch tracks. `None` when it has never been pushed.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Summaries of the unpushed commits, newest first.
+    pub commits: Vec<String>,
 }
 
-/// Collect branch tips for the repository to ensure revwalk covers all local branches.
-#[tracing::instrument(name = "Fetching git branches", level = "info", skip(repo))]
-fn collect_branch_tips(repo: &Repository) -> Vec<(String, Oid)> {
-    let mut branch_tips = Vec::new();
-    if let Ok(branches) = repo.branches(Some(git2::BranchType::Local)) {
-        for branch in branches.flatten() {
-            if let Ok(name_opt) = branch.0.name()
-                && let (Some(name), Some(target)) = (name_opt, branch.0.get().target())
-            {
-                branch_tips.push((name.to_string(), target));
-            }
+impl UnpushedBranch {
+    /// One-line description used in the "pending review" section of the summary.
+    pub fn describe(&self, repo: &std::path::Path) -> String {
+        let repo = repo
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_else(|| repo.to_string_lossy());
+        match &self.upstream {
+            Some(upstream) => format!(
+                "{repo}: {} is {} commit(s) ahead of {upstream}",
+                self.name, self.ahead
+            ),
+            None => format!(
+                "{repo}: {} has {} commit(s) not on any remote",
+                self.name, self.ahead
+            ),
         }
     }
-    branch_tips
 }
 
-/// Prepare a revwalk with all branch tips (or HEAD) pushed.
-#[tracing::instrument(
-    name = "Walking git revision history",
-    level = "info",
-    skip(repo, branch_tips)
-)]
-fn init_revwalk<'repo>(
-    repo: &'repo Repository,
-    branch_tips: &[(String, Oid)],
-) -> Option<Revwalk<'repo>> {
-    let mut revwalk = repo.revwalk().ok()?;
-    if branch_tips.is_empty() {
-        revwalk.push_head().ok()?;
-    } else {
-        for (_, tip) in branch_tips {
-            let _ = revwalk.push(*tip);
-        }
-    }
-    Some(revwalk)
+/// How a piece of parked work was left behind.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParkedKind {
+    /// A `git stash` entry.
+    Stash,
+    /// A commit recorded in the HEAD reflog that no branch or tag points to any more, e.g. work
+    /// committed on a detached HEAD or left behind by a reset.
+    Unreferenced,
 }
 
-/// Collect commits in the last `past_date` window, tracking the oldest commit found.
-#[tracing::instrument(
-    name = "Collecting recent git commits",
-    level = "info",
-    skip(repo, branch_tips)
-)]
-fn collect_recent_commits<'repo>(
-    repo: &'repo Repository,
-    branch_tips: &[(String, Oid)],
-    past_date: OffsetDateTime,
-) -> AppResult<(Vec<CommitMeta>, Option<Commit<'repo>>)> {
-    let revwalk = match init_revwalk(repo, branch_tips) {
-        Some(rw) => rw,
-        None => return Ok((Vec::new(), None)),
-    };
+/// Half-finished work created during the window that is not on any branch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParkedWork {
+    pub kind: ParkedKind,
+    pub message: String,
+    /// Abbreviated id of the stash or commit.
+    pub commit: String,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub timestamp: OffsetDateTime,
+}
 
-    let mut daily_commits: Vec<CommitMeta> = Vec::new();
-    let mut oldest_commit: Option<Commit> = None;
+/// Per-repository history bundle: diff summary plus commit metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitRepoHistory {
+    pub diff: DiffSummary,
+    pub commits: Vec<CommitMeta>,
+    /// Local branches whose work has not been pushed yet.
+    #[serde(default)]
+    pub unpushed: Vec<UnpushedBranch>,
+    /// Stashes and unreferenced commits created during the window.
+    #[serde(default)]
+    pub parked: Vec<ParkedWork>,
+    /// Crates and npm packages added, removed, or updated, parsed from manifests and lockfiles.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub dependency_changes: Vec<DependencyChange>,
+    /// URL of the `origin` remote (or the first remote when there is no `origin`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub remote_url: Option<String>,
+    /// Default branch of the remote, e.g. `main`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub default_branch: Option<String>,
+    /// Reasons the collected history is incomplete, e.g. a shallow or partial clone.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub limitations: Vec<String>,
+}
 
-    for oid in revwalk.flatten() {
-        debug!("Found git commit {} in {:?}", oid, repo.path());
-        let commit = repo.find_commit(oid)?;
-        trace!("Found commit object: {:?}", commit);
+impl GitRepoHistory {
+    /// Browser URL of the repository, when the remote is hosted somewhere with a web UI.
+    pub fn web_url(&self) -> Option<String> {
+        self.remote_url.as_deref().and_then(super::remote::web_url)
+    }
 
-        let time = commit.time();
-        let timestamp = unix_time_nsec_to_datetime(timestamp_secs_to_nsecs(time.seconds()));
-        if timestamp < past_date {
-            // We walked past the window; stop to avoid unnecessary work.
-            break;
+    /// Name to refer to the repository by: `owner/repo` from the remote, else the directory name.
+    pub fn project_name(&self) -> String {
+        if let Some(web_url) = self.web_url()
+            && let Some(name) = super::remote::project_name(&web_url)
+        {
+            return name.to_string();
         }
+        let path = &self.diff.repo_path;
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    }
 
-        let message = commit.message().unwrap_or_default().to_string();
-        let mut branches = Vec::new();
-        for (name, tip) in branch_tips {
-            if repo.graph_descendant_of(*tip, commit.id()).unwrap_or(false) {
-                branches.push(name.clone());
-            }
-        }
+    /// Whether the project name or path contains any of `patterns`, which must be lowercase.
+    pub fn matches_any(&self, patterns: &[String]) -> bool {
+        let project = self.project_name().to_lowercase();
+        // Windows paths are matched with forward slashes, so `work/api` works everywhere.
+        let path = self
+            .diff
+            .repo_path
+            .to_string_lossy()
+            .to_lowercase()
+            .replace('\\', "/");
+        patterns
+            .iter()
+            .any(|pattern| project.contains(pattern.as_str()) || path.contains(pattern.as_str()))
+    }
 
-        if oldest_commit
-            .as_ref()
-            .map(|c| commit.time().seconds() < c.time().seconds())
-            .unwrap_or(true)
-        {
-            oldest_commit = Some(commit.clone());
+    /// Link to `commit` on the repository's forge.
+    pub fn commit_url(&self, commit: &CommitMeta) -> Option<String> {
+        if commit.id.is_empty() {
+            return None;
         }
+        Some(super::remote::commit_url(&self.web_url()?, &commit.id))
+    }
+}
 
-        let (summary, body) = if let Some(idx) = message.find('\n') {
-            (
-                message[..idx].trim().to_string(),
-                Some(message[idx + 1..].trim().to_string()),
-            )
-        } else {
-            (message.clone(), None)
-        };
+/// Which repositories a run reports on, from `--repo` and `--exclude-repo`.
+///
+/// A pattern matches when the repository's project name or path contains it, ignoring case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
 
-        daily_commits.push(CommitMeta {
-            summary,
-            body,
-            timestamp,
-            branches,
-        });
+impl RepoFilter {
+    /// Keep only repositories matching one of `include` (all when empty), and none of `exclude`.
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        let lowercase = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| pattern.trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect()
+        };
+        RepoFilter {
+            include: lowercase(include),
+            exclude: lowercase(exclude),
+        }
     }
 
-    Ok((daily_commits, oldest_commit))
+    /// Whether `repo` is reported.
+    pub fn allows(&self, repo: &GitRepoHistory) -> bool {
+        (self.include.is_empty() || repo.matches_any(&self.include))
+            && !repo.matches_any(&self.exclude)
+    }
 }
 
-/// Commit staged and/or working directory changes into the repository so history is current.
-#[tracing::instrument(name = "Checking repo status", level = "info", skip(client, repo))]
-async fn check_repo_status<C: Config>(client: &Client<C>, repo: &Repository) -> AppResult<()> {
-    let mut opts = get_status_opts();
-
-    let statuses = repo.statuses(Some(&mut opts))?;
-    let mut staged_changes = false;
-    let mut working_dir_changes = false;
-    for entry in statuses.iter() {
-        let s = entry.status();
-        // look for flags that indicate working‐directory changes (vs just staged)
-        if s.intersects(
-            Status::WT_MODIFIED
-                | Status::WT_DELETED
-                | Status::WT_NEW
-                | Status::WT_TYPECHANGE
-                | Status::WT_RENAMED,
-        ) {
-            // There are working-directory changes
-            trace!("Working directory has changes in: {:?}", entry.path());
-            working_dir_changes = true;
+/// Rewrite `/c/src`, `/cygdrive/c/src`, and `c:\src` as `C:\src`, or `None` for a path without a
+/// drive letter.
+fn windows_path(path: &str) -> Option<String> {
+    let (drive, rest) = match path
+        .strip_prefix("/cygdrive/")
+        .or_else(|| path.strip_prefix('/'))
+    {
+        Some(msys) => {
+            let mut chars = msys.chars();
+            let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+            let rest = chars.as_str();
+            if !rest.is_empty() && !rest.starts_with('/') {
+                return None;
+            }
+            (drive, rest)
         }
-        if s.intersects(
-            Status::INDEX_MODIFIED
-                | Status::INDEX_DELETED
-                | Status::INDEX_NEW
-                | Status::INDEX_TYPECHANGE
-                | Status::INDEX_RENAMED,
-        ) {
-            // There are staged changes
-            trace!("Staged changes in: {:?}", entry.path());
-            staged_changes = true;
+        None => {
+            let mut chars = path.chars();
+            let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+            (drive, chars.as_str().strip_prefix(':')?)
         }
+    };
+    let rest = rest.replace('/', "\\");
+    Some(format!(
+        "{}:\\{}",
+        drive.to_ascii_uppercase(),
+        rest.trim_start_matches('\\')
+    ))
+}
+
+/// The directory a shell command ran in, in a form git can open.
+///
+/// On Windows, Git Bash and Cygwin record `/c/Users/me/src` where PowerShell records
+/// `C:\Users\me\src`, with the drive letter in either case; all of them become `C:\Users\me\src`
+/// so the repository is found, and checked only once.
+fn repo_directory(directory: &Path) -> PathBuf {
+    if cfg!(windows)
+        && let Some(path) = directory.to_str().and_then(windows_path)
+    {
+        return PathBuf::from(path);
     }
-    if !staged_changes && !working_dir_changes {
-        debug!("No changes to commit.");
-        return Ok(());
-    }
-    if staged_changes {
-        info!(
-            "Committing staged directory changes for {}...",
-            repo.path().display()
-        );
-        let (head_tree, parents) = head_tree_and_parents(repo)?;
-        let mut index = repo.index()?;
-        let diff =
-            repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut get_diff_opts()))?;
-        let tree_id = index.write_tree()?;
-        let tree = repo.find_tree(tree_id)?;
-        let commit_message = generate_commit_message(client, &diff, repo).await?;
-        let sig = repo.signature()?;
-        repo.commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            commit_message.to_string().as_str(),
-            &tree,
-            &parents.iter().collect::<Vec<&Commit>>(),
-        )?;
-        info!("Staged changes committed.");
-    }
-    if working_dir_changes {
-        info!(
-            "Committing working directory changes for {}...",
-            repo.path().display()
-        );
-        let (head_tree, parents) = head_tree_and_parents(repo)?;
-        let mut index = repo.index()?;
-        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
-        index.write()?;
-        let diff =
-            repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut get_diff_opts()))?;
-        let commit_message = generate_commit_message(client, &diff, repo).await?;
-        let tree_id = index.write_tree()?;
-        let tree = repo.find_tree(tree_id)?;
-        let sig = repo.signature()?;
-        repo.commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            commit_message.to_string().as_str(),
-            &tree,
-            &parents.iter().collect::<Vec<&Commit>>(),
-        )?;
-        info!("Working directory changes committed.");
-    }
-    Ok(())
+    directory.to_path_buf()
 }
 
 /// Collect git history for repositories seen in shell history over the specified duration.
-#[tracing::instrument(
-    name = "Collecting git history",
-    level = "info",
-    skip(client, shell_history)
-)]
-pub async fn get_git_history<C: Config>(
-    client: &Client<C>,
-    shell_history: &Vec<ShellHistoryEntry>,
+///
+/// Repositories are only ever opened through `ReadOnlyRepoAccess`, so collecting never commits
+/// or touches the index. See `git::commit` for the opt-in auto-commit pass.
+#[tracing::instrument(name = "Collecting git history", level = "info", skip(shell_history))]
+pub async fn get_git_history(
+    shell_history: &[ShellHistoryEntry],
     duration: &Duration,
 ) -> AppResult<Vec<GitRepoHistory>> {
     let mut visited = HashSet::new();
     let past_date = past_ts(duration);
     let mut git_history = Vec::new();
     for entry in shell_history {
-        if visited.contains(&entry.directory) {
+        let directory = repo_directory(&entry.directory);
+        if !visited.insert(directory.clone()) {
             continue;
         }
-        visited.insert(entry.directory.clone());
-        if let Ok(repo) = Repository::open(&entry.directory) {
-            match check_repo_status(client, &repo).await {
-                Ok(_) => debug!("Repository status checked for {:?}", entry.directory),
-                Err(e) => error!(
-                    "Failed to check repository status for {}: {}. Continuing without committing changes.",
-                    entry.directory.display(),
-                    e
+        if let Ok(repo) = ReadOnlyRepoAccess::open(&directory) {
+            debug!("Checking git history for repository in {:?}", directory);
+            match repo.history_since(past_date) {
+                Ok(Some(history)) => git_history.push(history),
+                Ok(None) => debug!("No recent commits in {:?}", directory),
+                Err(e) => warnings::record(
+                    WarningKind::Skipped,
+                    "git",
+                    format!(
+                        "Failed to read git history for {}: {}",
+                        directory.display(),
+                        e
+                    ),
                 ),
-            };
-            // Refresh state in case check_repo_status created new commits
-            if let Err(e) = repo.index().and_then(|mut idx| idx.read(true)) {
-                error!("Failed to refresh index for {:?}: {}", entry.directory, e);
-            }
-            debug!(
-                "Checking git history for repository in {:?}",
-                entry.directory
-            );
-            let branch_tips = collect_branch_tips(&repo);
-            let (daily_commits, oldest_commit) =
-                collect_recent_commits(&repo, &branch_tips, past_date)?;
-
-            if let Some(commit) = oldest_commit {
-                let head = repo.head()?;
-                let head_tree = head.peel_to_tree()?;
-                let commit_tree = commit.tree()?;
-                let diff = repo.diff_tree_to_tree(
-                    Some(&commit_tree),
-                    Some(&head_tree),
-                    Some(&mut get_diff_opts()),
-                )?;
-                let repo_path = repo.path().parent().unwrap();
-                if let Ok(diff_summary) = get_diff_summary(repo_path, &diff) {
-                    git_history.push(GitRepoHistory {
-                        diff: diff_summary,
-                        commits: daily_commits.clone(),
-                    });
-                }
             }
         }
     }
     Ok(git_history)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_msys_and_lowercase_drive_paths() {
+        assert_eq!(
+            windows_path("/c/Users/me/src/api").as_deref(),
+            Some("C:\\Users\\me\\src\\api")
+        );
+        assert_eq!(
+            windows_path("/cygdrive/d/work").as_deref(),
+            Some("D:\\work")
+        );
+        assert_eq!(
+            windows_path("c:\\Users\\me").as_deref(),
+            Some("C:\\Users\\me")
+        );
+        assert_eq!(windows_path("/c").as_deref(), Some("C:\\"));
+        assert_eq!(windows_path("/usr/local/src"), None);
+        assert_eq!(windows_path("src/api"), None);
+    }
+}
@@ -1,6 +1,18 @@
+/// Opt-in auto-commit of pending changes. The only module that writes to repositories.
+pub(crate) mod commit;
+
+/// Cargo and npm dependency changes parsed from manifests and lockfiles.
+pub mod deps;
+
 /// Git diff helpers and summary generation.
 pub(crate) mod diff;
 
-/// Git history collection and staging/state helpers.
+/// Git history types and read-only collection.
 pub mod hist;
 pub(crate) use hist::*;
+
+/// Read-only repository access used by collectors.
+pub(crate) mod read_only;
+
+/// Remote URL normalisation and forge commit links.
+pub(crate) mod remote;
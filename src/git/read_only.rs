@@ -0,0 +1,791 @@
+use std::path::Path;
+
+use git2::{BranchType, Commit, Oid, Repository, Revwalk};
+use time::OffsetDateTime;
+use tracing::{debug, trace, warn};
+
+use super::deps::{condense_lockfiles, dependency_changes};
+use super::diff::{get_diff_paths, get_diff_summary};
+use super::hist::{
+    CommitMeta, GitRepoHistory, ParkedKind, ParkedWork, UnpushedBranch, get_diff_opts,
+};
+use crate::AppResult;
+use crate::time_utils::{timestamp_secs_to_nsecs, unix_time_nsec_to_datetime};
+use crate::warnings::{self, WarningKind};
+
+/// Abbreviated (7 character) form of a commit id.
+fn short_id(oid: Oid) -> String {
+    oid.to_string().chars().take(7).collect()
+}
+
+/// Read-only view of a repository used by every collection path.
+///
+/// The wrapped `Repository` is private to this module and only history-reading operations are
+/// exposed, so collectors cannot commit, stage, or otherwise mutate a repository. Writing is
+/// reserved for `git::commit`, which works on a plain `Repository` and is only reached from
+/// `summarize --auto-commit`.
+pub struct ReadOnlyRepoAccess {
+    repo: Repository,
+}
+
+impl ReadOnlyRepoAccess {
+    /// Open the repository at `path` for reading.
+    pub fn open<P: AsRef<Path>>(path: P) -> AppResult<Self> {
+        Ok(Self {
+            repo: Repository::open(path)?,
+        })
+    }
+
+    /// Path of the repository root (the parent of the `.git` directory).
+    pub fn repo_path(&self) -> &Path {
+        let git_dir = self.repo.path();
+        git_dir.parent().unwrap_or(git_dir)
+    }
+
+    /// URL of the `origin` remote, falling back to the first configured remote.
+    pub fn remote_url(&self) -> Option<String> {
+        let remotes = self.repo.remotes().ok()?;
+        let name = if remotes.iter().flatten().any(|name| name == "origin") {
+            "origin".to_string()
+        } else {
+            remotes.iter().flatten().next()?.to_string()
+        };
+        self.repo.find_remote(&name).ok()?.url().map(str::to_string)
+    }
+
+    /// Default branch of the remote, read from `refs/remotes/<remote>/HEAD` as set by clone or
+    /// `git remote set-head`. Falls back to a local `main` or `master` branch.
+    pub fn default_branch(&self) -> Option<String> {
+        let remotes = self.repo.remotes().ok()?;
+        for remote in remotes.iter().flatten() {
+            let head = format!("refs/remotes/{remote}/HEAD");
+            if let Ok(reference) = self.repo.find_reference(&head)
+                && let Some(target) = reference.symbolic_target()
+                && let Some(branch) = target.strip_prefix(&format!("refs/remotes/{remote}/"))
+            {
+                return Some(branch.to_string());
+            }
+        }
+        ["main", "master"]
+            .into_iter()
+            .find(|name| self.repo.find_branch(name, BranchType::Local).is_ok())
+            .map(str::to_string)
+    }
+
+    /// Collect branch tips for the repository to ensure revwalk covers all local branches.
+    #[tracing::instrument(name = "Fetching git branches", level = "info", skip(self))]
+    pub fn branch_tips(&self) -> Vec<(String, Oid)> {
+        let mut branch_tips = Vec::new();
+        if let Ok(branches) = self.repo.branches(Some(git2::BranchType::Local)) {
+            for branch in branches.flatten() {
+                if let Ok(name_opt) = branch.0.name()
+                    && let (Some(name), Some(target)) = (name_opt, branch.0.get().target())
+                {
+                    branch_tips.push((name.to_string(), target));
+                }
+            }
+        }
+        branch_tips
+    }
+
+    /// Prepare a revwalk with all branch tips (or HEAD) pushed.
+    #[tracing::instrument(
+        name = "Walking git revision history",
+        level = "info",
+        skip(self, branch_tips)
+    )]
+    fn revwalk(&self, branch_tips: &[(String, Oid)]) -> Option<Revwalk<'_>> {
+        let mut revwalk = self.repo.revwalk().ok()?;
+        if branch_tips.is_empty() {
+            revwalk.push_head().ok()?;
+        } else {
+            for (_, tip) in branch_tips {
+                let _ = revwalk.push(*tip);
+            }
+        }
+        Some(revwalk)
+    }
+
+    /// Collect commits in the last `past_date` window, tracking the oldest commit found.
+    #[tracing::instrument(
+        name = "Collecting recent git commits",
+        level = "info",
+        skip(self, branch_tips)
+    )]
+    pub fn recent_commits(
+        &self,
+        branch_tips: &[(String, Oid)],
+        past_date: OffsetDateTime,
+    ) -> AppResult<(Vec<CommitMeta>, Option<Commit<'_>>)> {
+        let revwalk = match self.revwalk(branch_tips) {
+            Some(rw) => rw,
+            None => return Ok((Vec::new(), None)),
+        };
+
+        let mut daily_commits: Vec<CommitMeta> = Vec::new();
+        let mut oldest_commit: Option<Commit> = None;
+
+        for oid in revwalk.flatten() {
+            debug!("Found git commit {} in {:?}", oid, self.repo.path());
+            let commit = self.repo.find_commit(oid)?;
+            trace!("Found commit object: {:?}", commit);
+
+            let time = commit.time();
+            let timestamp = unix_time_nsec_to_datetime(timestamp_secs_to_nsecs(time.seconds()));
+            if timestamp < past_date {
+                // We walked past the window; stop to avoid unnecessary work.
+                break;
+            }
+
+            let message = commit.message().unwrap_or_default().to_string();
+            let mut branches = Vec::new();
+            for (name, tip) in branch_tips {
+                if self
+                    .repo
+                    .graph_descendant_of(*tip, commit.id())
+                    .unwrap_or(false)
+                {
+                    branches.push(name.clone());
+                }
+            }
+
+            if oldest_commit
+                .as_ref()
+                .map(|c| commit.time().seconds() < c.time().seconds())
+                .unwrap_or(true)
+            {
+                oldest_commit = Some(commit.clone());
+            }
+
+            let (summary, body) = if let Some(idx) = message.find('\n') {
+                (
+                    message[..idx].trim().to_string(),
+                    Some(message[idx + 1..].trim().to_string()),
+                )
+            } else {
+                (message.clone(), None)
+            };
+
+            daily_commits.push(CommitMeta {
+                id: commit.id().to_string(),
+                summary,
+                body,
+                timestamp,
+                branches,
+            });
+        }
+
+        Ok((daily_commits, oldest_commit))
+    }
+
+    /// Local branches with commits that are not on their upstream.
+    ///
+    /// Branches without an upstream are compared against every remote-tracking branch instead.
+    /// Repositories without remotes are purely local, so nothing is reported for them.
+    #[tracing::instrument(name = "Finding unpushed branches", level = "info", skip(self))]
+    pub fn unpushed_branches(&self) -> AppResult<Vec<UnpushedBranch>> {
+        let remote_tips: Vec<Oid> = self
+            .repo
+            .branches(Some(BranchType::Remote))?
+            .flatten()
+            .filter_map(|(branch, _)| branch.get().target())
+            .collect();
+        if remote_tips.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut unpushed = Vec::new();
+        for (branch, _) in self.repo.branches(Some(BranchType::Local))?.flatten() {
+            let (Ok(Some(name)), Some(tip)) = (branch.name(), branch.get().target()) else {
+                continue;
+            };
+            let name = name.to_string();
+            let upstream = branch.upstream().ok().and_then(|upstream| {
+                let target = upstream.get().target()?;
+                let name = upstream.name().ok()??.to_string();
+                Some((name, target))
+            });
+
+            let mut revwalk = self.repo.revwalk()?;
+            revwalk.push(tip)?;
+            let behind = match &upstream {
+                Some((_, upstream_tip)) => {
+                    revwalk.hide(*upstream_tip)?;
+                    self.repo.graph_ahead_behind(tip, *upstream_tip)?.1
+                }
+                None => {
+                    for remote_tip in &remote_tips {
+                        revwalk.hide(*remote_tip)?;
+                    }
+                    0
+                }
+            };
+            let mut commits = Vec::new();
+            for oid in revwalk.flatten() {
+                let commit = self.repo.find_commit(oid)?;
+                commits.push(commit.summary().unwrap_or_default().to_string());
+            }
+            if commits.is_empty() {
+                continue;
+            }
+            trace!("{name} has {} unpushed commit(s)", commits.len());
+            unpushed.push(UnpushedBranch {
+                name,
+                upstream: upstream.map(|(name, _)| name),
+                ahead: commits.len(),
+                behind,
+                commits,
+            });
+        }
+        Ok(unpushed)
+    }
+
+    /// Whether any local branch or tag contains `oid`.
+    fn is_referenced(&self, oid: Oid, ref_tips: &[Oid]) -> bool {
+        ref_tips
+            .iter()
+            .any(|tip| *tip == oid || self.repo.graph_descendant_of(*tip, oid).unwrap_or(false))
+    }
+
+    /// Stashes and unreferenced commits created since `past_date`, oldest first.
+    ///
+    /// Both come from reflogs (`refs/stash` and `HEAD`), which are read without touching the
+    /// stash or the working tree. Commits that were later amended are not reported.
+    #[tracing::instrument(name = "Finding parked work", level = "info", skip(self))]
+    pub fn parked_work(&self, past_date: OffsetDateTime) -> AppResult<Vec<ParkedWork>> {
+        let to_datetime =
+            |time: git2::Time| unix_time_nsec_to_datetime(timestamp_secs_to_nsecs(time.seconds()));
+        let mut parked = Vec::new();
+
+        if let Ok(stashes) = self.repo.reflog("refs/stash") {
+            for entry in stashes.iter() {
+                let timestamp = to_datetime(entry.committer().when());
+                if timestamp < past_date {
+                    continue;
+                }
+                parked.push(ParkedWork {
+                    kind: ParkedKind::Stash,
+                    message: entry.message().unwrap_or_default().to_string(),
+                    commit: short_id(entry.id_new()),
+                    timestamp,
+                });
+            }
+        }
+
+        let mut ref_tips: Vec<Oid> = Vec::new();
+        for reference in self.repo.references()?.flatten() {
+            let is_ref = reference.is_branch() || reference.is_tag();
+            if is_ref && let Ok(commit) = reference.peel_to_commit() {
+                ref_tips.push(commit.id());
+            }
+        }
+        let head_log = self.repo.reflog("HEAD")?;
+        let amended: Vec<Oid> = head_log
+            .iter()
+            .filter(|entry| {
+                entry
+                    .message()
+                    .is_some_and(|msg| msg.starts_with("commit (amend)"))
+            })
+            .map(|entry| entry.id_old())
+            .collect();
+        let mut seen: Vec<Oid> = Vec::new();
+        for entry in head_log.iter() {
+            let message = entry.message().unwrap_or_default();
+            let oid = entry.id_new();
+            let timestamp = to_datetime(entry.committer().when());
+            if timestamp < past_date
+                || !message.starts_with("commit")
+                || amended.contains(&oid)
+                || seen.contains(&oid)
+                || self.is_referenced(oid, &ref_tips)
+            {
+                continue;
+            }
+            seen.push(oid);
+            let summary = self
+                .repo
+                .find_commit(oid)
+                .ok()
+                .and_then(|commit| commit.summary().map(str::to_string))
+                .unwrap_or_else(|| message.to_string());
+            parked.push(ParkedWork {
+                kind: ParkedKind::Unreferenced,
+                message: summary,
+                commit: short_id(oid),
+                timestamp,
+            });
+        }
+
+        parked.sort_by_key(|work| work.timestamp);
+        Ok(parked)
+    }
+
+    /// Tags created since `past_date`, oldest first, with when they were made: the tagger time
+    /// of annotated tags and the commit time of lightweight ones.
+    pub fn tags_since(
+        &self,
+        past_date: OffsetDateTime,
+    ) -> AppResult<Vec<(String, OffsetDateTime)>> {
+        let to_datetime =
+            |time: git2::Time| unix_time_nsec_to_datetime(timestamp_secs_to_nsecs(time.seconds()));
+        let mut tags = Vec::new();
+        for reference in self.repo.references_glob("refs/tags/*")? {
+            let reference = reference?;
+            let Some(name) = reference.shorthand().map(str::to_string) else {
+                continue;
+            };
+            let time = match reference.peel_to_tag() {
+                Ok(tag) => tag.tagger().map(|tagger| to_datetime(tagger.when())),
+                Err(_) => reference
+                    .peel_to_commit()
+                    .ok()
+                    .map(|c| to_datetime(c.time())),
+            };
+            match time {
+                Some(time) if time >= past_date => tags.push((name, time)),
+                Some(_) => {}
+                None => trace!("Skipping tag {name} without a date"),
+            }
+        }
+        tags.sort_by_key(|(_, time)| *time);
+        Ok(tags)
+    }
+
+    /// Whether the repository is a shallow clone (`git clone --depth`).
+    pub fn is_shallow(&self) -> bool {
+        self.repo.is_shallow()
+    }
+
+    /// Object filter of a partial clone (`git clone --filter`), e.g. `blob:none`.
+    pub fn partial_clone_filter(&self) -> Option<String> {
+        let config = self.repo.config().ok()?;
+        let promisor = config.get_string("extensions.partialclone").ok();
+        let remotes = self.repo.remotes().ok()?;
+        let mut names: Vec<String> = promisor.iter().cloned().collect();
+        names.extend(remotes.iter().flatten().map(str::to_string));
+        for name in &names {
+            if let Ok(filter) = config.get_string(&format!("remote.{name}.partialclonefilter")) {
+                return Some(filter);
+            }
+            if config
+                .get_bool(&format!("remote.{name}.promisor"))
+                .unwrap_or(false)
+            {
+                return Some("unknown filter".into());
+            }
+        }
+        promisor.map(|_| "unknown filter".into())
+    }
+
+    /// Commits at the shallow boundary, read from `.git/shallow`.
+    fn shallow_boundary(&self) -> Vec<String> {
+        std::fs::read_to_string(self.repo.path().join("shallow"))
+            .map(|shallow| shallow.lines().map(|l| l.trim().to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Describe how a shallow or partial clone limits the history collected in the window.
+    fn clone_limitations(&self, commits: &[CommitMeta]) -> Vec<String> {
+        let mut limitations = Vec::new();
+        if self.is_shallow() {
+            // Reaching a boundary commit means the walk stopped at the clone depth rather than
+            // at the start of the window.
+            let boundary = self.shallow_boundary();
+            match commits.iter().find(|commit| boundary.contains(&commit.id)) {
+                Some(commit) => {
+                    debug!(
+                        "Shallow history of {} ends at {}",
+                        self.repo_path().display(),
+                        commit.id
+                    );
+                    limitations.push(format!(
+                        "Shallow clone: history stops at commit {} ({}); earlier commits in the window are not available locally.",
+                        &commit.id[..7.min(commit.id.len())],
+                        commit.timestamp
+                    ));
+                }
+                None => limitations.push(
+                    "Shallow clone: commit history may be truncated and ancestry-based fields (branches, ahead/behind counts) may be incomplete."
+                        .into(),
+                ),
+            }
+        }
+        if let Some(filter) = self.partial_clone_filter() {
+            limitations.push(format!(
+                "Partial clone ({filter}): file contents that were never fetched are missing from diffs."
+            ));
+        }
+        limitations
+    }
+
+    /// Commits since `past_date` plus the diff from the oldest of them to HEAD.
+    ///
+    /// Returns `None` when nothing was committed or parked in the window.
+    #[tracing::instrument(name = "Reading git history", level = "info", skip(self))]
+    pub fn history_since(&self, past_date: OffsetDateTime) -> AppResult<Option<GitRepoHistory>> {
+        let branch_tips = self.branch_tips();
+        let (daily_commits, oldest_commit) = self.recent_commits(&branch_tips, past_date)?;
+        let parked = self.parked_work(past_date)?;
+        if oldest_commit.is_none() && parked.is_empty() {
+            return Ok(None);
+        }
+        let head_tree = self.repo.head()?.peel_to_tree()?;
+        // Without new commits the diff is empty, but the parked work is still worth reporting.
+        let base_tree = match &oldest_commit {
+            Some(commit) => commit.tree()?,
+            None => head_tree.clone(),
+        };
+        let diff = self.repo.diff_tree_to_tree(
+            Some(&base_tree),
+            Some(&head_tree),
+            Some(&mut get_diff_opts()),
+        )?;
+        let mut limitations = self.clone_limitations(&daily_commits);
+        let (dependency_changes, omitted) =
+            dependency_changes(&self.repo, &base_tree, &head_tree, &diff);
+        if omitted > 0 {
+            limitations.push(format!(
+                "{omitted} more dependencies changed only in lockfiles and are not listed."
+            ));
+        }
+        let mut diff_summary = match get_diff_summary(self.repo_path(), &diff) {
+            Ok(summary) => summary,
+            Err(e) => {
+                // Typically blobs a partial clone never fetched. Keep the changed paths.
+                warnings::record(
+                    WarningKind::Truncated,
+                    "git",
+                    format!(
+                        "Unable to render patches for {}: {e}. Collecting changed paths only.",
+                        self.repo_path().display()
+                    ),
+                );
+                limitations.push(format!(
+                    "Patches are unavailable ({e}); only the list of changed files was collected."
+                ));
+                get_diff_paths(self.repo_path(), &diff)
+            }
+        };
+        condense_lockfiles(&mut diff_summary);
+        Ok(Some(GitRepoHistory {
+            diff: diff_summary,
+            commits: daily_commits,
+            unpushed: self.unpushed_branches()?,
+            parked,
+            dependency_changes,
+            remote_url: self.remote_url(),
+            default_branch: self.default_branch(),
+            limitations,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use git2::{Signature, Status};
+    use time::Duration;
+
+    use super::*;
+
+    fn temp_repo(name: &str) -> (PathBuf, Repository) {
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let dir = std::env::temp_dir().join(format!("{name}_{nonce}"));
+        let repo = Repository::init(&dir).unwrap();
+        fs::write(dir.join("tracked.txt"), "one\n").unwrap();
+        {
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("tracked.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = Signature::now("Test", "test@example.com").unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, "ENG-1 initial", &tree, &[])
+                .unwrap();
+        }
+        (dir, repo)
+    }
+
+    #[test]
+    fn collecting_leaves_pending_changes_alone() {
+        let (dir, repo) = temp_repo("read_only_repo");
+        fs::write(dir.join("tracked.txt"), "two\n").unwrap();
+        fs::write(dir.join("untracked.txt"), "new\n").unwrap();
+        let head_before = repo.head().unwrap().target().unwrap();
+        let index_before = fs::read(dir.join(".git/index")).unwrap();
+
+        let access = ReadOnlyRepoAccess::open(&dir).unwrap();
+        let history = access
+            .history_since(OffsetDateTime::now_utc() - Duration::hours(1))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(history.commits.len(), 1);
+        assert_eq!(history.commits[0].summary, "ENG-1 initial");
+        assert_eq!(repo.head().unwrap().target().unwrap(), head_before);
+        assert_eq!(fs::read(dir.join(".git/index")).unwrap(), index_before);
+        assert_eq!(
+            repo.status_file(Path::new("tracked.txt")).unwrap(),
+            Status::WT_MODIFIED
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reports_branches_ahead_of_remotes() {
+        let (dir, repo) = temp_repo("read_only_unpushed");
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        // Pretend the initial commit was pushed, then commit on top of it locally.
+        repo.reference("refs/remotes/origin/main", head.id(), true, "test")
+            .unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            "Local only",
+            &head.tree().unwrap(),
+            &[&head],
+        )
+        .unwrap();
+
+        let unpushed = ReadOnlyRepoAccess::open(&dir)
+            .unwrap()
+            .unpushed_branches()
+            .unwrap();
+
+        assert_eq!(unpushed.len(), 1);
+        assert_eq!(unpushed[0].upstream, None);
+        assert_eq!(unpushed[0].commits, vec!["Local only".to_string()]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn lists_tags_made_in_the_window() {
+        let (dir, repo) = temp_repo("read_only_tags");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        repo.tag("v1.0.0", head.as_object(), &sig, "Release 1.0.0", false)
+            .unwrap();
+        repo.tag_lightweight("nightly", head.as_object(), false)
+            .unwrap();
+        let access = ReadOnlyRepoAccess::open(&dir).unwrap();
+
+        let mut tags: Vec<String> = access
+            .tags_since(OffsetDateTime::now_utc() - Duration::hours(1))
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        tags.sort();
+
+        assert_eq!(tags, vec!["nightly".to_string(), "v1.0.0".to_string()]);
+        assert!(
+            access
+                .tags_since(OffsetDateTime::now_utc() + Duration::hours(1))
+                .unwrap()
+                .is_empty()
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// Commit a new version of `tracked.txt` on top of HEAD, `minutes` after the initial commit.
+    fn commit_change(dir: &Path, repo: &Repository, content: &str, minutes: i64) -> Oid {
+        fs::write(dir.join("tracked.txt"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let time = git2::Time::new(head.time().seconds() + minutes * 60, 0);
+        let sig = Signature::new("Test", "test@example.com", &time).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Update", &tree, &[&head])
+            .unwrap()
+    }
+
+    #[test]
+    fn notes_when_history_stops_at_a_shallow_boundary() {
+        let (dir, repo) = temp_repo("read_only_shallow");
+        let boundary = commit_change(&dir, &repo, "two\n", 1);
+        commit_change(&dir, &repo, "three\n", 2);
+        // What `git clone --depth 2` leaves behind: the boundary commit is grafted as a root.
+        fs::write(dir.join(".git/shallow"), format!("{boundary}\n")).unwrap();
+
+        let history = ReadOnlyRepoAccess::open(&dir)
+            .unwrap()
+            .history_since(OffsetDateTime::now_utc() - Duration::hours(1))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(history.commits.len(), 2);
+        assert_eq!(history.limitations.len(), 1);
+        assert!(history.limitations[0].contains(&boundary.to_string()[..7]));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn falls_back_to_paths_when_blobs_are_missing() {
+        let (dir, repo) = temp_repo("read_only_partial");
+        let blob = repo
+            .head()
+            .unwrap()
+            .peel_to_tree()
+            .unwrap()
+            .get_name("tracked.txt")
+            .unwrap()
+            .id()
+            .to_string();
+        commit_change(&dir, &repo, "two\n", 1);
+        let mut config = repo.config().unwrap();
+        config.set_str("remote.origin.url", "/dev/null").unwrap();
+        config.set_bool("remote.origin.promisor", true).unwrap();
+        config
+            .set_str("remote.origin.partialclonefilter", "blob:none")
+            .unwrap();
+        // Simulate a blob the partial clone never fetched.
+        fs::remove_file(dir.join(".git/objects").join(&blob[..2]).join(&blob[2..])).unwrap();
+
+        let history = ReadOnlyRepoAccess::open(&dir)
+            .unwrap()
+            .history_since(OffsetDateTime::now_utc() - Duration::hours(1))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(history.commits.len(), 2);
+        assert_eq!(history.diff.modified.len(), 1);
+        assert!(history.diff.modified[0].patch.is_empty());
+        assert!(
+            history
+                .limitations
+                .iter()
+                .any(|l| l.starts_with("Partial clone (blob:none)"))
+        );
+        assert!(
+            history
+                .limitations
+                .iter()
+                .any(|l| l.starts_with("Patches are unavailable"))
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reads_remote_url_and_default_branch() {
+        let (dir, repo) = temp_repo("read_only_remote");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let access = ReadOnlyRepoAccess::open(&dir).unwrap();
+        assert_eq!(access.remote_url(), None);
+
+        repo.remote("origin", "git@github.com:annie444/daily-ai.git")
+            .unwrap();
+        repo.reference("refs/remotes/origin/trunk", head.id(), true, "test")
+            .unwrap();
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/trunk",
+            true,
+            "test",
+        )
+        .unwrap();
+
+        assert_eq!(
+            access.remote_url().as_deref(),
+            Some("git@github.com:annie444/daily-ai.git")
+        );
+        assert_eq!(access.default_branch().as_deref(), Some("trunk"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn finds_stashes_and_commits_left_behind_by_a_reset() {
+        let (dir, mut repo) = temp_repo("read_only_parked");
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let experiment = repo
+            .commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "Try a new parser",
+                &head.tree().unwrap(),
+                &[&head],
+            )
+            .unwrap();
+        repo.reset(head.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        drop(head);
+        fs::write(dir.join("tracked.txt"), "half done\n").unwrap();
+        repo.stash_save(&sig, "half-finished experiment", None)
+            .unwrap();
+
+        let parked = ReadOnlyRepoAccess::open(&dir)
+            .unwrap()
+            .parked_work(OffsetDateTime::now_utc() - Duration::hours(1))
+            .unwrap();
+
+        let kinds: Vec<ParkedKind> = parked.iter().map(|work| work.kind).collect();
+        assert!(kinds.contains(&ParkedKind::Stash));
+        let unreferenced: Vec<&ParkedWork> = parked
+            .iter()
+            .filter(|work| work.kind == ParkedKind::Unreferenced)
+            .collect();
+        assert_eq!(unreferenced.len(), 1);
+        assert_eq!(unreferenced[0].message, "Try a new parser");
+        assert!(experiment.to_string().starts_with(&unreferenced[0].commit));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn lists_dependency_changes_instead_of_lockfile_patches() {
+        let (dir, repo) = temp_repo("read_only_dependencies");
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[dependencies]\ntokio = \"1.43\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Cargo.lock"),
+            "version = 4\n\n[[package]]\nname = \"tokio\"\nversion = \"1.43.0\"\n\n[[package]]\nname = \"mio\"\nversion = \"1.0.3\"\n",
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("Cargo.toml")).unwrap();
+        index.add_path(Path::new("Cargo.lock")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let time = git2::Time::new(head.time().seconds() + 60, 0);
+        let sig = Signature::new("Test", "test@example.com", &time).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Add tokio", &tree, &[&head])
+            .unwrap();
+
+        let history = ReadOnlyRepoAccess::open(&dir)
+            .unwrap()
+            .history_since(OffsetDateTime::now_utc() - Duration::hours(1))
+            .unwrap()
+            .unwrap();
+
+        let described: Vec<(String, bool)> = history
+            .dependency_changes
+            .iter()
+            .map(|change| (change.describe(), change.direct))
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                ("Added tokio 1.43.0".to_string(), true),
+                ("Added mio 1.0.3".to_string(), false),
+            ]
+        );
+        let lockfile = history
+            .diff
+            .added
+            .iter()
+            .find(|entry| entry.path.ends_with("Cargo.lock"))
+            .unwrap();
+        assert_eq!(lockfile.patch, "Lockfile updated; see dependency_changes");
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,197 @@
+use clap::ValueEnum;
+use time::{Date, Duration};
+use tracing::debug;
+
+use crate::AppResult;
+use crate::ai::summary::WorkSummary;
+use crate::archive::{self, ArchiveEntry};
+use crate::classify::embed::{self, Embedder, EmbedderSettings};
+use crate::time_utils;
+use crate::warnings::{self, WarningKind};
+
+/// Days of archived highlights compared against unless `--highlight-lookback` says otherwise.
+pub const DEFAULT_LOOKBACK_DAYS: u32 = 5;
+
+/// Cosine similarity from which two highlights count as the same news. The embedding model
+/// scores unrelated sentences around 0.75, so this only catches rewordings.
+const REPEAT_SIMILARITY: f32 = 0.92;
+
+/// Prefix marking a highlight that was already reported on an earlier day.
+const ONGOING: &str = "Ongoing: ";
+
+/// What to do with highlights that repeat one from the last few days.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatedHighlights {
+    /// Keep them, prefixed with `Ongoing:`
+    Mark,
+    /// Leave them out
+    Drop,
+    /// Keep them as they are
+    Keep,
+}
+
+/// Cosine similarity of two embeddings, or 0 when either is empty.
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+/// Which of `current` closely match any of `previous`.
+fn find_repeats(current: &[Vec<f32>], previous: &[Vec<f32>]) -> Vec<bool> {
+    current
+        .iter()
+        .map(|highlight| {
+            previous
+                .iter()
+                .any(|earlier| cosine_similarity(highlight, earlier) >= REPEAT_SIMILARITY)
+        })
+        .collect()
+}
+
+/// `highlights` with the repeats marked or dropped as `mode` says.
+fn apply(highlights: Vec<String>, repeats: &[bool], mode: RepeatedHighlights) -> Vec<String> {
+    highlights
+        .into_iter()
+        .zip(repeats)
+        .filter_map(|(highlight, &repeat)| match mode {
+            _ if !repeat => Some(highlight),
+            RepeatedHighlights::Mark => Some(format!("{ONGOING}{highlight}")),
+            RepeatedHighlights::Drop => None,
+            RepeatedHighlights::Keep => Some(highlight),
+        })
+        .collect()
+}
+
+/// Highlights of the archived runs from the `days` days before `today`, without their
+/// `Ongoing:` marks.
+fn previous_highlights(entries: &[ArchiveEntry], today: Date, days: u32) -> Vec<String> {
+    let first = today - Duration::days(days.into());
+    entries
+        .iter()
+        .filter(|entry| (first..today).contains(&entry.date))
+        .filter_map(|entry| entry.context.summary.as_ref())
+        .flat_map(|summary| &summary.highlights)
+        .map(|highlight| {
+            highlight
+                .strip_prefix(ONGOING)
+                .unwrap_or(highlight)
+                .to_string()
+        })
+        .collect()
+}
+
+/// Compare `summary`'s highlights with `previous` ones, and mark or drop those that say the
+/// same thing again.
+async fn dedupe(
+    summary: &mut WorkSummary,
+    previous: &[String],
+    embedder: &EmbedderSettings,
+    mode: RepeatedHighlights,
+) -> AppResult<()> {
+    let embedder = embed::load(embedder, "highlights").await?;
+    let current = embedder.embed_texts(&summary.highlights).await?;
+    let previous = embedder.embed_texts(previous).await?;
+    let repeats = find_repeats(&current, &previous);
+    debug!(
+        "{} of {} highlights repeat earlier days",
+        repeats.iter().filter(|repeat| **repeat).count(),
+        repeats.len()
+    );
+    summary.highlights = apply(std::mem::take(&mut summary.highlights), &repeats, mode);
+    Ok(())
+}
+
+/// Mark or drop the highlights of this run that repeat those archived in the last `days` days.
+///
+/// Only reads the local archive, though a remote `--embedder` is sent the highlights to embed.
+/// An unreadable archive or embedding model is reported as a data quality warning and the
+/// highlights are left as they are.
+#[tracing::instrument(
+    name = "Comparing highlights with earlier days",
+    level = "info",
+    skip(summary, embedder)
+)]
+pub async fn dedupe_run(
+    summary: &mut WorkSummary,
+    embedder: &EmbedderSettings,
+    days: u32,
+    mode: RepeatedHighlights,
+) {
+    if mode == RepeatedHighlights::Keep || days == 0 || summary.highlights.is_empty() {
+        return;
+    }
+    let today = time_utils::now_local().date();
+    let previous = match archive::load_all().await {
+        Ok(entries) => previous_highlights(&entries, today, days),
+        Err(e) => {
+            warnings::record(
+                WarningKind::Skipped,
+                "highlights",
+                format!("Unable to read the archive for repeated highlights: {e}"),
+            );
+            return;
+        }
+    };
+    if previous.is_empty() {
+        return;
+    }
+    if let Err(e) = dedupe(summary, &previous, embedder, mode).await {
+        warnings::record(
+            WarningKind::Skipped,
+            "highlights",
+            format!("Unable to compare highlights with earlier days: {e}"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+    use crate::context::FullContext;
+
+    #[test]
+    fn marks_or_drops_highlights_close_to_earlier_ones() {
+        let previous = vec![vec![1.0, 0.0, 0.0]];
+        let current = vec![vec![0.98, 0.1, 0.0], vec![0.0, 1.0, 0.0]];
+        let repeats = find_repeats(&current, &previous);
+        assert_eq!(repeats, [true, false]);
+
+        let highlights = || vec!["CI: still migrating".to_string(), "Parser: fixed".into()];
+        assert_eq!(
+            apply(highlights(), &repeats, RepeatedHighlights::Mark),
+            ["Ongoing: CI: still migrating", "Parser: fixed"]
+        );
+        assert_eq!(
+            apply(highlights(), &repeats, RepeatedHighlights::Drop),
+            ["Parser: fixed"]
+        );
+        assert_eq!(cosine_similarity(&[], &[1.0]), 0.0);
+    }
+
+    #[test]
+    fn compares_with_the_days_before_today() {
+        let entry = |date, highlight: &str| ArchiveEntry {
+            date,
+            context: FullContext {
+                summary: Some(WorkSummary {
+                    highlights: vec![highlight.to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+        let entries = [
+            entry(date!(2025 - 01 - 01), "Too old"),
+            entry(date!(2025 - 01 - 08), "Ongoing: CI: still migrating"),
+            entry(date!(2025 - 01 - 10), "Today's earlier run"),
+        ];
+        assert_eq!(
+            previous_highlights(&entries, date!(2025 - 01 - 10), 5),
+            ["CI: still migrating"]
+        );
+    }
+}
@@ -0,0 +1,493 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use time::Date;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use tokio::fs;
+use tracing::{info, trace, warn};
+use ulid::Ulid;
+
+use crate::ai::summary::WorkSummary;
+use crate::archive::{PurgeOptions, PurgeReport};
+use crate::cli::OutputFormat;
+use crate::context::FullContext;
+use crate::dirs::DirType;
+use crate::error::AppError;
+use crate::render::terminal;
+use crate::{AppResult, io_utils, time_utils};
+
+/// Table holding one row per run. Contexts and summaries are stored as JSON, so old rows stay
+/// readable through the same migrations as the file archive.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    run_id TEXT PRIMARY KEY NOT NULL,
+    created_at TEXT NOT NULL,
+    date TEXT NOT NULL,
+    summary TEXT,
+    context TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS runs_created_at ON runs (created_at);
+";
+
+/// A stored run.
+pub(crate) mod run {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "runs")]
+    pub struct Model {
+        /// ULID of the run.
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub run_id: String,
+        /// When the run was stored, as RFC 3339 in UTC.
+        pub created_at: String,
+        /// Local date of the run, e.g. `2025-01-31`.
+        pub date: String,
+        /// The generated `WorkSummary` as JSON, if the run had one.
+        #[sea_orm(column_type = "Text", nullable)]
+        pub summary: Option<String>,
+        /// The whole `FullContext` as JSON.
+        #[sea_orm(column_type = "Text")]
+        pub context: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// A stored run's summary, without the collected context.
+#[derive(Debug)]
+pub struct StoredSummary {
+    pub run_id: String,
+    pub date: String,
+    pub summary: Option<WorkSummary>,
+}
+
+impl StoredSummary {
+    fn from_row(row: run::Model) -> AppResult<Self> {
+        Ok(StoredSummary {
+            summary: row
+                .summary
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?,
+            run_id: row.run_id,
+            date: row.date,
+        })
+    }
+
+    /// First sentence of the summary, or an empty string.
+    pub fn headline(&self) -> &str {
+        let text = self
+            .summary
+            .as_ref()
+            .map_or("", |summary| summary.summary.trim());
+        match text.find(". ") {
+            Some(end) => &text[..=end],
+            None => text,
+        }
+    }
+}
+
+/// A run whose summary matched a search, with the matching entries.
+#[derive(Debug)]
+pub struct SearchHit {
+    pub run: StoredSummary,
+    /// `(section, text)` for every summary entry containing the search text.
+    pub matches: Vec<(&'static str, String)>,
+}
+
+/// Every text entry of `summary`, labelled with its section.
+fn summary_entries(summary: &WorkSummary) -> Vec<(&'static str, &str)> {
+    let mut entries = vec![
+        ("Summary", summary.summary.as_str()),
+        ("Shell", summary.shell_overview.as_str()),
+    ];
+    for (section, items) in [
+        ("Highlights", &summary.highlights),
+        ("Releases", &summary.releases),
+        ("Time breakdown", &summary.time_breakdown),
+        ("Tickets touched", &summary.tickets_touched),
+        ("Pending review", &summary.pending_review),
+        ("Communication", &summary.communication),
+        ("Repositories", &summary.repo_summaries),
+        ("Common themes", &summary.common_groups),
+        ("Notes", &summary.notes),
+        ("Learned today", &summary.learned),
+        ("Pace", &summary.pace),
+    ] {
+        entries.extend(items.iter().map(|item| (section, item.as_str())));
+    }
+    entries.retain(|(_, text)| !text.trim().is_empty());
+    entries
+}
+
+/// Path of the long-term history database, `~/.local/share/dailyai/history.db`.
+pub fn history_db_path() -> AppResult<PathBuf> {
+    Ok(DirType::Data.get_dir()?.join("history.db"))
+}
+
+/// Open (creating if needed) the history database at `path` and make sure its table exists.
+#[tracing::instrument(name = "Opening the history database", level = "info")]
+pub async fn connect<P: AsRef<Path> + std::fmt::Debug>(path: P) -> AppResult<DatabaseConnection> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=rwc", path.display()));
+    opt.sqlx_logging(false);
+    trace!("Connecting to history database");
+    let db = Database::connect(opt).await?;
+    db.execute_unprepared(SCHEMA).await?;
+    Ok(db)
+}
+
+/// Append a run to `db`. Storing the same run again replaces the earlier row.
+pub async fn append(db: &DatabaseConnection, context: &FullContext) -> AppResult<Ulid> {
+    // Runs always carry an id once collected; the fallback only covers hand-built contexts.
+    let run_id = match context.run_id {
+        Some(run_id) => run_id,
+        None => Ulid::new(),
+    };
+    let now = time_utils::now_local();
+    let row = run::ActiveModel {
+        run_id: Set(run_id.to_string()),
+        created_at: Set(now.to_offset(time::UtcOffset::UTC).format(&Rfc3339)?),
+        date: Set(now.date().to_string()),
+        summary: Set(context
+            .summary
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?),
+        context: Set(serde_json::to_string(context)?),
+    };
+    run::Entity::insert(row)
+        .on_conflict(
+            OnConflict::column(run::Column::RunId)
+                .update_columns([
+                    run::Column::CreatedAt,
+                    run::Column::Date,
+                    run::Column::Summary,
+                    run::Column::Context,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(run_id)
+}
+
+/// Append a run to the database file at `path`.
+#[tracing::instrument(name = "Storing run in SQLite", level = "info", skip(context))]
+pub async fn append_to<P: AsRef<Path> + std::fmt::Debug>(
+    path: P,
+    context: &FullContext,
+) -> AppResult<()> {
+    let db = connect(&path).await?;
+    let run_id = append(&db, context).await?;
+    info!("Stored run {run_id} in {}", path.as_ref().display());
+    Ok(())
+}
+
+/// Append a run to the long-term history database.
+pub async fn save(context: &FullContext) -> AppResult<()> {
+    append_to(history_db_path()?, context).await
+}
+
+/// The most recent `limit` runs, newest first.
+pub async fn list(db: &DatabaseConnection, limit: u64) -> AppResult<Vec<StoredSummary>> {
+    run::Entity::find()
+        .order_by_desc(run::Column::CreatedAt)
+        .limit(limit)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(StoredSummary::from_row)
+        .collect()
+}
+
+/// Load a stored run by run id, or the latest run of a `YYYY-MM-DD` day.
+pub async fn find(db: &DatabaseConnection, key: &str) -> AppResult<Option<FullContext>> {
+    let query = run::Entity::find().order_by_desc(run::Column::CreatedAt);
+    let query = match Date::parse(key, format_description!("[year]-[month]-[day]")) {
+        Ok(date) => query.filter(run::Column::Date.eq(date.to_string())),
+        Err(_) => query.filter(run::Column::RunId.eq(key.to_uppercase())),
+    };
+    Ok(query
+        .one(db)
+        .await?
+        .map(|row| FullContext::from_json(&row.context))
+        .transpose()?)
+}
+
+/// Runs whose summary, notes, or any other summary entry contains `text`, ignoring case,
+/// newest first.
+pub async fn search(db: &DatabaseConnection, text: &str, limit: u64) -> AppResult<Vec<SearchHit>> {
+    let needle = text.to_lowercase();
+    let mut query = run::Entity::find()
+        .filter(run::Column::Summary.is_not_null())
+        .order_by_desc(run::Column::CreatedAt);
+    // SQLite's LIKE narrows the rows down cheaply; text that JSON escapes is only matched below.
+    if !text.contains(['"', '\\']) && !text.chars().any(char::is_control) {
+        query = query.filter(run::Column::Summary.contains(text));
+    }
+    let mut hits = Vec::new();
+    for row in query.all(db).await? {
+        let run = StoredSummary::from_row(row)?;
+        let matches: Vec<(&'static str, String)> = run
+            .summary
+            .as_ref()
+            .map(summary_entries)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, entry)| entry.to_lowercase().contains(&needle))
+            .map(|(section, entry)| (section, entry.trim().to_string()))
+            .collect();
+        if !matches.is_empty() {
+            hits.push(SearchHit { run, matches });
+        }
+        if hits.len() as u64 >= limit {
+            break;
+        }
+    }
+    Ok(hits)
+}
+
+/// Open the history database for reading, or `None` when no run has been stored yet.
+async fn open_existing() -> AppResult<Option<DatabaseConnection>> {
+    let path = history_db_path()?;
+    if !fs::try_exists(&path).await? {
+        warn!("No history database yet. Run `daily-ai summarize` to start filling it.");
+        return Ok(None);
+    }
+    Ok(Some(connect(&path).await?))
+}
+
+/// Print the most recent runs, one per line.
+#[tracing::instrument(name = "Listing stored runs", level = "info")]
+pub async fn print_list(limit: u64) -> AppResult<()> {
+    let Some(db) = open_existing().await? else {
+        return Ok(());
+    };
+    let mut out = String::new();
+    for run in list(&db, limit).await? {
+        let _ = writeln!(out, "{}  {}  {}", run.date, run.run_id, run.headline());
+    }
+    terminal::print(out)
+}
+
+fn no_run(key: &str) -> AppError {
+    AppError::Config(format!(
+        "No stored run matches {key}; pass a run id or a date (YYYY-MM-DD) from `daily-ai history list`"
+    ))
+}
+
+/// Load the stored run matching `key`, a run id or a date, erroring when there is none.
+pub async fn load(key: &str) -> AppResult<FullContext> {
+    let context = match open_existing().await? {
+        Some(db) => find(&db, key).await?,
+        None => None,
+    };
+    context.ok_or_else(|| no_run(key))
+}
+
+/// Show a stored run like a fresh one: rendered on a terminal, or in `format`.
+#[tracing::instrument(name = "Showing a stored run", level = "info")]
+pub async fn show(key: &str, format: Option<&OutputFormat>) -> AppResult<()> {
+    let Some(db) = open_existing().await? else {
+        return Ok(());
+    };
+    let context = find(&db, key).await?.ok_or_else(|| no_run(key))?;
+    io_utils::print_output(format, &context).await
+}
+
+/// Print the runs matching `text`, with the matching summary entries under each.
+#[tracing::instrument(name = "Searching stored runs", level = "info")]
+pub async fn print_search(text: &str, limit: u64) -> AppResult<()> {
+    let Some(db) = open_existing().await? else {
+        return Ok(());
+    };
+    let hits = search(&db, text, limit).await?;
+    if hits.is_empty() {
+        info!("No stored summary mentions \"{text}\"");
+    }
+    let mut out = String::new();
+    for SearchHit { run, matches } in hits {
+        let _ = writeln!(out, "{}  {}", run.date, run.run_id);
+        for (section, entry) in matches {
+            let _ = writeln!(out, "  {section}: {entry}");
+        }
+    }
+    terminal::print(out)
+}
+
+/// Apply an archive purge to the long-term history database as well.
+#[tracing::instrument(name = "Purging history database", level = "info")]
+pub async fn purge(options: &PurgeOptions) -> AppResult<PurgeReport> {
+    purge_in(history_db_path()?, options).await
+}
+
+/// Purge runs older than `options.before` from the database at `path`, if there is one.
+///
+/// In paranoid mode SQLite overwrites deleted content and the file is vacuumed afterwards, so
+/// removed data does not linger in free pages.
+pub async fn purge_in<P: AsRef<Path> + std::fmt::Debug>(
+    path: P,
+    options: &PurgeOptions,
+) -> AppResult<PurgeReport> {
+    let mut report = PurgeReport::default();
+    if !fs::try_exists(path.as_ref()).await? {
+        return Ok(report);
+    }
+    let db = connect(&path).await?;
+    if options.paranoid {
+        db.execute_unprepared("PRAGMA secure_delete = ON").await?;
+    }
+    let rows = run::Entity::find()
+        .filter(run::Column::Date.lt(options.before.to_string()))
+        .all(&db)
+        .await?;
+    for row in rows {
+        if options.removes_runs() {
+            run::Entity::delete_by_id(row.run_id).exec(&db).await?;
+            report.removed += 1;
+            continue;
+        }
+        let mut context = FullContext::from_json(&row.context)?;
+        if options.scrub(&mut context) {
+            let mut row: run::ActiveModel = row.into();
+            row.summary = Set(context
+                .summary
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?);
+            row.context = Set(serde_json::to_string(&context)?);
+            row.update(&db).await?;
+            report.scrubbed += 1;
+        }
+    }
+    if options.paranoid {
+        db.execute_unprepared("VACUUM").await?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+
+    #[tokio::test]
+    async fn appends_runs_and_replaces_reruns() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        db.execute_unprepared(SCHEMA).await.unwrap();
+        let run_id = Ulid::from_string("01JH8ZQ0000000000000000000").unwrap();
+        let mut context = FullContext {
+            run_id: Some(run_id),
+            ..Default::default()
+        };
+
+        append(&db, &context).await.unwrap();
+        context.summary = Some(WorkSummary {
+            summary: "Stored the run.".into(),
+            ..Default::default()
+        });
+        append(&db, &context).await.unwrap();
+        append(&db, &FullContext::default()).await.unwrap();
+
+        let rows = run::Entity::find().all(&db).await.unwrap();
+        assert_eq!(rows.len(), 2);
+        let stored = rows
+            .iter()
+            .find(|row| row.run_id == run_id.to_string())
+            .unwrap();
+        let summary: WorkSummary =
+            serde_json::from_str(stored.summary.as_deref().unwrap()).unwrap();
+        assert_eq!(summary.summary, "Stored the run.");
+        assert_eq!(
+            FullContext::from_json(&stored.context).unwrap().run_id,
+            Some(run_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_finds_and_searches_runs() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        db.execute_unprepared(SCHEMA).await.unwrap();
+        let run_id = Ulid::from_string("01JH8ZQ0000000000000000000").unwrap();
+        append(
+            &db,
+            &FullContext {
+                run_id: Some(run_id),
+                summary: Some(WorkSummary {
+                    summary: "Rewrote the parser. Then reviewed PRs.".into(),
+                    notes: vec!["The Parser still lacks error recovery.".into()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let runs = list(&db, 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].headline(), "Rewrote the parser.");
+
+        let by_id = find(&db, &run_id.to_string().to_lowercase()).await.unwrap();
+        assert_eq!(by_id.unwrap().run_id, Some(run_id));
+        let by_date = find(&db, &runs[0].date).await.unwrap();
+        assert_eq!(by_date.unwrap().run_id, Some(run_id));
+        assert!(find(&db, "1999-01-01").await.unwrap().is_none());
+
+        let hits = search(&db, "PARSER", 10).await.unwrap();
+        assert_eq!(
+            hits[0].matches,
+            [
+                (
+                    "Summary",
+                    "Rewrote the parser. Then reviewed PRs.".to_string()
+                ),
+                (
+                    "Notes",
+                    "The Parser still lacks error recovery.".to_string()
+                ),
+            ]
+        );
+        assert!(search(&db, "lexer", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn purge_removes_old_runs() {
+        let nonce = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("history_purge_{nonce}/history.db"));
+        append_to(&path, &FullContext::default()).await.unwrap();
+        let tomorrow = time_utils::now_local().date().next_day().unwrap();
+        let options = |before| PurgeOptions {
+            before,
+            sources: vec![],
+            drop_summaries: true,
+            paranoid: true,
+        };
+
+        let kept = purge_in(&path, &options(tomorrow - time::Duration::days(2)))
+            .await
+            .unwrap();
+        let purged = purge_in(&path, &options(tomorrow)).await.unwrap();
+
+        assert_eq!((kept.removed, purged.removed), (0, 1));
+        let db = connect(&path).await.unwrap();
+        assert!(run::Entity::find().all(&db).await.unwrap().is_empty());
+        let _ = fs::remove_dir_all(path.parent().unwrap()).await;
+    }
+}
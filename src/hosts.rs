@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::debug;
+
+use crate::shell::ShellHistoryEntry;
+
+/// Remote connections made to one host with ssh, scp, rsync, sftp, or mosh.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostActivity {
+    /// Host name, address, or ssh config alias as typed.
+    pub host: String,
+    /// Remote users named on the command line, e.g. `deploy` in `ssh deploy@web-1`.
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// Number of connections of each kind: `ssh` for an interactive session, `ssh <program>` for
+    /// a remote command, `ssh jump` for a jump host, `scp upload`, `rsync download`, `sftp`, or
+    /// `mosh`.
+    pub categories: BTreeMap<String, usize>,
+    pub connections: usize,
+    pub failed_commands: usize,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_used: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_used: OffsetDateTime,
+}
+
+/// One connection parsed from a shell command.
+#[derive(Debug, PartialEq, Eq)]
+struct Connection {
+    host: String,
+    user: Option<String>,
+    category: String,
+}
+
+/// Single-letter flags of `tool` that take a separate value, so the value is not mistaken for
+/// the destination.
+fn flag_takes_value(tool: &str, flag: char) -> bool {
+    let flags = match tool {
+        "scp" => "cDFiJlmoPSX",
+        "rsync" => "efBMT",
+        _ => "bBcDEeFIiJLlmOoPpQRSWw",
+    };
+    flags.contains(flag)
+}
+
+/// Split `[user@]host`, also accepting `ssh://user@host:port/` and `rsync://host/module` URLs.
+fn split_target(target: &str) -> Option<(Option<String>, String)> {
+    let url = target
+        .strip_prefix("ssh://")
+        .or_else(|| target.strip_prefix("rsync://"));
+    let target = match url {
+        Some(rest) => rest.split('/').next().unwrap_or(rest),
+        None => target,
+    };
+    let (user, host) = match target.rsplit_once('@') {
+        Some((user, host)) => (Some(user.to_string()).filter(|u| !u.is_empty()), host),
+        None => (None, target),
+    };
+    let host = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None if url.is_some() => host.split(':').next().unwrap_or(host),
+        None => host,
+    };
+    (!host.is_empty()).then(|| (user, host.to_string()))
+}
+
+/// The `[user@]host` of an scp or rsync operand such as `web-1:/var/log`, `deploy@web-1:`,
+/// `host::module`, or `rsync://host/module`, or `None` for a local path.
+fn remote_operand(operand: &str) -> Option<(Option<String>, String)> {
+    if operand.starts_with("rsync://") || operand.starts_with("scp://") {
+        return split_target(&operand.replacen("scp://", "ssh://", 1));
+    }
+    // `[2001:db8::1]:/srv` has colons inside the brackets.
+    let target = match operand.find("]:") {
+        Some(end) => &operand[..=end],
+        None => operand.split_once(':')?.0,
+    };
+    if target.is_empty() || target.contains('/') || target.starts_with('.') {
+        return None;
+    }
+    split_target(target)
+}
+
+/// Parse the remote connections in one shell command line.
+///
+/// Pipelines and command lists (`|`, `&&`, `;`) are split like the Kubernetes parser does, and
+/// leading `sudo`, `env`, and `VAR=value` assignments are skipped.
+fn parse_command(command: &str) -> Vec<Connection> {
+    let mut connections = Vec::new();
+    for segment in command.split(['|', ';', '&', '\n']) {
+        let mut words = segment
+            .split_whitespace()
+            .map(|w| w.trim_matches(['"', '\'']))
+            .peekable();
+        while let Some(word) = words.peek() {
+            if *word == "sudo" || *word == "env" || word.contains('=') {
+                words.next();
+            } else {
+                break;
+            }
+        }
+        let Some(tool) = words.next() else {
+            continue;
+        };
+        let tool = tool.rsplit('/').next().unwrap_or(tool);
+        if !matches!(tool, "ssh" | "scp" | "rsync" | "sftp" | "mosh") {
+            continue;
+        }
+
+        let mut login = None;
+        let mut jump = None;
+        let mut positional = Vec::new();
+        while let Some(word) = words.next() {
+            if !positional.is_empty() && tool == "ssh" {
+                // Everything after the destination is the remote command.
+                positional.push(word);
+                continue;
+            }
+            if word == "--" {
+                positional.extend(words.by_ref());
+                break;
+            }
+            if word.starts_with("--") || word == "-" || !word.starts_with('-') {
+                if !word.starts_with("--") {
+                    positional.push(word);
+                }
+                continue;
+            }
+            let mut flags = word[1..].chars();
+            let Some(flag) = flags.next() else {
+                continue;
+            };
+            if !flag_takes_value(tool, flag) {
+                continue;
+            }
+            let inline = flags.as_str();
+            let value = if inline.is_empty() {
+                words.next().map(str::to_string)
+            } else {
+                Some(inline.to_string())
+            };
+            match (tool, flag) {
+                ("ssh" | "sftp" | "mosh", 'l') => login = value,
+                ("ssh" | "sftp" | "scp", 'J') => jump = value,
+                _ => {}
+            }
+        }
+
+        if let Some(jump) = jump.as_deref().and_then(|j| j.split(',').next_back())
+            && let Some((user, host)) = split_target(jump)
+        {
+            connections.push(Connection {
+                host,
+                user,
+                category: "ssh jump".into(),
+            });
+        }
+        match tool {
+            "scp" | "rsync" => {
+                let remotes: Vec<(usize, (Option<String>, String))> = positional
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, operand)| Some((i, remote_operand(operand)?)))
+                    .collect();
+                for (i, (user, host)) in remotes {
+                    let direction = if i + 1 == positional.len() {
+                        "upload"
+                    } else {
+                        "download"
+                    };
+                    connections.push(Connection {
+                        host,
+                        user,
+                        category: format!("{tool} {direction}"),
+                    });
+                }
+            }
+            _ => {
+                let Some((user, host)) = positional.first().and_then(|t| split_target(t)) else {
+                    continue;
+                };
+                let program = positional
+                    .iter()
+                    .skip(1)
+                    .find(|word| !matches!(**word, "sudo" | "env") && !word.contains('='));
+                let category = match program {
+                    Some(program) if tool == "ssh" => {
+                        format!("ssh {}", program.rsplit('/').next().unwrap_or(program))
+                    }
+                    _ => tool.to_string(),
+                };
+                connections.push(Connection {
+                    host,
+                    user: user.or(login),
+                    category,
+                });
+            }
+        }
+    }
+    connections
+}
+
+/// Group the ssh, scp, rsync, sftp, and mosh connections in shell history by host.
+pub fn find_host_activity(shell_history: &[ShellHistoryEntry]) -> Vec<HostActivity> {
+    let mut grouped: BTreeMap<String, HostActivity> = BTreeMap::new();
+    for entry in shell_history {
+        for connection in parse_command(&entry.command) {
+            let activity = grouped
+                .entry(connection.host.clone())
+                .or_insert_with(|| HostActivity {
+                    host: connection.host.clone(),
+                    users: Vec::new(),
+                    categories: BTreeMap::new(),
+                    connections: 0,
+                    failed_commands: 0,
+                    first_used: entry.date_time,
+                    last_used: entry.date_time,
+                });
+            if let Some(user) = connection.user
+                && !activity.users.contains(&user)
+            {
+                activity.users.push(user);
+            }
+            *activity.categories.entry(connection.category).or_default() += 1;
+            activity.connections += 1;
+            if entry.exit_code != 0 {
+                activity.failed_commands += 1;
+            }
+            activity.first_used = activity.first_used.min(entry.date_time);
+            activity.last_used = activity.last_used.max(entry.date_time);
+        }
+    }
+    let mut activity: Vec<HostActivity> = grouped.into_values().collect();
+    activity.sort_by_key(|a| a.first_used);
+    debug!("Found connections to {} hosts", activity.len());
+    activity
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::Duration;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn entry(command: &str, minute: u8, exit_code: i64) -> ShellHistoryEntry {
+        ShellHistoryEntry {
+            date_time: datetime!(2025-01-01 09:00 UTC)
+                .replace_minute(minute)
+                .unwrap(),
+            duration: Duration::ZERO,
+            host: "laptop".into(),
+            directory: PathBuf::from("/src/infra"),
+            command: command.into(),
+            exit_code,
+            session_id: "s".into(),
+        }
+    }
+
+    fn connection(host: &str, user: Option<&str>, category: &str) -> Connection {
+        Connection {
+            host: host.into(),
+            user: user.map(str::to_string),
+            category: category.into(),
+        }
+    }
+
+    #[test]
+    fn parses_ssh_scp_and_rsync_targets() {
+        assert_eq!(
+            parse_command("ssh -p 2222 -i ~/.ssh/deploy deploy@web-1 sudo systemctl restart api"),
+            vec![connection("web-1", Some("deploy"), "ssh systemctl")]
+        );
+        assert_eq!(
+            parse_command("ssh -J bastion -l admin db-1"),
+            vec![
+                connection("bastion", None, "ssh jump"),
+                connection("db-1", Some("admin"), "ssh"),
+            ]
+        );
+        assert_eq!(
+            parse_command("scp -P 2222 build.tar.gz web-1:/tmp/ && scp web-2:/var/log/app.log ."),
+            vec![
+                connection("web-1", None, "scp upload"),
+                connection("web-2", None, "scp download"),
+            ]
+        );
+        assert_eq!(
+            parse_command("rsync -avz -e 'ssh -p 22' ./site/ deploy@[2001:db8::1]:/srv/site"),
+            vec![connection("2001:db8::1", Some("deploy"), "rsync upload")]
+        );
+        assert_eq!(
+            parse_command("ssh ssh://git@forge.example.com:2222"),
+            vec![connection("forge.example.com", Some("git"), "ssh")]
+        );
+
+        assert!(parse_command("rsync -a ./a/ ./b/").is_empty());
+        assert!(parse_command("echo ssh web-1").is_empty());
+    }
+
+    #[test]
+    fn groups_connections_by_host() {
+        let activity = find_host_activity(&[
+            entry("ssh deploy@web-1", 5, 0),
+            entry("scp app.tar.gz web-1:", 10, 1),
+            entry("mosh db-1", 20, 0),
+        ]);
+
+        assert_eq!(activity.len(), 2);
+        let web = &activity[0];
+        assert_eq!(web.host, "web-1");
+        assert_eq!(web.users, vec!["deploy".to_string()]);
+        assert_eq!(web.connections, 2);
+        assert_eq!(web.categories["scp upload"], 1);
+        assert_eq!(web.failed_commands, 1);
+        assert_eq!(web.last_used - web.first_used, Duration::minutes(5));
+        assert_eq!(activity[1].categories["mosh"], 1);
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
+
+use crate::error::AppError;
+use crate::{AppResult, allowlist};
+
+/// How long to wait for a connection to any server.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for the next bytes of a response before giving up on it.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Proxy settings from the configuration file, set once at startup.
+static PROXY: OnceLock<ProxySettings> = OnceLock::new();
+
+/// A proxy for every HTTP request, from the `proxy` and `no_proxy` keys of the configuration
+/// file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    /// Proxy URL, e.g. `http://proxy.corp:3128`.
+    pub url: Option<String>,
+    /// Comma-separated hosts and domains reached directly, in `NO_PROXY` syntax.
+    pub no_proxy: Option<String>,
+}
+
+impl ProxySettings {
+    /// The proxy to route requests through, or `None` to reach servers directly.
+    fn proxy(&self) -> AppResult<Option<Proxy>> {
+        let Some(url) = &self.url else {
+            return Ok(None);
+        };
+        let no_proxy = self
+            .no_proxy
+            .as_deref()
+            .and_then(NoProxy::from_string)
+            .or_else(NoProxy::from_env);
+        let proxy = Proxy::all(url)
+            .map_err(|e| AppError::Config(format!("proxy: {url} is not a usable proxy: {e}")))?;
+        Ok(Some(proxy.no_proxy(no_proxy)))
+    }
+
+    /// Check that the proxy URL can be used, so a typo fails before anything is collected.
+    pub fn check(&self) -> Result<(), String> {
+        self.proxy().map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+/// Use `settings` for every client built from now on. Only the first call has an effect.
+///
+/// The proxy from `settings` is added to the network allowlist.
+pub fn configure(settings: ProxySettings) {
+    if let Some(proxy) = &settings.url {
+        allowlist::allow(proxy);
+    }
+    let _ = PROXY.set(settings);
+}
+
+/// A client builder with the user agent, connect timeout, and proxy shared by every request.
+///
+/// Requests only go through a proxy set in the configuration file; `HTTPS_PROXY`, `HTTP_PROXY`,
+/// and `ALL_PROXY` are ignored. Hosts are only reached, and redirects only followed, when they
+/// are on the network allowlist, after `hosts` are added to it. That holds for URLs with an IP
+/// address and for proxied requests too, which are never resolved here.
+fn base_builder(hosts: &[&str]) -> AppResult<ClientBuilder> {
+    base_builder_with(&PROXY.get().cloned().unwrap_or_default(), hosts)
+}
+
+#[allow(clippy::disallowed_methods)]
+fn base_builder_with(settings: &ProxySettings, hosts: &[&str]) -> AppResult<ClientBuilder> {
+    for host in hosts {
+        allowlist::allow(host);
+    }
+    let mut builder = Client::builder()
+        .user_agent(format!("daily-ai/{}", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(CONNECT_TIMEOUT)
+        .dns_resolver(Arc::new(allowlist::Resolver))
+        .redirect(allowlist::redirect_policy())
+        .no_proxy()
+        // First, so requests to other hosts never reach the configured proxy.
+        .proxy(allowlist::guard());
+    if let Some(proxy) = settings.proxy()? {
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+/// A client builder for API calls and downloads to `hosts`, which also gives up on stalled
+/// responses.
+pub fn builder(hosts: &[&str]) -> AppResult<ClientBuilder> {
+    Ok(base_builder(hosts)?.read_timeout(READ_TIMEOUT))
+}
+
+/// A client for API calls and downloads to `hosts`.
+pub fn client(hosts: &[&str]) -> AppResult<Client> {
+    Ok(builder(hosts)?.build()?)
+}
+
+/// A client for the language model server at `api_base`, without a read timeout: a model can
+/// take minutes to answer before sending anything back.
+pub fn model_server_client(api_base: &str) -> AppResult<Client> {
+    Ok(base_builder(&[api_base])?.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// `error` and every error that led to it, as one line.
+    fn error_chain(error: &dyn std::error::Error) -> String {
+        let mut chain = error.to_string();
+        let mut source = error.source();
+        while let Some(e) = source {
+            chain.push_str(": ");
+            chain.push_str(&e.to_string());
+            source = e.source();
+        }
+        chain
+    }
+
+    #[test]
+    fn rejects_unusable_proxy_urls() {
+        let settings = |url: &str| ProxySettings {
+            url: Some(url.into()),
+            no_proxy: Some("localhost,.corp".into()),
+        };
+        assert!(settings("http://proxy.corp:3128").check().is_ok());
+        assert!(settings("not a url").check().is_err());
+        assert!(ProxySettings::default().check().is_ok());
+    }
+
+    #[tokio::test]
+    async fn refuses_ip_addresses_off_the_allowlist() {
+        let client = base_builder_with(&ProxySettings::default(), &[])
+            .unwrap()
+            .build()
+            .unwrap();
+        // An address reserved for documentation, which nothing puts on the allowlist.
+        let error = client.get("http://192.0.2.1/").send().await.unwrap_err();
+        assert!(
+            error_chain(&error).contains("not on the network allowlist"),
+            "{error:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_only_allowed_hosts_through_the_proxy() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let settings = ProxySettings {
+            url: Some(format!("http://{}", proxy.local_addr().unwrap())),
+            no_proxy: Some("localhost".into()),
+        };
+        let hosts = [settings.url.as_deref().unwrap(), "allowed.daily-ai.invalid"];
+        let client = base_builder_with(&settings, &hosts)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let refused = tokio::time::timeout(
+            Duration::from_secs(5),
+            client.get("http://refused.daily-ai.invalid/").send(),
+        )
+        .await
+        .expect("the request to a host off the allowlist went to the proxy")
+        .unwrap_err();
+        assert!(
+            error_chain(&refused).contains("not on the network allowlist"),
+            "{refused:?}"
+        );
+
+        let allowed =
+            tokio::spawn(
+                async move { client.get("http://allowed.daily-ai.invalid/").send().await },
+            );
+        let (mut stream, _) = proxy.accept().await.unwrap();
+        let mut request = vec![0; 1024];
+        let read = stream.read(&mut request).await.unwrap();
+        assert!(
+            String::from_utf8_lossy(&request[..read])
+                .starts_with("GET http://allowed.daily-ai.invalid/ HTTP/1.1")
+        );
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        assert_eq!(allowed.await.unwrap().unwrap().status(), 204);
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::format_description::BorrowedFormatItem;
+use time::macros::format_description;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+use tokio::fs;
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::time_utils::{self, datetime_to_macos_time, macos_to_datetime, past_ts};
+
+/// Gradle daemon log timestamps, e.g. `2025-01-31T10:15:30.123+0100`.
+const GRADLE_DATETIME: &[BorrowedFormatItem] = format_description!(
+    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond][offset_hour sign:mandatory][offset_minute]"
+);
+
+/// IntelliJ `idea.log` timestamps, in local time without an offset: `2025-01-31 10:15:30,123`.
+const IDEA_DATETIME: &[BorrowedFormatItem] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second],[subsecond]");
+
+/// Logged by IntelliJ's compile driver when a build ends, followed by the error and warning
+/// counts and the duration.
+const IDEA_FINISHED: &str = "COMPILATION FINISHED (BUILD PROCESS);";
+
+/// Whether a run built or tested the project.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildKind {
+    Build,
+    Test,
+}
+
+/// A build or test run started from an IDE or build daemon during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IdeBuild {
+    /// Where the run was logged: `Xcode`, `Gradle`, or an IntelliJ-platform IDE such as
+    /// `IntelliJIdea2024.3`.
+    pub tool: String,
+    /// Project or scheme name, or the directory the build ran in.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub project: Option<String>,
+    pub kind: BuildKind,
+    /// `None` when the log does not say, e.g. for a cancelled Xcode build.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub succeeded: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub errors: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub warnings: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(with = "crate::serde_helpers::option_offset_datetime")]
+    pub started: Option<OffsetDateTime>,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub finished: OffsetDateTime,
+}
+
+/// A value from an XML property list.
+#[derive(Debug, Clone, PartialEq)]
+enum Plist {
+    Dict(BTreeMap<String, Plist>),
+    Array(Vec<Plist>),
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Other,
+}
+
+impl Plist {
+    fn get(&self, key: &str) -> Option<&Plist> {
+        match self {
+            Plist::Dict(entries) => entries.get(key),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Plist::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Plist::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Replace the five predefined XML entities.
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// A minimal reader for the XML property lists Xcode writes its log manifests in.
+struct PlistReader<'a> {
+    rest: &'a str,
+}
+
+impl<'a> PlistReader<'a> {
+    /// The next element tag as `(name, closing, self_closing)`, skipping text, the XML
+    /// declaration, and the doctype.
+    fn tag(&mut self) -> Option<(&'a str, bool, bool)> {
+        loop {
+            let start = self.rest.find('<')?;
+            let end = start + self.rest[start..].find('>')?;
+            let tag = &self.rest[start + 1..end];
+            self.rest = &self.rest[end + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            let closing = tag.starts_with('/');
+            let self_closing = tag.ends_with('/');
+            let name = tag
+                .trim_start_matches('/')
+                .trim_end_matches('/')
+                .split_whitespace()
+                .next()?;
+            return Some((name, closing, self_closing));
+        }
+    }
+
+    /// Text up to the closing tag of `name`.
+    fn text(&mut self, name: &str) -> Option<String> {
+        let close = format!("</{name}>");
+        let end = self.rest.find(&close)?;
+        let text = unescape_xml(&self.rest[..end]);
+        self.rest = &self.rest[end + close.len()..];
+        Some(text)
+    }
+
+    /// The value whose opening tag was just read.
+    fn value(&mut self, name: &str, self_closing: bool) -> Option<Plist> {
+        if self_closing {
+            return Some(match name {
+                "true" => Plist::Bool(true),
+                "false" => Plist::Bool(false),
+                "dict" => Plist::Dict(BTreeMap::new()),
+                "array" => Plist::Array(Vec::new()),
+                "string" => Plist::String(String::new()),
+                _ => Plist::Other,
+            });
+        }
+        Some(match name {
+            "dict" => {
+                let mut entries = BTreeMap::new();
+                loop {
+                    match self.tag()? {
+                        ("dict", true, _) => break,
+                        ("key", false, false) => {
+                            let key = self.text("key")?;
+                            let (name, _, self_closing) = self.tag()?;
+                            entries.insert(key, self.value(name, self_closing)?);
+                        }
+                        _ => return None,
+                    }
+                }
+                Plist::Dict(entries)
+            }
+            "array" => {
+                let mut items = Vec::new();
+                loop {
+                    match self.tag()? {
+                        ("array", true, _) => break,
+                        (name, false, self_closing) => items.push(self.value(name, self_closing)?),
+                        _ => return None,
+                    }
+                }
+                Plist::Array(items)
+            }
+            "string" => Plist::String(self.text(name)?),
+            "integer" | "real" => Plist::Number(self.text(name)?.trim().parse().ok()?),
+            _ => {
+                self.text(name)?;
+                Plist::Other
+            }
+        })
+    }
+}
+
+/// Parse an XML property list. Binary property lists are not supported.
+fn parse_plist(xml: &str) -> Option<Plist> {
+    let mut reader = PlistReader { rest: xml };
+    let (name, _, _) = reader.tag()?;
+    if name != "plist" {
+        return None;
+    }
+    let (name, _, self_closing) = reader.tag()?;
+    reader.value(name, self_closing)
+}
+
+/// Runs listed in an Xcode `LogStoreManifest.plist` that finished after `since` (macOS seconds).
+///
+/// `project` names the DerivedData folder the manifest belongs to, used when a log has no
+/// scheme.
+fn xcode_runs(manifest: &Plist, project: &str, kind: BuildKind, since: f64) -> Vec<IdeBuild> {
+    let Some(Plist::Dict(logs)) = manifest.get("logs") else {
+        return Vec::new();
+    };
+    logs.values()
+        .filter_map(|log| {
+            let stopped = log.get("timeStoppedRecording")?.as_f64()?;
+            if stopped <= since {
+                return None;
+            }
+            let status = log.get("primaryObservable");
+            let count = |key: &str| status?.get(key)?.as_f64().map(|n| n as u64);
+            let succeeded = match status?.get("highLevelStatus")?.as_str()? {
+                "S" | "W" => Some(true),
+                "E" => Some(false),
+                _ => None,
+            };
+            let errors = match kind {
+                BuildKind::Build => count("totalNumberOfErrors"),
+                BuildKind::Test => count("totalNumberOfTestFailures"),
+            };
+            Some(IdeBuild {
+                tool: "Xcode".into(),
+                project: Some(
+                    log.get("schemeIdentifier-schemeName")
+                        .and_then(Plist::as_str)
+                        .unwrap_or(project)
+                        .to_string(),
+                ),
+                kind,
+                succeeded,
+                errors,
+                warnings: count("totalNumberOfWarnings"),
+                started: log
+                    .get("timeStartedRecording")
+                    .and_then(Plist::as_f64)
+                    .map(macos_to_datetime),
+                finished: macos_to_datetime(stopped),
+            })
+        })
+        .collect()
+}
+
+/// Build results in a Gradle daemon log that finished after `since`.
+///
+/// Builds are attributed to the `currentDir` of the last build command the daemon received.
+fn gradle_runs(log: &str, since: OffsetDateTime) -> Vec<IdeBuild> {
+    let mut project: Option<String> = None;
+    let mut runs = Vec::new();
+    for line in log.lines() {
+        if let Some((_, dir)) = line.split_once("currentDir=") {
+            let dir = dir.split([',', '}']).next().unwrap_or(dir).trim();
+            project = Path::new(dir)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            continue;
+        }
+        let (succeeded, took) = if let Some((_, took)) = line.split_once("BUILD SUCCESSFUL in ") {
+            (true, took)
+        } else if let Some((_, took)) = line.split_once("BUILD FAILED in ") {
+            (false, took)
+        } else {
+            continue;
+        };
+        let Some(finished) = line
+            .split_whitespace()
+            .next()
+            .and_then(|ts| OffsetDateTime::parse(ts, GRADLE_DATETIME).ok())
+        else {
+            continue;
+        };
+        if finished <= since {
+            continue;
+        }
+        let started = humantime::parse_duration(took.trim())
+            .ok()
+            .and_then(|took| Duration::try_from(took).ok())
+            .map(|took| finished - took);
+        runs.push(IdeBuild {
+            tool: "Gradle".into(),
+            project: project.clone(),
+            kind: BuildKind::Build,
+            succeeded: Some(succeeded),
+            errors: None,
+            warnings: None,
+            started,
+            finished,
+        });
+    }
+    runs
+}
+
+/// Builds in the `idea.log` of the IntelliJ-platform IDE `tool` that finished after `since`.
+/// The log does not say which project was built.
+fn idea_runs(log: &str, tool: &str, offset: UtcOffset, since: OffsetDateTime) -> Vec<IdeBuild> {
+    log.lines()
+        .filter_map(|line| {
+            let (_, result) = line.split_once(IDEA_FINISHED)?;
+            let finished = PrimitiveDateTime::parse(line.get(..23)?, IDEA_DATETIME)
+                .ok()?
+                .assume_offset(offset);
+            if finished <= since {
+                return None;
+            }
+            // `Errors: 0; warnings: 3 took 4512 ms: 0 min 4sec`
+            let number = |label: &str| -> Option<u64> {
+                let (_, rest) = result.split_once(label)?;
+                rest.trim_start()
+                    .split(|c: char| !c.is_ascii_digit())
+                    .next()?
+                    .parse()
+                    .ok()
+            };
+            let errors = number("Errors:");
+            Some(IdeBuild {
+                tool: tool.to_string(),
+                project: None,
+                kind: BuildKind::Build,
+                succeeded: errors.map(|errors| errors == 0),
+                errors,
+                warnings: number("warnings:"),
+                started: number("took").map(|ms| finished - Duration::milliseconds(ms as i64)),
+                finished,
+            })
+        })
+        .collect()
+}
+
+/// Subdirectories of `dir`, or none when it is unreadable.
+async fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return dirs;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+    dirs
+}
+
+/// Files directly in `dir` whose names satisfy `matches`.
+async fn files(dir: &Path, matches: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_name().to_str().is_some_and(&matches) {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Whether `path` was modified after `since`, so older logs are not read at all.
+async fn modified_since(path: &Path, since: OffsetDateTime) -> bool {
+    fs::metadata(path)
+        .await
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| OffsetDateTime::from(modified) > since)
+}
+
+/// Xcode builds and test runs from the manifests under `DerivedData`.
+async fn xcode_builds(home: &Path, since: OffsetDateTime) -> Vec<IdeBuild> {
+    let derived_data = env::var("XCODE_DERIVED_DATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join("Library/Developer/Xcode/DerivedData"));
+    let mut builds = Vec::new();
+    for project_dir in subdirs(&derived_data).await {
+        let name = project_dir
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        // Folders are named `<Project>-<hash>`.
+        let project = name.rsplit_once('-').map_or(&*name, |(project, _)| project);
+        for (dir, kind) in [("Build", BuildKind::Build), ("Test", BuildKind::Test)] {
+            let manifest = project_dir
+                .join("Logs")
+                .join(dir)
+                .join("LogStoreManifest.plist");
+            if !modified_since(&manifest, since).await {
+                continue;
+            }
+            let Ok(xml) = fs::read_to_string(&manifest).await else {
+                debug!("Skipping unreadable {}", manifest.display());
+                continue;
+            };
+            match parse_plist(&xml) {
+                Some(plist) => builds.extend(xcode_runs(
+                    &plist,
+                    project,
+                    kind,
+                    datetime_to_macos_time(&since),
+                )),
+                None => debug!("Unable to parse {}", manifest.display()),
+            }
+        }
+    }
+    builds
+}
+
+/// Gradle builds from the daemon logs.
+async fn gradle_builds(home: &Path, since: OffsetDateTime) -> Vec<IdeBuild> {
+    let gradle_home = env::var("GRADLE_USER_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".gradle"));
+    let mut builds = Vec::new();
+    for version_dir in subdirs(&gradle_home.join("daemon")).await {
+        for log in files(&version_dir, |name| name.ends_with(".out.log")).await {
+            if !modified_since(&log, since).await {
+                continue;
+            }
+            if let Ok(text) = fs::read_to_string(&log).await {
+                builds.extend(gradle_runs(&text, since));
+            }
+        }
+    }
+    builds
+}
+
+/// IntelliJ-platform builds from each product's `idea.log`.
+async fn idea_builds(home: &Path, since: OffsetDateTime) -> Vec<IdeBuild> {
+    let offset = time_utils::local_offset();
+    let mut logs = Vec::new();
+    for product in subdirs(&home.join("Library/Logs/JetBrains")).await {
+        logs.push((product.join("idea.log"), product));
+    }
+    for product in subdirs(&home.join(".cache/JetBrains")).await {
+        logs.push((product.join("log/idea.log"), product));
+    }
+    let mut builds = Vec::new();
+    for (log, product) in logs {
+        if !modified_since(&log, since).await {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(&log).await else {
+            continue;
+        };
+        // Product folders are named like `IntelliJIdea2024.3` or `AndroidStudio2024.2`.
+        let tool = product.file_name().unwrap_or_default().to_string_lossy();
+        builds.extend(idea_runs(&text, &tool, offset, since));
+    }
+    builds
+}
+
+/// Collect builds and test runs started from Xcode, Gradle, and IntelliJ-platform IDEs during
+/// the window, with their outcome.
+///
+/// Tools without logs are skipped; nothing fails when none is installed.
+#[tracing::instrument(name = "Collecting IDE builds", level = "info")]
+pub async fn get_ide_builds(duration: &Duration) -> AppResult<Vec<IdeBuild>> {
+    let Some(home) = env::home_dir().or_else(|| env::var("HOME").ok().map(PathBuf::from)) else {
+        return Ok(Vec::new());
+    };
+    let since = past_ts(duration);
+    let mut builds = xcode_builds(&home, since).await;
+    builds.extend(gradle_builds(&home, since).await);
+    builds.extend(idea_builds(&home, since).await);
+    builds.sort_by_key(|build| build.finished);
+    trace!("IDE builds: {builds:?}");
+    debug!("Found {} IDE builds", builds.len());
+    Ok(builds)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, offset};
+
+    use super::*;
+
+    #[test]
+    fn reads_xcode_manifests() {
+        let stopped = datetime_to_macos_time(&datetime!(2025-01-31 10:00:30 UTC));
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>logFormatVersion</key>
+	<integer>11</integer>
+	<key>logs</key>
+	<dict>
+		<key>A1</key>
+		<dict>
+			<key>hasPrimaryLog</key>
+			<true/>
+			<key>primaryObservable</key>
+			<dict>
+				<key>highLevelStatus</key>
+				<string>E</string>
+				<key>totalNumberOfErrors</key>
+				<integer>2</integer>
+				<key>totalNumberOfWarnings</key>
+				<integer>5</integer>
+			</dict>
+			<key>schemeIdentifier-schemeName</key>
+			<string>Weather &amp; Tides</string>
+			<key>timeStartedRecording</key>
+			<real>{}</real>
+			<key>timeStoppedRecording</key>
+			<real>{stopped}</real>
+		</dict>
+		<key>A2</key>
+		<dict>
+			<key>primaryObservable</key>
+			<dict>
+				<key>highLevelStatus</key>
+				<string>S</string>
+			</dict>
+			<key>timeStoppedRecording</key>
+			<real>1.0</real>
+		</dict>
+	</dict>
+</dict>
+</plist>"#,
+            stopped - 30.0
+        );
+
+        let plist = parse_plist(&xml).unwrap();
+        let since = datetime_to_macos_time(&datetime!(2025-01-31 00:00 UTC));
+        let runs = xcode_runs(&plist, "Weather", BuildKind::Build, since);
+
+        assert_eq!(
+            runs,
+            [IdeBuild {
+                tool: "Xcode".into(),
+                project: Some("Weather & Tides".into()),
+                kind: BuildKind::Build,
+                succeeded: Some(false),
+                errors: Some(2),
+                warnings: Some(5),
+                started: Some(datetime!(2025-01-31 10:00 UTC)),
+                finished: datetime!(2025-01-31 10:00:30 UTC),
+            }]
+        );
+    }
+
+    #[test]
+    fn reads_gradle_and_intellij_logs() {
+        let since = datetime!(2025-01-31 00:00 UTC);
+        let gradle = "\
+2025-01-31T10:00:00.000+0000 [DEBUG] [org.gradle.launcher.daemon.server.exec.ExecuteBuild] Executing build: Build{id=1, currentDir=/src/android-app}
+2025-01-31T10:01:04.500+0000 [LIFECYCLE] [org.gradle.internal.buildevents.BuildResultLogger] BUILD FAILED in 1m 4s
+2025-01-30T09:00:00.000+0000 [LIFECYCLE] [org.gradle.internal.buildevents.BuildResultLogger] BUILD SUCCESSFUL in 3s";
+        let runs = gradle_runs(gradle, since);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].project.as_deref(), Some("android-app"));
+        assert_eq!(runs[0].succeeded, Some(false));
+        assert_eq!(runs[0].started, Some(datetime!(2025-01-31 10:00:00.5 UTC)));
+
+        let idea = "\
+2025-01-31 11:00:00,000 [ 100]   INFO - #c.i.c.i.CompileDriver - COMPILATION STARTED (BUILD PROCESS)
+2025-01-31 11:00:04,512 [ 104]   INFO - #c.i.c.i.CompileDriver - COMPILATION FINISHED (BUILD PROCESS); Errors: 0; warnings: 3 took 4512 ms: 0 min 4sec";
+        let runs = idea_runs(idea, "IntelliJIdea2024.3", offset!(+1), since);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].succeeded, Some(true));
+        assert_eq!((runs[0].errors, runs[0].warnings), (Some(0), Some(3)));
+        assert_eq!(runs[0].finished, datetime!(2025-01-31 10:00:04.512 UTC));
+        assert_eq!(runs[0].started, Some(datetime!(2025-01-31 10:00 UTC)));
+    }
+}
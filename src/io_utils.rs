@@ -1,15 +1,24 @@
 use std::collections::HashSet;
+use std::env;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize, ser};
+use time::OffsetDateTime;
+use time::macros::format_description;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
-use tracing::debug;
+use tracing::{debug, info, warn};
 
 use crate::AppResult;
 use crate::cli::OutputFormat;
-use crate::context::FullContext;
+use crate::context::{FullContext, SourceKind};
+use crate::error::AppError;
 use crate::git::diff::{DiffFromTo, DiffSummary, DiffWithPatch};
+use crate::history;
+use crate::render::{ics, jsonl, markdown, obsidian, org, pdf, standup, terminal, text};
+use crate::time_utils;
 
 /// Aggregated view of paths per repository used when writing summaries to disk.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -24,19 +33,300 @@ pub struct RepoPathsSummary {
     pub conflicted: HashSet<PathBuf>,
 }
 
-/// Write output in the requested format (json or directory layout).
+/// Values substituted for `{{name}}` placeholders in `--output` paths.
+#[derive(Debug, Clone)]
+pub struct OutputVars<'a> {
+    /// When the run started, in local time.
+    pub now: OffsetDateTime,
+    /// Active profile name; `default` when unset.
+    pub profile: Option<&'a str>,
+}
+
+impl OutputVars<'_> {
+    /// Variables for a run starting now.
+    pub fn now() -> Self {
+        OutputVars {
+            now: time_utils::now_local(),
+            profile: None,
+        }
+    }
+
+    /// Value of a template variable, or `None` if the name is unknown.
+    fn lookup(&self, name: &str) -> AppResult<Option<String>> {
+        let now = self.now;
+        Ok(Some(match name {
+            "date" => now.format(format_description!("[year]-[month]-[day]"))?,
+            "time" => now.format(format_description!("[hour]-[minute]"))?,
+            "year" => now.format(format_description!("[year]"))?,
+            "month" => now.format(format_description!("[month]"))?,
+            "day" => now.format(format_description!("[day]"))?,
+            "week" => now.format(format_description!("[week_number repr:iso]"))?,
+            "weekday" => now.weekday().to_string(),
+            "profile" => self.profile.unwrap_or("default").to_string(),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+/// Expand `{{date}}`-style placeholders and a leading `~` in an output path.
+///
+/// Supported variables are `date` (`2025-01-31`), `time` (`09-30`), `year`, `month`, `day`,
+/// `week` (ISO week number), `weekday` (`Friday`), and `profile`. Unknown variables are an
+/// error rather than being written literally into the file name.
+pub fn expand_output_path(output: &Path, vars: &OutputVars<'_>) -> AppResult<PathBuf> {
+    let template = output.to_string_lossy();
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template.as_ref();
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + len].trim();
+        let value = vars.lookup(name)?.ok_or_else(|| {
+            AppError::Config(format!(
+                "Unknown variable {{{{{name}}}}} in output path {template}"
+            ))
+        })?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&value);
+        rest = &rest[start + len + 2..];
+    }
+    expanded.push_str(rest);
+
+    let home_relative = expanded
+        .strip_prefix("~/")
+        .or_else(|| (expanded == "~").then_some(""));
+    match (home_relative, env::home_dir()) {
+        (Some(relative), Some(home)) => Ok(home.join(relative)),
+        _ => Ok(PathBuf::from(expanded)),
+    }
+}
+
+/// Output format implied by the extension of `output`, or `Dir` for a trailing separator.
+fn format_for_path(output: &Path) -> Option<OutputFormat> {
+    let raw = output.to_string_lossy();
+    if raw.ends_with('/') || raw.ends_with(std::path::MAIN_SEPARATOR) {
+        return Some(OutputFormat::Dir);
+    }
+    match output.extension()?.to_str()?.to_lowercase().as_str() {
+        "json" => Some(OutputFormat::Json),
+        "jsonl" | "ndjson" => Some(OutputFormat::Jsonl),
+        "md" | "markdown" => Some(OutputFormat::Markdown),
+        "txt" | "text" => Some(OutputFormat::Text),
+        "pdf" => Some(OutputFormat::Pdf),
+        "org" => Some(OutputFormat::Org),
+        "db" | "sqlite" | "sqlite3" => Some(OutputFormat::Sqlite),
+        "ics" | "ical" => Some(OutputFormat::Ics),
+        _ => None,
+    }
+}
+
+/// Whether `format` is written as a plain-text document, which any text file extension suits.
+fn is_text_document(format: &OutputFormat) -> bool {
+    matches!(
+        format,
+        OutputFormat::Markdown | OutputFormat::Text | OutputFormat::Standup | OutputFormat::Org
+    )
+}
+
+/// Pick the format to write `output` in.
+///
+/// An explicit `--format` wins, but is an error when the extension of `output` implies a
+/// different format, unless both are text documents, e.g. `--format standup` to `standup.txt`. Without `--format`, the extension decides, falling back to JSON.
+pub fn resolve_output_format(
+    output: &Path,
+    format: Option<&OutputFormat>,
+) -> AppResult<OutputFormat> {
+    match (format, format_for_path(output)) {
+        // A vault is a directory, so a trailing separator is expected.
+        (Some(OutputFormat::Obsidian), Some(OutputFormat::Dir)) => Ok(OutputFormat::Obsidian),
+        (Some(explicit), Some(inferred))
+            if *explicit != inferred
+                && !(is_text_document(explicit) && is_text_document(&inferred)) =>
+        {
+            Err(AppError::Config(format!(
+                "--format {} conflicts with --output {}, which implies --format {}",
+                format_name(explicit),
+                output.display(),
+                format_name(&inferred)
+            )))
+        }
+        (Some(explicit), _) => Ok(explicit.clone()),
+        (None, Some(inferred)) => Ok(inferred),
+        (None, None) => {
+            if output.extension().is_some() {
+                warn!(
+                    "No output format matches {}; writing JSON (pass --format to choose)",
+                    output.display()
+                );
+            }
+            Ok(OutputFormat::Json)
+        }
+    }
+}
+
+/// The `--format` value naming `format`.
+pub fn format_name(format: &OutputFormat) -> String {
+    format
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_else(|| format!("{format:?}"))
+}
+
+/// Write output in the requested format (json, json lines, directory layout, markdown, plain
+/// text, pdf, org, a stand-up update, a row appended to a SQLite database, a section of an
+/// Obsidian daily note, or an iCalendar file).
+///
+/// Placeholders in `output` are expanded with [`expand_output_path`], and missing parent
+/// directories are created so templated paths like `~/journal/{{year}}/{{date}}.md` work.
 #[tracing::instrument(name = "Saving output to disk", level = "info", skip(context))]
 pub async fn write_output<P: AsRef<Path> + std::fmt::Debug>(
     output: P,
+    vars: &OutputVars<'_>,
     format: &OutputFormat,
     context: &FullContext,
 ) -> AppResult<()> {
+    let output = expand_output_path(output.as_ref(), vars)?;
+    debug!("Writing output to {}", output.display());
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).await?;
+    }
     match format {
         OutputFormat::Json => write_json_output(output, context).await,
         OutputFormat::Dir => write_dir_output(output, context).await,
+        OutputFormat::Markdown => write_file(output, markdown::render(context)).await,
+        OutputFormat::Text => write_file(output, text::render(context)).await,
+        OutputFormat::Pdf => write_file(output, pdf::render(context)?).await,
+        OutputFormat::Org => write_file(output, org::render(context)).await,
+        OutputFormat::Standup => write_file(output, standup::render(context)).await,
+        OutputFormat::Jsonl => write_file(output, jsonl::render(context)?).await,
+        OutputFormat::Sqlite => history::append_to(output, context).await,
+        OutputFormat::Obsidian => write_obsidian_output(&output, vars, context).await,
+        OutputFormat::Ics => write_file(output, ics::render(context)?).await,
+    }
+}
+
+/// Merge the run into the daily note for `vars.now` in the vault at `vault`.
+///
+/// Daily notes go in the folder set in the vault's daily notes settings
+/// (`.obsidian/daily-notes.json`), or the vault root.
+async fn write_obsidian_output(
+    vault: &Path,
+    vars: &OutputVars<'_>,
+    context: &FullContext,
+) -> AppResult<()> {
+    let folder = match fs::read_to_string(vault.join(".obsidian/daily-notes.json")).await {
+        Ok(settings) => serde_json::from_str::<serde_json::Value>(&settings)?["folder"]
+            .as_str()
+            .map(|folder| folder.trim_matches('/').to_string())
+            .unwrap_or_default(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let dir = vault.join(folder);
+    fs::create_dir_all(&dir).await?;
+    let note = dir.join(format!("{}.md", vars.lookup("date")?.unwrap_or_default()));
+    let existing = match fs::read_to_string(&note).await {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    write_file(
+        &note,
+        obsidian::merge(&existing, &obsidian::section(context)),
+    )
+    .await?;
+    info!("Updated the daily note {}", note.display());
+    Ok(())
+}
+
+/// Show a run's output on stdout when no `--output` is given.
+///
+/// On a terminal, summaries are rendered from Markdown to ANSI and everything is shown through
+/// `$PAGER`. Otherwise the plain text is printed: Markdown with `--format markdown`, plain text,
+/// Org, the stand-up update, JSON Lines, or iCalendar with `--format text`, `org`, `standup`,
+/// `jsonl`, or `ics`, JSON otherwise.
+/// `--format pdf` is only written to a pipe, never to the terminal, and `--format sqlite` and
+/// `obsidian` always need `--output`.
+pub async fn print_output(format: Option<&OutputFormat>, context: &FullContext) -> AppResult<()> {
+    let tty = std::io::stdout().is_terminal();
+    if format == Some(&OutputFormat::Sqlite) {
+        return Err(AppError::Config(
+            "--format sqlite needs --output, e.g. `--output runs.db`".into(),
+        ));
+    }
+    if format == Some(&OutputFormat::Obsidian) {
+        return Err(AppError::Config(
+            "--format obsidian needs --output pointing at the vault, e.g. `--output ~/Notes/`"
+                .into(),
+        ));
+    }
+    if format == Some(&OutputFormat::Pdf) {
+        if tty {
+            return Err(AppError::Config(
+                "--format pdf needs --output or a pipe, e.g. `--format pdf > summary.pdf`".into(),
+            ));
+        }
+        return terminal::print(pdf::render(context)?);
+    }
+    let as_markdown = match format {
+        Some(format) => *format == OutputFormat::Markdown,
+        None => tty && context.summary.is_some(),
+    };
+    let mut text = match format {
+        Some(OutputFormat::Text) => text::render(context),
+        Some(OutputFormat::Org) => org::render(context),
+        Some(OutputFormat::Standup) => standup::render(context),
+        Some(OutputFormat::Jsonl) => jsonl::render(context)?,
+        Some(OutputFormat::Ics) => ics::render(context)?,
+        _ if as_markdown => {
+            let rendered = markdown::render(context);
+            if tty {
+                terminal::to_terminal(&rendered)
+            } else {
+                rendered
+            }
+        }
+        _ => serde_json::to_string_pretty(context)?,
+    };
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    if tty {
+        terminal::page(&text).await
+    } else {
+        terminal::print(&text)
     }
 }
 
+/// Write a generated Markdown document to `output`, or show it like a summary when there is none:
+/// rendered and paged on a terminal, plain otherwise.
+pub async fn output_markdown(output: Option<&Path>, markdown: &str) -> AppResult<()> {
+    match output {
+        Some(output) => output_file(output, &OutputVars::now(), markdown).await,
+        None if std::io::stdout().is_terminal() => {
+            terminal::page(&terminal::to_terminal(markdown)).await
+        }
+        None => terminal::print(markdown),
+    }
+}
+
+/// Write a rendered document to `output`, expanding its placeholders with `vars` and creating
+/// missing parent directories.
+pub async fn output_file(
+    output: &Path,
+    vars: &OutputVars<'_>,
+    data: impl AsRef<[u8]>,
+) -> AppResult<()> {
+    let output = expand_output_path(output, vars)?;
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).await?;
+    }
+    write_file(&output, data).await?;
+    info!("Wrote {}", output.display());
+    Ok(())
+}
+
 /// Write output to a directory structure.
 #[tracing::instrument(
     name = "Creating directories and writing output",
@@ -50,17 +340,32 @@ async fn write_dir_output<P: AsRef<Path> + std::fmt::Debug>(
     // Ensure base output directory exists.
     fs::create_dir_all(&output).await?;
 
-    // Write shell history
-    let shell_history_path = output.as_ref().join("shell_history.json");
-    write_json_output(shell_history_path, &context.shell_history).await?;
+    // Write each collected source; git history gets a directory per repository below.
+    for section in context.context.sections() {
+        if section.kind() == SourceKind::CommitHistory {
+            continue;
+        }
+        let section_path = output
+            .as_ref()
+            .join(format!("{}.json", section.kind().name()));
+        write_json_output(section_path, &section.items_json()?).await?;
+    }
+
+    // Record the run ID so a directory can be matched to its archive entry and exports
+    if let Some(run_id) = context.run_id {
+        let run_path = output.as_ref().join("run.json");
+        write_json_output(run_path, &serde_json::json!({ "run_id": run_id })).await?;
+    }
 
-    // Write safari history
-    let safari_history_path = output.as_ref().join("safari_history.json");
-    write_json_output(safari_history_path, &context.safari_history).await?;
+    // Write data quality warnings
+    if !context.data_quality.is_empty() {
+        let data_quality_path = output.as_ref().join("data_quality.json");
+        write_json_output(data_quality_path, &context.data_quality).await?;
+    }
 
     // Write git commit histories
     let mut unknown_repo_count = 1;
-    for repo_history in &context.commit_history {
+    for repo_history in context.context.commit_history() {
         let DiffSummary {
             repo_path,
             unmodified,
@@ -139,18 +444,42 @@ async fn write_json_output<P: AsRef<Path> + std::fmt::Debug, S: ser::Serialize>(
 }
 
 /// Write raw string data to a file, overwriting any existing content.
-async fn write_file<P: AsRef<Path> + std::fmt::Debug>(output: P, data: String) -> AppResult<()> {
+async fn write_file<P: AsRef<Path> + std::fmt::Debug, D: AsRef<[u8]>>(
+    output: P,
+    data: D,
+) -> AppResult<()> {
     let mut file = fs::OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
         .open(output)
         .await?;
-    file.write_all(data.as_bytes()).await?;
+    file.write_all(data.as_ref()).await?;
     file.flush().await?;
     Ok(())
 }
 
+/// Overwrite a file with zeros and flush it to disk before removing it.
+///
+/// This is best effort: copy-on-write and journaling filesystems or SSD wear levelling may keep
+/// older copies of the blocks around.
+#[tracing::instrument(name = "Shredding file", level = "debug")]
+pub async fn shred_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> AppResult<()> {
+    let len = fs::metadata(&path).await?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(&path).await?;
+    let zeros = vec![0u8; 64 * 1024];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk]).await?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all().await?;
+    drop(file);
+    fs::remove_file(&path).await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::HashSet, path::PathBuf};
@@ -160,6 +489,7 @@ mod tests {
 
     use crate::{
         classify::UrlCluster,
+        context::Context,
         git::diff::{DiffFromTo, DiffSummary, DiffWithPatch},
         git::hist::{CommitMeta, GitRepoHistory},
         safari::SafariHistoryItem,
@@ -214,19 +544,29 @@ mod tests {
             conflicted: HashSet::new(),
         };
         let commits = vec![CommitMeta {
+            id: String::new(),
             summary: "init".into(),
             body: None,
             timestamp: OffsetDateTime::UNIX_EPOCH,
             branches: vec!["main".into()],
         }];
-        let commit_history = vec![GitRepoHistory { diff, commits }];
+        let commit_history = vec![GitRepoHistory {
+            diff,
+            commits,
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        }];
 
-        FullContext {
-            shell_history,
-            safari_history,
-            commit_history,
-            summary: None,
-        }
+        FullContext::from(
+            Context::default()
+                .with_shell_history(shell_history)
+                .with_safari_history(safari_history)
+                .with_commit_history(commit_history),
+        )
     }
 
     #[tokio::test]
@@ -296,6 +636,19 @@ mod tests {
         let _ = fs::remove_dir_all(dir).await;
     }
 
+    #[tokio::test]
+    async fn shred_file_removes_file() {
+        let dir = temp_dir("shred");
+        fs::create_dir_all(&dir).await.unwrap();
+        let file = dir.join("secret.bin");
+        fs::write(&file, vec![7u8; 100_000]).await.unwrap();
+
+        shred_file(&file).await.unwrap();
+
+        assert!(!file.exists());
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
     #[tokio::test]
     async fn write_output_json_writes_single_file() {
         let dir = temp_dir("write_output_json");
@@ -303,7 +656,7 @@ mod tests {
         let file = dir.join("output.json");
         let context = sample_context();
 
-        write_output(&file, &OutputFormat::Json, &context)
+        write_output(&file, &OutputVars::now(), &OutputFormat::Json, &context)
             .await
             .unwrap();
 
@@ -311,4 +664,77 @@ mod tests {
         assert!(contents.contains("shell_history"));
         let _ = fs::remove_dir_all(dir).await;
     }
+
+    #[tokio::test]
+    async fn write_output_expands_templated_paths() {
+        let dir = temp_dir("write_output_template");
+        let vars = OutputVars {
+            now: time::macros::datetime!(2025-01-31 09:05 UTC),
+            profile: Some("work"),
+        };
+
+        assert_eq!(
+            expand_output_path(
+                Path::new("/j/{{year}}/{{ date }}-{{weekday}}-{{profile}}.md"),
+                &vars
+            )
+            .unwrap(),
+            PathBuf::from("/j/2025/2025-01-31-Friday-work.md")
+        );
+        assert!(expand_output_path(Path::new("/j/{{nope}}.md"), &vars).is_err());
+
+        write_output(
+            dir.join("{{month}}/{{date}}.json"),
+            &vars,
+            &OutputFormat::Json,
+            &sample_context(),
+        )
+        .await
+        .unwrap();
+
+        assert!(dir.join("01/2025-01-31.json").exists());
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
+    #[test]
+    fn infers_output_format_from_extension() {
+        let resolve = |path: &str, format| resolve_output_format(Path::new(path), format);
+
+        assert_eq!(resolve("report.md", None).unwrap(), OutputFormat::Markdown);
+        assert_eq!(
+            resolve("~/j/{{date}}.JSON", None).unwrap(),
+            OutputFormat::Json
+        );
+        assert_eq!(resolve("out/", None).unwrap(), OutputFormat::Dir);
+        assert_eq!(resolve("{{date}}.pdf", None).unwrap(), OutputFormat::Pdf);
+        assert_eq!(resolve("runs.sqlite", None).unwrap(), OutputFormat::Sqlite);
+        assert_eq!(resolve("report.html", None).unwrap(), OutputFormat::Json);
+        assert_eq!(
+            resolve("report.txt", Some(&OutputFormat::Markdown)).unwrap(),
+            OutputFormat::Markdown
+        );
+        assert_eq!(
+            resolve("report.md", Some(&OutputFormat::Markdown)).unwrap(),
+            OutputFormat::Markdown
+        );
+        assert_eq!(
+            resolve("standup.txt", Some(&OutputFormat::Standup)).unwrap(),
+            OutputFormat::Standup
+        );
+        assert_eq!(
+            resolve("standup.md", Some(&OutputFormat::Standup)).unwrap(),
+            OutputFormat::Standup
+        );
+        assert_eq!(
+            resolve("report.md", Some(&OutputFormat::Text)).unwrap(),
+            OutputFormat::Text
+        );
+        assert!(resolve("report.txt", Some(&OutputFormat::Pdf)).is_err());
+
+        let err = resolve("report.md", Some(&OutputFormat::Json)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--format json conflicts with --output report.md, which implies --format markdown"
+        );
+    }
 }
@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::shell::ShellHistoryEntry;
+
+/// Workload kinds looked up when enriching activity from the cluster.
+const WORKLOAD_KINDS: &str = "deployments,statefulsets,daemonsets,cronjobs,jobs";
+
+/// A workload in a namespace that was touched by a command.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct K8sWorkload {
+    pub kind: String,
+    pub name: String,
+    /// Ready replicas, when the kind has replicas.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ready: Option<i64>,
+    /// Desired replicas, when the kind has replicas.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub desired: Option<i64>,
+}
+
+/// `kubectl`/`helm` usage against one cluster context and namespace.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct K8sActivity {
+    /// kubeconfig context. `None` when commands relied on the current context and the cluster
+    /// was not queried.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub context: Option<String>,
+    /// Namespace. `None` when commands relied on the context's default namespace.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub namespace: Option<String>,
+    /// Number of times each `tool verb` pair (e.g. `kubectl logs`, `helm upgrade`) was run.
+    pub verbs: BTreeMap<String, usize>,
+    /// Resources and Helm releases named on the command line, e.g. `deployment/api` or `pods`.
+    pub resources: Vec<String>,
+    /// Workloads the resources resolve to. Only filled when the cluster is queried.
+    #[serde(default)]
+    pub workloads: Vec<K8sWorkload>,
+    pub failed_commands: usize,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_used: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_used: OffsetDateTime,
+}
+
+/// One `kubectl` or `helm` invocation parsed from a shell command.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct K8sInvocation {
+    tool: String,
+    verb: String,
+    context: Option<String>,
+    namespace: Option<String>,
+    resources: Vec<String>,
+}
+
+/// Flags that take a separate value, so the value is not mistaken for a positional argument.
+fn flag_takes_value(flag: &str) -> bool {
+    matches!(
+        flag,
+        "-n" | "--namespace"
+            | "--context"
+            | "--kube-context"
+            | "--kubeconfig"
+            | "-l"
+            | "--selector"
+            | "-o"
+            | "--output"
+            | "-f"
+            | "--filename"
+            | "-c"
+            | "--container"
+            | "--cluster"
+            | "--user"
+            | "-s"
+            | "--server"
+            | "--since"
+            | "--tail"
+            | "--version"
+            | "--values"
+            | "--set"
+            | "--set-string"
+            | "--timeout"
+            | "--replicas"
+            | "--image"
+            | "-p"
+            | "--patch"
+            | "--type"
+            | "--field-selector"
+    )
+}
+
+/// Parse the `kubectl`/`helm` invocations in one shell command line.
+///
+/// Pipelines and command lists (`|`, `&&`, `;`) are split so `kubectl get pods | grep api` and
+/// `cd infra && helm upgrade ...` are both found. Leading `sudo`, `env`, and `VAR=value`
+/// assignments are skipped.
+fn parse_command(command: &str) -> Vec<K8sInvocation> {
+    let mut invocations = Vec::new();
+    for segment in command.split(['|', ';', '&', '\n']) {
+        let mut words = segment.split_whitespace().peekable();
+        while let Some(word) = words.peek() {
+            if *word == "sudo" || *word == "env" || word.contains('=') {
+                words.next();
+            } else {
+                break;
+            }
+        }
+        let Some(tool) = words.next() else {
+            continue;
+        };
+        let tool = tool.rsplit('/').next().unwrap_or(tool);
+        if tool != "kubectl" && tool != "helm" {
+            continue;
+        }
+
+        let mut invocation = K8sInvocation {
+            tool: tool.to_string(),
+            ..Default::default()
+        };
+        let mut positional = Vec::new();
+        let mut words = words.map(|w| w.trim_matches(['"', '\'']));
+        while let Some(word) = words.next() {
+            if word == "--" {
+                break;
+            }
+            if !word.starts_with('-') {
+                positional.push(word.to_string());
+                continue;
+            }
+            let (flag, inline) = match word.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_string())),
+                None => (word, None),
+            };
+            let value = if inline.is_some() || !flag_takes_value(flag) {
+                inline
+            } else {
+                words.next().map(str::to_string)
+            };
+            match flag {
+                "-n" | "--namespace" => invocation.namespace = value,
+                "--context" | "--kube-context" => invocation.context = value,
+                "-A" | "--all-namespaces" => invocation.namespace = Some("*".into()),
+                _ => {}
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        invocation.verb = positional.next().unwrap_or_default();
+        // `kubectl rollout restart deploy/api` and `kubectl config use-context x` have sub-verbs.
+        if matches!(
+            invocation.verb.as_str(),
+            "rollout" | "config" | "auth" | "certificate" | "repo"
+        ) && let Some(sub) = positional.next()
+        {
+            invocation.verb = format!("{} {sub}", invocation.verb);
+        }
+        let rest: Vec<String> = positional.collect();
+        invocation.resources = if invocation.tool == "helm" {
+            // `helm upgrade <release> <chart>`: the release is the interesting name.
+            rest.into_iter().take(1).collect()
+        } else {
+            match rest.as_slice() {
+                // `get pods api-7d9f` or `describe deployment api`
+                [kind, names @ ..] if !kind.contains('/') && !names.is_empty() => {
+                    names.iter().map(|name| format!("{kind}/{name}")).collect()
+                }
+                _ => rest,
+            }
+        };
+        invocations.push(invocation);
+    }
+    invocations
+}
+
+/// Group `kubectl`/`helm` usage in shell history by context and namespace.
+pub fn find_k8s_activity(shell_history: &[ShellHistoryEntry]) -> Vec<K8sActivity> {
+    let mut grouped: BTreeMap<(Option<String>, Option<String>), K8sActivity> = BTreeMap::new();
+    for entry in shell_history {
+        for invocation in parse_command(&entry.command) {
+            let activity = grouped
+                .entry((invocation.context.clone(), invocation.namespace.clone()))
+                .or_insert_with(|| K8sActivity {
+                    context: invocation.context.clone(),
+                    namespace: invocation.namespace.clone(),
+                    verbs: BTreeMap::new(),
+                    resources: Vec::new(),
+                    workloads: Vec::new(),
+                    failed_commands: 0,
+                    first_used: entry.date_time,
+                    last_used: entry.date_time,
+                });
+            let verb = format!("{} {}", invocation.tool, invocation.verb);
+            *activity.verbs.entry(verb.trim().to_string()).or_default() += 1;
+            for resource in invocation.resources {
+                if !activity.resources.contains(&resource) {
+                    activity.resources.push(resource);
+                }
+            }
+            if entry.exit_code != 0 {
+                activity.failed_commands += 1;
+            }
+            activity.first_used = activity.first_used.min(entry.date_time);
+            activity.last_used = activity.last_used.max(entry.date_time);
+        }
+    }
+    let mut activity: Vec<K8sActivity> = grouped.into_values().collect();
+    activity.sort_by_key(|a| a.first_used);
+    activity
+}
+
+/// Run `kubectl` and return its stdout, or `None` when it is missing or the call fails.
+async fn run_kubectl(args: &[&str]) -> AppResult<Option<String>> {
+    let output = match Command::new("kubectl").args(args).output().await {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("kubectl is not installed");
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !output.status.success() {
+        debug!(
+            "kubectl {} exited with {}: {}",
+            args.join(" "),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Parse `kubectl get <kinds> -o json` output into workloads.
+fn parse_workloads(output: &str) -> Vec<K8sWorkload> {
+    let Ok(value) = serde_json::from_str::<Value>(output) else {
+        return Vec::new();
+    };
+    value["items"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    Some(K8sWorkload {
+                        kind: item["kind"].as_str()?.to_string(),
+                        name: item["metadata"]["name"].as_str()?.to_string(),
+                        ready: item["status"]["readyReplicas"]
+                            .as_i64()
+                            .or_else(|| item["status"]["numberReady"].as_i64()),
+                        desired: item["spec"]["replicas"]
+                            .as_i64()
+                            .or_else(|| item["status"]["desiredNumberScheduled"].as_i64()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a command-line resource (`deploy/api`, `pods/api-7d9f-x2k`, `api`) refers to `workload`.
+///
+/// Pod names are matched by prefix, since they are derived from their workload's name.
+fn touches(resource: &str, workload: &K8sWorkload) -> bool {
+    let name = resource.rsplit('/').next().unwrap_or(resource);
+    name == workload.name
+        || name
+            .strip_prefix(&workload.name)
+            .is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// Fill in the current context and the workloads each group's resources refer to.
+async fn enrich(activity: &mut [K8sActivity]) -> AppResult<()> {
+    let current = run_kubectl(&["config", "current-context"])
+        .await?
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    let mut cache: BTreeMap<(String, String), Vec<K8sWorkload>> = BTreeMap::new();
+    for group in activity.iter_mut() {
+        if group.context.is_none() {
+            group.context = current.clone();
+        }
+        let (Some(context), Some(namespace)) = (&group.context, &group.namespace) else {
+            continue;
+        };
+        if namespace == "*" || group.resources.is_empty() {
+            continue;
+        }
+        let key = (context.clone(), namespace.clone());
+        if !cache.contains_key(&key) {
+            let workloads = run_kubectl(&[
+                "get",
+                WORKLOAD_KINDS,
+                "--context",
+                context,
+                "--namespace",
+                namespace,
+                "--request-timeout",
+                "10s",
+                "-o",
+                "json",
+            ])
+            .await?
+            .map(|output| parse_workloads(&output))
+            .unwrap_or_default();
+            cache.insert(key.clone(), workloads);
+        }
+        let mut seen = BTreeSet::new();
+        group.workloads = cache[&key]
+            .iter()
+            .filter(|w| group.resources.iter().any(|r| touches(r, w)))
+            .filter(|w| seen.insert((w.kind.clone(), w.name.clone())))
+            .cloned()
+            .collect();
+    }
+    Ok(())
+}
+
+/// Collect Kubernetes activity from `kubectl`/`helm` commands in shell history.
+///
+/// With `query_cluster`, the current kubeconfig context is resolved and each namespace is
+/// queried (read-only `kubectl get`) for the workloads the commands touched.
+#[tracing::instrument(
+    name = "Collecting Kubernetes activity",
+    level = "info",
+    skip(shell_history)
+)]
+pub async fn get_k8s_activity(
+    shell_history: &[ShellHistoryEntry],
+    query_cluster: bool,
+) -> AppResult<Vec<K8sActivity>> {
+    let mut activity = find_k8s_activity(shell_history);
+    if query_cluster && !activity.is_empty() {
+        enrich(&mut activity).await?;
+    }
+    debug!("Found Kubernetes activity in {} namespaces", activity.len());
+    Ok(activity)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::Duration;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn entry(command: &str, minute: u8, exit_code: i64) -> ShellHistoryEntry {
+        ShellHistoryEntry {
+            date_time: datetime!(2025-01-01 09:00 UTC)
+                .replace_minute(minute)
+                .unwrap(),
+            duration: Duration::ZERO,
+            host: "host".into(),
+            directory: PathBuf::from("/src/infra"),
+            command: command.into(),
+            exit_code,
+            session_id: "s".into(),
+        }
+    }
+
+    #[test]
+    fn parses_kubectl_and_helm_invocations() {
+        let parsed = parse_command(
+            "KUBECONFIG=~/.kube/prod kubectl -n payments get pods api-7d9f-x2k -o wide | grep Crash",
+        );
+        assert_eq!(
+            parsed,
+            vec![K8sInvocation {
+                tool: "kubectl".into(),
+                verb: "get".into(),
+                context: None,
+                namespace: Some("payments".into()),
+                resources: vec!["pods/api-7d9f-x2k".into()],
+            }]
+        );
+
+        let parsed = parse_command(
+            "cd charts && helm upgrade --kube-context=prod api ./api --namespace=payments --set image.tag=v2",
+        );
+        assert_eq!(parsed[0].verb, "upgrade");
+        assert_eq!(parsed[0].context.as_deref(), Some("prod"));
+        assert_eq!(parsed[0].resources, vec!["api".to_string()]);
+
+        let parsed = parse_command("kubectl rollout restart deploy/api -n payments");
+        assert_eq!(parsed[0].verb, "rollout restart");
+        assert_eq!(parsed[0].resources, vec!["deploy/api".to_string()]);
+
+        assert!(parse_command("echo kubectl").is_empty());
+    }
+
+    #[test]
+    fn groups_by_namespace_and_matches_workloads() {
+        let activity = find_k8s_activity(&[
+            entry("kubectl -n payments logs api-7d9f-x2k", 5, 0),
+            entry("kubectl -n payments rollout restart deployment/api", 10, 1),
+            entry("kubectl get ns", 20, 0),
+        ]);
+
+        assert_eq!(activity.len(), 2);
+        let payments = &activity[0];
+        assert_eq!(payments.namespace.as_deref(), Some("payments"));
+        assert_eq!(payments.verbs["kubectl rollout restart"], 1);
+        assert_eq!(payments.failed_commands, 1);
+        assert_eq!(
+            payments.last_used - payments.first_used,
+            Duration::minutes(5)
+        );
+
+        let workloads = parse_workloads(
+            r#"{"items":[
+                {"kind":"Deployment","metadata":{"name":"api"},"spec":{"replicas":3},"status":{"readyReplicas":2}},
+                {"kind":"Deployment","metadata":{"name":"api-gateway"},"spec":{"replicas":1},"status":{}}
+            ]}"#,
+        );
+        let touched: Vec<&str> = workloads
+            .iter()
+            .filter(|w| payments.resources.iter().any(|r| touches(r, w)))
+            .map(|w| w.name.as_str())
+            .collect();
+        assert_eq!(touched, vec!["api"]);
+        assert_eq!(
+            (workloads[0].ready, workloads[0].desired),
+            (Some(2), Some(3))
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::context::Context;
+use crate::warnings::{self, WarningKind};
+
+/// How many leads of each kind are handed to the model.
+const MAX_LEADS: usize = 15;
+
+/// Hosts that serve reference documentation.
+const DOC_HOSTS: &[&str] = &[
+    "docs.rs",
+    "developer.mozilla.org",
+    "pkg.go.dev",
+    "learn.microsoft.com",
+    "man7.org",
+    "doc.rust-lang.org",
+    "readthedocs.io",
+    "readthedocs.org",
+];
+
+/// Deterministic hints of what was new today, for the learning log query to build on.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LearningLeads {
+    /// Hosts visited today that no archived run has visited before.
+    pub first_seen_domains: Vec<String>,
+    /// Packages added to a `Cargo.lock` in today's diffs.
+    pub new_crates: Vec<String>,
+    /// Documentation pages visited today, as `title (url)`.
+    pub docs_visited: Vec<String>,
+}
+
+impl LearningLeads {
+    pub fn is_empty(&self) -> bool {
+        self.first_seen_domains.is_empty()
+            && self.new_crates.is_empty()
+            && self.docs_visited.is_empty()
+    }
+}
+
+/// Host of `url`, lowercased and without a leading `www.`.
+fn host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    if host.is_empty() {
+        return None;
+    }
+    let host = host.to_lowercase();
+    Some(
+        host.strip_prefix("www.")
+            .map(str::to_string)
+            .unwrap_or(host),
+    )
+}
+
+fn is_docs(url: &str, host: &str) -> bool {
+    DOC_HOSTS
+        .iter()
+        .any(|docs| host == *docs || host.ends_with(&format!(".{docs}")))
+        || host.starts_with("docs.")
+        || url.contains("/docs/")
+        || url.contains("/doc/")
+}
+
+fn visited_hosts(context: &Context) -> BTreeSet<String> {
+    context
+        .safari_history()
+        .iter()
+        .flat_map(|cluster| &cluster.urls)
+        .filter_map(|item| host(&item.url))
+        .collect()
+}
+
+/// Packages whose `name` line is added, but not removed, by a `Cargo.lock` patch.
+fn added_crates(patch: &str) -> BTreeSet<String> {
+    let name = |line: &str| {
+        line.trim()
+            .strip_prefix("name = \"")
+            .and_then(|rest| rest.strip_suffix('"'))
+            .map(str::to_string)
+    };
+    let mut added = BTreeSet::new();
+    let mut removed = BTreeSet::new();
+    for line in patch.lines() {
+        if let Some(rest) = line.strip_prefix('+') {
+            added.extend(name(rest));
+        } else if let Some(rest) = line.strip_prefix('-') {
+            removed.extend(name(rest));
+        }
+    }
+    &added - &removed
+}
+
+/// Find learning leads in `context`. Domains count as first seen when none of the `known`
+/// hosts match.
+pub fn find(context: &Context, known: &HashSet<String>) -> LearningLeads {
+    let first_seen_domains = visited_hosts(context)
+        .into_iter()
+        .filter(|host| !known.contains(host))
+        .take(MAX_LEADS)
+        .collect();
+
+    let new_crates = context
+        .commit_history()
+        .iter()
+        .flat_map(|repo| repo.diff.added.iter().chain(&repo.diff.modified))
+        .filter(|file| {
+            file.path
+                .file_name()
+                .is_some_and(|name| name == "Cargo.lock")
+        })
+        .flat_map(|file| added_crates(&file.patch))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .take(MAX_LEADS)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let docs_visited = context
+        .safari_history()
+        .iter()
+        .flat_map(|cluster| &cluster.urls)
+        .filter(|item| host(&item.url).is_some_and(|host| is_docs(&item.url, &host)))
+        .filter(|item| seen.insert(item.url.as_str()))
+        .map(|item| match item.title.as_deref().map(str::trim) {
+            Some(title) if !title.is_empty() => format!("{title} ({})", item.url),
+            _ => item.url.clone(),
+        })
+        .take(MAX_LEADS)
+        .collect();
+
+    LearningLeads {
+        first_seen_domains,
+        new_crates,
+        docs_visited,
+    }
+}
+
+/// Find learning leads in `context`, using the hosts of every archived run as the known ones.
+///
+/// An unreadable archive is reported as a data quality warning and no domain counts as first
+/// seen, rather than every one.
+pub async fn find_for_run(context: &Context) -> LearningLeads {
+    match archive::load_all().await {
+        Ok(entries) => {
+            let known = entries
+                .iter()
+                .flat_map(|entry| visited_hosts(&entry.context.context))
+                .collect();
+            find(context, &known)
+        }
+        Err(e) => {
+            warnings::record(
+                WarningKind::Skipped,
+                "learning",
+                format!("Unable to read the archive for first-seen domains: {e}"),
+            );
+            LearningLeads {
+                first_seen_domains: Vec::new(),
+                ..find(context, &HashSet::new())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::classify::UrlCluster;
+    use crate::git::GitRepoHistory;
+    use crate::git::diff::{DiffSummary, DiffWithPatch};
+    use crate::safari::SafariHistoryItem;
+
+    fn visit(url: &str, title: &str) -> SafariHistoryItem {
+        SafariHistoryItem {
+            url: url.into(),
+            title: Some(title.into()),
+            visit_count: 1,
+            last_visited: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn finds_new_domains_crates_and_docs() {
+        let lock_patch = "@@ -10,6 +10,12 @@\n [[package]]\n-name = \"old-dep\"\n+name = \"old-dep\"\n+version = \"0.2.0\"\n+\n+[[package]]\n+name = \"winnow\"\n+version = \"0.6.0\"\n";
+        let repo = GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from("/src/app"),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: vec![DiffWithPatch {
+                    path: PathBuf::from("Cargo.lock"),
+                    patch: lock_patch.into(),
+                }],
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits: Vec::new(),
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        };
+        let context = Context::default()
+            .with_commit_history(vec![repo])
+            .with_safari_history(vec![UrlCluster {
+                label: "Parsing".into(),
+                urls: vec![
+                    visit("https://docs.rs/winnow/latest/winnow/", "winnow - Rust"),
+                    visit(
+                        "https://www.github.com/winnow-rs/winnow",
+                        "winnow-rs/winnow",
+                    ),
+                    visit("https://user@blog.example.com:8080/post?id=1", "A post"),
+                ],
+            }]);
+        let known = HashSet::from(["github.com".to_string()]);
+
+        let leads = find(&context, &known);
+
+        assert_eq!(leads.first_seen_domains, ["blog.example.com", "docs.rs"]);
+        assert_eq!(leads.new_crates, ["winnow"]);
+        assert_eq!(
+            leads.docs_visited,
+            ["winnow - Rust (https://docs.rs/winnow/latest/winnow/)"]
+        );
+    }
+}
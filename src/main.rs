@@ -1,45 +1,231 @@
 pub(crate) mod ai;
+pub(crate) mod allowlist;
+pub(crate) mod apple_notes;
+pub(crate) mod archive;
+pub(crate) mod ask;
+mod backoff;
+pub(crate) mod chromium;
+pub(crate) mod ci;
 pub(crate) mod classify;
 pub(crate) mod cli;
+pub(crate) mod clipboard;
+pub(crate) mod collector;
+pub(crate) mod compare;
+pub(crate) mod config;
+pub(crate) mod containers;
 mod context;
+pub(crate) mod dashboard;
 pub(crate) mod dirs;
+pub(crate) mod draft;
+pub(crate) mod email;
 pub(crate) mod entity;
 mod error;
+pub(crate) mod export;
+pub(crate) mod external;
+pub(crate) mod feed;
 pub(crate) mod git;
+pub(crate) mod highlights;
+pub(crate) mod history;
+pub(crate) mod hosts;
+pub(crate) mod http;
+pub(crate) mod ide_builds;
 mod io_utils;
+pub(crate) mod k8s;
+pub(crate) mod learning;
 mod logging;
+pub(crate) mod messages;
+pub(crate) mod notes;
+pub(crate) mod pace;
+pub(crate) mod proofread;
+pub(crate) mod recordings;
+pub(crate) mod redact;
+pub(crate) mod releases;
+pub(crate) mod render;
+pub(crate) mod review;
+pub(crate) mod rollup;
+pub(crate) mod run_status;
 pub(crate) mod safari;
+pub(crate) mod schedule;
 pub(crate) mod serde_helpers;
 pub(crate) mod shell;
+pub(crate) mod slack;
+pub(crate) mod sqlite;
+pub(crate) mod stats;
+pub(crate) mod status;
+pub(crate) mod tags;
+pub(crate) mod tasks;
+pub(crate) mod tickets;
 pub(crate) mod time_utils;
+pub(crate) mod timesheet;
+pub(crate) mod tmux;
+pub(crate) mod verify;
+pub(crate) mod warnings;
+pub(crate) mod webhook;
 
 pub(crate) use error::AppResult;
 
-use std::process::exit;
+use std::process::ExitCode;
 
-use clap::Parser;
-use tracing::info;
+use clap::{CommandFactory, FromArgMatches};
+use tracing::error;
 
-use cli::{GetDefaultArgs, GetVerbosity};
+use cli::GetVerbosity;
+use error::ExitStatus;
 
-/// Entrypoint: parse CLI args, set up logging, run command, and emit history output.
-#[tokio::main]
-async fn main() -> AppResult<()> {
-    let args = cli::Cli::parse();
+/// Entrypoint: read the local time zone while the process has a single thread, then run the
+/// command on the Tokio runtime.
+fn main() -> ExitCode {
+    time_utils::init_local_offset();
+    match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(start()),
+        Err(e) => {
+            eprintln!("Unable to start the async runtime: {e}");
+            ExitStatus::Failure.into()
+        }
+    }
+}
+
+/// Parse CLI args, set up logging, run the command, and report an [`ExitStatus`].
+async fn start() -> ExitCode {
+    // Values from the configuration file, or the chosen profile in it, become the defaults of
+    // the matching options.
+    let profile = config::profile_arg(std::env::args_os());
+    let config = config::FileConfig::load()
+        .await
+        .and_then(|config| config.with_profile(profile.as_deref()));
+    let mut command = match &config {
+        Ok(config) => config.apply(cli::Cli::command()),
+        Err(_) => cli::Cli::command(),
+    };
+    let args = match command
+        .try_get_matches_from_mut(std::env::args_os())
+        .and_then(|matches| cli::Cli::from_arg_matches(&matches))
+        .map_err(|e| e.format(&mut command))
+    {
+        Ok(args) => args,
+        Err(e) => {
+            // `--help` and `--version` also arrive here, and are not failures.
+            let _ = e.print();
+            return if e.use_stderr() {
+                ExitStatus::Config
+            } else {
+                ExitStatus::Success
+            }
+            .into();
+        }
+    };
 
     logging::setup_logger(args.cmd.get_verbosity());
 
-    let combined_hist = args.cmd.run().await?;
+    // A broken file should not stop `config init --force` from replacing it.
+    if let Err(e) = config
+        && !matches!(args.cmd, cli::Cmd::Config { .. })
+    {
+        error!("{e}");
+        return e.exit_status().into();
+    }
+    if let Ok(config) = &config {
+        http::configure(config.proxy_settings());
+        ai::glossary::configure(config.glossary.clone().unwrap_or_default());
+        email::smtp::configure(config.smtp.clone().unwrap_or_default());
+        // Checked when the file was loaded.
+        ai::retry::configure(config.request_policy().unwrap_or_default());
+    }
+    let about_me = config
+        .as_ref()
+        .ok()
+        .and_then(|config| config.about_me.clone())
+        .unwrap_or_default();
+    // Prompt files are read even without a configuration file.
+    let (tone, length) = args.cmd.summary_presets();
+    ai::prompt::configure(ai::prompt::PromptVars::new(&about_me).with_presets(tone, length));
+    ai::persona::configure(about_me);
+    let fetch_policy = config
+        .as_ref()
+        .ok()
+        .and_then(|config| config.fetch_policy().ok())
+        .unwrap_or_default();
+    ai::tools::fetch::configure(ai::tools::fetch::FetchPolicy {
+        allow_any_url: args.cmd.allow_any_url(),
+        ..fetch_policy
+    });
+    if let Some(output_args) = args.cmd.output_args() {
+        render::text::configure(output_args.text_width);
+    }
+    ai::provider::configure(args.cmd.provider()).await;
 
-    let hist_str = serde_json::to_string_pretty(&combined_hist)?;
+    match run(&args).await {
+        Ok(status) => status.into(),
+        Err(e) => {
+            error!("{e}");
+            e.exit_status().into()
+        }
+    }
+}
 
-    let default_args = args.cmd.get_default_args();
+/// Run the command and emit its output.
+async fn run(args: &cli::Cli) -> AppResult<ExitStatus> {
+    let cmd = &args.cmd;
+    let output_args = cmd.output_args();
+    // Read the `--template` up front so a missing or broken template fails before collecting.
+    let template = match output_args.and_then(|args| args.template.as_deref()) {
+        Some(path) => Some(render::template::ReportTemplate::load(path).await?),
+        None => None,
+    };
+    // Likewise resolve the output format so a conflicting `--format` fails early.
+    let output = match output_args {
+        Some(default_args) if template.is_none() => default_args
+            .output
+            .as_ref()
+            .zip(default_args.output_format()?),
+        _ => None,
+    };
+
+    // Likewise for missing SMTP settings when the run is to be emailed.
+    let email = cmd.email_exporter()?;
+    let webhooks = cmd.webhook_exporters()?;
+
+    let Some(combined_hist) = cmd.run().await? else {
+        return Ok(ExitStatus::Success);
+    };
+
+    let output_vars = io_utils::OutputVars {
+        profile: args.profile.as_deref(),
+        ..io_utils::OutputVars::now()
+    };
+    if let Some(template) = &template {
+        let rendered = template.render(&combined_hist)?;
+        match output_args.and_then(|args| args.output.as_deref()) {
+            Some(output) => io_utils::output_file(output, &output_vars, rendered).await?,
+            None => render::terminal::print(rendered)?,
+        }
+    } else if let Some((output, format)) = output {
+        io_utils::write_output(output, &output_vars, &format, &combined_hist).await?;
+    } else {
+        let format = output_args.and_then(|args| args.format.as_ref());
+        io_utils::print_output(format, &combined_hist).await?;
+    }
+
+    if email.is_some() || !webhooks.is_empty() {
+        let mut ledger = export::ExportLedger::load().await?;
+        if let Some(exporter) = email {
+            export::export(&exporter, &mut ledger, &combined_hist).await?;
+        }
+        for exporter in &webhooks {
+            export::export(exporter, &mut ledger, &combined_hist).await?;
+        }
+    }
 
-    if let Some(output) = &default_args.output {
-        io_utils::write_output(output, &default_args.format, &combined_hist).await?;
+    let unavailable = combined_hist
+        .summary
+        .as_ref()
+        .is_some_and(|summary| !summary.unavailable.is_empty());
+    if combined_hist.context.failed_collectors.is_empty() && !unavailable {
+        Ok(ExitStatus::Success)
     } else {
-        info!("Combined History:");
-        info!("{}", hist_str);
+        Ok(ExitStatus::Partial)
     }
-    exit(0);
 }
@@ -0,0 +1,161 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::error::AppError;
+use crate::time_utils::{macos_past_ts, macos_to_datetime};
+
+/// Per-conversation message counts and time ranges. Message bodies and participant
+/// identifiers are never read.
+///
+/// Modern `chat.db` files store `message.date` in nanoseconds since 2001; older ones use seconds.
+/// The query normalises both to seconds.
+const CONVERSATIONS_QUERY: &str = "
+SELECT
+    c.style = 43 AS is_group,
+    COALESCE(c.service_name, 'unknown') AS service,
+    SUM(m.is_from_me) AS sent,
+    COUNT(*) - SUM(m.is_from_me) AS received,
+    MIN(CASE WHEN m.date > 100000000000 THEN m.date / 1000000000.0 ELSE m.date * 1.0 END) AS first_message,
+    MAX(CASE WHEN m.date > 100000000000 THEN m.date / 1000000000.0 ELSE m.date * 1.0 END) AS last_message
+FROM message m
+JOIN chat_message_join cmj ON cmj.message_id = m.ROWID
+JOIN chat c ON c.ROWID = cmj.chat_id
+WHERE (CASE WHEN m.date > 100000000000 THEN m.date / 1000000000.0 ELSE m.date * 1.0 END) > ?
+GROUP BY c.ROWID
+ORDER BY last_message ASC";
+
+/// Raw aggregate row returned by `CONVERSATIONS_QUERY`.
+#[derive(Debug, FromQueryResult)]
+struct ConversationRow {
+    is_group: bool,
+    service: String,
+    sent: i64,
+    received: i64,
+    first_message: f64,
+    last_message: f64,
+}
+
+/// Metadata about one Messages conversation that was active during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MessageConversation {
+    pub is_group: bool,
+    /// Transport used by the conversation, e.g. `iMessage` or `SMS`.
+    pub service: String,
+    pub sent: i64,
+    pub received: i64,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_message: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_message: OffsetDateTime,
+}
+
+impl From<ConversationRow> for MessageConversation {
+    fn from(row: ConversationRow) -> Self {
+        MessageConversation {
+            is_group: row.is_group,
+            service: row.service,
+            sent: row.sent,
+            received: row.received,
+            first_message: macos_to_datetime(row.first_message),
+            last_message: macos_to_datetime(row.last_message),
+        }
+    }
+}
+
+/// Resolve the Messages `chat.db` path, honouring `MESSAGES_DB_PATH`.
+#[tracing::instrument(name = "Searching for the Messages database file", level = "info")]
+fn get_messages_db_path() -> AppResult<PathBuf> {
+    if let Ok(path) = env::var("MESSAGES_DB_PATH") {
+        return Ok(PathBuf::from(path));
+    }
+    env::home_dir()
+        .or_else(|| env::var("HOME").ok().map(PathBuf::from))
+        .map(|home| home.join("Library/Messages/chat.db"))
+        .ok_or_else(|| AppError::DirNotFound("~/Library/Messages".into()))
+}
+
+/// Open the Messages database read-only.
+#[tracing::instrument(name = "Connecting to the Messages database", level = "info")]
+async fn connect_to_db<P: AsRef<Path> + std::fmt::Debug>(
+    db_path: P,
+) -> AppResult<DatabaseConnection> {
+    let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=ro", db_path.as_ref().display()));
+    opt.sqlx_logging(false);
+    trace!("Connecting to Messages database");
+    Ok(Database::connect(opt).await?)
+}
+
+/// Fetch conversation-level Messages activity for the window.
+///
+/// Reading `chat.db` requires Full Disk Access for the terminal running daily-ai.
+#[tracing::instrument(name = "Fetching Messages activity", level = "info")]
+pub async fn get_messages_activity(duration: &Duration) -> AppResult<Vec<MessageConversation>> {
+    let db_path = get_messages_db_path()?;
+    let db = connect_to_db(&db_path).await?;
+    let conversations = fetch_conversations(&db, macos_past_ts(duration)).await?;
+    debug!("Fetched {} active conversations", conversations.len());
+    Ok(conversations)
+}
+
+/// Run `CONVERSATIONS_QUERY` for messages newer than `since` (macOS seconds).
+async fn fetch_conversations(
+    db: &DatabaseConnection,
+    since: f64,
+) -> AppResult<Vec<MessageConversation>> {
+    let stmt =
+        Statement::from_sql_and_values(DbBackend::Sqlite, CONVERSATIONS_QUERY, [since.into()]);
+    Ok(ConversationRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(MessageConversation::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn aggregates_conversations_across_date_encodings() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            "CREATE TABLE message (ROWID INTEGER PRIMARY KEY, text TEXT, date INTEGER, is_from_me INTEGER)",
+            "CREATE TABLE chat (ROWID INTEGER PRIMARY KEY, style INTEGER, service_name TEXT)",
+            "CREATE TABLE chat_message_join (chat_id INTEGER, message_id INTEGER)",
+            "INSERT INTO chat VALUES (1, 45, 'iMessage'), (2, 43, 'SMS')",
+            // Chat 1 uses nanoseconds, chat 2 the legacy seconds encoding.
+            "INSERT INTO message VALUES
+                (1, 'old', 500000000000000000, 0),
+                (2, 'hi', 700000000000000000, 1),
+                (3, 'yo', 700000060000000000, 0),
+                (4, 'group', 700000120, 0)",
+            "INSERT INTO chat_message_join VALUES (1, 1), (1, 2), (1, 3), (2, 4)",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+
+        let conversations = fetch_conversations(&db, 600_000_000.0).await.unwrap();
+
+        assert_eq!(conversations.len(), 2);
+        let direct = &conversations[0];
+        assert!(!direct.is_group);
+        assert_eq!((direct.sent, direct.received), (1, 1));
+        assert_eq!(
+            direct.last_message - direct.first_message,
+            time::Duration::minutes(1)
+        );
+        assert!(conversations[1].is_group);
+        assert_eq!(conversations[1].service, "SMS");
+    }
+}
@@ -0,0 +1,320 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime};
+use tokio::fs;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::error::AppError;
+use crate::time_utils::{self, past_ts, system_time_to_offset_datetime};
+use crate::warnings::{self, WarningKind};
+
+/// Directories that hold vault metadata rather than notes.
+const SKIPPED_DIRS: [&str; 4] = [".obsidian", ".trash", ".git", "node_modules"];
+
+/// A Markdown note created or modified during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NoteActivity {
+    /// Vault the note was found in.
+    pub vault: PathBuf,
+    /// Path relative to the vault root.
+    pub path: PathBuf,
+    pub title: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// From the `created`/`date` front-matter field, when present.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(with = "crate::serde_helpers::option_offset_datetime")]
+    pub created: Option<OffsetDateTime>,
+    /// The later of the file's mtime and the `updated`/`modified` front-matter field.
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub modified: OffsetDateTime,
+    /// Whether the note was created during the window (rather than only edited).
+    pub new: bool,
+    pub words: usize,
+}
+
+/// Fields read from a note's YAML front matter. Only flat `key: value` pairs and simple lists
+/// are understood, which covers what Obsidian and most static-site tools write.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct FrontMatter {
+    title: Option<String>,
+    created: Option<OffsetDateTime>,
+    updated: Option<OffsetDateTime>,
+    tags: Vec<String>,
+}
+
+/// Parse a front-matter date: RFC 3339, `YYYY-MM-DD HH:MM[:SS]`, `YYYY-MM-DDTHH:MM[:SS]`, or a
+/// bare `YYYY-MM-DD`. Dates without an offset are taken as local time.
+fn parse_date(value: &str) -> Option<OffsetDateTime> {
+    let value = value.trim().trim_matches(['"', '\'']);
+    if let Ok(dt) = OffsetDateTime::parse(value, &Rfc3339) {
+        return Some(dt);
+    }
+    let offset = time_utils::local_offset();
+    let value = value.replacen('T', " ", 1);
+    for format in [
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]"),
+    ] {
+        if let Ok(dt) = PrimitiveDateTime::parse(&value, format) {
+            return Some(dt.assume_offset(offset));
+        }
+    }
+    Date::parse(&value, format_description!("[year]-[month]-[day]"))
+        .ok()
+        .map(|date| date.midnight().assume_offset(offset))
+}
+
+/// Split a `[a, b]` or `a, b` list value into items.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split([',', ' '])
+        .map(|item| item.trim().trim_matches(['"', '\'', '#']).to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Split a note into its front matter and body.
+fn parse_note(content: &str) -> (FrontMatter, &str) {
+    let mut front = FrontMatter::default();
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (front, content);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (front, content);
+    };
+    let body = rest[end + 4..].trim_start_matches(['-', '\r', '\n']);
+
+    let mut list_key: Option<String> = None;
+    for line in rest[..end].lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            if list_key.as_deref().is_some_and(|k| k == "tags") {
+                front.tags.extend(parse_list(item));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        list_key = value.is_empty().then(|| key.clone());
+        match key.as_str() {
+            "title" if !value.is_empty() => {
+                front.title = Some(value.trim_matches(['"', '\'']).to_string())
+            }
+            "created" | "date" | "created_at" => front.created = parse_date(value),
+            "updated" | "modified" | "updated_at" | "lastmod" => front.updated = parse_date(value),
+            "tags" | "tag" => front.tags.extend(parse_list(value)),
+            _ => {}
+        }
+    }
+    (front, body)
+}
+
+/// Build the activity record for a note, or `None` if it was not touched since `since`.
+fn note_activity(
+    vault: &Path,
+    path: PathBuf,
+    content: &str,
+    mtime: OffsetDateTime,
+    since: OffsetDateTime,
+) -> Option<NoteActivity> {
+    let (front, body) = parse_note(content);
+    let modified = front.updated.map_or(mtime, |updated| updated.max(mtime));
+    let new = front.created.is_some_and(|created| created >= since);
+    if modified < since && !new {
+        return None;
+    }
+    let title = front
+        .title
+        .or_else(|| {
+            body.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|heading| heading.trim().to_string())
+        })
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+    Some(NoteActivity {
+        vault: vault.to_path_buf(),
+        title,
+        tags: front.tags,
+        created: front.created,
+        modified,
+        new,
+        words: body.split_whitespace().count(),
+        path,
+    })
+}
+
+/// Resolve the vault directory from the CLI flag or `NOTES_VAULT`.
+fn vault_path(flag: Option<&Path>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf)
+        .or_else(|| env::var_os("NOTES_VAULT").map(PathBuf::from))
+}
+
+/// Collect Markdown notes created or modified during the window from the vault given on the
+/// command line or in `NOTES_VAULT`. Returns nothing when no vault is configured.
+#[tracing::instrument(name = "Collecting notes", level = "info")]
+pub async fn get_notes_activity(
+    vault: Option<&Path>,
+    duration: &Duration,
+) -> AppResult<Vec<NoteActivity>> {
+    let Some(vault) = vault_path(vault) else {
+        debug!("No notes vault configured");
+        return Ok(Vec::new());
+    };
+    if !vault.is_dir() {
+        warnings::record(
+            WarningKind::Skipped,
+            "notes",
+            format!("Notes vault {} is not a directory", vault.display()),
+        );
+        return Ok(Vec::new());
+    }
+    scan_vault(&vault, duration).await
+}
+
+/// Walk `vault` for notes touched during the window.
+///
+/// Hidden directories and vault metadata (`.obsidian`, `.trash`) are skipped. Only note titles,
+/// tags, dates, and word counts are collected; bodies are read on demand by the `get_notes` tool.
+async fn scan_vault(vault: &Path, duration: &Duration) -> AppResult<Vec<NoteActivity>> {
+    let since = past_ts(duration);
+    let mut notes = Vec::new();
+    let mut dirs = vec![vault.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                let kind = if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    WarningKind::Permission
+                } else {
+                    WarningKind::Skipped
+                };
+                warnings::record(
+                    kind,
+                    "notes",
+                    format!("Unable to read notes directory {}: {e}", dir.display()),
+                );
+                continue;
+            }
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    dirs.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() || path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let mtime = system_time_to_offset_datetime(entry.metadata().await?.modified()?);
+            // Old files are still read: a front-matter `created` date can place them in the window.
+            let Ok(content) = fs::read_to_string(&path).await else {
+                debug!("Skipping non-UTF-8 note {}", path.display());
+                continue;
+            };
+            let relative = path.strip_prefix(vault).unwrap_or(&path).to_path_buf();
+            if let Some(note) = note_activity(vault, relative, &content, mtime, since) {
+                notes.push(note);
+            }
+        }
+    }
+    notes.sort_by_key(|note| note.modified);
+    debug!("Found {} notes touched in {}", notes.len(), vault.display());
+    Ok(notes)
+}
+
+/// Read a collected note's body, without front matter.
+///
+/// Only notes in `notes` can be read, so the agent cannot use this to open arbitrary files.
+pub async fn read_note(notes: &[NoteActivity], path: &Path) -> AppResult<String> {
+    let Some(note) = notes.iter().find(|note| note.path == path) else {
+        return Err(AppError::Other(format!(
+            "{} is not one of the collected notes",
+            path.display()
+        )));
+    };
+    let content = fs::read_to_string(note.vault.join(&note.path)).await?;
+    Ok(parse_note(&content).1.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    const NOTE: &str = "---
+title: \"Parser design\"
+created: 2025-01-01T09:30:00Z
+updated: 2025-01-01 11:00
+tags: [rust, parsing]
+aliases:
+  - parser
+---
+# Ignored heading
+
+Stack-based parser notes.
+";
+
+    #[test]
+    fn reads_front_matter_dates_titles_and_tags() {
+        let (front, body) = parse_note(NOTE);
+        assert_eq!(front.title.as_deref(), Some("Parser design"));
+        assert_eq!(front.created, Some(datetime!(2025-01-01 09:30 UTC)));
+        assert!(front.updated.is_some());
+        assert_eq!(front.tags, vec!["rust".to_string(), "parsing".to_string()]);
+        assert!(body.starts_with("# Ignored heading"));
+
+        let (front, _) = parse_note("---\ntags:\n  - '#meeting'\n  - standup\n---\nbody");
+        assert_eq!(
+            front.tags,
+            vec!["meeting".to_string(), "standup".to_string()]
+        );
+    }
+
+    #[test]
+    fn keeps_notes_created_or_modified_in_the_window() {
+        let since = datetime!(2025-01-01 08:00 UTC);
+        let old = datetime!(2024-06-01 00:00 UTC);
+
+        let note =
+            note_activity(Path::new("/vault"), "a/parser.md".into(), NOTE, old, since).unwrap();
+        assert!(note.new);
+        assert_eq!(note.words, 6);
+
+        let edited = note_activity(
+            Path::new("/vault"),
+            "b/todo.md".into(),
+            "# Todo\n\n- [ ] ship it\n",
+            datetime!(2025-01-01 10:00 UTC),
+            since,
+        )
+        .unwrap();
+        assert!(!edited.new);
+        assert_eq!(edited.title, "Todo");
+
+        assert!(note_activity(Path::new("/vault"), "c/old.md".into(), "old", old, since).is_none());
+    }
+}
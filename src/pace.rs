@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use time::{Date, Duration, UtcOffset, Weekday};
+
+use crate::archive;
+use crate::context::FullContext;
+use crate::dashboard::activity_timestamps;
+use crate::time_utils;
+use crate::warnings::{self, WarningKind};
+
+/// Width of the activity slots the timeline is bucketed into.
+const SLOT_MINUTES: i64 = 15;
+
+/// Days looked back over for late nights.
+const LATE_NIGHT_WINDOW_DAYS: i64 = 7;
+/// Late nights within the window that are worth a note.
+const LATE_NIGHTS: usize = 3;
+/// Activity from this hour until [`LATE_NIGHT_END`] counts as late.
+const LATE_NIGHT_START: u8 = 22;
+const LATE_NIGHT_END: u8 = 5;
+
+/// Back-to-back weekends with activity that are worth a note.
+const WEEKEND_STREAK: usize = 2;
+
+/// Recent and earlier workdays whose longest breaks are compared.
+const RECENT_WORKDAYS: usize = 5;
+const EARLIER_WORKDAYS: usize = 10;
+/// Breaks are "shrinking" when recent ones are below this share of earlier ones, and shorter
+/// than [`SHORT_BREAK_MINUTES`].
+const SHRINK_RATIO: f64 = 0.6;
+const SHORT_BREAK_MINUTES: i64 = 60;
+
+/// A sign that the recent pace may not be sustainable.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "signal", rename_all = "snake_case")]
+pub enum PaceSignal {
+    /// Activity between 22:00 and 05:00 on several of the last days.
+    LateNights { nights: usize, days: i64 },
+    /// Activity on several weekends in a row.
+    WeekendStreak { weekends: usize },
+    /// The longest break of recent workdays is much shorter than it used to be.
+    ShrinkingBreaks {
+        recent_minutes: i64,
+        earlier_minutes: i64,
+    },
+}
+
+impl fmt::Display for PaceSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaceSignal::LateNights { nights, days } => write!(
+                f,
+                "Activity ran past {LATE_NIGHT_START}:00 on {nights} of the last {days} days. \
+                 An earlier finish might be worth it."
+            ),
+            PaceSignal::WeekendStreak { weekends } => write!(
+                f,
+                "There has been work on each of the last {weekends} weekends. \
+                 Consider keeping the next one free."
+            ),
+            PaceSignal::ShrinkingBreaks {
+                recent_minutes,
+                earlier_minutes,
+            } => write!(
+                f,
+                "The longest break on recent workdays was about {recent_minutes} minutes, \
+                 down from {earlier_minutes}. A proper pause could help."
+            ),
+        }
+    }
+}
+
+/// Active 15-minute slots per local date, as minutes since that date's midnight.
+fn timeline<'a>(
+    contexts: impl IntoIterator<Item = &'a FullContext>,
+    offset: UtcOffset,
+) -> BTreeMap<Date, BTreeSet<i64>> {
+    let mut days: BTreeMap<Date, BTreeSet<i64>> = BTreeMap::new();
+    for ts in contexts.into_iter().flat_map(activity_timestamps) {
+        let local = ts.to_offset(offset);
+        let minute = i64::from(local.hour()) * 60 + i64::from(local.minute());
+        days.entry(local.date())
+            .or_default()
+            .insert(minute - minute % SLOT_MINUTES);
+    }
+    days
+}
+
+fn late_nights(days: &BTreeMap<Date, BTreeSet<i64>>, last: Date) -> Option<PaceSignal> {
+    let first = last - Duration::days(LATE_NIGHT_WINDOW_DAYS - 1);
+    let nights = days
+        .range(first..=last)
+        .filter(|(_, slots)| {
+            slots.iter().any(|&minute| {
+                let hour = minute / 60;
+                hour >= i64::from(LATE_NIGHT_START) || hour < i64::from(LATE_NIGHT_END)
+            })
+        })
+        .count();
+    (nights >= LATE_NIGHTS).then_some(PaceSignal::LateNights {
+        nights,
+        days: LATE_NIGHT_WINDOW_DAYS,
+    })
+}
+
+fn weekend_streak(days: &BTreeMap<Date, BTreeSet<i64>>, last: Date) -> Option<PaceSignal> {
+    let mut saturday = last;
+    while saturday.weekday() != Weekday::Saturday {
+        saturday = saturday.previous_day()?;
+    }
+    let mut weekends = 0;
+    loop {
+        let sunday = saturday.next_day()?;
+        if !days.contains_key(&saturday) && !days.contains_key(&sunday) {
+            break;
+        }
+        weekends += 1;
+        saturday -= Duration::weeks(1);
+    }
+    (weekends >= WEEKEND_STREAK).then_some(PaceSignal::WeekendStreak { weekends })
+}
+
+/// Longest gap between two active slots of a day, in minutes.
+fn longest_break(slots: &BTreeSet<i64>) -> i64 {
+    slots
+        .iter()
+        .zip(slots.iter().skip(1))
+        .map(|(a, b)| b - a - SLOT_MINUTES)
+        .max()
+        .unwrap_or(0)
+}
+
+fn shrinking_breaks(days: &BTreeMap<Date, BTreeSet<i64>>) -> Option<PaceSignal> {
+    let workdays: Vec<i64> = days
+        .iter()
+        .rev()
+        .filter(|(date, slots)| {
+            !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday) && slots.len() > 1
+        })
+        .map(|(_, slots)| longest_break(slots))
+        .take(RECENT_WORKDAYS + EARLIER_WORKDAYS)
+        .collect();
+    if workdays.len() < RECENT_WORKDAYS + EARLIER_WORKDAYS / 2 {
+        return None;
+    }
+    let (recent, earlier) = workdays.split_at(RECENT_WORKDAYS);
+    let recent_minutes = recent.iter().sum::<i64>() / recent.len() as i64;
+    let earlier_minutes = earlier.iter().sum::<i64>() / earlier.len() as i64;
+    let shrinking = (recent_minutes as f64) < earlier_minutes as f64 * SHRINK_RATIO
+        && recent_minutes < SHORT_BREAK_MINUTES;
+    shrinking.then_some(PaceSignal::ShrinkingBreaks {
+        recent_minutes,
+        earlier_minutes,
+    })
+}
+
+/// Look for late nights, weekend streaks, and shrinking breaks in the timelines of `contexts`,
+/// up to and including the `last` local date.
+pub fn assess<'a>(
+    contexts: impl IntoIterator<Item = &'a FullContext>,
+    last: Date,
+    offset: UtcOffset,
+) -> Vec<PaceSignal> {
+    let mut days = timeline(contexts, offset);
+    days.retain(|date, _| *date <= last);
+    [
+        late_nights(&days, last),
+        weekend_streak(&days, last),
+        shrinking_breaks(&days),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Assess the pace of the archived runs together with `current`, as of today.
+///
+/// Only reads the local archive. An unreadable archive is reported as a data quality warning
+/// and the assessment falls back to `current` alone.
+pub async fn assess_run(current: &FullContext) -> Vec<PaceSignal> {
+    let now = time_utils::now_local();
+    let entries = match archive::load_all().await {
+        Ok(entries) => entries,
+        Err(e) => {
+            warnings::record(
+                WarningKind::Skipped,
+                "pace",
+                format!("Unable to read the archive for the pace check: {e}"),
+            );
+            Vec::new()
+        }
+    };
+    let contexts = entries.iter().map(|entry| &entry.context).chain([current]);
+    assess(contexts, now.date(), now.offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::OffsetDateTime;
+    use time::macros::{date, datetime, offset};
+
+    use super::*;
+    use crate::context::Context;
+    use crate::shell::ShellHistoryEntry;
+
+    fn run(times: Vec<OffsetDateTime>) -> FullContext {
+        FullContext::from(
+            Context::default().with_shell_history(
+                times
+                    .into_iter()
+                    .map(|date_time| ShellHistoryEntry {
+                        date_time,
+                        duration: Duration::ZERO,
+                        host: "localhost".into(),
+                        directory: PathBuf::from("/tmp"),
+                        command: "make".into(),
+                        exit_code: 0,
+                        session_id: "abc".into(),
+                    })
+                    .collect(),
+            ),
+        )
+    }
+
+    #[test]
+    fn flags_late_nights_and_weekend_streaks() {
+        // 2025-01-18 and 2025-01-11 are Saturdays.
+        let late = run(vec![
+            datetime!(2025-01-13 22:30 UTC),
+            datetime!(2025-01-14 23:10 UTC),
+            datetime!(2025-01-16 01:00 UTC),
+            datetime!(2025-01-11 10:00 UTC),
+            datetime!(2025-01-19 10:00 UTC),
+        ]);
+
+        let signals = assess([&late], date!(2025 - 01 - 19), offset!(UTC));
+
+        assert_eq!(
+            signals,
+            [
+                PaceSignal::LateNights { nights: 3, days: 7 },
+                PaceSignal::WeekendStreak { weekends: 2 },
+            ]
+        );
+        // In UTC+3 the same activity falls after midnight, which is just as late.
+        assert_eq!(
+            assess([&late], date!(2025 - 01 - 19), offset!(+3))[0],
+            PaceSignal::LateNights { nights: 3, days: 7 }
+        );
+        assert!(assess([&late], date!(2025 - 01 - 12), offset!(UTC)).is_empty());
+    }
+
+    #[test]
+    fn flags_shrinking_breaks() {
+        // Three weeks of workdays: a two-hour lunch break, then back-to-back for the last five.
+        let mut times = Vec::new();
+        let mut day = date!(2025 - 01 - 06);
+        let mut workdays = 0;
+        while workdays < 15 {
+            if !matches!(day.weekday(), Weekday::Saturday | Weekday::Sunday) {
+                let start = day.with_hms(9, 0, 0).unwrap().assume_utc();
+                let gap = if workdays < 10 { 120 } else { 15 };
+                times.push(start);
+                times.push(start + Duration::minutes(15 + gap));
+                workdays += 1;
+            }
+            day = day.next_day().unwrap();
+        }
+
+        let signals = assess([&run(times)], day, offset!(UTC));
+
+        assert_eq!(
+            signals,
+            [PaceSignal::ShrinkingBreaks {
+                recent_minutes: 15,
+                earlier_minutes: 120,
+            }]
+        );
+        assert!(signals[0].to_string().starts_with("The longest break"));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::LazyLock;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use regex::Regex;
+use tracing::{debug, info, warn};
+
+use crate::ai;
+use crate::ai::proofread::Passage;
+use crate::ai::summary::WorkSummary;
+
+/// What a correction must leave as it was: numbers, URLs, and code spans.
+static FACT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"https?://[^\s)\]>"'`]*[^\s)\]>"'`.,;:!?]|`[^`\n]+`|\d+(?:[.:,]\d+)*"#)
+        .expect("valid regex")
+});
+
+/// Most a correction may lengthen or shorten a passage, as a fraction of its length. Fixing
+/// typos and dropped words changes little; more means the model rewrote the passage.
+const MAX_LENGTH_CHANGE: f64 = 0.2;
+
+/// The prose of `summary`: its paragraphs and the items of its lists, leaving out the empty ones.
+fn prose(summary: &mut WorkSummary) -> Vec<&mut String> {
+    let WorkSummary {
+        summary,
+        highlights,
+        time_breakdown,
+        common_groups,
+        repo_summaries,
+        shell_overview,
+        releases,
+        tickets_touched,
+        pending_review,
+        communication,
+        notes,
+        learned,
+        ..
+    } = summary;
+    let lists = [
+        highlights,
+        time_breakdown,
+        common_groups,
+        repo_summaries,
+        releases,
+        tickets_touched,
+        pending_review,
+        communication,
+        notes,
+        learned,
+    ];
+    [summary, shell_overview]
+        .into_iter()
+        .chain(lists.into_iter().flatten())
+        .filter(|text| !text.trim().is_empty())
+        .collect()
+}
+
+/// Whether `corrected` only fixes the spelling and grammar of `original`: it keeps every
+/// number, URL, and code span in order, and is about as long.
+fn keeps_facts(original: &str, corrected: &str) -> bool {
+    let facts = |text| {
+        FACT.find_iter(text)
+            .map(|fact| fact.as_str())
+            .collect::<Vec<_>>()
+    };
+    let (before, after) = (original.chars().count(), corrected.chars().count());
+    let change = before.abs_diff(after) as f64 / before.max(1) as f64;
+    !corrected.trim().is_empty()
+        && change <= MAX_LENGTH_CHANGE
+        && facts(original) == facts(corrected)
+}
+
+/// Fix typos and broken sentences in the prose of `summary`, which small local models often
+/// leave behind.
+///
+/// Every passage is sent to the model in one question. A correction that changes a number, URL,
+/// or code span, or much of the passage's length, is not used; when the question fails the
+/// summary is left as it is.
+#[tracing::instrument(name = "Proofreading the summary", level = "info", skip_all)]
+pub async fn proofread_run<C: Config>(client: &Client<C>, model: &str, summary: &mut WorkSummary) {
+    let mut texts = prose(summary);
+    let passages: Vec<Passage> = texts
+        .iter()
+        .enumerate()
+        .map(|(id, text)| Passage {
+            id,
+            text: text.to_string(),
+        })
+        .collect();
+    if passages.is_empty() {
+        return;
+    }
+    let corrected = match ai::proofread::proofread(client, model, &passages).await {
+        Ok(corrected) => corrected,
+        Err(e) => {
+            warn!("Leaving the summary as written: {e}");
+            return;
+        }
+    };
+    let mut fixed = 0;
+    for passage in corrected {
+        let Some(text) = texts.get_mut(passage.id) else {
+            continue;
+        };
+        if passage.text == **text {
+            continue;
+        }
+        if keeps_facts(text, &passage.text) {
+            **text = passage.text;
+            fixed += 1;
+        } else {
+            debug!("Not using the correction of {text:?} to {:?}", passage.text);
+        }
+    }
+    info!("Corrected {fixed} of {} passages", passages.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_takes_corrections_that_keep_the_facts() {
+        assert!(keeps_facts(
+            "Fixd the the flaky `lexer` test in 3 repos, see https://ci.example.com/7",
+            "Fixed the flaky `lexer` test in 3 repos; see https://ci.example.com/7."
+        ));
+        assert!(!keeps_facts(
+            "Merged 3 pull requests",
+            "Merged 4 pull requests"
+        ));
+        assert!(!keeps_facts("Ran `cargo tset`", "Ran `cargo test`"));
+        assert!(!keeps_facts(
+            "Reviewed the parser",
+            "Reviewed the parser and shipped the new lexer to production"
+        ));
+        assert!(!keeps_facts("Reviewed the parser", " "));
+
+        let mut summary = WorkSummary {
+            summary: "Shiped the parser.".into(),
+            highlights: vec!["Parser".into(), String::new()],
+            notes: vec!["Note".into()],
+            ..Default::default()
+        };
+        let texts: Vec<String> = prose(&mut summary)
+            .into_iter()
+            .map(|text| text.to_string())
+            .collect();
+        assert_eq!(texts, ["Shiped the parser.", "Parser", "Note"]);
+    }
+}
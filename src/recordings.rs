@@ -0,0 +1,232 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::{Duration, OffsetDateTime};
+use tokio::fs;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::time_utils::{past_ts, system_time_to_offset_datetime};
+use crate::warnings::{self, WarningKind};
+
+/// An asciinema recording made during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Recording {
+    /// The `title` from the recording header, or the file name without `.cast`.
+    pub title: String,
+    pub path: PathBuf,
+    /// Command recorded instead of an interactive shell, from `asciinema rec --command`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub command: Option<String>,
+    /// Start of the recording, from the header timestamp or the file's mtime.
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub recorded: OffsetDateTime,
+    /// Rounded to whole seconds.
+    #[serde(with = "crate::serde_helpers::duration")]
+    pub duration: Duration,
+}
+
+impl Recording {
+    /// Length for display, e.g. `3m 12s`.
+    pub fn length(&self) -> String {
+        let seconds = self.duration.whole_seconds().max(0) as u64;
+        humantime::format_duration(std::time::Duration::from_secs(seconds)).to_string()
+    }
+}
+
+/// Fields read from an asciicast file.
+#[derive(Debug, Default, PartialEq)]
+struct CastInfo {
+    title: Option<String>,
+    command: Option<String>,
+    timestamp: Option<OffsetDateTime>,
+    duration: Duration,
+}
+
+/// Parse an asciicast v2 or v3 file: a JSON header line followed by one JSON event per line.
+///
+/// v2 events carry the time since the start of the recording and v3 events the time since the
+/// previous event, so the length is the last time or the sum. A `duration` in the header wins.
+/// Returns `None` for files that are not asciicasts.
+fn parse_cast(content: &str) -> Option<CastInfo> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Value = serde_json::from_str(lines.next()?).ok()?;
+    let version = header.get("version")?.as_u64()?;
+    if !matches!(version, 2 | 3) {
+        return None;
+    }
+    let mut seconds = 0.0;
+    for line in lines {
+        let Ok(Value::Array(event)) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let Some(time) = event.first().and_then(Value::as_f64) else {
+            continue;
+        };
+        if version == 2 {
+            seconds = time;
+        } else {
+            seconds += time;
+        }
+    }
+    if let Some(duration) = header.get("duration").and_then(Value::as_f64) {
+        seconds = duration;
+    }
+    let text = |key: &str| {
+        header
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    Some(CastInfo {
+        title: text("title"),
+        command: text("command"),
+        timestamp: header
+            .get("timestamp")
+            .and_then(Value::as_i64)
+            .and_then(|ts| OffsetDateTime::from_unix_timestamp(ts).ok()),
+        duration: Duration::seconds(seconds.round() as i64),
+    })
+}
+
+/// Resolve the recordings directory from the CLI flag or `ASCIINEMA_RECORDINGS_DIR`.
+fn recordings_dir(flag: Option<&Path>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf)
+        .or_else(|| env::var_os("ASCIINEMA_RECORDINGS_DIR").map(PathBuf::from))
+}
+
+/// Collect asciinema recordings (`.cast` files) started during the window from the directory
+/// given on the command line or in `ASCIINEMA_RECORDINGS_DIR`. Returns nothing when no directory
+/// is configured.
+#[tracing::instrument(name = "Collecting terminal recordings", level = "info")]
+pub async fn get_recordings(dir: Option<&Path>, duration: &Duration) -> AppResult<Vec<Recording>> {
+    let Some(dir) = recordings_dir(dir) else {
+        debug!("No recordings directory configured");
+        return Ok(Vec::new());
+    };
+    if !dir.is_dir() {
+        warnings::record(
+            WarningKind::Skipped,
+            "asciinema",
+            format!("Recordings directory {} is not a directory", dir.display()),
+        );
+        return Ok(Vec::new());
+    }
+    let since = past_ts(duration);
+    let mut recordings = Vec::new();
+    let mut dirs = vec![dir.clone()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                warnings::record(
+                    WarningKind::Skipped,
+                    "asciinema",
+                    format!("Unable to read recordings directory {}: {e}", dir.display()),
+                );
+                continue;
+            }
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                if !entry.file_name().to_string_lossy().starts_with('.') {
+                    dirs.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() || path.extension().is_none_or(|ext| ext != "cast") {
+                continue;
+            }
+            // Recordings are written as they go, so anything started in the window was modified
+            // in it too.
+            let mtime = system_time_to_offset_datetime(entry.metadata().await?.modified()?);
+            if mtime < since {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path).await else {
+                debug!("Skipping unreadable recording {}", path.display());
+                continue;
+            };
+            let Some(cast) = parse_cast(&content) else {
+                debug!(
+                    "Skipping {}: not an asciicast v2 or v3 file",
+                    path.display()
+                );
+                continue;
+            };
+            let recorded = cast.timestamp.unwrap_or(mtime - cast.duration);
+            if recorded < since {
+                continue;
+            }
+            recordings.push(Recording {
+                title: cast.title.unwrap_or_else(|| {
+                    path.file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                }),
+                command: cast.command,
+                recorded,
+                duration: cast.duration,
+                path,
+            });
+        }
+    }
+    recordings.sort_by_key(|recording| recording.recorded);
+    debug!("Found {} recordings", recordings.len());
+    Ok(recordings)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn reads_v2_headers_and_event_times() {
+        let cast = parse_cast(concat!(
+            r#"{"version": 2, "width": 80, "height": 24, "timestamp": 1735723800, "title": "Parser demo", "env": {"SHELL": "/bin/zsh"}}"#,
+            "\n",
+            r#"[0.25, "o", "$ "]"#,
+            "\n",
+            r#"[61.6, "o", "exit\r\n"]"#,
+            "\n",
+        ))
+        .unwrap();
+        assert_eq!(cast.title.as_deref(), Some("Parser demo"));
+        assert_eq!(cast.command, None);
+        assert_eq!(cast.timestamp, Some(datetime!(2025-01-01 09:30 UTC)));
+        assert_eq!(cast.duration, Duration::seconds(62));
+    }
+
+    #[test]
+    fn sums_v3_event_intervals() {
+        let cast = parse_cast(concat!(
+            r#"{"version": 3, "term": {"cols": 80, "rows": 24}, "command": "cargo test"}"#,
+            "\n",
+            r#"[1.5, "o", "running"]"#,
+            "\n",
+            r#"[2.5, "o", "ok"]"#,
+            "\n",
+            r#"[0.2, "x", "0"]"#,
+            "\n",
+        ))
+        .unwrap();
+        assert_eq!(cast.title, None);
+        assert_eq!(cast.command.as_deref(), Some("cargo test"));
+        assert_eq!(cast.duration, Duration::seconds(4));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(parse_cast(""), None);
+        assert_eq!(parse_cast("not json"), None);
+        assert_eq!(parse_cast(r#"{"version": 1, "stdout": []}"#), None);
+    }
+}
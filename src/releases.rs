@@ -0,0 +1,287 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::debug;
+
+use crate::AppResult;
+use crate::git::GitRepoHistory;
+use crate::git::read_only::ReadOnlyRepoAccess;
+use crate::shell::ShellHistoryEntry;
+use crate::time_utils::past_ts;
+use crate::warnings::{self, WarningKind};
+
+/// How a release was made.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseKind {
+    /// A git tag created in the window.
+    Tag,
+    /// `cargo publish`.
+    CargoPublish,
+    /// `npm publish`, `pnpm publish`, or `yarn publish`.
+    NpmPublish,
+    /// `gh release create`.
+    GithubRelease,
+}
+
+/// A tag, published package, or GitHub release made during the window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Release {
+    pub kind: ReleaseKind,
+    /// `owner/repo` of the repository, the package named with `-p`, or the directory name.
+    pub project: String,
+    /// Tag name, when known.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<String>,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub time: OffsetDateTime,
+}
+
+impl Release {
+    /// One-line description used in the "releases" section of the summary, e.g.
+    /// `Tagged annie444/daily-ai v1.2.0`.
+    pub fn describe(&self) -> String {
+        let name = match &self.version {
+            Some(version) => format!("{} {version}", self.project),
+            None => self.project.clone(),
+        };
+        match self.kind {
+            ReleaseKind::Tag => format!("Tagged {name}"),
+            ReleaseKind::CargoPublish => format!("Published {name} to crates.io"),
+            ReleaseKind::NpmPublish => format!("Published {name} to npm"),
+            ReleaseKind::GithubRelease => format!("Created a GitHub release for {name}"),
+        }
+    }
+}
+
+/// A release command found in one shell command line, before its project is known.
+#[derive(Debug, PartialEq, Eq)]
+struct ReleaseCommand {
+    kind: ReleaseKind,
+    /// Package or repository named on the command line.
+    project: Option<String>,
+    version: Option<String>,
+}
+
+/// `gh release create` flags that take a separate value.
+fn gh_flag_takes_value(flag: &str) -> bool {
+    matches!(
+        flag,
+        "-t" | "--title"
+            | "-n"
+            | "--notes"
+            | "-F"
+            | "--notes-file"
+            | "--target"
+            | "-R"
+            | "--repo"
+            | "--discussion-category"
+            | "--notes-start-tag"
+    )
+}
+
+/// Parse the publish and release commands in one shell command line.
+///
+/// Pipelines and command lists are split, and leading `sudo`, `env`, and `VAR=value`
+/// assignments are skipped, as for the Kubernetes parser. Dry runs are ignored.
+fn parse_command(command: &str) -> Vec<ReleaseCommand> {
+    let mut releases = Vec::new();
+    for segment in command.split(['|', ';', '&', '\n']) {
+        let mut words = segment
+            .split_whitespace()
+            .map(|w| w.trim_matches(['"', '\'']))
+            .peekable();
+        while let Some(word) = words.peek() {
+            if *word == "sudo" || *word == "env" || word.contains('=') {
+                words.next();
+            } else {
+                break;
+            }
+        }
+        let words: Vec<&str> = words.collect();
+        if words.contains(&"--dry-run") {
+            continue;
+        }
+        let Some((tool, args)) = words.split_first() else {
+            continue;
+        };
+        let tool = tool.rsplit('/').next().unwrap_or(tool);
+        let mut project = None;
+        let mut version = None;
+        let kind = match (tool, args) {
+            ("cargo", ["publish", rest @ ..]) => {
+                let mut rest = rest.iter();
+                while let Some(word) = rest.next() {
+                    if let Some(package) = word.strip_prefix("--package=") {
+                        project = Some(package.to_string());
+                    } else if matches!(*word, "-p" | "--package") {
+                        project = rest.next().map(|p| p.to_string());
+                    }
+                }
+                ReleaseKind::CargoPublish
+            }
+            ("npm" | "pnpm", ["publish", ..])
+            | ("yarn", ["publish", ..] | ["npm", "publish", ..]) => ReleaseKind::NpmPublish,
+            ("gh", ["release", "create", rest @ ..]) => {
+                let mut rest = rest.iter();
+                while let Some(word) = rest.next() {
+                    if let Some(repo) = word.strip_prefix("--repo=") {
+                        project = Some(repo.to_string());
+                    } else if matches!(*word, "-R" | "--repo") {
+                        project = rest.next().map(|r| r.to_string());
+                    } else if gh_flag_takes_value(word) {
+                        rest.next();
+                    } else if !word.starts_with('-') && version.is_none() {
+                        version = Some(word.to_string());
+                    }
+                }
+                ReleaseKind::GithubRelease
+            }
+            _ => continue,
+        };
+        releases.push(ReleaseCommand {
+            kind,
+            project,
+            version,
+        });
+    }
+    releases
+}
+
+/// The project a command run in `directory` belongs to: the repository containing it, or the
+/// directory's name.
+fn project_for(directory: &Path, repos: &[GitRepoHistory]) -> String {
+    repos
+        .iter()
+        .filter(|repo| directory.starts_with(&repo.diff.repo_path))
+        .max_by_key(|repo| repo.diff.repo_path.components().count())
+        .map(GitRepoHistory::project_name)
+        .unwrap_or_else(|| {
+            directory
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| directory.display().to_string())
+        })
+}
+
+/// Find the successful publish and release commands in shell history.
+pub fn find_release_commands(
+    shell_history: &[ShellHistoryEntry],
+    repos: &[GitRepoHistory],
+) -> Vec<Release> {
+    let mut releases = Vec::new();
+    for entry in shell_history.iter().filter(|entry| entry.exit_code == 0) {
+        for command in parse_command(&entry.command) {
+            releases.push(Release {
+                kind: command.kind,
+                project: command
+                    .project
+                    .unwrap_or_else(|| project_for(&entry.directory, repos)),
+                version: command.version,
+                time: entry.date_time,
+            });
+        }
+    }
+    releases
+}
+
+/// Collect releases made during the window: tags created in the repositories with recent
+/// commits, and `cargo publish`, `npm publish`, and `gh release create` runs from shell history.
+#[tracing::instrument(
+    name = "Collecting releases",
+    level = "info",
+    skip(shell_history, repos)
+)]
+pub async fn get_releases(
+    shell_history: &[ShellHistoryEntry],
+    repos: &[GitRepoHistory],
+    duration: &Duration,
+) -> AppResult<Vec<Release>> {
+    let since = past_ts(duration);
+    let mut releases = find_release_commands(shell_history, repos);
+    for repo in repos {
+        let tags = ReadOnlyRepoAccess::open(&repo.diff.repo_path)
+            .and_then(|access| access.tags_since(since));
+        match tags {
+            Ok(tags) => releases.extend(tags.into_iter().map(|(tag, time)| Release {
+                kind: ReleaseKind::Tag,
+                project: repo.project_name(),
+                version: Some(tag),
+                time,
+            })),
+            Err(e) => warnings::record(
+                WarningKind::Skipped,
+                "releases",
+                format!(
+                    "Failed to read tags for {}: {e}",
+                    repo.diff.repo_path.display()
+                ),
+            ),
+        }
+    }
+    releases.sort_by_key(|release| release.time);
+    debug!("Found {} releases", releases.len());
+    Ok(releases)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn entry(command: &str, directory: &str, exit_code: i64) -> ShellHistoryEntry {
+        ShellHistoryEntry {
+            date_time: datetime!(2025-01-01 09:00 UTC),
+            duration: Duration::ZERO,
+            host: "laptop".into(),
+            directory: PathBuf::from(directory),
+            command: command.into(),
+            exit_code,
+            session_id: "s".into(),
+        }
+    }
+
+    #[test]
+    fn parses_publish_and_release_commands() {
+        assert_eq!(
+            parse_command("cargo publish -p daily-ai-core --locked"),
+            vec![ReleaseCommand {
+                kind: ReleaseKind::CargoPublish,
+                project: Some("daily-ai-core".into()),
+                version: None,
+            }]
+        );
+        assert_eq!(
+            parse_command("gh release create -R annie444/daily-ai --title Final v1.2.0 dist/*"),
+            vec![ReleaseCommand {
+                kind: ReleaseKind::GithubRelease,
+                project: Some("annie444/daily-ai".into()),
+                version: Some("v1.2.0".into()),
+            }]
+        );
+        assert_eq!(
+            parse_command("npm version patch && yarn npm publish --access public")[0].kind,
+            ReleaseKind::NpmPublish
+        );
+        assert!(parse_command("cargo publish --dry-run").is_empty());
+        assert!(parse_command("gh release list").is_empty());
+    }
+
+    #[test]
+    fn skips_failed_commands_and_names_the_directory() {
+        let releases = find_release_commands(
+            &[
+                entry("npm publish", "/src/widgets", 0),
+                entry("cargo publish", "/src/parser", 101),
+            ],
+            &[],
+        );
+
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].describe(), "Published widgets to npm");
+    }
+}
@@ -0,0 +1,138 @@
+use crate::dashboard::html::escape;
+
+/// Convert the inline Markdown the renderers emit (backslash escapes, code spans, and links)
+/// into HTML.
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\\' if rest.len() > 1 => {
+                let escaped = rest[1..].chars().next().unwrap_or('\\');
+                out.push_str(&escape(&escaped.to_string()));
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+            '`' => {
+                if let Some(end) = rest[1..].find('`') {
+                    out.push_str(&format!("<code>{}</code>", escape(&rest[1..1 + end])));
+                    rest = &rest[end + 2..];
+                    continue;
+                }
+            }
+            '[' => {
+                let link = rest.find("](").and_then(|mid| {
+                    let end = mid + 2 + rest[mid + 2..].find(')')?;
+                    Some((&rest[1..mid], &rest[mid + 2..end], end))
+                });
+                if let Some((label, url, end)) = link {
+                    out.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape(url),
+                        inline(label)
+                    ));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push_str(&escape(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Render the Markdown produced by [`super::markdown`] and the other Markdown reports as HTML
+/// elements, without a surrounding document.
+///
+/// Only the subset those reports use is understood: ATX headings, `-` bullets, paragraphs,
+/// code spans, and links. Anything else is shown as text.
+pub fn markdown_body(markdown: &str) -> String {
+    let mut body = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut in_list = false;
+    let flush = |body: &mut String, paragraph: &mut Vec<String>, in_list: &mut bool| {
+        if !paragraph.is_empty() {
+            body.push_str(&format!("<p>{}</p>\n", paragraph.join(" ")));
+            paragraph.clear();
+        }
+        if *in_list {
+            body.push_str("</ul>\n");
+            *in_list = false;
+        }
+    };
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        let heading = line
+            .find(' ')
+            .filter(|&level| (1..=6).contains(&level) && line[..level].chars().all(|c| c == '#'));
+        if let Some(level) = heading {
+            flush(&mut body, &mut paragraph, &mut in_list);
+            body.push_str(&format!(
+                "<h{level}>{}</h{level}>\n",
+                inline(line[level..].trim())
+            ));
+        } else if let Some(item) = line.strip_prefix("- ") {
+            if !paragraph.is_empty() {
+                flush(&mut body, &mut paragraph, &mut in_list);
+            }
+            if !in_list {
+                body.push_str("<ul>\n");
+                in_list = true;
+            }
+            body.push_str(&format!("<li>{}</li>\n", inline(item.trim())));
+        } else if line.is_empty() {
+            flush(&mut body, &mut paragraph, &mut in_list);
+        } else {
+            if in_list {
+                flush(&mut body, &mut paragraph, &mut in_list);
+            }
+            paragraph.push(inline(line.trim()));
+        }
+    }
+    flush(&mut body, &mut paragraph, &mut in_list);
+    body
+}
+
+/// Render Markdown reports as a standalone HTML document, e.g. for an email body; see
+/// [`markdown_body`].
+pub fn render_markdown(title: &str, markdown: &str) -> String {
+    let body = markdown_body(markdown);
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+</head>
+<body style="font-family: -apple-system, 'Segoe UI', sans-serif; max-width: 42em; line-height: 1.5">
+{body}</body>
+</html>
+"#,
+        escape(title)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_markdown_report_subset() {
+        let html = render_markdown(
+            "Daily summary",
+            "# Daily summary\n\nShipped the parser.\nAll green.\n\n## Commits\n\n\
+             - [`abc1234`](https://example.com/c/abc1234) Fix \\<tag\\> & \\*stars\\*\n\
+             - `def5678` Bump CI\n\nRun `01J`\n",
+        );
+
+        assert!(html.contains("<h1>Daily summary</h1>"));
+        assert!(html.contains("<p>Shipped the parser. All green.</p>"));
+        assert!(html.contains(
+            "<ul>\n<li><a href=\"https://example.com/c/abc1234\"><code>abc1234</code></a> \
+             Fix &lt;tag&gt; &amp; *stars*</li>\n<li><code>def5678</code> Bump CI</li>\n</ul>"
+        ));
+        assert!(html.contains("<p>Run <code>01J</code></p>"));
+    }
+}
@@ -0,0 +1,183 @@
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Duration, OffsetDateTime, UtcOffset};
+use tracing::warn;
+
+use crate::AppResult;
+use crate::ai::summary::TimeBlock;
+use crate::context::FullContext;
+
+/// Content lines longer than this many octets are folded (RFC 5545 §3.1).
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Escape a TEXT value (RFC 5545 §3.3.11).
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append `line` with CRLF, folding it onto continuation lines that start with a space.
+fn push_line(out: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            octets = 1;
+        }
+        out.push(c);
+        octets += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// A UTC DATE-TIME such as `20250131T090500Z`.
+fn timestamp(time: OffsetDateTime) -> AppResult<String> {
+    Ok(time.to_offset(UtcOffset::UTC).format(format_description!(
+        "[year][month][day]T[hour][minute][second]Z"
+    ))?)
+}
+
+/// Start and end of `block`. A missing end is taken from the estimated duration, and so is a
+/// missing start when the end is known; blocks with neither are left out.
+fn block_times(block: &TimeBlock) -> Option<(OffsetDateTime, OffsetDateTime)> {
+    let parse = |time: &Option<String>| {
+        time.as_deref()
+            .and_then(|time| OffsetDateTime::parse(time.trim(), &Rfc3339).ok())
+    };
+    let duration = humantime::parse_duration(block.duration.trim())
+        .ok()
+        .and_then(|duration| Duration::try_from(duration).ok());
+    let (start, end) = match (parse(&block.start), parse(&block.end)) {
+        (Some(start), Some(end)) => (start, end),
+        (Some(start), None) => (start, start + duration?),
+        (None, Some(end)) => (end - duration?, end),
+        (None, None) => return None,
+    };
+    (end > start).then_some((start, end))
+}
+
+/// The events of a run's time breakdown as an iCalendar file, `now` being the creation time.
+fn calendar(context: &FullContext, now: OffsetDateTime) -> AppResult<String> {
+    let blocks = context
+        .summary
+        .as_ref()
+        .map(|summary| summary.time_blocks.as_slice())
+        .unwrap_or_default();
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(
+        &mut out,
+        &format!(
+            "PRODID:-//daily-ai//daily-ai {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+    );
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    let stamp = timestamp(now)?;
+    let mut skipped = 0;
+    for (index, block) in blocks.iter().enumerate() {
+        let Some((start, end)) = block_times(block) else {
+            skipped += 1;
+            continue;
+        };
+        let start = timestamp(start)?;
+        // The same run always produces the same ids, so re-importing replaces its events.
+        let uid = match context.run_id {
+            Some(run_id) => format!("{run_id}-{index}@daily-ai"),
+            None => format!("{start}-{index}@daily-ai"),
+        };
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{uid}"));
+        push_line(&mut out, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut out, &format!("DTSTART:{start}"));
+        push_line(&mut out, &format!("DTEND:{}", timestamp(end)?));
+        push_line(
+            &mut out,
+            &format!("SUMMARY:{}", escape(block.description.trim())),
+        );
+        push_line(
+            &mut out,
+            &format!(
+                "DESCRIPTION:{}",
+                escape(&format!("Estimated time: {}", block.duration.trim()))
+            ),
+        );
+        push_line(&mut out, "TRANSP:TRANSPARENT");
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    if skipped > 0 {
+        warn!("{skipped} time blocks have no start or end time and were left out of the calendar");
+    }
+    Ok(out)
+}
+
+/// Render the run's time breakdown as an iCalendar (`.ics`) file with one event per block.
+pub fn render(context: &FullContext) -> AppResult<String> {
+    calendar(context, OffsetDateTime::now_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+
+    #[test]
+    fn writes_an_event_per_timed_block() {
+        let block = |description: &str, start: Option<&str>, end: Option<&str>| TimeBlock {
+            duration: "1h 30m".into(),
+            description: description.into(),
+            start: start.map(str::to_string),
+            end: end.map(str::to_string),
+        };
+        let context = FullContext {
+            summary: Some(WorkSummary {
+                time_blocks: vec![
+                    block(
+                        "Debugged the parser; fixed timestamps, finally",
+                        Some("2025-01-31T09:05:00+01:00"),
+                        Some("2025-01-31T10:00:00+01:00"),
+                    ),
+                    block("Code review", Some("2025-01-31T13:00:00Z"), None),
+                    block("Unplaced work", None, None),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let ics = calendar(&context, datetime!(2025-01-31 18:00 UTC)).unwrap();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART:20250131T080500Z\r\nDTEND:20250131T090000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Debugged the parser\\; fixed timestamps\\, finally\r\n"));
+        assert!(ics.contains("DTSTART:20250131T130000Z\r\nDTEND:20250131T143000Z\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_OCTETS));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let mut out = String::new();
+        push_line(&mut out, &format!("SUMMARY:{}", "é".repeat(50)));
+        let lines: Vec<&str> = out.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with(' '));
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+    }
+}
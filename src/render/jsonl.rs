@@ -0,0 +1,153 @@
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::AppResult;
+use crate::context::{FullContext, Section};
+
+/// Serialize `item` as one JSON object tagged with `type`. Items that are not objects are
+/// wrapped as `{"type": ..., "value": ...}`.
+fn tagged(kind: &str, item: impl Serialize) -> AppResult<Value> {
+    let mut object = match serde_json::to_value(item)? {
+        Value::Object(object) => object,
+        value => Map::from_iter([("value".to_string(), value)]),
+    };
+    object.insert("type".into(), kind.into());
+    Ok(Value::Object(object))
+}
+
+/// Render a run as JSON Lines: one tagged object per collected item and per summary section.
+///
+/// The first line (`"type": "run"`) identifies the run. Sources get one line per item, tagged
+/// with the source's section name, except that browsing is split into `url_cluster` and `url`
+/// lines and git history into `repository` and `commit` lines. Patches are left out; use
+/// `--format dir` for those.
+pub fn render(context: &FullContext) -> AppResult<String> {
+    let mut lines = vec![tagged(
+        "run",
+        json!({
+            "run_id": context.run_id,
+            "version": context.context.version,
+            "failed_collectors": context.context.failed_collectors,
+        }),
+    )?];
+    for section in context.context.sections() {
+        match section {
+            Section::SafariHistory(clusters) => {
+                for cluster in clusters {
+                    lines.push(tagged(
+                        "url_cluster",
+                        json!({ "label": cluster.label, "urls": cluster.urls.len() }),
+                    )?);
+                    for url in &cluster.urls {
+                        let mut line = tagged("url", url)?;
+                        line["cluster"] = cluster.label.clone().into();
+                        lines.push(line);
+                    }
+                }
+            }
+            Section::CommitHistory(repos) => {
+                for repo in repos {
+                    let path = &repo.diff.repo_path;
+                    lines.push(tagged(
+                        "repository",
+                        json!({
+                            "repo": path,
+                            "project": repo.project_name(),
+                            "remote_url": repo.web_url(),
+                            "default_branch": repo.default_branch,
+                            "unpushed": repo.unpushed,
+                            "parked": repo.parked,
+                            "dependency_changes": repo.dependency_changes,
+                            "limitations": repo.limitations,
+                        }),
+                    )?);
+                    for commit in &repo.commits {
+                        let mut line = tagged("commit", commit)?;
+                        line["repo"] = serde_json::to_value(path)?;
+                        line["url"] = repo.commit_url(commit).into();
+                        lines.push(line);
+                    }
+                }
+            }
+            section => {
+                let kind = section.kind().name();
+                if let Value::Array(items) = section.items_json()? {
+                    for item in items {
+                        lines.push(tagged(kind, item)?);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(summary) = &context.summary
+        && let Value::Object(fields) = serde_json::to_value(summary)?
+    {
+        for (name, content) in fields {
+            let empty = match &content {
+                Value::String(text) => text.is_empty(),
+                Value::Array(items) => items.is_empty(),
+                Value::Null => true,
+                _ => false,
+            };
+            if !empty {
+                lines.push(tagged(
+                    "summary_section",
+                    json!({ "section": name, "content": content }),
+                )?);
+            }
+        }
+    }
+    for warning in &context.data_quality {
+        lines.push(tagged("data_warning", warning)?);
+    }
+
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&serde_json::to_string(&line)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+    use crate::context::Context;
+    use crate::tmux::TmuxSession;
+
+    #[test]
+    fn emits_one_tagged_line_per_item() {
+        let session = |name: &str| TmuxSession {
+            multiplexer: Default::default(),
+            name: name.into(),
+            created: OffsetDateTime::UNIX_EPOCH,
+            last_activity: OffsetDateTime::UNIX_EPOCH,
+            attached: false,
+            attached_for: time::Duration::ZERO,
+            windows: Vec::new(),
+        };
+        let context = FullContext {
+            context: Context::default().with_tmux(vec![session("work"), session("play")]),
+            summary: Some(WorkSummary {
+                summary: "Shipped JSONL.".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let output = render(&context).unwrap();
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let types: Vec<&str> = lines.iter().map(|l| l["type"].as_str().unwrap()).collect();
+        assert_eq!(types, ["run", "tmux", "tmux", "summary_section"]);
+        assert_eq!(lines[2]["name"], "play");
+        assert_eq!(lines[3]["section"], "summary");
+        assert_eq!(lines[3]["content"], "Shipped JSONL.");
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::context::FullContext;
+use crate::git::GitRepoHistory;
+use crate::git::deps::DependencyChange;
+use crate::tags::Tagging;
+
+/// Escape characters that would otherwise be read as Markdown link or emphasis syntax.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Append a `## title` section with one bullet per item, or nothing when there are no items.
+fn push_list(out: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "## {title}\n");
+    for item in items {
+        let _ = writeln!(out, "- {}", escape(item));
+    }
+    out.push('\n');
+}
+
+/// `text` with the emoji and tags given to `key`, if any.
+fn tagged(tags: &BTreeMap<String, Tagging>, key: &str, text: &str) -> String {
+    match tags.get(key) {
+        Some(tagging) => tagging.decorate(text),
+        None => text.to_string(),
+    }
+}
+
+/// Commits for one repository, linked to the forge when the remote is known.
+fn push_repo_commits(out: &mut String, repo: &GitRepoHistory, tags: &BTreeMap<String, Tagging>) {
+    let name = repo.project_name();
+    let _ = write!(out, "### {}", tagged(tags, &name, &escape(&name)));
+    if let Some(web_url) = repo.web_url() {
+        let _ = write!(out, " ([remote]({web_url}))");
+    }
+    let _ = writeln!(out, "\n\n`{}`\n", repo.diff.repo_path.display());
+    for commit in &repo.commits {
+        let short = commit.id.get(..7).unwrap_or(&commit.id);
+        match repo.commit_url(commit) {
+            Some(url) => {
+                let _ = writeln!(out, "- [`{short}`]({url}) {}", escape(&commit.summary));
+            }
+            None if !short.is_empty() => {
+                let _ = writeln!(out, "- `{short}` {}", escape(&commit.summary));
+            }
+            None => {
+                let _ = writeln!(out, "- {}", escape(&commit.summary));
+            }
+        }
+    }
+    let dependencies: Vec<String> = repo
+        .dependency_changes
+        .iter()
+        .filter(|change| change.direct)
+        .map(DependencyChange::describe)
+        .collect();
+    if !dependencies.is_empty() {
+        let _ = writeln!(out, "\nDependencies: {}", escape(&dependencies.join("; ")));
+    }
+    out.push('\n');
+}
+
+/// Render the summary and commit list of a run as a Markdown document.
+pub fn render(context: &FullContext) -> String {
+    let mut out = String::from("# Daily summary\n\n");
+    let no_tags = BTreeMap::new();
+    let tags = context
+        .summary
+        .as_ref()
+        .map_or(&no_tags, |summary| &summary.tags);
+    if let Some(summary) = &context.summary {
+        if !summary.summary.is_empty() {
+            let _ = writeln!(out, "{}\n", summary.summary.trim());
+        }
+        let highlights: Vec<String> = summary
+            .highlights
+            .iter()
+            .map(|highlight| tagged(tags, highlight, highlight))
+            .collect();
+        for (title, items) in [
+            ("Highlights", &highlights),
+            ("Releases", &summary.releases),
+            ("Time breakdown", &summary.time_breakdown),
+            ("Tickets touched", &summary.tickets_touched),
+            ("Pending review", &summary.pending_review),
+            ("Communication", &summary.communication),
+            ("Repositories", &summary.repo_summaries),
+            ("Common themes", &summary.common_groups),
+        ] {
+            push_list(&mut out, title, items);
+        }
+        // Browsing groups are listed only to show their tags.
+        let browsing: Vec<String> = context
+            .context
+            .safari_history()
+            .iter()
+            .filter(|cluster| tags.contains_key(&cluster.label))
+            .map(|cluster| {
+                let pages = match cluster.urls.len() {
+                    1 => "1 page".to_string(),
+                    n => format!("{n} pages"),
+                };
+                format!("{} ({pages})", tagged(tags, &cluster.label, &cluster.label))
+            })
+            .collect();
+        push_list(&mut out, "Browsing", &browsing);
+        if !summary.shell_overview.is_empty() {
+            let _ = writeln!(out, "## Shell\n\n{}\n", summary.shell_overview.trim());
+        }
+        push_list(&mut out, "Learned today", &summary.learned);
+        push_list(&mut out, "Notes", &summary.notes);
+        push_list(&mut out, "Pace", &summary.pace);
+    }
+    let data_quality: Vec<String> = context
+        .data_quality
+        .iter()
+        .map(|warning| format!("{} ({}): {}", warning.kind, warning.source, warning.message))
+        .collect();
+    push_list(&mut out, "Data quality", &data_quality);
+    if !context.context.recordings().is_empty() {
+        out.push_str("## Recordings\n\n");
+        for recording in context.context.recordings() {
+            let _ = writeln!(
+                out,
+                "- [{}](<file://{}>) ({})",
+                escape(&recording.title),
+                recording.path.display(),
+                recording.length()
+            );
+        }
+        out.push('\n');
+    }
+    if context
+        .context
+        .commit_history()
+        .iter()
+        .any(|repo| !repo.commits.is_empty())
+    {
+        out.push_str("## Commits\n\n");
+        for repo in context
+            .context
+            .commit_history()
+            .iter()
+            .filter(|repo| !repo.commits.is_empty())
+        {
+            push_repo_commits(&mut out, repo, tags);
+        }
+    }
+    if let Some(run_id) = context.run_id {
+        let _ = writeln!(out, "Run `{run_id}`");
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+    use crate::classify::UrlCluster;
+    use crate::context::Context;
+    use crate::git::CommitMeta;
+    use crate::git::diff::DiffSummary;
+    use crate::recordings::Recording;
+    use crate::warnings::{DataWarning, WarningKind};
+
+    #[test]
+    fn links_commits_to_the_forge() {
+        let commit = |id: &str, summary: &str| CommitMeta {
+            id: id.into(),
+            summary: summary.into(),
+            body: None,
+            timestamp: OffsetDateTime::UNIX_EPOCH,
+            branches: vec!["main".into()],
+        };
+        let diff = DiffSummary {
+            repo_path: PathBuf::from("/src/daily-ai"),
+            unmodified: HashSet::new(),
+            added: Vec::new(),
+            deleted: HashSet::new(),
+            modified: Vec::new(),
+            renamed: HashSet::new(),
+            copied: HashSet::new(),
+            untracked: Vec::new(),
+            typechange: HashSet::new(),
+            unreadable: HashSet::new(),
+            conflicted: HashSet::new(),
+        };
+        let context = FullContext {
+            context: Context::default().with_commit_history(vec![GitRepoHistory {
+                diff,
+                commits: vec![commit(
+                    "0123456789abcdef0123456789abcdef01234567",
+                    "Add *markdown* output",
+                )],
+                unpushed: Vec::new(),
+                parked: Vec::new(),
+                dependency_changes: Vec::new(),
+                remote_url: Some("git@github.com:annie444/daily-ai.git".into()),
+                default_branch: Some("main".into()),
+                limitations: Vec::new(),
+            }]),
+            summary: Some(WorkSummary {
+                summary: "Shipped the renderer.".into(),
+                highlights: vec!["Markdown output".into()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let markdown = render(&context);
+
+        assert!(markdown.contains("Shipped the renderer.\n\n## Highlights\n\n- Markdown output\n"));
+        assert!(
+            markdown
+                .contains("### annie444/daily-ai ([remote](https://github.com/annie444/daily-ai))")
+        );
+        assert!(markdown.contains(
+            "- [`0123456`](https://github.com/annie444/daily-ai/commit/0123456789abcdef0123456789abcdef01234567) Add \\*markdown\\* output"
+        ));
+        assert!(!markdown.contains("## Time breakdown"));
+        assert!(!markdown.contains("## Data quality"));
+    }
+
+    #[test]
+    fn lists_data_quality_warnings() {
+        let context = FullContext {
+            data_quality: vec![DataWarning {
+                kind: WarningKind::Truncated,
+                source: "git".into(),
+                message: "The 2.0 MiB patch for assets/data.csv was reduced to line counts".into(),
+            }],
+            ..Default::default()
+        };
+
+        assert!(render(&context).ends_with(
+            "## Data quality\n\n- Truncated (git): The 2.0 MiB patch for assets/data.csv was reduced to line counts\n"
+        ));
+    }
+
+    #[test]
+    fn links_recordings() {
+        let context = FullContext {
+            context: Context::default().with_recordings(vec![Recording {
+                title: "Parser [demo]".into(),
+                path: PathBuf::from("/home/me/casts/parser demo.cast"),
+                command: None,
+                recorded: OffsetDateTime::UNIX_EPOCH,
+                duration: time::Duration::seconds(192),
+            }]),
+            ..Default::default()
+        };
+
+        assert!(render(&context).ends_with(
+            "## Recordings\n\n- [Parser \\[demo\\]](<file:///home/me/casts/parser demo.cast>) (3m 12s)\n"
+        ));
+    }
+
+    #[test]
+    fn shows_tags_next_to_tagged_items() {
+        let research = Tagging {
+            emoji: "🔍".into(),
+            tags: vec!["research".into()],
+        };
+        let context = FullContext {
+            context: Context::default().with_safari_history(vec![
+                UrlCluster {
+                    label: "Tokio docs".into(),
+                    urls: Vec::new(),
+                },
+                UrlCluster {
+                    label: "Untagged".into(),
+                    urls: Vec::new(),
+                },
+            ]),
+            summary: Some(WorkSummary {
+                highlights: vec!["Read about *tokio*".into(), "Lunch".into()],
+                tags: BTreeMap::from([
+                    ("Tokio docs".to_string(), research.clone()),
+                    ("Read about *tokio*".to_string(), research),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let markdown = render(&context);
+
+        assert!(
+            markdown.contains("## Highlights\n\n- 🔍 Read about \\*tokio\\* #research\n- Lunch\n")
+        );
+        assert!(markdown.contains("## Browsing\n\n- 🔍 Tokio docs #research (0 pages)\n\n"));
+    }
+
+    #[test]
+    fn ends_with_the_run_id() {
+        let run_id = ulid::Ulid::from_string("01JH8ZQ0000000000000000000").unwrap();
+        let context = FullContext {
+            run_id: Some(run_id),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&context),
+            "# Daily summary\n\nRun `01JH8ZQ0000000000000000000`\n"
+        );
+    }
+}
@@ -0,0 +1,32 @@
+/// Standalone HTML rendering of the Markdown reports, used for email bodies.
+pub(crate) mod html;
+
+/// iCalendar export of a run's time breakdown.
+pub(crate) mod ics;
+
+/// JSON Lines rendering, one tagged object per collected item.
+pub(crate) mod jsonl;
+
+/// Markdown rendering of a run's summary and commits.
+pub(crate) mod markdown;
+
+/// Obsidian daily-note section, merged into an existing note.
+pub(crate) mod obsidian;
+
+/// Org-mode rendering of a run's summary and commits.
+pub(crate) mod org;
+
+/// PDF rendering of a run, laid out from the Markdown output.
+pub(crate) mod pdf;
+
+/// Plain-text "Yesterday / Today / Blockers" stand-up rendering.
+pub(crate) mod standup;
+
+/// User-supplied minijinja templates for `--template`.
+pub(crate) mod template;
+
+/// ANSI rendering of Markdown and paging through `$PAGER`.
+pub(crate) mod terminal;
+
+/// Plain-text rendering of a run, wrapped for screen readers and mail.
+pub(crate) mod text;
@@ -0,0 +1,97 @@
+use super::markdown;
+use crate::context::FullContext;
+
+/// Heading of the section daily-ai owns in the daily note.
+pub const HEADING: &str = "## Daily AI";
+
+/// Level of an ATX heading line (`## Title` is 2), or `None` for any other line.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// The run rendered as the body of the `## Daily AI` section: the Markdown output without its
+/// title, with headings moved two levels down so they nest under the section.
+pub fn section(context: &FullContext) -> String {
+    let rendered = markdown::render(context);
+    let mut lines: Vec<String> = Vec::new();
+    for line in rendered.lines() {
+        match heading_level(line) {
+            Some(1) => continue,
+            Some(_) => lines.push(format!("##{line}")),
+            None => lines.push(line.to_string()),
+        }
+    }
+    let body = lines.join("\n");
+    format!("{HEADING}\n\n{}\n", body.trim())
+}
+
+/// Merge `section` into the daily note `existing`.
+///
+/// An earlier `## Daily AI` section is replaced up to the next heading of level 1 or 2, so
+/// anything the user wrote before or after it is kept. Without one, the section is appended.
+pub fn merge(existing: &str, section: &str) -> String {
+    let lines: Vec<&str> = existing.lines().collect();
+    let Some(start) = lines.iter().position(|line| line.trim_end() == HEADING) else {
+        let existing = existing.trim_end();
+        return if existing.is_empty() {
+            section.to_string()
+        } else {
+            format!("{existing}\n\n{section}")
+        };
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| heading_level(line).is_some_and(|level| level <= 2))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    let mut merged = String::new();
+    for line in &lines[..start] {
+        merged.push_str(line);
+        merged.push('\n');
+    }
+    merged.push_str(section);
+    if end < lines.len() {
+        merged.push('\n');
+        for line in &lines[end..] {
+            merged.push_str(line);
+            merged.push('\n');
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+
+    #[test]
+    fn replaces_only_its_own_section() {
+        let context = FullContext {
+            summary: Some(WorkSummary {
+                summary: "Shipped the parser.".into(),
+                highlights: vec!["Parser rewrite".into()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let section = section(&context);
+        assert_eq!(
+            section,
+            "## Daily AI\n\nShipped the parser.\n\n#### Highlights\n\n- Parser rewrite\n"
+        );
+
+        let fresh = merge("", &section);
+        assert_eq!(fresh, section);
+        let note = "# 2025-01-31\n\n## Journal\nSlept well.\n";
+        let appended = merge(note, &section);
+        assert_eq!(appended, format!("{}\n\n{section}", note.trim_end()));
+
+        let edited = format!("{appended}\n## Evening\nGym.\n");
+        let rerun = merge(&edited, "## Daily AI\n\nSecond run.\n");
+        assert_eq!(
+            rerun,
+            "# 2025-01-31\n\n## Journal\nSlept well.\n\n## Daily AI\n\nSecond run.\n\n## Evening\nGym.\n"
+        );
+    }
+}
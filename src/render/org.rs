@@ -0,0 +1,145 @@
+use std::fmt::Write;
+
+use crate::context::FullContext;
+use crate::git::GitRepoHistory;
+
+/// Keep `text` from closing an Org link description early.
+fn link_description(text: &str) -> String {
+    text.replace('[', "(").replace(']', ")")
+}
+
+/// Append a `* title` heading with one list item per entry, or nothing when there are none.
+fn push_list(out: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "* {title}\n");
+    for item in items {
+        let _ = writeln!(out, "- {}", item.trim());
+    }
+    out.push('\n');
+}
+
+/// Commits for one repository, linked to the forge when the remote is known.
+fn push_repo_commits(out: &mut String, repo: &GitRepoHistory) {
+    match repo.web_url() {
+        Some(web_url) => {
+            let _ = writeln!(
+                out,
+                "** [[{web_url}][{}]]",
+                link_description(&repo.project_name())
+            );
+        }
+        None => {
+            let _ = writeln!(out, "** {}", repo.project_name());
+        }
+    }
+    let _ = writeln!(out, "\n~{}~\n", repo.diff.repo_path.display());
+    for commit in &repo.commits {
+        let short = commit.id.get(..7).unwrap_or(&commit.id);
+        match repo.commit_url(commit) {
+            Some(url) => {
+                let _ = writeln!(out, "- [[{url}][{short}]] {}", commit.summary);
+            }
+            None if !short.is_empty() => {
+                let _ = writeln!(out, "- ={short}= {}", commit.summary);
+            }
+            None => {
+                let _ = writeln!(out, "- {}", commit.summary);
+            }
+        }
+    }
+    out.push('\n');
+}
+
+/// Render the summary and commit list of a run as an Org document, with the same sections as
+/// the Markdown output.
+pub fn render(context: &FullContext) -> String {
+    let mut out = String::from("#+TITLE: Daily summary\n");
+    if let Some(run_id) = context.run_id {
+        let _ = writeln!(out, "#+PROPERTY: RUN_ID {run_id}");
+    }
+    out.push('\n');
+    if let Some(summary) = &context.summary {
+        if !summary.summary.is_empty() {
+            let _ = writeln!(out, "{}\n", summary.summary.trim());
+        }
+        for (title, items) in [
+            ("Highlights", &summary.highlights),
+            ("Releases", &summary.releases),
+            ("Time breakdown", &summary.time_breakdown),
+            ("Tickets touched", &summary.tickets_touched),
+            ("Pending review", &summary.pending_review),
+            ("Communication", &summary.communication),
+            ("Repositories", &summary.repo_summaries),
+            ("Common themes", &summary.common_groups),
+        ] {
+            push_list(&mut out, title, items);
+        }
+        if !summary.shell_overview.is_empty() {
+            let _ = writeln!(out, "* Shell\n\n{}\n", summary.shell_overview.trim());
+        }
+        push_list(&mut out, "Learned today", &summary.learned);
+        push_list(&mut out, "Notes", &summary.notes);
+        push_list(&mut out, "Pace", &summary.pace);
+    }
+    let data_quality: Vec<String> = context
+        .data_quality
+        .iter()
+        .map(|warning| format!("{} ({}): {}", warning.kind, warning.source, warning.message))
+        .collect();
+    push_list(&mut out, "Data quality", &data_quality);
+    if !context.context.recordings().is_empty() {
+        out.push_str("* Recordings\n\n");
+        for recording in context.context.recordings() {
+            let _ = writeln!(
+                out,
+                "- [[file:{}][{}]] ({})",
+                recording.path.display(),
+                link_description(&recording.title),
+                recording.length()
+            );
+        }
+        out.push('\n');
+    }
+    let repos: Vec<&GitRepoHistory> = context
+        .context
+        .commit_history()
+        .iter()
+        .filter(|repo| !repo.commits.is_empty())
+        .collect();
+    if !repos.is_empty() {
+        out.push_str("* Commits\n\n");
+        for repo in repos {
+            push_repo_commits(&mut out, repo);
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+
+    #[test]
+    fn renders_sections_as_org_headings() {
+        let context = FullContext {
+            run_id: Some(ulid::Ulid::from_string("01JH8ZQ0000000000000000000").unwrap()),
+            summary: Some(WorkSummary {
+                summary: "Shipped the renderer.".into(),
+                highlights: vec!["Org output".into()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&context),
+            "#+TITLE: Daily summary\n#+PROPERTY: RUN_ID 01JH8ZQ0000000000000000000\n\nShipped the renderer.\n\n* Highlights\n\n- Org output\n"
+        );
+        assert_eq!(link_description("a [b]"), "a (b)");
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, ObjectId, Stream, dictionary};
+use tracing::warn;
+
+use super::markdown;
+use crate::AppResult;
+use crate::context::FullContext;
+
+/// US Letter, in points.
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 54.0;
+
+const BODY_SIZE: f32 = 10.5;
+/// Bullet text is indented by this much, with the bullet hanging in the margin.
+const BULLET_INDENT: f32 = 14.0;
+
+/// Average Helvetica glyph width as a fraction of the font size, used to wrap lines. Slightly
+/// generous so that wide glyphs rarely run into the margin.
+const AVERAGE_GLYPH_WIDTH: f32 = 0.52;
+
+/// Fonts from the standard 14, so nothing needs embedding.
+const REGULAR: &str = "F1";
+const BOLD: &str = "F2";
+
+/// Split `text` into lines of at most `max_chars` characters, breaking at spaces where possible.
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word;
+        while word.chars().count() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let split = word
+                .char_indices()
+                .nth(max_chars)
+                .map_or(word.len(), |(i, _)| i);
+            lines.push(word[..split].to_string());
+            word = &word[split..];
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Strip inline Markdown: links become their label, and code ticks and escapes are dropped.
+fn plain(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut chars = markdown.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            '`' | '[' => {}
+            ']' if chars.peek() == Some(&'(') => {
+                // Skip the link target.
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        break;
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Encode `text` for the standard fonts' WinAnsiEncoding, replacing anything it lacks with `?`
+/// and adding it to `missing`.
+fn win_ansi(text: &str, missing: &mut BTreeSet<char>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            ' '..='~' => bytes.push(c as u8),
+            '\u{a0}'..='\u{ff}' => bytes.push(c as u32 as u8),
+            '€' => bytes.push(0x80),
+            '…' => bytes.push(0x85),
+            '‘' => bytes.push(0x91),
+            '’' => bytes.push(0x92),
+            '“' => bytes.push(0x93),
+            '”' => bytes.push(0x94),
+            '•' => bytes.push(0x95),
+            '–' => bytes.push(0x96),
+            '—' => bytes.push(0x97),
+            '→' => bytes.extend(b"->"),
+            '\t' => bytes.push(b' '),
+            _ => {
+                missing.insert(c);
+                bytes.push(b'?');
+            }
+        }
+    }
+    bytes
+}
+
+/// Lays text out top to bottom, starting a new page when the current one is full.
+struct Layout {
+    pages: Vec<Vec<Operation>>,
+    y: f32,
+    /// Characters the fonts lack, shown as `?`.
+    missing: BTreeSet<char>,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout {
+            pages: vec![Vec::new()],
+            y: PAGE_HEIGHT - MARGIN,
+            missing: BTreeSet::new(),
+        }
+    }
+
+    /// Move down by `height`, breaking the page first if it would not fit.
+    fn advance(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+    }
+
+    fn text(&mut self, font: &str, size: f32, x: f32, text: &str) {
+        let page = self.pages.last_mut().expect("layout always has a page");
+        page.extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![font.into(), size.into()]),
+            Operation::new("Td", vec![x.into(), self.y.into()]),
+            Operation::new(
+                "Tj",
+                vec![Object::string_literal(win_ansi(text, &mut self.missing))],
+            ),
+            Operation::new("ET", vec![]),
+        ]);
+    }
+
+    /// Write a wrapped paragraph, with `marker` (e.g. a bullet) hanging before the first line.
+    fn paragraph(&mut self, font: &str, size: f32, indent: f32, marker: Option<&str>, text: &str) {
+        let width = PAGE_WIDTH - 2.0 * MARGIN - indent;
+        let max_chars = (width / (size * AVERAGE_GLYPH_WIDTH)) as usize;
+        for (i, line) in wrap(text, max_chars.max(1)).iter().enumerate() {
+            self.advance(size * 1.35);
+            if let Some(marker) = marker.filter(|_| i == 0) {
+                self.text(font, size, MARGIN + indent - BULLET_INDENT * 0.7, marker);
+            }
+            self.text(font, size, MARGIN + indent, line);
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+}
+
+/// Lay out Markdown headings, bullets, and paragraphs.
+fn lay_out(markdown: &str) -> Layout {
+    let mut layout = Layout::new();
+    for line in markdown.lines() {
+        if let Some(title) = line.strip_prefix("# ") {
+            layout.paragraph(BOLD, 20.0, 0.0, None, &plain(title));
+            layout.gap(6.0);
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            layout.gap(8.0);
+            layout.paragraph(BOLD, 14.0, 0.0, None, &plain(heading));
+            layout.gap(2.0);
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            layout.gap(4.0);
+            layout.paragraph(BOLD, 12.0, 0.0, None, &plain(heading));
+        } else if let Some(item) = line.strip_prefix("- ") {
+            layout.paragraph(REGULAR, BODY_SIZE, BULLET_INDENT, Some("•"), &plain(item));
+        } else if line.trim().is_empty() {
+            layout.gap(BODY_SIZE * 0.6);
+        } else {
+            layout.paragraph(REGULAR, BODY_SIZE, 0.0, None, &plain(line));
+        }
+    }
+    layout
+}
+
+/// Render the summary and commit list of a run as a PDF document.
+///
+/// The layout follows the Markdown output, set in Helvetica so no fonts are embedded. The
+/// standard fonts only cover Windows-1252, so other characters, such as CJK text or emoji, are
+/// shown as `?` and listed in a warning; the Markdown and text outputs keep them.
+pub fn render(context: &FullContext) -> AppResult<Vec<u8>> {
+    render_markdown(&markdown::render(context))
+}
+
+/// Lay out a Markdown document of headings, bullets, and paragraphs as a PDF document.
+pub fn render_markdown(markdown: &str) -> AppResult<Vec<u8>> {
+    let layout = lay_out(markdown);
+    if !layout.missing.is_empty() {
+        let missing: String = layout.missing.iter().take(20).collect();
+        warn!(
+            "The PDF shows {} characters its fonts lack as `?`: {missing}; use another --format \
+             to keep them",
+            layout.missing.len()
+        );
+    }
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font = |name: &str| {
+        dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => Object::Name(name.as_bytes().to_vec()),
+            "Encoding" => "WinAnsiEncoding",
+        }
+    };
+    let regular_id = doc.add_object(font("Helvetica"));
+    let bold_id = doc.add_object(font("Helvetica-Bold"));
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! {
+            REGULAR => regular_id,
+            BOLD => bold_id,
+        },
+    });
+
+    let mut kids: Vec<Object> = Vec::with_capacity(layout.pages.len());
+    for operations in layout.pages {
+        let content = Content { operations }.encode()?;
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        let page_id: ObjectId = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(page_id.into());
+    }
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+
+    #[test]
+    fn wraps_and_strips_markdown() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10),
+            ["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap("abcdefghijkl", 5), ["abcde", "fghij", "kl"]);
+        assert_eq!(
+            plain("[`0123456`](https://example.com/c/0123456) Add \\*markdown\\*"),
+            "0123456 Add *markdown*"
+        );
+        let mut missing = BTreeSet::new();
+        assert_eq!(
+            win_ansi("café — ok ✓ 日本", &mut missing),
+            b"caf\xe9 \x97 ok ? ??"
+        );
+        assert_eq!(missing, BTreeSet::from(['✓', '日', '本']));
+    }
+
+    #[test]
+    fn renders_a_multi_page_document() {
+        let context = FullContext {
+            summary: Some(WorkSummary {
+                summary: "Shipped the renderer.".into(),
+                highlights: (0..80).map(|i| format!("Highlight number {i}")).collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let bytes = render(&context).unwrap();
+
+        assert!(bytes.starts_with(b"%PDF-1.5"));
+        let doc = Document::load_mem(&bytes).unwrap();
+        assert!(doc.get_pages().len() >= 2);
+    }
+}
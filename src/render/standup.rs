@@ -0,0 +1,97 @@
+use std::fmt::Write;
+
+use crate::ai::standup::Standup;
+use crate::ai::summary::WorkSummary;
+use crate::context::FullContext;
+
+/// Build an update from the summary's own fields, for runs without a generated stand-up (e.g.
+/// archived runs): highlights become "yesterday" and unpushed work becomes "today".
+fn from_summary(summary: &WorkSummary) -> Standup {
+    let yesterday = if summary.highlights.is_empty() {
+        summary
+            .summary
+            .split_inclusive(". ")
+            .next()
+            .map(|sentence| sentence.trim().to_string())
+            .filter(|sentence| !sentence.is_empty())
+            .into_iter()
+            .collect()
+    } else {
+        summary.highlights.clone()
+    };
+    Standup {
+        yesterday,
+        today: summary.pending_review.clone(),
+        blockers: Vec::new(),
+    }
+}
+
+fn push_part(out: &mut String, title: &str, items: &[String], empty: &str) {
+    let _ = writeln!(out, "{title}:");
+    if items.is_empty() {
+        let _ = writeln!(out, "- {empty}");
+    }
+    for item in items {
+        let _ = writeln!(out, "- {}", item.trim());
+    }
+}
+
+/// Render a run as a plain-text "Yesterday / Today / Blockers" stand-up update.
+pub fn render(context: &FullContext) -> String {
+    let standup = match &context.summary {
+        Some(WorkSummary {
+            standup: Some(standup),
+            ..
+        }) => standup.clone(),
+        Some(summary) => from_summary(summary),
+        None => Standup::default(),
+    };
+    let mut out = String::new();
+    push_part(
+        &mut out,
+        "Yesterday",
+        &standup.yesterday,
+        "Nothing recorded",
+    );
+    push_part(&mut out, "Today", &standup.today, "Nothing planned yet");
+    push_part(&mut out, "Blockers", &standup.blockers, "None");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_the_generated_update_and_falls_back_to_the_summary() {
+        let mut summary = WorkSummary {
+            summary: "Rewrote the parser. Then reviewed PRs.".into(),
+            pending_review: vec!["parser-rewrite: 3 unpushed commits".into()],
+            ..Default::default()
+        };
+        let context = |summary: &WorkSummary| FullContext {
+            summary: Some(WorkSummary {
+                summary: summary.summary.clone(),
+                pending_review: summary.pending_review.clone(),
+                standup: summary.standup.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&context(&summary)),
+            "Yesterday:\n- Rewrote the parser.\nToday:\n- parser-rewrite: 3 unpushed commits\nBlockers:\n- None\n"
+        );
+
+        summary.standup = Some(Standup {
+            yesterday: vec!["Rewrote the parser".into()],
+            today: vec!["Open PR for the parser rewrite".into()],
+            blockers: vec!["Waiting on CI runners".into()],
+        });
+        assert_eq!(
+            render(&context(&summary)),
+            "Yesterday:\n- Rewrote the parser\nToday:\n- Open PR for the parser rewrite\nBlockers:\n- Waiting on CI runners\n"
+        );
+    }
+}
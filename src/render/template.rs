@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use minijinja::{Environment, UndefinedBehavior};
+use serde_json::{Map, Value, json};
+use time::OffsetDateTime;
+use time::macros::format_description;
+use tokio::fs;
+
+use super::markdown;
+use crate::AppResult;
+use crate::context::FullContext;
+use crate::time_utils;
+
+/// A user-supplied minijinja template for `--template`.
+#[derive(Debug, Clone)]
+pub struct ReportTemplate {
+    /// File name, which also decides auto-escaping: `.html` and `.xml` templates escape values.
+    name: String,
+    source: String,
+}
+
+impl ReportTemplate {
+    /// Read and parse the template at `path`, so a broken template fails before collecting.
+    pub async fn load(path: &Path) -> AppResult<Self> {
+        let source = fs::read_to_string(path).await?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "template".into());
+        let template = ReportTemplate { name, source };
+        Environment::new().template_from_named_str(&template.name, &template.source)?;
+        Ok(template)
+    }
+
+    /// Render the template with the run's variables; see [`variables`].
+    pub fn render(&self, context: &FullContext) -> AppResult<String> {
+        let now = time_utils::now_local();
+        let mut env = Environment::new();
+        // Lets templates walk into sections and summary fields a run does not have.
+        env.set_undefined_behavior(UndefinedBehavior::Chainable);
+        let template = env.template_from_named_str(&self.name, &self.source)?;
+        Ok(template.render(variables(context, now)?)?)
+    }
+}
+
+/// Variables available to templates:
+///
+/// - `summary`: the [`WorkSummary`](crate::ai::summary::WorkSummary), when the run has one.
+/// - `sources`: each collected source's items by section name, e.g. `sources.commit_history`.
+/// - `data_quality`, `failed_collectors`, and `run_id`.
+/// - `markdown`: the run as the Markdown output renders it, to wrap or embed.
+/// - `date` and `time`: when the report was rendered, as `2025-01-31` and `09:30`.
+fn variables(context: &FullContext, now: OffsetDateTime) -> AppResult<Value> {
+    let mut sources = Map::new();
+    for section in context.context.sections() {
+        sources.insert(section.kind().name().to_string(), section.items_json()?);
+    }
+    let mut variables = json!({
+        "sources": sources,
+        "data_quality": context.data_quality,
+        "failed_collectors": context.context.failed_collectors,
+        "run_id": context.run_id.map(|id| id.to_string()),
+        "markdown": markdown::render(context),
+        "date": now.format(format_description!("[year]-[month]-[day]"))?,
+        "time": now.format(format_description!("[hour]:[minute]"))?,
+    });
+    if let Some(summary) = &context.summary {
+        variables["summary"] = serde_json::to_value(summary)?;
+    }
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::ai::summary::WorkSummary;
+    use crate::context::Context;
+    use crate::email::EmailThread;
+
+    #[test]
+    fn exposes_sources_by_name_and_the_summary() {
+        let thread = EmailThread {
+            source: "IMAP".into(),
+            subject: "Release plan".into(),
+            senders: Default::default(),
+            sent: 1,
+            received: 0,
+            first_message: datetime!(2025-01-31 09:00 UTC),
+            last_message: datetime!(2025-01-31 09:00 UTC),
+        };
+        let mut context = FullContext::from(Context::default().with_email(vec![thread]));
+        let now = datetime!(2025-01-31 17:45 UTC);
+
+        let vars = variables(&context, now).unwrap();
+        assert_eq!(vars["sources"]["email"][0]["subject"], "Release plan");
+        assert_eq!(
+            (vars["date"].as_str(), vars["time"].as_str()),
+            (Some("2025-01-31"), Some("17:45"))
+        );
+        assert!(vars.get("summary").is_none());
+
+        context.summary = Some(WorkSummary {
+            highlights: vec!["Parser rewrite".into()],
+            ..Default::default()
+        });
+        let vars = variables(&context, now).unwrap();
+        assert_eq!(vars["summary"]["highlights"][0], "Parser rewrite");
+        assert!(
+            vars["markdown"]
+                .as_str()
+                .unwrap()
+                .contains("- Parser rewrite")
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::env;
+use std::io::{ErrorKind, Write};
+use std::process::Stdio;
+
+use termimad::MadSkin;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::AppResult;
+
+/// Pager used when `$PAGER` is not set.
+const DEFAULT_PAGER: &str = "less";
+
+/// `less` flags used when `$LESS` is not set: keep colours, and exit when the text fits on
+/// one screen.
+const DEFAULT_LESS: &str = "FRX";
+
+/// Render Markdown as ANSI-styled text wrapped to `width` columns.
+pub fn to_ansi(markdown: &str, width: usize) -> String {
+    MadSkin::default().text(markdown, Some(width)).to_string()
+}
+
+/// Render Markdown as ANSI-styled text for the current terminal.
+pub fn to_terminal(markdown: &str) -> String {
+    let (width, _) = termimad::terminal_size();
+    to_ansi(markdown, width as usize)
+}
+
+/// Print `text` to stdout, ignoring a closed pipe (e.g. `daily-ai | head`).
+pub fn print(text: impl AsRef<[u8]>) -> AppResult<()> {
+    match std::io::stdout().lock().write_all(text.as_ref()) {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Show `text` through `$PAGER` (`less` by default).
+///
+/// Falls back to printing directly when the pager is empty, `cat`, or cannot be started.
+pub async fn page(text: &str) -> AppResult<()> {
+    let pager = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.into());
+    if pager.trim().is_empty() || pager.trim() == "cat" {
+        return print(text);
+    }
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(&pager).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", DEFAULT_LESS);
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            debug!("Could not start pager {pager}: {e}");
+            return print(text);
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closes its input when the user quits before reaching the end.
+        match stdin.write_all(text.as_bytes()).await {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+    let status = child.wait().await?;
+    debug!("Pager {pager} exited with {status}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown_as_ansi() {
+        let rendered = to_ansi("# Daily summary\n\n- **Shipped** the `parser` fix\n", 80);
+
+        assert!(rendered.contains("Daily summary"));
+        assert!(rendered.contains("Shipped"));
+        assert!(rendered.contains("\x1b["));
+        assert!(!rendered.contains("**"));
+        assert!(!rendered.contains("# "));
+    }
+}
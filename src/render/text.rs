@@ -0,0 +1,165 @@
+use std::sync::OnceLock;
+
+use super::markdown;
+use crate::context::FullContext;
+
+/// Columns plain text is wrapped at unless `--text-width` says otherwise.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Columns to wrap at, from `--text-width`.
+static WIDTH: OnceLock<usize> = OnceLock::new();
+
+/// Wrap plain text at `width` columns from now on. Only the first call has an effect.
+pub fn configure(width: usize) {
+    let _ = WIDTH.set(width);
+}
+
+/// The inline Markdown the renderers emit (backslash escapes, code spans, emphasis, and links)
+/// as plain text. Links keep their target in parentheses, as they would be read out.
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\\' if rest.len() > 1 => {
+                let escaped = rest[1..].chars().next().unwrap_or('\\');
+                out.push(escaped);
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+            '`' | '*' => {
+                rest = &rest[1..];
+                continue;
+            }
+            '[' => {
+                let link = rest.find("](").and_then(|mid| {
+                    let end = mid + 2 + rest[mid + 2..].find(')')?;
+                    Some((&rest[1..mid], &rest[mid + 2..end], end))
+                });
+                if let Some((label, url, end)) = link {
+                    let label = inline(label);
+                    let url = url.trim_start_matches('<').trim_end_matches('>');
+                    if label == url {
+                        out.push_str(url);
+                    } else {
+                        out.push_str(&format!("{label} ({url})"));
+                    }
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Append `text` wrapped at `width` columns, starting the first line with `first` and the others
+/// with `rest`. Words longer than a line are left whole.
+fn push_wrapped(out: &mut String, text: &str, width: usize, first: &str, rest: &str) {
+    let mut line = first.to_string();
+    let mut empty = true;
+    for word in text.split_whitespace() {
+        let columns = line.chars().count() + word.chars().count() + usize::from(!empty);
+        if !empty && columns > width {
+            out.push_str(line.trim_end());
+            out.push('\n');
+            line = rest.to_string();
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        empty = false;
+    }
+    out.push_str(line.trim_end());
+    out.push('\n');
+}
+
+/// Turn the Markdown produced by [`super::markdown`] and the other Markdown reports into plain
+/// text wrapped at `width` columns.
+///
+/// Headings are underlined with `=` for the title, `-` for sections, and `~` below that, so
+/// they read the same in a terminal, an email, and a screen reader. Bullets keep their `-`, with
+/// wrapped lines indented under the text.
+fn from_markdown(markdown: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let flush = |out: &mut String, paragraph: &mut Vec<String>| {
+        if !paragraph.is_empty() {
+            push_wrapped(out, &paragraph.join(" "), width, "", "");
+            paragraph.clear();
+        }
+    };
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        let heading = line
+            .find(' ')
+            .filter(|&level| (1..=6).contains(&level) && line[..level].chars().all(|c| c == '#'));
+        if let Some(level) = heading {
+            flush(&mut out, &mut paragraph);
+            let title = inline(line[level..].trim());
+            let marker = match level {
+                1 => "=",
+                2 => "-",
+                _ => "~",
+            };
+            out.push_str(&title);
+            out.push('\n');
+            out.push_str(&marker.repeat(title.chars().count().min(width)));
+            out.push('\n');
+        } else if let Some(item) = line.strip_prefix("- ") {
+            flush(&mut out, &mut paragraph);
+            push_wrapped(&mut out, &inline(item.trim()), width, "- ", "  ");
+        } else if line.is_empty() {
+            flush(&mut out, &mut paragraph);
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+        } else {
+            paragraph.push(inline(line.trim()));
+        }
+    }
+    flush(&mut out, &mut paragraph);
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// Render a Markdown report as plain text wrapped at `--text-width`.
+pub fn render_markdown(markdown: &str) -> String {
+    from_markdown(markdown, WIDTH.get().copied().unwrap_or(DEFAULT_WIDTH))
+}
+
+/// Render the summary and commit list of a run as plain text.
+pub fn render(context: &FullContext) -> String {
+    render_markdown(&markdown::render(context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown_as_wrapped_plain_text() {
+        let markdown = "# Daily summary\n\nShipped the **parser** and `lexer`.\n\n\
+             ## Highlights\n\n\
+             - Fixed \\*all\\* the [flaky tests](https://ci.example.com/runs/7) in the parser crate\n\
+             - [Parser \\[demo\\]](<file:///casts/demo.cast>) (3m 12s)\n\n\
+             ### me/app\n\nRun `01JH8ZQ`\n";
+
+        assert_eq!(
+            from_markdown(markdown, 40),
+            "Daily summary\n=============\n\n\
+             Shipped the parser and lexer.\n\n\
+             Highlights\n----------\n\n\
+             - Fixed *all* the flaky tests\n  (https://ci.example.com/runs/7) in the\n  parser crate\n\
+             - Parser [demo]\n  (file:///casts/demo.cast) (3m 12s)\n\n\
+             me/app\n~~~~~~\n\n\
+             Run 01JH8ZQ\n"
+        );
+    }
+}
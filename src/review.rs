@@ -0,0 +1,142 @@
+use std::env;
+use std::io::Write;
+
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::dirs::DirType;
+use crate::error::AppError;
+
+/// Editor used for `--interactive` edits when neither `$VISUAL` nor `$EDITOR` is set.
+const DEFAULT_EDITOR: &str = "vi";
+
+/// What the user decided about a section of the summary.
+pub enum Decision<T> {
+    /// Use it as the model wrote it.
+    Accept,
+    /// Ask the model again, with this feedback as an extra note.
+    Regenerate(String),
+    /// Use the user's edit instead.
+    Edited(T),
+}
+
+/// An answer to the review question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Accept,
+    Regenerate,
+    Edit,
+}
+
+/// The choice `answer` names, with an empty answer accepting.
+fn parse_choice(answer: &str) -> Option<Choice> {
+    match answer.trim().to_lowercase().as_str() {
+        "" | "a" | "accept" | "y" | "yes" => Some(Choice::Accept),
+        "r" | "regenerate" => Some(Choice::Regenerate),
+        "e" | "edit" => Some(Choice::Edit),
+        _ => None,
+    }
+}
+
+/// Write `text` to the terminal. The review goes to stderr so it never mixes with a summary
+/// printed to stdout.
+fn show(text: &str) -> AppResult<()> {
+    let mut stderr = std::io::stderr().lock();
+    stderr.write_all(text.as_bytes())?;
+    stderr.flush()?;
+    Ok(())
+}
+
+/// Print `question` and read one line of answer, or `None` at the end of input.
+async fn ask(question: &str) -> AppResult<Option<String>> {
+    show(question)?;
+    Ok(BufReader::new(tokio::io::stdin())
+        .lines()
+        .next_line()
+        .await?)
+}
+
+/// Open `text` in `$VISUAL` or `$EDITOR` and return what the user saved.
+async fn edit(text: &str) -> AppResult<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let path = DirType::Cache
+        .ensure_dir_async()
+        .await?
+        .join("review-section.json");
+    fs::write(&path, text).await?;
+    // The editor may carry arguments, e.g. `code --wait`, so it is run through the shell.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status()
+        .await?;
+    debug!("Editor {editor} exited with {status}");
+    let edited = fs::read_to_string(&path).await;
+    let _ = fs::remove_file(&path).await;
+    if !status.success() {
+        return Err(AppError::Other(format!(
+            "The editor {editor} exited with {status}"
+        )));
+    }
+    Ok(edited?)
+}
+
+/// Show the `title` section of the summary, as the JSON `section`, and ask whether to accept
+/// it, regenerate it with feedback, or edit it in `$EDITOR`. Edits are read back with `parse`,
+/// and ones that do not parse are offered for editing again. The end of input accepts.
+pub async fn review<T>(
+    title: &str,
+    section: &str,
+    parse: impl Fn(&str) -> AppResult<T>,
+) -> AppResult<Decision<T>> {
+    show(&format!("\n── {title} ──\n{section}\n\n"))?;
+    let mut draft = section.to_string();
+    loop {
+        let Some(answer) = ask("Accept, regenerate with feedback, or edit? [A/r/e] ").await? else {
+            return Ok(Decision::Accept);
+        };
+        match parse_choice(&answer) {
+            Some(Choice::Accept) => return Ok(Decision::Accept),
+            Some(Choice::Regenerate) => {
+                let feedback = ask("What should change? ").await?.unwrap_or_default();
+                if !feedback.trim().is_empty() {
+                    return Ok(Decision::Regenerate(feedback.trim().to_string()));
+                }
+            }
+            Some(Choice::Edit) => {
+                let edited = edit(&draft).await?;
+                match parse(&edited) {
+                    Ok(section) => return Ok(Decision::Edited(section)),
+                    Err(e) => {
+                        show(&format!("The edited {title} could not be read: {e}\n"))?;
+                        draft = edited;
+                    }
+                }
+            }
+            None => show("Answer a to accept, r to regenerate, or e to edit.\n")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_short_and_long_answers() {
+        assert_eq!(parse_choice(""), Some(Choice::Accept));
+        assert_eq!(parse_choice(" Y\n"), Some(Choice::Accept));
+        assert_eq!(parse_choice("r"), Some(Choice::Regenerate));
+        assert_eq!(parse_choice("Edit"), Some(Choice::Edit));
+        assert_eq!(parse_choice("maybe"), None);
+    }
+}
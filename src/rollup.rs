@@ -0,0 +1,155 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use clap::ValueEnum;
+use time::Duration;
+
+use crate::ai::rollup::{RollupQuery, generate_rollup};
+use crate::ai::token_budget::TokenBudget;
+use crate::archive;
+use crate::error::AppError;
+use crate::pace;
+use crate::stats::Trends;
+use crate::{AppResult, io_utils, time_utils};
+
+/// How much time a rollup covers.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RollupPeriod {
+    /// The last 7 days
+    #[default]
+    Week,
+    /// The last 30 days
+    Month,
+}
+
+impl RollupPeriod {
+    fn duration(self) -> Duration {
+        match self {
+            RollupPeriod::Week => Duration::days(7),
+            RollupPeriod::Month => Duration::days(30),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            RollupPeriod::Week => "week",
+            RollupPeriod::Month => "month",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            RollupPeriod::Week => "Weekly report",
+            RollupPeriod::Month => "Monthly report",
+        }
+    }
+}
+
+fn push_list(out: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "## {title}\n");
+    for item in items {
+        let _ = writeln!(out, "- {}", item.trim());
+    }
+    out.push('\n');
+}
+
+/// Render a rollup as Markdown: the model's report, the period's numbers, and any pace notes.
+pub fn render(
+    period: RollupPeriod,
+    report: &RollupQuery,
+    trends: &Trends,
+    pace: &[pace::PaceSignal],
+) -> String {
+    let mut out = format!("# {}\n\n", period.title());
+    if let (Some(from), Some(to)) = (trends.from, trends.to) {
+        let _ = writeln!(out, "{from} to {to}, {} logged days\n", trends.days);
+    }
+    if !report.summary.is_empty() {
+        let _ = writeln!(out, "{}\n", report.summary.trim());
+    }
+    push_list(&mut out, "Accomplishments", &report.accomplishments);
+    push_list(&mut out, "Themes", &report.themes);
+    push_list(&mut out, "Trends", &report.trends);
+    let numbers = [
+        format!(
+            "Commits: {} ({}/day)",
+            trends.commits, trends.commits_per_day
+        ),
+        format!("Active hours: {}", trends.active_hours),
+        format!(
+            "Deep-work hours: {} ({}/day)",
+            trends.deep_work_hours, trends.deep_work_hours_per_day
+        ),
+    ];
+    push_list(&mut out, "By the numbers", &numbers);
+    push_list(&mut out, "Carried over", &report.carry_over);
+    let pace: Vec<String> = pace.iter().map(ToString::to_string).collect();
+    push_list(&mut out, "Pace", &pace);
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// Summarize the archived days of the last `period` into one report, written to `output` or
+/// shown. The days the model looks up are cut to fit `budget`.
+#[tracing::instrument(name = "Rolling up daily summaries", level = "info", skip(client))]
+pub async fn rollup<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    period: RollupPeriod,
+    output: Option<&Path>,
+    budget: TokenBudget,
+) -> AppResult<()> {
+    let now = time_utils::now_local();
+    let today = now.date();
+    let since = today.saturating_sub(period.duration());
+    // Sorted oldest first, so the period is one slice.
+    let archived = archive::load_all().await?;
+    let start = archived.partition_point(|entry| entry.date <= since);
+    let end = archived.partition_point(|entry| entry.date <= today);
+    let entries = &archived[start..end.max(start)];
+    if entries.iter().all(|entry| entry.context.summary.is_none()) {
+        return Err(AppError::Config(format!(
+            "No summarized runs in the last {}. Run `daily-ai summarize` to start filling the \
+             archive",
+            period.name()
+        )));
+    }
+    let trends = Trends::compute(entries);
+    let report = generate_rollup(client, model, period.name(), entries, &trends, budget).await?;
+    // The whole archive, not just the period, so streaks that started earlier still count.
+    let offset = time_utils::local_offset();
+    let pace = pace::assess(archived.iter().map(|entry| &entry.context), today, offset);
+    io_utils::output_markdown(output, &render(period, &report, &trends, &pace)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_report_numbers_and_pace() {
+        let report = RollupQuery {
+            summary: "A week of parser work.".into(),
+            accomplishments: vec!["Landed the winnow parser".into()],
+            themes: Vec::new(),
+            trends: Vec::new(),
+            carry_over: Vec::new(),
+            notes: Vec::new(),
+        };
+        let trends = Trends::compute(&[]);
+        let pace = [pace::PaceSignal::WeekendStreak { weekends: 2 }];
+
+        let markdown = render(RollupPeriod::Week, &report, &trends, &pace);
+
+        assert!(markdown.starts_with(
+            "# Weekly report\n\nA week of parser work.\n\n## Accomplishments\n\n- Landed the winnow parser\n\n## By the numbers\n\n- Commits: 0 (0/day)\n"
+        ));
+        assert!(markdown.ends_with("## Pace\n\n- There has been work on each of the last 2 weekends. Consider keeping the next one free.\n"));
+    }
+}
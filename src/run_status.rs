@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use tokio::fs;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::dirs::DirType;
+use crate::time_utils;
+
+/// File in the state directory that describes the current or last run.
+const STATUS_FILE: &str = "run-status.json";
+
+/// Where a run, or one stage of it, has got to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Running,
+    Finished,
+    Failed,
+}
+
+/// One step of a run, such as a collector or the summary.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Stage {
+    /// The collector's name, e.g. `git`, or the step, e.g. `summary`.
+    pub name: String,
+    pub state: State,
+    /// When the stage started, as RFC 3339.
+    pub started: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished: Option<String>,
+    /// How many items the stage produced, e.g. commits and commands for a collector.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Progress of a run, written to `run-status.json` in the state directory as each stage starts
+/// and finishes, so menubar apps and launchers can show it without reading the logs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RunStatus {
+    pub run_id: String,
+    pub pid: u32,
+    pub state: State,
+    pub started: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished: Option<String>,
+    /// The stages in the order they started.
+    pub stages: Vec<Stage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RunStatus {
+    fn new(run_id: String) -> Self {
+        RunStatus {
+            run_id,
+            pid: std::process::id(),
+            state: State::Running,
+            started: now(),
+            finished: None,
+            stages: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Mark `name` as running, restarting it if it ran before.
+    fn start(&mut self, name: &str) {
+        self.stages.retain(|stage| stage.name != name);
+        self.stages.push(Stage {
+            name: name.to_string(),
+            state: State::Running,
+            started: now(),
+            finished: None,
+            count: None,
+            error: None,
+        });
+    }
+
+    /// Mark `name` as done, with the items it produced or the error it failed with.
+    fn finish(&mut self, name: &str, result: Result<Option<usize>, String>) {
+        if !self.stages.iter().any(|stage| stage.name == name) {
+            self.start(name);
+        }
+        let Some(stage) = self.stages.iter_mut().find(|stage| stage.name == name) else {
+            return;
+        };
+        stage.finished = Some(now());
+        match result {
+            Ok(count) => {
+                stage.state = State::Finished;
+                stage.count = count;
+            }
+            Err(error) => {
+                stage.state = State::Failed;
+                stage.error = Some(error);
+            }
+        }
+    }
+
+    /// Mark the whole run as done, failing any stage still running.
+    fn end(&mut self, error: Option<String>) {
+        let finished = now();
+        for stage in &mut self.stages {
+            if stage.state == State::Running {
+                stage.state = State::Failed;
+                stage.finished = Some(finished.clone());
+            }
+        }
+        self.state = if error.is_some() {
+            State::Failed
+        } else {
+            State::Finished
+        };
+        self.finished = Some(finished);
+        self.error = error;
+    }
+}
+
+tokio::task_local! {
+    static STATUS: RefCell<RunStatus>;
+}
+
+/// The current time as RFC 3339.
+fn now() -> String {
+    time_utils::now_local().format(&Rfc3339).unwrap_or_default()
+}
+
+/// The status of the current or last run, or `None` before the first one.
+pub async fn load() -> AppResult<Option<RunStatus>> {
+    let path = DirType::State.get_dir()?.join(STATUS_FILE);
+    match fs::read_to_string(&path).await {
+        Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace the status file with `json`. The file is written next to it and renamed into place,
+/// so a reader never sees half of it.
+async fn write_status(json: String) -> AppResult<()> {
+    let dir = DirType::State.ensure_dir_async().await?;
+    let partial = dir.join(format!("{STATUS_FILE}.{}", std::process::id()));
+    fs::write(&partial, json).await?;
+    fs::rename(&partial, dir.join(STATUS_FILE)).await?;
+    Ok(())
+}
+
+/// Apply `change` to the current run's status and write it out. Outside [`scope`] nothing
+/// happens; a status file that cannot be written is only logged, as it must not fail the run.
+async fn update(change: impl FnOnce(&mut RunStatus)) {
+    let Ok(json) = STATUS.try_with(|status| {
+        let mut status = status.borrow_mut();
+        change(&mut status);
+        serde_json::to_string_pretty(&*status)
+    }) else {
+        return;
+    };
+    let written: AppResult<()> = match json {
+        Ok(json) => write_status(json).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = written {
+        debug!("Unable to write {STATUS_FILE}: {e}");
+    }
+}
+
+/// Run `future` as the run `run_id`, reporting its progress in the status file.
+pub async fn scope<T, F>(run_id: String, future: F) -> AppResult<T>
+where
+    F: Future<Output = AppResult<T>>,
+{
+    STATUS
+        .scope(RefCell::new(RunStatus::new(run_id)), async move {
+            update(|_| {}).await;
+            let output = future.await;
+            let error = output.as_ref().err().map(ToString::to_string);
+            update(|status| status.end(error)).await;
+            output
+        })
+        .await
+}
+
+/// Report that the stage `name` has started.
+pub async fn start(name: &str) {
+    update(|status| status.start(name)).await;
+}
+
+/// Report that the stage `name` has finished, having produced `count` items when that means
+/// something for it.
+pub async fn finish(name: &str, count: Option<usize>) {
+    update(|status| status.finish(name, Ok(count))).await;
+}
+
+/// Report that the stage `name` failed with `error`.
+pub async fn fail(name: &str, error: impl ToString) {
+    update(|status| status.finish(name, Err(error.to_string()))).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_stages_and_fails_those_left_running() {
+        let mut status = RunStatus::new("run-1".into());
+        status.start("git");
+        status.finish("git", Ok(Some(3)));
+        status.start("shell");
+        status.finish("shell", Err("no history".into()));
+        status.start("summary");
+        status.end(Some("model unavailable".into()));
+
+        let states: Vec<_> = status
+            .stages
+            .iter()
+            .map(|stage| (stage.name.as_str(), stage.state, stage.count))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                ("git", State::Finished, Some(3)),
+                ("shell", State::Failed, None),
+                ("summary", State::Failed, None),
+            ]
+        );
+        assert_eq!(status.stages[1].error.as_deref(), Some("no history"));
+        assert_eq!(status.state, State::Failed);
+        assert!(status.stages.iter().all(|stage| stage.finished.is_some()));
+    }
+}
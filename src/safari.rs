@@ -2,15 +2,16 @@ use std::env;
 use std::path::{Path, PathBuf};
 
 use sea_orm::{
-    ColumnTrait, ConnectOptions, Database, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    ConnectOptions, Database, DatabaseConnection, DbBackend, FromQueryResult, Statement,
 };
 use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::AppResult;
-use crate::entity::{history_items, history_visits};
-use crate::time_utils::{datetime_to_macos_time, macos_past_ts, macos_to_datetime, midnight_utc};
+use crate::error::AppError;
+use crate::sqlite::{is_busy, retry_busy};
+use crate::time_utils::{macos_past_ts, macos_to_datetime, midnight_utc};
 
 /// Minimal subset of Safari history we need for downstream processing.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -22,6 +23,22 @@ pub struct SafariHistoryItem {
     pub last_visited: OffsetDateTime,
 }
 
+/// Whether `url` belongs to a sign-in, OAuth, or SSO flow, which says nothing about the work done.
+pub fn is_sign_in_url(url: &str) -> bool {
+    let mut url = url.to_lowercase();
+    url = url.replace("https://", "");
+    url = url.replace("http://", "");
+    let domain = url.rsplit_once('/').map(|(base, _)| base).unwrap_or(&url);
+    let (domain, path) = domain.split_once('/').unwrap_or((domain, ""));
+    domain.contains("oauth")
+        || domain.contains("login")
+        || path.contains("auth")
+        || path.contains("signin")
+        || domain.contains("sso")
+        || path.contains("callback")
+        || domain.contains("duosecurity")
+}
+
 /// Return true if a candidate path points to an existing file.
 fn valid_db_path(path: &Path) -> bool {
     path.exists() && path.is_file()
@@ -60,72 +77,258 @@ fn get_safari_history_db_path() -> PathBuf {
     .unwrap_or_else(|| PathBuf::from("/Users/username/Library/Safari/History.db"))
 }
 
-/// Open the Safari history sqlite database at the provided path.
+/// Open the Safari history sqlite database at the provided path. An `immutable` connection
+/// ignores the locks Safari holds, but also changes still in its write-ahead log.
 #[tracing::instrument(name = "Connecting to the Safari history database", level = "info")]
 async fn connect_to_db<P: AsRef<Path> + std::fmt::Debug>(
     db_path: P,
+    immutable: bool,
 ) -> AppResult<DatabaseConnection> {
-    let mut opt = ConnectOptions::new(format!("sqlite://{}", db_path.as_ref().display()));
+    let mut url = format!("sqlite://{}", db_path.as_ref().display());
+    if immutable {
+        url.push_str("?mode=ro&immutable=1");
+    }
+    let mut opt = ConnectOptions::new(url);
     opt.sqlx_logging(false);
     trace!("Connecting to Safari History database");
     let db = Database::connect(opt).await?;
     Ok(db)
 }
 
-/// Fetch Safari history entries from the past 24 hours (UTC) ordered by most recent visit.
+/// Pages visited after `?` (macOS seconds), each with its latest visit; SQLite takes the bare
+/// `{title}` column from the row `MAX` picked. `{title}` and `{visit_count}` are replaced by the
+/// columns the detected schema has.
+const HISTORY_QUERY: &str = "
+SELECT
+    i.url AS url,
+    {title} AS title,
+    {visit_count} AS visit_count,
+    MAX(v.visit_time) AS visit_time
+FROM history_items i
+JOIN history_visits v ON v.history_item = i.id
+WHERE v.visit_time > ?
+GROUP BY i.id
+ORDER BY visit_time DESC";
+
+/// Raw row returned by `HISTORY_QUERY`.
+#[derive(Debug, FromQueryResult)]
+struct HistoryRow {
+    url: String,
+    title: Option<String>,
+    visit_count: i64,
+    visit_time: Option<f64>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ColumnRow {
+    name: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct VersionRow {
+    user_version: i64,
+}
+
+/// The layout of a `History.db`, which changes between Safari releases: page titles moved from
+/// `history_items` to each visit, and `visit_count` is not kept by every release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SafariSchema {
+    /// Expression for the page title.
+    title: &'static str,
+    /// Expression for how often the page was visited.
+    visit_count: &'static str,
+}
+
+impl SafariSchema {
+    /// Work out the layout of `db`, or fail with the schema version and the missing columns when
+    /// it is not one daily-ai can read.
+    async fn detect(db: &DatabaseConnection) -> AppResult<Self> {
+        let version = VersionRow::find_by_statement(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT user_version FROM pragma_user_version",
+        ))
+        .one(db)
+        .await?
+        .map_or(0, |row| row.user_version);
+        let items = columns(db, "history_items").await?;
+        let visits = columns(db, "history_visits").await?;
+
+        // Every known layout has these.
+        let required: [(&str, &[String], &[&str]); 2] = [
+            ("history_items", &items, &["id", "url"]),
+            ("history_visits", &visits, &["history_item", "visit_time"]),
+        ];
+        let missing: Vec<String> = required
+            .into_iter()
+            .flat_map(|(table, present, needed)| {
+                needed
+                    .iter()
+                    .filter(|column| !present.iter().any(|c| c == *column))
+                    .map(move |column| format!("{table}.{column}"))
+            })
+            .collect();
+        if !missing.is_empty() {
+            return Err(AppError::UnsupportedSafariSchema(
+                version,
+                missing.join(", "),
+            ));
+        }
+
+        let has = |columns: &[String], name: &str| columns.iter().any(|c| c == name);
+        let schema = SafariSchema {
+            title: if has(&visits, "title") {
+                "v.title"
+            } else if has(&items, "title") {
+                "i.title"
+            } else {
+                "NULL"
+            },
+            visit_count: if has(&items, "visit_count") {
+                "i.visit_count"
+            } else {
+                "(SELECT COUNT(*) FROM history_visits c WHERE c.history_item = i.id)"
+            },
+        };
+        debug!("Detected Safari history schema version {version}: {schema:?}");
+        Ok(schema)
+    }
+
+    /// `HISTORY_QUERY` for this layout.
+    fn query(&self) -> String {
+        HISTORY_QUERY
+            .replace("{title}", self.title)
+            .replace("{visit_count}", self.visit_count)
+    }
+}
+
+/// Columns of `table`, or none when it does not exist.
+async fn columns(db: &DatabaseConnection, table: &str) -> AppResult<Vec<String>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "SELECT name FROM pragma_table_info(?)",
+        [table.into()],
+    );
+    Ok(ColumnRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| row.name)
+        .collect())
+}
+
+/// Fetch Safari history entries from the past 24 hours (UTC) ordered by most recent visit, from
+/// `db_path` or else the database found in the usual places.
+///
+/// Safari locks its database while writing to it. Reads are retried while it does, and a
+/// database that stays locked is read as immutable, which may miss the last few minutes.
 #[tracing::instrument(name = "Fetching the Safari history", level = "info")]
-pub async fn get_safari_history(duration: &Duration) -> AppResult<Vec<SafariHistoryItem>> {
-    let db_path = get_safari_history_db_path();
-    let db = connect_to_db(db_path).await?;
+pub async fn get_safari_history(
+    db_path: Option<&Path>,
+    duration: &Duration,
+) -> AppResult<Vec<SafariHistoryItem>> {
+    let db_path = db_path.map_or_else(get_safari_history_db_path, Path::to_path_buf);
+    let since = macos_past_ts(duration);
 
-    trace!("Connected to Safari History database");
+    let history = match retry_busy("Safari history", || read_history(&db_path, since, false)).await
+    {
+        Err(e) if is_busy(&e) => {
+            warn!("The Safari history database is still locked; reading it as immutable");
+            read_history(&db_path, since, true).await?
+        }
+        history => history?,
+    };
 
-    let past_date = macos_past_ts(duration);
-    let history_items = history_items::Entity::find()
-        .find_with_related(history_visits::Entity)
-        .filter(history_visits::Column::VisitTime.gt(past_date))
-        .order_by_desc(history_visits::Column::VisitTime)
-        .all(&db)
-        .await?;
+    debug!("Fetched {} history items", history.len());
 
-    debug!("Fetched {} history items", history_items.len());
+    Ok(history)
+}
+
+/// Connect to the database at `db_path` and read the pages visited after `since`.
+async fn read_history(
+    db_path: &Path,
+    since: f64,
+    immutable: bool,
+) -> AppResult<Vec<SafariHistoryItem>> {
+    let db = connect_to_db(db_path, immutable).await?;
+    trace!("Connected to Safari History database");
+    let history = fetch_history(&db, since).await;
+    let _ = db.close().await;
+    history
+}
+
+/// Run the query for the detected schema for pages visited after `since` (macOS seconds).
+async fn fetch_history(db: &DatabaseConnection, since: f64) -> AppResult<Vec<SafariHistoryItem>> {
+    let schema = SafariSchema::detect(db).await?;
+    let stmt = Statement::from_sql_and_values(DbBackend::Sqlite, schema.query(), [since.into()]);
+    let rows = HistoryRow::find_by_statement(stmt).all(db).await?;
 
     trace!("Processing Safari history items");
 
     let mid = midnight_utc();
-    let mid_macos = datetime_to_macos_time(&mid);
-
-    let safari_history = history_items
+    Ok(rows
         .into_iter()
-        .filter(|(item, _)| {
-            let mut url = item.url.to_lowercase();
-            url = url.replace("https://", "");
-            url = url.replace("http://", "");
-            let domain = url.rsplit_once('/').map(|(base, _)| base).unwrap_or(&url);
-            let (domain, path) = domain.split_once('/').unwrap_or((domain, ""));
-            !domain.contains("oauth")
-                && !domain.contains("login")
-                && !path.contains("auth")
-                && !path.contains("signin")
-                && !domain.contains("sso")
-                && !path.contains("callback")
-                && !domain.contains("duosecurity")
-        })
-        .map(|(item, visits)| {
-            // Use the first visit (most recent, due to order_by_desc) to drive title and timestamp.
-            let last_visited = visits.first().map_or(mid, |visit| {
-                macos_to_datetime(TryInto::<f64>::try_into(visit.visit_time).unwrap_or(mid_macos))
-            });
-            SafariHistoryItem {
-                url: item.url,
-                title: visits.first().and_then(|visit| visit.title.clone()),
-                visit_count: item.visit_count,
-                last_visited,
-            }
+        .filter(|row| !is_sign_in_url(&row.url))
+        .map(|row| SafariHistoryItem {
+            url: row.url,
+            title: row.title,
+            visit_count: row.visit_count,
+            last_visited: row.visit_time.map_or(mid, macos_to_datetime),
         })
-        .collect();
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+
+    async fn db(statements: &[&str]) -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in statements {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn reads_layouts_with_titles_on_items_and_no_visit_count() {
+        let db = db(&[
+            "CREATE TABLE history_items (id INTEGER PRIMARY KEY, url TEXT, title TEXT)",
+            "CREATE TABLE history_visits (id INTEGER PRIMARY KEY, history_item INTEGER, visit_time REAL)",
+            "INSERT INTO history_items VALUES (1, 'https://docs.rs/sea-orm', 'sea_orm - Rust'),
+                (2, 'https://login.example.com/', 'Sign in')",
+            "INSERT INTO history_visits VALUES (1, 1, 700000100), (2, 1, 700000300),
+                (3, 2, 700000200), (4, 1, 500000000)",
+        ])
+        .await;
+
+        let history = fetch_history(&db, 600_000_000.0).await.unwrap();
+
+        let [page] = &history[..] else {
+            panic!("expected one page, got {history:?}");
+        };
+        assert_eq!(page.url, "https://docs.rs/sea-orm");
+        assert_eq!(page.title.as_deref(), Some("sea_orm - Rust"));
+        assert_eq!(page.visit_count, 3);
+        assert_eq!(page.last_visited, macos_to_datetime(700_000_300.0));
+    }
+
+    #[tokio::test]
+    async fn names_the_version_and_columns_of_unknown_layouts() {
+        let db = db(&[
+            "PRAGMA user_version = 42",
+            "CREATE TABLE history_items (id INTEGER PRIMARY KEY, address TEXT)",
+            "CREATE TABLE history_visits (id INTEGER PRIMARY KEY, history_item INTEGER, visit_time REAL)",
+        ])
+        .await;
 
-    trace!("Completed processing Safari history items");
+        let error = fetch_history(&db, 0.0).await.unwrap_err();
 
-    Ok(safari_history)
+        assert!(
+            matches!(&error, AppError::UnsupportedSafariSchema(42, missing) if missing == "history_items.url"),
+            "{error}"
+        );
+        assert!(error.to_string().contains("file an issue"));
+    }
 }
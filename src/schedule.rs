@@ -0,0 +1,288 @@
+use std::path::Path;
+
+use regex::Regex;
+use time::{Duration, OffsetDateTime, Time};
+use tokio::fs;
+use tokio::process::Command;
+use tracing::debug;
+
+/// Name of the program as it appears in a crontab line or a launch agent.
+const PROGRAM: &str = "daily-ai";
+
+/// How far ahead to look for the next run of a schedule.
+const HORIZON_DAYS: i64 = 366;
+
+/// When cron runs a job: the sets of minutes, hours, days of the month, months, and weekdays
+/// (Sunday is 0) it fires on, as bit masks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of the month and the weekday were both restricted, in which case a day
+    /// matching either one runs the job.
+    either_day: bool,
+}
+
+/// The values `field` of a cron expression selects between `min` and `max`, as a bit mask.
+/// Supports `*`, numbers, ranges, lists, and steps; names such as `mon` are not.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (low, high) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((low, high)) => (low.parse().ok()?, high.parse().ok()?),
+            // `5/15` runs from 5 to the end of the range.
+            None if part.contains('/') => (range.parse().ok()?, max),
+            None => (range.parse().ok()?, range.parse().ok()?),
+        };
+        if low < min || high > max || low > high {
+            return None;
+        }
+        for value in (low..=high).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Some(mask)
+}
+
+impl CronSchedule {
+    /// Parse the five time fields of a crontab line, or one of the `@daily`-style shorthands.
+    pub fn parse(expression: &str) -> Option<Self> {
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+        let [minute, hour, day, month, weekday] = expression
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .try_into()
+            .ok()?;
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // Both 0 and 7 are Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Some(CronSchedule {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            either_day: day != "*" && weekday != "*",
+        })
+    }
+
+    /// The first time after `now` the schedule fires, in `now`'s offset.
+    pub fn next_after(&self, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        let start = now.replace_time(Time::from_hms(now.hour(), now.minute(), 0).ok()?)
+            + Duration::minutes(1);
+        for offset in 0..HORIZON_DAYS {
+            let date = start.date() + Duration::days(offset);
+            let day = self.days & (1 << date.day()) != 0;
+            let weekday = self.weekdays & (1 << date.weekday().number_days_from_sunday()) != 0;
+            let day_matches = if self.either_day {
+                day || weekday
+            } else {
+                day && weekday
+            };
+            if self.months & (1 << u8::from(date.month())) == 0 || !day_matches {
+                continue;
+            }
+            let first = if offset == 0 {
+                (start.hour(), start.minute())
+            } else {
+                (0, 0)
+            };
+            for hour in first.0..24 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+                let from = if hour == first.0 { first.1 } else { 0 };
+                if let Some(minute) = (from..60).find(|m| self.minutes & (1 << m) != 0) {
+                    let time = Time::from_hms(hour, minute, 0).ok()?;
+                    return Some(date.with_time(time).assume_offset(now.offset()));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A schedule that runs daily-ai, and where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledRun {
+    /// `crontab` or the path of the launch agent.
+    pub source: String,
+    /// The schedule as a cron expression.
+    pub expression: String,
+    pub next: OffsetDateTime,
+}
+
+/// Cron expressions of the crontab lines that run daily-ai.
+fn crontab_expressions(crontab: &str) -> Vec<String> {
+    crontab
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && line.contains(PROGRAM))
+        .filter_map(|line| {
+            if line.starts_with('@') {
+                return line.split_whitespace().next().map(str::to_string);
+            }
+            let fields: Vec<&str> = line.split_whitespace().take(5).collect();
+            (fields.len() == 5).then(|| fields.join(" "))
+        })
+        .collect()
+}
+
+/// Cron expressions for the `StartCalendarInterval` entries of a launch agent's property list.
+fn launchd_expressions(plist: &str) -> Vec<String> {
+    let Some((_, intervals)) = plist.split_once("<key>StartCalendarInterval</key>") else {
+        return Vec::new();
+    };
+    let key = Regex::new(r"<key>(Minute|Hour|Day|Month|Weekday)</key>\s*<integer>(\d+)</integer>")
+        .expect("valid regex");
+    let mut expressions = Vec::new();
+    // One `<dict>` per interval; the first one without any of the keys ends the list.
+    for dict in intervals.split("</dict>") {
+        let mut fields = ["*"; 5].map(str::to_string);
+        let mut found = false;
+        for capture in key.captures_iter(dict) {
+            let at = match &capture[1] {
+                "Minute" => 0,
+                "Hour" => 1,
+                "Day" => 2,
+                "Month" => 3,
+                _ => 4,
+            };
+            fields[at] = capture[2].to_string();
+            found = true;
+        }
+        if !found {
+            break;
+        }
+        expressions.push(fields.join(" "));
+    }
+    expressions
+}
+
+/// Schedules in the user's crontab.
+async fn from_crontab() -> Vec<(String, String)> {
+    match Command::new("crontab").arg("-l").output().await {
+        Ok(output) if output.status.success() => {
+            crontab_expressions(&String::from_utf8_lossy(&output.stdout))
+                .into_iter()
+                .map(|expression| ("crontab".to_string(), expression))
+                .collect()
+        }
+        Ok(_) => Vec::new(),
+        Err(e) => {
+            debug!("Unable to read the crontab: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// Schedules in the user's launch agents on macOS.
+async fn from_launch_agents(home: &Path) -> Vec<(String, String)> {
+    let mut schedules = Vec::new();
+    let Ok(mut entries) = fs::read_dir(home.join("Library/LaunchAgents")).await else {
+        return schedules;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "plist") {
+            continue;
+        }
+        let Ok(plist) = fs::read_to_string(&path).await else {
+            continue;
+        };
+        if plist.contains(PROGRAM) {
+            for expression in launchd_expressions(&plist) {
+                schedules.push((path.display().to_string(), expression));
+            }
+        }
+    }
+    schedules
+}
+
+/// The next time a crontab entry or launch agent runs daily-ai, if any does.
+pub async fn next_run(now: OffsetDateTime) -> Option<ScheduledRun> {
+    let mut schedules = from_crontab().await;
+    if let Some(home) = std::env::home_dir() {
+        schedules.extend(from_launch_agents(&home).await);
+    }
+    schedules
+        .into_iter()
+        .filter_map(|(source, expression)| {
+            let next = CronSchedule::parse(&expression)?.next_after(now)?;
+            Some(ScheduledRun {
+                source,
+                expression,
+                next,
+            })
+        })
+        .min_by_key(|run| run.next)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn finds_the_next_time_a_cron_schedule_fires() {
+        // Friday evening.
+        let now = datetime!(2025-01-10 18:30 +01:00);
+        let weekdays = CronSchedule::parse("0 18 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(now),
+            Some(datetime!(2025-01-13 18:00 +01:00))
+        );
+        let every_quarter = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(
+            every_quarter.next_after(now),
+            Some(datetime!(2025-01-10 18:45 +01:00))
+        );
+        let sundays = CronSchedule::parse("30 9 * * 7").unwrap();
+        assert_eq!(
+            sundays.next_after(now),
+            Some(datetime!(2025-01-12 09:30 +01:00))
+        );
+        assert_eq!(
+            CronSchedule::parse("@daily").unwrap().next_after(now),
+            Some(datetime!(2025-01-11 00:00 +01:00))
+        );
+        assert_eq!(CronSchedule::parse("0 18 * * mon"), None);
+        assert_eq!(CronSchedule::parse("0 25 * * *"), None);
+    }
+
+    #[test]
+    fn reads_schedules_from_crontab_and_launch_agents() {
+        let crontab = "# m h dom mon dow\n\
+                       0 18 * * 1-5 $HOME/.cargo/bin/daily-ai summarize --archive\n\
+                       @hourly daily-ai collect all\n\
+                       5 4 * * * backup.sh\n";
+        assert_eq!(crontab_expressions(crontab), ["0 18 * * 1-5", "@hourly"]);
+
+        let plist = "<dict>\n<key>ProgramArguments</key>\n\
+                     <array><string>daily-ai</string></array>\n\
+                     <key>StartCalendarInterval</key>\n<array>\n\
+                     <dict><key>Hour</key><integer>17</integer>\
+                     <key>Minute</key><integer>45</integer>\
+                     <key>Weekday</key><integer>5</integer></dict>\n\
+                     <dict><key>Hour</key><integer>9</integer></dict>\n</array>\n</dict>";
+        assert_eq!(launchd_expressions(plist), ["45 17 * * 5", "* 9 * * *"]);
+    }
+}
@@ -44,6 +44,40 @@ pub mod offset_datetime {
     }
 }
 
+/// Serde helpers for optional `OffsetDateTime` values, using the same formats as
+/// [`offset_datetime`].
+pub mod option_offset_datetime {
+    use super::*;
+
+    /// Serialize `Some` values as RFC 3339 and `None` as `null`.
+    pub fn serialize<S>(dt: &Option<OffsetDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match dt {
+            Some(dt) => super::offset_datetime::serialize(dt, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserialize an optional timestamp in either supported format.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<OffsetDateTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|raw| {
+                PrimitiveDateTime::parse(&raw, super::offset_datetime::INPUT_FORMAT)
+                    .or_else(|_| {
+                        PrimitiveDateTime::parse(&raw, &super::offset_datetime::OUTPUT_FORMAT)
+                    })
+                    .map_err(serde::de::Error::custom)
+                    .map(|pdt| pdt.assume_utc())
+            })
+            .transpose()
+    }
+}
+
 /// Serde helpers for `std::time::Duration`.
 ///
 /// The duration is represented as an integer followed by a unit suffix.
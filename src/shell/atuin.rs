@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use atuin_client::{
     database::{Database, Sqlite},
@@ -11,26 +11,13 @@ use atuin_common::record::RecordId;
 use atuin_dotfiles::store::{AliasStore, var::VarStore};
 use atuin_kv::store::KvStore;
 use atuin_scripts::store::ScriptStore;
-use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::AppResult;
+use super::ShellHistoryEntry;
 use crate::error::AppError;
-
-/// Represents a single shell command execution retrieved from Atuin.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ShellHistoryEntry {
-    #[serde(with = "crate::serde_helpers::offset_datetime")]
-    pub date_time: OffsetDateTime,
-    #[serde(with = "crate::serde_helpers::duration")]
-    pub duration: Duration,
-    pub host: String,
-    pub directory: PathBuf,
-    pub command: String,
-    pub exit_code: i64,
-    pub session_id: String,
-}
+use crate::sqlite::{is_busy, remove_snapshot, retry_busy, snapshot};
+use crate::{AppResult, allowlist};
 
 impl From<&History> for ShellHistoryEntry {
     /// Convert an Atuin history record into our internal serializable shape.
@@ -193,26 +180,16 @@ fn filter_recent_history(records: &[History], duration: &Duration) -> Vec<ShellH
         .collect()
 }
 
-/// Convert the Atuin sqlite + record store into a history iterator.
-#[tracing::instrument(name = "Collecting shell history", level = "info")]
-pub async fn get_history(sync: bool, duration: &Duration) -> AppResult<Vec<ShellHistoryEntry>> {
-    let settings = Settings::new().map_err(|e| AppError::Other(e.to_string()))?;
-
-    let db_path = PathBuf::from(settings.db_path.as_str());
-    let record_store_path = PathBuf::from(settings.record_store_path.as_str());
+/// Whether atuin is set up on this machine (its settings load and its history database exists).
+pub fn is_configured() -> bool {
+    Settings::new().is_ok_and(|settings| Path::new(settings.db_path.as_str()).exists())
+}
 
-    // The sqlite DB holds history rows; the record store holds encrypted blobs.
+/// Every history row in the atuin database at `db_path`.
+async fn list_history(db_path: &Path, settings: &Settings) -> AppResult<Vec<History>> {
     let db = Sqlite::new(db_path, settings.local_timeout).await?;
-    let store = SqliteStore::new(record_store_path, settings.local_timeout)
-        .await
-        .map_err(|e| AppError::AtuinClient(format!("Unable to open the sqlite store: {0}", e)))?;
-
-    if sync {
-        sync_history(&settings, &store, &db).await?;
-    }
-
     // Use both default and global contexts to capture commands executed in any shell session.
-    let history = db
+    Ok(db
         .list(
             &[settings.default_filter_mode(), FilterMode::Global],
             &atuin_client::database::current_context(),
@@ -220,7 +197,49 @@ pub async fn get_history(sync: bool, duration: &Duration) -> AppResult<Vec<Shell
             false,
             false,
         )
+        .await?)
+}
+
+/// Convert the Atuin sqlite + record store into a history iterator.
+///
+/// The atuin daemon and shell hooks lock the database while writing to it. Reads are retried
+/// while they do, and a database that stays locked is read from a copy.
+#[tracing::instrument(name = "Reading atuin history", level = "info")]
+pub async fn get_history(sync: bool, duration: &Duration) -> AppResult<Vec<ShellHistoryEntry>> {
+    let settings = Settings::new().map_err(|e| AppError::Other(e.to_string()))?;
+
+    let db_path = PathBuf::from(settings.db_path.as_str());
+    let record_store_path = PathBuf::from(settings.record_store_path.as_str());
+
+    if sync {
+        // atuin's own client does the syncing, so this only records that the server is reached.
+        allowlist::allow(&settings.sync_address);
+        // The sqlite DB holds history rows; the record store holds encrypted blobs.
+        let (path, timeout) = (&db_path, settings.local_timeout);
+        let db = retry_busy(
+            "atuin",
+            || async move { Ok(Sqlite::new(path, timeout).await?) },
+        )
         .await?;
+        let store = SqliteStore::new(record_store_path, settings.local_timeout)
+            .await
+            .map_err(|e| {
+                AppError::AtuinClient(format!("Unable to open the sqlite store: {0}", e))
+            })?;
+        sync_history(&settings, &store, &db).await?;
+    }
+
+    let history = match retry_busy("atuin", || list_history(&db_path, &settings)).await {
+        Err(e) if is_busy(&e) => {
+            // Opening the database runs atuin's migrations, so it cannot be opened immutable.
+            warn!("The atuin database is still locked; reading a copy of it");
+            let copy = snapshot(&db_path, "atuin").await?;
+            let history = list_history(&copy, &settings).await;
+            remove_snapshot(&copy).await?;
+            history?
+        }
+        history => history?,
+    };
 
     Ok(filter_recent_history(&history, duration))
 }
@@ -0,0 +1,372 @@
+use std::env;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use time::{Duration, OffsetDateTime};
+use tokio::process::Command;
+use tracing::debug;
+
+use super::ShellHistoryEntry;
+use crate::AppResult;
+
+/// zsh writes some bytes as this marker followed by the byte XORed with 0x20.
+const ZSH_META: u8 = 0x83;
+
+/// Shell history file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+impl HistoryFormat {
+    fn name(self) -> &'static str {
+        match self {
+            HistoryFormat::Zsh => "zsh",
+            HistoryFormat::Bash => "bash",
+            HistoryFormat::Fish => "fish",
+        }
+    }
+
+    /// Resolve the history file, honouring `ZSH_HISTORY_PATH`, `BASH_HISTORY_PATH`, and
+    /// `FISH_HISTORY_PATH`.
+    fn path(self, home: &Path) -> PathBuf {
+        let var = match self {
+            HistoryFormat::Zsh => "ZSH_HISTORY_PATH",
+            HistoryFormat::Bash => "BASH_HISTORY_PATH",
+            HistoryFormat::Fish => "FISH_HISTORY_PATH",
+        };
+        if let Some(path) = env::var_os(var) {
+            return PathBuf::from(path);
+        }
+        match self {
+            HistoryFormat::Zsh => env::var_os("ZDOTDIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.to_path_buf())
+                .join(".zsh_history"),
+            HistoryFormat::Bash => home.join(".bash_history"),
+            HistoryFormat::Fish => env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".local/share"))
+                .join("fish/fish_history"),
+        }
+    }
+
+    fn parse(self, raw: &[u8]) -> Vec<RawCommand> {
+        match self {
+            HistoryFormat::Zsh => parse_zsh(raw),
+            HistoryFormat::Bash => parse_bash(&String::from_utf8_lossy(raw)),
+            HistoryFormat::Fish => parse_fish(&String::from_utf8_lossy(raw)),
+        }
+    }
+}
+
+/// A command read from a history file, before its working directory is reconstructed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RawCommand {
+    time: OffsetDateTime,
+    duration: Duration,
+    command: String,
+}
+
+/// Parse zsh's extended history format (`: <start>:<elapsed>;<command>`).
+///
+/// Lines without a timestamp (written without `EXTENDED_HISTORY`) are skipped, since they
+/// cannot be placed in the window.
+fn parse_zsh(raw: &[u8]) -> Vec<RawCommand> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut raw = raw.iter();
+    while let Some(&byte) = raw.next() {
+        match byte {
+            ZSH_META => bytes.extend(raw.next().map(|next| next ^ 0x20)),
+            byte => bytes.push(byte),
+        }
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let mut lines = text.lines();
+    let mut commands = Vec::new();
+    while let Some(line) = lines.next() {
+        let Some((header, command)) = line.strip_prefix(": ").and_then(|l| l.split_once(';'))
+        else {
+            continue;
+        };
+        let Some((start, elapsed)) = header.split_once(':') else {
+            continue;
+        };
+        let (Ok(start), Ok(elapsed)) = (start.trim().parse(), elapsed.trim().parse()) else {
+            continue;
+        };
+        let mut command = command.to_string();
+        // Newlines inside a command are written as a backslash at the end of the line.
+        while command.ends_with('\\') {
+            let Some(next) = lines.next() else {
+                break;
+            };
+            command.pop();
+            command.push('\n');
+            command.push_str(next);
+        }
+        let Ok(time) = OffsetDateTime::from_unix_timestamp(start) else {
+            continue;
+        };
+        commands.push(RawCommand {
+            time,
+            duration: Duration::seconds(elapsed),
+            command,
+        });
+    }
+    commands
+}
+
+/// Parse bash history written with `HISTTIMEFORMAT` set (`#<start>` before each command).
+///
+/// Lines up to the next timestamp belong to the same command. Commands without a timestamp
+/// are skipped.
+fn parse_bash(text: &str) -> Vec<RawCommand> {
+    let mut commands = Vec::new();
+    let mut current: Option<(OffsetDateTime, Vec<&str>)> = None;
+    let mut push = |current: Option<(OffsetDateTime, Vec<&str>)>| {
+        if let Some((time, lines)) = current.filter(|(_, lines)| !lines.is_empty()) {
+            commands.push(RawCommand {
+                time,
+                duration: Duration::ZERO,
+                command: lines.join("\n"),
+            });
+        }
+    };
+    for line in text.lines() {
+        let timestamp = line
+            .strip_prefix('#')
+            .filter(|ts| !ts.is_empty() && ts.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|ts| OffsetDateTime::from_unix_timestamp(ts.parse().ok()?).ok());
+        if let Some(time) = timestamp {
+            push(current.replace((time, Vec::new())));
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    push(current);
+    commands
+}
+
+/// Undo fish's escaping of backslashes and newlines in history commands.
+fn unescape_fish(command: &str) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('\\')) => {
+                chars.next();
+                out.push('\\');
+            }
+            ('\\', Some('n')) => {
+                chars.next();
+                out.push('\n');
+            }
+            (c, _) => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parse fish's YAML-like `fish_history` (`- cmd: ...` followed by `  when: <start>`).
+fn parse_fish(text: &str) -> Vec<RawCommand> {
+    let mut commands = Vec::new();
+    let mut command = None;
+    for line in text.lines() {
+        if let Some(cmd) = line.strip_prefix("- cmd: ") {
+            command = Some(unescape_fish(cmd));
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ") {
+            let time = when
+                .trim()
+                .parse()
+                .ok()
+                .and_then(|ts| OffsetDateTime::from_unix_timestamp(ts).ok());
+            if let (Some(command), Some(time)) = (command.take(), time) {
+                commands.push(RawCommand {
+                    time,
+                    duration: Duration::ZERO,
+                    command,
+                });
+            }
+        }
+    }
+    commands
+}
+
+/// Resolve a `cd` argument against `cwd`, normalising `.` and `..` without touching the disk.
+fn resolve_cd(cwd: &Path, home: &Path, arg: &str) -> PathBuf {
+    let arg = arg.trim_matches(|c| c == '"' || c == '\'');
+    let target = match arg.strip_prefix('~') {
+        Some("") => home.to_path_buf(),
+        Some(rest) if rest.starts_with(['/', '\\']) => home.join(&rest[1..]),
+        _ => cwd.join(arg),
+    };
+    let mut normal = PathBuf::new();
+    for component in target.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// Estimate each command's working directory by replaying `cd` commands from `home`.
+///
+/// History files do not record where a command ran, so this treats the file as one long
+/// session. It is good enough to find the repositories that were worked in.
+fn replay_directories(commands: &[RawCommand], home: &Path) -> Vec<PathBuf> {
+    let mut cwd = home.to_path_buf();
+    let mut previous = cwd.clone();
+    commands
+        .iter()
+        .map(|raw| {
+            let directory = cwd.clone();
+            for segment in raw.command.split(['\n', ';']).flat_map(|s| s.split("&&")) {
+                let mut words = segment.split_whitespace();
+                if words.next() != Some("cd") {
+                    continue;
+                }
+                let target = match words.next() {
+                    None => home.to_path_buf(),
+                    Some("-") => previous.clone(),
+                    Some(arg) => resolve_cd(&cwd, home, arg),
+                };
+                previous = std::mem::replace(&mut cwd, target);
+            }
+            directory
+        })
+        .collect()
+}
+
+/// Host name recorded on entries read from history files.
+async fn hostname() -> String {
+    match Command::new("hostname").output().await {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+        _ => env::var("HOSTNAME").unwrap_or_else(|_| "localhost".into()),
+    }
+}
+
+/// Read the commands run since `since` from a shell's history file.
+///
+/// History files record neither exit codes nor working directories: exit codes are reported
+/// as 0 and directories are estimated from `cd` commands. Returns nothing when the file does
+/// not exist.
+#[tracing::instrument(name = "Reading shell history file", level = "info")]
+pub async fn read_history(
+    format: HistoryFormat,
+    since: OffsetDateTime,
+) -> AppResult<Vec<ShellHistoryEntry>> {
+    let Some(home) = env::home_dir() else {
+        debug!("No home directory; skipping {} history", format.name());
+        return Ok(Vec::new());
+    };
+    let path = format.path(&home);
+    let raw = match tokio::fs::read(&path).await {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("No {} history at {}", format.name(), path.display());
+            return Ok(Vec::new());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let commands = format.parse(&raw);
+    let directories = replay_directories(&commands, &home);
+    let host = hostname().await;
+    let history: Vec<ShellHistoryEntry> = commands
+        .into_iter()
+        .zip(directories)
+        .filter(|(raw, _)| raw.time >= since)
+        .map(|(raw, directory)| ShellHistoryEntry {
+            date_time: raw.time,
+            duration: raw.duration,
+            host: host.clone(),
+            directory,
+            command: raw.command,
+            exit_code: 0,
+            session_id: format.name().to_string(),
+        })
+        .collect();
+    debug!(
+        "Read {} {} history entries from {}",
+        history.len(),
+        format.name(),
+        path.display()
+    );
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn commands(parsed: &[RawCommand]) -> Vec<&str> {
+        parsed.iter().map(|raw| raw.command.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_each_history_format() {
+        let mut zsh = b": 1735722000:3;cargo test\n: 1735722060:0;echo caf".to_vec();
+        zsh.extend([ZSH_META, 0xc3 ^ 0x20, 0xa9]);
+        zsh.extend(b" \\\nand more\nno timestamp here\n");
+        let parsed = parse_zsh(&zsh);
+        assert_eq!(
+            commands(&parsed),
+            ["cargo test", "echo caf\u{e9} \nand more"]
+        );
+        assert_eq!(parsed[0].time, datetime!(2025-01-01 09:00 UTC));
+        assert_eq!(parsed[0].duration, Duration::seconds(3));
+
+        let bash = "ls\n#1735722000\ngit status\n#1735722060\nfor f in *; do\n  echo $f\ndone\n";
+        assert_eq!(
+            commands(&parse_bash(bash)),
+            ["git status", "for f in *; do\n  echo $f\ndone"]
+        );
+
+        let fish = "- cmd: echo a\\\\b\\nc\n  when: 1735722000\n  paths:\n    - a\n- cmd: ls\n  when: 1735722060\n";
+        let parsed = parse_fish(fish);
+        assert_eq!(commands(&parsed), ["echo a\\b\nc", "ls"]);
+        assert_eq!(parsed[1].time, datetime!(2025-01-01 09:01 UTC));
+    }
+
+    #[test]
+    fn replays_cd_to_estimate_directories() {
+        let raw = |command: &str| RawCommand {
+            time: OffsetDateTime::UNIX_EPOCH,
+            duration: Duration::ZERO,
+            command: command.into(),
+        };
+        let history = [
+            raw("cd ~/src/app"),
+            raw("cargo build"),
+            raw("cd ../lib && make"),
+            raw("cd -"),
+            raw("cd /tmp"),
+            raw("cd"),
+            raw("ls"),
+        ];
+
+        assert_eq!(
+            replay_directories(&history, Path::new("/home/me")),
+            [
+                "/home/me",
+                "/home/me/src/app",
+                "/home/me/src/app",
+                "/home/me/src/lib",
+                "/home/me/src/app",
+                "/tmp",
+                "/home/me",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+}
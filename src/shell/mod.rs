@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::debug;
+
+use crate::AppResult;
+use crate::time_utils::past_ts;
+use crate::warnings::{self, WarningKind};
+
+/// Atuin's history database and record store.
+pub(crate) mod atuin;
+
+/// Plain history files written by zsh, bash, and fish.
+pub(crate) mod files;
+
+/// Represents a single shell command execution retrieved from Atuin or a shell history file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShellHistoryEntry {
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub date_time: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::duration")]
+    pub duration: Duration,
+    pub host: String,
+    pub directory: PathBuf,
+    pub command: String,
+    pub exit_code: i64,
+    pub session_id: String,
+}
+
+/// Where shell history is read from.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShellBackend {
+    /// Atuin when it is set up, otherwise every zsh, bash, and fish history file found
+    #[default]
+    Auto,
+    /// Atuin's history database
+    Atuin,
+    /// `~/.zsh_history` (needs `setopt EXTENDED_HISTORY` for timestamps)
+    Zsh,
+    /// `~/.bash_history` (needs `HISTTIMEFORMAT` to be set for timestamps)
+    Bash,
+    /// fish's `fish_history`
+    Fish,
+}
+
+/// Read every history file found, oldest command first.
+async fn read_history_files(since: OffsetDateTime) -> AppResult<Vec<ShellHistoryEntry>> {
+    let mut history = Vec::new();
+    for format in [
+        files::HistoryFormat::Zsh,
+        files::HistoryFormat::Bash,
+        files::HistoryFormat::Fish,
+    ] {
+        history.extend(files::read_history(format, since).await?);
+    }
+    history.sort_by_key(|entry| entry.date_time);
+    Ok(history)
+}
+
+/// Collect shell history for the window from the chosen backend.
+///
+/// `sync` only applies to atuin. With [`ShellBackend::Auto`], atuin is used when it is set up
+/// and readable; otherwise the zsh, bash, and fish history files are read instead.
+#[tracing::instrument(name = "Collecting shell history", level = "info")]
+pub async fn get_history(
+    backend: ShellBackend,
+    sync: bool,
+    duration: &Duration,
+) -> AppResult<Vec<ShellHistoryEntry>> {
+    let since = past_ts(duration);
+    match backend {
+        ShellBackend::Atuin => atuin::get_history(sync, duration).await,
+        ShellBackend::Zsh => files::read_history(files::HistoryFormat::Zsh, since).await,
+        ShellBackend::Bash => files::read_history(files::HistoryFormat::Bash, since).await,
+        ShellBackend::Fish => files::read_history(files::HistoryFormat::Fish, since).await,
+        ShellBackend::Auto => {
+            if atuin::is_configured() {
+                match atuin::get_history(sync, duration).await {
+                    Ok(history) => return Ok(history),
+                    Err(e) => warnings::record(
+                        WarningKind::Skipped,
+                        "shell",
+                        format!("Unable to read atuin history; using history files: {e}"),
+                    ),
+                }
+            } else {
+                debug!("atuin is not set up; reading shell history files");
+            }
+            read_history_files(since).await
+        }
+    }
+}
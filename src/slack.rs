@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::error::AppError;
+use crate::http;
+use crate::time_utils::past_ts;
+
+const SEARCH_URL: &str = "https://slack.com/api/search.messages";
+
+/// Results per search page; Slack's maximum.
+const PAGE_SIZE: &str = "100";
+
+/// Pages read at most, so a very chatty window cannot stall the run.
+const MAX_PAGES: u64 = 20;
+
+/// Messages sent to one Slack channel or conversation during the window. Message text is never
+/// kept.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SlackChannelActivity {
+    /// `#name` for channels; direct and group messages are not named, so that the people in
+    /// them are not either.
+    pub channel: String,
+    pub sent: usize,
+    /// Distinct threads replied in.
+    pub threads: usize,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub first_message: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_message: OffsetDateTime,
+}
+
+impl SlackChannelActivity {
+    /// One-line description used in the "communication" section of the summary.
+    pub fn describe(&self) -> String {
+        match self.threads {
+            0 => format!("Slack {}: {} sent", self.channel, self.sent),
+            1 => format!("Slack {}: {} sent in 1 thread", self.channel, self.sent),
+            threads => format!(
+                "Slack {}: {} sent in {threads} threads",
+                self.channel, self.sent
+            ),
+        }
+    }
+}
+
+/// The channel label of a search match's `channel` object.
+fn channel_label(channel: &Value) -> String {
+    let flag = |name: &str| channel[name].as_bool().unwrap_or(false);
+    if flag("is_im") {
+        "direct message".into()
+    } else if flag("is_mpim") {
+        "group direct message".into()
+    } else {
+        match channel["name"].as_str() {
+            Some(name) if !name.is_empty() => format!("#{name}"),
+            _ => "unknown channel".into(),
+        }
+    }
+}
+
+/// The time of a Slack message timestamp such as `1738321200.000100`.
+fn message_time(ts: &str) -> Option<OffsetDateTime> {
+    let secs: f64 = ts.parse().ok()?;
+    OffsetDateTime::from_unix_timestamp(secs as i64).ok()
+}
+
+/// The thread a match was posted in, from the `thread_ts` of its permalink.
+fn thread_of(permalink: &str) -> Option<&str> {
+    permalink
+        .split_once('?')?
+        .1
+        .split('&')
+        .find_map(|param| param.strip_prefix("thread_ts="))
+}
+
+/// Group search matches sent after `since` by channel, ordered by last activity.
+fn aggregate(matches: &[Value], since: OffsetDateTime) -> Vec<SlackChannelActivity> {
+    let mut channels: BTreeMap<String, (SlackChannelActivity, BTreeSet<String>)> = BTreeMap::new();
+    for message in matches {
+        let Some(time) = message["ts"].as_str().and_then(message_time) else {
+            continue;
+        };
+        if time <= since {
+            continue;
+        }
+        let channel = channel_label(&message["channel"]);
+        let key = message["channel"]["id"]
+            .as_str()
+            .map_or_else(|| channel.clone(), str::to_string);
+        let (activity, threads) = channels.entry(key).or_insert_with(|| {
+            (
+                SlackChannelActivity {
+                    channel,
+                    sent: 0,
+                    threads: 0,
+                    first_message: time,
+                    last_message: time,
+                },
+                BTreeSet::new(),
+            )
+        });
+        activity.sent += 1;
+        activity.first_message = activity.first_message.min(time);
+        activity.last_message = activity.last_message.max(time);
+        if let Some(thread) = message["permalink"].as_str().and_then(thread_of) {
+            threads.insert(thread.to_string());
+        }
+    }
+    let mut channels: Vec<SlackChannelActivity> = channels
+        .into_values()
+        .map(|(activity, threads)| SlackChannelActivity {
+            threads: threads.len(),
+            ..activity
+        })
+        .collect();
+    channels.sort_by_key(|c| c.last_message);
+    channels
+}
+
+/// Count the messages sent on Slack during the window, per channel.
+///
+/// Uses `search.messages` with a user token from `SLACK_USER_TOKEN` (scope `search:read`).
+/// Only each match's channel, timestamp, and permalink are read; message text is discarded.
+#[tracing::instrument(name = "Collecting Slack activity", level = "info")]
+pub async fn get_slack_activity(duration: &Duration) -> AppResult<Vec<SlackChannelActivity>> {
+    let token = env::var("SLACK_USER_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            AppError::Config("The Slack collector needs a user token in SLACK_USER_TOKEN".into())
+        })?;
+    let since = past_ts(duration);
+    // `after:` is day-granular and exclusive, so search from the day before and filter by time.
+    let query = format!(
+        "from:me after:{}",
+        since.date().previous_day().unwrap_or(since.date())
+    );
+    let client = http::client(&[SEARCH_URL])?;
+
+    let mut matches = Vec::new();
+    for page in 1..=MAX_PAGES {
+        let resp = client
+            .get(SEARCH_URL)
+            .bearer_auth(&token)
+            .query(&[
+                ("query", query.as_str()),
+                ("count", PAGE_SIZE),
+                ("page", &page.to_string()),
+            ])
+            .send()
+            .await?;
+        let mut data: Value = serde_json::from_str(&resp.text().await?)?;
+        if data["ok"].as_bool() != Some(true) {
+            return Err(AppError::Other(format!(
+                "Slack search failed: {}",
+                data["error"].as_str().unwrap_or("unknown error")
+            )));
+        }
+        let pages = data["messages"]["paging"]["pages"].as_u64().unwrap_or(1);
+        if let Value::Array(found) = data["messages"]["matches"].take() {
+            trace!("Slack search page {page}/{pages}: {} matches", found.len());
+            matches.extend(found);
+        }
+        if page >= pages {
+            break;
+        }
+    }
+    let channels = aggregate(&matches, since);
+    debug!("Found sent Slack messages in {} channels", channels.len());
+    Ok(channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn counts_sent_messages_and_threads_per_channel() {
+        let matches = [
+            json!({
+                "channel": { "id": "C1", "name": "outage", "is_channel": true },
+                "ts": "1738328400.000100",
+                "permalink": "https://acme.slack.com/archives/C1/p1738328400000100?thread_ts=1738328000.000200&cid=C1",
+                "text": "rolling back now"
+            }),
+            json!({
+                "channel": { "id": "C1", "name": "outage", "is_channel": true },
+                "ts": "1738339200.000100",
+                "permalink": "https://acme.slack.com/archives/C1/p1738339200000100"
+            }),
+            json!({
+                "channel": { "id": "D1", "name": "U0123", "is_im": true },
+                "ts": "1738330000.000000",
+                "permalink": "https://acme.slack.com/archives/D1/p1738330000000000"
+            }),
+            json!({
+                "channel": { "id": "C1", "name": "outage" },
+                "ts": "1738200000.000000",
+                "permalink": "https://acme.slack.com/archives/C1/p1738200000000000"
+            }),
+        ];
+
+        let channels = aggregate(&matches, datetime!(2025-01-31 00:00 UTC));
+
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].channel, "direct message");
+        let outage = &channels[1];
+        assert_eq!((outage.sent, outage.threads), (2, 1));
+        assert_eq!(outage.first_message, datetime!(2025-01-31 13:00 UTC));
+        assert_eq!(outage.last_message, datetime!(2025-01-31 16:00 UTC));
+        assert_eq!(outage.describe(), "Slack #outage: 2 sent in 1 thread");
+        assert!(
+            !serde_json::to_string(&channels)
+                .unwrap()
+                .contains("rolling")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::fs;
+use tracing::warn;
+
+use crate::AppResult;
+use crate::backoff::Backoff;
+use crate::dirs::DirType;
+use crate::error::AppError;
+use crate::io_utils::shred_file;
+
+/// How many times a read that found the database locked is tried again.
+const BUSY_RETRIES: u32 = 4;
+
+/// Waits between reads: 250 milliseconds at first, and 2 seconds before the last.
+const BACKOFF: Backoff = Backoff::new(Duration::from_millis(250), Duration::from_secs(2));
+
+/// Whether `error` is SQLite saying another process, such as Safari or the atuin daemon, holds a
+/// lock on the database.
+pub fn is_busy(error: &AppError) -> bool {
+    let message = error.to_string();
+    message.contains("database is locked")
+        || message.contains("database table is locked")
+        || message.contains("SQLITE_BUSY")
+}
+
+/// Run `read`, trying again with exponential backoff while the `what` database is locked. The
+/// last error is returned once the retries run out, so the caller can fall back to another way
+/// of reading it.
+pub async fn retry_busy<T, F, Fut>(what: &str, mut read: F) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    for attempt in 1..=BUSY_RETRIES {
+        match read().await {
+            Err(e) if is_busy(&e) => {
+                let wait = BACKOFF.wait(attempt);
+                warn!(
+                    "The {what} database is locked; retrying in {} ({attempt} of {BUSY_RETRIES})",
+                    humantime::format_duration(wait)
+                );
+                tokio::time::sleep(wait).await;
+            }
+            result => return result,
+        }
+    }
+    read().await
+}
+
+/// `path` with `suffix` appended, e.g. the `-wal` file next to a database.
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Copy the database at `db_path` and its write-ahead log into the cache directory, to read
+/// while the app that owns it keeps it locked. Remove the copy with [`remove_snapshot`].
+#[tracing::instrument(name = "Copying a locked database", level = "debug")]
+pub async fn snapshot(db_path: &Path, name: &str) -> AppResult<PathBuf> {
+    let copy = DirType::Cache
+        .ensure_dir_async()
+        .await?
+        .join(format!("{name}-snapshot.sqlite"));
+    fs::copy(db_path, &copy).await?;
+    let wal = sidecar(db_path, "-wal");
+    if fs::try_exists(&wal).await? {
+        fs::copy(&wal, sidecar(&copy, "-wal")).await?;
+    }
+    Ok(copy)
+}
+
+/// Shred a copy made by [`snapshot`], with the journal files SQLite left next to it.
+pub async fn remove_snapshot(copy: &Path) -> AppResult<()> {
+    for suffix in ["-wal", "-shm"] {
+        let file = sidecar(copy, suffix);
+        if fs::try_exists(&file).await? {
+            shred_file(&file).await?;
+        }
+    }
+    shred_file(copy).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn retries_only_while_the_database_is_locked() {
+        let locked = || AppError::Other("error returned from database: database is locked".into());
+        assert!(is_busy(&locked()));
+        assert!(!is_busy(&AppError::Other("no such table: history".into())));
+
+        let mut attempts = 0;
+        let read = retry_busy("test", || {
+            attempts += 1;
+            let result = if attempts < 3 {
+                Err(locked())
+            } else {
+                Ok(attempts)
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(read.unwrap(), 3);
+
+        let mut attempts = 0;
+        let read: AppResult<()> = retry_busy("test", || {
+            attempts += 1;
+            async { Err(AppError::Other("no such table: history".into())) }
+        })
+        .await;
+        assert!(read.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use time::{Date, Duration};
+use tracing::warn;
+
+use crate::AppResult;
+use crate::archive::{self, ArchiveEntry};
+use crate::dashboard::DayStats;
+use crate::dashboard::heatmap::Heatmap;
+use crate::render::terminal;
+use crate::time_utils;
+
+/// How many URL categories and repositories to list.
+const TOP_N: usize = 5;
+
+/// How `daily-ai stats` prints its results.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// Aligned plain text
+    #[default]
+    Table,
+    /// A JSON object
+    Json,
+    /// Markdown tables
+    Markdown,
+}
+
+/// A name and how often it came up.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ranked {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Trends over the archived runs of a period.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Trends {
+    /// First and last archived day in the period.
+    pub from: Option<Date>,
+    pub to: Option<Date>,
+    /// Archived days in the period. Averages are per archived day.
+    pub days: usize,
+    pub commits: usize,
+    pub commits_per_day: f64,
+    pub active_hours: f64,
+    /// Hours spent in unbroken stretches of activity of an hour or more.
+    pub deep_work_hours: f64,
+    pub deep_work_hours_per_day: f64,
+    /// Browsing categories by the number of days they came up on.
+    pub top_url_categories: Vec<Ranked>,
+    /// Repositories by commits in the period.
+    pub top_repos: Vec<Ranked>,
+    /// Hosts by ssh, scp, rsync, sftp, and mosh connections in the period.
+    pub top_hosts: Vec<Ranked>,
+}
+
+/// The `TOP_N` entries of `counts` with the highest counts, ties broken by name.
+fn top(counts: HashMap<String, usize>) -> Vec<Ranked> {
+    let mut ranked: Vec<Ranked> = counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| Ranked { name, count })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    ranked.truncate(TOP_N);
+    ranked
+}
+
+fn hours(minutes: i64) -> f64 {
+    (minutes as f64 / 60.0 * 10.0).round() / 10.0
+}
+
+fn per_day(total: f64, days: usize) -> f64 {
+    if days == 0 {
+        0.0
+    } else {
+        (total / days as f64 * 10.0).round() / 10.0
+    }
+}
+
+impl Trends {
+    /// Compute the trends for `entries`, which should already be limited to the period.
+    pub fn compute(entries: &[ArchiveEntry]) -> Self {
+        let mut total = DayStats::default();
+        let mut categories: HashMap<String, usize> = HashMap::new();
+        let mut repos: HashMap<String, usize> = HashMap::new();
+        let mut hosts: HashMap<String, usize> = HashMap::new();
+        for entry in entries {
+            let day = DayStats::from_context(&entry.context);
+            total.commits += day.commits;
+            total.active_minutes += day.active_minutes;
+            total.deep_work_minutes += day.deep_work_minutes;
+
+            // Count each category once per day, so a recurring topic outranks one busy day.
+            let labels: BTreeSet<&str> = entry
+                .context
+                .context
+                .safari_history()
+                .iter()
+                .map(|cluster| cluster.label.trim())
+                .filter(|label| !label.is_empty())
+                .collect();
+            for label in labels {
+                *categories.entry(label.to_string()).or_default() += 1;
+            }
+            for repo in entry.context.context.commit_history() {
+                *repos.entry(repo.project_name()).or_default() += repo.commits.len();
+            }
+            for host in entry.context.context.hosts() {
+                *hosts.entry(host.host.clone()).or_default() += host.connections;
+            }
+        }
+        let deep_work_hours = hours(total.deep_work_minutes);
+        Trends {
+            from: entries.first().map(|entry| entry.date),
+            to: entries.last().map(|entry| entry.date),
+            days: entries.len(),
+            commits: total.commits,
+            commits_per_day: per_day(total.commits as f64, entries.len()),
+            active_hours: hours(total.active_minutes),
+            deep_work_hours,
+            deep_work_hours_per_day: per_day(deep_work_hours, entries.len()),
+            top_url_categories: top(categories),
+            top_repos: top(repos),
+            top_hosts: top(hosts),
+        }
+    }
+
+    fn period(&self) -> String {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => format!("{from} to {to} ({} archived days)", self.days),
+            _ => "no archived days".into(),
+        }
+    }
+
+    /// Render as aligned plain text.
+    pub fn to_table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Period           {}", self.period());
+        let _ = writeln!(
+            out,
+            "Commits          {} ({}/day)",
+            self.commits, self.commits_per_day
+        );
+        let _ = writeln!(out, "Active hours     {}", self.active_hours);
+        let _ = writeln!(
+            out,
+            "Deep-work hours  {} ({}/day)",
+            self.deep_work_hours, self.deep_work_hours_per_day
+        );
+        for (title, unit, ranked) in [
+            ("Top URL categories", "days", &self.top_url_categories),
+            ("Most-touched repos", "commits", &self.top_repos),
+            ("Hosts connected to", "connections", &self.top_hosts),
+        ] {
+            if ranked.is_empty() {
+                continue;
+            }
+            let width = ranked
+                .iter()
+                .map(|r| r.name.chars().count())
+                .chain([title.len()])
+                .max()
+                .unwrap_or(0);
+            let _ = writeln!(out, "\n{title:width$}  {unit}");
+            for Ranked { name, count } in ranked {
+                let _ = writeln!(out, "{name:width$}  {count}");
+            }
+        }
+        out
+    }
+
+    /// Render as Markdown, with one table per ranking.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Trends\n\n");
+        let _ = writeln!(out, "{}\n", self.period());
+        let _ = writeln!(out, "| Metric | Total | Per day |\n| --- | ---: | ---: |");
+        let _ = writeln!(
+            out,
+            "| Commits | {} | {} |",
+            self.commits, self.commits_per_day
+        );
+        let _ = writeln!(
+            out,
+            "| Active hours | {} | {} |",
+            self.active_hours,
+            per_day(self.active_hours, self.days)
+        );
+        let _ = writeln!(
+            out,
+            "| Deep-work hours | {} | {} |",
+            self.deep_work_hours, self.deep_work_hours_per_day
+        );
+        for (title, column, unit, ranked) in [
+            (
+                "Top URL categories",
+                "Category",
+                "Days",
+                &self.top_url_categories,
+            ),
+            (
+                "Most-touched repos",
+                "Repository",
+                "Commits",
+                &self.top_repos,
+            ),
+            ("Hosts connected to", "Host", "Connections", &self.top_hosts),
+        ] {
+            if ranked.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n## {title}\n\n| {column} | {unit} |\n| --- | ---: |");
+            for Ranked { name, count } in ranked {
+                let _ = writeln!(out, "| {} | {count} |", name.replace('|', "\\|"));
+            }
+        }
+        out
+    }
+}
+
+/// Print trends for the archived runs of the last `period`, or with `heatmap`, when in the week
+/// the activity happened.
+#[tracing::instrument(name = "Showing archive statistics", level = "info")]
+pub async fn show(period: Duration, format: StatsFormat, heatmap: bool) -> AppResult<()> {
+    let today = time_utils::now_local().date();
+    let since = today.saturating_sub(period);
+    let entries: Vec<ArchiveEntry> = archive::load_all()
+        .await?
+        .into_iter()
+        .filter(|entry| entry.date > since)
+        .collect();
+    if entries.is_empty() {
+        warn!("No archived runs in the period. Run `daily-ai summarize` to start filling it.");
+    }
+    if heatmap {
+        let offset = time_utils::local_offset();
+        let heatmap = Heatmap::from_contexts(entries.iter().map(|entry| &entry.context), offset);
+        return terminal::print(heatmap.to_terminal());
+    }
+    let trends = Trends::compute(&entries);
+    match format {
+        StatsFormat::Table => terminal::print(trends.to_table()),
+        StatsFormat::Json => terminal::print(serde_json::to_string_pretty(&trends)? + "\n"),
+        StatsFormat::Markdown => terminal::print(trends.to_markdown()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::classify::UrlCluster;
+    use crate::context::{Context, FullContext};
+    use crate::git::diff::DiffSummary;
+    use crate::git::{CommitMeta, GitRepoHistory};
+    use crate::hosts::HostActivity;
+
+    fn repo(path: &str, commits: usize) -> GitRepoHistory {
+        GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from(path),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: Vec::new(),
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits: (0..commits)
+                .map(|i| CommitMeta {
+                    id: String::new(),
+                    summary: format!("Commit {i}"),
+                    body: None,
+                    timestamp: datetime!(2025-01-01 09:00 UTC) + Duration::minutes(i as i64),
+                    branches: Vec::new(),
+                })
+                .collect(),
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        }
+    }
+
+    fn day(date: Date, repos: Vec<GitRepoHistory>, labels: &[&str]) -> ArchiveEntry {
+        let clusters = labels
+            .iter()
+            .map(|label| UrlCluster {
+                label: label.to_string(),
+                urls: Vec::new(),
+            })
+            .collect();
+        ArchiveEntry {
+            date,
+            context: FullContext::from(
+                Context::default()
+                    .with_commit_history(repos)
+                    .with_safari_history(clusters),
+            ),
+        }
+    }
+
+    #[test]
+    fn computes_trends_across_days() {
+        let entries = [
+            day(
+                date!(2025 - 01 - 30),
+                vec![repo("/src/app", 3), repo("/src/lib", 1)],
+                &["Rust async", "Rust async", "Kubernetes"],
+            ),
+            day(
+                date!(2025 - 01 - 31),
+                vec![repo("/src/app", 2)],
+                &["Rust async"],
+            ),
+        ];
+
+        let trends = Trends::compute(&entries);
+
+        assert_eq!((trends.days, trends.commits), (2, 6));
+        assert_eq!(trends.commits_per_day, 3.0);
+        assert_eq!(
+            trends.top_url_categories,
+            [
+                Ranked {
+                    name: "Rust async".into(),
+                    count: 2
+                },
+                Ranked {
+                    name: "Kubernetes".into(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(trends.top_repos[0].name, "app");
+        assert_eq!(trends.top_repos[0].count, 5);
+        assert!(
+            trends
+                .to_table()
+                .starts_with("Period           2025-01-30 to 2025-01-31 (2 archived days)\nCommits          6 (3/day)\n")
+        );
+        assert!(
+            trends
+                .to_markdown()
+                .contains("## Most-touched repos\n\n| Repository | Commits |\n| --- | ---: |\n| app | 5 |\n| lib | 1 |\n")
+        );
+    }
+
+    #[test]
+    fn ranks_hosts_by_connections() {
+        let host = |name: &str, connections: usize| HostActivity {
+            host: name.into(),
+            users: Vec::new(),
+            categories: Default::default(),
+            connections,
+            failed_commands: 0,
+            first_used: datetime!(2025-01-01 09:00 UTC),
+            last_used: datetime!(2025-01-01 09:00 UTC),
+        };
+        let entries = [
+            ArchiveEntry {
+                date: date!(2025 - 01 - 30),
+                context: FullContext::from(
+                    Context::default().with_hosts(vec![host("web-1", 2), host("db-1", 1)]),
+                ),
+            },
+            ArchiveEntry {
+                date: date!(2025 - 01 - 31),
+                context: FullContext::from(Context::default().with_hosts(vec![host("db-1", 4)])),
+            },
+        ];
+
+        let trends = Trends::compute(&entries);
+
+        assert_eq!(
+            trends.top_hosts,
+            [
+                Ranked {
+                    name: "db-1".into(),
+                    count: 5
+                },
+                Ranked {
+                    name: "web-1".into(),
+                    count: 2
+                },
+            ]
+        );
+        assert!(
+            trends
+                .to_table()
+                .contains("Hosts connected to  connections\ndb-1")
+        );
+    }
+}
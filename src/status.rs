@@ -0,0 +1,279 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use tokio::fs;
+
+use crate::ai::cache;
+use crate::dirs::DirType;
+use crate::git::diff::format_size;
+use crate::render::terminal;
+use crate::run_status::{self, RunStatus, State};
+use crate::{AppResult, archive, history, schedule, time_utils};
+
+/// The next scheduled run and the crontab entry or launch agent it comes from.
+#[derive(Serialize, Debug)]
+pub struct NextRun {
+    /// As RFC 3339.
+    pub at: String,
+    pub source: String,
+    pub schedule: String,
+}
+
+/// The archived days and the history database.
+#[derive(Serialize, Debug)]
+pub struct ArchiveStatus {
+    pub path: PathBuf,
+    pub days: usize,
+    pub bytes: u64,
+    pub history_bytes: u64,
+}
+
+/// The cache directory, with the model answers cached in it.
+#[derive(Serialize, Debug)]
+pub struct CacheStatus {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub cached_answers: usize,
+    /// Answers older than 30 days, removed the next time the cache is used.
+    pub expired_answers: usize,
+    /// Why the cache could not be read, when it could not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What `daily-ai status` reports, in the shape `--json` prints it, for menubar and tray apps.
+#[derive(Serialize, Debug)]
+pub struct Status {
+    /// The current run, or the last one, as written to `run-status.json`.
+    pub last_run: Option<RunStatus>,
+    pub next_run: Option<NextRun>,
+    pub archive: ArchiveStatus,
+    pub cache: CacheStatus,
+}
+
+/// Total size of the files under `dir`, or 0 when it does not exist.
+async fn dir_size(dir: &Path) -> AppResult<u64> {
+    let mut total = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if !fs::try_exists(&dir).await? {
+            continue;
+        }
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    Ok(total)
+}
+
+/// Number of archived days in `dir`.
+async fn count_days(dir: &Path) -> AppResult<usize> {
+    if !fs::try_exists(dir).await? {
+        return Ok(0);
+    }
+    let mut days = 0;
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.path().extension().is_some_and(|ext| ext == "json") {
+            days += 1;
+        }
+    }
+    Ok(days)
+}
+
+impl Status {
+    /// Gather the status from the state, data, and cache directories, the crontab, and launch
+    /// agents.
+    pub async fn gather() -> AppResult<Self> {
+        let now = time_utils::now_local();
+        let next_run = match schedule::next_run(now).await {
+            Some(run) => Some(NextRun {
+                at: run.next.format(&Rfc3339)?,
+                source: run.source,
+                schedule: run.expression,
+            }),
+            None => None,
+        };
+        let archive_dir = archive::archive_dir()?;
+        let history_db = history::history_db_path()?;
+        let archive = ArchiveStatus {
+            days: count_days(&archive_dir).await?,
+            bytes: dir_size(&archive_dir).await?,
+            history_bytes: match fs::metadata(&history_db).await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            },
+            path: archive_dir,
+        };
+        let cache_dir = DirType::Cache.get_dir()?;
+        let cache = match (cache::count().await, dir_size(&cache_dir).await) {
+            (Ok((cached_answers, expired_answers)), Ok(bytes)) => CacheStatus {
+                path: cache_dir,
+                bytes,
+                cached_answers,
+                expired_answers,
+                error: None,
+            },
+            (Err(e), _) | (_, Err(e)) => CacheStatus {
+                path: cache_dir,
+                bytes: 0,
+                cached_answers: 0,
+                expired_answers: 0,
+                error: Some(e.to_string()),
+            },
+        };
+        Ok(Status {
+            last_run: run_status::load().await?,
+            next_run,
+            archive,
+            cache,
+        })
+    }
+
+    /// The status as aligned plain text.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        match &self.last_run {
+            Some(run) => {
+                let result = match (run.state, &run.error) {
+                    (State::Running, _) => "running".to_string(),
+                    (State::Finished, _) => "finished".to_string(),
+                    (State::Failed, Some(error)) => format!("failed: {error}"),
+                    (State::Failed, None) => "failed".to_string(),
+                };
+                let _ = writeln!(out, "Last run    {} ({result})", local_time(&run.started));
+                for stage in &run.stages {
+                    let detail = match (stage.state, &stage.error, stage.count) {
+                        (_, Some(error), _) => format!("failed: {error}"),
+                        (State::Running, ..) => "running".to_string(),
+                        (State::Failed, ..) => "failed".to_string(),
+                        (State::Finished, None, Some(count)) => format!("done, {count} items"),
+                        (State::Finished, None, None) => "done".to_string(),
+                    };
+                    let _ = writeln!(out, "  {:<10}{detail}", stage.name);
+                }
+            }
+            None => {
+                let _ = writeln!(out, "Last run    never");
+            }
+        }
+        match &self.next_run {
+            Some(next) => {
+                let _ = writeln!(
+                    out,
+                    "Next run    {} ({}: {})",
+                    local_time(&next.at),
+                    next.source,
+                    next.schedule
+                );
+            }
+            None => {
+                let _ = writeln!(out, "Next run    not scheduled");
+            }
+        }
+        let _ = writeln!(
+            out,
+            "Archive     {} days, {} (history database {})",
+            self.archive.days,
+            format_size(self.archive.bytes),
+            format_size(self.archive.history_bytes)
+        );
+        match &self.cache.error {
+            Some(error) => {
+                let _ = writeln!(out, "Cache       unreadable: {error}");
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "Cache       {}, {} cached answers ({} expired)",
+                    format_size(self.cache.bytes),
+                    self.cache.cached_answers,
+                    self.cache.expired_answers
+                );
+            }
+        }
+        out
+    }
+}
+
+/// An RFC 3339 time from the status file as `YYYY-MM-DD HH:MM`, or as written if it does not
+/// parse.
+fn local_time(rfc3339: &str) -> String {
+    OffsetDateTime::parse(rfc3339, &Rfc3339)
+        .ok()
+        .and_then(|time| {
+            time.format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                .ok()
+        })
+        .unwrap_or_else(|| rfc3339.to_string())
+}
+
+/// Print the status, as JSON with `json`.
+#[tracing::instrument(name = "Checking status", level = "debug")]
+pub async fn show(json: bool) -> AppResult<()> {
+    let status = Status::gather().await?;
+    if json {
+        terminal::print(serde_json::to_string_pretty(&status)? + "\n")
+    } else {
+        terminal::print(status.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_the_last_run_and_what_is_kept() {
+        let json = r#"{
+            "run_id": "01JH0000000000000000000000",
+            "pid": 42,
+            "state": "failed",
+            "started": "2025-01-10T18:00:00+01:00",
+            "finished": "2025-01-10T18:02:00+01:00",
+            "stages": [
+                {"name": "git", "state": "finished", "started": "2025-01-10T18:00:00+01:00",
+                 "count": 12},
+                {"name": "summary", "state": "failed", "started": "2025-01-10T18:01:00+01:00",
+                 "error": "model unavailable"}
+            ],
+            "error": "model unavailable"
+        }"#;
+        let status = Status {
+            last_run: Some(serde_json::from_str(json).unwrap()),
+            next_run: None,
+            archive: ArchiveStatus {
+                path: PathBuf::from("archive"),
+                days: 31,
+                bytes: 4 * 1024 * 1024,
+                history_bytes: 512,
+            },
+            cache: CacheStatus {
+                path: PathBuf::from("cache"),
+                bytes: 2048,
+                cached_answers: 10,
+                expired_answers: 1,
+                error: None,
+            },
+        };
+        assert_eq!(
+            status.to_text(),
+            "Last run    2025-01-10 18:00 (failed: model unavailable)\n\
+             \x20 git       done, 12 items\n\
+             \x20 summary   failed: model unavailable\n\
+             Next run    not scheduled\n\
+             Archive     31 days, 4.0 MiB (history database 512 B)\n\
+             Cache       2.0 KiB, 10 cached answers (1 expired)\n"
+        );
+    }
+}
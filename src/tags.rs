@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::ai;
+use crate::ai::summary::WorkSummary;
+use crate::context::Context;
+
+/// Most tags given to one item.
+const MAX_TAGS: usize = 3;
+
+/// A machine tag, its emoji, and the words that give it away.
+struct Rule {
+    tag: &'static str,
+    emoji: &'static str,
+    words: &'static [&'static str],
+}
+
+/// The tags, in order of precedence: the first that matches picks the item's emoji.
+const RULES: &[Rule] = &[
+    Rule {
+        tag: "bugfix",
+        emoji: "🐛",
+        words: &[
+            "bug",
+            "bugfix",
+            "bugs",
+            "crash",
+            "fix",
+            "fixed",
+            "fixes",
+            "hotfix",
+            "panic",
+            "regression",
+        ],
+    },
+    Rule {
+        tag: "ops",
+        emoji: "⚙️",
+        words: &[
+            "ci",
+            "deploy",
+            "deployed",
+            "deployment",
+            "docker",
+            "helm",
+            "infra",
+            "k8s",
+            "kubernetes",
+            "monitoring",
+            "pipeline",
+            "release",
+            "released",
+            "terraform",
+        ],
+    },
+    Rule {
+        tag: "research",
+        emoji: "🔍",
+        words: &[
+            "article",
+            "compared",
+            "explore",
+            "explored",
+            "investigate",
+            "investigated",
+            "paper",
+            "research",
+            "rfc",
+            "stackoverflow",
+            "tutorial",
+            "wiki",
+        ],
+    },
+    Rule {
+        tag: "feature",
+        emoji: "✨",
+        words: &[
+            "add",
+            "added",
+            "feature",
+            "implement",
+            "implemented",
+            "introduce",
+            "introduced",
+            "support",
+        ],
+    },
+    Rule {
+        tag: "refactor",
+        emoji: "♻️",
+        words: &[
+            "cleanup",
+            "refactor",
+            "refactored",
+            "rename",
+            "renamed",
+            "restructure",
+            "restructured",
+            "simplify",
+            "simplified",
+        ],
+    },
+    Rule {
+        tag: "testing",
+        emoji: "🧪",
+        words: &["coverage", "flaky", "test", "tested", "testing", "tests"],
+    },
+    Rule {
+        tag: "docs",
+        emoji: "📝",
+        words: &["changelog", "docs", "documentation", "documented", "readme"],
+    },
+    Rule {
+        tag: "review",
+        emoji: "👀",
+        words: &["approved", "pr", "review", "reviewed", "reviewing"],
+    },
+    Rule {
+        tag: "deps",
+        emoji: "📦",
+        words: &[
+            "bump",
+            "bumped",
+            "dependencies",
+            "dependency",
+            "upgrade",
+            "upgraded",
+        ],
+    },
+];
+
+/// An emoji and machine tags such as `#bugfix`, shown next to a browsing group, repository, or
+/// highlight so a long report can be scanned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Tagging {
+    pub emoji: String,
+    /// Tag names, without the `#`.
+    pub tags: Vec<String>,
+}
+
+impl Tagging {
+    /// `text` with the emoji before it and the tags after it.
+    pub fn decorate(&self, text: &str) -> String {
+        let tags: Vec<String> = self.tags.iter().map(|tag| format!("#{tag}")).collect();
+        format!("{} {text} {}", self.emoji, tags.join(" "))
+    }
+}
+
+/// Tag `text` from the words in it, or `None` when no rule matches.
+fn by_rules(text: &str) -> Option<Tagging> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let matched: Vec<&Rule> = RULES
+        .iter()
+        .filter(|rule| words.iter().any(|word| rule.words.contains(&word.as_str())))
+        .take(MAX_TAGS)
+        .collect();
+    Some(Tagging {
+        emoji: matched.first()?.emoji.to_string(),
+        tags: matched.iter().map(|rule| rule.tag.to_string()).collect(),
+    })
+}
+
+/// Keep the tags the model gave that are known, and use the emoji of the first when the model
+/// gave none.
+fn from_model(emoji: String, tags: Vec<String>) -> Option<Tagging> {
+    let mut known: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if RULES.iter().any(|rule| rule.tag == tag) && !known.contains(&tag) {
+            known.push(tag);
+        }
+    }
+    known.truncate(MAX_TAGS);
+    let first = RULES.iter().find(|rule| rule.tag == *known.first()?)?;
+    let emoji = match emoji.trim() {
+        "" => first.emoji.to_string(),
+        emoji => emoji.to_string(),
+    };
+    Some(Tagging { emoji, tags: known })
+}
+
+/// The items to tag, keyed as the renderers look them up, with the text to tag them from:
+/// browsing groups by label, with their page titles; repositories by name, with their commit
+/// messages; and highlights as they are.
+fn items(context: &Context, summary: &WorkSummary) -> Vec<(String, String)> {
+    let clusters = context.safari_history().iter().map(|cluster| {
+        let titles: Vec<&str> = cluster
+            .urls
+            .iter()
+            .filter_map(|page| page.title.as_deref())
+            .collect();
+        (
+            cluster.label.clone(),
+            format!("{} {}", cluster.label, titles.join(" ")),
+        )
+    });
+    let repos = context
+        .commit_history()
+        .iter()
+        .filter(|repo| !repo.commits.is_empty())
+        .map(|repo| {
+            let messages: Vec<&str> = repo
+                .commits
+                .iter()
+                .map(|commit| commit.summary.as_str())
+                .collect();
+            (repo.project_name(), messages.join(" "))
+        });
+    let highlights = summary
+        .highlights
+        .iter()
+        .map(|highlight| (highlight.clone(), highlight.clone()));
+    clusters.chain(repos).chain(highlights).collect()
+}
+
+/// Give the run's browsing groups, repositories, and highlights an emoji and tags.
+///
+/// Words such as "fixed" or "deploy" decide the tags. The items no word gives away are sent
+/// to the model in one question; when that fails they are left untagged.
+#[tracing::instrument(name = "Tagging the summary", level = "info", skip_all)]
+pub async fn tag_run<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    context: &Context,
+    summary: &mut WorkSummary,
+) {
+    let mut tags = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for (key, text) in items(context, summary) {
+        match by_rules(&text) {
+            Some(tagging) => {
+                tags.insert(key, tagging);
+            }
+            None if !untagged.contains(&key) => untagged.push(key),
+            None => {}
+        }
+    }
+    untagged.retain(|key| !tags.contains_key(key));
+    if !untagged.is_empty() {
+        match ai::tagging::suggest_tags(client, model, &untagged).await {
+            Ok(suggested) => {
+                for item in suggested {
+                    if untagged.contains(&item.item)
+                        && let Some(tagging) = from_model(item.emoji, item.tags)
+                    {
+                        tags.insert(item.item, tagging);
+                    }
+                }
+            }
+            Err(e) => warn!("Leaving {} items untagged: {e}", untagged.len()),
+        }
+    }
+    info!("Tagged {} items", tags.len());
+    summary.tags = tags;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_from_words_and_checks_the_models_tags() {
+        let tagging = by_rules("Fixed the CI pipeline crash; bumped tokio").unwrap();
+        assert_eq!(tagging.emoji, "🐛");
+        assert_eq!(tagging.tags, ["bugfix", "ops", "deps"]);
+        assert_eq!(tagging.decorate("Parser"), "🐛 Parser #bugfix #ops #deps");
+        assert_eq!(by_rules("Prefixed the lexer tokens"), None);
+
+        let tagging = from_model(String::new(), vec!["#Research".into(), "meetings".into()]);
+        assert_eq!(
+            tagging,
+            Some(Tagging {
+                emoji: "🔍".into(),
+                tags: vec!["research".into()],
+            })
+        );
+        assert_eq!(from_model("🎉".into(), vec!["party".into()]), None);
+    }
+}
@@ -0,0 +1,324 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::AppResult;
+use crate::time_utils::{datetime_to_macos_time, macos_to_datetime, past_ts};
+use crate::warnings::{self, WarningKind};
+
+/// Completed to-dos since `?` (Unix seconds), with their project, the project of their heading,
+/// or their area. `status = 3` is "completed"; canceled to-dos (`2`) are left out.
+const THINGS_QUERY: &str = "
+SELECT
+    t.title AS title,
+    COALESCE(p.title, hp.title, a.title) AS project,
+    t.stopDate AS completed
+FROM TMTask t
+LEFT JOIN TMTask p ON p.uuid = t.project
+LEFT JOIN TMTask h ON h.uuid = t.heading
+LEFT JOIN TMTask hp ON hp.uuid = h.project
+LEFT JOIN TMArea a ON a.uuid = t.area
+WHERE t.type = 0 AND t.status = 3 AND t.trashed = 0 AND t.stopDate > ?
+ORDER BY t.stopDate ASC";
+
+/// Actions completed since `?` (macOS seconds), with their project. Rows with a `projectInfo`
+/// are the projects themselves.
+const OMNIFOCUS_QUERY: &str = "
+SELECT
+    t.name AS title,
+    p.name AS project,
+    t.dateCompleted AS completed
+FROM Task t
+LEFT JOIN Task p ON p.persistentIdentifier = t.containingProjectInfo
+WHERE t.projectInfo IS NULL AND t.dateCompleted > ?
+ORDER BY t.dateCompleted ASC";
+
+/// Reminders completed since `?` (macOS seconds), with their list.
+const REMINDERS_QUERY: &str = "
+SELECT
+    r.ZTITLE AS title,
+    l.ZNAME AS project,
+    r.ZCOMPLETIONDATE AS completed
+FROM ZREMCDREMINDER r
+LEFT JOIN ZREMCDBASELIST l ON l.Z_PK = r.ZLIST
+WHERE r.ZCOMPLETED = 1
+    AND COALESCE(r.ZMARKEDFORDELETION, 0) = 0
+    AND r.ZCOMPLETIONDATE > ?
+ORDER BY r.ZCOMPLETIONDATE ASC";
+
+/// Raw row returned by the task queries. `completed` is in the app's own epoch.
+#[derive(Debug, FromQueryResult)]
+struct TaskRow {
+    title: Option<String>,
+    project: Option<String>,
+    completed: f64,
+}
+
+/// A to-do app whose completed tasks are collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskApp {
+    Things,
+    OmniFocus,
+    Reminders,
+}
+
+impl TaskApp {
+    const ALL: [TaskApp; 3] = [TaskApp::Things, TaskApp::OmniFocus, TaskApp::Reminders];
+
+    fn name(self) -> &'static str {
+        match self {
+            TaskApp::Things => "Things",
+            TaskApp::OmniFocus => "OmniFocus",
+            TaskApp::Reminders => "Reminders",
+        }
+    }
+
+    fn query(self) -> &'static str {
+        match self {
+            TaskApp::Things => THINGS_QUERY,
+            TaskApp::OmniFocus => OMNIFOCUS_QUERY,
+            TaskApp::Reminders => REMINDERS_QUERY,
+        }
+    }
+
+    /// Things stores Unix timestamps; OmniFocus and Reminders use the macOS epoch.
+    fn timestamp(self, time: &OffsetDateTime) -> f64 {
+        match self {
+            TaskApp::Things => time.unix_timestamp() as f64,
+            TaskApp::OmniFocus | TaskApp::Reminders => datetime_to_macos_time(time),
+        }
+    }
+
+    fn to_datetime(self, timestamp: f64) -> OffsetDateTime {
+        match self {
+            TaskApp::Things => OffsetDateTime::from_unix_timestamp(timestamp as i64)
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+            TaskApp::OmniFocus | TaskApp::Reminders => macos_to_datetime(timestamp),
+        }
+    }
+
+    /// Environment variable that overrides where the database is looked for.
+    fn env_var(self) -> &'static str {
+        match self {
+            TaskApp::Things => "THINGS_DB_PATH",
+            TaskApp::OmniFocus => "OMNIFOCUS_DB_PATH",
+            TaskApp::Reminders => "REMINDERS_DB_PATH",
+        }
+    }
+
+    /// Databases to read. Reminders keeps one store per account, and Things one per sync
+    /// account directory.
+    fn db_paths(self) -> Vec<PathBuf> {
+        if let Ok(path) = env::var(self.env_var()) {
+            return vec![PathBuf::from(path)];
+        }
+        let Some(library) = env::home_dir()
+            .or_else(|| env::var("HOME").ok().map(PathBuf::from))
+            .map(|home| home.join("Library"))
+        else {
+            return Vec::new();
+        };
+        match self {
+            TaskApp::Things => {
+                let group = library.join("Group Containers/JLMPQHK86H.com.culturedcode.ThingsMac");
+                matching_children(&group, |name| name.starts_with("ThingsData-"))
+                    .into_iter()
+                    .map(|dir| dir.join("Things Database.thingsdatabase/main.sqlite"))
+                    .collect()
+            }
+            TaskApp::OmniFocus => ["OmniFocus4", "OmniFocus3"]
+                .into_iter()
+                .map(|app| {
+                    library.join(format!(
+                        "Containers/com.omnigroup.{app}/Data/Library/Application Support/\
+                         OmniFocus/OmniFocus Caches/OmniFocusDatabase2"
+                    ))
+                })
+                .find(|path| path.exists())
+                .into_iter()
+                .collect(),
+            TaskApp::Reminders => matching_children(
+                &library.join("Group Containers/group.com.apple.reminders/Container_v1/Stores"),
+                |name| name.starts_with("Data-") && name.ends_with(".sqlite"),
+            ),
+        }
+    }
+}
+
+/// Entries of `dir` whose file name satisfies `matches`, or none when `dir` is unreadable.
+fn matching_children(dir: &Path, matches: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_str().is_some_and(&matches))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// A task marked done during the window in Things, OmniFocus, or Reminders.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CompletedTask {
+    /// App the task was completed in: `Things`, `OmniFocus`, or `Reminders`.
+    pub source: String,
+    pub title: String,
+    /// Project, area, or list the task belongs to.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub project: Option<String>,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub completed: OffsetDateTime,
+}
+
+/// Open a task database read-only.
+async fn connect_to_db(path: &Path) -> AppResult<DatabaseConnection> {
+    let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=ro", path.display()));
+    opt.sqlx_logging(false);
+    trace!("Connecting to {}", path.display());
+    Ok(Database::connect(opt).await?)
+}
+
+/// Run `app`'s query for tasks completed after `since`.
+async fn fetch_completed(
+    db: &DatabaseConnection,
+    app: TaskApp,
+    since: OffsetDateTime,
+) -> AppResult<Vec<CompletedTask>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        app.query(),
+        [app.timestamp(&since).into()],
+    );
+    Ok(TaskRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            let title = row.title?.trim().to_string();
+            (!title.is_empty()).then(|| CompletedTask {
+                source: app.name().to_string(),
+                title,
+                project: row.project.filter(|p| !p.trim().is_empty()),
+                completed: app.to_datetime(row.completed),
+            })
+        })
+        .collect())
+}
+
+/// Collect the tasks completed during the window in Things 3, OmniFocus, and Apple Reminders.
+///
+/// Apps that are not installed are skipped silently; a database that cannot be read (usually
+/// for lack of Full Disk Access) is reported as a data-quality warning and the other apps are
+/// still read.
+#[tracing::instrument(name = "Collecting completed tasks", level = "info")]
+pub async fn get_completed_tasks(duration: &Duration) -> AppResult<Vec<CompletedTask>> {
+    let since = past_ts(duration);
+    let mut tasks = Vec::new();
+    for app in TaskApp::ALL {
+        for path in app.db_paths().into_iter().filter(|path| path.exists()) {
+            let result = match connect_to_db(&path).await {
+                Ok(db) => fetch_completed(&db, app, since).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(found) => {
+                    debug!("Found {} completed tasks in {}", found.len(), app.name());
+                    tasks.extend(found);
+                }
+                Err(e) => warnings::record(
+                    WarningKind::Skipped,
+                    "tasks",
+                    format!("Unable to read {} at {}: {e}", app.name(), path.display()),
+                ),
+            }
+        }
+    }
+    tasks.sort_by_key(|task| task.completed);
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+    use time::macros::datetime;
+
+    use super::*;
+
+    async fn db(statements: &[&str]) -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in statements {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn reads_completed_tasks_from_each_app() {
+        let since = datetime!(2025-01-31 00:00 UTC);
+        let things = db(&[
+            "CREATE TABLE TMTask (uuid TEXT, title TEXT, type INTEGER, status INTEGER, trashed INTEGER, stopDate REAL, project TEXT, heading TEXT, area TEXT)",
+            "CREATE TABLE TMArea (uuid TEXT, title TEXT)",
+            "INSERT INTO TMArea VALUES ('a', 'Work')",
+            "INSERT INTO TMTask VALUES
+                ('p', 'Parser rewrite', 1, 0, 0, NULL, NULL, NULL, 'a'),
+                ('h', 'Milestone 1', 2, 0, 0, NULL, 'p', NULL, NULL),
+                ('1', 'Benchmark winnow', 0, 3, 0, 1738321200, NULL, 'h', NULL),
+                ('2', 'File expenses', 0, 3, 0, 1738324800, NULL, NULL, 'a'),
+                ('3', 'Dropped idea', 0, 2, 0, 1738324800, NULL, NULL, NULL),
+                ('4', 'Last week', 0, 3, 0, 1737000000, NULL, NULL, NULL)",
+        ])
+        .await;
+        let tasks = fetch_completed(&things, TaskApp::Things, since)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].title, "Benchmark winnow");
+        assert_eq!(tasks[0].project.as_deref(), Some("Parser rewrite"));
+        assert_eq!(tasks[0].completed, datetime!(2025-01-31 11:00 UTC));
+        assert_eq!(tasks[1].project.as_deref(), Some("Work"));
+
+        let completed = datetime_to_macos_time(&datetime!(2025-01-31 15:00 UTC));
+        let reminders = db(&[
+            "CREATE TABLE ZREMCDREMINDER (ZTITLE TEXT, ZCOMPLETED INTEGER, ZCOMPLETIONDATE REAL, ZMARKEDFORDELETION INTEGER, ZLIST INTEGER)",
+            "CREATE TABLE ZREMCDBASELIST (Z_PK INTEGER PRIMARY KEY, ZNAME TEXT)",
+            "INSERT INTO ZREMCDBASELIST VALUES (1, 'Errands')",
+            &format!(
+                "INSERT INTO ZREMCDREMINDER VALUES
+                    ('Renew passport', 1, {completed}, 0, 1),
+                    ('Still open', 0, NULL, 0, 1),
+                    ('Deleted', 1, {completed}, 1, 1)"
+            ),
+        ])
+        .await;
+        let tasks = fetch_completed(&reminders, TaskApp::Reminders, since)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].source, "Reminders");
+        assert_eq!(tasks[0].project.as_deref(), Some("Errands"));
+        assert_eq!(tasks[0].completed, datetime!(2025-01-31 15:00 UTC));
+
+        let omnifocus = db(&[
+            "CREATE TABLE Task (persistentIdentifier TEXT, name TEXT, dateCompleted REAL, containingProjectInfo TEXT, projectInfo TEXT)",
+            &format!(
+                "INSERT INTO Task VALUES
+                    ('p', 'Release 2.0', {completed}, NULL, 'p'),
+                    ('t', 'Tag the release', {completed}, 'p', NULL)"
+            ),
+        ])
+        .await;
+        let tasks = fetch_completed(&omnifocus, TaskApp::OmniFocus, since)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].project.as_deref(), Some("Release 2.0"));
+    }
+}
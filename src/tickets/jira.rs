@@ -0,0 +1,157 @@
+use std::env;
+
+use serde_json::Value;
+use time::OffsetDateTime;
+use time::format_description::BorrowedFormatItem;
+use time::macros::format_description;
+use tracing::trace;
+
+use super::{TicketDetails, TicketSource, TicketTransition};
+use crate::AppResult;
+use crate::error::AppError;
+use crate::http;
+
+/// Jira timestamps look like `2025-01-02T03:04:05.000+0000`, which is not quite RFC 3339.
+const JIRA_DATETIME: &[BorrowedFormatItem] = format_description!(
+    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond][offset_hour sign:mandatory][offset_minute]"
+);
+
+/// Jira Cloud issue lookups through the REST v3 API using basic (email + API token) auth.
+pub struct JiraSource {
+    client: reqwest::Client,
+    base_url: String,
+    email: String,
+    token: String,
+}
+
+impl JiraSource {
+    /// Build a Jira source from `JIRA_BASE_URL`, `JIRA_EMAIL`, and `JIRA_API_TOKEN`.
+    /// Returns `Ok(None)` when Jira is not configured.
+    pub fn from_env() -> AppResult<Option<Self>> {
+        let (Ok(base_url), Ok(email), Ok(token)) = (
+            env::var("JIRA_BASE_URL"),
+            env::var("JIRA_EMAIL"),
+            env::var("JIRA_API_TOKEN"),
+        ) else {
+            return Ok(None);
+        };
+        let client = http::client(&[&base_url])?;
+        Ok(Some(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            email,
+            token,
+        }))
+    }
+
+    /// Status transitions from the issue changelog that happened after `since`.
+    fn transitions(issue: &Value, since: OffsetDateTime) -> Vec<TicketTransition> {
+        let histories = issue["changelog"]["histories"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut transitions: Vec<TicketTransition> = histories
+            .iter()
+            .filter_map(|history| {
+                let at = OffsetDateTime::parse(history["created"].as_str()?, JIRA_DATETIME).ok()?;
+                if at < since {
+                    return None;
+                }
+                let item = history["items"]
+                    .as_array()?
+                    .iter()
+                    .find(|item| item["field"].as_str() == Some("status"))?;
+                Some(TicketTransition {
+                    from: item["fromString"].as_str().map(str::to_string),
+                    to: item["toString"].as_str()?.to_string(),
+                    at,
+                })
+            })
+            .collect();
+        transitions.sort_by_key(|t| t.at);
+        transitions
+    }
+}
+
+impl TicketSource for JiraSource {
+    fn name(&self) -> &'static str {
+        "jira"
+    }
+
+    async fn fetch(&self, key: &str, since: OffsetDateTime) -> AppResult<Option<TicketDetails>> {
+        let url = format!(
+            "{}/rest/api/3/issue/{key}?fields=summary,status&expand=changelog",
+            self.base_url
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .basic_auth(&self.email, Some(&self.token))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(AppError::Other(format!(
+                "Jira returned {} for {key}",
+                resp.status()
+            )));
+        }
+        let issue: Value = serde_json::from_str(&resp.text().await?)?;
+        trace!("Jira issue {key}: {issue}");
+        Ok(Some(TicketDetails {
+            title: issue["fields"]["summary"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            status: issue["fields"]["status"]["name"]
+                .as_str()
+                .unwrap_or("Unknown")
+                .to_string(),
+            url: format!("{}/browse/{key}", self.base_url),
+            transitions: Self::transitions(&issue, since),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_status_transitions_after_cutoff() {
+        let issue = json!({
+            "changelog": {
+                "histories": [
+                    {
+                        "created": "2025-01-01T09:00:00.000+0000",
+                        "items": [{"field": "status", "fromString": "Backlog", "toString": "To Do"}]
+                    },
+                    {
+                        "created": "2025-01-02T15:30:00.000+0100",
+                        "items": [
+                            {"field": "assignee", "fromString": null, "toString": "me"},
+                            {"field": "status", "fromString": "In Progress", "toString": "Done"}
+                        ]
+                    },
+                    {
+                        "created": "2025-01-02T10:00:00.000+0000",
+                        "items": [{"field": "status", "fromString": "To Do", "toString": "In Progress"}]
+                    }
+                ]
+            }
+        });
+        let since = OffsetDateTime::parse("2025-01-02T00:00:00.000+0000", JIRA_DATETIME).unwrap();
+
+        let transitions = JiraSource::transitions(&issue, since);
+
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0].to, "In Progress");
+        assert_eq!(transitions[1].from.as_deref(), Some("In Progress"));
+        assert_eq!(transitions[1].to, "Done");
+    }
+}
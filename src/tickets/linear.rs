@@ -0,0 +1,99 @@
+use std::env;
+
+use serde_json::{Value, json};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::trace;
+
+use super::{TicketDetails, TicketSource, TicketTransition};
+use crate::AppResult;
+use crate::error::AppError;
+use crate::http;
+
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+const ISSUE_QUERY: &str = "query Issue($id: String!) {
+  issue(id: $id) {
+    title
+    url
+    state { name }
+    history(first: 50) {
+      nodes { createdAt fromState { name } toState { name } }
+    }
+  }
+}";
+
+/// Linear issue lookups through the GraphQL API using a personal API key.
+pub struct LinearSource {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl LinearSource {
+    /// Build a Linear source from `LINEAR_API_KEY`. Returns `Ok(None)` when Linear is not configured.
+    pub fn from_env() -> AppResult<Option<Self>> {
+        let Ok(api_key) = env::var("LINEAR_API_KEY") else {
+            return Ok(None);
+        };
+        let client = http::client(&[LINEAR_API_URL])?;
+        Ok(Some(Self { client, api_key }))
+    }
+}
+
+impl TicketSource for LinearSource {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    async fn fetch(&self, key: &str, since: OffsetDateTime) -> AppResult<Option<TicketDetails>> {
+        let body = json!({ "query": ISSUE_QUERY, "variables": { "id": key } });
+        let resp = self
+            .client
+            .post(LINEAR_API_URL)
+            .header(reqwest::header::AUTHORIZATION, &self.api_key)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&body)?)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(AppError::Other(format!(
+                "Linear returned {} for {key}",
+                resp.status()
+            )));
+        }
+        let data: Value = serde_json::from_str(&resp.text().await?)?;
+        trace!("Linear issue {key}: {data}");
+        let issue = &data["data"]["issue"];
+        if issue.is_null() {
+            // Linear reports unknown identifiers as a GraphQL error with a null issue.
+            return Ok(None);
+        }
+
+        let mut transitions: Vec<TicketTransition> = issue["history"]["nodes"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|node| {
+                let at = OffsetDateTime::parse(node["createdAt"].as_str()?, &Rfc3339).ok()?;
+                let to = node["toState"]["name"].as_str()?;
+                (at >= since).then(|| TicketTransition {
+                    from: node["fromState"]["name"].as_str().map(str::to_string),
+                    to: to.to_string(),
+                    at,
+                })
+            })
+            .collect();
+        transitions.sort_by_key(|t| t.at);
+
+        Ok(Some(TicketDetails {
+            title: issue["title"].as_str().unwrap_or_default().to_string(),
+            status: issue["state"]["name"]
+                .as_str()
+                .unwrap_or("Unknown")
+                .to_string(),
+            url: issue["url"].as_str().unwrap_or_default().to_string(),
+            transitions,
+        }))
+    }
+}
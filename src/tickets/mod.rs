@@ -0,0 +1,330 @@
+/// Jira Cloud REST client.
+pub(crate) mod jira;
+
+/// Linear GraphQL client.
+pub(crate) mod linear;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, info, warn};
+
+use crate::AppResult;
+use crate::git::GitRepoHistory;
+use crate::time_utils::past_ts;
+use crate::warnings::{self, WarningKind};
+
+/// A status change on a ticket, as reported by the tracker.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TicketTransition {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub from: Option<String>,
+    pub to: String,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub at: OffsetDateTime,
+}
+
+/// Ticket details returned by a `TicketSource`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TicketDetails {
+    pub title: String,
+    pub status: String,
+    pub url: String,
+    pub transitions: Vec<TicketTransition>,
+}
+
+/// A ticket referenced by the day's git activity, enriched with tracker data when available.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TicketActivity {
+    pub key: String,
+    /// Name of the tracker that resolved the ticket (`jira`, `linear`), if any did.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub details: Option<TicketDetails>,
+    pub repos: Vec<PathBuf>,
+    pub branches: Vec<String>,
+    pub commits: Vec<String>,
+}
+
+impl TicketActivity {
+    /// One-line description used in the "tickets touched" section of the summary.
+    pub fn describe(&self) -> String {
+        match &self.details {
+            Some(details) => {
+                let transitions = details
+                    .transitions
+                    .iter()
+                    .map(|t| match &t.from {
+                        Some(from) => format!("{from} → {}", t.to),
+                        None => t.to.clone(),
+                    })
+                    .collect::<Vec<String>>();
+                if transitions.is_empty() {
+                    format!("{} [{}]: {}", self.key, details.status, details.title)
+                } else {
+                    format!(
+                        "{} [{}]: {}",
+                        self.key,
+                        transitions.join(", "),
+                        details.title
+                    )
+                }
+            }
+            None => format!(
+                "{}: referenced in {} commit(s)",
+                self.key,
+                self.commits.len().max(self.branches.len())
+            ),
+        }
+    }
+}
+
+/// A ticket tracker that can resolve a ticket key into its details.
+pub trait TicketSource {
+    /// Short identifier of the tracker, e.g. `jira`.
+    fn name(&self) -> &'static str;
+
+    /// Fetch a ticket and the status transitions that happened after `since`.
+    /// Returns `Ok(None)` when the tracker does not know the key.
+    async fn fetch(&self, key: &str, since: OffsetDateTime) -> AppResult<Option<TicketDetails>>;
+}
+
+/// Upper-case prefixes that look like ticket keys but are encodings, hashes, or standards.
+const NON_TICKET_PREFIXES: &[&str] = &[
+    "AES", "CVE", "GPT", "HTTP", "ISO", "MD", "PEP", "RFC", "SHA", "TLS", "UTF",
+];
+
+/// Extract ticket keys such as `ENG-123` or `PROJ-42` from free text.
+///
+/// Both Jira and Linear use an upper-case team/project prefix followed by a dash and a number.
+pub fn extract_ticket_keys(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let at_boundary = i == 0 || !chars[i - 1].is_ascii_alphanumeric();
+        if !at_boundary || !chars[i].is_ascii_uppercase() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && (chars[i].is_ascii_uppercase() || chars[i].is_ascii_digit()) {
+            i += 1;
+        }
+        if i >= chars.len() || chars[i] != '-' || i - start < 2 {
+            continue;
+        }
+        let dash = i;
+        i += 1;
+        let digits_start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        let ends_cleanly = i >= chars.len() || !chars[i].is_ascii_alphanumeric();
+        if i > digits_start && ends_cleanly {
+            let prefix: String = chars[start..dash].iter().collect();
+            let key: String = chars[start..i].iter().collect();
+            if !keys.contains(&key)
+                && chars[dash + 1] != '0'
+                && !NON_TICKET_PREFIXES.contains(&prefix.as_str())
+            {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+/// Push `value` onto `values` unless it is already present.
+fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+/// Correlate ticket keys found in commit messages and branch names across repositories.
+#[tracing::instrument(name = "Finding ticket references", level = "info", skip_all)]
+pub fn find_ticket_references(commit_history: &[GitRepoHistory]) -> Vec<TicketActivity> {
+    let mut tickets: BTreeMap<String, TicketActivity> = BTreeMap::new();
+    for repo_hist in commit_history {
+        let repo = &repo_hist.diff.repo_path;
+        for commit in &repo_hist.commits {
+            let mut text = commit.summary.clone();
+            if let Some(body) = &commit.body {
+                text.push('\n');
+                text.push_str(body);
+            }
+            let from_message = extract_ticket_keys(&text).into_iter().map(|k| (k, None));
+            let from_branches = commit.branches.iter().flat_map(|b| {
+                extract_ticket_keys(b)
+                    .into_iter()
+                    .map(move |k| (k, Some(b)))
+            });
+            for (key, branch) in from_message.chain(from_branches) {
+                let ticket = tickets
+                    .entry(key.clone())
+                    .or_insert_with(|| TicketActivity {
+                        key,
+                        source: None,
+                        details: None,
+                        repos: Vec::new(),
+                        branches: Vec::new(),
+                        commits: Vec::new(),
+                    });
+                push_unique(&mut ticket.repos, repo.clone());
+                match branch {
+                    Some(branch) => push_unique(&mut ticket.branches, branch.clone()),
+                    None => push_unique(&mut ticket.commits, commit.summary.clone()),
+                }
+            }
+        }
+    }
+    tickets.into_values().collect()
+}
+
+/// Try to resolve every ticket through `source`, leaving already-resolved tickets untouched.
+async fn enrich_with<S: TicketSource>(
+    source: &S,
+    tickets: &mut [TicketActivity],
+    since: OffsetDateTime,
+) {
+    for ticket in tickets.iter_mut().filter(|t| t.details.is_none()) {
+        match source.fetch(&ticket.key, since).await {
+            Ok(Some(details)) => {
+                debug!("Resolved {} through {}", ticket.key, source.name());
+                ticket.source = Some(source.name().to_string());
+                ticket.details = Some(details);
+            }
+            Ok(None) => debug!("{} does not know about {}", source.name(), ticket.key),
+            Err(e) => warnings::record(
+                WarningKind::Skipped,
+                "tickets",
+                format!(
+                    "Unable to fetch {} from {}: {}. Continuing without tracker details.",
+                    ticket.key,
+                    source.name(),
+                    e
+                ),
+            ),
+        }
+    }
+}
+
+/// Collect tickets touched during the window: keys come from git, details from configured trackers.
+///
+/// Trackers are configured through the environment (`JIRA_BASE_URL`, `JIRA_EMAIL`,
+/// `JIRA_API_TOKEN`, and `LINEAR_API_KEY`). Without them, only the locally referenced keys are
+/// reported.
+#[tracing::instrument(
+    name = "Collecting ticket activity",
+    level = "info",
+    skip(commit_history)
+)]
+pub async fn get_ticket_activity(
+    commit_history: &[GitRepoHistory],
+    duration: &Duration,
+) -> AppResult<Vec<TicketActivity>> {
+    let mut tickets = find_ticket_references(commit_history);
+    if tickets.is_empty() {
+        return Ok(tickets);
+    }
+    info!("Found {} ticket reference(s) in git history", tickets.len());
+    let since = past_ts(duration);
+
+    if let Some(jira) = jira::JiraSource::from_env()? {
+        enrich_with(&jira, &mut tickets, since).await;
+    }
+    if let Some(linear) = linear::LinearSource::from_env()? {
+        enrich_with(&linear, &mut tickets, since).await;
+    }
+    Ok(tickets)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::git::CommitMeta;
+    use crate::git::diff::DiffSummary;
+
+    fn repo_history(path: &str, commits: Vec<CommitMeta>) -> GitRepoHistory {
+        GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from(path),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: Vec::new(),
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits,
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        }
+    }
+
+    fn commit(summary: &str, branches: &[&str]) -> CommitMeta {
+        CommitMeta {
+            id: String::new(),
+            summary: summary.into(),
+            body: None,
+            timestamp: OffsetDateTime::UNIX_EPOCH,
+            branches: branches.iter().map(|b| b.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn extracts_keys_at_word_boundaries() {
+        let keys = extract_ticket_keys("ENG-123: fix parser (see OPS-7, not UTF-8 or aENG-9)");
+        assert_eq!(keys, vec!["ENG-123".to_string(), "OPS-7".to_string()]);
+    }
+
+    #[test]
+    fn ignores_single_letter_prefixes_and_leading_zeros() {
+        assert!(extract_ticket_keys("A-1 and X-99").is_empty());
+        assert!(extract_ticket_keys("ENG-0123").is_empty());
+        assert_eq!(
+            extract_ticket_keys("feature/ENG-42-new-cli"),
+            vec!["ENG-42"]
+        );
+    }
+
+    #[test]
+    fn correlates_commits_and_branches() {
+        let history = vec![
+            repo_history(
+                "/work/api",
+                vec![
+                    commit("ENG-1 add endpoint", &["feature/ENG-1-endpoint"]),
+                    commit("tidy up", &["main"]),
+                ],
+            ),
+            repo_history("/work/web", vec![commit("Wire ENG-1 into UI", &["main"])]),
+        ];
+
+        let tickets = find_ticket_references(&history);
+
+        assert_eq!(tickets.len(), 1);
+        let ticket = &tickets[0];
+        assert_eq!(ticket.key, "ENG-1");
+        assert_eq!(
+            ticket.repos,
+            vec![PathBuf::from("/work/api"), PathBuf::from("/work/web")]
+        );
+        assert_eq!(ticket.branches, vec!["feature/ENG-1-endpoint".to_string()]);
+        assert_eq!(ticket.commits.len(), 2);
+    }
+}
@@ -1,9 +1,47 @@
-use time::{Duration, OffsetDateTime, Time};
-use tracing::trace;
+use std::sync::{Once, OnceLock};
+
+use time::{Duration, OffsetDateTime, Time, UtcOffset};
+use tracing::{trace, warn};
 
 /// Seconds between Unix epoch (1970) and macOS epoch (2001).
 const MACOS_EPOCH_OFFSET: f64 = 978_307_200.0;
 
+/// Seconds between the Windows FILETIME epoch (1601), used by Chromium-based browsers, and Unix
+/// epoch (1970).
+const WEBKIT_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// The machine's UTC offset, or `None` when it could not be read.
+static LOCAL_OFFSET: OnceLock<Option<UtcOffset>> = OnceLock::new();
+
+/// Read the machine's UTC offset for [`local_offset`].
+///
+/// `time` refuses to read it once the process has more than one thread, because reading the
+/// time zone races with threads changing the environment, so this must run before the Tokio
+/// runtime starts.
+pub fn init_local_offset() {
+    let _ = LOCAL_OFFSET.set(UtcOffset::current_local_offset().ok());
+}
+
+/// The machine's UTC offset as read by [`init_local_offset`], or `None` when it could not be.
+pub fn known_local_offset() -> Option<UtcOffset> {
+    *LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().ok())
+}
+
+/// The machine's UTC offset, for dates and times shown to the user or matched against days.
+/// Falls back to UTC, with a warning the first time, when the offset could not be read.
+pub fn local_offset() -> UtcOffset {
+    static WARNED: Once = Once::new();
+    known_local_offset().unwrap_or_else(|| {
+        WARNED.call_once(|| warn!("Unable to read the local time zone, so times are in UTC"));
+        UtcOffset::UTC
+    })
+}
+
+/// The current date and time in the machine's time zone; see [`local_offset`].
+pub fn now_local() -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_offset(local_offset())
+}
+
 /// Convert an `OffsetDateTime` to macOS timestamp (seconds since 2001-01-01) as f64.
 #[tracing::instrument(
     name = "Converting standard date and time to a MacOS timestamp",
@@ -56,7 +94,7 @@ pub fn unix_time_nsec_to_datetime(secs: i128) -> OffsetDateTime {
     // Convert to local time for user-facing output.
     OffsetDateTime::from_unix_timestamp_nanos(secs)
         .unwrap()
-        .to_offset(OffsetDateTime::now_local().unwrap().offset())
+        .to_offset(local_offset())
 }
 
 /// Convert macOS timestamp (seconds since 2001) to Unix time in nanoseconds.
@@ -85,6 +123,35 @@ pub fn midnight_utc() -> OffsetDateTime {
     OffsetDateTime::new_utc(today.date(), Time::MIDNIGHT)
 }
 
+/// Convert an `OffsetDateTime` to a Chromium timestamp (microseconds since 1601-01-01 UTC).
+#[tracing::instrument(
+    name = "Converting standard date and time to a Chromium timestamp",
+    level = "debug"
+)]
+pub fn datetime_to_webkit_time(dt: &OffsetDateTime) -> i64 {
+    (dt.unix_timestamp_nanos() / 1_000) as i64 + WEBKIT_EPOCH_OFFSET * 1_000_000
+}
+
+/// Chromium timestamp for the given duration ago.
+#[tracing::instrument(
+    name = "Calculating the date and time in the past as a Chromium timestamp",
+    level = "debug"
+)]
+pub fn webkit_past_ts(duration: &Duration) -> i64 {
+    datetime_to_webkit_time(&past_ts(duration))
+}
+
+/// Convert a Chromium timestamp (microseconds since 1601-01-01) to UTC `OffsetDateTime`.
+#[tracing::instrument(
+    name = "Converting Chromium timestamp to date and time",
+    level = "debug"
+)]
+pub fn webkit_to_datetime(webkit_time: i64) -> OffsetDateTime {
+    let micros = webkit_time - WEBKIT_EPOCH_OFFSET * 1_000_000;
+    OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * 1_000)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
 #[tracing::instrument(name = "Converting SystemTime to OffsetDateTime", level = "debug")]
 pub fn system_time_to_offset_datetime(st: std::time::SystemTime) -> OffsetDateTime {
     let duration_since_epoch = st
@@ -120,6 +187,14 @@ mod tests {
         assert!(delta <= 1, "drift too large: {}µs", delta);
     }
 
+    #[test]
+    fn datetime_and_webkit_roundtrip() {
+        let dt = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let webkit = datetime_to_webkit_time(&dt);
+        assert_eq!(webkit, 13_344_473_600_000_000);
+        assert_eq!(webkit_to_datetime(webkit), dt);
+    }
+
     #[test]
     fn midnight_is_midnight_utc() {
         let mid = midnight_utc();
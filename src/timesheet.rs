@@ -0,0 +1,529 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use async_openai::Client;
+use async_openai::config::Config;
+use time::{Date, Duration};
+use tracing::info;
+
+use crate::ai::timesheet::{LineWork, generate_descriptions};
+use crate::archive::{self, ArchiveEntry};
+use crate::dashboard::{ACTIVE_SLOT_SECS, DayStats};
+use crate::error::AppError;
+use crate::git::GitRepoHistory;
+use crate::{AppResult, io_utils, time_utils};
+
+/// Bill the repositories matching any of `patterns` as `name`, given as `NAME=PATTERN[,PATTERN]`.
+///
+/// A pattern matches when the repository's project name or path contains it, ignoring case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectMapping {
+    pub name: String,
+    pub patterns: Vec<String>,
+}
+
+impl ProjectMapping {
+    fn matches(&self, repo: &GitRepoHistory) -> bool {
+        repo.matches_any(&self.patterns)
+    }
+}
+
+/// Split `NAME=VALUE`, requiring both sides.
+fn split_assignment<'a>(value: &'a str, what: &str) -> Result<(&'a str, &'a str), String> {
+    match value.split_once('=') {
+        Some((name, rest)) if !name.trim().is_empty() && !rest.trim().is_empty() => {
+            Ok((name.trim(), rest.trim()))
+        }
+        _ => Err(format!("expected NAME={what}, got \"{value}\"")),
+    }
+}
+
+impl FromStr for ProjectMapping {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, patterns) = split_assignment(value, "PATTERN[,PATTERN]")?;
+        Ok(ProjectMapping {
+            name: name.to_string(),
+            patterns: patterns
+                .split(',')
+                .map(|pattern| pattern.trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        })
+    }
+}
+
+/// Hourly rate of one project, given as `NAME=AMOUNT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectRate {
+    pub project: String,
+    pub amount: f64,
+}
+
+impl FromStr for ProjectRate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (project, amount) = split_assignment(value, "AMOUNT")?;
+        let amount: f64 = amount
+            .parse()
+            .map_err(|_| format!("\"{amount}\" is not an hourly rate"))?;
+        if !amount.is_finite() || amount < 0.0 {
+            return Err(format!("\"{amount}\" is not an hourly rate"));
+        }
+        Ok(ProjectRate {
+            project: project.to_string(),
+            amount,
+        })
+    }
+}
+
+/// One billed project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineItem {
+    pub project: String,
+    /// Days with measured time on the project.
+    pub days: usize,
+    pub hours: f64,
+    pub rate: Option<f64>,
+    /// Commits of the period as `YYYY-MM-DD: summary`, for the work description.
+    pub commits: Vec<String>,
+    pub description: Option<String>,
+}
+
+impl LineItem {
+    pub fn amount(&self) -> Option<f64> {
+        self.rate.map(|rate| rate * self.hours)
+    }
+}
+
+/// Measured time per project over a period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timesheet {
+    pub from: Option<Date>,
+    pub to: Option<Date>,
+    pub currency: Option<String>,
+    pub lines: Vec<LineItem>,
+    /// Active time that could not be tied to a repository, such as browsing on its own.
+    pub unassigned_hours: f64,
+}
+
+#[derive(Default)]
+struct Measured {
+    minutes: f64,
+    days: BTreeSet<Date>,
+    commits: Vec<String>,
+}
+
+fn round_hours(minutes: f64) -> f64 {
+    (minutes / 60.0 * 100.0).round() / 100.0
+}
+
+/// The billed project of `repo`: the first mapping that matches it, or its own project name.
+fn project_of(repo: &GitRepoHistory, mappings: &[ProjectMapping]) -> String {
+    mappings
+        .iter()
+        .find(|mapping| mapping.matches(repo))
+        .map_or_else(|| repo.project_name(), |mapping| mapping.name.clone())
+}
+
+impl Timesheet {
+    /// Measure time per project across `entries`.
+    ///
+    /// Time is counted in the same 15-minute slots as the active time of `daily-ai stats`. A
+    /// slot belongs to the repositories that had a commit or a shell command run inside them
+    /// in it, split evenly when there are several; other active slots are unassigned.
+    pub fn measure(
+        entries: &[ArchiveEntry],
+        mappings: &[ProjectMapping],
+        rates: &[ProjectRate],
+        default_rate: Option<f64>,
+        currency: Option<String>,
+    ) -> Self {
+        let mut measured: BTreeMap<String, Measured> = BTreeMap::new();
+        let mut unassigned_minutes = 0.0;
+        for entry in entries {
+            let context = &entry.context.context;
+            let repos: Vec<(&GitRepoHistory, String)> = context
+                .commit_history()
+                .iter()
+                .map(|repo| (repo, project_of(repo, mappings)))
+                .collect();
+            let mut slots: BTreeMap<i64, BTreeSet<&str>> = BTreeMap::new();
+            for (repo, project) in &repos {
+                for commit in &repo.commits {
+                    let slot = commit
+                        .timestamp
+                        .unix_timestamp()
+                        .div_euclid(ACTIVE_SLOT_SECS);
+                    slots.entry(slot).or_default().insert(project.as_str());
+                    measured
+                        .entry(project.clone())
+                        .or_default()
+                        .commits
+                        .push(format!("{}: {}", entry.date, commit.summary));
+                }
+            }
+            for command in context.shell_history() {
+                // The innermost repository, for repositories nested in one another.
+                let Some((_, project)) = repos
+                    .iter()
+                    .filter(|(repo, _)| command.directory.starts_with(&repo.diff.repo_path))
+                    .max_by_key(|(repo, _)| repo.diff.repo_path.components().count())
+                else {
+                    continue;
+                };
+                let slot = command
+                    .date_time
+                    .unix_timestamp()
+                    .div_euclid(ACTIVE_SLOT_SECS);
+                slots.entry(slot).or_default().insert(project.as_str());
+            }
+
+            let slot_minutes = (ACTIVE_SLOT_SECS / 60) as f64;
+            for projects in slots.values() {
+                for project in projects {
+                    let line = measured.entry(project.to_string()).or_default();
+                    line.minutes += slot_minutes / projects.len() as f64;
+                    line.days.insert(entry.date);
+                }
+            }
+            let active = DayStats::from_context(&entry.context).active_minutes as f64;
+            unassigned_minutes += (active - slots.len() as f64 * slot_minutes).max(0.0);
+        }
+
+        let lines = measured
+            .into_iter()
+            .filter(|(_, line)| line.minutes > 0.0)
+            .map(|(project, line)| {
+                let rate = rates
+                    .iter()
+                    .rev()
+                    .find(|rate| rate.project.eq_ignore_ascii_case(&project))
+                    .map(|rate| rate.amount)
+                    .or(default_rate);
+                LineItem {
+                    project,
+                    days: line.days.len(),
+                    hours: round_hours(line.minutes),
+                    rate,
+                    commits: line.commits,
+                    description: None,
+                }
+            })
+            .collect();
+        Timesheet {
+            from: entries.first().map(|entry| entry.date),
+            to: entries.last().map(|entry| entry.date),
+            currency,
+            lines,
+            unassigned_hours: round_hours(unassigned_minutes),
+        }
+    }
+
+    pub fn total_hours(&self) -> f64 {
+        (self.lines.iter().map(|line| line.hours).sum::<f64>() * 100.0).round() / 100.0
+    }
+
+    /// The total amount, when every line has a rate.
+    pub fn total_amount(&self) -> Option<f64> {
+        self.lines.iter().map(LineItem::amount).sum()
+    }
+
+    fn money_header(&self, title: &str) -> String {
+        match &self.currency {
+            Some(currency) => format!("{title} ({currency})"),
+            None => title.to_string(),
+        }
+    }
+
+    /// Render as CSV, one row per project followed by a total row.
+    pub fn to_csv(&self) -> String {
+        let money = |amount: Option<f64>| amount.map(|a| format!("{a:.2}")).unwrap_or_default();
+        let mut out = format!(
+            "project,days,hours,{},{},description\n",
+            csv_field(&self.money_header("rate")),
+            csv_field(&self.money_header("amount"))
+        );
+        for line in &self.lines {
+            let _ = writeln!(
+                out,
+                "{},{},{:.2},{},{},{}",
+                csv_field(&line.project),
+                line.days,
+                line.hours,
+                money(line.rate),
+                money(line.amount()),
+                csv_field(line.description.as_deref().unwrap_or_default())
+            );
+        }
+        let _ = writeln!(
+            out,
+            "Total,,{:.2},,{},",
+            self.total_hours(),
+            money(self.total_amount())
+        );
+        out
+    }
+
+    /// Render as a Markdown table, with the work descriptions below it.
+    pub fn to_markdown(&self) -> String {
+        let money = |amount: Option<f64>| amount.map(|a| format!("{a:.2}")).unwrap_or_default();
+        let mut out = String::from("# Timesheet\n\n");
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            let _ = writeln!(out, "{from} to {to}\n");
+        }
+        let _ = writeln!(
+            out,
+            "| Project | Days | Hours | {} | {} |",
+            self.money_header("Rate"),
+            self.money_header("Amount")
+        );
+        out.push_str("| --- | ---: | ---: | ---: | ---: |\n");
+        for line in &self.lines {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {:.2} | {} | {} |",
+                line.project.replace('|', "\\|"),
+                line.days,
+                line.hours,
+                money(line.rate),
+                money(line.amount())
+            );
+        }
+        let _ = writeln!(
+            out,
+            "| **Total** | | **{:.2}** | | {} |",
+            self.total_hours(),
+            self.total_amount()
+                .map(|amount| format!("**{amount:.2}**"))
+                .unwrap_or_default()
+        );
+        if self.unassigned_hours > 0.0 {
+            let _ = writeln!(
+                out,
+                "\n{:.2} h of active time outside any repository is not billed.",
+                self.unassigned_hours
+            );
+        }
+        let described: Vec<&LineItem> = self
+            .lines
+            .iter()
+            .filter(|line| line.description.is_some())
+            .collect();
+        if !described.is_empty() {
+            out.push_str("\n## Work descriptions\n");
+            for line in described {
+                let _ = write!(
+                    out,
+                    "\n### {}\n\n{}\n",
+                    line.project,
+                    line.description.as_deref().unwrap_or_default().trim()
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Quote `value` for CSV when it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Options of `daily-ai timesheet`.
+#[derive(Debug)]
+pub struct TimesheetOptions<'a> {
+    pub period: Duration,
+    pub projects: &'a [ProjectMapping],
+    pub rates: &'a [ProjectRate],
+    pub default_rate: Option<f64>,
+    pub currency: Option<String>,
+    /// Have the model write a work description for each line.
+    pub describe: bool,
+    pub csv: bool,
+}
+
+/// Build a timesheet from the archived runs of the last `period` and write it to `output` or
+/// show it. CSV is written with `options.csv` or an `output` ending in `.csv`.
+#[tracing::instrument(name = "Building a timesheet", level = "info", skip(client))]
+pub async fn timesheet<C: Config>(
+    client: &Client<C>,
+    model: &str,
+    options: TimesheetOptions<'_>,
+    output: Option<&Path>,
+) -> AppResult<()> {
+    let today = time_utils::now_local().date();
+    let since = today.saturating_sub(options.period);
+    let entries: Vec<ArchiveEntry> = archive::load_all()
+        .await?
+        .into_iter()
+        .filter(|entry| entry.date > since && entry.date <= today)
+        .collect();
+    let mut sheet = Timesheet::measure(
+        &entries,
+        options.projects,
+        options.rates,
+        options.default_rate,
+        options.currency,
+    );
+    if sheet.lines.is_empty() {
+        return Err(AppError::Config(format!(
+            "No commits or shell commands in a repository across the {} archived run(s) in the \
+             period; try a longer --period",
+            entries.len()
+        )));
+    }
+    if sheet.unassigned_hours > 0.0 {
+        info!(
+            "{:.2} h of active time could not be tied to a repository",
+            sheet.unassigned_hours
+        );
+    }
+    if options.describe {
+        let work: Vec<LineWork> = sheet.lines.iter().map(LineWork::from).collect();
+        let mut descriptions: HashMap<String, String> = generate_descriptions(client, model, &work)
+            .await?
+            .into_iter()
+            .map(|line| (line.project, line.description))
+            .collect();
+        for line in &mut sheet.lines {
+            line.description = descriptions.remove(&line.project);
+        }
+    }
+    let csv = options.csv
+        || output.is_some_and(|output| {
+            output
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        });
+    match (csv, output) {
+        (true, Some(output)) => {
+            io_utils::output_file(output, &io_utils::OutputVars::now(), sheet.to_csv()).await
+        }
+        (true, None) => crate::render::terminal::print(sheet.to_csv()),
+        (false, output) => io_utils::output_markdown(output, &sheet.to_markdown()).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use time::OffsetDateTime;
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::context::{Context, FullContext};
+    use crate::git::CommitMeta;
+    use crate::git::diff::DiffSummary;
+    use crate::shell::ShellHistoryEntry;
+
+    fn repo(path: &str, commits: &[(&str, OffsetDateTime)]) -> GitRepoHistory {
+        GitRepoHistory {
+            diff: DiffSummary {
+                repo_path: PathBuf::from(path),
+                unmodified: HashSet::new(),
+                added: Vec::new(),
+                deleted: HashSet::new(),
+                modified: Vec::new(),
+                renamed: HashSet::new(),
+                copied: HashSet::new(),
+                untracked: Vec::new(),
+                typechange: HashSet::new(),
+                unreadable: HashSet::new(),
+                conflicted: HashSet::new(),
+            },
+            commits: commits
+                .iter()
+                .map(|(summary, timestamp)| CommitMeta {
+                    id: summary.to_string(),
+                    summary: summary.to_string(),
+                    body: None,
+                    timestamp: *timestamp,
+                    branches: Vec::new(),
+                })
+                .collect(),
+            unpushed: Vec::new(),
+            parked: Vec::new(),
+            dependency_changes: Vec::new(),
+            remote_url: None,
+            default_branch: None,
+            limitations: Vec::new(),
+        }
+    }
+
+    fn command(directory: &str, date_time: OffsetDateTime) -> ShellHistoryEntry {
+        ShellHistoryEntry {
+            date_time,
+            duration: Duration::seconds(1),
+            host: "laptop".into(),
+            directory: PathBuf::from(directory),
+            command: "cargo test".into(),
+            exit_code: 0,
+            session_id: "1".into(),
+        }
+    }
+
+    #[test]
+    fn measures_and_bills_time_per_project() {
+        let context = Context::default()
+            .with_commit_history(vec![
+                repo(
+                    "/src/acme-api",
+                    &[("Add invoices endpoint", datetime!(2025-01-30 09:05 UTC))],
+                ),
+                repo(
+                    "/src/acme-web",
+                    &[("Invoice page", datetime!(2025-01-30 09:10 UTC))],
+                ),
+                repo("/src/dotfiles", &[]),
+            ])
+            .with_shell_history(vec![
+                command("/src/acme-api/src", datetime!(2025-01-30 09:20 UTC)),
+                command("/src/dotfiles", datetime!(2025-01-30 11:00 UTC)),
+                command("/tmp", datetime!(2025-01-30 12:00 UTC)),
+            ]);
+        let entries = [ArchiveEntry {
+            date: date!(2025 - 01 - 30),
+            context: FullContext::from(context),
+        }];
+        let mappings = ["Acme=acme-".parse::<ProjectMapping>().unwrap()];
+        let rates = ["acme=100".parse::<ProjectRate>().unwrap()];
+
+        let sheet = Timesheet::measure(&entries, &mappings, &rates, None, Some("EUR".into()));
+
+        // Acme: the 09:00 and 09:15 slots; dotfiles: the 11:00 slot; 12:00 is unassigned.
+        assert_eq!(sheet.lines.len(), 2);
+        assert_eq!(sheet.lines[0].project, "Acme");
+        assert_eq!(sheet.lines[0].hours, 0.5);
+        assert_eq!(sheet.lines[0].amount(), Some(50.0));
+        assert_eq!(sheet.lines[0].commits.len(), 2);
+        assert_eq!(sheet.lines[1].project, "dotfiles");
+        assert_eq!(sheet.lines[1].rate, None);
+        assert_eq!(sheet.unassigned_hours, 0.25);
+        assert_eq!(sheet.total_amount(), None);
+
+        let mut described = sheet.clone();
+        described.lines[0].description = Some("Built invoicing, \"end to end\".".into());
+        let csv = described.to_csv();
+        assert!(csv.starts_with("project,days,hours,rate (EUR),amount (EUR),description\n"));
+        assert!(
+            csv.contains("Acme,1,0.50,100.00,50.00,\"Built invoicing, \"\"end to end\"\".\"\n")
+        );
+        assert!(csv.ends_with("Total,,0.75,,,\n"));
+        let markdown = described.to_markdown();
+        assert!(markdown.contains("| Acme | 1 | 0.50 | 100.00 | 50.00 |\n"));
+        assert!(markdown.contains("### Acme\n\nBuilt invoicing"));
+        assert!("Acme".parse::<ProjectMapping>().is_err());
+        assert!("Acme=-5".parse::<ProjectRate>().is_err());
+    }
+}
@@ -0,0 +1,583 @@
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use tokio::fs;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::AppResult;
+use crate::shell::ShellHistoryEntry;
+use crate::time_utils::{past_ts, system_time_to_offset_datetime};
+
+/// `list-sessions` format: name, created, last attached, last activity, attached client count.
+const SESSION_FORMAT: &str = "#{session_name}\t#{session_created}\t#{session_last_attached}\t#{session_activity}\t#{session_attached}";
+
+/// `list-windows -a` format: session name, window index, window name, active pane directory,
+/// active pane title.
+const WINDOW_FORMAT: &str =
+    "#{session_name}\t#{window_index}\t#{window_name}\t#{pane_current_path}\t#{pane_title}";
+
+/// Window names that say nothing about the project: shells and zellij's default tab names.
+const GENERIC_WINDOW_NAMES: &[&str] = &["zsh", "bash", "fish", "sh", "nu", "pwsh", "Tab"];
+
+/// The terminal multiplexer a session belongs to.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Multiplexer {
+    #[default]
+    Tmux,
+    Zellij,
+}
+
+/// A tmux window (or zellij tab) and the directory and title of its active pane.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TmuxWindow {
+    pub index: u32,
+    pub name: String,
+    pub pane_title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>,
+}
+
+/// A tmux or zellij session that saw activity during the window.
+///
+/// Sessions read from saved state (tmux-resurrect files or zellij's session cache) use the time
+/// the state was saved for `created` and `last_activity`, and are never reported as attached.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TmuxSession {
+    #[serde(default)]
+    pub multiplexer: Multiplexer,
+    pub name: String,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub created: OffsetDateTime,
+    #[serde(with = "crate::serde_helpers::offset_datetime")]
+    pub last_activity: OffsetDateTime,
+    pub attached: bool,
+    /// Time since the session was last attached: up to now while attached, up to the last
+    /// activity otherwise. tmux does not keep a full attach history, so this is an estimate.
+    #[serde(with = "crate::serde_helpers::duration")]
+    pub attached_for: Duration,
+    pub windows: Vec<TmuxWindow>,
+}
+
+/// Run a tmux listing command. Returns `None` when tmux is not installed or no server is running.
+async fn run_tmux(args: &[&str]) -> AppResult<Option<String>> {
+    let output = match Command::new("tmux").args(args).output().await {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("tmux is not installed");
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !output.status.success() {
+        debug!(
+            "tmux exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Parse a unix timestamp field, treating empty or zero as missing.
+fn parse_ts(field: &str) -> Option<OffsetDateTime> {
+    match field.parse::<i64>().ok()? {
+        0 => None,
+        secs => OffsetDateTime::from_unix_timestamp(secs).ok(),
+    }
+}
+
+/// Parse `list-windows` output into windows grouped by session name.
+fn parse_windows(output: &str) -> HashMap<String, Vec<TmuxWindow>> {
+    let mut windows: HashMap<String, Vec<TmuxWindow>> = HashMap::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.splitn(5, '\t').collect();
+        let [session, index, name, directory, pane_title] = fields[..] else {
+            continue;
+        };
+        let Ok(index) = index.parse() else {
+            continue;
+        };
+        windows
+            .entry(session.to_string())
+            .or_default()
+            .push(TmuxWindow {
+                index,
+                name: name.to_string(),
+                pane_title: pane_title.to_string(),
+                directory: (!directory.is_empty()).then(|| PathBuf::from(directory)),
+            });
+    }
+    windows
+}
+
+/// Parse `list-sessions` output, keeping sessions active since `since`.
+fn parse_sessions(
+    output: &str,
+    mut windows: HashMap<String, Vec<TmuxWindow>>,
+    since: OffsetDateTime,
+    now: OffsetDateTime,
+) -> Vec<TmuxSession> {
+    let mut sessions = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [name, created, last_attached, activity, attached] = fields[..] else {
+            continue;
+        };
+        let (Some(created), Some(last_activity)) = (parse_ts(created), parse_ts(activity)) else {
+            continue;
+        };
+        if last_activity < since {
+            continue;
+        }
+        let attached = attached.parse::<u32>().unwrap_or(0) > 0;
+        let attached_for = match parse_ts(last_attached) {
+            Some(last_attached) => {
+                let until = if attached { now } else { last_activity };
+                (until - last_attached.max(since)).max(Duration::ZERO)
+            }
+            None => Duration::ZERO,
+        };
+        sessions.push(TmuxSession {
+            multiplexer: Multiplexer::Tmux,
+            name: name.to_string(),
+            created,
+            last_activity,
+            attached,
+            attached_for,
+            windows: windows.remove(name).unwrap_or_default(),
+        });
+    }
+    sessions
+}
+
+/// Undo tmux-resurrect's escaping of spaces in paths.
+fn unescape_resurrect(field: &str) -> String {
+    field.replace("\\ ", " ")
+}
+
+/// Parse a tmux-resurrect save file written at `saved_at`.
+///
+/// Window lines are `window<TAB>session<TAB>index<TAB>:name<TAB>...`, and pane lines are
+/// `pane<TAB>session<TAB>window index<TAB>window active<TAB>:flags<TAB>pane index<TAB>title<TAB>
+/// :directory<TAB>pane active<TAB>...`.
+fn parse_resurrect(text: &str, saved_at: OffsetDateTime) -> Vec<TmuxSession> {
+    let mut sessions: Vec<TmuxSession> = Vec::new();
+    let mut panes: HashMap<(String, u32), (String, PathBuf)> = HashMap::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields[..] {
+            ["window", session, index, name, ..] => {
+                let Ok(index) = index.parse() else {
+                    continue;
+                };
+                let window = TmuxWindow {
+                    index,
+                    name: name.trim_start_matches(':').to_string(),
+                    pane_title: String::new(),
+                    directory: None,
+                };
+                match sessions.iter_mut().find(|s| s.name == session) {
+                    Some(existing) => existing.windows.push(window),
+                    None => sessions.push(TmuxSession {
+                        multiplexer: Multiplexer::Tmux,
+                        name: session.to_string(),
+                        created: saved_at,
+                        last_activity: saved_at,
+                        attached: false,
+                        attached_for: Duration::ZERO,
+                        windows: vec![window],
+                    }),
+                }
+            }
+            ["pane", session, index, _, _, _, title, directory, "1", ..] => {
+                let Ok(index) = index.parse() else {
+                    continue;
+                };
+                let directory = unescape_resurrect(directory.trim_start_matches(':'));
+                panes.insert(
+                    (session.to_string(), index),
+                    (title.to_string(), PathBuf::from(directory)),
+                );
+            }
+            _ => {}
+        }
+    }
+    for session in &mut sessions {
+        for window in &mut session.windows {
+            if let Some((title, directory)) = panes.remove(&(session.name.clone(), window.index)) {
+                window.pane_title = title;
+                window.directory = Some(directory);
+            }
+        }
+    }
+    sessions
+}
+
+/// Strip the quotes around a KDL string value.
+fn kdl_string(value: &str) -> &str {
+    value.trim().trim_matches('"')
+}
+
+/// The value of `key="..."` in a KDL node line.
+fn kdl_property<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("{key}=\""))? + key.len() + 2;
+    let len = line[start..].find('"')?;
+    Some(&line[start..start + len])
+}
+
+/// Parse the tabs of a zellij `session-layout.kdl`, with the directory of each tab's first pane
+/// that has one. Relative pane directories are resolved against the layout's `cwd`, which is
+/// also used for tabs without one.
+fn parse_zellij_layout(text: &str) -> Vec<TmuxWindow> {
+    let mut root: Option<PathBuf> = None;
+    let mut windows: Vec<TmuxWindow> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        // Templates for new tabs and swap layouts follow the session's own tabs.
+        if line.starts_with("new_tab_template") || line.starts_with("swap_") {
+            break;
+        }
+        if let Some(cwd) = line.strip_prefix("cwd ") {
+            root = Some(PathBuf::from(kdl_string(cwd)));
+        } else if line.starts_with("tab ") {
+            windows.push(TmuxWindow {
+                index: windows.len() as u32 + 1,
+                name: kdl_property(line, "name").unwrap_or_default().to_string(),
+                pane_title: String::new(),
+                directory: None,
+            });
+        } else if line.starts_with("pane ")
+            && let Some(window) = windows.last_mut()
+            && window.directory.is_none()
+            && let Some(cwd) = kdl_property(line, "cwd")
+        {
+            window.directory = Some(match &root {
+                Some(root) => root.join(cwd),
+                None => PathBuf::from(cwd),
+            });
+        }
+    }
+    if let Some(root) = root {
+        for window in windows.iter_mut().filter(|w| w.directory.is_none()) {
+            window.directory = Some(root.clone());
+        }
+    }
+    windows
+}
+
+/// When `path` was last written, if that was after `since`.
+async fn modified_after(path: &Path, since: OffsetDateTime) -> Option<OffsetDateTime> {
+    let modified = fs::metadata(path).await.ok()?.modified().ok()?;
+    let modified = system_time_to_offset_datetime(modified);
+    (modified >= since).then_some(modified)
+}
+
+/// Sessions from the latest tmux-resurrect save, when it was written during the window. Used
+/// when no tmux server is running.
+async fn resurrect_sessions(home: &Path, since: OffsetDateTime) -> AppResult<Vec<TmuxSession>> {
+    let mut dirs = vec![home.join(".tmux/resurrect")];
+    if let Some(data) = env::var_os("XDG_DATA_HOME") {
+        dirs.insert(0, PathBuf::from(data).join("tmux/resurrect"));
+    }
+    dirs.push(home.join(".local/share/tmux/resurrect"));
+    for dir in dirs {
+        let last = dir.join("last");
+        let Some(saved_at) = modified_after(&last, since).await else {
+            continue;
+        };
+        let text = fs::read_to_string(&last).await?;
+        return Ok(parse_resurrect(&text, saved_at));
+    }
+    Ok(Vec::new())
+}
+
+/// Sessions whose zellij layout cache was written during the window, live or exited.
+async fn zellij_sessions(home: &Path, since: OffsetDateTime) -> AppResult<Vec<TmuxSession>> {
+    let cache = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".cache"));
+    let roots = [
+        cache.join("zellij"),
+        home.join("Library/Caches/org.Zellij-Contributors.Zellij"),
+    ];
+    let mut sessions = Vec::new();
+    for root in roots {
+        let Ok(mut versions) = fs::read_dir(&root).await else {
+            continue;
+        };
+        while let Some(version) = versions.next_entry().await? {
+            let Ok(mut entries) = fs::read_dir(version.path().join("session_info")).await else {
+                continue;
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let layout = entry.path().join("session-layout.kdl");
+                let Some(saved_at) = modified_after(&layout, since).await else {
+                    continue;
+                };
+                sessions.push(TmuxSession {
+                    multiplexer: Multiplexer::Zellij,
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    created: saved_at,
+                    last_activity: saved_at,
+                    attached: false,
+                    attached_for: Duration::ZERO,
+                    windows: parse_zellij_layout(&fs::read_to_string(&layout).await?),
+                });
+            }
+        }
+    }
+    Ok(sessions)
+}
+
+/// Collect tmux and zellij sessions (with their windows) that were active during the window.
+///
+/// tmux sessions come from the running server, or from the latest tmux-resurrect save when no
+/// server is running. Returns an empty list when neither multiplexer left any trace.
+#[tracing::instrument(name = "Collecting tmux sessions", level = "info")]
+pub async fn get_tmux_sessions(duration: &Duration) -> AppResult<Vec<TmuxSession>> {
+    let since = past_ts(duration);
+    let home = env::home_dir().or_else(|| env::var("HOME").ok().map(PathBuf::from));
+    let mut sessions = match run_tmux(&["list-sessions", "-F", SESSION_FORMAT]).await? {
+        Some(sessions) => {
+            let windows = run_tmux(&["list-windows", "-a", "-F", WINDOW_FORMAT])
+                .await?
+                .map(|output| parse_windows(&output))
+                .unwrap_or_default();
+            parse_sessions(&sessions, windows, since, OffsetDateTime::now_utc())
+        }
+        None => match &home {
+            Some(home) => resurrect_sessions(home, since).await?,
+            None => Vec::new(),
+        },
+    };
+    if let Some(home) = &home {
+        sessions.extend(zellij_sessions(home, since).await?);
+    }
+    debug!("Found {} active multiplexer sessions", sessions.len());
+    Ok(sessions)
+}
+
+/// Multiplexer labels for one shell session, from the sessions and windows open in the
+/// directories it ran commands in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShellSessionHint {
+    pub session_id: String,
+    /// `session/window` names, or just the session name when the window name is generic.
+    pub labels: Vec<String>,
+}
+
+/// Label each shell session with the multiplexer windows whose directory contains one of the
+/// session's working directories, as a project hint when directory names are unhelpful.
+///
+/// Windows open in the home directory or `/` match everything and are left out.
+pub fn shell_session_hints(
+    sessions: &[TmuxSession],
+    shell_history: &[ShellHistoryEntry],
+) -> Vec<ShellSessionHint> {
+    let home = env::home_dir();
+    let windows: Vec<(&Path, String)> = sessions
+        .iter()
+        .flat_map(|session| {
+            session.windows.iter().filter_map(|window| {
+                let directory = window.directory.as_deref()?;
+                if directory == Path::new("/") || Some(directory) == home.as_deref() {
+                    return None;
+                }
+                let generic = window.name.is_empty()
+                    || GENERIC_WINDOW_NAMES
+                        .iter()
+                        .any(|name| window.name.split_whitespace().next() == Some(name));
+                let label = if generic {
+                    session.name.clone()
+                } else {
+                    format!("{}/{}", session.name, window.name)
+                };
+                Some((directory, label))
+            })
+        })
+        .collect();
+
+    let mut hints: Vec<ShellSessionHint> = Vec::new();
+    for entry in shell_history {
+        let labels: Vec<&String> = windows
+            .iter()
+            .filter(|(directory, _)| entry.directory.starts_with(directory))
+            .map(|(_, label)| label)
+            .collect();
+        if labels.is_empty() {
+            continue;
+        }
+        let index = match hints.iter().position(|h| h.session_id == entry.session_id) {
+            Some(index) => index,
+            None => {
+                hints.push(ShellSessionHint {
+                    session_id: entry.session_id.clone(),
+                    labels: Vec::new(),
+                });
+                hints.len() - 1
+            }
+        };
+        let merged: BTreeSet<String> = hints[index]
+            .labels
+            .drain(..)
+            .chain(labels.into_iter().cloned())
+            .collect();
+        hints[index].labels = merged.into_iter().collect();
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn parses_active_sessions_with_windows() {
+        let windows = parse_windows(
+            "work\t1\tnvim\t/src/app\tsrc/main.rs\nwork\t2\tcargo\t/src/app\tcargo test\nold\t1\tzsh\t/\thost\n",
+        );
+        let since = datetime!(2025-01-01 08:00 UTC);
+        let now = datetime!(2025-01-01 12:00 UTC);
+        let sessions = parse_sessions(
+            // work: created yesterday, attached at 09:00 and still attached.
+            // old: idle since before the window.
+            "work\t1735660800\t1735722000\t1735731000\t1\nold\t1735660800\t1735660800\t1735664400\t0\n",
+            windows,
+            since,
+            now,
+        );
+
+        assert_eq!(sessions.len(), 1);
+        let work = &sessions[0];
+        assert_eq!(work.name, "work");
+        assert!(work.attached);
+        assert_eq!(work.attached_for, Duration::hours(3));
+        assert_eq!(work.windows.len(), 2);
+        assert_eq!(work.windows[1].pane_title, "cargo test");
+        assert_eq!(
+            work.windows[1].directory.as_deref(),
+            Some(Path::new("/src/app"))
+        );
+    }
+
+    #[test]
+    fn detached_sessions_count_until_last_activity() {
+        let sessions = parse_sessions(
+            "scratch\t1735722000\t1735722000\t1735725600\t0\n",
+            HashMap::new(),
+            datetime!(2025-01-01 00:00 UTC),
+            datetime!(2025-01-01 12:00 UTC),
+        );
+
+        assert_eq!(sessions[0].attached_for, Duration::hours(1));
+        assert!(!sessions[0].attached);
+    }
+
+    #[test]
+    fn reads_resurrect_saves() {
+        let saved_at = datetime!(2025-01-01 12:00 UTC);
+        let sessions = parse_resurrect(
+            "pane\tclient\t1\t1\t:*\t0\tvim\t:/work/acme\\ web\t1\tnvim\t:nvim\n\
+             pane\tclient\t1\t1\t:*\t1\tzsh\t:/tmp\t0\tzsh\t:\n\
+             window\tclient\t1\t:editor\t1\t:*\tlayout\n\
+             state\tclient\n",
+            saved_at,
+        );
+
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].last_activity, saved_at);
+        let window = &sessions[0].windows[0];
+        assert_eq!(window.name, "editor");
+        assert_eq!(window.pane_title, "vim");
+        assert_eq!(
+            window.directory.as_deref(),
+            Some(Path::new("/work/acme web"))
+        );
+    }
+
+    #[test]
+    fn reads_zellij_layout_tabs() {
+        let windows = parse_zellij_layout(
+            r#"layout {
+    cwd "/home/me"
+    tab name="api" focus=true hide_floating_panes=true {
+        pane command="cargo" cwd="src/acme-api" {
+            args "watch"
+        }
+    }
+    tab name="Tab #2" {
+        pane
+    }
+    new_tab_template {
+        pane
+    }
+    swap_tiled_layout name="vertical" {
+        tab max_panes=5 {
+            pane
+        }
+    }
+}"#,
+        );
+
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].name, "api");
+        assert_eq!(
+            windows[0].directory.as_deref(),
+            Some(Path::new("/home/me/src/acme-api"))
+        );
+        assert_eq!(windows[1].directory.as_deref(), Some(Path::new("/home/me")));
+    }
+
+    #[test]
+    fn labels_shell_sessions_by_window_directory() {
+        let window = |name: &str, directory: &str| TmuxWindow {
+            index: 1,
+            name: name.into(),
+            pane_title: String::new(),
+            directory: Some(PathBuf::from(directory)),
+        };
+        let sessions = vec![TmuxSession {
+            multiplexer: Multiplexer::Zellij,
+            name: "client".into(),
+            created: OffsetDateTime::UNIX_EPOCH,
+            last_activity: OffsetDateTime::UNIX_EPOCH,
+            attached: false,
+            attached_for: Duration::ZERO,
+            windows: vec![window("editor", "/work/x"), window("zsh", "/work/x/infra")],
+        }];
+        let entry = |session_id: &str, directory: &str| ShellHistoryEntry {
+            date_time: OffsetDateTime::UNIX_EPOCH,
+            duration: Duration::ZERO,
+            host: "host".into(),
+            directory: PathBuf::from(directory),
+            command: "make".into(),
+            exit_code: 0,
+            session_id: session_id.into(),
+        };
+
+        let hints = shell_session_hints(
+            &sessions,
+            &[
+                entry("a", "/work/x/infra/tf"),
+                entry("a", "/work/x"),
+                entry("b", "/elsewhere"),
+            ],
+        );
+
+        assert_eq!(
+            hints,
+            [ShellSessionHint {
+                session_id: "a".into(),
+                labels: vec!["client".into(), "client/editor".into()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+use clap::ValueEnum;
+use regex::Regex;
+use tracing::{info, warn};
+
+use crate::AppResult;
+use crate::ai::summary::WorkSummary;
+use crate::context::Context;
+use crate::tickets::extract_ticket_keys;
+
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s)\]>"'`]+"#).expect("valid regex"));
+static COUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(\d+)\s+(commits?|repositories|repos|tickets?|pages|commands)\b")
+        .expect("valid regex")
+});
+static CODE_SPAN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`([^`\n]+)`").expect("valid regex"));
+static REPO_SUMMARY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Repo ([^:]+): ").expect("valid regex"));
+
+/// What to do with specifics in the summary that the collected data does not back up.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClaimCheck {
+    /// Do not check the summary
+    #[default]
+    Off,
+    /// List them in the summary's notes
+    Flag,
+    /// Remove the sentences and list items that make them, and list them in the notes
+    Fix,
+}
+
+/// The kinds of specifics checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClaimKind {
+    Repository,
+    Ticket,
+    Url,
+    Count,
+    Identifier,
+}
+
+impl fmt::Display for ClaimKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ClaimKind::Repository => "repository",
+            ClaimKind::Ticket => "ticket",
+            ClaimKind::Url => "URL",
+            ClaimKind::Count => "count",
+            ClaimKind::Identifier => "name",
+        })
+    }
+}
+
+/// A specific the collected data does not back up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Claim {
+    kind: ClaimKind,
+    text: String,
+}
+
+/// What the collected data says, to check claims against.
+#[derive(Debug)]
+struct Facts {
+    /// The whole context as JSON: commit messages, diffs, paths, commands, titles, and URLs.
+    haystack: String,
+    /// Repositories as the repository summaries name them: the last two parts of their path.
+    repos: Vec<String>,
+    /// How many commits, repositories, tickets, pages, and commands were collected.
+    totals: HashMap<&'static str, usize>,
+}
+
+/// The last two components of `path`, as the repository summaries name repositories.
+fn short_repo_name(path: &str) -> String {
+    let parts: Vec<&str> = path.split('/').collect();
+    match parts.as_slice() {
+        [.., owner, name] => format!("{owner}/{name}"),
+        _ => path.to_string(),
+    }
+}
+
+impl Facts {
+    fn from_context(context: &Context) -> AppResult<Self> {
+        let repos = context.commit_history();
+        let totals = HashMap::from([
+            ("commits", repos.iter().map(|repo| repo.commits.len()).sum()),
+            ("repositories", repos.len()),
+            ("tickets", context.tickets().len()),
+            (
+                "pages",
+                context
+                    .safari_history()
+                    .iter()
+                    .map(|cluster| cluster.urls.len())
+                    .sum(),
+            ),
+            ("commands", context.shell_history().len()),
+        ]);
+        Ok(Facts {
+            haystack: serde_json::to_string(context)?,
+            repos: repos
+                .iter()
+                .map(|repo| short_repo_name(&repo.diff.repo_path.to_string_lossy()))
+                .collect(),
+            totals,
+        })
+    }
+
+    /// The claims in `text` these facts do not back up.
+    fn unsupported(&self, text: &str) -> Vec<Claim> {
+        let mut claims = Vec::new();
+        let mut claim = |kind, text: &str| {
+            claims.push(Claim {
+                kind,
+                text: text.to_string(),
+            })
+        };
+        if let Some(repo) = REPO_SUMMARY.captures(text)
+            && !self.repos.contains(&repo[1].to_string())
+        {
+            claim(ClaimKind::Repository, &repo[1]);
+        }
+        for key in extract_ticket_keys(text) {
+            if !self.haystack.contains(&key) {
+                claim(ClaimKind::Ticket, &key);
+            }
+        }
+        for url in URL.find_iter(text) {
+            let url = url
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if !self.haystack.contains(url.trim_end_matches('/')) {
+                claim(ClaimKind::Url, url);
+            }
+        }
+        for count in COUNT.captures_iter(text) {
+            let noun = match count[2].to_lowercase().as_str() {
+                "commit" | "commits" => "commits",
+                "repositories" | "repos" => "repositories",
+                "ticket" | "tickets" => "tickets",
+                "pages" => "pages",
+                _ => "commands",
+            };
+            let claimed: usize = count[1].parse().unwrap_or(usize::MAX);
+            if claimed > self.totals.get(noun).copied().unwrap_or_default() {
+                claim(ClaimKind::Count, &count[0]);
+            }
+        }
+        for span in CODE_SPAN.captures_iter(text) {
+            // JSON escapes quotes and backslashes, so look for the name as it is serialized.
+            let escaped = serde_json::to_string(&span[1]).unwrap_or_default();
+            if !self.haystack.contains(escaped.trim_matches('"')) {
+                claim(ClaimKind::Identifier, &span[1]);
+            }
+        }
+        claims
+    }
+}
+
+/// The sentences of `text`, each with its punctuation and the whitespace after it.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?')
+            && chars.peek().is_some_and(|(_, next)| next.is_whitespace())
+        {
+            while let Some((_, next)) = chars.peek()
+                && next.is_whitespace()
+            {
+                chars.next();
+            }
+            let end = chars.peek().map_or(text.len(), |(end, _)| *end);
+            sentences.push(&text[start..end]);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
+
+/// Check the prose `text`, dropping the sentences with unsupported claims when `fix` is set.
+fn check_prose(facts: &Facts, text: &mut String, fix: bool, found: &mut Vec<Claim>) {
+    let mut kept = String::new();
+    for sentence in sentences(text) {
+        let claims = facts.unsupported(sentence);
+        if claims.is_empty() || !fix {
+            kept.push_str(sentence);
+        }
+        found.extend(claims);
+    }
+    if fix {
+        *text = kept.trim_end().to_string();
+    }
+}
+
+/// Check the list `items`, dropping those with unsupported claims when `fix` is set.
+fn check_list(facts: &Facts, items: &mut Vec<String>, fix: bool, found: &mut Vec<Claim>) {
+    items.retain(|item| {
+        let claims = facts.unsupported(item);
+        let keep = claims.is_empty() || !fix;
+        found.extend(claims);
+        keep
+    });
+}
+
+/// Check the repository names, ticket keys, URLs, counts, and names in backticks that the model
+/// wrote in `summary` against `context`, as `mode` says.
+///
+/// The sections built from the data itself, such as the tickets touched, are not checked. Each
+/// claim the data does not back up is listed in the summary's notes, so a reader can tell what
+/// to double-check or what was removed.
+pub fn check_summary(
+    summary: &mut WorkSummary,
+    context: &Context,
+    mode: ClaimCheck,
+) -> AppResult<()> {
+    if mode == ClaimCheck::Off {
+        return Ok(());
+    }
+    let facts = Facts::from_context(context)?;
+    let fix = mode == ClaimCheck::Fix;
+    let mut found: Vec<(&str, Claim)> = Vec::new();
+    let mut section = |name: &'static str, claims: Vec<Claim>| {
+        found.extend(claims.into_iter().map(|claim| (name, claim)));
+    };
+    let mut claims = Vec::new();
+    check_prose(&facts, &mut summary.summary, fix, &mut claims);
+    section("summary", std::mem::take(&mut claims));
+    check_prose(&facts, &mut summary.shell_overview, fix, &mut claims);
+    section("shell overview", std::mem::take(&mut claims));
+    let lists = [
+        ("highlights", &mut summary.highlights),
+        ("time breakdown", &mut summary.time_breakdown),
+        ("common groups", &mut summary.common_groups),
+        ("repository summaries", &mut summary.repo_summaries),
+        ("learned", &mut summary.learned),
+    ];
+    for (name, items) in lists {
+        check_list(&facts, items, fix, &mut claims);
+        section(name, std::mem::take(&mut claims));
+    }
+
+    if found.is_empty() {
+        info!("Every checked claim in the summary matches the collected data");
+        return Ok(());
+    }
+    let action = if fix { "Removed" } else { "Unverified" };
+    for (section, claim) in found {
+        let note = format!(
+            "{action} {} in the {section}: {} (not found in the collected data)",
+            claim.kind, claim.text
+        );
+        warn!("{note}");
+        if !summary.notes.contains(&note) {
+            summary.notes.push(note);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts() -> Facts {
+        Facts {
+            haystack: concat!(
+                r#"{"summary":"ENG-42 fix `fit_context`","#,
+                r#""url":"https://github.com/me/app/pull/7"}"#
+            )
+            .to_string(),
+            repos: vec!["me/app".to_string()],
+            totals: HashMap::from([("commits", 5), ("repositories", 1)]),
+        }
+    }
+
+    #[test]
+    fn flags_or_removes_claims_the_data_does_not_back_up() {
+        let facts = facts();
+        let backed =
+            "Repo me/app: fixed ENG-42 in `fit_context` (https://github.com/me/app/pull/7).";
+        assert!(facts.unsupported(backed).is_empty());
+        let claims = facts.unsupported("Repo me/lib: made 12 commits for ENG-99 in `trim_all`.");
+        let kinds: Vec<ClaimKind> = claims.iter().map(|claim| claim.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ClaimKind::Repository,
+                ClaimKind::Ticket,
+                ClaimKind::Count,
+                ClaimKind::Identifier
+            ]
+        );
+
+        let mut text = "Fixed ENG-42. Closed ENG-99 too! Made 3 commits.".to_string();
+        let mut found = Vec::new();
+        check_prose(&facts, &mut text, true, &mut found);
+        assert_eq!(text, "Fixed ENG-42. Made 3 commits.");
+        assert_eq!(found[0].text, "ENG-99");
+
+        let mut items = vec!["Repo me/app: ok".to_string(), "Repo me/lib: no".to_string()];
+        check_list(&facts, &mut items, false, &mut found);
+        assert_eq!(items.len(), 2);
+        check_list(&facts, &mut items, true, &mut found);
+        assert_eq!(items, ["Repo me/app: ok"]);
+    }
+}
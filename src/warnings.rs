@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::AppError;
+
+/// What kind of gap a [`DataWarning`] describes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// A data source could not be read because access was denied.
+    Permission,
+    /// Data was collected, but only partly (e.g. an oversized patch reduced to line counts).
+    Truncated,
+    /// A repository, mailbox, tracker, or similar was left out.
+    Skipped,
+    /// A cached file was unreadable and had to be rebuilt.
+    CacheCorrupt,
+    /// A collector failed outright.
+    CollectorFailed,
+}
+
+impl WarningKind {
+    /// Classify a collector's error: permission failures are reported as such.
+    pub fn for_error(error: &AppError) -> Self {
+        match error {
+            AppError::Command(e) if e.kind() == ErrorKind::PermissionDenied => {
+                WarningKind::Permission
+            }
+            _ => WarningKind::CollectorFailed,
+        }
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WarningKind::Permission => "Permission denied",
+            WarningKind::Truncated => "Truncated",
+            WarningKind::Skipped => "Skipped",
+            WarningKind::CacheCorrupt => "Corrupt cache",
+            WarningKind::CollectorFailed => "Collector failed",
+        })
+    }
+}
+
+/// A gap in the collected data, shown in the report's "Data quality" section.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DataWarning {
+    pub kind: WarningKind,
+    /// Collector or component the warning came from, e.g. `git`.
+    pub source: String,
+    pub message: String,
+}
+
+tokio::task_local! {
+    static WARNINGS: RefCell<Vec<DataWarning>>;
+}
+
+/// Run `future` with a fresh warning list, returning its output and the warnings it recorded.
+pub async fn scope<F: Future>(future: F) -> (F::Output, Vec<DataWarning>) {
+    WARNINGS
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = future.await;
+            (output, WARNINGS.with(RefCell::take))
+        })
+        .await
+}
+
+/// Log a data gap and add it to the current run's warnings.
+///
+/// Repeats of the same warning are kept once. Outside [`scope`] (e.g. on a blocking thread) the
+/// warning is only logged.
+pub fn record(kind: WarningKind, source: &str, message: impl Into<String>) {
+    let warning = DataWarning {
+        kind,
+        source: source.to_string(),
+        message: message.into(),
+    };
+    warn!("{}", warning.message);
+    let _ = WARNINGS.try_with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn gathers_warnings_recorded_in_the_scope() {
+        let ((), warnings) = scope(async {
+            record(WarningKind::Skipped, "git", "Failed to read /src/app");
+            tokio::task::yield_now().await;
+            record(WarningKind::Skipped, "git", "Failed to read /src/app");
+            record(WarningKind::Truncated, "git", "Large patch");
+        })
+        .await;
+        record(WarningKind::Skipped, "git", "outside any run");
+
+        assert_eq!(
+            warnings,
+            vec![
+                DataWarning {
+                    kind: WarningKind::Skipped,
+                    source: "git".into(),
+                    message: "Failed to read /src/app".into(),
+                },
+                DataWarning {
+                    kind: WarningKind::Truncated,
+                    source: "git".into(),
+                    message: "Large patch".into(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::env;
+use std::time::Duration;
+
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use ring::hmac;
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use tracing::warn;
+use ulid::Ulid;
+
+use crate::ai::summary::WorkSummary;
+use crate::backoff::Backoff;
+use crate::context::FullContext;
+use crate::error::AppError;
+use crate::export::Exporter;
+use crate::{AppResult, http, time_utils};
+
+/// Environment variable holding the key that webhook requests are signed with.
+const SECRET_VAR: &str = "DAILY_AI_WEBHOOK_SECRET";
+
+/// Header with the HMAC-SHA256 of the request body, as `sha256=<hex>`.
+const SIGNATURE_HEADER: &str = "X-Daily-AI-Signature-256";
+
+/// How many times a delivery that failed in passing is sent again.
+const RETRIES: u32 = 3;
+
+/// Waits between deliveries: 2, 4, then 8 seconds.
+const BACKOFF: Backoff = Backoff::new(Duration::from_secs(2), Duration::from_secs(8));
+
+/// What is sent to a webhook when a run's summary is ready.
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    /// `summary.ready`, or `summary.updated` when a retried run replaces an earlier delivery.
+    event: &'a str,
+    /// Identifies this delivery; repeated in the `replaces` field of a later update.
+    delivery: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replaces: Option<&'a str>,
+    run_id: Option<Ulid>,
+    /// When the payload was sent, in RFC 3339.
+    sent_at: String,
+    summary: Option<&'a WorkSummary>,
+}
+
+/// POSTs a run's summary as JSON to a URL given with `--webhook`, for home automation or
+/// internal systems to react to.
+///
+/// When `DAILY_AI_WEBHOOK_SECRET` is set, each request carries the HMAC-SHA256 of its body,
+/// keyed with the secret, in the `X-Daily-AI-Signature-256` header as `sha256=<hex>`.
+pub struct WebhookExporter {
+    url: String,
+    key: Option<hmac::Key>,
+    client: Client,
+}
+
+impl WebhookExporter {
+    pub fn new(url: String) -> AppResult<Self> {
+        let key = env::var(SECRET_VAR)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()));
+        let client = http::client(&[&url])?;
+        Ok(WebhookExporter { url, key, client })
+    }
+
+    /// Send `body`, retrying connection failures, rate limits, and server errors with
+    /// exponential backoff.
+    async fn post(&self, event: &str, body: String) -> AppResult<()> {
+        let signature = self.key.as_ref().map(|key| signature(key, body.as_bytes()));
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header(CONTENT_TYPE, "application/json")
+                .header("X-Daily-AI-Event", event)
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+            let failure = match request.send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) if resp.status().is_server_error() || resp.status().as_u16() == 429 => {
+                    format!("the server answered {}", resp.status())
+                }
+                Ok(resp) => {
+                    return Err(AppError::Other(format!(
+                        "The webhook at {} answered {}",
+                        self.url,
+                        resp.status()
+                    )));
+                }
+                Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => e.to_string(),
+                Err(e) => return Err(e.into()),
+            };
+            if attempt == RETRIES {
+                return Err(AppError::Other(format!(
+                    "The webhook at {} failed {} times; last time {failure}",
+                    self.url,
+                    RETRIES + 1
+                )));
+            }
+            attempt += 1;
+            let wait = BACKOFF.wait(attempt);
+            warn!(
+                "Webhook delivery to {} failed ({failure}); retrying in {} ({attempt} of {RETRIES})",
+                self.url,
+                humantime::format_duration(wait)
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn deliver(&self, context: &FullContext, replaces: Option<&str>) -> AppResult<String> {
+        let delivery = Ulid::new().to_string();
+        let event = match replaces {
+            Some(_) => "summary.updated",
+            None => "summary.ready",
+        };
+        let now = time_utils::now_local();
+        let payload = Payload {
+            event,
+            delivery: &delivery,
+            replaces,
+            run_id: context.run_id,
+            sent_at: now.format(&Rfc3339)?,
+            summary: context.summary.as_ref(),
+        };
+        self.post(event, serde_json::to_string(&payload)?).await?;
+        Ok(delivery)
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` keyed with `key`.
+fn signature(key: &hmac::Key, body: &[u8]) -> String {
+    let tag = hmac::sign(key, body);
+    let hex: String = tag
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+impl Exporter for WebhookExporter {
+    fn target(&self) -> String {
+        format!("webhook:{}", self.url)
+    }
+
+    async fn create(&self, context: &FullContext) -> AppResult<String> {
+        self.deliver(context, None).await
+    }
+
+    async fn update(&self, remote_id: &str, context: &FullContext) -> AppResult<()> {
+        self.deliver(context, Some(remote_id)).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_the_body_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"Jefe");
+        assert_eq!(
+            signature(&key, b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}